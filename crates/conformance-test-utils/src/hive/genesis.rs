@@ -1,23 +1,37 @@
-use std::collections::HashMap;
+pub mod export;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::io::Error as IoError;
-use std::path::Path;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use eyre::Result;
-use kakarot_rpc_core::client::constants::STARKNET_NATIVE_TOKEN;
+use kakarot_rpc_core::client::chain_spec::ChainSpec;
+use kakarot_rpc_core::client::config::ChainIdConfig;
 use kakarot_rpc_core::test_utils::deploy_helpers::compute_kakarot_contracts_class_hash;
+use katana_core::db::serde::state::{SerializableClassRecord, SerializableState, SerializableStorageRecord};
+use katana_core::utils::contract::get_contract_class;
 use lazy_static::lazy_static;
 use pallet_starknet::genesis_loader::{ContractClass, GenesisLoader, HexFelt};
+use rayon::prelude::*;
 use reth_primitives::{Address, Bytes, H256, U256, U64};
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use starknet::core::types::contract::legacy::LegacyContractClass;
 use starknet::core::types::FieldElement;
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress as KatanaContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey as KatanaStorageKey;
 
+use super::stats::{AccountStats, GenesisStats};
 use crate::kakarot::compute_starknet_address;
 use crate::madara::utils::{
-    genesis_fund_starknet_address, genesis_set_bytecode, genesis_set_storage_kakarot_contract_account,
-    genesis_set_storage_starknet_contract,
+    genesis_fund_starknet_address, genesis_set_blockhash, genesis_set_bytecode, genesis_set_nonce,
+    genesis_set_storage_kakarot_contract_account, genesis_set_storage_starknet_contract,
 };
-use crate::types::Felt;
+use crate::types::{ContractAddress, Felt, StorageKey, StorageValue};
 
 /// Types from https://github.com/ethereum/go-ethereum/blob/master/core/genesis.go#L49C1-L58
 #[derive(Serialize, Deserialize)]
@@ -31,11 +45,53 @@ pub struct HiveGenesisConfig {
     pub nonce: U64,
     pub timestamp: U64,
     pub alloc: HashMap<Address, AccountInfo>,
+    /// Base fee of the genesis block, set by the genesis files of the networks past London.
+    #[serde(default, deserialize_with = "deserialize_optional_quantity", skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+    /// Hashes of the blocks preceding the genesis, by block number, returned by the BLOCKHASH
+    /// opcode for these blocks. Not part of the geth genesis, set by the tests relying on
+    /// historical block hashes or imported from a chain, see
+    /// [`HiveGenesisConfig::import_block_hashes`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub block_hashes: BTreeMap<u64, H256>,
 }
 
 impl HiveGenesisConfig {
+    /// Reads the genesis file, failing if its forks are not scheduled in order.
     pub fn from_file(path: &str) -> Result<Self> {
-        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+        let genesis: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        genesis.config.validate_fork_order()?;
+        Ok(genesis)
+    }
+
+    /// Adds the block hashes of an imported chain, read from a JSON object mapping the block
+    /// numbers to their hashes. The imported hashes replace the hashes already set for the same
+    /// block numbers.
+    pub fn import_block_hashes(&mut self, path: &str) -> Result<()> {
+        let block_hashes: BTreeMap<u64, H256> = serde_json::from_str(&fs::read_to_string(path)?)?;
+        self.block_hashes.extend(block_hashes);
+        Ok(())
+    }
+
+    /// Returns the chain id of the genesis, which the RPC serving the generated Madara genesis
+    /// must be configured with through `KAKAROT_CHAIN_ID`.
+    pub fn chain_id(&self) -> Result<ChainIdConfig> {
+        Ok(ChainIdConfig::Fixed(u64::try_from(self.config.chain_id)?))
+    }
+
+    /// Returns the chain spec of the RPC serving the generated Madara genesis, set through
+    /// `KAKAROT_CHAIN_SPEC`: the chain id, the block gas limit and the base fee of the genesis,
+    /// the other parameters keeping their default value.
+    pub fn chain_spec(&self) -> Result<ChainSpec> {
+        let mut chain_spec = ChainSpec {
+            chain_id: u64::try_from(self.config.chain_id)?,
+            block_gas_limit: self.gas_limit.to::<u64>(),
+            ..Default::default()
+        };
+        if let Some(base_fee_per_gas) = self.base_fee_per_gas {
+            chain_spec.base_fee_per_gas = u64::try_from(base_fee_per_gas)?;
+        }
+        Ok(chain_spec)
     }
 }
 
@@ -45,37 +101,275 @@ lazy_static! {
     pub static ref BLOCKHASH_REGISTRY_ADDRESS: FieldElement = FieldElement::from_hex_be("0x9002").unwrap(); // Safe unwrap, 0x9002
 }
 
+/// Cairo contract predeployed in the combined genesis alongside Kakarot, such as an oracle or a
+/// bridge used by a test network.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CairoPredeploy {
+    pub address: ContractAddress,
+    /// Path of the compiled Cairo 0 class of the contract.
+    pub class_path: PathBuf,
+    /// Initial storage of the contract, as (storage key, value) pairs.
+    #[serde(default)]
+    pub storage: Vec<(StorageKey, StorageValue)>,
+}
+
+impl CairoPredeploy {
+    /// Reads a JSON list of predeploys.
+    pub fn from_file(path: &str) -> Result<Vec<Self>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Computes the class hash of the compiled class of the contract.
+    fn class_hash(&self) -> Result<FieldElement, IoError> {
+        let contract_class: LegacyContractClass = serde_json::from_reader(fs::File::open(&self.class_path)?)?;
+        contract_class.class_hash().map_err(|err| {
+            IoError::new(
+                ErrorKind::InvalidData,
+                format!("failed to compute the class hash of {}: {err}", self.class_path.display()),
+            )
+        })
+    }
+}
+
+/// Canonical ordering of a Madara genesis, so that two genesis files generated from the same
+/// inputs are identical and can be diffed.
+///
+/// Once canonicalized, a genesis upholds the following invariants:
+/// - contract classes are sorted by class hash;
+/// - contracts are sorted by address;
+/// - storage entries are sorted by contract address, then by storage key;
+/// - nonces are sorted by contract address;
+/// - each class hash, contract address and (contract address, storage key) pair appears once, the
+///   last entry pushed to the loader wins, as it would when the genesis is loaded.
+///
+/// Canonicalizing is idempotent and doesn't depend on the order in which the entries were pushed.
+pub trait CanonicalGenesis {
+    /// Sorts and deduplicates the entries of the genesis following the canonical ordering.
+    fn canonicalize(&mut self);
+}
+
+impl CanonicalGenesis for GenesisLoader {
+    fn canonicalize(&mut self) {
+        canonicalize_entries(&mut self.contract_classes, |(class_hash, _)| class_hash.0);
+        canonicalize_entries(&mut self.contracts, |(address, _)| address.0);
+        canonicalize_entries(&mut self.storage, |((address, key), _)| (address.0, key.0));
+        canonicalize_entries(&mut self.nonces, |(address, _)| address.0);
+    }
+}
+
+/// Sorts the entries by key, only keeping the last entry of each key.
+fn canonicalize_entries<T, K: Ord>(entries: &mut Vec<T>, key: impl Fn(&T) -> K) {
+    // Reversing before the stable sort puts the last entry of each key first
+    entries.reverse();
+    entries.sort_by_key(&key);
+    entries.dedup_by(|a, b| key(a) == key(b));
+}
+
 /// Convert Hive Genesis Config to Madara Genesis Config
 ///
 /// This function will:
-/// 1. Load the Madara genesis file
-/// 2. Compute the class hash of Kakarot contracts
-/// 3. Add Kakarot contracts to Loader
-/// 4. Add Hive accounts to Loader (fund, storage, bytecode, proxy implementation)
+/// 1. Load the Madara genesis file, failing if it doesn't deploy the native token
+/// 2. Convert the Hive genesis to Starknet, see [`convert_hive_genesis`]
+/// 3. Add the converted classes, contracts and storage to Loader, and the nonces of the externally
+///    owned accounts as the nonces of their Starknet accounts
+/// 4. Canonicalize the Loader, see [`CanonicalGenesis`]
 /// 5. Serialize Loader to Madara genesis file
+///
+/// Returns the size statistics of the Hive accounts, see [`GenesisStats`].
 pub async fn serialize_hive_to_madara_genesis_config(
     hive_genesis: HiveGenesisConfig,
     mut madara_loader: GenesisLoader,
+    native_token_address: FieldElement,
+    predeploys: &[CairoPredeploy],
     combined_genesis: &Path,
     compiled_path: &Path,
-) -> Result<(), IoError> {
+) -> Result<GenesisStats, IoError> {
+    // The balances of the accounts are written to the native token, which must be deployed
+    if !madara_loader.contracts.iter().any(|(address, _)| address.0 == native_token_address) {
+        return Err(IoError::new(
+            ErrorKind::InvalidInput,
+            format!("native token {native_token_address:#x} is not deployed in the Madara genesis"),
+        ));
+    }
+
+    let deployed_addresses = madara_loader.contracts.iter().map(|(address, _)| address.0).collect();
+    let genesis =
+        convert_hive_genesis(hive_genesis, native_token_address, predeploys, compiled_path, deployed_addresses)?;
+
+    madara_loader.contract_classes.extend(
+        genesis
+            .classes
+            .into_iter()
+            .map(|(class_hash, path)| (HexFelt(class_hash), ContractClass::Path { path, version: 0 })),
+    );
+    madara_loader
+        .contracts
+        .extend(genesis.contracts.into_iter().map(|(address, class_hash)| (HexFelt(address), HexFelt(class_hash))));
+    madara_loader.storage.extend(genesis.storage.into_iter().map(hex_felts));
+    // The nonce of an externally owned account is the protocol nonce of its Starknet account
+    madara_loader.nonces.extend(
+        genesis.nonces.into_iter().map(|(address, nonce)| (HexFelt(address), HexFelt(FieldElement::from(nonce)))),
+    );
+
+    // Sort the loader to get a deterministic output
+    madara_loader.canonicalize();
+
+    // Serialize the loader to a string
+    let madara_genesis_str = serde_json::to_string_pretty(&madara_loader)?;
+    // Write the string to a file
+    fs::write(combined_genesis, madara_genesis_str)?;
+
+    Ok(genesis.stats)
+}
+
+/// Convert Hive Genesis Config to a Katana state dump, loaded with `katana --load-state`.
+///
+/// The Hive genesis is converted as for Madara, see [`convert_hive_genesis`], and merged into the
+/// Katana state dump of the genesis, which must deploy the native token and declare its class. The
+/// contracts of the Katana genesis keep their storage, the native token holding the balances of the
+/// accounts along with its ERC20 storage. As for Madara, the nonces of the externally owned
+/// accounts are set as the nonces of their Starknet accounts.
+///
+/// Returns the size statistics of the Hive accounts, see [`GenesisStats`].
+pub async fn serialize_hive_to_katana_genesis(
+    hive_genesis: HiveGenesisConfig,
+    mut katana_state: SerializableState,
+    native_token_address: FieldElement,
+    predeploys: &[CairoPredeploy],
+    katana_genesis: &Path,
+    compiled_path: &Path,
+) -> Result<GenesisStats, IoError> {
+    // The balances of the accounts are written to the native token, which must be deployed
+    let native_token = katana_state.storage.get(&katana_contract_address(native_token_address)?).ok_or_else(|| {
+        IoError::new(
+            ErrorKind::InvalidInput,
+            format!("native token {native_token_address:#x} is not deployed in the Katana genesis"),
+        )
+    })?;
+    if !katana_state.classes.contains_key(&native_token.class_hash) {
+        return Err(IoError::new(
+            ErrorKind::InvalidInput,
+            format!("class {} of the native token is not declared in the Katana genesis", native_token.class_hash.0),
+        ));
+    }
+
+    let deployed_addresses = katana_state
+        .storage
+        .keys()
+        .map(|address| {
+            FieldElement::from_byte_slice_be(address.0.key().bytes())
+                .map_err(|err| IoError::new(ErrorKind::InvalidData, err.to_string()))
+        })
+        .collect::<Result<_, _>>()?;
+    let genesis =
+        convert_hive_genesis(hive_genesis, native_token_address, predeploys, compiled_path, deployed_addresses)?;
+
+    for (class_hash, path) in &genesis.classes {
+        katana_state.classes.insert(ClassHash(StarkFelt::from(*class_hash)), katana_class_record(Path::new(path))?);
+    }
+    for (address, class_hash) in &genesis.contracts {
+        let record = katana_state.storage.entry(katana_contract_address(*address)?).or_insert_with(|| {
+            SerializableStorageRecord {
+                nonce: Nonce::default(),
+                class_hash: ClassHash::default(),
+                storage: BTreeMap::new(),
+            }
+        });
+        record.class_hash = ClassHash(StarkFelt::from(*class_hash));
+    }
+    for (address, nonce) in &genesis.nonces {
+        if let Some(record) = katana_state.storage.get_mut(&katana_contract_address(*address)?) {
+            record.nonce = Nonce(StarkFelt::from(*nonce));
+        }
+    }
+    for ((address, key), value) in &genesis.storage {
+        let record = katana_state.storage.get_mut(&katana_contract_address(address.0)?).ok_or_else(|| {
+            IoError::new(ErrorKind::InvalidData, format!("storage written to {:#x}, which isn't deployed", address.0))
+        })?;
+        let key = PatriciaKey::try_from(StarkFelt::from(key.0))
+            .map_err(|err| IoError::new(ErrorKind::InvalidData, err.to_string()))?;
+        record.storage.insert(KatanaStorageKey(key), StarkFelt::from(value.0));
+    }
+
+    fs::write(katana_genesis, serde_json::to_string_pretty(&katana_state)?)?;
+
+    Ok(genesis.stats)
+}
+
+/// Reads the compiled Cairo 0 class at the path as a class record of the Katana state. Cairo 0
+/// classes have no compiled class, their compiled class hash is zero as in the Starknet state.
+fn katana_class_record(path: &Path) -> Result<SerializableClassRecord, IoError> {
+    let class = fs::read_to_string(path)?;
+    // Katana panics on an invalid class instead of failing
+    let class = std::panic::catch_unwind(|| get_contract_class(&class)).map_err(|_| {
+        IoError::new(ErrorKind::InvalidData, format!("{} is not a compiled Cairo 0 class", path.display()))
+    })?;
+    Ok(SerializableClassRecord { class: class.into(), compiled_hash: CompiledClassHash::default() })
+}
+
+/// Converts an address to the contract address of the Katana state.
+fn katana_contract_address(address: FieldElement) -> Result<KatanaContractAddress, IoError> {
+    let address = PatriciaKey::try_from(StarkFelt::from(address))
+        .map_err(|err| IoError::new(ErrorKind::InvalidData, err.to_string()))?;
+    Ok(KatanaContractAddress(address))
+}
+
+/// Kakarot contracts, Hive accounts and Cairo predeploys converted to Starknet, written to the
+/// genesis of a sequencer.
+struct ConvertedGenesis {
+    /// Cairo 0 classes, as (class hash, path of the compiled class).
+    classes: Vec<(FieldElement, String)>,
+    /// Deployed contracts, as (address, class hash).
+    contracts: Vec<(FieldElement, FieldElement)>,
+    /// Nonces of the Starknet accounts of the externally owned accounts which have one, as
+    /// (address, nonce).
+    nonces: Vec<(FieldElement, u64)>,
+    storage: Vec<((Felt, Felt), Felt)>,
+    stats: GenesisStats,
+}
+
+/// Converts the Hive genesis to Starknet.
+///
+/// This function will:
+/// 1. Compute the class hash of Kakarot contracts
+/// 2. Add Kakarot contracts (class, contract, storage)
+/// 3. Seed the blockhash registry with the historical block hashes of the Hive genesis, failing if
+///    one of them doesn't fit in a felt
+/// 4. Add Hive accounts (fund, storage, bytecode, nonce, proxy implementation)
+/// 5. Add the Cairo predeploys (class, contract, storage), failing if one of them is deployed at
+///    the address of another contract, or at one of the `deployed_addresses` of the sequencer
+///    genesis
+fn convert_hive_genesis(
+    hive_genesis: HiveGenesisConfig,
+    native_token_address: FieldElement,
+    predeploys: &[CairoPredeploy],
+    compiled_path: &Path,
+    mut deployed_addresses: HashSet<FieldElement>,
+) -> Result<ConvertedGenesis, IoError> {
+    hive_genesis.config.validate_fork_order()?;
+
+    let mut genesis = ConvertedGenesis {
+        classes: vec![],
+        contracts: vec![],
+        nonces: vec![],
+        storage: vec![],
+        stats: Default::default(),
+    };
+
     // Compute the class hash of Kakarot contracts
     let class_hashes = compute_kakarot_contracts_class_hash();
 
     // { contract : class_hash }
     let mut kakarot_contracts = HashMap::<String, FieldElement>::new();
 
-    // Add Kakarot contracts Contract Classes to loader
+    // Add Kakarot contracts Contract Classes
     // Vec so no need to sort
     class_hashes.iter().for_each(|(filename, class_hash)| {
-        madara_loader.contract_classes.push((
-            HexFelt(*class_hash),
-            ContractClass::Path {
-                // Add the compiled path to the Kakarot contract filename
-                path: compiled_path.join(filename).with_extension("json").into_os_string().into_string().unwrap(), /* safe unwrap,
-                                                                                             * valid path */
-                version: 0,
-            },
+        genesis.classes.push((
+            *class_hash,
+            // Add the compiled path to the Kakarot contract filename, safe unwrap since it's a valid path
+            compiled_path.join(filename).with_extension("json").into_os_string().into_string().unwrap(),
         ));
 
         // Add Kakarot contracts {contract : class_hash} to Kakarot Contracts HashMap
@@ -89,20 +383,19 @@ pub async fn serialize_hive_to_madara_genesis_config(
         *kakarot_contracts.get("contract_account").expect("Failed to get contract_account class hash");
     let eoa_class_hash = *kakarot_contracts.get("externally_owned_account").expect("Failed to get eoa class hash");
 
-    // Add Kakarot contracts to Loader
-    madara_loader.contracts.push((
-        HexFelt(*KAKAROT_ADDRESSES),
-        HexFelt(*kakarot_contracts.get("kakarot").expect("Failed to get kakarot class hash")),
-    ));
-    madara_loader.contracts.push((
-        HexFelt(*BLOCKHASH_REGISTRY_ADDRESS),
-        HexFelt(*kakarot_contracts.get("blockhash_registry").expect("Failed to get blockhash_registry class hash")),
+    // Add Kakarot contracts
+    genesis
+        .contracts
+        .push((*KAKAROT_ADDRESSES, *kakarot_contracts.get("kakarot").expect("Failed to get kakarot class hash")));
+    genesis.contracts.push((
+        *BLOCKHASH_REGISTRY_ADDRESS,
+        *kakarot_contracts.get("blockhash_registry").expect("Failed to get blockhash_registry class hash"),
     ));
 
     // Set storage keys of Kakarot contract
     // https://github.com/kkrt-labs/kakarot/blob/main/src/kakarot/constants.cairo
     let storage_keys = [
-        ("native_token_address", FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap()),
+        ("native_token_address", native_token_address),
         ("contract_account_class_hash", contract_account_class_hash),
         ("externally_owned_account", eoa_class_hash),
         ("account_proxy_class_hash", account_proxy_class_hash),
@@ -110,101 +403,305 @@ pub async fn serialize_hive_to_madara_genesis_config(
     ];
 
     storage_keys.iter().for_each(|(key, value)| {
-        let storage_tuple = genesis_set_storage_starknet_contract(*KAKAROT_ADDRESSES, key, &[], *value, 0);
-        madara_loader
-            .storage
-            .push(unsafe { std::mem::transmute::<((Felt, Felt), Felt), ((HexFelt, HexFelt), HexFelt)>(storage_tuple) });
+        genesis.storage.push(genesis_set_storage_starknet_contract(*KAKAROT_ADDRESSES, key, &[], *value, 0));
     });
 
-    // Add Hive accounts to loader
+    // Seed the blockhash registry, which stores the hashes as felts
+    for (block_number, block_hash) in &hive_genesis.block_hashes {
+        let block_hash = FieldElement::from_bytes_be(block_hash.as_fixed_bytes()).map_err(|_| {
+            IoError::new(
+                ErrorKind::InvalidInput,
+                format!("hash {block_hash:?} of block {block_number} doesn't fit in a felt"),
+            )
+        })?;
+        genesis.storage.push(genesis_set_blockhash(*BLOCKHASH_REGISTRY_ADDRESS, *block_number, block_hash));
+    }
+
+    // Add Hive accounts
     // Convert the EVM accounts to Starknet accounts using compute_starknet_address
     // Sort by key to ensure deterministic order
     let mut hive_accounts: Vec<(reth_primitives::H160, AccountInfo)> = hive_genesis.alloc.into_iter().collect();
     hive_accounts.sort_by_key(|(address, _)| *address);
-    hive_accounts.iter().for_each(|(evm_address, account_info)| {
-        // Use the given Kakarot contract address and declared proxy class hash for compute_starknet_address
-        let starknet_address = compute_starknet_address(
-            *KAKAROT_ADDRESSES,
-            account_proxy_class_hash,
-            FieldElement::from_byte_slice_be(evm_address.as_bytes()).unwrap(), /* safe unwrap since evm_address
-                                                                                * is 20 bytes */
-        );
-        // Push to contracts
-        madara_loader.contracts.push((HexFelt(starknet_address), HexFelt(account_proxy_class_hash)));
-
-        // Set the balance of the account
-        // Call genesis_fund_starknet_address util to get the storage tuples
-        let balance_storage_tuples = genesis_fund_starknet_address(starknet_address, account_info.balance);
-        balance_storage_tuples.iter().for_each(|balance_storage_tuple| {
-            madara_loader.storage.push(unsafe {
-                std::mem::transmute::<((Felt, Felt), Felt), ((HexFelt, HexFelt), HexFelt)>(*balance_storage_tuple)
-            });
-        });
+    let class_hashes = AccountClassHashes {
+        proxy: account_proxy_class_hash,
+        contract_account: contract_account_class_hash,
+        eoa: eoa_class_hash,
+    };
+    // The accounts are converted in parallel, collecting an indexed parallel iterator keeps the
+    // order of the accounts
+    let converted_accounts: Vec<ConvertedAccount> = hive_accounts
+        .par_iter()
+        .map(|(evm_address, account_info)| {
+            convert_hive_account(*evm_address, account_info, &class_hashes, native_token_address)
+        })
+        .collect();
 
-        // Set the storage of the account, if any
-        if let Some(storage) = account_info.storage.as_ref() {
-            let mut storage: Vec<(U256, U256)> = storage.iter().map(|(k, v)| (*k, *v)).collect();
-            storage.sort_by_key(|(key, _)| *key);
-            storage.iter().for_each(|(key, value)| {
-                // Call genesis_set_storage_kakarot_contract_account util to get the storage tuples
-                let storage_tuples = genesis_set_storage_kakarot_contract_account(starknet_address, *key, *value);
-                storage_tuples.iter().for_each(|storage_tuples| {
-                    madara_loader.storage.push(unsafe {
-                        std::mem::transmute::<((Felt, Felt), Felt), ((HexFelt, HexFelt), HexFelt)>(*storage_tuples)
-                    });
-                });
-            });
+    for account in converted_accounts {
+        let starknet_address = account.stats.starknet_address;
+        genesis.contracts.push((starknet_address, account_proxy_class_hash));
+        if account.nonce > 0 {
+            genesis.nonces.push((starknet_address, account.nonce));
         }
+        genesis.storage.extend(account.storage);
+        genesis.stats.accounts.push(account.stats);
+    }
 
-        // Determine the proxy implementation class hash based on whether bytecode is present
-        // Set the bytecode to the storage of the account, if any
-        let proxy_implementation_class_hash = if let Some(bytecode) = account_info.code.as_ref() {
-            // Call genesis_set_code_kakarot_contract_account util to get the storage tuples
-            let code_storage_tuples = genesis_set_bytecode(bytecode, starknet_address);
-            // Set the bytecode of the account
-            madara_loader.storage.extend(code_storage_tuples.iter().map(|code_storage_tuple| unsafe {
-                std::mem::transmute::<((Felt, Felt), Felt), ((HexFelt, HexFelt), HexFelt)>(*code_storage_tuple)
-            }));
-
-            // Since it has bytecode, it's a contract account
-            contract_account_class_hash
-        } else {
-            // Since it has no bytecode, it's an externally owned account
-            eoa_class_hash
-        };
+    // Add the Cairo predeploys
+    deployed_addresses.extend(genesis.contracts.iter().map(|(address, _)| *address));
+    for predeploy in predeploys {
+        let address = predeploy.address.0;
+        if !deployed_addresses.insert(address) {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("predeploy address {address:#x} is already used by another contract"),
+            ));
+        }
 
-        // Set the proxy implementation of the account to the determined class hash
-        let proxy_implementation_storage_tuples = genesis_set_storage_starknet_contract(
-            starknet_address,
-            "_implementation",
-            &[],
-            proxy_implementation_class_hash,
-            0, // 0 since it's storage value is felt
-        );
-        madara_loader.storage.push(unsafe {
-            std::mem::transmute::<((Felt, Felt), Felt), ((HexFelt, HexFelt), HexFelt)>(
-                proxy_implementation_storage_tuples,
-            )
-        });
-    });
+        let class_hash = predeploy.class_hash()?;
+        let path = predeploy.class_path.clone().into_os_string().into_string().map_err(|path| {
+            IoError::new(ErrorKind::InvalidInput, format!("class path {} is not valid UTF-8", path.to_string_lossy()))
+        })?;
+        genesis.classes.push((class_hash, path));
+        genesis.contracts.push((address, class_hash));
+        genesis.storage.extend(predeploy.storage.iter().map(|(key, value)| ((Felt(address), *key), *value)));
+    }
 
-    // Serialize the loader to a string
-    let madara_genesis_str = serde_json::to_string_pretty(&madara_loader)?;
-    // Write the string to a file
-    fs::write(combined_genesis, madara_genesis_str)?;
+    Ok(genesis)
+}
 
-    Ok(())
+/// Class hashes of the Kakarot accounts.
+struct AccountClassHashes {
+    proxy: FieldElement,
+    contract_account: FieldElement,
+    eoa: FieldElement,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Hive account converted to a Kakarot account: the storage tuples written for it, the nonce of
+/// its Starknet account and its size statistics.
+struct ConvertedAccount {
+    storage: Vec<((Felt, Felt), Felt)>,
+    /// Nonce of the Starknet account, the nonce of an externally owned account. The nonce of a
+    /// contract account is part of its storage.
+    nonce: u64,
+    stats: AccountStats,
+}
+
+/// Converts a Hive account to the storage tuples of a Kakarot account deployed at its Starknet
+/// address: its balance, its EVM storage, its bytecode and its proxy implementation.
+fn convert_hive_account(
+    evm_address: Address,
+    account_info: &AccountInfo,
+    class_hashes: &AccountClassHashes,
+    native_token_address: FieldElement,
+) -> ConvertedAccount {
+    // Use the given Kakarot contract address and declared proxy class hash for compute_starknet_address
+    let starknet_address = compute_starknet_address(
+        *KAKAROT_ADDRESSES,
+        class_hashes.proxy,
+        FieldElement::from_byte_slice_be(evm_address.as_bytes()).unwrap(), // safe unwrap since evm_address is 20 bytes
+    );
+
+    // Set the balance of the account
+    // Call genesis_fund_starknet_address util to get the storage tuples
+    let mut storage = genesis_fund_starknet_address(native_token_address, starknet_address, account_info.balance);
+    let mut stats = AccountStats {
+        evm_address,
+        starknet_address,
+        bytecode_size: 0,
+        bytecode_chunks: 0,
+        storage_entries: 0,
+        total_felts: 0,
+    };
+
+    // Set the storage of the account, if any
+    if let Some(account_storage) = account_info.storage.as_ref() {
+        let mut account_storage: Vec<(U256, U256)> = account_storage.iter().map(|(k, v)| (*k, *v)).collect();
+        account_storage.sort_by_key(|(key, _)| *key);
+        stats.storage_entries = account_storage.len();
+        for (key, value) in account_storage {
+            // Call genesis_set_storage_kakarot_contract_account util to get the storage tuples
+            storage.extend(genesis_set_storage_kakarot_contract_account(starknet_address, key, value));
+        }
+    }
+
+    // Determine the proxy implementation class hash based on whether bytecode is present
+    // Set the bytecode to the storage of the account, if any
+    let mut nonce = 0;
+    let proxy_implementation_class_hash = if let Some(bytecode) = account_info.code.as_ref() {
+        // Call genesis_set_code_kakarot_contract_account util to get the storage tuples
+        let code_storage_tuples = genesis_set_bytecode(bytecode, starknet_address);
+        stats.bytecode_size = bytecode.len();
+        stats.bytecode_chunks = code_storage_tuples.len();
+        storage.extend(code_storage_tuples);
+
+        if account_info.nonce() > 0 {
+            storage.push(genesis_set_nonce(starknet_address, account_info.nonce()));
+        }
+
+        // Since it has bytecode, it's a contract account
+        class_hashes.contract_account
+    } else {
+        // Since it has no bytecode, it's an externally owned account
+        nonce = account_info.nonce();
+        class_hashes.eoa
+    };
+
+    // Set the proxy implementation of the account to the determined class hash
+    storage.push(genesis_set_storage_starknet_contract(
+        starknet_address,
+        "_implementation",
+        &[],
+        proxy_implementation_class_hash,
+        0, // 0 since it's storage value is felt
+    ));
+
+    stats.total_felts = storage.len();
+    ConvertedAccount { storage, nonce, stats }
+}
+
+/// Converts a storage tuple to the felts of the loader.
+fn hex_felts(((address, key), value): ((Felt, Felt), Felt)) -> ((HexFelt, HexFelt), HexFelt) {
+    ((HexFelt(address.0), HexFelt(key.0)), HexFelt(value.0))
+}
+
+/// Chain config of the genesis, with the fork schedule, see
+/// https://github.com/ethereum/go-ethereum/blob/master/params/config.go. The forks activated by
+/// block number are unset when the chain doesn't activate them, as are the forks activated by
+/// timestamp.
+#[derive(Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
     pub chain_id: i128,
-    pub homestead_block: i128,
-    pub eip150_block: i128,
-    pub eip150_hash: H256,
-    pub eip155_block: i128,
-    pub eip158_block: i128,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homestead_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eip150_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eip150_hash: Option<H256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eip155_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eip158_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub byzantium_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constantinople_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub petersburg_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub istanbul_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub muir_glacier_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub berlin_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub london_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arrow_glacier_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gray_glacier_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_netsplit_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shanghai_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancun_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prague_time: Option<u64>,
+    /// Total difficulty at which the chain switched to the proof of stake.
+    #[serde(default, deserialize_with = "deserialize_optional_quantity", skip_serializing_if = "Option::is_none")]
+    pub terminal_total_difficulty: Option<U256>,
+    #[serde(default)]
+    pub terminal_total_difficulty_passed: bool,
+}
+
+impl Config {
+    /// Checks that the forks are scheduled in order, as geth does: a fork can't be activated before
+    /// the previous one, nor be activated while a previous fork isn't. The glacier forks, which
+    /// only delay the difficulty bomb, and the merge netsplit block can be left unset.
+    pub fn validate_fork_order(&self) -> Result<(), IoError> {
+        // (name, activation, optional)
+        let block_forks = [
+            ("homesteadBlock", self.homestead_block, false),
+            ("eip150Block", self.eip150_block, false),
+            ("eip155Block", self.eip155_block, false),
+            ("eip158Block", self.eip158_block, false),
+            ("byzantiumBlock", self.byzantium_block, false),
+            ("constantinopleBlock", self.constantinople_block, false),
+            ("petersburgBlock", self.petersburg_block, false),
+            ("istanbulBlock", self.istanbul_block, false),
+            ("muirGlacierBlock", self.muir_glacier_block, true),
+            ("berlinBlock", self.berlin_block, false),
+            ("londonBlock", self.london_block, false),
+            ("arrowGlacierBlock", self.arrow_glacier_block, true),
+            ("grayGlacierBlock", self.gray_glacier_block, true),
+            ("mergeNetsplitBlock", self.merge_netsplit_block, true),
+        ];
+        let time_forks = [
+            ("shanghaiTime", self.shanghai_time, false),
+            ("cancunTime", self.cancun_time, false),
+            ("pragueTime", self.prague_time, false),
+        ];
+        validate_forks(&block_forks)?;
+        validate_forks(&time_forks)?;
+
+        // The forks activated by timestamp follow the merge, and so London
+        if let (Some((name, _, _)), None) =
+            (time_forks.iter().find(|(_, activation, _)| activation.is_some()), self.london_block)
+        {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("unsupported fork ordering: londonBlock not enabled, but {name} enabled"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the forks, listed in their order, are activated in order.
+fn validate_forks(forks: &[(&str, Option<u64>, bool)]) -> Result<(), IoError> {
+    let mut last: Option<(&str, Option<u64>)> = None;
+    for (name, activation, optional) in forks {
+        if let Some((last_name, last_activation)) = last {
+            match (last_activation, activation) {
+                (None, Some(_)) => {
+                    return Err(IoError::new(
+                        ErrorKind::InvalidInput,
+                        format!("unsupported fork ordering: {last_name} not enabled, but {name} enabled"),
+                    ));
+                }
+                (Some(last_activation), Some(activation)) if last_activation > *activation => {
+                    return Err(IoError::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "unsupported fork ordering: {last_name} enabled at {last_activation}, but {name} enabled \
+                             at {activation}"
+                        ),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        // An unset optional fork doesn't prevent the next forks
+        if !optional || activation.is_some() {
+            last = Some((name, *activation));
+        }
+    }
+    Ok(())
+}
+
+/// Deserializes an optional quantity, given either as a JSON number or as a decimal or hex string,
+/// as geth accepts both in the genesis files.
+fn deserialize_optional_quantity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<U256>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Number(number)) => number
+            .as_u64()
+            .map(|quantity| Some(U256::from(quantity)))
+            .ok_or_else(|| D::Error::custom(format!("quantity {number} should be a 64 bits integer or a string"))),
+        Some(Value::String(quantity)) => U256::from_str(&quantity).map(Some).map_err(D::Error::custom),
+        Some(value) => Err(D::Error::custom(format!("invalid quantity {value}"))),
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -212,17 +709,31 @@ pub struct AccountInfo {
     pub balance: U256,
     pub code: Option<Bytes>,
     pub storage: Option<HashMap<U256, U256>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U64>,
+}
+
+impl AccountInfo {
+    /// Returns the nonce of the account, zero when not set.
+    pub fn nonce(&self) -> u64 {
+        self.nonce.map(|nonce| nonce.to::<u64>()).unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
 
+    use kakarot_rpc_core::client::constants::STARKNET_NATIVE_TOKEN;
     use pallet_starknet::genesis_loader::GenesisLoader;
     use reth_primitives::U256;
 
     use super::*;
 
+    lazy_static! {
+        static ref NATIVE_TOKEN: FieldElement = FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap();
+    }
+
     #[test]
     fn test_read_hive_genesis() {
         // Read the hive genesis file
@@ -256,12 +767,159 @@ mod tests {
             })
         }));
 
+        // Verify the chain id of the genesis
+        assert_eq!(ChainIdConfig::Fixed(7), genesis.chain_id().unwrap());
+
         // Verify the code field for each account, if exists, is not empty
         assert!(
             genesis.alloc.values().all(|account_info| account_info.code.as_ref().map_or(true, |code| !code.is_empty()))
         );
     }
 
+    #[test]
+    fn test_read_hive_genesis_with_modern_forks() {
+        // Given
+        let genesis = serde_json::json!({
+            "config": {
+                "chainId": 1337,
+                "homesteadBlock": 0,
+                "eip150Block": 0,
+                "eip155Block": 0,
+                "eip158Block": 0,
+                "byzantiumBlock": 0,
+                "constantinopleBlock": 0,
+                "petersburgBlock": 0,
+                "istanbulBlock": 0,
+                "berlinBlock": 0,
+                "londonBlock": 0,
+                "mergeNetsplitBlock": 0,
+                "shanghaiTime": 0,
+                "cancunTime": 1000,
+                "terminalTotalDifficulty": 0,
+                "terminalTotalDifficultyPassed": true
+            },
+            "coinbase": "0x0000000000000000000000000000000000000000",
+            "difficulty": "0x0",
+            "extraData": "0x",
+            "gasLimit": "0x1c9c380",
+            "nonce": "0x0",
+            "timestamp": "0x0",
+            "baseFeePerGas": "0x3b9aca00",
+            "alloc": {}
+        });
+
+        // When
+        let genesis: HiveGenesisConfig = serde_json::from_value(genesis).unwrap();
+
+        // Then
+        genesis.config.validate_fork_order().unwrap();
+        assert_eq!(Some(1000), genesis.config.cancun_time);
+        assert_eq!(Some(U256::ZERO), genesis.config.terminal_total_difficulty);
+        assert_eq!(None, genesis.config.eip150_hash);
+        let chain_spec = genesis.chain_spec().unwrap();
+        assert_eq!(1337, chain_spec.chain_id);
+        assert_eq!(1_000_000_000, chain_spec.base_fee_per_gas);
+        assert_eq!(30_000_000, chain_spec.block_gas_limit);
+    }
+
+    #[test]
+    fn test_convert_hive_account_with_nonce() {
+        // Given
+        let account_info: AccountInfo =
+            serde_json::from_value(serde_json::json!({ "balance": "0x1", "code": "0x6000", "nonce": "0x2" })).unwrap();
+        let class_hashes = AccountClassHashes {
+            proxy: FieldElement::from(1u8),
+            contract_account: FieldElement::from(2u8),
+            eoa: FieldElement::from(3u8),
+        };
+
+        // When
+        let account =
+            convert_hive_account(Address::from_low_u64_be(0xabc), &account_info, &class_hashes, *NATIVE_TOKEN);
+
+        // Then
+        let nonce_slot = genesis_set_nonce(account.stats.starknet_address, 2);
+        assert_eq!(2, account_info.nonce());
+        assert!(account.storage.contains(&nonce_slot));
+        let without_nonce: AccountInfo = serde_json::from_value(serde_json::json!({ "balance": "0x1" })).unwrap();
+        assert_eq!(0, without_nonce.nonce());
+    }
+
+    #[test]
+    fn test_convert_hive_genesis_is_deterministic() {
+        // Given
+        let account = |i: u64| AccountInfo {
+            balance: U256::from(i),
+            code: (i % 2 == 0).then(|| Bytes::from(vec![0x60, i as u8])),
+            storage: (i % 2 == 0).then(|| HashMap::from([(U256::from(i), U256::from(i)), (U256::ZERO, U256::from(1))])),
+            nonce: Some(U64::from(i)),
+        };
+        let mut hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        hive_genesis.alloc = (1..=64).map(|i| (Address::from_low_u64_be(i), account(i))).collect();
+        let kakarot_contracts: HashMap<String, FieldElement> =
+            compute_kakarot_contracts_class_hash().into_iter().collect();
+        let class_hashes = AccountClassHashes {
+            proxy: kakarot_contracts["proxy"],
+            contract_account: kakarot_contracts["contract_account"],
+            eoa: kakarot_contracts["externally_owned_account"],
+        };
+
+        // When
+        let genesis = convert_hive_genesis(
+            hive_genesis,
+            *NATIVE_TOKEN,
+            &[],
+            Path::new("./cairo-contracts/build"),
+            HashSet::new(),
+        )
+        .unwrap();
+
+        // Then
+        // The accounts converted in parallel are written in the order of the sequential conversion
+        let sequential: Vec<((Felt, Felt), Felt)> = (1..=64)
+            .flat_map(|i| {
+                convert_hive_account(Address::from_low_u64_be(i), &account(i), &class_hashes, *NATIVE_TOKEN).storage
+            })
+            .collect();
+        let accounts_storage = &genesis.storage[genesis.storage.len() - sequential.len()..];
+        assert_eq!(serde_json::to_string(&sequential).unwrap(), serde_json::to_string(accounts_storage).unwrap());
+        let evm_addresses: Vec<Address> = genesis.stats.accounts.iter().map(|account| account.evm_address).collect();
+        assert_eq!((1..=64).map(Address::from_low_u64_be).collect::<Vec<_>>(), evm_addresses);
+    }
+
+    #[test]
+    fn test_validate_fork_order() {
+        // Given
+        let base = || Config {
+            chain_id: 7,
+            homestead_block: Some(0),
+            eip150_block: Some(0),
+            eip155_block: Some(0),
+            eip158_block: Some(0),
+            byzantium_block: Some(0),
+            constantinople_block: Some(0),
+            petersburg_block: Some(0),
+            istanbul_block: Some(0),
+            berlin_block: Some(10),
+            london_block: Some(20),
+            ..Default::default()
+        };
+
+        // Then
+        base().validate_fork_order().unwrap();
+        // The glacier forks are optional
+        Config { arrow_glacier_block: Some(30), ..base() }.validate_fork_order().unwrap();
+        let unordered = Config { london_block: Some(5), ..base() }.validate_fork_order().unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, unordered.kind());
+        assert!(unordered.to_string().contains("londonBlock"));
+        let missing = Config { berlin_block: None, ..base() }.validate_fork_order().unwrap_err();
+        assert!(missing.to_string().contains("berlinBlock not enabled"));
+        let time_before_london = Config { london_block: None, shanghai_time: Some(0), ..base() };
+        assert!(time_before_london.validate_fork_order().is_err());
+        let unordered_time = Config { shanghai_time: Some(10), cancun_time: Some(5), ..base() };
+        assert!(unordered_time.validate_fork_order().is_err());
+    }
+
     #[tokio::test]
     async fn test_madara_genesis() {
         // Given
@@ -272,17 +930,342 @@ mod tests {
         let compiled_path = Path::new("./cairo-contracts/build");
 
         // When
-        serialize_hive_to_madara_genesis_config(hive_genesis, madara_loader, combined_genesis, compiled_path)
-            .await
-            .unwrap();
+        let stats = serialize_hive_to_madara_genesis_config(
+            hive_genesis,
+            madara_loader,
+            *NATIVE_TOKEN,
+            &[],
+            combined_genesis,
+            compiled_path,
+        )
+        .await
+        .unwrap();
 
         // Then
+        assert_eq!(7, stats.accounts.len());
+        assert!(stats.accounts.windows(2).all(|pair| pair[0].evm_address < pair[1].evm_address));
+        assert_eq!(2, stats.total_storage_entries()); // the only account with storage has 2 slots
         let combined_genesis = fs::read_to_string("./src/test_data/combined_genesis.json").unwrap();
         let loader: GenesisLoader =
             serde_json::from_str(&combined_genesis).expect("Failed to read combined_genesis.json");
         assert_eq!(9 + 2 + 7, loader.contracts.len()); // 9 original + 2 Kakarot contracts + 7 hive
+        assert!(loader.contracts.windows(2).all(|pair| pair[0].0.0 < pair[1].0.0));
+        let storage_keys: Vec<_> = loader.storage.iter().map(|((address, key), _)| (address.0, key.0)).collect();
+        assert!(storage_keys.windows(2).all(|pair| pair[0] < pair[1]));
 
         // After
         fs::remove_file("./src/test_data/combined_genesis.json").unwrap();
     }
+
+    #[tokio::test]
+    async fn test_madara_genesis_with_eoa_nonce() {
+        // Given
+        let mut hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        let sender = Address::from_str("0xcf49fda3be353c69b41ed96333cd24302da4556f").unwrap();
+        hive_genesis.alloc.get_mut(&sender).unwrap().nonce = Some(U64::from(3));
+        let madara_loader =
+            serde_json::from_str::<GenesisLoader>(std::include_str!("../test_data/madara_genesis.json")).unwrap();
+        let combined_genesis = Path::new("./src/test_data/combined_genesis_with_eoa_nonce.json");
+        let compiled_path = Path::new("./cairo-contracts/build");
+
+        // When
+        let stats = serialize_hive_to_madara_genesis_config(
+            hive_genesis,
+            madara_loader,
+            *NATIVE_TOKEN,
+            &[],
+            combined_genesis,
+            compiled_path,
+        )
+        .await
+        .unwrap();
+
+        // Then
+        let starknet_address =
+            stats.accounts.iter().find(|account| account.evm_address == sender).unwrap().starknet_address;
+        let loader: GenesisLoader = serde_json::from_str(&fs::read_to_string(combined_genesis).unwrap()).unwrap();
+        assert_eq!(1, loader.nonces.len());
+        assert_eq!((starknet_address, FieldElement::from(3u8)), (loader.nonces[0].0.0, loader.nonces[0].1.0));
+
+        // After
+        fs::remove_file(combined_genesis).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_katana_genesis() {
+        // Given
+        let hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        let katana_genesis = Path::new("./src/test_data/katana_genesis.json");
+        let compiled_path = Path::new("./cairo-contracts/build");
+        let (katana_state, native_token_class_hash, erc20_key) = katana_state_with_native_token(compiled_path);
+
+        // When
+        let stats = serialize_hive_to_katana_genesis(
+            hive_genesis,
+            katana_state,
+            *NATIVE_TOKEN,
+            &[],
+            katana_genesis,
+            compiled_path,
+        )
+        .await
+        .unwrap();
+
+        // Then
+        assert_eq!(7, stats.accounts.len());
+        let state: SerializableState = serde_json::from_str(&fs::read_to_string(katana_genesis).unwrap()).unwrap();
+        assert_eq!(1 + 2 + 7, state.storage.len()); // native token + 2 Kakarot contracts + 7 hive
+        let native_token = state.storage.get(&katana_contract_address(*NATIVE_TOKEN).unwrap()).unwrap();
+        assert_eq!(native_token_class_hash, native_token.class_hash);
+        assert!(state.classes.contains_key(&native_token_class_hash));
+        assert!(native_token.storage.contains_key(&erc20_key)); // ERC20 storage of the Katana genesis
+        assert!(native_token.storage.len() > 1); // balances of the hive accounts
+        let kakarot = state.storage.get(&katana_contract_address(*KAKAROT_ADDRESSES).unwrap()).unwrap();
+        assert_eq!(CompiledClassHash::default(), state.classes.get(&kakarot.class_hash).unwrap().compiled_hash);
+
+        // After
+        fs::remove_file(katana_genesis).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_katana_genesis_rejects_undeclared_native_token_class() {
+        // Given
+        let hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        let compiled_path = Path::new("./cairo-contracts/build");
+        let (mut katana_state, _, _) = katana_state_with_native_token(compiled_path);
+        katana_state.classes.clear();
+
+        // When
+        let result = serialize_hive_to_katana_genesis(
+            hive_genesis,
+            katana_state,
+            *NATIVE_TOKEN,
+            &[],
+            Path::new("./src/test_data/unused_katana_genesis.json"),
+            compiled_path,
+        )
+        .await;
+
+        // Then
+        assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_katana_class_record_rejects_invalid_class() {
+        // Given
+        let path = Path::new("./src/test_data/hive_genesis.json");
+
+        // Then
+        assert_eq!(ErrorKind::InvalidData, katana_class_record(path).unwrap_err().kind());
+    }
+
+    /// Returns a Katana genesis state deploying the native token with a storage entry, along with
+    /// the class hash of the native token and the key of the entry.
+    fn katana_state_with_native_token(compiled_path: &Path) -> (SerializableState, ClassHash, KatanaStorageKey) {
+        let class_hash = ClassHash(StarkFelt::from(FieldElement::from(0x20000_u64)));
+        let key = KatanaStorageKey(PatriciaKey::try_from(StarkFelt::from(FieldElement::from(0x1234_u64))).unwrap());
+        let mut state = SerializableState::default();
+        let class_path = compiled_path.join("blockhash_registry.json");
+        state.classes.insert(class_hash, katana_class_record(&class_path).unwrap());
+        let record = SerializableStorageRecord {
+            nonce: Nonce::default(),
+            class_hash,
+            storage: BTreeMap::from([(key, StarkFelt::from(FieldElement::from(18_u8)))]),
+        };
+        state.storage.insert(katana_contract_address(*NATIVE_TOKEN).unwrap(), record);
+        (state, class_hash, key)
+    }
+
+    #[tokio::test]
+    async fn test_madara_genesis_with_block_hashes() {
+        // Given
+        let mut hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        let block_hash = H256::from_low_u64_be(0x1234);
+        hive_genesis.block_hashes.insert(1, block_hash);
+        let madara_loader =
+            serde_json::from_str::<GenesisLoader>(std::include_str!("../test_data/madara_genesis.json")).unwrap();
+        let combined_genesis = Path::new("./src/test_data/combined_genesis_with_block_hashes.json");
+        let compiled_path = Path::new("./cairo-contracts/build");
+
+        // When
+        serialize_hive_to_madara_genesis_config(
+            hive_genesis,
+            madara_loader,
+            *NATIVE_TOKEN,
+            &[],
+            combined_genesis,
+            compiled_path,
+        )
+        .await
+        .unwrap();
+
+        // Then
+        let loader: GenesisLoader = serde_json::from_str(&fs::read_to_string(combined_genesis).unwrap()).unwrap();
+        let ((_, expected_key), _) =
+            genesis_set_blockhash(*BLOCKHASH_REGISTRY_ADDRESS, 1, FieldElement::from(0x1234_u64));
+        assert!(loader.storage.iter().any(|((address, key), value)| {
+            address.0 == *BLOCKHASH_REGISTRY_ADDRESS
+                && key.0 == expected_key.0
+                && value.0 == FieldElement::from(0x1234_u64)
+        }));
+
+        // After
+        fs::remove_file(combined_genesis).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_madara_genesis_rejects_block_hash_above_prime() {
+        // Given
+        let mut hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        hive_genesis.block_hashes.insert(1, H256::repeat_byte(0xff));
+        let madara_loader =
+            serde_json::from_str::<GenesisLoader>(std::include_str!("../test_data/madara_genesis.json")).unwrap();
+
+        // When
+        let result = serialize_hive_to_madara_genesis_config(
+            hive_genesis,
+            madara_loader,
+            *NATIVE_TOKEN,
+            &[],
+            Path::new("./src/test_data/unused_genesis.json"),
+            Path::new("./cairo-contracts/build"),
+        )
+        .await;
+
+        // Then
+        assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+    }
+
+    #[tokio::test]
+    async fn test_madara_genesis_with_predeploys() {
+        // Given
+        let hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        let madara_loader =
+            serde_json::from_str::<GenesisLoader>(std::include_str!("../test_data/madara_genesis.json")).unwrap();
+        let combined_genesis = Path::new("./src/test_data/combined_genesis_with_predeploys.json");
+        let compiled_path = Path::new("./cairo-contracts/build");
+        let oracle_address = FieldElement::from(0x9003_u64);
+        let predeploys = [CairoPredeploy {
+            address: Felt(oracle_address),
+            class_path: compiled_path.join("blockhash_registry.json"),
+            storage: vec![(Felt(FieldElement::ONE), Felt(FieldElement::TWO))],
+        }];
+
+        // When
+        serialize_hive_to_madara_genesis_config(
+            hive_genesis,
+            madara_loader,
+            *NATIVE_TOKEN,
+            &predeploys,
+            combined_genesis,
+            compiled_path,
+        )
+        .await
+        .unwrap();
+
+        // Then
+        let loader: GenesisLoader = serde_json::from_str(&fs::read_to_string(combined_genesis).unwrap()).unwrap();
+        assert_eq!(9 + 2 + 7 + 1, loader.contracts.len()); // 9 original + 2 Kakarot contracts + 7 hive + 1 predeploy
+        let (_, class_hash) = loader.contracts.iter().find(|(address, _)| address.0 == oracle_address).unwrap();
+        assert!(loader.contract_classes.iter().any(|(hash, _)| hash.0 == class_hash.0));
+        assert!(loader.storage.iter().any(|((address, key), value)| {
+            address.0 == oracle_address && key.0 == FieldElement::ONE && value.0 == FieldElement::TWO
+        }));
+
+        // After
+        fs::remove_file(combined_genesis).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_madara_genesis_rejects_predeploy_at_used_address() {
+        // Given
+        let hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        let madara_loader =
+            serde_json::from_str::<GenesisLoader>(std::include_str!("../test_data/madara_genesis.json")).unwrap();
+        let compiled_path = Path::new("./cairo-contracts/build");
+        let predeploys = [CairoPredeploy {
+            address: Felt(*KAKAROT_ADDRESSES),
+            class_path: compiled_path.join("blockhash_registry.json"),
+            storage: vec![],
+        }];
+
+        // When
+        let result = serialize_hive_to_madara_genesis_config(
+            hive_genesis,
+            madara_loader,
+            *NATIVE_TOKEN,
+            &predeploys,
+            Path::new("./src/test_data/unused_genesis.json"),
+            compiled_path,
+        )
+        .await;
+
+        // Then
+        assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+    }
+
+    #[tokio::test]
+    async fn test_madara_genesis_rejects_undeployed_native_token() {
+        // Given
+        let hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        let madara_loader =
+            serde_json::from_str::<GenesisLoader>(std::include_str!("../test_data/madara_genesis.json")).unwrap();
+
+        // When
+        let result = serialize_hive_to_madara_genesis_config(
+            hive_genesis,
+            madara_loader,
+            FieldElement::from(0x1234_u64),
+            &[],
+            Path::new("./src/test_data/unused_genesis.json"),
+            Path::new("./cairo-contracts/build"),
+        )
+        .await;
+
+        // Then
+        assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_canonicalize_genesis() {
+        // Given
+        let genesis = std::include_str!("../test_data/madara_genesis.json");
+        let mut loader = serde_json::from_str::<GenesisLoader>(genesis).unwrap();
+        let mut reversed_loader = serde_json::from_str::<GenesisLoader>(genesis).unwrap();
+        reversed_loader.contract_classes.reverse();
+        reversed_loader.contracts.reverse();
+        reversed_loader.storage.reverse();
+
+        // When
+        loader.canonicalize();
+        reversed_loader.canonicalize();
+        let canonical = serde_json::to_string(&loader).unwrap();
+        loader.canonicalize();
+
+        // Then
+        assert_eq!(canonical, serde_json::to_string(&reversed_loader).unwrap());
+        assert_eq!(canonical, serde_json::to_string(&loader).unwrap());
+    }
+
+    #[test]
+    fn test_canonicalize_keeps_last_storage_entry() {
+        // Given
+        let mut loader =
+            serde_json::from_str::<GenesisLoader>(std::include_str!("../test_data/madara_genesis.json")).unwrap();
+        let (address, key) = (FieldElement::from(0x9001_u64), FieldElement::from(1_u64));
+        loader.storage.push(((HexFelt(address), HexFelt(key)), HexFelt(FieldElement::from(1_u64))));
+        loader.storage.push(((HexFelt(address), HexFelt(key)), HexFelt(FieldElement::from(2_u64))));
+
+        // When
+        loader.canonicalize();
+
+        // Then
+        let values: Vec<FieldElement> = loader
+            .storage
+            .iter()
+            .filter(|((a, k), _)| a.0 == address && k.0 == key)
+            .map(|(_, value)| value.0)
+            .collect();
+        assert_eq!(vec![FieldElement::from(2_u64)], values);
+    }
 }
@@ -0,0 +1,245 @@
+//! Reverse of the Hive genesis conversion: exports the state of a live Kakarot deployment at a
+//! block as the `alloc` of a geth genesis, to fork the deployment into a local test environment.
+//!
+//! The accounts exported are the ones deployed by Kakarot, found from their `evm_contract_deployed`
+//! events, and still deployed at the block. The storage of a contract account is enumerated from
+//! the storage keys written to its Starknet account by the state diffs of the blocks up to the
+//! export block. Kakarot keeps an EVM slot under a hashed Starknet key, so a written key is mapped
+//! back to its slot by hashing candidate slots: the first [`SEQUENTIAL_SLOTS`] slots, which hold
+//! the state variables of the usual storage layouts, and the slots given by the caller, e.g. the
+//! mapping slots of the genesis the deployment started from. Keys matching no candidate, such as
+//! the bytecode chunks, aren't exported.
+use std::collections::{HashMap, HashSet};
+
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::client::errors::EthApiError;
+use kakarot_rpc_core::models::account::AccountType;
+use kakarot_rpc_core::models::ConversionError;
+use reth_primitives::{Address, BlockId, Bytes, U256, U64};
+use starknet::core::types::{BlockId as StarknetBlockId, FieldElement, MaybePendingStateUpdate};
+use starknet::providers::Provider;
+
+use super::AccountInfo;
+use crate::madara::utils::genesis_set_storage_kakarot_contract_account;
+
+/// Number of the first EVM slots tried as preimages of the storage keys written to the contract
+/// accounts.
+pub const SEQUENTIAL_SLOTS: u64 = 256;
+
+/// Exports the accounts deployed by Kakarot at the block as a geth genesis `alloc`, trying the
+/// `candidate_slots` on top of the sequential slots to decode the storage of the contract
+/// accounts.
+pub async fn export_hive_alloc<P: Provider + Send + Sync>(
+    client: &dyn KakarotEthApi<P>,
+    block_id: BlockId,
+    candidate_slots: &[U256],
+) -> Result<HashMap<Address, AccountInfo>, EthApiError<P::Error>> {
+    let written_keys = written_storage_keys(client, block_id).await?;
+    let slots = StorageSlots::new(candidate_slots);
+
+    let mut alloc = HashMap::new();
+    for account in client.deployed_accounts().await? {
+        let address = account.evm_address;
+        let account_type = client.account_details(address, block_id).await?.account_type;
+        // Accounts deployed after the block
+        if account_type == AccountType::Undeployed {
+            continue;
+        }
+
+        let (balance, nonce) = tokio::try_join!(client.balance(address, block_id), client.nonce(address, block_id))?;
+        let mut code = Bytes::default();
+        let mut storage = Vec::new();
+        if account_type == AccountType::Contract {
+            code = client.get_code(address, block_id).await?;
+            let keys = written_keys.get(&account.starknet_address).into_iter().flatten();
+            for slot in slots.resolve(keys) {
+                storage.push((slot, client.storage_at(address, slot, block_id).await?));
+            }
+        }
+
+        alloc.insert(address, exported_account(balance, nonce, code, storage)?);
+    }
+
+    Ok(alloc)
+}
+
+/// Returns the storage keys written to each Starknet contract by the blocks up to the block.
+async fn written_storage_keys<P: Provider + Send + Sync>(
+    client: &dyn KakarotEthApi<P>,
+    block_id: BlockId,
+) -> Result<HashMap<FieldElement, HashSet<FieldElement>>, EthApiError<P::Error>> {
+    let starknet_block_id = client.starknet_block_id(block_id).await?;
+    let block_number = client.map_block_id_to_block_number(&starknet_block_id).await?;
+    let provider = client.starknet_provider();
+
+    let mut written_keys: HashMap<FieldElement, HashSet<FieldElement>> = HashMap::new();
+    for number in 0..=block_number {
+        let state_diff = match provider.get_state_update(StarknetBlockId::Number(number)).await? {
+            MaybePendingStateUpdate::Update(update) => update.state_diff,
+            MaybePendingStateUpdate::PendingUpdate(update) => update.state_diff,
+        };
+        for diff in state_diff.storage_diffs {
+            written_keys.entry(diff.address).or_default().extend(diff.storage_entries.iter().map(|entry| entry.key));
+        }
+    }
+
+    Ok(written_keys)
+}
+
+/// Preimages of the Starknet storage keys of the EVM slots of a Kakarot contract account, which
+/// don't depend on the account.
+struct StorageSlots(HashMap<FieldElement, U256>);
+
+impl StorageSlots {
+    fn new(candidate_slots: &[U256]) -> Self {
+        let slots = (0..SEQUENTIAL_SLOTS).map(U256::from).chain(candidate_slots.iter().copied());
+        Self(
+            slots
+                .flat_map(|slot| {
+                    genesis_set_storage_kakarot_contract_account(FieldElement::ZERO, slot, U256::ZERO)
+                        .into_iter()
+                        .map(move |((_, key), _)| (key.0, slot))
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the sorted EVM slots of the written Starknet keys, a slot being written by either
+    /// of its two keys.
+    fn resolve<'a>(&self, keys: impl IntoIterator<Item = &'a FieldElement>) -> Vec<U256> {
+        let slots: HashSet<U256> = keys.into_iter().filter_map(|key| self.0.get(key).copied()).collect();
+        let mut slots: Vec<U256> = slots.into_iter().collect();
+        slots.sort();
+        slots
+    }
+}
+
+/// Builds the genesis account read from Kakarot, leaving out the empty code and storage slots
+/// which geth genesis files omit, and failing on a nonce which doesn't fit the genesis nonce.
+fn exported_account(
+    balance: U256,
+    nonce: U256,
+    code: Bytes,
+    storage: Vec<(U256, U256)>,
+) -> Result<AccountInfo, ConversionError<u64>> {
+    let nonce: u64 = nonce.try_into()?;
+    let storage: HashMap<U256, U256> = storage.into_iter().filter(|(_, value)| *value != U256::ZERO).collect();
+    Ok(AccountInfo {
+        balance,
+        code: (!code.is_empty()).then_some(code),
+        storage: (!storage.is_empty()).then_some(storage),
+        nonce: (nonce != 0).then(|| U64::from(nonce)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use kakarot_rpc_core::client::constants::STARKNET_NATIVE_TOKEN;
+
+    use super::super::{convert_hive_genesis, HiveGenesisConfig};
+    use super::*;
+
+    #[test]
+    fn test_exported_account() {
+        // Given
+        let code = Bytes::from(vec![0x60, 0x00]);
+        let storage = vec![(U256::from(1), U256::from(42)), (U256::from(2), U256::ZERO)];
+
+        // When
+        let contract = exported_account(U256::from(10), U256::from(1), code.clone(), storage).unwrap();
+        let eoa = exported_account(U256::from(10), U256::ZERO, Bytes::default(), vec![]).unwrap();
+
+        // Then
+        assert_eq!(Some(code), contract.code);
+        assert_eq!(Some(HashMap::from([(U256::from(1), U256::from(42))])), contract.storage);
+        assert_eq!(1, contract.nonce());
+        assert_eq!(U256::from(10), eoa.balance);
+        assert!(eoa.code.is_none() && eoa.storage.is_none() && eoa.nonce.is_none());
+    }
+
+    #[test]
+    fn test_exported_account_rejects_nonce_overflow() {
+        // Given
+        let nonce = U256::from(u64::MAX) + U256::from(1);
+
+        // When
+        let result = exported_account(U256::ZERO, nonce, Bytes::default(), vec![]);
+
+        // Then
+        assert!(matches!(result, Err(ConversionError::UintConversionError(_))));
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        // Given
+        let compiled_path = Path::new("./cairo-contracts/build");
+        let native_token = FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap();
+        let mapping_slot =
+            U256::from_str_radix("6661e9d6d8b923d5bbaab1b96e1dd51ff6ea2a93520fdc9eb75d059238b8c5e9", 16).unwrap();
+        let contract = Address::from_low_u64_be(0xc0de);
+        let eoa = Address::from_low_u64_be(0xe0a);
+        let original = HashMap::from([
+            (U256::from(0), U256::from(0x1234)),
+            (U256::from(3), U256::from(7)),
+            (mapping_slot, U256::from(0x5678)),
+        ]);
+        let mut hive_genesis = HiveGenesisConfig::from_file("./src/test_data/hive_genesis.json").unwrap();
+        hive_genesis.alloc = HashMap::from([
+            (
+                contract,
+                AccountInfo {
+                    balance: U256::from(1),
+                    code: Some(Bytes::from(vec![0x60, 0x00])),
+                    storage: Some(original.clone()),
+                    nonce: Some(U64::from(2)),
+                },
+            ),
+            (eoa, AccountInfo { balance: U256::from(2), code: None, storage: None, nonce: Some(U64::from(5)) }),
+        ]);
+        let hive_genesis = serde_json::to_string(&hive_genesis).unwrap();
+        let imported = convert_hive_genesis(
+            serde_json::from_str(&hive_genesis).unwrap(),
+            native_token,
+            &[],
+            compiled_path,
+            HashSet::new(),
+        )
+        .unwrap();
+
+        // When
+        // The keys written to the contract account, as found in the state diffs
+        let starknet_address =
+            imported.stats.accounts.iter().find(|account| account.evm_address == contract).unwrap().starknet_address;
+        let written_keys: Vec<FieldElement> = imported
+            .storage
+            .iter()
+            .filter(|((address, _), _)| address.0 == starknet_address)
+            .map(|((_, key), _)| key.0)
+            .collect();
+        let slots = StorageSlots::new(&[mapping_slot]).resolve(&written_keys);
+        let storage = slots.iter().map(|slot| (*slot, original[slot])).collect();
+        let mut exported: serde_json::Value = serde_json::from_str(&hive_genesis).unwrap();
+        exported["alloc"] = serde_json::to_value(HashMap::from([
+            (contract, exported_account(U256::from(1), U256::from(2), Bytes::from(vec![0x60, 0x00]), storage).unwrap()),
+            (eoa, exported_account(U256::from(2), U256::from(5), Bytes::default(), vec![]).unwrap()),
+        ]))
+        .unwrap();
+        let reimported = convert_hive_genesis(
+            serde_json::from_value(exported).unwrap(),
+            native_token,
+            &[],
+            compiled_path,
+            HashSet::new(),
+        )
+        .unwrap();
+
+        // Then
+        assert_eq!(vec![U256::from(0), U256::from(3), mapping_slot], slots);
+        assert_eq!(imported.storage, reimported.storage);
+        assert_eq!(imported.contracts, reimported.contracts);
+        assert_eq!(imported.nonces, reimported.nonces);
+        assert_eq!(1, reimported.nonces.len());
+    }
+}
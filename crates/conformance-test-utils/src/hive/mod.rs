@@ -1 +1,2 @@
 pub mod genesis;
+pub mod stats;
@@ -0,0 +1,175 @@
+//! Size statistics of the accounts converted to the Madara genesis.
+//!
+//! The sequencers load the whole genesis when booting, an oversized genesis only fails then. The
+//! statistics are checked against [`GenesisLimits`] right after the conversion instead, and each
+//! exceeded limit is reported as a warning.
+use std::fmt;
+
+use reth_primitives::Address;
+use starknet::core::types::FieldElement;
+
+/// Maximum size of the bytecode of a contract, from EIP-170.
+pub const MAX_BYTECODE_SIZE: usize = 24_576;
+/// Default maximum number of storage felts written for a single account.
+pub const DEFAULT_MAX_ACCOUNT_FELTS: usize = 100_000;
+/// Default maximum number of storage felts written for all the accounts.
+pub const DEFAULT_MAX_TOTAL_FELTS: usize = 5_000_000;
+
+/// Limits above which a genesis is unlikely to boot on Madara or Katana in practice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenesisLimits {
+    /// Maximum size of the bytecode of an account, in bytes.
+    pub max_bytecode_size: usize,
+    /// Maximum number of storage felts written for a single account.
+    pub max_account_felts: usize,
+    /// Maximum number of storage felts written for all the accounts.
+    pub max_total_felts: usize,
+}
+
+impl Default for GenesisLimits {
+    fn default() -> Self {
+        Self {
+            max_bytecode_size: MAX_BYTECODE_SIZE,
+            max_account_felts: DEFAULT_MAX_ACCOUNT_FELTS,
+            max_total_felts: DEFAULT_MAX_TOTAL_FELTS,
+        }
+    }
+}
+
+/// Storage written to the genesis for an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountStats {
+    pub evm_address: Address,
+    pub starknet_address: FieldElement,
+    /// Size of the bytecode of the account, in bytes.
+    pub bytecode_size: usize,
+    /// Number of 16 bytes chunks of bytecode written.
+    pub bytecode_chunks: usize,
+    /// Number of EVM storage slots written.
+    pub storage_entries: usize,
+    /// Number of storage felts written for the account: balance, bytecode, EVM storage and proxy
+    /// implementation.
+    pub total_felts: usize,
+}
+
+/// Storage written to the genesis for all the accounts, in the order of the accounts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenesisStats {
+    pub accounts: Vec<AccountStats>,
+}
+
+impl GenesisStats {
+    pub fn total_bytecode_chunks(&self) -> usize {
+        self.accounts.iter().map(|account| account.bytecode_chunks).sum()
+    }
+
+    pub fn total_storage_entries(&self) -> usize {
+        self.accounts.iter().map(|account| account.storage_entries).sum()
+    }
+
+    pub fn total_felts(&self) -> usize {
+        self.accounts.iter().map(|account| account.total_felts).sum()
+    }
+
+    /// Returns a warning for each limit exceeded by the genesis.
+    pub fn warnings(&self, limits: &GenesisLimits) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for account in &self.accounts {
+            if account.bytecode_size > limits.max_bytecode_size {
+                warnings.push(format!(
+                    "account {:?} has {} bytes of bytecode, more than the {} bytes limit",
+                    account.evm_address, account.bytecode_size, limits.max_bytecode_size
+                ));
+            }
+            if account.total_felts > limits.max_account_felts {
+                warnings.push(format!(
+                    "account {:?} writes {} storage felts, more than the {} felts limit",
+                    account.evm_address, account.total_felts, limits.max_account_felts
+                ));
+            }
+        }
+        if self.total_felts() > limits.max_total_felts {
+            warnings.push(format!(
+                "the accounts write {} storage felts, more than the {} felts limit",
+                self.total_felts(),
+                limits.max_total_felts
+            ));
+        }
+        warnings
+    }
+}
+
+impl fmt::Display for GenesisStats {
+    /// Formats a line per account followed by the summary and the warnings for the default
+    /// limits.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for account in &self.accounts {
+            writeln!(
+                f,
+                "{:?} ({:#x}): {} bytecode chunks, {} storage entries, {} felts",
+                account.evm_address,
+                account.starknet_address,
+                account.bytecode_chunks,
+                account.storage_entries,
+                account.total_felts
+            )?;
+        }
+        writeln!(
+            f,
+            "{} accounts: {} bytecode chunks, {} storage entries, {} felts",
+            self.accounts.len(),
+            self.total_bytecode_chunks(),
+            self.total_storage_entries(),
+            self.total_felts()
+        )?;
+        for warning in self.warnings(&GenesisLimits::default()) {
+            writeln!(f, "warning: {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(bytecode_size: usize, storage_entries: usize) -> AccountStats {
+        let bytecode_chunks = (bytecode_size + 15) / 16;
+        AccountStats {
+            evm_address: Address::from_low_u64_be(1),
+            starknet_address: FieldElement::ONE,
+            bytecode_size,
+            bytecode_chunks,
+            storage_entries,
+            total_felts: 2 + bytecode_chunks + 2 * storage_entries + 1,
+        }
+    }
+
+    #[test]
+    fn test_genesis_stats_totals() {
+        // Given
+        let stats = GenesisStats { accounts: vec![account(0, 0), account(32, 2)] };
+
+        // Then
+        assert_eq!(2, stats.total_bytecode_chunks());
+        assert_eq!(2, stats.total_storage_entries());
+        assert_eq!(3 + 9, stats.total_felts());
+        assert!(stats.warnings(&GenesisLimits::default()).is_empty());
+        assert!(stats.to_string().ends_with("2 accounts: 2 bytecode chunks, 2 storage entries, 12 felts\n"));
+    }
+
+    #[test]
+    fn test_genesis_stats_warnings() {
+        // Given
+        let stats = GenesisStats { accounts: vec![account(MAX_BYTECODE_SIZE + 1, 0), account(0, 10)] };
+        let limits = GenesisLimits { max_account_felts: 20, max_total_felts: 1_000, ..Default::default() };
+
+        // When
+        let warnings = stats.warnings(&limits);
+
+        // Then
+        assert_eq!(4, warnings.len());
+        assert!(warnings[0].contains("bytes of bytecode"));
+        assert!(warnings[3].starts_with("the accounts write"));
+    }
+}
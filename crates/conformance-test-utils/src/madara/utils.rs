@@ -1,5 +1,4 @@
-use kakarot_rpc_core::client::constants::STARKNET_NATIVE_TOKEN;
-use kakarot_rpc_core::client::helpers::split_u256_into_field_elements;
+use kakarot_rpc_core::models::conversions::{bytes_to_u128_felts, u256_to_felts};
 use reth_primitives::{Bytes, U256};
 use starknet::core::types::FieldElement;
 use starknet::core::utils::get_storage_var_address;
@@ -16,14 +15,10 @@ pub fn genesis_set_bytecode(
     bytecode: &Bytes,
     starknet_address: FieldElement,
 ) -> Vec<((ContractAddress, StorageKey), StorageValue)> {
-    bytecode
-        .chunks(16)
+    bytes_to_u128_felts(bytecode)
+        .into_iter()
         .enumerate()
-        .map(|(i, x)| {
-            let mut storage_value = [0u8; 16];
-            storage_value[..x.len()].copy_from_slice(x);
-            let storage_value = FieldElement::from(u128::from_be_bytes(storage_value));
-
+        .map(|(i, storage_value)| {
             genesis_set_storage_starknet_contract(
                 starknet_address,
                 "bytecode_",
@@ -69,11 +64,12 @@ pub fn genesis_set_storage_starknet_contract(
 /// pre-funded with the specified `amount`. The `amount` is split into two 128-bit chunks, which
 /// are stored in the storage keys at offsets 0 and 1.
 pub fn genesis_fund_starknet_address(
+    native_token_address: FieldElement,
     starknet_address: FieldElement,
     amount: U256,
 ) -> Vec<((ContractAddress, StorageKey), StorageValue)> {
     // Split the amount into two 128-bit chunks.
-    let amount = split_u256_into_field_elements(amount);
+    let amount = u256_to_felts(amount);
 
     // Iterate over the storage key offsets and generate the storage tuples.
     amount
@@ -81,7 +77,7 @@ pub fn genesis_fund_starknet_address(
         .enumerate() // Enumerate the key offsets.
         .map(|(offset, value)| {
             genesis_set_storage_starknet_contract(
-                FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap(), // Safe unwrap
+                native_token_address,
                 "ERC20_balances",
                 &[starknet_address],
                 *value,
@@ -104,10 +100,10 @@ pub fn genesis_set_storage_kakarot_contract_account(
     value: U256,
 ) -> Vec<((ContractAddress, StorageKey), StorageValue)> {
     // Split the key into Vec of two 128-bit chunks.
-    let keys = split_u256_into_field_elements(key);
+    let keys = u256_to_felts(key);
 
     // Split the value into two 128-bit chunks.
-    let values = split_u256_into_field_elements(value);
+    let values = u256_to_felts(value);
 
     // Iterate over the storage key offsets and generate the storage tuples.
     values
@@ -125,6 +121,29 @@ pub fn genesis_set_storage_kakarot_contract_account(
         .collect()
 }
 
+/// Generates the genesis storage tuple for setting the nonce of a Kakarot contract account.
+///
+/// The nonce of a contract account, from which the addresses of the contracts it creates are
+/// derived, is stored in its storage variable "nonce". The nonce of an externally owned account is
+/// the protocol nonce of its Starknet account instead, which isn't part of the storage.
+pub fn genesis_set_nonce(starknet_address: FieldElement, nonce: u64) -> ((ContractAddress, StorageKey), StorageValue) {
+    genesis_set_storage_starknet_contract(starknet_address, "nonce", &[], FieldElement::from(nonce), 0)
+}
+
+/// Generates the genesis storage tuple for setting the hash of a block in the blockhash registry.
+///
+/// This function calculates the storage key of the storage variable "blockhash_" for the given
+/// `block_number`, split into two 128-bit chunks as a Uint256. The value stored is the hash of the
+/// block, which the BLOCKHASH opcode returns for this block number.
+pub fn genesis_set_blockhash(
+    blockhash_registry_address: FieldElement,
+    block_number: u64,
+    block_hash: FieldElement,
+) -> ((ContractAddress, StorageKey), StorageValue) {
+    let keys = u256_to_felts(U256::from(block_number));
+    genesis_set_storage_starknet_contract(blockhash_registry_address, "blockhash_", &keys, block_hash, 0)
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -133,10 +152,10 @@ mod tests {
 
     use kakarot_rpc_core::client::api::KakarotStarknetApi;
     use kakarot_rpc_core::client::constants::STARKNET_NATIVE_TOKEN;
-    use kakarot_rpc_core::client::helpers::split_u256_into_field_elements;
     use kakarot_rpc_core::contracts::account::Account;
     use kakarot_rpc_core::contracts::contract_account::ContractAccount;
     use kakarot_rpc_core::mock::constants::ACCOUNT_ADDRESS;
+    use kakarot_rpc_core::models::conversions::u256_to_felts;
     use kakarot_rpc_core::test_utils::deploy_helpers::KakarotTestEnvironmentContext;
     use kakarot_rpc_core::test_utils::fixtures::kakarot_test_env_ctx;
     use katana_core::backend::state::StorageRecord;
@@ -282,7 +301,7 @@ mod tests {
         let token_fee_address = FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap();
         let storage_variable_name = "ERC20_balances";
         let amount = U256::from_str("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb").unwrap();
-        let amount_split = split_u256_into_field_elements(amount);
+        let amount_split = u256_to_felts(amount);
 
         // This is equivalent to pre-funding the Starknet address with
         // 0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb Fee Tokens.
@@ -308,7 +327,7 @@ mod tests {
         ];
 
         // When
-        let result = genesis_fund_starknet_address(starknet_address, amount);
+        let result = genesis_fund_starknet_address(token_fee_address, starknet_address, amount);
 
         // Then
         assert_eq!(result, expected_output);
@@ -324,7 +343,7 @@ mod tests {
         let key = U256::from_str("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb").unwrap();
         let storage_variable_name = "storage_";
         let value = U256::from_str("0xccccccccccccccccccccccccccccccccdddddddddddddddddddddddddddddddd").unwrap();
-        let value_split = split_u256_into_field_elements(value);
+        let value_split = u256_to_felts(value);
 
         // This is equivalent to setting the storage of Kakarot contract account's `storage_` variable at
         // index 0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb to
@@ -334,16 +353,14 @@ mod tests {
             (
                 (
                     starknet_address.into(),
-                    get_storage_var_address(storage_variable_name, &split_u256_into_field_elements(key))
-                        .unwrap()
-                        .into(), // offset for value.low
+                    get_storage_var_address(storage_variable_name, &u256_to_felts(key)).unwrap().into(), /* offset for value.low */
                 ),
                 value_split[0].into(), // value.low
             ),
             (
                 (
                     starknet_address.into(),
-                    (get_storage_var_address(storage_variable_name, &split_u256_into_field_elements(key)).unwrap()
+                    (get_storage_var_address(storage_variable_name, &u256_to_felts(key)).unwrap()
                         + FieldElement::from(1u64))
                     .into(), // offset for value.high
                 ),
@@ -357,6 +374,36 @@ mod tests {
         assert_eq!(result, expected_output);
     }
 
+    /// This test verifies that the `genesis_set_blockhash` function generates the storage tuple of
+    /// the `blockhash_` variable of the blockhash registry, keyed by the block number as a Uint256.
+    #[test]
+    fn test_genesis_set_blockhash() {
+        // Given
+        let registry_address = FieldElement::from_hex_be("0x9002").unwrap();
+        let block_hash = FieldElement::from_hex_be("0xabcdef").unwrap();
+
+        // When
+        let result = genesis_set_blockhash(registry_address, 42, block_hash);
+
+        // Then
+        let expected_key =
+            get_storage_var_address("blockhash_", &[FieldElement::from(42u64), FieldElement::ZERO]).unwrap();
+        assert_eq!(result, ((registry_address.into(), expected_key.into()), block_hash.into()));
+    }
+
+    #[test]
+    fn test_genesis_set_nonce() {
+        // Given
+        let starknet_address = FieldElement::from_hex_be("0x1234").unwrap();
+
+        // When
+        let result = genesis_set_nonce(starknet_address, 3);
+
+        // Then
+        let expected_key = get_storage_var_address("nonce", &[]).unwrap();
+        assert_eq!(result, ((starknet_address.into(), expected_key.into()), FieldElement::from(3u64).into()));
+    }
+
     #[rstest]
     #[tokio::test(flavor = "multi_thread")]
     async fn test_kakarot_contract_account_storage(kakarot_test_env_ctx: KakarotTestEnvironmentContext) {
@@ -405,7 +452,7 @@ mod tests {
         // Deploy the contract account with the set genesis storage and retrieve the storage on the contract
         let starknet_client = test_environment.client().starknet_provider();
         let genesis_contract = ContractAccount::new(genesis_address, &starknet_client);
-        let [key_low, key_high] = split_u256_into_field_elements(expected_key);
+        let [key_low, key_high] = u256_to_felts(expected_key);
         let actual_value =
             genesis_contract.storage(&key_low, &key_high, &StarknetBlockId::Tag(BlockTag::Latest)).await.unwrap();
 
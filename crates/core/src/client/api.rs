@@ -1,10 +1,12 @@
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use ethers::types::transaction::eip712::TypedData;
 use eyre::Result;
 use reth_primitives::{Address, BlockId, BlockNumberOrTag, Bytes, H256, U128, U256, U64};
 use reth_rpc_types::{
-    BlockTransactions, CallRequest, FeeHistory, Filter, Index, Log, RichBlock, SyncStatus,
+    BlockTransactions, CallRequest, FeeHistory, Filter, FilterChanges, Index, Log, RichBlock, SyncStatus,
     Transaction as EtherTransaction, TransactionReceipt,
 };
 use starknet::core::types::{
@@ -13,21 +15,50 @@ use starknet::core::types::{
 use starknet::providers::sequencer::models::TransactionSimulationInfo;
 use starknet::providers::Provider;
 
+use super::cache::CacheStats;
 use super::errors::EthApiError;
+use super::reorg::BlockLink;
+use crate::models::account::{AccountDetails, DeployedAccount};
 use crate::models::balance::TokenBalances;
+use crate::models::block_stats::BlockStats;
+use crate::models::simulation::{SimulationRequest, TransactionSimulation};
+use crate::models::state_override::{StarknetStateWrite, StateOverride};
+use crate::models::trace::{BlockTraceResult, GethTrace, LocalizedFlatTrace, TraceResults, TraceType, TracingOptions};
 use crate::models::transaction::StarknetTransactions;
 
 #[async_trait]
 pub trait KakarotEthApi<P: Provider + Send + Sync>: KakarotStarknetApi<P> + Send + Sync {
     async fn block_number(&self) -> Result<U64, EthApiError<P::Error>>;
 
+    fn caches_chain_tip(&self) -> bool;
+
+    fn cache_stats(&self) -> BTreeMap<String, CacheStats>;
+
+    async fn block_stats(&self, block_id: BlockId) -> Result<Option<BlockStats>, EthApiError<P::Error>>;
+
+    async fn handle_new_head(&self, block_number: u64) -> Result<(), EthApiError<P::Error>>;
+
+    async fn block_link(&self, block_number: u64) -> Result<Option<BlockLink>, EthApiError<P::Error>>;
+
+    fn handle_reorg(&self, from_block: u64);
+
     async fn transaction_by_hash(&self, hash: H256) -> Result<Option<EtherTransaction>, EthApiError<P::Error>>;
 
     async fn get_code(&self, ethereum_address: Address, block_id: BlockId) -> Result<Bytes, EthApiError<P::Error>>;
 
     async fn get_logs(&self, filter: Filter) -> Result<Vec<Log>, EthApiError<P::Error>>;
 
-    async fn call(&self, to: Address, calldata: Bytes, block_id: BlockId) -> Result<Bytes, EthApiError<P::Error>>;
+    async fn index_logs(&self, from_block: u64, to_block: u64) -> Result<usize, EthApiError<P::Error>>;
+
+    fn log_index_enabled(&self) -> bool;
+
+    async fn call(
+        &self,
+        to: Address,
+        calldata: Bytes,
+        block_id: BlockId,
+        state_override: Option<StateOverride>,
+    ) -> Result<Bytes, EthApiError<P::Error>>;
 
     async fn transaction_by_block_id_and_index(
         &self,
@@ -43,6 +74,9 @@ pub trait KakarotEthApi<P: Provider + Send + Sync>: KakarotStarknetApi<P> + Send
 
     async fn transaction_receipt(&self, hash: H256) -> Result<Option<TransactionReceipt>, EthApiError<P::Error>>;
 
+    async fn block_receipts(&self, block_id: BlockId)
+    -> Result<Option<Vec<TransactionReceipt>>, EthApiError<P::Error>>;
+
     async fn nonce(&self, ethereum_address: Address, block_id: BlockId) -> Result<U256, EthApiError<P::Error>>;
 
     async fn balance(&self, ethereum_address: Address, block_id: BlockId) -> Result<U256, EthApiError<P::Error>>;
@@ -62,12 +96,47 @@ pub trait KakarotEthApi<P: Provider + Send + Sync>: KakarotStarknetApi<P> + Send
 
     async fn send_transaction(&self, bytes: Bytes) -> Result<H256, EthApiError<P::Error>>;
 
+    async fn transaction_by_starknet_hash(&self, hash: H256)
+    -> Result<Option<EtherTransaction>, EthApiError<P::Error>>;
+
+    async fn starknet_transaction_hash(&self, ethereum_hash: H256) -> Result<Option<H256>, EthApiError<P::Error>>;
+
+    async fn ethereum_transaction_hash(&self, starknet_hash: H256) -> Result<Option<H256>, EthApiError<P::Error>>;
+
+    async fn deployed_accounts(&self) -> Result<Vec<DeployedAccount>, EthApiError<P::Error>>;
+
+    async fn deploy_eoa(&self, ethereum_address: Address) -> Result<Option<H256>, EthApiError<P::Error>>;
+
+    fn compute_starknet_addresses(&self, ethereum_addresses: &[Address]) -> Vec<FieldElement>;
+
+    async fn account_details(
+        &self,
+        ethereum_address: Address,
+        block_id: BlockId,
+    ) -> Result<AccountDetails, EthApiError<P::Error>>;
+
+    async fn send_unsigned_transaction(&self, request: CallRequest) -> Result<H256, EthApiError<P::Error>>;
+
+    fn accounts(&self) -> Vec<Address>;
+
+    fn sign(&self, address: Address, message: Bytes) -> Result<Bytes, EthApiError<P::Error>>;
+
+    async fn sign_transaction(&self, request: CallRequest) -> Result<Bytes, EthApiError<P::Error>>;
+
+    fn sign_typed_data(&self, address: Address, typed_data: &TypedData) -> Result<Bytes, EthApiError<P::Error>>;
+
     async fn get_transaction_count_by_block(&self, block_id: BlockId) -> Result<U64, EthApiError<P::Error>>;
 
+    fn coinbase(&self) -> Option<Address>;
+
     fn base_fee_per_gas(&self) -> U256;
 
+    fn chain_id(&self) -> u64;
+
     fn max_priority_fee_per_gas(&self) -> U128;
 
+    fn block_gas_limit(&self) -> U256;
+
     async fn fee_history(
         &self,
         block_count: U256,
@@ -77,7 +146,66 @@ pub trait KakarotEthApi<P: Provider + Send + Sync>: KakarotStarknetApi<P> + Send
 
     async fn estimate_gas(&self, request: CallRequest, block_id: BlockId) -> Result<U256, EthApiError<P::Error>>;
 
+    async fn simulate_ethereum_transaction(
+        &self,
+        request: SimulationRequest,
+        block_id: BlockId,
+    ) -> Result<TransactionSimulation, EthApiError<P::Error>>;
+
     async fn gas_price(&self) -> Result<U256, EthApiError<P::Error>>;
+
+    async fn new_filter(&self, filter: Filter) -> Result<U64, EthApiError<P::Error>>;
+
+    async fn new_block_filter(&self) -> Result<U64, EthApiError<P::Error>>;
+
+    async fn new_pending_transaction_filter(&self) -> Result<U64, EthApiError<P::Error>>;
+
+    fn uninstall_filter(&self, id: U64) -> bool;
+
+    async fn get_filter_changes(&self, id: U64) -> Result<FilterChanges, EthApiError<P::Error>>;
+
+    async fn get_filter_logs(&self, id: U64) -> Result<FilterChanges, EthApiError<P::Error>>;
+
+    async fn pending_transactions(&self) -> Result<Vec<EtherTransaction>, EthApiError<P::Error>>;
+
+    async fn trace_transaction(&self, hash: H256, options: TracingOptions) -> Result<GethTrace, EthApiError<P::Error>>;
+
+    async fn trace_call(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+        options: TracingOptions,
+    ) -> Result<GethTrace, EthApiError<P::Error>>;
+
+    async fn trace_block_by_number(
+        &self,
+        number: BlockNumberOrTag,
+        options: TracingOptions,
+    ) -> Result<Vec<BlockTraceResult>, EthApiError<P::Error>>;
+
+    async fn parity_trace_transaction(
+        &self,
+        hash: H256,
+    ) -> Result<Option<Vec<LocalizedFlatTrace>>, EthApiError<P::Error>>;
+
+    async fn parity_trace_block(
+        &self,
+        number: BlockNumberOrTag,
+    ) -> Result<Vec<LocalizedFlatTrace>, EthApiError<P::Error>>;
+
+    async fn replay_transaction(
+        &self,
+        hash: H256,
+        trace_types: HashSet<TraceType>,
+    ) -> Result<TraceResults, EthApiError<P::Error>>;
+}
+
+/// Backend writing the state of the Starknet sequencer, used to apply the state overrides of
+/// `eth_call`. Only a sequencer run by the RPC, like the dev network, can provide one.
+#[async_trait]
+pub trait StateOverrideBackend: Send + Sync {
+    /// Applies the writes and returns the writes restoring the previous state.
+    async fn apply(&self, writes: Vec<StarknetStateWrite>) -> Result<Vec<StarknetStateWrite>>;
 }
 
 #[async_trait]
@@ -86,10 +214,14 @@ pub trait KakarotStarknetApi<P: Provider + Send + Sync>: Send + Sync {
 
     fn proxy_account_class_hash(&self) -> FieldElement;
 
+    fn native_token_address(&self) -> FieldElement;
+
     fn starknet_provider(&self) -> Arc<P>;
 
     async fn map_block_id_to_block_number(&self, block_id: &StarknetBlockId) -> Result<u64, EthApiError<P::Error>>;
 
+    async fn starknet_block_id(&self, block_id: BlockId) -> Result<StarknetBlockId, EthApiError<P::Error>>;
+
     async fn submit_starknet_transaction(
         &self,
         request: BroadcastedInvokeTransactionV1,
@@ -123,9 +255,14 @@ pub trait KakarotStarknetApi<P: Provider + Send + Sync>: Send + Sync {
     async fn simulate_transaction(
         &self,
         request: BroadcastedInvokeTransactionV1,
-        block_number: u64,
+        block_id: StarknetBlockId,
         skip_validate: bool,
     ) -> Result<TransactionSimulationInfo, EthApiError<P::Error>>;
 
     async fn filter_events(&self, request: EventFilterWithPage) -> Result<Vec<EmittedEvent>, EthApiError<P::Error>>;
+
+    async fn starknet_transaction_trace(
+        &self,
+        transaction_hash: FieldElement,
+    ) -> Result<serde_json::Value, EthApiError<P::Error>>;
 }
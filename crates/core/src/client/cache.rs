@@ -0,0 +1,723 @@
+//! Caches shared by all the `KakarotEthApi` methods: the converted blocks and receipts, and the
+//! state of the accounts at the chain tip.
+//!
+//! Blocks are keyed by hash, which identifies their content, and indexed by number. Pending blocks
+//! and pending receipts are never cached, and requests by tag (`latest`, `pending`, ...) always hit
+//! the provider since the block they resolve to changes. When the chain reorganizes, the blocks
+//! from the reorganized height are dropped with [`BlockCache::invalidate_from`].
+//!
+//! The state read at the `latest` block (balances, code and EVM addresses of the accounts) is only
+//! cached while the head watcher reports the new blocks: each new head drops the entries changed
+//! by the state diff of the block, and everything when the diff is unknown.
+//!
+//! Each cache counts its hits, misses, evictions (entries dropped to make room for new ones) and
+//! invalidations (entries dropped because they became stale), returned by `kakarot_cacheStats`
+//! and exported in the Prometheus format by [`encode_prometheus`].
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use lru::LruCache;
+use reth_primitives::{Address, Bloom, Bytes, H256, U256};
+use reth_rpc_types::{RichBlock, TransactionReceipt};
+use serde::Serialize;
+use starknet::core::types::{BlockId as StarknetBlockId, FieldElement, StateDiff};
+use starknet::core::utils::get_storage_var_address;
+
+use super::errors::ConfigError;
+use crate::models::block_stats::BlockStats;
+use crate::models::fee_history::BlockFees;
+use crate::models::felt::Felt252Wrapper;
+
+/// Default number of blocks and of receipts kept in the cache.
+pub const DEFAULT_BLOCK_CACHE_SIZE: usize = 1024;
+
+/// Configuration of the block and receipt cache, and of the chain tip state cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockCacheConfig {
+    /// Number of blocks, and of receipts, kept in the cache, which is also the number of balances,
+    /// codes and EVM addresses kept in the chain tip state cache. The caches are disabled when 0.
+    pub size: usize,
+}
+
+impl Default for BlockCacheConfig {
+    fn default() -> Self {
+        Self { size: DEFAULT_BLOCK_CACHE_SIZE }
+    }
+}
+
+impl BlockCacheConfig {
+    /// Create a new `BlockCacheConfig` from environment variables, falling back to the default
+    /// size when `KAKAROT_BLOCK_CACHE_SIZE` is not set.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let size = match std::env::var("KAKAROT_BLOCK_CACHE_SIZE") {
+            Ok(size) => size.parse::<usize>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_BLOCK_CACHE_SIZE should be a positive integer, got {size}"
+                ))
+            })?,
+            Err(_) => DEFAULT_BLOCK_CACHE_SIZE,
+        };
+        Ok(Self { size })
+    }
+}
+
+/// Counters of a cache, returned by `kakarot_cacheStats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped to make room for new entries.
+    pub evictions: u64,
+    /// Entries dropped because they became stale.
+    pub invalidations: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+/// LRU cache counting its hits, misses, evictions and invalidations.
+#[derive(Debug)]
+struct CountedLru<K: Hash + Eq, V> {
+    entries: Mutex<LruCache<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl<K: Hash + Eq + Copy, V: Clone> CountedLru<K, V> {
+    fn new(size: NonZeroUsize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(size)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LruCache<K, V>> {
+        self.entries.lock().expect("cache poisoned")
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        let value = self.lock().get(key).cloned();
+        match value {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        value
+    }
+
+    /// Records a miss for a lookup which couldn't reach the cache.
+    fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn put(&self, key: K, value: V) {
+        // Pushing returns the replaced entry of the same key, or the evicted least recently used one
+        if let Some((dropped_key, _)) = self.lock().push(key, value) {
+            if dropped_key != key {
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Drops the stale entries, for which `is_stale` returns true.
+    fn invalidate(&self, is_stale: impl Fn(&K, &V) -> bool) {
+        let mut entries = self.lock();
+        let stale_keys: Vec<K> =
+            entries.iter().filter(|(key, value)| is_stale(key, value)).map(|(key, _)| *key).collect();
+        for key in &stale_keys {
+            entries.pop(key);
+        }
+        self.invalidations.fetch_add(stale_keys.len() as u64, Ordering::Relaxed);
+    }
+
+    fn invalidate_all(&self) {
+        let mut entries = self.lock();
+        self.invalidations.fetch_add(entries.len() as u64, Ordering::Relaxed);
+        entries.clear();
+    }
+
+    fn stats(&self) -> CacheStats {
+        let entries = self.lock();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            entries: entries.len(),
+            capacity: entries.cap().get(),
+        }
+    }
+}
+
+/// Encodes the counters of the caches in the Prometheus text format, labelled by cache name.
+pub fn encode_prometheus(stats: &BTreeMap<String, CacheStats>) -> String {
+    type Metric = (&'static str, &'static str, &'static str, fn(&CacheStats) -> u64);
+    const METRICS: [Metric; 6] = [
+        ("kakarot_cache_hits_total", "counter", "Lookups served by the cache.", |stats| stats.hits),
+        ("kakarot_cache_misses_total", "counter", "Lookups not served by the cache.", |stats| stats.misses),
+        ("kakarot_cache_evictions_total", "counter", "Entries dropped to make room for new entries.", |stats| {
+            stats.evictions
+        }),
+        ("kakarot_cache_invalidations_total", "counter", "Entries dropped because they became stale.", |stats| {
+            stats.invalidations
+        }),
+        ("kakarot_cache_entries", "gauge", "Entries in the cache.", |stats| stats.entries as u64),
+        ("kakarot_cache_capacity", "gauge", "Maximum number of entries in the cache.", |stats| stats.capacity as u64),
+    ];
+
+    let mut encoded = String::new();
+    for (name, kind, help, value) in METRICS {
+        // Writing to a string never fails
+        let _ = writeln!(encoded, "# HELP {name} {help}");
+        let _ = writeln!(encoded, "# TYPE {name} {kind}");
+        for (cache, cache_stats) in stats {
+            let _ = writeln!(encoded, "{name}{{cache=\"{cache}\"}} {}", value(cache_stats));
+        }
+    }
+    encoded
+}
+
+/// LRU cache of the converted blocks and receipts, and of the gas used, the fees paid in and the
+/// statistics of the blocks.
+#[derive(Debug)]
+pub struct BlockCache {
+    /// Blocks by hash and by transaction hydration.
+    blocks: CountedLru<(H256, bool), RichBlock>,
+    /// Hashes of the cached blocks by number.
+    block_hashes: Mutex<LruCache<u64, H256>>,
+    /// Receipts by transaction hash.
+    receipts: CountedLru<H256, TransactionReceipt>,
+    /// Gas used by the blocks and bloom of their logs by hash, shared by the conversions of a block
+    /// with and without its transactions.
+    gas_used_and_blooms: CountedLru<H256, (U256, Bloom)>,
+    /// Fees paid in the blocks by number, for `eth_feeHistory`.
+    fees: CountedLru<u64, BlockFees>,
+    /// Statistics of the blocks by number, for `kakarot_getBlockStats`.
+    block_stats: CountedLru<u64, BlockStats>,
+}
+
+impl BlockCache {
+    /// Create a new `BlockCache`, or `None` if the configured size is 0.
+    pub fn new(config: &BlockCacheConfig) -> Option<Self> {
+        let size = NonZeroUsize::new(config.size)?;
+        Some(Self {
+            blocks: CountedLru::new(size),
+            block_hashes: Mutex::new(LruCache::new(size)),
+            receipts: CountedLru::new(size),
+            gas_used_and_blooms: CountedLru::new(size),
+            fees: CountedLru::new(size),
+            block_stats: CountedLru::new(size),
+        })
+    }
+
+    /// Returns the cached block. Blocks requested by tag are never served from the cache.
+    pub fn get_block(&self, block_id: &StarknetBlockId, hydrated_tx: bool) -> Option<RichBlock> {
+        let hash = match block_id {
+            StarknetBlockId::Hash(hash) => Felt252Wrapper::from(*hash).into(),
+            StarknetBlockId::Number(number) => {
+                let hash = self.block_hashes.lock().expect("block cache poisoned").get(number).copied();
+                match hash {
+                    Some(hash) => hash,
+                    None => {
+                        self.blocks.record_miss();
+                        return None;
+                    }
+                }
+            }
+            StarknetBlockId::Tag(_) => return None,
+        };
+        self.blocks.get(&(hash, hydrated_tx))
+    }
+
+    /// Caches the block, unless it is pending.
+    pub fn insert_block(&self, block: &RichBlock, hydrated_tx: bool) {
+        let (Some(hash), Some(number)) = (block.header.hash, block.header.number) else {
+            return;
+        };
+        self.block_hashes.lock().expect("block cache poisoned").put(number.to::<u64>(), hash);
+        self.blocks.put((hash, hydrated_tx), block.clone());
+    }
+
+    /// Returns the cached receipt of the transaction.
+    pub fn get_receipt(&self, transaction_hash: &H256) -> Option<TransactionReceipt> {
+        self.receipts.get(transaction_hash)
+    }
+
+    /// Caches the receipt, unless the transaction is pending.
+    pub fn insert_receipt(&self, receipt: &TransactionReceipt) {
+        if receipt.block_hash.is_none() {
+            return;
+        }
+        let Some(transaction_hash) = receipt.transaction_hash else {
+            return;
+        };
+        self.receipts.put(transaction_hash, receipt.clone());
+    }
+
+    /// Returns the cached gas used by the block with the hash and bloom of its logs.
+    pub fn get_gas_used_and_bloom(&self, block_hash: &H256) -> Option<(U256, Bloom)> {
+        self.gas_used_and_blooms.get(block_hash)
+    }
+
+    /// Caches the gas used by the block with the hash and the bloom of its logs.
+    pub fn insert_gas_used_and_bloom(&self, block_hash: H256, gas_used_and_bloom: (U256, Bloom)) {
+        self.gas_used_and_blooms.put(block_hash, gas_used_and_bloom);
+    }
+
+    /// Returns the cached fees paid in the block.
+    pub fn get_block_fees(&self, block_number: u64) -> Option<BlockFees> {
+        self.fees.get(&block_number)
+    }
+
+    /// Caches the fees paid in the block.
+    pub fn insert_block_fees(&self, block_number: u64, fees: BlockFees) {
+        self.fees.put(block_number, fees);
+    }
+
+    /// Returns the cached statistics of the block.
+    pub fn get_block_stats(&self, block_number: u64) -> Option<BlockStats> {
+        self.block_stats.get(&block_number)
+    }
+
+    /// Caches the statistics of the block.
+    pub fn insert_block_stats(&self, stats: BlockStats) {
+        self.block_stats.put(stats.number.as_u64(), stats);
+    }
+
+    /// Drops the blocks, receipts, fees and statistics from the given block number onwards, after a
+    /// reorganization of the chain.
+    pub fn invalidate_from(&self, block_number: u64) {
+        let mut block_hashes = self.block_hashes.lock().expect("block cache poisoned");
+        let stale_numbers: Vec<u64> =
+            block_hashes.iter().map(|(number, _)| *number).filter(|n| *n >= block_number).collect();
+        for number in stale_numbers {
+            block_hashes.pop(&number);
+        }
+        drop(block_hashes);
+
+        let is_stale = |number: Option<U256>| number.map_or(false, |n| n.to::<u64>() >= block_number);
+
+        self.blocks.invalidate(|_, block| is_stale(block.header.number));
+        self.receipts.invalidate(|_, receipt| is_stale(receipt.block_number));
+        self.fees.invalidate(|number, _| *number >= block_number);
+        self.block_stats.invalidate(|number, _| *number >= block_number);
+    }
+
+    /// Returns the counters of the block, receipt, gas used, fee and block statistics caches.
+    pub fn stats(&self) -> [(&'static str, CacheStats); 5] {
+        [
+            ("blocks", self.blocks.stats()),
+            ("receipts", self.receipts.stats()),
+            ("gasUsed", self.gas_used_and_blooms.stats()),
+            ("feeHistory", self.fees.stats()),
+            ("blockStats", self.block_stats.stats()),
+        ]
+    }
+
+    /// Checks the new head of the chain against the cached blocks. When the cached block at the
+    /// height of the head or of its parent differs, the chain reorganized: the blocks from that
+    /// height onwards are dropped and `true` is returned.
+    pub fn handle_new_head(&self, block_number: u64, hash: H256, parent_hash: H256) -> bool {
+        let (stale_head, stale_parent) = {
+            let block_hashes = self.block_hashes.lock().expect("block cache poisoned");
+            let stale_head = block_hashes.peek(&block_number).map_or(false, |cached| *cached != hash);
+            let stale_parent = block_number
+                .checked_sub(1)
+                .and_then(|parent_number| block_hashes.peek(&parent_number))
+                .map_or(false, |cached| *cached != parent_hash);
+            (stale_head, stale_parent)
+        };
+
+        if stale_parent {
+            self.invalidate_from(block_number - 1);
+        } else if stale_head {
+            self.invalidate_from(block_number);
+        }
+        stale_head || stale_parent
+    }
+}
+
+/// Changes of the Starknet state made by a block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateChanges {
+    /// Storage keys written, by contract.
+    storage: HashMap<FieldElement, HashSet<FieldElement>>,
+    /// Contracts deployed or whose class was replaced.
+    classes: HashSet<FieldElement>,
+}
+
+impl From<&StateDiff> for StateChanges {
+    fn from(state_diff: &StateDiff) -> Self {
+        let mut storage: HashMap<FieldElement, HashSet<FieldElement>> = HashMap::new();
+        for diff in &state_diff.storage_diffs {
+            storage.entry(diff.address).or_default().extend(diff.storage_entries.iter().map(|entry| entry.key));
+        }
+        let classes = state_diff
+            .deployed_contracts
+            .iter()
+            .map(|contract| contract.address)
+            .chain(state_diff.replaced_classes.iter().map(|contract| contract.contract_address))
+            .collect();
+        Self { storage, classes }
+    }
+}
+
+impl StateChanges {
+    fn is_storage_written(&self, contract: &FieldElement, key: &FieldElement) -> bool {
+        self.storage.get(contract).map_or(false, |keys| keys.contains(key))
+    }
+
+    fn is_contract_changed(&self, contract: &FieldElement) -> bool {
+        self.storage.contains_key(contract) || self.classes.contains(contract)
+    }
+}
+
+/// LRU cache of the state of the accounts at the latest block.
+#[derive(Debug)]
+pub struct StateCache {
+    native_token: FieldElement,
+    /// Latest block reported by the head watcher, the cache is unused until the first report.
+    head: Mutex<Option<u64>>,
+    /// Native token balances by EVM address, with the storage slot of the balance.
+    balances: CountedLru<Address, (FieldElement, U256)>,
+    /// Code by EVM address, with the Starknet address of the account.
+    code: CountedLru<Address, (FieldElement, Bytes)>,
+    /// EVM addresses by Starknet address.
+    evm_addresses: CountedLru<FieldElement, Address>,
+}
+
+impl StateCache {
+    /// Create a new `StateCache` of the balances in the `native_token`, or `None` if the
+    /// configured size is 0.
+    pub fn new(config: &BlockCacheConfig, native_token: FieldElement) -> Option<Self> {
+        let size = NonZeroUsize::new(config.size)?;
+        Some(Self {
+            native_token,
+            head: Mutex::new(None),
+            balances: CountedLru::new(size),
+            code: CountedLru::new(size),
+            evm_addresses: CountedLru::new(size),
+        })
+    }
+
+    /// Returns the latest block the cached entries are valid for, or `None` while the head
+    /// watcher hasn't reported any block.
+    pub fn head(&self) -> Option<u64> {
+        *self.head.lock().expect("state cache poisoned")
+    }
+
+    /// Records the new head and drops the entries changed by the block. Every entry is dropped
+    /// when the changes are unknown or when blocks were skipped since the previous head.
+    pub fn apply_new_head(&self, block_number: u64, changes: Option<&StateChanges>) {
+        let mut head = self.head.lock().expect("state cache poisoned");
+        let changes = changes.filter(|_| head.map_or(false, |head| head.checked_add(1) == Some(block_number)));
+
+        match changes {
+            Some(changes) => {
+                self.balances.invalidate(|_, (slot, _)| {
+                    // The balance is a Uint256, stored in two consecutive slots
+                    changes.is_storage_written(&self.native_token, slot)
+                        || changes.is_storage_written(&self.native_token, &(*slot + FieldElement::ONE))
+                });
+                self.code.invalidate(|_, (starknet_address, _)| changes.is_contract_changed(starknet_address));
+                self.evm_addresses.invalidate(|starknet_address, _| changes.classes.contains(starknet_address));
+            }
+            None => {
+                self.balances.invalidate_all();
+                self.code.invalidate_all();
+                self.evm_addresses.invalidate_all();
+            }
+        }
+        *head = Some(block_number);
+    }
+
+    /// Returns the cached balance of the account.
+    pub fn get_balance(&self, address: &Address) -> Option<U256> {
+        self.balances.get(address).map(|(_, balance)| balance)
+    }
+
+    /// Caches the balance of the account read at the given head, unless a new head arrived since.
+    pub fn insert_balance(&self, head: u64, address: Address, starknet_address: FieldElement, balance: U256) {
+        let Ok(slot) = get_storage_var_address("ERC20_balances", &[starknet_address]) else {
+            return;
+        };
+        self.insert_at(head, || {
+            self.balances.put(address, (slot, balance));
+        });
+    }
+
+    /// Returns the cached code of the account.
+    pub fn get_code(&self, address: &Address) -> Option<Bytes> {
+        self.code.get(address).map(|(_, code)| code)
+    }
+
+    /// Caches the code of the account read at the given head, unless a new head arrived since.
+    pub fn insert_code(&self, head: u64, address: Address, starknet_address: FieldElement, code: Bytes) {
+        self.insert_at(head, || {
+            self.code.put(address, (starknet_address, code));
+        });
+    }
+
+    /// Returns the cached EVM address of the Starknet account.
+    pub fn get_evm_address(&self, starknet_address: &FieldElement) -> Option<Address> {
+        self.evm_addresses.get(starknet_address)
+    }
+
+    /// Caches the EVM address of the Starknet account read at the given head, unless a new head
+    /// arrived since.
+    pub fn insert_evm_address(&self, head: u64, starknet_address: FieldElement, address: Address) {
+        self.insert_at(head, || {
+            self.evm_addresses.put(starknet_address, address);
+        });
+    }
+
+    /// Returns the counters of the balance, code and EVM address caches.
+    pub fn stats(&self) -> [(&'static str, CacheStats); 3] {
+        [("balances", self.balances.stats()), ("code", self.code.stats()), ("evmAddresses", self.evm_addresses.stats())]
+    }
+
+    /// Runs the insertion while holding the head, so that it can't race with a new head.
+    fn insert_at(&self, head: u64, insert: impl FnOnce()) {
+        let current_head = self.head.lock().expect("state cache poisoned");
+        if *current_head == Some(head) {
+            insert();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Address, Bloom, Bytes};
+    use reth_rpc_types::{Block, BlockTransactions, Header};
+    use starknet::core::types::{BlockTag, ContractStorageDiffItem, DeployedContractItem, StorageEntry};
+
+    use super::*;
+    use crate::client::constants::STARKNET_NATIVE_TOKEN;
+
+    fn block(number: Option<u64>, hash: Option<H256>) -> RichBlock {
+        let header = Header {
+            hash,
+            parent_hash: H256::zero(),
+            uncles_hash: H256::zero(),
+            miner: Address::zero(),
+            state_root: H256::zero(),
+            transactions_root: H256::zero(),
+            receipts_root: H256::zero(),
+            number: number.map(U256::from),
+            gas_used: U256::ZERO,
+            gas_limit: U256::ZERO,
+            extra_data: Bytes::default(),
+            logs_bloom: Bloom::default(),
+            timestamp: U256::ZERO,
+            difficulty: U256::ZERO,
+            nonce: None,
+            base_fee_per_gas: None,
+            mix_hash: H256::zero(),
+            withdrawals_root: None,
+        };
+        Block {
+            header,
+            total_difficulty: U256::ZERO,
+            uncles: vec![],
+            transactions: BlockTransactions::Hashes(vec![]),
+            size: None,
+            withdrawals: None,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_block_cache_lookups() {
+        // Given
+        let cache = BlockCache::new(&BlockCacheConfig::default()).unwrap();
+        let hash = H256::from_low_u64_be(0xabc);
+        let cached = block(Some(7), Some(hash));
+
+        // When
+        cache.insert_block(&cached, false);
+        cache.insert_block(&block(None, None), false);
+
+        // Then
+        let by_hash = StarknetBlockId::Hash(FieldElement::from(0xabc_u64));
+        assert_eq!(Some(cached.clone()), cache.get_block(&by_hash, false));
+        assert_eq!(Some(cached), cache.get_block(&StarknetBlockId::Number(7), false));
+        assert_eq!(None, cache.get_block(&StarknetBlockId::Number(7), true));
+        assert_eq!(None, cache.get_block(&StarknetBlockId::Tag(BlockTag::Latest), false));
+    }
+
+    #[test]
+    fn test_block_cache_invalidate_from() {
+        // Given
+        let cache = BlockCache::new(&BlockCacheConfig::default()).unwrap();
+        for number in 1..=3 {
+            cache.insert_block(&block(Some(number), Some(H256::from_low_u64_be(number))), true);
+        }
+
+        // When
+        cache.invalidate_from(2);
+
+        // Then
+        assert!(cache.get_block(&StarknetBlockId::Number(1), true).is_some());
+        assert!(cache.get_block(&StarknetBlockId::Number(2), true).is_none());
+        assert!(cache.get_block(&StarknetBlockId::Hash(FieldElement::from(3_u64)), true).is_none());
+    }
+
+    #[test]
+    fn test_block_cache_disabled() {
+        assert!(BlockCache::new(&BlockCacheConfig { size: 0 }).is_none());
+    }
+
+    #[test]
+    fn test_block_cache_handle_new_head() {
+        // Given
+        let cache = BlockCache::new(&BlockCacheConfig::default()).unwrap();
+        for number in 1..=2 {
+            cache.insert_block(&block(Some(number), Some(H256::from_low_u64_be(number))), false);
+        }
+
+        // When
+        let extends_chain = !cache.handle_new_head(3, H256::from_low_u64_be(3), H256::from_low_u64_be(2));
+        let reorged = cache.handle_new_head(3, H256::from_low_u64_be(0x33), H256::from_low_u64_be(0x22));
+
+        // Then
+        assert!(extends_chain);
+        assert!(reorged);
+        assert!(cache.get_block(&StarknetBlockId::Number(1), false).is_some());
+        assert!(cache.get_block(&StarknetBlockId::Number(2), false).is_none());
+    }
+
+    fn state_diff(storage: Vec<(FieldElement, FieldElement)>, deployed: Vec<FieldElement>) -> StateDiff {
+        StateDiff {
+            storage_diffs: storage
+                .into_iter()
+                .map(|(address, key)| ContractStorageDiffItem {
+                    address,
+                    storage_entries: vec![StorageEntry { key, value: FieldElement::ONE }],
+                })
+                .collect(),
+            deprecated_declared_classes: vec![],
+            declared_classes: vec![],
+            deployed_contracts: deployed
+                .into_iter()
+                .map(|address| DeployedContractItem { address, class_hash: FieldElement::ONE })
+                .collect(),
+            replaced_classes: vec![],
+            nonces: vec![],
+        }
+    }
+
+    #[test]
+    fn test_state_cache_apply_new_head() {
+        // Given
+        let native_token = FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap();
+        let cache = StateCache::new(&BlockCacheConfig::default(), native_token).unwrap();
+        let (alice, bob) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let (alice_starknet, bob_starknet) = (FieldElement::from(0xa_u64), FieldElement::from(0xb_u64));
+        let alice_slot = get_storage_var_address("ERC20_balances", &[alice_starknet]).unwrap();
+
+        cache.apply_new_head(10, None);
+        cache.insert_balance(10, alice, alice_starknet, U256::from(100));
+        cache.insert_balance(10, bob, bob_starknet, U256::from(200));
+        cache.insert_code(10, alice, alice_starknet, Bytes::from(vec![1]));
+        cache.insert_code(10, bob, bob_starknet, Bytes::from(vec![2]));
+        cache.insert_evm_address(10, alice_starknet, alice);
+
+        // When
+        let changes =
+            StateChanges::from(&state_diff(vec![(native_token, alice_slot), (bob_starknet, 0_u64.into())], vec![]));
+        cache.apply_new_head(11, Some(&changes));
+
+        // Then
+        assert_eq!(Some(11), cache.head());
+        assert_eq!(None, cache.get_balance(&alice));
+        assert_eq!(Some(U256::from(200)), cache.get_balance(&bob));
+        assert_eq!(Some(Bytes::from(vec![1])), cache.get_code(&alice));
+        assert_eq!(None, cache.get_code(&bob));
+        assert_eq!(Some(alice), cache.get_evm_address(&alice_starknet));
+
+        // When
+        let changes = StateChanges::from(&state_diff(vec![], vec![alice_starknet]));
+        cache.apply_new_head(12, Some(&changes));
+
+        // Then
+        assert_eq!(None, cache.get_code(&alice));
+        assert_eq!(None, cache.get_evm_address(&alice_starknet));
+    }
+
+    #[test]
+    fn test_state_cache_drops_everything_on_unknown_changes() {
+        // Given
+        let cache =
+            StateCache::new(&BlockCacheConfig::default(), FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap())
+                .unwrap();
+        let alice = Address::from_low_u64_be(1);
+        let alice_starknet = FieldElement::from(0xa_u64);
+
+        // When
+        cache.insert_balance(10, alice, alice_starknet, U256::from(100));
+
+        // Then
+        assert_eq!(None, cache.get_balance(&alice), "nothing is cached before the first head");
+
+        // When
+        cache.apply_new_head(10, None);
+        cache.insert_balance(9, alice, alice_starknet, U256::from(100));
+
+        // Then
+        assert_eq!(None, cache.get_balance(&alice), "balances read at a previous head are not cached");
+
+        // When
+        cache.insert_balance(10, alice, alice_starknet, U256::from(100));
+        cache.apply_new_head(12, Some(&StateChanges::default()));
+
+        // Then
+        assert_eq!(None, cache.get_balance(&alice), "skipped blocks drop every entry");
+    }
+
+    #[test]
+    fn test_cache_stats() {
+        // Given
+        let cache = BlockCache::new(&BlockCacheConfig { size: 2 }).unwrap();
+        for number in 1..=3 {
+            cache.insert_block(&block(Some(number), Some(H256::from_low_u64_be(number))), false);
+        }
+
+        // When
+        cache.get_block(&StarknetBlockId::Number(3), false);
+        cache.get_block(&StarknetBlockId::Number(1), false);
+        cache.get_block(&StarknetBlockId::Hash(FieldElement::from(2_u64)), true);
+        cache.invalidate_from(3);
+
+        // Then
+        let [(name, stats), ..] = cache.stats();
+        assert_eq!("blocks", name);
+        assert_eq!(CacheStats { hits: 1, misses: 2, evictions: 1, invalidations: 1, entries: 1, capacity: 2 }, stats);
+    }
+
+    #[test]
+    fn test_encode_prometheus() {
+        // Given
+        let stats = BTreeMap::from([(
+            "blocks".to_string(),
+            CacheStats { hits: 3, misses: 1, evictions: 0, invalidations: 2, entries: 5, capacity: 8 },
+        )]);
+
+        // When
+        let encoded = encode_prometheus(&stats);
+
+        // Then
+        assert!(
+            encoded.contains("# TYPE kakarot_cache_hits_total counter\nkakarot_cache_hits_total{cache=\"blocks\"} 3\n")
+        );
+        assert!(encoded.contains("kakarot_cache_invalidations_total{cache=\"blocks\"} 2\n"));
+        assert!(encoded.contains("# TYPE kakarot_cache_capacity gauge\nkakarot_cache_capacity{cache=\"blocks\"} 8\n"));
+    }
+}
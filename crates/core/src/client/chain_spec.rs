@@ -0,0 +1,126 @@
+//! Parameters of a Kakarot network which differ between deployments: the chain id and the gas and
+//! fee values returned to the Ethereum clients.
+//!
+//! The defaults are the values of [`crate::client::constants`]. A new network overrides them with a
+//! JSON file, e.g.
+//!
+//! ```json
+//! { "chainId": 1802203764, "baseFeePerGas": 1000000000, "blockGasLimit": 7000000 }
+//! ```
+//!
+//! where the missing fields keep their default value.
+use std::path::Path;
+
+use reth_primitives::{U128, U256};
+use serde::{Deserialize, Serialize};
+use starknet::core::types::FieldElement;
+
+use super::constants::gas::{BASE_FEE_PER_GAS, MAX_PRIORITY_FEE_PER_GAS, MINIMUM_GAS_FEE};
+use super::constants::{CHAIN_ID, ESTIMATE_GAS, GAS_LIMIT, MAX_FEE};
+use super::errors::ConfigError;
+
+/// Chain parameters of a Kakarot deployment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct ChainSpec {
+    /// Chain id, overridden by `KAKAROT_CHAIN_ID` when set.
+    pub chain_id: u64,
+    /// Base fee of the blocks, in wei.
+    pub base_fee_per_gas: u64,
+    /// Priority fee suggested by `eth_maxPriorityFeePerGas`, in wei.
+    pub max_priority_fee_per_gas: u128,
+    /// Gas limit of the blocks, also the maximum gas limit of a transaction.
+    pub block_gas_limit: u64,
+    /// Lower bound of the gas estimates, so that wallets accept to send the transactions.
+    pub minimum_gas: u64,
+    /// Gas estimate returned by the networks which can't simulate the transactions.
+    pub default_gas_estimate: u64,
+    /// Maximum fee of the Starknet transactions relaying the Ethereum transactions, in the unit of
+    /// the fee token.
+    pub max_fee: u64,
+}
+
+impl Default for ChainSpec {
+    fn default() -> Self {
+        Self {
+            chain_id: CHAIN_ID,
+            base_fee_per_gas: BASE_FEE_PER_GAS,
+            max_priority_fee_per_gas: MAX_PRIORITY_FEE_PER_GAS.to(),
+            block_gas_limit: GAS_LIMIT.to(),
+            minimum_gas: MINIMUM_GAS_FEE,
+            default_gas_estimate: ESTIMATE_GAS.to(),
+            // Safe unwrap: the default max fee fits in a u64
+            max_fee: u64::try_from(*MAX_FEE).unwrap(),
+        }
+    }
+}
+
+impl ChainSpec {
+    /// Loads the chain spec from a JSON file.
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            ConfigError::EnvironmentVariableSetWrong(format!("Failed to read the chain spec {}: {err}", path.display()))
+        })?;
+        serde_json::from_str(&content).map_err(|err| {
+            ConfigError::EnvironmentVariableSetWrong(format!("Invalid chain spec {}: {err}", path.display()))
+        })
+    }
+
+    /// Create a new `ChainSpec` from the `KAKAROT_CHAIN_SPEC` environment variable, the path of
+    /// its JSON file. Defaults to the Kakarot constants.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        match std::env::var("KAKAROT_CHAIN_SPEC") {
+            Ok(path) => Self::from_file(Path::new(&path)),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn base_fee_per_gas(&self) -> U256 {
+        U256::from(self.base_fee_per_gas)
+    }
+
+    pub fn max_priority_fee_per_gas(&self) -> U128 {
+        U128::from(self.max_priority_fee_per_gas)
+    }
+
+    pub fn block_gas_limit(&self) -> U256 {
+        U256::from(self.block_gas_limit)
+    }
+
+    pub fn max_fee(&self) -> FieldElement {
+        FieldElement::from(self.max_fee)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_chain_spec() {
+        // Given
+        let chain_spec = ChainSpec::default();
+
+        // Then
+        assert_eq!(CHAIN_ID, chain_spec.chain_id);
+        assert_eq!(*GAS_LIMIT, chain_spec.block_gas_limit());
+        assert_eq!(MAX_PRIORITY_FEE_PER_GAS, chain_spec.max_priority_fee_per_gas());
+        assert_eq!(*MAX_FEE, chain_spec.max_fee());
+        assert_eq!(*ESTIMATE_GAS, U256::from(chain_spec.default_gas_estimate));
+    }
+
+    #[test]
+    fn test_partial_chain_spec() {
+        // Given
+        let json = r#"{ "chainId": 1802203764, "baseFeePerGas": 1000000000 }"#;
+
+        // When
+        let chain_spec: ChainSpec = serde_json::from_str(json).unwrap();
+
+        // Then
+        assert_eq!(1_802_203_764, chain_spec.chain_id);
+        assert_eq!(U256::from(1_000_000_000u64), chain_spec.base_fee_per_gas());
+        assert_eq!(MINIMUM_GAS_FEE, chain_spec.minimum_gas);
+        assert!(serde_json::from_str::<ChainSpec>(r#"{ "gasLimit": 1 }"#).is_err());
+    }
+}
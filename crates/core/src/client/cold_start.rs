@@ -0,0 +1,203 @@
+//! Cold-start scan of the recent blocks, rebuilding after a restart the state the RPC keeps in
+//! memory about the transactions it relayed.
+//!
+//! Without it, a restarted RPC forgets which raw transactions it already relayed, and relays again
+//! a transaction resubmitted by a wallet instead of rejecting it as `already known`. The relayer
+//! accounts also restart from the nonce of the pending block, which lags behind when the
+//! transactions they sent before the restart are still being received. The scan goes over the last
+//! blocks and the pending block, and for every Kakarot transaction which landed:
+//! - records its Ethereum hash as relayed, and indexes it to its Starknet hash,
+//! - resumes the nonce of the relayer account which sent it, if any.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use reth_primitives::H256;
+use starknet::core::types::{BlockId as StarknetBlockId, BlockTag, FieldElement};
+use starknet::providers::Provider;
+
+use super::errors::{ConfigError, EthApiError};
+use super::transaction_index::TRANSACTION_SCAN_CONCURRENCY;
+use super::KakarotClient;
+use crate::models::block::BlockWithTxs;
+use crate::models::transaction::StarknetTransaction;
+
+/// Default number of latest blocks scanned at startup.
+pub const DEFAULT_COLD_START_SCAN_BLOCKS: u64 = 32;
+
+/// Configuration of the cold-start scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColdStartConfig {
+    /// Number of latest blocks scanned, on top of the pending block. Zero disables the scan.
+    pub block_count: u64,
+}
+
+impl Default for ColdStartConfig {
+    fn default() -> Self {
+        Self { block_count: DEFAULT_COLD_START_SCAN_BLOCKS }
+    }
+}
+
+impl ColdStartConfig {
+    pub fn new(block_count: u64) -> Self {
+        Self { block_count }
+    }
+
+    /// Create a new `ColdStartConfig` from the `KAKAROT_COLD_START_SCAN_BLOCKS` environment
+    /// variable, falling back to [`DEFAULT_COLD_START_SCAN_BLOCKS`] when it is not set.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        match std::env::var("KAKAROT_COLD_START_SCAN_BLOCKS") {
+            Ok(block_count) => block_count.parse::<u64>().map(Self::new).map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_COLD_START_SCAN_BLOCKS should be a positive integer, got {block_count}"
+                ))
+            }),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+/// Starknet invoke transaction found by the scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScannedTransaction {
+    pub starknet_hash: H256,
+    /// Hash of the wrapped Ethereum transaction, `None` if the transaction doesn't wrap one, e.g.
+    /// a transaction of a relayer account.
+    pub ethereum_hash: Option<H256>,
+    pub sender_address: FieldElement,
+    pub nonce: FieldElement,
+}
+
+impl ScannedTransaction {
+    fn new(transaction: StarknetTransaction) -> Option<Self> {
+        Some(Self {
+            starknet_hash: transaction.transaction_hash().ok()?.into(),
+            ethereum_hash: transaction.ethereum_transaction_hash().ok(),
+            sender_address: transaction.sender_address().ok()?.into(),
+            nonce: transaction.nonce().ok()?.into(),
+        })
+    }
+}
+
+/// Returns the nonce following the scanned transactions of each sender.
+pub fn next_nonces(transactions: &[ScannedTransaction]) -> HashMap<FieldElement, FieldElement> {
+    let mut nonces: HashMap<FieldElement, FieldElement> = HashMap::new();
+    for transaction in transactions {
+        let next_nonce = transaction.nonce + FieldElement::ONE;
+        nonces
+            .entry(transaction.sender_address)
+            .and_modify(|nonce| *nonce = (*nonce).max(next_nonce))
+            .or_insert(next_nonce);
+    }
+    nonces
+}
+
+/// Summary of the cold-start scan.
+#[derive(Debug, Clone)]
+pub struct ColdStartReport {
+    /// Number of blocks scanned, including the pending block.
+    pub blocks_scanned: u64,
+    /// Number of Kakarot transactions recorded as relayed.
+    pub relayed_transactions: usize,
+    /// Number of relayer accounts whose nonce was resumed.
+    pub relayer_nonces: usize,
+    /// Time spent scanning.
+    pub elapsed: Duration,
+}
+
+impl<P: Provider + Send + Sync> KakarotClient<P> {
+    /// Scans the latest blocks and the pending block to rebuild the relayed transactions and the
+    /// nonces of the relayer accounts. A block which can't be fetched is skipped.
+    pub async fn cold_start_scan(&self, config: &ColdStartConfig) -> Result<ColdStartReport, EthApiError<P::Error>> {
+        let start = Instant::now();
+        if config.block_count == 0 {
+            return Ok(ColdStartReport {
+                blocks_scanned: 0,
+                relayed_transactions: 0,
+                relayer_nonces: 0,
+                elapsed: start.elapsed(),
+            });
+        }
+
+        let latest_block_number = self.starknet_provider.block_number().await?;
+        let first_block_number = latest_block_number.saturating_sub(config.block_count - 1);
+        let block_ids: Vec<StarknetBlockId> = (first_block_number..=latest_block_number)
+            .map(StarknetBlockId::Number)
+            .chain([StarknetBlockId::Tag(BlockTag::Pending)])
+            .collect();
+
+        let mut blocks_scanned = 0;
+        let mut transactions = Vec::new();
+        for chunk in block_ids.chunks(TRANSACTION_SCAN_CONCURRENCY) {
+            let blocks = join_all(chunk.iter().map(|block_id| self.starknet_provider.get_block_with_txs(*block_id)));
+            for (block_id, block) in chunk.iter().zip(blocks.await) {
+                match block {
+                    Ok(block) => {
+                        blocks_scanned += 1;
+                        let block_transactions = BlockWithTxs::new(block).transactions().into_iter();
+                        transactions.extend(
+                            block_transactions.filter_map(|tx| ScannedTransaction::new(StarknetTransaction::from(tx))),
+                        );
+                    }
+                    Err(err) => tracing::warn!("Cold-start scan skipped block {block_id:?}: {err}"),
+                }
+            }
+        }
+
+        // The transactions landed, a resubmission is already known
+        let mut relayed_transactions = 0;
+        for transaction in &transactions {
+            if let Some(ethereum_hash) = transaction.ethereum_hash {
+                self.relayed_transactions.insert(ethereum_hash);
+                self.transaction_index.index_transaction(ethereum_hash, transaction.starknet_hash);
+                relayed_transactions += 1;
+            }
+        }
+
+        let mut relayer_nonces = 0;
+        if let Some(relayer) = &self.relayer {
+            let scanned_nonces = next_nonces(&transactions);
+            let pending = StarknetBlockId::Tag(BlockTag::Pending);
+            for address in relayer.addresses() {
+                let pending_nonce = self.starknet_provider.get_nonce(pending, address).await.ok();
+                let scanned_nonce = scanned_nonces.get(&address).copied();
+                let Some(next_nonce) = [scanned_nonce, pending_nonce].into_iter().flatten().max() else {
+                    continue;
+                };
+                if relayer.resume_nonce(address, next_nonce).await {
+                    relayer_nonces += 1;
+                }
+            }
+        }
+
+        Ok(ColdStartReport { blocks_scanned, relayed_transactions, relayer_nonces, elapsed: start.elapsed() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scanned(sender_address: u64, nonce: u64) -> ScannedTransaction {
+        ScannedTransaction {
+            starknet_hash: H256::from_low_u64_be(nonce),
+            ethereum_hash: None,
+            sender_address: FieldElement::from(sender_address),
+            nonce: FieldElement::from(nonce),
+        }
+    }
+
+    #[test]
+    fn test_next_nonces() {
+        // Given
+        let transactions = vec![scanned(1, 4), scanned(2, 0), scanned(1, 6), scanned(1, 5)];
+
+        // When
+        let nonces = next_nonces(&transactions);
+
+        // Then
+        assert_eq!(2, nonces.len());
+        assert_eq!(Some(&FieldElement::from(7u8)), nonces.get(&FieldElement::from(1u8)));
+        assert_eq!(Some(&FieldElement::ONE), nonces.get(&FieldElement::from(2u8)));
+    }
+}
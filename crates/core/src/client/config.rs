@@ -1,11 +1,27 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
 use eyre::Result;
+use reth_primitives::Address;
 use starknet::core::types::FieldElement;
 use starknet::providers::jsonrpc::{HttpTransport, JsonRpcTransport};
-use starknet::providers::{JsonRpcClient, SequencerGatewayProvider};
+use starknet::providers::{JsonRpcClient, Provider, ProviderError, SequencerGatewayProvider};
 use url::Url;
 
-use super::constants::{KATANA_RPC_URL, MADARA_RPC_URL};
+use super::api::StateOverrideBackend;
+use super::cache::BlockCacheConfig;
+use super::chain_spec::ChainSpec;
+use super::constants::{
+    CHAIN_ID, DEFAULT_TRANSACTION_CONVERSION_CONCURRENCY, KATANA_RPC_URL, MADARA_RPC_URL, STARKNET_NATIVE_TOKEN,
+};
 use super::errors::ConfigError;
+use super::gas_estimation::GasEstimationConfig;
+use super::logs::LogsConfig;
+use super::relayer::RelayerConfig;
+use super::sender_policy::SenderPolicy;
+use super::signer::EthSigner;
+use super::transaction_index::TransactionLookupConfig;
+use crate::storage::StorageConfig;
 
 fn get_env_var(name: &str) -> Result<String, ConfigError> {
     std::env::var(name).map_err(|_| ConfigError::EnvironmentVariableMissing(name.into()))
@@ -48,6 +64,68 @@ impl Network {
     }
 }
 
+/// Chain id of Kakarot, returned by `eth_chainId` and `net_version` and expected in the signed
+/// transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainIdConfig {
+    /// Fixed chain id.
+    Fixed(u64),
+    /// Chain id derived from the chain id of the Starknet network, see
+    /// [`chain_id_from_starknet`]. It must be resolved with [`ChainIdConfig::resolve`] before
+    /// creating the client.
+    Starknet,
+}
+
+impl Default for ChainIdConfig {
+    fn default() -> Self {
+        Self::Fixed(CHAIN_ID)
+    }
+}
+
+impl ChainIdConfig {
+    /// Create a new `ChainIdConfig` from the `KAKAROT_CHAIN_ID` environment variable, either a
+    /// decimal or hex chain id, or `starknet` to derive it from the Starknet network. Defaults to
+    /// `CHAIN_ID`.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let chain_id = match std::env::var("KAKAROT_CHAIN_ID") {
+            Ok(chain_id) => chain_id,
+            Err(_) => return Ok(Self::default()),
+        };
+        if chain_id.eq_ignore_ascii_case("starknet") {
+            return Ok(Self::Starknet);
+        }
+
+        let parsed = match chain_id.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => chain_id.parse::<u64>(),
+        };
+        parsed.map(Self::Fixed).map_err(|_| {
+            ConfigError::EnvironmentVariableSetWrong(format!(
+                "KAKAROT_CHAIN_ID should be a chain id or \"starknet\", got {chain_id}"
+            ))
+        })
+    }
+
+    /// Returns the chain id, fetching the chain id of the Starknet network if it is derived from
+    /// it.
+    pub async fn resolve<P: Provider + Send + Sync>(self, provider: &P) -> Result<u64, ProviderError<P::Error>> {
+        match self {
+            Self::Fixed(chain_id) => Ok(chain_id),
+            Self::Starknet => Ok(chain_id_from_starknet(provider.chain_id().await?)),
+        }
+    }
+}
+
+/// Derives the Kakarot chain id from the Starknet chain id, an ASCII short string such as
+/// `SN_GOERLI`, by keeping its lowest 53 bits: wallets expect chain ids to fit in a JavaScript
+/// number.
+pub fn chain_id_from_starknet(starknet_chain_id: FieldElement) -> u64 {
+    let bytes = starknet_chain_id.to_bytes_be();
+    let mut low_bytes = [0u8; 8];
+    low_bytes.copy_from_slice(&bytes[24..]);
+    u64::from_be_bytes(low_bytes) & ((1 << 53) - 1)
+}
+
 #[derive(Default, Clone)]
 /// Configuration for the Starknet RPC client.
 pub struct StarknetConfig {
@@ -57,11 +135,75 @@ pub struct StarknetConfig {
     pub kakarot_address: FieldElement,
     /// Proxy account class hash.
     pub proxy_account_class_hash: FieldElement,
+    /// Chain id of Kakarot.
+    pub chain_id: ChainIdConfig,
+    /// Gas and fee parameters of the deployment.
+    pub chain_spec: ChainSpec,
+    /// Address of the ERC20 holding the balances of the EVM accounts, the native token of Kakarot.
+    /// Defaults to the fee token of the Starknet network.
+    pub native_token_address: FieldElement,
+    /// Address of the ERC20 paying the Starknet fees of the relayed transactions, when it differs
+    /// from the native token.
+    pub fee_token_address: Option<FieldElement>,
+    /// Starknet block in which Kakarot was deployed, there is no Kakarot history before it.
+    pub kakarot_deployment_block: u64,
+    /// Lookup of the transactions by their Ethereum hash.
+    pub transaction_lookup: TransactionLookupConfig,
+    /// Fee recipient, returned by `eth_coinbase`, used as the `miner` of the blocks and credited
+    /// by the relayer. When not set, the sequencer of each block truncated to an EVM address is
+    /// used.
+    pub coinbase: Option<Address>,
+    /// Accounts managed by the RPC, used to sign the transactions sent through
+    /// `eth_sendTransaction`.
+    pub signer: EthSigner,
+    /// Restrictions on the senders and the transactions relayed to Kakarot.
+    pub sender_policy: SenderPolicy,
+    /// Cache of the converted blocks and receipts.
+    pub block_cache: BlockCacheConfig,
+    /// Maximum number of transactions converted concurrently when building a full block, each
+    /// conversion querying the Starknet provider.
+    pub transaction_conversion_concurrency: usize,
+    /// Limits of the `eth_getLogs` queries.
+    pub logs: LogsConfig,
+    /// Conversion of the Starknet fee estimates into EVM gas by `eth_estimateGas`.
+    pub gas_estimation: GasEstimationConfig,
+    /// Index the logs locally, filled by block ranges through
+    /// [`crate::client::api::KakarotEthApi::index_logs`].
+    pub log_index: bool,
+    /// Storage of the installed filters, of the transaction hashes mapping and of the log index.
+    pub storage: StorageConfig,
+    /// Backend applying the state overrides of `eth_call`, which are rejected when not set.
+    pub state_override_backend: Option<Arc<dyn StateOverrideBackend>>,
+    /// Starknet accounts deploying the Kakarot accounts of the senders relaying their first
+    /// transaction. Without them, the senders must be deployed beforehand.
+    pub relayer: Option<RelayerConfig>,
 }
 
 impl StarknetConfig {
     pub fn new(network: Network, kakarot_address: FieldElement, proxy_account_class_hash: FieldElement) -> Self {
-        StarknetConfig { network, kakarot_address, proxy_account_class_hash }
+        StarknetConfig {
+            network,
+            kakarot_address,
+            proxy_account_class_hash,
+            chain_id: ChainIdConfig::default(),
+            chain_spec: ChainSpec::default(),
+            // Safe unwrap: the default native token address is a valid felt
+            native_token_address: FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap(),
+            fee_token_address: None,
+            kakarot_deployment_block: 0,
+            transaction_lookup: TransactionLookupConfig::default(),
+            coinbase: None,
+            signer: EthSigner::default(),
+            sender_policy: SenderPolicy::default(),
+            block_cache: BlockCacheConfig::default(),
+            transaction_conversion_concurrency: DEFAULT_TRANSACTION_CONVERSION_CONCURRENCY,
+            logs: LogsConfig::default(),
+            gas_estimation: GasEstimationConfig::default(),
+            log_index: false,
+            storage: StorageConfig::default(),
+            state_override_backend: None,
+            relayer: None,
+        }
     }
 
     /// Create a new `StarknetConfig` from environment variables.
@@ -95,7 +237,102 @@ impl StarknetConfig {
             ))
         })?;
 
-        Ok(StarknetConfig::new(network, kakarot_address, proxy_account_class_hash))
+        // The chain id of the environment overrides the one of the chain spec
+        let chain_spec = ChainSpec::from_env()?;
+        let chain_id = match std::env::var("KAKAROT_CHAIN_ID") {
+            Ok(_) => ChainIdConfig::from_env()?,
+            Err(_) => ChainIdConfig::Fixed(chain_spec.chain_id),
+        };
+
+        let native_token_address = match std::env::var("KAKAROT_NATIVE_TOKEN_ADDRESS") {
+            Ok(address) => Some(FieldElement::from_hex_be(&address).map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_NATIVE_TOKEN_ADDRESS should be provided as a hex string, got {address}"
+                ))
+            })?),
+            Err(_) => None,
+        };
+
+        let fee_token_address = match std::env::var("KAKAROT_FEE_TOKEN_ADDRESS") {
+            Ok(address) => Some(FieldElement::from_hex_be(&address).map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_FEE_TOKEN_ADDRESS should be provided as a hex string, got {address}"
+                ))
+            })?),
+            Err(_) => None,
+        };
+
+        let kakarot_deployment_block = match std::env::var("KAKAROT_DEPLOYMENT_BLOCK") {
+            Ok(block) => block.parse::<u64>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_DEPLOYMENT_BLOCK should be a block number, got {block}"
+                ))
+            })?,
+            Err(_) => 0,
+        };
+
+        let transaction_lookup = TransactionLookupConfig::from_env()?;
+
+        let coinbase = match std::env::var("KAKAROT_COINBASE") {
+            Ok(coinbase) => Some(Address::from_str(&coinbase).map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_COINBASE should be provided as an EVM address, got {coinbase}"
+                ))
+            })?),
+            Err(_) => None,
+        };
+
+        let signer = EthSigner::from_env()?;
+
+        let sender_policy = SenderPolicy::from_env()?;
+
+        let block_cache = BlockCacheConfig::from_env()?;
+
+        let transaction_conversion_concurrency = match std::env::var("KAKAROT_TRANSACTION_CONVERSION_CONCURRENCY") {
+            Ok(concurrency) => {
+                concurrency.parse::<usize>().ok().filter(|concurrency| *concurrency > 0).ok_or_else(|| {
+                    ConfigError::EnvironmentVariableSetWrong(format!(
+                        "KAKAROT_TRANSACTION_CONVERSION_CONCURRENCY should be a strictly positive integer, got \
+                         {concurrency}"
+                    ))
+                })?
+            }
+            Err(_) => DEFAULT_TRANSACTION_CONVERSION_CONCURRENCY,
+        };
+
+        let logs = LogsConfig::from_env()?;
+
+        let gas_estimation = GasEstimationConfig::from_env()?;
+
+        let log_index = std::env::var("KAKAROT_LOG_INDEX").map(|v| v.to_lowercase() == "true").unwrap_or(false);
+
+        let storage = StorageConfig::from_env()?;
+
+        let relayer = RelayerConfig::from_env()?;
+
+        let config = StarknetConfig::new(network, kakarot_address, proxy_account_class_hash);
+        let native_token_address = native_token_address.unwrap_or(config.native_token_address);
+        // A fee token equal to the native token is the single token setup
+        let fee_token_address = fee_token_address.filter(|fee_token| *fee_token != native_token_address);
+        Ok(StarknetConfig {
+            chain_id,
+            chain_spec,
+            native_token_address,
+            fee_token_address,
+            kakarot_deployment_block,
+            transaction_lookup,
+            coinbase,
+            signer,
+            sender_policy,
+            block_cache,
+            transaction_conversion_concurrency,
+            logs,
+            gas_estimation,
+            log_index,
+            storage,
+            relayer,
+            ..config
+        })
     }
 }
 
@@ -1,5 +1,7 @@
+use std::str::FromStr;
+
 use lazy_static::lazy_static;
-use reth_primitives::{H256, H64, U128, U256, U8};
+use reth_primitives::{Address, H256, H64, U128, U256, U8};
 use starknet::accounts::Call as StarknetCall;
 use starknet::core::types::FieldElement;
 use starknet::macros::selector;
@@ -16,6 +18,15 @@ pub const EARLIEST_BLOCK_NUMBER: u64 = 0;
 /// Current chunk limit for pathfinder https://github.com/eqlabs/pathfinder/blob/main/crates/storage/src/connection/event.rs#L11
 pub const CHUNK_SIZE_LIMIT: u64 = 1024;
 
+/// Default number of transactions converted concurrently when building a full block.
+pub const DEFAULT_TRANSACTION_CONVERSION_CONCURRENCY: usize = 16;
+
+/// Maximum number of blocks returned by `eth_feeHistory`, as in geth.
+pub const MAX_FEE_HISTORY_BLOCK_COUNT: u64 = 1024;
+
+/// Number of blocks whose fees are fetched concurrently by `eth_feeHistory`.
+pub const FEE_HISTORY_CONCURRENCY: usize = 8;
+
 pub const MADARA_RPC_URL: &str = "http://127.0.0.1:9944";
 
 pub const KATANA_RPC_URL: &str = "http://0.0.0.0:5050";
@@ -30,10 +41,18 @@ pub mod selectors {
     pub const ETH_CALL: FieldElement = selector!("eth_call");
     pub const ETH_SEND_TRANSACTION: FieldElement = selector!("eth_send_transaction");
     pub const COMPUTE_STARKNET_ADDRESS: FieldElement = selector!("compute_starknet_address");
+    pub const DEPLOY_EXTERNALLY_OWNED_ACCOUNT: FieldElement = selector!("deploy_externally_owned_account");
 
     pub const GET_EVM_ADDRESS: FieldElement = selector!("get_evm_address");
+    pub const GET_IMPLEMENTATION: FieldElement = selector!("get_implementation");
 
     pub const BALANCE_OF: FieldElement = selector!("balanceOf");
+    pub const TOTAL_SUPPLY: FieldElement = selector!("totalSupply");
+    pub const ALLOWANCE: FieldElement = selector!("allowance");
+    pub const NAME: FieldElement = selector!("name");
+    pub const SYMBOL: FieldElement = selector!("symbol");
+    pub const DECIMALS: FieldElement = selector!("decimals");
+    pub const TRANSFER: FieldElement = selector!("Transfer");
 
     pub const EVM_CONTRACT_DEPLOYED: FieldElement = selector!("evm_contract_deployed");
 }
@@ -89,6 +108,13 @@ lazy_static! {
     pub static ref TOTAL_DIFFICULTY: Option<U256> = None;
 }
 
+lazy_static! {
+    /// Reserved EVM address at which the Starknet fee token is exposed as a read-only ERC20, the
+    /// placeholder commonly used for the native token of EVM chains.
+    pub static ref NATIVE_TOKEN_ERC20_ADDRESS: Address =
+        Address::from_str("0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE").unwrap();
+}
+
 lazy_static! {
     pub static ref KAKAROT_CLIENT_VERSION: String = format!("kakarot_{}", env!("CARGO_PKG_VERSION"));
 }
@@ -1,13 +1,15 @@
-use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE, SERVER_IS_BUSY_CODE, UNKNOWN_ERROR_CODE};
 use jsonrpsee::types::ErrorObject;
+use reth_primitives::{Address, Bytes, H256, U64};
 use starknet::core::types::{FromByteSliceError, StarknetError};
 use starknet::providers::ProviderError;
 use thiserror::Error;
 
 use super::helpers::DataDecodingError;
+use super::validation::InvalidTransactionError;
+use crate::models::revert::{revert_data_from_error_message, revert_message};
 use crate::models::ConversionError;
 
-/// List of JSON-RPC error codes from reth
+/// List of JSON-RPC error codes from reth, following the codes returned by geth.
 #[derive(Debug, Copy, PartialEq, Eq, Clone)]
 pub enum EthRpcErrorCode {
     /// Custom geth error code, <https://github.com/vapory-legacy/wiki/blob/master/JSON-RPC-Error-Codes-Improvement-Proposal.md>
@@ -20,6 +22,14 @@ pub enum EthRpcErrorCode {
     ResourceNotFound = -32001,
     /// Failed to send transaction, See also <https://github.com/MetaMask/eth-rpc-errors/blob/main/src/error-constants.ts>
     TransactionRejected = -32003,
+    /// Request exceeds defined limit, See also <https://eips.ethereum.org/EIPS/eip-1474#error-codes>
+    LimitExceeded = -32005,
+    /// The method doesn't exist or isn't supported by Kakarot, <https://www.jsonrpc.org/specification#error_object>
+    MethodNotFound = -32601,
+    /// Invalid method parameters, <https://www.jsonrpc.org/specification#error_object>
+    InvalidParams = -32602,
+    /// Internal error of the RPC or of the Starknet node, <https://www.jsonrpc.org/specification#error_object>
+    InternalError = -32603,
 }
 
 // Error that can accure when preparing configuration.
@@ -39,6 +49,60 @@ pub enum ConfigError {
     InvalidNetwork(String),
 }
 
+/// Error that can accure when signing a transaction with a managed account.
+#[derive(Debug, Error)]
+pub enum SignerError {
+    /// Invalid hex private key.
+    #[error("invalid private key {0}")]
+    InvalidPrivateKey(String),
+    /// Keystore decryption error.
+    #[error("failed to decrypt keystore: {0}")]
+    Keystore(String),
+    /// The account isn't managed by the RPC.
+    #[error("unknown account {0:#x}")]
+    UnknownAccount(Address),
+    /// Signing error.
+    #[error("failed to sign: {0}")]
+    SigningFailed(String),
+    /// Typed data which can't be hashed following EIP-712.
+    #[error("invalid typed data: {0}")]
+    InvalidTypedData(String),
+}
+
+impl SignerError {
+    /// Returns the JSON-RPC error code of the error.
+    pub fn code(&self) -> EthRpcErrorCode {
+        match self {
+            Self::UnknownAccount(_) => EthRpcErrorCode::InvalidInput,
+            Self::InvalidPrivateKey(_) | Self::InvalidTypedData(_) => EthRpcErrorCode::InvalidParams,
+            Self::Keystore(_) | Self::SigningFailed(_) => EthRpcErrorCode::InternalError,
+        }
+    }
+}
+
+impl From<SignerError> for ErrorObject<'static> {
+    fn from(error: SignerError) -> Self {
+        rpc_err(error.code() as i32, error.to_string())
+    }
+}
+
+/// Transaction rejected by the sender policy of the RPC.
+#[derive(Debug, Error)]
+pub enum SenderPolicyError {
+    /// The sender isn't in the allow-list.
+    #[error("sender {0:#x} is not allowed to transact")]
+    NotAllowed(Address),
+    /// The sender is in the deny-list.
+    #[error("sender {0:#x} is denied")]
+    Denied(Address),
+    /// The transaction transfers more than the maximum value.
+    #[error("transaction value {value} exceeds the maximum of {max_value}")]
+    ValueTooHigh { value: u128, max_value: u128 },
+    /// The gas limit of the transaction exceeds the maximum.
+    #[error("transaction gas limit {gas} exceeds the maximum of {max_gas}")]
+    GasTooHigh { gas: u64, max_gas: u64 },
+}
+
 /// Error that can accure when interacting with the Kakarot ETH API.
 #[derive(Debug, Error)]
 pub enum EthApiError<E: std::error::Error> {
@@ -60,14 +124,95 @@ pub enum EthApiError<E: std::error::Error> {
     /// Missing parameter error.
     #[error("Missing parameter: {0}")]
     MissingParameterError(String),
+    /// Invalid parameter error.
+    #[error("Invalid parameter: {0}")]
+    InvalidParameterError(String),
     /// Configuration error.
     #[error(transparent)]
     ConfigError(#[from] ConfigError),
+    /// Unknown or expired filter.
+    #[error("filter not found")]
+    FilterNotFound(U64),
+    /// Unknown transaction.
+    #[error("transaction {0:#x} not found")]
+    TransactionNotFound(H256),
+    /// Signer error.
+    #[error(transparent)]
+    SignerError(#[from] SignerError),
+    /// Sender policy error.
+    #[error(transparent)]
+    SenderPolicyError(#[from] SenderPolicyError),
+    /// Transaction rejected by the pre-flight validation.
+    #[error(transparent)]
+    InvalidTransaction(#[from] InvalidTransactionError),
+    /// Query returning more results than the configured maximum.
+    #[error("query returned more than {0} results")]
+    TooManyResults(usize),
+    /// Method or option which Kakarot, or the Starknet network it runs on, doesn't support.
+    #[error("{0} is not supported")]
+    Unsupported(String),
+    /// EVM execution reverted, with the revert data.
+    #[error("{}", revert_message(.0))]
+    EvmRevert(Bytes),
     /// Other error.
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
+impl<E: std::error::Error> EthApiError<E> {
+    /// Maps an error carrying the revert payload of a reverted Kakarot execution to an
+    /// `EvmRevert` error with the revert data of the EVM. Other errors are left unchanged.
+    pub fn map_revert(self) -> Self {
+        let message = match &self {
+            Self::RequestError(err) => err.to_string(),
+            Self::FeederGatewayError(message) => message.clone(),
+            _ => return self,
+        };
+        revert_data_from_error_message(&message).map(Self::EvmRevert).unwrap_or(self)
+    }
+
+    /// Returns the JSON-RPC error code of the error, the same code geth returns for the same
+    /// failure so that the clients can handle the errors of Kakarot like the errors of Ethereum.
+    pub fn code(&self) -> EthRpcErrorCode {
+        match self {
+            Self::RequestError(ProviderError::StarknetError(err)) => match err {
+                StarknetError::BlockNotFound
+                | StarknetError::ClassHashNotFound
+                | StarknetError::ContractNotFound
+                | StarknetError::NoBlocks
+                | StarknetError::TransactionHashNotFound => EthRpcErrorCode::ResourceNotFound,
+                StarknetError::ContractError => EthRpcErrorCode::ExecutionError,
+                StarknetError::InvalidContractClass | StarknetError::ClassAlreadyDeclared => {
+                    EthRpcErrorCode::InvalidInput
+                }
+                StarknetError::InvalidContinuationToken | StarknetError::InvalidTransactionIndex => {
+                    EthRpcErrorCode::InvalidParams
+                }
+                StarknetError::PageSizeTooBig | StarknetError::TooManyKeysInFilter => EthRpcErrorCode::LimitExceeded,
+                StarknetError::FailedToReceiveTransaction => EthRpcErrorCode::TransactionRejected,
+            },
+            Self::RequestError(ProviderError::RateLimited) => EthRpcErrorCode::LimitExceeded,
+            Self::RequestError(ProviderError::ArrayLengthMismatch | ProviderError::Other(_)) => {
+                EthRpcErrorCode::InternalError
+            }
+            Self::DataDecodingError(DataDecodingError::TransactionDecodingError(_)) => EthRpcErrorCode::InvalidInput,
+            Self::KakarotDataFilteringError(_) | Self::TransactionNotFound(_) => EthRpcErrorCode::ResourceNotFound,
+            Self::MissingParameterError(_) | Self::InvalidParameterError(_) => EthRpcErrorCode::InvalidParams,
+            Self::FilterNotFound(_) | Self::InvalidTransaction(_) => EthRpcErrorCode::InvalidInput,
+            Self::SignerError(err) => err.code(),
+            Self::SenderPolicyError(_) => EthRpcErrorCode::TransactionRejected,
+            Self::TooManyResults(_) => EthRpcErrorCode::LimitExceeded,
+            Self::Unsupported(_) => EthRpcErrorCode::MethodNotFound,
+            Self::EvmRevert(_) => EthRpcErrorCode::ExecutionError,
+            Self::ConversionError(_)
+            | Self::DataDecodingError(_)
+            | Self::FeederGatewayError(_)
+            | Self::ConfigError(_)
+            | Self::Other(_) => EthRpcErrorCode::InternalError,
+        }
+    }
+}
+
 impl<T, E: std::error::Error> From<ConversionError<T>> for EthApiError<E> {
     fn from(err: ConversionError<T>) -> Self {
         Self::ConversionError(err.to_string())
@@ -82,42 +227,15 @@ impl<E: std::error::Error> From<FromByteSliceError> for EthApiError<E> {
 
 impl<E: std::error::Error> From<EthApiError<E>> for ErrorObject<'static> {
     fn from(error: EthApiError<E>) -> Self {
+        let code = error.code() as i32;
         match error {
-            EthApiError::RequestError(err_provider) => match err_provider {
-                ProviderError::StarknetError(err) => match err {
-                    StarknetError::BlockNotFound
-                    | StarknetError::ClassHashNotFound
-                    | StarknetError::ContractNotFound
-                    | StarknetError::NoBlocks
-                    | StarknetError::TransactionHashNotFound => {
-                        rpc_err(EthRpcErrorCode::ResourceNotFound as i32, err_provider.to_string())
-                    }
-                    StarknetError::ContractError => {
-                        rpc_err(EthRpcErrorCode::ExecutionError as i32, err_provider.to_string())
-                    }
-                    StarknetError::InvalidContractClass
-                    | StarknetError::InvalidContinuationToken
-                    | StarknetError::InvalidTransactionIndex
-                    | StarknetError::PageSizeTooBig
-                    | StarknetError::TooManyKeysInFilter
-                    | StarknetError::ClassAlreadyDeclared => {
-                        rpc_err(EthRpcErrorCode::InvalidInput as i32, err_provider.to_string())
-                    }
-                    StarknetError::FailedToReceiveTransaction => {
-                        rpc_err(EthRpcErrorCode::TransactionRejected as i32, err_provider.to_string())
-                    }
-                },
-                ProviderError::ArrayLengthMismatch => rpc_err(INVALID_PARAMS_CODE, err_provider.to_string()),
-                ProviderError::RateLimited => rpc_err(SERVER_IS_BUSY_CODE, err_provider.to_string()),
-                ProviderError::Other(_) => rpc_err(UNKNOWN_ERROR_CODE, err_provider.to_string()),
-            },
-            EthApiError::ConversionError(err) => rpc_err(INTERNAL_ERROR_CODE, err),
-            EthApiError::DataDecodingError(err) => rpc_err(INTERNAL_ERROR_CODE, err.to_string()),
-            EthApiError::KakarotDataFilteringError(err) => rpc_err(INTERNAL_ERROR_CODE, err),
-            EthApiError::FeederGatewayError(err) => rpc_err(INTERNAL_ERROR_CODE, err),
-            EthApiError::MissingParameterError(err) => rpc_err(INVALID_PARAMS_CODE, err),
-            EthApiError::ConfigError(err) => rpc_err(INTERNAL_ERROR_CODE, err.to_string()),
-            EthApiError::Other(err) => rpc_err(INTERNAL_ERROR_CODE, err.to_string()),
+            // The revert data is returned in the data of the error, like geth
+            EthApiError::EvmRevert(data) => ErrorObject::owned(code, revert_message(&data), Some(data)),
+            EthApiError::ConversionError(message)
+            | EthApiError::FeederGatewayError(message)
+            | EthApiError::MissingParameterError(message)
+            | EthApiError::InvalidParameterError(message) => rpc_err(code, message),
+            error => rpc_err(code, error.to_string()),
         }
     }
 }
@@ -132,3 +250,76 @@ impl<E: std::error::Error> From<EthApiError<E>> for jsonrpsee::core::Error {
 pub fn rpc_err(code: i32, msg: impl Into<String>) -> jsonrpsee::types::error::ErrorObject<'static> {
     jsonrpsee::types::error::ErrorObject::owned(code, msg.into(), None::<()>)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Error = EthApiError<std::io::Error>;
+
+    fn error_object(error: Error) -> ErrorObject<'static> {
+        error.into()
+    }
+
+    #[test]
+    fn test_error_codes_follow_geth() {
+        // Given
+        let errors = [
+            (Error::InvalidParameterError("invalid block tag".into()), EthRpcErrorCode::InvalidParams),
+            (Error::MissingParameterError("to".into()), EthRpcErrorCode::InvalidParams),
+            (Error::EvmRevert(Bytes::default()), EthRpcErrorCode::ExecutionError),
+            (Error::TooManyResults(10_000), EthRpcErrorCode::LimitExceeded),
+            (Error::RequestError(ProviderError::RateLimited), EthRpcErrorCode::LimitExceeded),
+            (
+                Error::RequestError(ProviderError::StarknetError(StarknetError::PageSizeTooBig)),
+                EthRpcErrorCode::LimitExceeded,
+            ),
+            (
+                Error::RequestError(ProviderError::StarknetError(StarknetError::BlockNotFound)),
+                EthRpcErrorCode::ResourceNotFound,
+            ),
+            (Error::TransactionNotFound(H256::zero()), EthRpcErrorCode::ResourceNotFound),
+            (Error::SignerError(SignerError::UnknownAccount(Address::zero())), EthRpcErrorCode::InvalidInput),
+            (
+                Error::SignerError(SignerError::InvalidTypedData("missing domain".into())),
+                EthRpcErrorCode::InvalidParams,
+            ),
+            (
+                Error::SenderPolicyError(SenderPolicyError::Denied(Address::zero())),
+                EthRpcErrorCode::TransactionRejected,
+            ),
+            (Error::ConversionError("overflow".into()), EthRpcErrorCode::InternalError),
+            (Error::Unsupported("transaction simulation".into()), EthRpcErrorCode::MethodNotFound),
+        ];
+
+        for (error, expected) in errors {
+            // When
+            let message = error.to_string();
+            let code = error.code();
+
+            // Then
+            assert_eq!(expected, code, "{message}");
+            assert_eq!(expected as i32, error_object(error).code(), "{message}");
+        }
+        assert_eq!(-32602, EthRpcErrorCode::InvalidParams as i32);
+        assert_eq!(-32601, EthRpcErrorCode::MethodNotFound as i32);
+        assert_eq!(-32603, EthRpcErrorCode::InternalError as i32);
+    }
+
+    #[test]
+    fn test_error_messages() {
+        // Given
+        let revert_data = Bytes::from(vec![0xde, 0xad, 0xbe, 0xef]);
+
+        // When
+        let revert = error_object(Error::EvmRevert(revert_data));
+        let invalid_parameter = error_object(Error::InvalidParameterError("invalid block tag".into()));
+        let not_found = error_object(Error::TransactionNotFound(H256::from_low_u64_be(1)));
+
+        // Then
+        assert_eq!("execution reverted", revert.message());
+        assert_eq!(Some(r#""0xdeadbeef""#), revert.data().map(|data| data.get()));
+        assert_eq!("invalid block tag", invalid_parameter.message());
+        assert_eq!(format!("transaction {:#x} not found", H256::from_low_u64_be(1)), not_found.message());
+    }
+}
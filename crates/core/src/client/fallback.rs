@@ -0,0 +1,365 @@
+//! Failover between several Starknet JSON-RPC endpoints.
+//!
+//! [`FallbackTransport`] sends each request to the current endpoint and, on a transient error (a
+//! connection failure, a timeout, a 429 or a 5xx status), marks the endpoint unhealthy, rotates to
+//! the next healthy one and retries with an exponential backoff. The errors returned by the node
+//! itself in a JSON-RPC response are not retried. Unhealthy endpoints are skipped until their
+//! cooldown expires or a health check finds them back up.
+//!
+//! The transport is wrapped in a `JsonRpcClient`, see [`FallbackProvider`], which is a regular
+//! Starknet `Provider` for the Kakarot client.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use starknet::providers::jsonrpc::{JsonRpcMethod, JsonRpcResponse, JsonRpcTransport};
+use starknet::providers::JsonRpcClient;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use url::Url;
+
+use super::errors::ConfigError;
+
+/// Default number of retries of a request failing with a transient error.
+pub const DEFAULT_MAX_RETRIES: usize = 5;
+/// Default delay before the first retry, doubled at each retry.
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// Default maximum delay between two retries.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+/// Default time during which an endpoint that failed is skipped.
+pub const DEFAULT_UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(30);
+/// Default interval between two health checks of the endpoints.
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Starknet provider failing over between several JSON-RPC endpoints.
+pub type FallbackProvider = JsonRpcClient<FallbackTransport>;
+
+/// Configuration of the failover between Starknet JSON-RPC endpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FallbackConfig {
+    /// URLs of the endpoints, by order of preference.
+    pub urls: Vec<Url>,
+    /// Number of retries of a request failing with a transient error.
+    pub max_retries: usize,
+    /// Delay before the first retry, doubled at each retry.
+    pub initial_backoff: Duration,
+    /// Maximum delay between two retries.
+    pub max_backoff: Duration,
+    /// Time during which an endpoint that failed is skipped.
+    pub unhealthy_cooldown: Duration,
+    /// Interval between two health checks of the endpoints.
+    pub health_check_interval: Duration,
+}
+
+impl FallbackConfig {
+    pub fn new(urls: Vec<Url>) -> Self {
+        Self {
+            urls,
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            unhealthy_cooldown: DEFAULT_UNHEALTHY_COOLDOWN,
+            health_check_interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+        }
+    }
+
+    /// Create a new `FallbackConfig` from environment variables, with `primary_url` as the
+    /// preferred endpoint. Returns `None` when `STARKNET_FALLBACK_RPC_URLS`, a comma separated
+    /// list of the fallback endpoints, is not set. The number of retries can be set with
+    /// `STARKNET_RPC_MAX_RETRIES`.
+    pub fn from_env(primary_url: Url) -> Result<Option<Self>, ConfigError> {
+        let fallback_urls = match std::env::var("STARKNET_FALLBACK_RPC_URLS") {
+            Ok(urls) => urls,
+            Err(_) => return Ok(None),
+        };
+
+        let mut urls = vec![primary_url];
+        for url in fallback_urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+            urls.push(Url::parse(url)?);
+        }
+
+        let max_retries = match std::env::var("STARKNET_RPC_MAX_RETRIES") {
+            Ok(max_retries) => max_retries.parse::<usize>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "STARKNET_RPC_MAX_RETRIES should be a positive integer, got {max_retries}"
+                ))
+            })?,
+            Err(_) => DEFAULT_MAX_RETRIES,
+        };
+
+        Ok(Some(Self { max_retries, ..Self::new(urls) }))
+    }
+
+    /// Returns the delay before the retry following the given number of failed attempts.
+    pub fn backoff(&self, failed_attempts: usize) -> Duration {
+        let factor = 1u32.checked_shl(failed_attempts.saturating_sub(1) as u32).unwrap_or(u32::MAX);
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FallbackTransportError {
+    #[error("no Starknet endpoint configured")]
+    NoEndpoint,
+    #[error("request to {url} failed: {source}")]
+    Reqwest { url: Url, source: reqwest::Error },
+    #[error("request to {url} failed with status {status}")]
+    Status { url: Url, status: StatusCode },
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl FallbackTransportError {
+    /// Returns whether the request may succeed when retried, possibly on another endpoint.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Reqwest { source, .. } => source.is_connect() || source.is_timeout() || source.is_request(),
+            Self::Status { status, .. } => is_transient_status(*status),
+            Self::NoEndpoint | Self::Json(_) => false,
+        }
+    }
+}
+
+/// Returns whether the HTTP status is a transient failure of the endpoint: rate limited or a
+/// server error.
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Endpoints of the failover, with the time until which each one is considered unhealthy.
+#[derive(Debug)]
+struct Endpoints {
+    urls: Vec<Url>,
+    unhealthy_until: Mutex<Vec<Option<Instant>>>,
+    current: AtomicUsize,
+}
+
+impl Endpoints {
+    fn new(urls: Vec<Url>) -> Self {
+        let unhealthy_until = Mutex::new(vec![None; urls.len()]);
+        Self { urls, unhealthy_until, current: AtomicUsize::new(0) }
+    }
+
+    /// Returns the index of the endpoint to use: the current one if healthy, else the next
+    /// healthy one. Falls back to the current endpoint when none is healthy.
+    fn select(&self) -> usize {
+        let current = self.current.load(Ordering::Relaxed);
+        let now = Instant::now();
+        // The lock is never held across a panic, it can't be poisoned
+        let unhealthy_until = self.unhealthy_until.lock().expect("poisoned lock");
+        (0..self.urls.len())
+            .map(|offset| (current + offset) % self.urls.len())
+            .find(|index| unhealthy_until[*index].map_or(true, |until| until <= now))
+            .unwrap_or(current)
+    }
+
+    /// Marks the endpoint unhealthy for the cooldown and rotates to the next one.
+    fn mark_unhealthy(&self, index: usize, cooldown: Duration) {
+        self.unhealthy_until.lock().expect("poisoned lock")[index] = Some(Instant::now() + cooldown);
+        let _ =
+            self.current.compare_exchange(index, (index + 1) % self.urls.len(), Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    fn mark_healthy(&self, index: usize) {
+        self.unhealthy_until.lock().expect("poisoned lock")[index] = None;
+    }
+
+    fn is_healthy(&self, index: usize) -> bool {
+        self.unhealthy_until.lock().expect("poisoned lock")[index].map_or(true, |until| until <= Instant::now())
+    }
+}
+
+/// JSON-RPC transport sending the requests to the first healthy endpoint of a list, retrying the
+/// transient failures on the next endpoints.
+#[derive(Debug, Clone)]
+pub struct FallbackTransport {
+    client: Client,
+    endpoints: Arc<Endpoints>,
+    config: FallbackConfig,
+}
+
+impl FallbackTransport {
+    pub fn new(config: FallbackConfig) -> Self {
+        Self { client: Client::new(), endpoints: Arc::new(Endpoints::new(config.urls.clone())), config }
+    }
+
+    /// Returns the URL of the endpoint currently in use.
+    pub fn current_url(&self) -> Option<&Url> {
+        self.endpoints.urls.get(self.endpoints.select())
+    }
+
+    /// Returns the URLs of the endpoints with whether each one is currently healthy.
+    pub fn health(&self) -> Vec<(Url, bool)> {
+        self.endpoints
+            .urls
+            .iter()
+            .enumerate()
+            .map(|(index, url)| (url.clone(), self.endpoints.is_healthy(index)))
+            .collect()
+    }
+
+    /// Spawns a task probing every endpoint at the health check interval, so that an endpoint
+    /// back up is used again before its cooldown expires, and a failing one is skipped before a
+    /// request hits it.
+    pub fn spawn_health_checks(&self) -> JoinHandle<()> {
+        let transport = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(transport.config.health_check_interval);
+            loop {
+                interval.tick().await;
+                for index in 0..transport.endpoints.urls.len() {
+                    match transport.post(index, JsonRpcMethod::BlockNumber, &serde_json::json!([])).await {
+                        Ok(_) => transport.endpoints.mark_healthy(index),
+                        Err(err) if err.is_transient() => {
+                            tracing::warn!("Starknet endpoint {} is unhealthy: {err}", transport.endpoints.urls[index]);
+                            transport.endpoints.mark_unhealthy(index, transport.config.unhealthy_cooldown);
+                        }
+                        Err(_) => {}
+                    }
+                }
+            }
+        })
+    }
+
+    /// Sends the request to the endpoint and returns the body of the response.
+    async fn post(
+        &self,
+        index: usize,
+        method: JsonRpcMethod,
+        params: &serde_json::Value,
+    ) -> Result<String, FallbackTransportError> {
+        let url = &self.endpoints.urls[index];
+        let request_body = serde_json::json!({ "id": 1, "jsonrpc": "2.0", "method": method, "params": params });
+        let reqwest_err = |source| FallbackTransportError::Reqwest { url: url.clone(), source };
+
+        let response = self
+            .client
+            .post(url.clone())
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&request_body)?)
+            .send()
+            .await
+            .map_err(reqwest_err)?;
+
+        let status = response.status();
+        if is_transient_status(status) {
+            return Err(FallbackTransportError::Status { url: url.clone(), status });
+        }
+        response.text().await.map_err(reqwest_err)
+    }
+}
+
+#[async_trait]
+impl JsonRpcTransport for FallbackTransport {
+    type Error = FallbackTransportError;
+
+    async fn send_request<P, R>(&self, method: JsonRpcMethod, params: P) -> Result<JsonRpcResponse<R>, Self::Error>
+    where
+        P: Serialize + Send,
+        R: DeserializeOwned,
+    {
+        if self.endpoints.urls.is_empty() {
+            return Err(FallbackTransportError::NoEndpoint);
+        }
+        let params = serde_json::to_value(params)?;
+
+        let mut failed_attempts = 0;
+        loop {
+            let index = self.endpoints.select();
+            match self.post(index, method, &params).await {
+                Ok(response_body) => return Ok(serde_json::from_str(&response_body)?),
+                Err(err) if err.is_transient() && failed_attempts < self.config.max_retries => {
+                    tracing::warn!("Starknet request {method:?} failed, retrying on the next endpoint: {err}");
+                    self.endpoints.mark_unhealthy(index, self.config.unhealthy_cooldown);
+                    failed_attempts += 1;
+                    tokio::time::sleep(self.config.backoff(failed_attempts)).await;
+                }
+                Err(err) => {
+                    if err.is_transient() {
+                        self.endpoints.mark_unhealthy(index, self.config.unhealthy_cooldown);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(count: usize) -> Vec<Url> {
+        (0..count).map(|port| Url::parse(&format!("http://127.0.0.1:{}", port + 1)).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        // Given
+        let config = FallbackConfig::new(urls(1));
+
+        // Then
+        assert_eq!(Duration::from_millis(100), config.backoff(1));
+        assert_eq!(Duration::from_millis(200), config.backoff(2));
+        assert_eq!(Duration::from_millis(800), config.backoff(4));
+        assert_eq!(DEFAULT_MAX_BACKOFF, config.backoff(10));
+        assert_eq!(DEFAULT_MAX_BACKOFF, config.backoff(usize::MAX));
+    }
+
+    #[test]
+    fn test_endpoints_rotate_on_failure() {
+        // Given
+        let endpoints = Endpoints::new(urls(3));
+
+        // When
+        endpoints.mark_unhealthy(0, Duration::from_secs(60));
+
+        // Then
+        assert_eq!(1, endpoints.select());
+
+        // When
+        endpoints.mark_unhealthy(1, Duration::from_secs(60));
+        endpoints.mark_unhealthy(2, Duration::from_secs(60));
+
+        // Then
+        assert_eq!(0, endpoints.select());
+
+        // When
+        endpoints.mark_healthy(2);
+
+        // Then
+        assert_eq!(2, endpoints.select());
+        assert!(!endpoints.is_healthy(1));
+    }
+
+    #[test]
+    fn test_transient_status() {
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_transient_status(StatusCode::OK));
+        assert!(!is_transient_status(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_endpoints_are_retried_then_fail() {
+        // Given
+        let config = FallbackConfig {
+            max_retries: 2,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            ..FallbackConfig::new(urls(2))
+        };
+        let transport = FallbackTransport::new(config);
+
+        // When
+        let result = transport.send_request::<_, u64>(JsonRpcMethod::BlockNumber, serde_json::json!([])).await;
+
+        // Then
+        assert!(matches!(result, Err(FallbackTransportError::Reqwest { .. })));
+        assert!(transport.health().iter().all(|(_, healthy)| !healthy));
+    }
+}
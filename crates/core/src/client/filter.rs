@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reth_primitives::{H256, U64};
+use reth_rpc_types::Filter;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{prefixed_key, Storage, StorageError};
+
+/// Filters that are not polled during this period are uninstalled, following geth's behavior.
+pub const DEFAULT_FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Key prefix of the installed filters in the storage, followed by the filter id.
+const FILTERS_PREFIX: &[u8] = b"filters/";
+
+/// Key of the next filter id in the storage, so that the ids aren't reused after a restart.
+const NEXT_FILTER_ID_KEY: &[u8] = b"next_filter_id";
+
+/// The kind of changes a filter tracks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FilterKind {
+    /// Logs matching the filter.
+    Log(Box<Filter>),
+    /// Hashes of the new blocks.
+    Block,
+    /// Hashes of the new pending transactions.
+    PendingTransaction,
+}
+
+/// An installed filter.
+#[derive(Debug)]
+struct ActiveFilter {
+    kind: FilterKind,
+    /// Last block for which changes were returned.
+    last_polled_block: u64,
+    /// Pending transactions already returned, only used by pending transaction filters.
+    seen_pending_transactions: HashSet<H256>,
+    /// Last time the filter was installed or polled.
+    last_poll: Instant,
+}
+
+/// Filter as persisted in the storage. The pending transactions already returned are not
+/// persisted, and the expiry of the filters restarts with the RPC.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredFilter {
+    kind: FilterKind,
+    last_polled_block: u64,
+}
+
+/// Snapshot of a filter taken at the beginning of a poll.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterPoll {
+    pub kind: FilterKind,
+    pub last_polled_block: u64,
+}
+
+/// Stores the filters installed through `eth_newFilter`, `eth_newBlockFilter` and
+/// `eth_newPendingTransactionFilter`, assigns their ids and tracks the last block polled by each
+/// of them. Filters which aren't polled for longer than the timeout are removed. The filters can be
+/// persisted in a [`Storage`], so that the dapps keep polling them across restarts.
+#[derive(Debug)]
+pub struct FilterStore {
+    filters: Mutex<HashMap<U64, ActiveFilter>>,
+    next_id: AtomicU64,
+    timeout: Duration,
+    storage: Option<Arc<dyn Storage>>,
+}
+
+impl Default for FilterStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_FILTER_TIMEOUT)
+    }
+}
+
+impl FilterStore {
+    pub fn new(timeout: Duration) -> Self {
+        Self { filters: Mutex::new(HashMap::new()), next_id: AtomicU64::new(1), timeout, storage: None }
+    }
+
+    /// Create a new `FilterStore` persisted in the storage, loaded with the stored filters.
+    pub fn with_storage(timeout: Duration, storage: Arc<dyn Storage>) -> Result<Self, StorageError> {
+        let mut filters = HashMap::new();
+        for (key, value) in storage.scan_prefix(FILTERS_PREFIX)? {
+            let id = decode_id(&key[FILTERS_PREFIX.len()..])?;
+            let stored: StoredFilter = serde_json::from_slice(&value).map_err(StorageError::new)?;
+            let filter = ActiveFilter {
+                kind: stored.kind,
+                last_polled_block: stored.last_polled_block,
+                seen_pending_transactions: HashSet::new(),
+                last_poll: Instant::now(),
+            };
+            filters.insert(U64::from(id), filter);
+        }
+        let next_id = match storage.get(NEXT_FILTER_ID_KEY)? {
+            Some(next_id) => decode_id(&next_id)?,
+            None => 1,
+        };
+        Ok(Self { filters: Mutex::new(filters), next_id: AtomicU64::new(next_id), timeout, storage: Some(storage) })
+    }
+
+    /// Installs a new filter, starting at `current_block`, and returns its id.
+    pub fn install(&self, kind: FilterKind, current_block: u64) -> U64 {
+        let id = U64::from(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let filter = ActiveFilter {
+            kind,
+            last_polled_block: current_block,
+            seen_pending_transactions: HashSet::new(),
+            last_poll: Instant::now(),
+        };
+
+        let mut filters = self.filters.lock().expect("filter store poisoned");
+        self.remove_expired(&mut filters);
+        self.persist(id, &filter);
+        filters.insert(id, filter);
+        id
+    }
+
+    /// Uninstalls a filter. Returns false if the filter didn't exist.
+    pub fn uninstall(&self, id: U64) -> bool {
+        let removed = self.filters.lock().expect("filter store poisoned").remove(&id).is_some();
+        if removed {
+            self.forget(id);
+        }
+        removed
+    }
+
+    /// Returns the kind of the filter, without updating its poll state.
+    pub fn kind(&self, id: U64) -> Option<FilterKind> {
+        let mut filters = self.filters.lock().expect("filter store poisoned");
+        self.remove_expired(&mut filters);
+        filters.get_mut(&id).map(|filter| {
+            filter.last_poll = Instant::now();
+            filter.kind.clone()
+        })
+    }
+
+    /// Starts a poll of the filter: refreshes its expiry and returns its current state.
+    pub fn start_poll(&self, id: U64) -> Option<FilterPoll> {
+        let mut filters = self.filters.lock().expect("filter store poisoned");
+        self.remove_expired(&mut filters);
+        filters.get_mut(&id).map(|filter| {
+            filter.last_poll = Instant::now();
+            FilterPoll { kind: filter.kind.clone(), last_polled_block: filter.last_polled_block }
+        })
+    }
+
+    /// Ends a poll of the filter by moving its cursor to `polled_block`.
+    pub fn end_poll(&self, id: U64, polled_block: u64) {
+        if let Some(filter) = self.filters.lock().expect("filter store poisoned").get_mut(&id) {
+            if polled_block > filter.last_polled_block {
+                filter.last_polled_block = polled_block;
+                self.persist(id, filter);
+            }
+        }
+    }
+
+    /// Moves back the cursors of the filters which polled blocks from `from_block` onwards,
+    /// reverted by a reorganization of the chain, so that the blocks replacing them are returned by
+    /// the next polls.
+    pub fn rewind(&self, from_block: u64) {
+        let last_kept_block = from_block.saturating_sub(1);
+        let mut filters = self.filters.lock().expect("filter store poisoned");
+        for (id, filter) in filters.iter_mut().filter(|(_, filter)| filter.last_polled_block > last_kept_block) {
+            filter.last_polled_block = last_kept_block;
+            self.persist(*id, filter);
+        }
+    }
+
+    /// Records the transactions currently pending and returns the ones the filter didn't return
+    /// yet. Transactions which left the pending state are forgotten.
+    pub fn new_pending_transactions(&self, id: U64, pending: Vec<H256>) -> Vec<H256> {
+        let mut filters = self.filters.lock().expect("filter store poisoned");
+        let filter = match filters.get_mut(&id) {
+            Some(filter) => filter,
+            None => return vec![],
+        };
+
+        let new_transactions =
+            pending.iter().filter(|hash| !filter.seen_pending_transactions.contains(hash)).cloned().collect();
+        filter.seen_pending_transactions = pending.into_iter().collect();
+        new_transactions
+    }
+
+    /// Returns the number of installed filters.
+    pub fn len(&self) -> usize {
+        self.filters.lock().expect("filter store poisoned").len()
+    }
+
+    /// Returns true if no filter is installed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn remove_expired(&self, filters: &mut HashMap<U64, ActiveFilter>) {
+        filters.retain(|id, filter| {
+            let expired = filter.last_poll.elapsed() >= self.timeout;
+            if expired {
+                self.forget(*id);
+            }
+            !expired
+        });
+    }
+
+    /// Stores the filter, along with the next filter id. The filters are still served from memory
+    /// when the storage fails, only their persistence is lost.
+    fn persist(&self, id: U64, filter: &ActiveFilter) {
+        let Some(storage) = &self.storage else {
+            return;
+        };
+        let stored = StoredFilter { kind: filter.kind.clone(), last_polled_block: filter.last_polled_block };
+        let persisted = serde_json::to_vec(&stored).map_err(StorageError::new).and_then(|stored| {
+            let next_id = self.next_id.load(Ordering::Relaxed).to_be_bytes().to_vec();
+            storage.put_batch(&[(filter_key(id), stored), (NEXT_FILTER_ID_KEY.to_vec(), next_id)])
+        });
+        if let Err(err) = persisted {
+            tracing::warn!("Failed to persist the filter {id}: {err}");
+        }
+    }
+
+    fn forget(&self, id: U64) {
+        if let Some(storage) = &self.storage {
+            if let Err(err) = storage.delete(&filter_key(id)) {
+                tracing::warn!("Failed to remove the filter {id} from the storage: {err}");
+            }
+        }
+    }
+}
+
+fn filter_key(id: U64) -> Vec<u8> {
+    prefixed_key(FILTERS_PREFIX, &id.to::<u64>().to_be_bytes())
+}
+
+fn decode_id(bytes: &[u8]) -> Result<u64, StorageError> {
+    let bytes: [u8; 8] = bytes.try_into().map_err(|_| StorageError::new("invalid stored filter id"))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn test_install_assigns_unique_ids() {
+        // Given
+        let store = FilterStore::default();
+
+        // When
+        let block_filter = store.install(FilterKind::Block, 10);
+        let pending_filter = store.install(FilterKind::PendingTransaction, 10);
+
+        // Then
+        assert_ne!(block_filter, pending_filter);
+        assert_eq!(2, store.len());
+        assert_eq!(Some(FilterKind::Block), store.kind(block_filter));
+    }
+
+    #[test]
+    fn test_uninstall() {
+        // Given
+        let store = FilterStore::default();
+        let id = store.install(FilterKind::Block, 10);
+
+        // When
+        let removed = store.uninstall(id);
+        let removed_twice = store.uninstall(id);
+
+        // Then
+        assert!(removed);
+        assert!(!removed_twice);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_poll_moves_cursor() {
+        // Given
+        let store = FilterStore::default();
+        let id = store.install(FilterKind::Block, 10);
+
+        // When
+        let first_poll = store.start_poll(id).unwrap();
+        store.end_poll(id, 15);
+        let second_poll = store.start_poll(id).unwrap();
+
+        // Then
+        assert_eq!(10, first_poll.last_polled_block);
+        assert_eq!(15, second_poll.last_polled_block);
+    }
+
+    #[test]
+    fn test_rewind_moves_back_the_cursors_after_the_fork() {
+        // Given
+        let store = FilterStore::default();
+        let (early, late) = (store.install(FilterKind::Block, 10), store.install(FilterKind::Block, 10));
+        store.end_poll(late, 20);
+
+        // When
+        store.rewind(15);
+
+        // Then
+        assert_eq!(10, store.start_poll(early).unwrap().last_polled_block);
+        assert_eq!(14, store.start_poll(late).unwrap().last_polled_block);
+    }
+
+    #[test]
+    fn test_expired_filters_are_removed() {
+        // Given
+        let store = FilterStore::new(Duration::ZERO);
+        let id = store.install(FilterKind::Block, 10);
+
+        // When
+        let poll = store.start_poll(id);
+
+        // Then
+        assert_eq!(None, poll);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_new_pending_transactions() {
+        // Given
+        let store = FilterStore::default();
+        let id = store.install(FilterKind::PendingTransaction, 10);
+        let (first, second, third) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2), H256::from_low_u64_be(3));
+
+        // When
+        let first_changes = store.new_pending_transactions(id, vec![first, second]);
+        let second_changes = store.new_pending_transactions(id, vec![second, third]);
+
+        // Then
+        assert_eq!(vec![first, second], first_changes);
+        assert_eq!(vec![third], second_changes);
+    }
+
+    #[test]
+    fn test_filters_are_reloaded_from_storage() {
+        // Given
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let store = FilterStore::with_storage(DEFAULT_FILTER_TIMEOUT, Arc::clone(&storage)).unwrap();
+        let id = store.install(FilterKind::Log(Box::default()), 10);
+        let uninstalled = store.install(FilterKind::Block, 10);
+        store.end_poll(id, 15);
+        store.uninstall(uninstalled);
+
+        // When
+        let reloaded = FilterStore::with_storage(DEFAULT_FILTER_TIMEOUT, storage).unwrap();
+
+        // Then
+        assert_eq!(1, reloaded.len());
+        assert_eq!(
+            Some(FilterPoll { kind: FilterKind::Log(Box::default()), last_polled_block: 15 }),
+            reloaded.start_poll(id)
+        );
+        assert!(reloaded.install(FilterKind::Block, 15) > uninstalled);
+    }
+}
@@ -0,0 +1,116 @@
+//! Estimation of the gas of a transaction by `eth_estimateGas`.
+//!
+//! The transaction is simulated on Starknet through Kakarot, and the Starknet fee estimate is
+//! converted into EVM gas: the overall fee divided by the gas price of the estimate. When the
+//! binary search is enabled, the estimate is refined like geth does, by simulating the
+//! transaction with decreasing gas limits until the minimal sufficient one is found. A buffer
+//! is finally added on top of the estimate, so that a transaction sent with it doesn't run out of
+//! gas when the state changes between the estimation and the inclusion.
+use std::future::Future;
+
+use super::errors::ConfigError;
+
+/// Relative precision at which the binary search stops, as in geth: the search is not worth the
+/// simulations once the bounds are within 1.5% of each other.
+const BINARY_SEARCH_ERROR_RATIO: f64 = 0.015;
+
+/// Configuration of the gas estimation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasEstimationConfig {
+    /// Search the minimal sufficient gas limit, each step simulating the transaction.
+    pub binary_search: bool,
+    /// Percentage added on top of the estimate.
+    pub buffer_percentage: u64,
+}
+
+impl GasEstimationConfig {
+    /// Create a new `GasEstimationConfig` from the `KAKAROT_ESTIMATE_GAS_BINARY_SEARCH` and
+    /// `KAKAROT_ESTIMATE_GAS_BUFFER_PERCENTAGE` environment variables. Defaults to the plain
+    /// conversion of the fee estimate, without buffer.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let binary_search =
+            std::env::var("KAKAROT_ESTIMATE_GAS_BINARY_SEARCH").map(|v| v.to_lowercase() == "true").unwrap_or(false);
+        let buffer_percentage = match std::env::var("KAKAROT_ESTIMATE_GAS_BUFFER_PERCENTAGE") {
+            Ok(percentage) => percentage.parse::<u64>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_ESTIMATE_GAS_BUFFER_PERCENTAGE should be a percentage, got {percentage}"
+                ))
+            })?,
+            Err(_) => 0,
+        };
+        Ok(Self { binary_search, buffer_percentage })
+    }
+
+    /// Adds the buffer to the estimate, at most the gas limit.
+    pub fn with_buffer(&self, gas: u64, gas_limit: u64) -> u64 {
+        let buffered = u128::from(gas) * u128::from(100 + self.buffer_percentage) / 100;
+        u64::try_from(buffered).unwrap_or(u64::MAX).min(gas_limit.max(gas))
+    }
+}
+
+/// Converts a Starknet fee into EVM gas at the given gas price, rounding up so that the gas covers
+/// the fee.
+pub fn fee_to_gas(overall_fee: u64, gas_price: u64) -> u64 {
+    if gas_price == 0 {
+        return 0;
+    }
+    overall_fee / gas_price + u64::from(overall_fee % gas_price != 0)
+}
+
+/// Returns the minimal gas limit in `(lo, hi]` for which `succeeds` returns true, assuming it
+/// succeeds at `hi` and that a larger gas limit never fails where a smaller one succeeds.
+pub async fn binary_search_gas<F, Fut, E>(mut lo: u64, mut hi: u64, mut succeeds: F) -> Result<u64, E>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<bool, E>>,
+{
+    while lo + 1 < hi {
+        if ((hi - lo) as f64) / (hi as f64) < BINARY_SEARCH_ERROR_RATIO {
+            break;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if succeeds(mid).await? {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Ok(hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_to_gas() {
+        assert_eq!(3, fee_to_gas(25, 10));
+        assert_eq!(2, fee_to_gas(20, 10));
+        assert_eq!(0, fee_to_gas(20, 0));
+    }
+
+    #[test]
+    fn test_with_buffer() {
+        // Given
+        let config = GasEstimationConfig { binary_search: false, buffer_percentage: 20 };
+
+        // Then
+        assert_eq!(60_000, config.with_buffer(50_000, 1_000_000));
+        assert_eq!(55_000, config.with_buffer(50_000, 55_000));
+        assert_eq!(50_000, GasEstimationConfig::default().with_buffer(50_000, 1_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_binary_search_gas() {
+        // Given
+        let required = 123_456;
+
+        // When
+        let gas =
+            binary_search_gas(20_999, 1_000_000, |gas| async move { Ok::<_, ()>(gas >= required) }).await.unwrap();
+
+        // Then
+        assert!(gas >= required);
+        assert!((gas - required) as f64 / gas as f64 <= BINARY_SEARCH_ERROR_RATIO);
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use reth_primitives::H256;
+use reth_rpc_types::{BlockTransactions, RichBlock};
+use starknet::core::types::{BlockId as StarknetBlockId, BlockTag};
+use starknet::providers::Provider;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use super::api::KakarotEthApi;
+use super::errors::EthApiError;
+use super::reorg::{BlockLink, ChainTracker};
+
+/// Default interval between two polls of the Starknet provider.
+pub const DEFAULT_HEAD_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Maximum number of blocks published after the watcher fell behind, older blocks are skipped.
+const MAX_CATCH_UP_BLOCKS: u64 = 64;
+
+/// Capacity of the broadcast channels, slow receivers skip the oldest items.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Event of the chain tip published to the subscribers of the new heads.
+#[derive(Debug, Clone)]
+pub enum HeadEvent {
+    /// New block of the canonical chain, without its transactions.
+    NewHead(RichBlock),
+    /// The blocks from `from_block` onwards were reverted, the blocks replacing them being
+    /// published next.
+    Reorg { from_block: u64 },
+}
+
+/// Background task polling the Starknet provider and publishing the new blocks and the new
+/// pending transactions to its subscribers.
+///
+/// The task is started by the first subscription and only polls the provider while there are
+/// subscribers, unless the client caches data of the chain tip: the task is then started with
+/// [`HeadWatcher::start`] and reports every new block to the client, which invalidates its caches.
+///
+/// The hashes of the recent blocks are tracked to detect the reorganizations of the chain tip: the
+/// reverted blocks are reported to the client, which drops their data, and to the subscribers
+/// before the blocks replacing them.
+pub struct HeadWatcher<P: Provider + Send + Sync + 'static> {
+    kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    interval: Duration,
+    invalidate_caches: bool,
+    heads: broadcast::Sender<HeadEvent>,
+    pending_transactions: broadcast::Sender<H256>,
+    task: OnceLock<JoinHandle<()>>,
+}
+
+impl<P: Provider + Send + Sync + 'static> HeadWatcher<P> {
+    pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>, interval: Duration) -> Self {
+        let (heads, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let (pending_transactions, _) = broadcast::channel(CHANNEL_CAPACITY);
+        let invalidate_caches = kakarot_client.caches_chain_tip();
+        Self { kakarot_client, interval, invalidate_caches, heads, pending_transactions, task: OnceLock::new() }
+    }
+
+    /// Subscribes to the new blocks, published without their transactions, and to the
+    /// reorganizations of the chain tip.
+    pub fn subscribe_new_heads(&self) -> broadcast::Receiver<HeadEvent> {
+        let receiver = self.heads.subscribe();
+        self.start();
+        receiver
+    }
+
+    /// Subscribes to the hashes of the transactions entering the pending block.
+    pub fn subscribe_pending_transactions(&self) -> broadcast::Receiver<H256> {
+        let receiver = self.pending_transactions.subscribe();
+        self.start();
+        receiver
+    }
+
+    /// Spawns the polling task if it isn't running yet. Must be called from a tokio runtime.
+    pub fn start(&self) {
+        self.task.get_or_init(|| {
+            let poller = Poller {
+                kakarot_client: Arc::clone(&self.kakarot_client),
+                invalidate_caches: self.invalidate_caches,
+                heads: self.heads.clone(),
+                pending_transactions: self.pending_transactions.clone(),
+                last_block: None,
+                chain: ChainTracker::default(),
+                seen_pending_transactions: HashSet::new(),
+            };
+            tokio::spawn(poller.run(self.interval))
+        });
+    }
+}
+
+impl<P: Provider + Send + Sync + 'static> Drop for HeadWatcher<P> {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.get() {
+            task.abort();
+        }
+    }
+}
+
+struct Poller<P: Provider + Send + Sync + 'static> {
+    kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    /// Whether the new blocks are reported to the client, even without subscribers.
+    invalidate_caches: bool,
+    heads: broadcast::Sender<HeadEvent>,
+    pending_transactions: broadcast::Sender<H256>,
+    /// Last block processed.
+    last_block: Option<u64>,
+    /// Hashes of the recent blocks processed, against which the reorganizations are detected.
+    chain: ChainTracker,
+    /// Pending transactions already published.
+    seen_pending_transactions: HashSet<H256>,
+}
+
+impl<P: Provider + Send + Sync + 'static> Poller<P> {
+    async fn run(mut self, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+
+            if self.invalidate_caches || self.heads.receiver_count() > 0 {
+                self.poll_new_heads().await;
+            } else {
+                // Start from the head again once someone subscribes
+                self.last_block = None;
+                self.chain.clear();
+            }
+
+            if self.pending_transactions.receiver_count() > 0 {
+                self.poll_pending_transactions().await;
+            } else {
+                self.seen_pending_transactions.clear();
+            }
+        }
+    }
+
+    async fn poll_new_heads(&mut self) {
+        let current_block = match self.kakarot_client.block_number().await {
+            Ok(block_number) => block_number.as_u64(),
+            Err(err) => {
+                tracing::warn!("Head watcher failed to fetch the block number: {err}");
+                return;
+            }
+        };
+
+        let mut block_number = match self.last_block {
+            // The chain got shorter, the blocks above the current one were reverted
+            Some(last_block) if current_block <= last_block => current_block,
+            Some(last_block) => (last_block + 1).max(current_block.saturating_sub(MAX_CATCH_UP_BLOCKS - 1)),
+            None => current_block,
+        };
+
+        while block_number <= current_block {
+            let link = match self.kakarot_client.block_link(block_number).await {
+                Ok(Some(link)) => link,
+                Ok(None) => return,
+                Err(err) => {
+                    tracing::warn!("Head watcher failed to fetch block {block_number}: {err}");
+                    return;
+                }
+            };
+
+            match self.reverted_block(link).await {
+                Ok(Some(from_block)) => {
+                    self.handle_reorg(from_block);
+                    block_number = from_block;
+                    continue;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    tracing::warn!("Head watcher failed to check block {block_number} for a reorganization: {err}");
+                    return;
+                }
+            }
+
+            // The block was already processed, only checked for a reorganization
+            if self.last_block.is_some_and(|last_block| block_number <= last_block) {
+                block_number += 1;
+                continue;
+            }
+
+            // Invalidate the caches before fetching the block, which might be a stale cached one
+            if self.invalidate_caches {
+                if let Err(err) = self.kakarot_client.handle_new_head(block_number).await {
+                    tracing::warn!("Head watcher failed to invalidate the caches at block {block_number}: {err}");
+                }
+            }
+
+            if self.heads.receiver_count() > 0 {
+                let block = self
+                    .kakarot_client
+                    .get_eth_block_from_starknet_block(StarknetBlockId::Number(block_number), false)
+                    .await;
+                match block {
+                    // Sending only fails when all receivers are gone, which is checked on the next tick
+                    Ok(block) => {
+                        let _ = self.heads.send(HeadEvent::NewHead(block));
+                    }
+                    Err(err) => {
+                        tracing::warn!("Head watcher failed to fetch block {block_number}: {err}");
+                        return;
+                    }
+                }
+            }
+
+            self.chain.record(&link);
+            self.last_block = Some(block_number);
+            block_number += 1;
+        }
+    }
+
+    /// Returns the number of the first tracked block reverted according to the block, walking back
+    /// its ancestors until one of them is the tracked block at its height.
+    async fn reverted_block(&self, link: BlockLink) -> Result<Option<u64>, EthApiError<P::Error>> {
+        let mut reverted = self.chain.reverted_at(&link);
+        let mut child = link;
+        while let Some(parent_number) = self.chain.reverted_parent(&child) {
+            reverted = Some(parent_number);
+            child = match self.kakarot_client.block_link(parent_number).await? {
+                Some(parent) => parent,
+                None => break,
+            };
+        }
+        Ok(reverted)
+    }
+
+    /// Drops the reverted blocks and reports them to the client and the subscribers.
+    fn handle_reorg(&mut self, from_block: u64) {
+        tracing::info!("Chain reorganized from block {from_block}, rolling back the blocks processed since");
+        self.chain.rollback(from_block);
+        self.last_block = from_block.checked_sub(1);
+        self.kakarot_client.handle_reorg(from_block);
+        let _ = self.heads.send(HeadEvent::Reorg { from_block });
+    }
+
+    async fn poll_pending_transactions(&mut self) {
+        let block =
+            self.kakarot_client.get_eth_block_from_starknet_block(StarknetBlockId::Tag(BlockTag::Pending), false).await;
+        let pending: HashSet<H256> = match block {
+            Ok(block) => match block.inner.transactions {
+                BlockTransactions::Hashes(hashes) => hashes.into_iter().collect(),
+                BlockTransactions::Full(transactions) => transactions.into_iter().map(|tx| tx.hash).collect(),
+            },
+            Err(err) => {
+                tracing::warn!("Head watcher failed to fetch the pending block: {err}");
+                return;
+            }
+        };
+
+        for hash in pending.difference(&self.seen_pending_transactions) {
+            let _ = self.pending_transactions.send(*hash);
+        }
+        self.seen_pending_transactions = pending;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::U256;
+    use starknet::providers::jsonrpc::JsonRpcClient;
+
+    use super::*;
+    use crate::client::config::{Network, StarknetConfig};
+    use crate::client::KakarotClient;
+    use crate::mock::constants::{KAKAROT_ADDRESS, PROXY_ACCOUNT_CLASS_HASH};
+    use crate::mock::mock_starknet::ChainMockTransport;
+
+    fn hash_of(event: HeadEvent) -> Option<H256> {
+        match event {
+            HeadEvent::NewHead(block) => block.header.hash,
+            HeadEvent::Reorg { .. } => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poller_publishes_reorg_before_replacement_heads() {
+        // Given
+        let transport = ChainMockTransport::default();
+        let hashes = Arc::clone(&transport.hashes);
+        *hashes.lock().unwrap() = vec![0x10, 0x11, 0x12];
+        let config = StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH);
+        let client: Arc<dyn KakarotEthApi<JsonRpcClient<ChainMockTransport>>> =
+            Arc::new(KakarotClient::new(config, JsonRpcClient::new(transport)));
+        let (heads, mut receiver) = broadcast::channel(CHANNEL_CAPACITY);
+        let mut poller = Poller {
+            kakarot_client: client,
+            invalidate_caches: false,
+            heads,
+            pending_transactions: broadcast::channel(CHANNEL_CAPACITY).0,
+            last_block: None,
+            chain: ChainTracker::default(),
+            seen_pending_transactions: HashSet::new(),
+        };
+        let hash = |hash: u64| Some(H256::from_low_u64_be(hash));
+
+        // When
+        poller.poll_new_heads().await;
+        hashes.lock().unwrap().push(0x13);
+        poller.poll_new_heads().await;
+        // Block 3 is replaced, and block 4 built on top of its replacement
+        hashes.lock().unwrap().splice(3.., [0x23, 0x24]);
+        poller.poll_new_heads().await;
+
+        // Then
+        assert_eq!(hash(0x12), hash_of(receiver.try_recv().unwrap()));
+        assert_eq!(hash(0x13), hash_of(receiver.try_recv().unwrap()));
+        assert!(matches!(receiver.try_recv().unwrap(), HeadEvent::Reorg { from_block: 3 }));
+        let replacement = receiver.try_recv().unwrap();
+        assert!(matches!(&replacement, HeadEvent::NewHead(block) if block.header.number == Some(U256::from(3))));
+        assert_eq!(hash(0x23), hash_of(replacement));
+        assert_eq!(hash(0x24), hash_of(receiver.try_recv().unwrap()));
+        assert!(receiver.try_recv().is_err());
+    }
+}
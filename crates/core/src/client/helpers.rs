@@ -1,15 +1,19 @@
 use eyre::Result;
-use reth_primitives::{Bloom, Bytes, H160, U128, U256};
+use reth_primitives::{Address, Bloom, Bytes, H160};
 use reth_rlp::DecodeError;
 use reth_rpc_types::TransactionReceipt;
 use starknet::core::types::{
-    FieldElement, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs, ValueOutOfRangeError,
+    BlockId as StarknetBlockId, BlockTag, FieldElement, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs,
+    ValueOutOfRangeError,
 };
+use starknet::core::utils::get_contract_address;
 use thiserror::Error;
 
 use super::constants::{CUMULATIVE_GAS_USED, EFFECTIVE_GAS_PRICE, GAS_USED, TRANSACTION_TYPE};
 use crate::client::constants::selectors::ETH_SEND_TRANSACTION;
 use crate::client::errors::EthApiError;
+use crate::models::conversions::felts_to_bytes;
+use crate::models::felt::Felt252Wrapper;
 use crate::models::ConversionError;
 
 #[derive(Debug, Error)]
@@ -20,6 +24,10 @@ pub enum DataDecodingError {
     TransactionDecodingError(#[from] DecodeError),
     #[error("{entrypoint} returned invalid array length, expected {expected}, got {actual}")]
     InvalidReturnArrayLength { entrypoint: String, expected: usize, actual: usize },
+    #[error("failed to decode the return data of {entrypoint}: {message}")]
+    InvalidReturnData { entrypoint: String, message: String },
+    #[error("failed to decode the {event} event: {message}")]
+    InvalidEventData { event: String, message: String },
 }
 
 #[derive(Debug)]
@@ -44,9 +52,27 @@ pub fn decode_eth_call_return<T: std::error::Error>(
 ) -> Result<Vec<FieldElement>, EthApiError<T>> {
     // Parse and decode Kakarot's return data (temporary solution and not scalable - will
     // fail is Kakarot API changes)
+    decode_felt_array("eth_call or eth_send_transaction", call_result)
+}
 
+/// Returns the EVM bytecode returned by the `bytecode` entrypoint of a Kakarot account, an array
+/// of felts holding one byte each, prefixed with its length. An empty result, returned for an
+/// undeployed account, is an empty bytecode.
+pub fn decode_bytecode<T: std::error::Error>(call_result: &[FieldElement]) -> Result<Bytes, EthApiError<T>> {
+    if call_result.is_empty() {
+        return Ok(Bytes::default());
+    }
+    Ok(felts_to_bytes(&decode_felt_array("bytecode", call_result)?)?)
+}
+
+/// Returns the felts of an array returned by an entrypoint, checking them against the length
+/// prefixing the array.
+fn decode_felt_array<T: std::error::Error>(
+    entrypoint: &str,
+    call_result: &[FieldElement],
+) -> Result<Vec<FieldElement>, EthApiError<T>> {
     let return_data_len = *call_result.first().ok_or_else(|| DataDecodingError::InvalidReturnArrayLength {
-        entrypoint: "eth_call or eth_send_transaction".into(),
+        entrypoint: entrypoint.into(),
         expected: 1,
         actual: 0,
     })?;
@@ -54,14 +80,14 @@ pub fn decode_eth_call_return<T: std::error::Error>(
         return_data_len.try_into().map_err(|e: ValueOutOfRangeError| ConversionError::<()>::Other(e.to_string()))?;
 
     let return_data = call_result.get(1..).ok_or_else(|| DataDecodingError::InvalidReturnArrayLength {
-        entrypoint: "eth_call or eth_send_transaction".into(),
+        entrypoint: entrypoint.into(),
         expected: 2,
         actual: 1,
     })?;
 
     if return_data.len() != return_data_len as usize {
         return Err(DataDecodingError::InvalidReturnArrayLength {
-            entrypoint: "eth_call or eth_send_transaction".into(),
+            entrypoint: entrypoint.into(),
             expected: return_data_len as usize,
             actual: return_data.len(),
         }
@@ -71,12 +97,6 @@ pub fn decode_eth_call_return<T: std::error::Error>(
     Ok(return_data.to_vec())
 }
 
-#[must_use]
-pub fn vec_felt_to_bytes(vec_felt: Vec<FieldElement>) -> Bytes {
-    let bytes: Vec<u8> = vec_felt.into_iter().filter_map(|x: FieldElement| u8::try_from(x).ok()).collect();
-    Bytes::from(bytes)
-}
-
 #[must_use]
 pub fn create_default_transaction_receipt() -> TransactionReceipt {
     TransactionReceipt {
@@ -104,10 +124,6 @@ pub fn create_default_transaction_receipt() -> TransactionReceipt {
     }
 }
 
-pub fn bytes_to_felt_vec(bytes: &Bytes) -> Vec<FieldElement> {
-    bytes.to_vec().into_iter().map(FieldElement::from).collect()
-}
-
 /// Constructs the calldata for a raw Starknet invoke transaction call
 pub fn raw_kakarot_calldata(kakarot_address: FieldElement, mut calldata: Vec<FieldElement>) -> Vec<FieldElement> {
     let mut execute_calldata: Vec<FieldElement> = vec![
@@ -123,86 +139,25 @@ pub fn raw_kakarot_calldata(kakarot_address: FieldElement, mut calldata: Vec<Fie
     execute_calldata
 }
 
-/// Helper function to split a U256 value into two FieldElements.
-pub fn split_u256_into_field_elements(value: U256) -> [FieldElement; 2] {
-    let low = value & U256::from(U128::MAX);
-    let high = value >> 128;
-    [
-        FieldElement::from_bytes_be(&low.to_be_bytes()).unwrap(), // Safe unwrap <= U128::MAX.
-        FieldElement::from_bytes_be(&high.to_be_bytes()).unwrap(), // Safe unwrap <= U128::MAX.
-    ]
-}
-
-#[cfg(test)]
-mod tests {
-
-    use rstest::*;
-
-    use super::*;
-
-    #[test]
-    fn test_bytes_to_felt_vec() {
-        let bytes = Bytes::from(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
-        let felt_vec = bytes_to_felt_vec(&bytes);
-        assert_eq!(felt_vec.len(), 10);
-        assert_eq!(
-            felt_vec,
-            vec![
-                FieldElement::from(1_u64),
-                FieldElement::from(2_u64),
-                FieldElement::from(3_u64),
-                FieldElement::from(4_u64),
-                FieldElement::from(5_u64),
-                FieldElement::from(6_u64),
-                FieldElement::from(7_u64),
-                FieldElement::from(8_u64),
-                FieldElement::from(9_u64),
-                FieldElement::from(10_u64)
-            ]
-        );
-    }
-
-    #[test]
-    fn test_vec_felt_to_bytes() {
-        // Given
-        let bytecode: Vec<FieldElement> =
-            serde_json::from_str(include_str!("../models/test_data/bytecode/starknet/counter.json")).unwrap();
-
-        // When
-        let bytes = vec_felt_to_bytes(bytecode);
-
-        // Then
-        let expected: Bytes =
-            serde_json::from_str(include_str!("../models/test_data/bytecode/eth/counter.json")).unwrap();
-        assert_eq!(expected, bytes);
+/// Returns the query parameter selecting the block in the requests to the feeder gateway, e.g.
+/// `blockNumber=pending` or `blockHash=0x...`.
+pub fn gateway_block_param(block_id: &StarknetBlockId) -> (&'static str, String) {
+    match block_id {
+        StarknetBlockId::Number(number) => ("blockNumber", number.to_string()),
+        StarknetBlockId::Tag(BlockTag::Latest) => ("blockNumber", "latest".to_string()),
+        StarknetBlockId::Tag(BlockTag::Pending) => ("blockNumber", "pending".to_string()),
+        StarknetBlockId::Hash(hash) => ("blockHash", format!("{hash:#x}")),
     }
+}
 
-    #[rstest]
-    #[test]
-    #[case(
-        "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
-        "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"
-    )]
-    #[case(
-        "0x0000000000000000000000000000000000000000000000000000000000000000",
-        "0x0000000000000000000000000000000000000000000000000000000000000000"
-    )]
-    #[case(
-        "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
-        "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"
-    )]
-    fn test_split_u256_into_field_elements(#[case] input: U256, #[case] expected: U256) {
-        // When
-        let result = split_u256_into_field_elements(input);
-
-        // Then
-        // Recalculate the U256 values using the resulting FieldElements
-        // The first is the low 128 bits of the U256 value
-        // The second is the high 128 bits of the U256 value and is left shifted by 128 bits
-        let result: U256 =
-            U256::from_be_bytes(result[1].to_bytes_be()) << 128 | U256::from_be_bytes(result[0].to_bytes_be());
-
-        // Assert that the expected and recombined U256 values are equal
-        assert_eq!(expected, result);
-    }
+/// Computes the Starknet address of the Kakarot account of an EVM address, without calling
+/// Kakarot. Accounts are proxies deployed by Kakarot, salted with the EVM address and without
+/// constructor calldata.
+pub fn compute_starknet_address(
+    kakarot_address: FieldElement,
+    proxy_account_class_hash: FieldElement,
+    ethereum_address: Address,
+) -> FieldElement {
+    let salt: Felt252Wrapper = ethereum_address.into();
+    get_contract_address(salt.into(), proxy_account_class_hash, &[], kakarot_address)
 }
@@ -0,0 +1,232 @@
+//! Limit on the number of concurrent requests to the Starknet node.
+//!
+//! [`LimitedTransport`] holds a permit of a [`ConcurrencyLimiter`] shared by all the requests of
+//! the provider for the duration of each request, so that a busy RPC doesn't overwhelm a smaller
+//! Starknet node. The requests above the limit wait for a permit, and the limiter records how
+//! long they waited, so that operators can see when the limit becomes the bottleneck.
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use starknet::providers::jsonrpc::{JsonRpcMethod, JsonRpcResponse, JsonRpcTransport};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use super::errors::ConfigError;
+
+/// Time spent by the requests waiting for a permit.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QueueStats {
+    /// Requests that got a permit.
+    pub requests: u64,
+    /// Requests that had to wait for a permit because the limit was reached.
+    pub queued_requests: u64,
+    /// Total time spent waiting for a permit.
+    pub wait_duration: Duration,
+    /// Longest time spent waiting for a permit.
+    pub max_wait_duration: Duration,
+}
+
+/// Semaphore bounding the number of concurrent requests to the Starknet node.
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    semaphore: Semaphore,
+    max_concurrent_requests: usize,
+    /// Requests currently waiting for a permit.
+    waiting: AtomicU64,
+    stats: Mutex<QueueStats>,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(max_concurrent_requests: usize) -> Self {
+        Self {
+            semaphore: Semaphore::new(max_concurrent_requests),
+            max_concurrent_requests,
+            waiting: AtomicU64::new(0),
+            stats: Mutex::new(QueueStats::default()),
+        }
+    }
+
+    /// Create a new `ConcurrencyLimiter` from the `STARKNET_RPC_MAX_CONCURRENT_REQUESTS`
+    /// environment variable. Returns `None` if it is not set, which leaves the requests
+    /// unbounded.
+    pub fn from_env() -> Result<Option<Arc<Self>>, ConfigError> {
+        let Ok(max_concurrent_requests) = std::env::var("STARKNET_RPC_MAX_CONCURRENT_REQUESTS") else {
+            return Ok(None);
+        };
+        match max_concurrent_requests.parse::<usize>() {
+            Ok(max_concurrent_requests) if max_concurrent_requests > 0 => {
+                Ok(Some(Arc::new(Self::new(max_concurrent_requests))))
+            }
+            _ => Err(ConfigError::EnvironmentVariableSetWrong(format!(
+                "STARKNET_RPC_MAX_CONCURRENT_REQUESTS should be a positive integer, got {max_concurrent_requests}"
+            ))),
+        }
+    }
+
+    pub fn max_concurrent_requests(&self) -> usize {
+        self.max_concurrent_requests
+    }
+
+    /// Returns the number of requests currently sent to the Starknet node.
+    pub fn in_flight(&self) -> usize {
+        self.max_concurrent_requests - self.semaphore.available_permits()
+    }
+
+    /// Returns the number of requests currently waiting for a permit.
+    pub fn waiting(&self) -> u64 {
+        self.waiting.load(Ordering::Relaxed)
+    }
+
+    pub fn stats(&self) -> QueueStats {
+        *self.stats.lock().expect("poisoned lock")
+    }
+
+    /// Waits for a permit to send a request, released when dropped.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        let started_at = Instant::now();
+        let (permit, queued) = match self.semaphore.try_acquire() {
+            Ok(permit) => (permit, false),
+            Err(_) => {
+                self.waiting.fetch_add(1, Ordering::Relaxed);
+                // The semaphore is never closed
+                let permit = self.semaphore.acquire().await.expect("limiter semaphore closed");
+                self.waiting.fetch_sub(1, Ordering::Relaxed);
+                (permit, true)
+            }
+        };
+
+        let waited = started_at.elapsed();
+        let mut stats = self.stats.lock().expect("poisoned lock");
+        stats.requests += 1;
+        stats.queued_requests += u64::from(queued);
+        stats.wait_duration += waited;
+        stats.max_wait_duration = stats.max_wait_duration.max(waited);
+        permit
+    }
+
+    /// Encodes the state of the limiter in the Prometheus text format.
+    pub fn encode_prometheus(&self) -> String {
+        let stats = self.stats();
+        let metrics: [(&str, &str, &str, String); 6] = [
+            (
+                "kakarot_starknet_max_concurrent_requests",
+                "gauge",
+                "Maximum number of concurrent requests to the Starknet node.",
+                self.max_concurrent_requests.to_string(),
+            ),
+            (
+                "kakarot_starknet_in_flight_requests",
+                "gauge",
+                "Requests currently sent to the Starknet node.",
+                self.in_flight().to_string(),
+            ),
+            (
+                "kakarot_starknet_waiting_requests",
+                "gauge",
+                "Requests currently waiting for the concurrency limit.",
+                self.waiting().to_string(),
+            ),
+            (
+                "kakarot_starknet_queued_requests_total",
+                "counter",
+                "Requests that waited for the concurrency limit.",
+                stats.queued_requests.to_string(),
+            ),
+            (
+                "kakarot_starknet_queue_wait_seconds_sum",
+                "counter",
+                "Total time spent waiting for the concurrency limit.",
+                stats.wait_duration.as_secs_f64().to_string(),
+            ),
+            (
+                "kakarot_starknet_queue_wait_seconds_max",
+                "gauge",
+                "Longest time spent waiting for the concurrency limit.",
+                stats.max_wait_duration.as_secs_f64().to_string(),
+            ),
+        ];
+
+        // Writing to a string never fails
+        let mut encoded = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(encoded, "# HELP {name} {help}");
+            let _ = writeln!(encoded, "# TYPE {name} {kind}");
+            let _ = writeln!(encoded, "{name} {value}");
+        }
+        encoded
+    }
+}
+
+/// JSON-RPC transport bounding the number of concurrent requests of the inner transport. The
+/// transport is a no-op when built without a limiter.
+#[derive(Debug)]
+pub struct LimitedTransport<T> {
+    inner: T,
+    limiter: Option<Arc<ConcurrencyLimiter>>,
+}
+
+impl<T> LimitedTransport<T> {
+    pub fn new(inner: T, limiter: Option<Arc<ConcurrencyLimiter>>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcTransport + Send + Sync> JsonRpcTransport for LimitedTransport<T> {
+    type Error = T::Error;
+
+    async fn send_request<P, R>(&self, method: JsonRpcMethod, params: P) -> Result<JsonRpcResponse<R>, Self::Error>
+    where
+        P: Serialize + Send,
+        R: DeserializeOwned,
+    {
+        let _permit = match &self.limiter {
+            Some(limiter) => Some(limiter.acquire().await),
+            None => None,
+        };
+        self.inner.send_request(method, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limiter_queues_requests_above_the_limit() {
+        // Given
+        let limiter = Arc::new(ConcurrencyLimiter::new(1));
+        let permit = limiter.acquire().await;
+
+        // When
+        let queued = tokio::spawn({
+            let limiter = Arc::clone(&limiter);
+            async move {
+                let _permit = limiter.acquire().await;
+            }
+        });
+        while limiter.waiting() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // Then
+        assert_eq!(1, limiter.in_flight());
+
+        // When
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(permit);
+        queued.await.unwrap();
+
+        // Then
+        let stats = limiter.stats();
+        assert_eq!(2, stats.requests);
+        assert_eq!(1, stats.queued_requests);
+        assert!(stats.max_wait_duration >= Duration::from_millis(10));
+        assert_eq!(0, limiter.in_flight());
+        assert!(limiter.encode_prometheus().contains("kakarot_starknet_queued_requests_total 1\n"));
+    }
+}
@@ -0,0 +1,223 @@
+//! Optional local index of the Kakarot logs, enabled with `KAKAROT_LOG_INDEX`.
+//!
+//! The index is filled by block ranges, e.g. by a backfill of the historical blocks, and keeps
+//! track of the blocks it covers: a `eth_getLogs` query whose block range is entirely indexed is
+//! served from the index instead of the Starknet events. The logs are kept in the [`Storage`] of
+//! the client, the numbers of the indexed blocks being loaded in memory on start.
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+
+use reth_rpc_types::{Filter, Log, ValueOrArray};
+
+use crate::storage::{prefixed_key, Entry, MemoryStorage, Storage, StorageError};
+
+/// Key prefix of the logs of the indexed blocks in the storage, followed by the block number.
+const LOGS_PREFIX: &[u8] = b"logs/";
+
+fn block_key(block_number: u64) -> Vec<u8> {
+    prefixed_key(LOGS_PREFIX, &block_number.to_be_bytes())
+}
+
+/// Logs of the indexed blocks, stored by block number. An indexed block without logs has an empty
+/// entry.
+#[derive(Debug)]
+pub struct LogIndex {
+    blocks: RwLock<BTreeSet<u64>>,
+    storage: Arc<dyn Storage>,
+}
+
+impl Default for LogIndex {
+    fn default() -> Self {
+        Self { blocks: RwLock::default(), storage: Arc::new(MemoryStorage::default()) }
+    }
+}
+
+impl LogIndex {
+    /// Create a new `LogIndex` stored in the storage, covering the blocks already stored.
+    pub fn with_storage(storage: Arc<dyn Storage>) -> Result<Self, StorageError> {
+        let blocks = storage
+            .scan_prefix(LOGS_PREFIX)?
+            .into_iter()
+            .map(|(key, _)| {
+                let block_number: [u8; 8] = key[LOGS_PREFIX.len()..]
+                    .try_into()
+                    .map_err(|_| StorageError::new("invalid stored block number"))?;
+                Ok(u64::from_be_bytes(block_number))
+            })
+            .collect::<Result<_, StorageError>>()?;
+        Ok(Self { blocks: RwLock::new(blocks), storage })
+    }
+
+    /// Indexes the logs of the inclusive block range, which must be all the logs of the range.
+    /// The logs outside of the range are ignored.
+    pub fn insert(&self, from_block: u64, to_block: u64, logs: Vec<Log>) -> Result<(), StorageError> {
+        let mut range_logs: Vec<Vec<Log>> = (from_block..=to_block).map(|_| vec![]).collect();
+        for log in logs {
+            let block_number = log.block_number.and_then(|block_number| u64::try_from(block_number).ok());
+            if let Some(block_logs) = block_number
+                .filter(|block_number| (from_block..=to_block).contains(block_number))
+                .and_then(|block_number| range_logs.get_mut((block_number - from_block) as usize))
+            {
+                block_logs.push(log);
+            }
+        }
+        let entries = (from_block..=to_block)
+            .zip(range_logs)
+            .map(|(block_number, block_logs)| {
+                Ok((block_key(block_number), serde_json::to_vec(&block_logs).map_err(StorageError::new)?))
+            })
+            .collect::<Result<Vec<Entry>, StorageError>>()?;
+        self.storage.put_batch(&entries)?;
+        self.blocks.write().expect("log index poisoned").extend(from_block..=to_block);
+        Ok(())
+    }
+
+    /// Drops the logs of the blocks from `from_block` onwards, reverted by a reorganization of the
+    /// chain. The blocks aren't covered anymore until they are indexed again.
+    pub fn rollback(&self, from_block: u64) -> Result<(), StorageError> {
+        let mut blocks = self.blocks.write().expect("log index poisoned");
+        for block_number in blocks.split_off(&from_block) {
+            self.storage.delete(&block_key(block_number))?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if all the blocks of the inclusive range are indexed.
+    pub fn covers(&self, from_block: u64, to_block: u64) -> bool {
+        if to_block < from_block {
+            return false;
+        }
+        let blocks = self.blocks.read().expect("log index poisoned");
+        blocks.range(from_block..=to_block).count() as u64 == to_block - from_block + 1
+    }
+
+    /// Returns the indexed logs of the inclusive block range matching the address and the topics of
+    /// the filter, in block order.
+    pub fn logs(&self, filter: &Filter, from_block: u64, to_block: u64) -> Result<Vec<Log>, StorageError> {
+        let block_numbers: Vec<u64> =
+            self.blocks.read().expect("log index poisoned").range(from_block..=to_block).copied().collect();
+        let mut logs = vec![];
+        for block_number in block_numbers {
+            let Some(block_logs) = self.storage.get(&block_key(block_number))? else {
+                continue;
+            };
+            let block_logs: Vec<Log> = serde_json::from_slice(&block_logs).map_err(StorageError::new)?;
+            logs.extend(block_logs.into_iter().filter(|log| log_matches(filter, log)));
+        }
+        Ok(logs)
+    }
+
+    /// Returns the number of indexed blocks.
+    pub fn indexed_blocks(&self) -> usize {
+        self.blocks.read().expect("log index poisoned").len()
+    }
+}
+
+/// Returns true if the log matches the address and the topics of the filter. An empty list of
+/// addresses or topics, like a missing one, matches any log.
+pub fn log_matches(filter: &Filter, log: &Log) -> bool {
+    let address_matches = match &filter.address {
+        None => true,
+        Some(ValueOrArray::Value(address)) => *address == log.address,
+        Some(ValueOrArray::Array(addresses)) => addresses.is_empty() || addresses.contains(&log.address),
+    };
+
+    address_matches
+        && filter.topics.iter().enumerate().all(|(position, topic)| {
+            let log_topic = log.topics.get(position);
+            match topic {
+                None | Some(ValueOrArray::Value(None)) => true,
+                Some(ValueOrArray::Value(Some(topic))) => log_topic == Some(topic),
+                Some(ValueOrArray::Array(topics)) => {
+                    topics.is_empty() || topics.iter().any(|topic| topic.is_none() || log_topic == topic.as_ref())
+                }
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Address, Bytes, H256, U256};
+
+    use super::*;
+
+    fn log(block_number: u64, address: Address, topics: Vec<H256>) -> Log {
+        Log {
+            address,
+            topics,
+            data: Bytes::default(),
+            block_hash: None,
+            block_number: Some(U256::from(block_number)),
+            transaction_hash: None,
+            transaction_index: None,
+            log_index: None,
+            removed: false,
+        }
+    }
+
+    #[test]
+    fn test_log_index_covers_inserted_ranges() {
+        // Given
+        let index = LogIndex::default();
+
+        // When
+        index.insert(10, 19, vec![log(12, Address::zero(), vec![]), log(30, Address::zero(), vec![])]).unwrap();
+        index.insert(20, 24, vec![]).unwrap();
+
+        // Then
+        assert!(index.covers(10, 24));
+        assert!(index.covers(15, 15));
+        assert!(!index.covers(9, 24));
+        assert!(!index.covers(20, 25));
+        assert_eq!(15, index.indexed_blocks());
+        assert_eq!(1, index.logs(&Filter::default(), 10, 24).unwrap().len());
+    }
+
+    #[test]
+    fn test_log_index_is_reloaded_from_storage() {
+        // Given
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let index = LogIndex::with_storage(Arc::clone(&storage)).unwrap();
+        index.insert(10, 12, vec![log(11, Address::zero(), vec![])]).unwrap();
+
+        // When
+        let reloaded = LogIndex::with_storage(storage).unwrap();
+
+        // Then
+        assert!(reloaded.covers(10, 12));
+        assert_eq!(3, reloaded.indexed_blocks());
+        assert_eq!(vec![log(11, Address::zero(), vec![])], reloaded.logs(&Filter::default(), 10, 12).unwrap());
+    }
+
+    #[test]
+    fn test_log_index_rollback() {
+        // Given
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let index = LogIndex::with_storage(Arc::clone(&storage)).unwrap();
+        index.insert(10, 19, vec![log(12, Address::zero(), vec![]), log(16, Address::zero(), vec![])]).unwrap();
+
+        // When
+        index.rollback(15).unwrap();
+
+        // Then
+        assert!(index.covers(10, 14));
+        assert!(!index.covers(10, 15));
+        assert_eq!(1, index.logs(&Filter::default(), 10, 19).unwrap().len());
+        assert_eq!(5, LogIndex::with_storage(storage).unwrap().indexed_blocks());
+    }
+
+    #[test]
+    fn test_log_matches_filter() {
+        // Given
+        let address = Address::from_low_u64_be(1);
+        let topic = H256::from_low_u64_be(2);
+        let log = log(1, address, vec![topic]);
+
+        // Then
+        assert!(log_matches(&Filter::default(), &log));
+        assert!(log_matches(&Filter::default().address(address).topic0(topic), &log));
+        assert!(!log_matches(&Filter::default().address(Address::from_low_u64_be(3)), &log));
+        assert!(!log_matches(&Filter::default().topic0(H256::from_low_u64_be(3)), &log));
+        assert!(!log_matches(&Filter::default().topic1(topic), &log));
+    }
+}
@@ -0,0 +1,84 @@
+//! Limits of the `eth_getLogs` queries.
+//!
+//! The block range of a query is split into chunks, queried concurrently against the Starknet
+//! events and merged back in block order. Each chunk is paginated with the continuation tokens of
+//! Starknet, and the query fails as soon as it returns more logs than the configured maximum.
+use super::errors::ConfigError;
+
+/// Default number of blocks queried at once for the events of a `eth_getLogs` query.
+pub const DEFAULT_LOGS_BLOCK_RANGE_CHUNK_SIZE: u64 = 1000;
+
+/// Default maximum number of logs returned by a `eth_getLogs` query, as in geth.
+pub const DEFAULT_LOGS_MAX_RESULTS: usize = 10_000;
+
+/// Number of chunks of a block range queried concurrently.
+pub const LOGS_CHUNK_CONCURRENCY: usize = 4;
+
+/// Configuration of the `eth_getLogs` queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogsConfig {
+    /// Number of blocks queried at once.
+    pub block_range_chunk_size: u64,
+    /// Maximum number of logs returned by a query, beyond which the query fails.
+    pub max_results: usize,
+}
+
+impl Default for LogsConfig {
+    fn default() -> Self {
+        Self { block_range_chunk_size: DEFAULT_LOGS_BLOCK_RANGE_CHUNK_SIZE, max_results: DEFAULT_LOGS_MAX_RESULTS }
+    }
+}
+
+impl LogsConfig {
+    /// Create a new `LogsConfig` from environment variables, falling back to the defaults when
+    /// `KAKAROT_LOGS_BLOCK_RANGE_CHUNK_SIZE` or `KAKAROT_LOGS_MAX_RESULTS` are not set.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let block_range_chunk_size =
+            positive_env_var("KAKAROT_LOGS_BLOCK_RANGE_CHUNK_SIZE")?.unwrap_or(DEFAULT_LOGS_BLOCK_RANGE_CHUNK_SIZE);
+        let max_results = positive_env_var("KAKAROT_LOGS_MAX_RESULTS")?.unwrap_or(DEFAULT_LOGS_MAX_RESULTS);
+        Ok(Self { block_range_chunk_size, max_results })
+    }
+}
+
+fn positive_env_var<T: std::str::FromStr + Default + PartialOrd>(name: &str) -> Result<Option<T>, ConfigError> {
+    match std::env::var(name) {
+        Ok(value) => value.parse::<T>().ok().filter(|value| *value > T::default()).map(Some).ok_or_else(|| {
+            ConfigError::EnvironmentVariableSetWrong(format!(
+                "{name} should be a strictly positive integer, got {value}"
+            ))
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Splits the inclusive block range into consecutive inclusive chunks of at most `chunk_size`
+/// blocks.
+pub fn block_range_chunks(from_block: u64, to_block: u64, chunk_size: u64) -> Vec<(u64, u64)> {
+    // A zero chunk size would never cover the range
+    let chunk_size = chunk_size.max(1);
+    let mut chunks = vec![];
+    let mut start = from_block;
+    while start <= to_block {
+        let end = start.saturating_add(chunk_size - 1).min(to_block);
+        chunks.push((start, end));
+        match end.checked_add(1) {
+            Some(next) => start = next,
+            None => break,
+        }
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_range_chunks() {
+        assert_eq!(vec![(0, 9), (10, 19), (20, 25)], block_range_chunks(0, 25, 10));
+        assert_eq!(vec![(5, 5)], block_range_chunks(5, 5, 10));
+        assert_eq!(vec![(1, 1), (2, 2)], block_range_chunks(1, 2, 0));
+        assert!(block_range_chunks(3, 2, 10).is_empty());
+        assert_eq!(vec![(u64::MAX, u64::MAX)], block_range_chunks(u64::MAX, u64::MAX, 10));
+    }
+}
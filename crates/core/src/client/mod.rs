@@ -1,135 +1,565 @@
 pub mod api;
+pub mod cache;
+pub mod chain_spec;
+pub mod cold_start;
 pub mod config;
 pub mod constants;
 pub mod errors;
+pub mod fallback;
+pub mod filter;
+pub mod gas_estimation;
+pub mod head_watcher;
 pub mod helpers;
+pub mod limiter;
+pub mod log_index;
+pub mod logs;
+pub mod pending_transactions;
+pub mod relayed;
+pub mod relayer;
+pub mod reorg;
+pub mod sender_policy;
+pub mod signer;
 #[cfg(test)]
 pub mod tests;
+pub mod transaction_index;
+pub mod validation;
+pub mod warmup;
 
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use async_trait::async_trait;
+use bytes::BytesMut;
+use ethers::abi::{AbiDecode, AbiEncode};
+use ethers::types::transaction::eip712::TypedData;
 use eyre::Result;
-use futures::future::join_all;
+use futures::future::{join_all, try_join_all};
+use futures::stream::{self, StreamExt, TryStreamExt};
 use reqwest::Client;
 use reth_primitives::{
     AccessList, Address, BlockId, BlockNumberOrTag, Bloom, Bytes, Signature, Transaction, TransactionKind,
-    TransactionSigned, TxEip1559, H256, U128, U256, U64, U8,
+    TransactionSigned, TxEip1559, H256, U128, U256, U64,
 };
-use reth_rlp::Decodable;
 use reth_rpc_types::{
-    BlockTransactions, CallRequest, FeeHistory, Filter, Index, Log, RichBlock, SyncInfo, SyncStatus,
-    Transaction as EtherTransaction, TransactionReceipt,
+    BlockTransactions, CallRequest, FeeHistory, Filter, FilterBlockOption, FilterChanges, Index, Log, RichBlock,
+    SyncInfo, SyncStatus, Transaction as EtherTransaction, TransactionReceipt,
 };
+use starknet::accounts::Call;
 use starknet::core::types::{
-    BlockId as StarknetBlockId, BlockTag, BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1, EmittedEvent,
-    Event, EventFilterWithPage, EventsPage, FieldElement, InvokeTransactionReceipt, MaybePendingBlockWithTxHashes,
-    MaybePendingBlockWithTxs, MaybePendingTransactionReceipt, ResultPageRequest, StarknetError, SyncStatusType,
-    Transaction as TransactionType, TransactionReceipt as StarknetTransactionReceipt,
-    TransactionStatus as StarknetTransactionStatus,
+    BlockId as StarknetBlockId, BlockStatus, BlockTag, BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1,
+    EmittedEvent, Event, EventFilter, EventFilterWithPage, EventsPage, FieldElement, MaybePendingBlockWithTxHashes,
+    MaybePendingBlockWithTxs, MaybePendingStateUpdate, MaybePendingTransactionReceipt, PendingTransactionReceipt,
+    ResultPageRequest, StarknetError, SyncStatusType, Transaction as TransactionType,
+    TransactionReceipt as StarknetTransactionReceipt, TransactionStatus,
 };
 use starknet::providers::sequencer::models::{FeeEstimate, FeeUnit, TransactionSimulationInfo, TransactionTrace};
 use starknet::providers::{Provider, ProviderError};
 
-use self::api::{KakarotEthApi, KakarotStarknetApi};
-use self::config::{Network, StarknetConfig};
-use self::constants::gas::{BASE_FEE_PER_GAS, MAX_PRIORITY_FEE_PER_GAS, MINIMUM_GAS_FEE};
-use self::constants::selectors::EVM_CONTRACT_DEPLOYED;
+use self::api::{KakarotEthApi, KakarotStarknetApi, StateOverrideBackend};
+use self::cache::{BlockCache, CacheStats, StateCache, StateChanges};
+use self::chain_spec::ChainSpec;
+use self::config::{ChainIdConfig, Network, StarknetConfig};
+use self::constants::selectors::{DEPLOY_EXTERNALLY_OWNED_ACCOUNT, ETH_SEND_TRANSACTION, EVM_CONTRACT_DEPLOYED};
 use self::constants::{
-    ACCOUNT_ADDRESS, CHAIN_ID, CHUNK_SIZE_LIMIT, COUNTER_CALL_MAINNET, COUNTER_CALL_TESTNET1, COUNTER_CALL_TESTNET2,
-    ESTIMATE_GAS, MAX_FEE, STARKNET_NATIVE_TOKEN,
+    ACCOUNT_ADDRESS, CHUNK_SIZE_LIMIT, COUNTER_CALL_MAINNET, COUNTER_CALL_TESTNET1, COUNTER_CALL_TESTNET2,
+    EARLIEST_BLOCK_NUMBER, FEE_HISTORY_CONCURRENCY, NATIVE_TOKEN_ERC20_ADDRESS,
 };
-use self::errors::EthApiError;
-use self::helpers::{bytes_to_felt_vec, raw_kakarot_calldata, DataDecodingError};
+use self::errors::{ConfigError, EthApiError, SignerError};
+use self::filter::{FilterKind, FilterPoll, FilterStore, DEFAULT_FILTER_TIMEOUT};
+use self::gas_estimation::{binary_search_gas, fee_to_gas, GasEstimationConfig};
+use self::helpers::{
+    compute_starknet_address, decode_eth_call_return, gateway_block_param, raw_kakarot_calldata, DataDecodingError,
+};
+use self::log_index::LogIndex;
+use self::logs::{block_range_chunks, LogsConfig, LOGS_CHUNK_CONCURRENCY};
+use self::pending_transactions::PendingTransactionTracker;
+use self::relayed::RelayedTransactions;
+use self::relayer::{RelayerPool, DEPLOYMENT_POLL_INTERVAL, DEPLOYMENT_TIMEOUT};
+use self::reorg::BlockLink;
+use self::sender_policy::SenderPolicy;
+use self::signer::EthSigner;
+use self::transaction_index::{TransactionIndex, TransactionLookupConfig, TRANSACTION_SCAN_CONCURRENCY};
+use self::validation::{validate_sender_state, validate_transaction, InvalidTransactionError, SenderState};
+use crate::contracts::abi::to_ethers_u256;
 use crate::contracts::account::{Account, KakarotAccount};
 use crate::contracts::contract_account::ContractAccount;
-use crate::contracts::erc20::ethereum_erc20::EthereumErc20;
+use crate::contracts::erc20::ethereum_erc20::{AllowanceCall, BalanceOfCall, EthereumErc20, IERC20Calls};
 use crate::contracts::erc20::starknet_erc20::StarknetErc20;
 use crate::contracts::kakarot::KakarotContract;
+use crate::models::account::{AccountDetails, AccountType, DeployedAccount};
 use crate::models::balance::{FutureTokenBalance, TokenBalances};
-use crate::models::block::{BlockWithTxHashes, BlockWithTxs, EthBlockId};
+use crate::models::block::{logs_bloom, BlockWithTxHashes, BlockWithTxs, EthBlockId};
+use crate::models::block_stats::BlockStats;
+use crate::models::conversions::{bytes_to_felts, felts_to_bytes, u256_to_felts};
 use crate::models::convertible::{ConvertibleStarknetBlock, ConvertibleStarknetEvent, ConvertibleStarknetTransaction};
 use crate::models::event::StarknetEvent;
 use crate::models::event_filter::EthEventFilter;
+use crate::models::fee_history::{
+    actual_fee, build_fee_history, fee_history_range, validate_reward_percentiles, BlockFees,
+};
 use crate::models::felt::Felt252Wrapper;
-use crate::models::transaction::{StarknetTransaction, StarknetTransactions};
+use crate::models::receipt::BlockReceiptContext;
+use crate::models::simulation::{
+    find_simulated_invocation, native_token_flows, nonce_changes, simulated_events, AccountDiff, Delta,
+    SimulationRequest, TransactionSimulation,
+};
+use crate::models::state_override::{account_override_writes, StateOverride, StateRestore};
+use crate::models::trace::{
+    find_invocation, invocation_result, revert_reason, BlockTraceResult, CallFrame, CallLogFrame, CallType, FlatTrace,
+    GethTrace, LocalizedFlatTrace, TraceResults, TraceType, TracingOptions,
+};
+use crate::models::transaction::{rpc_transaction, StarknetTransaction, StarknetTransactions};
 use crate::models::ConversionError;
+use crate::storage::{MemoryStorage, StorageError};
+
+/// Loads a part of the local state from the storage, starting from an empty one when the stored
+/// entries can't be read.
+fn load_or_default<T: Default>(name: &str, load: impl FnOnce() -> Result<T, StorageError>) -> T {
+    load().unwrap_or_else(|err| {
+        tracing::error!("Failed to load the {name} from the storage, starting from an empty state: {err}");
+        T::default()
+    })
+}
 
 pub struct KakarotClient<P: Provider + Send + Sync> {
     starknet_provider: Arc<P>,
     kakarot_contract: KakarotContract<P>,
     network: Network,
+    /// HTTP client of the requests sent to the feeder gateway, and to the node outside of the
+    /// provider.
+    http_client: Client,
+    chain_id: u64,
+    chain_spec: ChainSpec,
+    native_token_address: FieldElement,
+    fee_token_address: Option<FieldElement>,
+    kakarot_deployment_block: u64,
+    filters: FilterStore,
+    transaction_lookup: TransactionLookupConfig,
+    transaction_index: TransactionIndex,
+    relayed_transactions: RelayedTransactions,
+    pending_transactions: PendingTransactionTracker,
+    relayer: Option<RelayerPool>,
+    coinbase: Option<Address>,
+    signer: EthSigner,
+    sender_policy: SenderPolicy,
+    block_cache: Option<BlockCache>,
+    state_cache: Option<StateCache>,
+    transaction_conversion_concurrency: usize,
+    logs_config: LogsConfig,
+    gas_estimation: GasEstimationConfig,
+    log_index: Option<LogIndex>,
+    state_override_backend: Option<Arc<dyn StateOverrideBackend>>,
+    // Calls with state overrides write the shared sequencer state, they hold the lock exclusively
+    // until the state is restored while the reads of the state share it
+    state_override_lock: Arc<tokio::sync::RwLock<()>>,
+    /// Last block known to be accepted on L1, the lower bound of the search of the `safe` and
+    /// `finalized` blocks.
+    l1_accepted_block: Mutex<Option<u64>>,
 }
 
 impl<P: Provider + Send + Sync> KakarotClient<P> {
-    /// Create a new `KakarotClient`.
+    /// Create a new `KakarotClient`, resolving first the chain id derived from the chain id of the
+    /// Starknet network. Fails if the chain id of the Starknet network can't be fetched.
+    pub async fn try_new(
+        mut starknet_config: StarknetConfig,
+        starknet_provider: P,
+    ) -> Result<Self, ProviderError<P::Error>> {
+        let chain_id = starknet_config.chain_id.resolve(&starknet_provider).await?;
+        starknet_config.chain_id = ChainIdConfig::Fixed(chain_id);
+        Ok(Self::new(starknet_config, starknet_provider))
+    }
+
+    /// Create a new `KakarotClient`. A chain id derived from the Starknet network can't be fetched
+    /// here, the client must be created with [`KakarotClient::try_new`] to resolve it.
     pub fn new(starknet_config: StarknetConfig, starknet_provider: P) -> Self {
-        let StarknetConfig { kakarot_address, proxy_account_class_hash, network } = starknet_config;
+        let StarknetConfig {
+            kakarot_address,
+            proxy_account_class_hash,
+            network,
+            chain_id,
+            chain_spec,
+            native_token_address,
+            fee_token_address,
+            kakarot_deployment_block,
+            transaction_lookup,
+            coinbase,
+            signer,
+            sender_policy,
+            block_cache,
+            transaction_conversion_concurrency,
+            logs,
+            gas_estimation,
+            log_index,
+            storage,
+            state_override_backend,
+            relayer,
+        } = starknet_config;
+
+        // The client keeps serving without persistence rather than failing to start
+        let storage = storage.open().unwrap_or_else(|err| {
+            tracing::error!("Failed to open the storage, keeping the data in memory: {err}");
+            Arc::new(MemoryStorage::default())
+        });
+
+        let chain_id = match chain_id {
+            ChainIdConfig::Fixed(chain_id) => chain_id,
+            ChainIdConfig::Starknet => {
+                tracing::error!(
+                    "The chain id derived from Starknet must be resolved by KakarotClient::try_new, falling back to {}",
+                    chain_spec.chain_id
+                );
+                chain_spec.chain_id
+            }
+        };
 
         let starknet_provider = Arc::new(starknet_provider);
 
         let kakarot_contract =
             KakarotContract::new(Arc::clone(&starknet_provider), kakarot_address, proxy_account_class_hash);
 
-        Self { starknet_provider, network, kakarot_contract }
+        Self {
+            starknet_provider,
+            network,
+            http_client: Client::new(),
+            chain_id,
+            chain_spec,
+            native_token_address,
+            fee_token_address,
+            kakarot_contract,
+            kakarot_deployment_block,
+            filters: load_or_default("filters", || {
+                FilterStore::with_storage(DEFAULT_FILTER_TIMEOUT, Arc::clone(&storage))
+            }),
+            transaction_index: load_or_default("transaction hashes", || {
+                TransactionIndex::with_storage(Arc::clone(&storage), transaction_lookup.index_size)
+            }),
+            transaction_lookup,
+            relayed_transactions: RelayedTransactions::default(),
+            pending_transactions: PendingTransactionTracker::default(),
+            relayer: relayer.map(|relayer| RelayerPool::new(relayer, chain_spec.max_fee())),
+            coinbase,
+            signer,
+            sender_policy,
+            block_cache: BlockCache::new(&block_cache),
+            state_cache: StateCache::new(&block_cache, native_token_address),
+            // A zero concurrency would never convert any transaction
+            transaction_conversion_concurrency: transaction_conversion_concurrency.max(1),
+            logs_config: logs,
+            gas_estimation,
+            log_index: log_index.then(|| load_or_default("log index", || LogIndex::with_storage(storage))),
+            state_override_backend,
+            state_override_lock: Arc::default(),
+            l1_accepted_block: Mutex::new(None),
+        }
     }
-}
 
-#[async_trait]
-impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
-    /// Returns the latest block number
-    async fn block_number(&self) -> Result<U64, EthApiError<P::Error>> {
-        let block_number = self.starknet_provider.block_number().await?;
-        Ok(block_number.into())
+    /// Checks that the configured coinbase is a deployed Kakarot account, so that the fees it
+    /// receives can be spent.
+    pub async fn validate_coinbase(&self) -> Result<(), EthApiError<P::Error>> {
+        let coinbase = match self.coinbase {
+            Some(coinbase) => coinbase,
+            None => return Ok(()),
+        };
+
+        let latest = StarknetBlockId::Tag(BlockTag::Latest);
+        let starknet_address = self.compute_starknet_address(coinbase, &latest).await?;
+        let invalid_coinbase = |reason: &str| {
+            EthApiError::ConfigError(ConfigError::EnvironmentVariableSetWrong(format!(
+                "KAKAROT_COINBASE {coinbase:#x} {reason}"
+            )))
+        };
+
+        match self.starknet_provider.get_class_hash_at(latest, starknet_address).await {
+            Ok(class_hash) if class_hash == self.proxy_account_class_hash() => Ok(()),
+            Ok(_) => Err(invalid_coinbase("is not a Kakarot account")),
+            Err(ProviderError::StarknetError(StarknetError::ContractNotFound)) => {
+                Err(invalid_coinbase("has no deployed Kakarot account"))
+            }
+            Err(err) => Err(err.into()),
+        }
     }
 
-    /// Returns the bytecode of a contract given its address and a block id.
-    async fn get_code(&self, ethereum_address: Address, block_id: BlockId) -> Result<Bytes, EthApiError<P::Error>> {
-        let starknet_block_id: StarknetBlockId = EthBlockId::new(block_id).try_into()?;
+    /// Checks that the configured native token, and the fee token if set, are deployed on the
+    /// Starknet network, so that the balances aren't silently read as zero from a missing contract.
+    pub async fn validate_native_token(&self) -> Result<(), EthApiError<P::Error>> {
+        let tokens = std::iter::once(("KAKAROT_NATIVE_TOKEN_ADDRESS", self.native_token_address))
+            .chain(self.fee_token_address.map(|fee_token| ("KAKAROT_FEE_TOKEN_ADDRESS", fee_token)));
+        for (variable, token_address) in tokens {
+            let latest = StarknetBlockId::Tag(BlockTag::Latest);
+            match self.starknet_provider.get_class_hash_at(latest, token_address).await {
+                Ok(_) => {}
+                Err(ProviderError::StarknetError(StarknetError::ContractNotFound)) => {
+                    return Err(EthApiError::ConfigError(ConfigError::EnvironmentVariableSetWrong(format!(
+                        "{variable} {token_address:#x} has no deployed contract"
+                    ))));
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
 
-        // Convert the hex-encoded string to a FieldElement
-        let ethereum_address: Felt252Wrapper = ethereum_address.into();
-        let ethereum_address = ethereum_address.into();
+    /// Returns the hashes of the blocks in the given range.
+    async fn block_hashes(&self, from_block: u64, to_block: u64) -> Result<Vec<H256>, EthApiError<P::Error>> {
+        let handles = (from_block..=to_block)
+            .map(|block_number| self.starknet_provider.get_block_with_tx_hashes(StarknetBlockId::Number(block_number)));
 
-        let starknet_contract_address =
-            self.kakarot_contract.compute_starknet_address(&ethereum_address, &starknet_block_id).await?;
+        let mut hashes = vec![];
+        for block in join_all(handles).await {
+            if let MaybePendingBlockWithTxHashes::Block(block) = block? {
+                let hash: Felt252Wrapper = block.block_hash.into();
+                hashes.push(hash.into());
+            }
+        }
+        Ok(hashes)
+    }
 
-        let provider = self.starknet_provider();
-        let contract_account = ContractAccount::new(starknet_contract_address, &provider);
-        let bytecode = contract_account.bytecode(&starknet_block_id).await?;
+    /// Returns the hashes of the transactions in the pending block.
+    async fn pending_transaction_hashes(&self) -> Result<Vec<H256>, EthApiError<P::Error>> {
+        let block = self.starknet_provider.get_block_with_tx_hashes(StarknetBlockId::Tag(BlockTag::Pending)).await?;
+        let transactions = BlockWithTxHashes::new(block).transactions();
+        Ok(transactions.into_iter().map(|hash| Felt252Wrapper::from(hash).into()).collect())
+    }
 
-        // Convert the result of the function call to a vector of bytes
-        Ok(bytecode)
+    /// Returns the hash of the Starknet transaction wrapping the Ethereum transaction with the
+    /// given hash. Blocks which aren't indexed yet are scanned, within the configured lookup
+    /// depth.
+    async fn find_starknet_transaction_hash(&self, ethereum_hash: H256) -> Result<Option<H256>, EthApiError<P::Error>> {
+        if let Some(hash) = self.transaction_index.get(&ethereum_hash) {
+            return Ok(Some(hash));
+        }
+
+        let latest_block = self.block_number().await?.as_u64();
+        if self.transaction_index.is_known_miss(&ethereum_hash, latest_block) {
+            return Ok(None);
+        }
+
+        let _scan = self.transaction_index.acquire_scan().await;
+        // The transaction may have been indexed by another scan in the meantime
+        if let Some(hash) = self.transaction_index.get(&ethereum_hash) {
+            return Ok(Some(hash));
+        }
+
+        let depth = if self.transaction_lookup.exhaustive { None } else { Some(self.transaction_lookup.scan_depth) };
+        let blocks = self.transaction_index.blocks_to_scan(latest_block, self.kakarot_deployment_block, depth);
+
+        for chunk in blocks.chunks(TRANSACTION_SCAN_CONCURRENCY) {
+            let handles = chunk
+                .iter()
+                .map(|block_number| self.starknet_provider.get_block_with_txs(StarknetBlockId::Number(*block_number)));
+            let fetched_blocks = join_all(handles).await;
+
+            // Index the blocks in the scan order to keep the indexed range contiguous
+            for (block_number, block) in chunk.iter().zip(fetched_blocks) {
+                // A block which can't be fetched is left out of the index, and scanned again later
+                let block = match block {
+                    Ok(block) => block,
+                    Err(err) => {
+                        tracing::warn!("Transaction lookup skipped block {block_number}: {err}");
+                        continue;
+                    }
+                };
+                let transactions = BlockWithTxs::new(block).transactions().into_iter().filter_map(|tx| {
+                    let tx = StarknetTransaction::from(tx);
+                    let ethereum_hash = tx.ethereum_transaction_hash().ok()?;
+                    let starknet_hash: H256 = tx.transaction_hash().ok()?.into();
+                    Some((ethereum_hash, starknet_hash))
+                });
+                self.transaction_index.index_block(*block_number, transactions.collect::<Vec<_>>());
+            }
+
+            if let Some(hash) = self.transaction_index.get(&ethereum_hash) {
+                return Ok(Some(hash));
+            }
+        }
+
+        self.transaction_index.record_miss(ethereum_hash, latest_block);
+        Ok(None)
     }
 
-    /// Returns the logs corresponding to the filter
-    async fn get_logs(&self, filter: Filter) -> Result<Vec<Log>, EthApiError<P::Error>> {
-        // Check the block range
-        let current_block: u64 = self.block_number().await?.low_u64();
-        let from_block = filter.get_from_block();
-        let to_block = filter.get_to_block();
+    /// Returns the top level EVM call frame of a mined Kakarot transaction, given its receipt. The
+    /// output and the revert reason are read from the Starknet trace of the transaction.
+    async fn transaction_call_frame(
+        &self,
+        transaction: EtherTransaction,
+        receipt: Option<TransactionReceipt>,
+    ) -> Result<CallFrame, EthApiError<P::Error>> {
+        let starknet_hash: Felt252Wrapper = transaction.hash.try_into()?;
+        let trace = self.starknet_transaction_trace(starknet_hash.into()).await?;
+
+        let invocation = find_invocation(&trace, self.kakarot_address(), ETH_SEND_TRANSACTION);
+        let output = match invocation.and_then(invocation_result) {
+            Some(result) => Some(felts_to_bytes(&decode_eth_call_return::<P::Error>(&result)?)?),
+            None => None,
+        };
+        let reverted = receipt.as_ref().and_then(|receipt| receipt.status_code) == Some(U64::ZERO);
+        let error = revert_reason(&trace).or_else(|| reverted.then(|| "execution reverted".to_string()));
 
-        let filter = match (from_block, to_block) {
-            (Some(from), _) if from > current_block => return Ok(vec![]),
-            (_, Some(to)) if to > current_block => filter.to_block(current_block),
-            (Some(from), Some(to)) if to < from => return Ok(vec![]),
-            _ => filter,
+        let (call_type, to) = match transaction.to {
+            Some(to) => (CallType::Call, Some(to)),
+            None => (CallType::Create, receipt.as_ref().and_then(|receipt| receipt.contract_address)),
+        };
+        let (gas_used, logs) = match receipt {
+            Some(receipt) => (receipt.gas_used.unwrap_or_default(), receipt.logs),
+            None => (U256::ZERO, vec![]),
         };
 
-        // Convert the eth log filter to a starknet event filter
-        let filter: EthEventFilter = filter.into();
-        let event_filter = filter.to_starknet_filter(self)?;
+        Ok(CallFrame {
+            call_type,
+            from: transaction.from,
+            to,
+            value: Some(transaction.value),
+            gas: transaction.gas,
+            gas_used,
+            input: transaction.input,
+            output,
+            error,
+            calls: vec![],
+            logs: logs.into_iter().map(CallLogFrame::from).collect(),
+        })
+    }
 
-        // Filter events
-        let events = self
-            .filter_events(EventFilterWithPage {
-                event_filter,
-                result_page_request: ResultPageRequest { continuation_token: None, chunk_size: CHUNK_SIZE_LIMIT },
-            })
-            .await?;
+    /// Returns the Parity flat traces of a mined Kakarot transaction, located in its block.
+    async fn localized_traces(
+        &self,
+        transaction: EtherTransaction,
+        receipt: Option<TransactionReceipt>,
+    ) -> Result<Vec<LocalizedFlatTrace>, EthApiError<P::Error>> {
+        let (block_hash, block_number, transaction_hash, transaction_position) =
+            (transaction.block_hash, transaction.block_number, transaction.hash, transaction.transaction_index);
+        let frame = self.transaction_call_frame(transaction, receipt).await?;
+        Ok(FlatTrace::flatten(&frame)
+            .into_iter()
+            .map(|trace| LocalizedFlatTrace { trace, block_hash, block_number, transaction_hash, transaction_position })
+            .collect())
+    }
+
+    /// Returns the receipts of the Kakarot transactions of the converted block by transaction hash,
+    /// converted in a single pass instead of one block fetch per receipt. The pending block has
+    /// no receipts.
+    async fn receipts_by_hash(
+        &self,
+        block: &RichBlock,
+    ) -> Result<HashMap<H256, TransactionReceipt>, EthApiError<P::Error>> {
+        let Some(hash) = block.header.hash else {
+            return Ok(HashMap::new());
+        };
+        let block_hash: Felt252Wrapper = hash.try_into()?;
+        let receipts = self.fetch_block_receipts(StarknetBlockId::Hash(block_hash.into())).await?.unwrap_or_default();
+        Ok(receipts.into_iter().filter_map(|receipt| receipt.transaction_hash.map(|hash| (hash, receipt))).collect())
+    }
+
+    /// Builds the Kakarot invoke transaction of an unsigned transaction request, encoded with an
+    /// empty signature, to be simulated. Returns the sender along with the invoke transaction.
+    async fn unsigned_invoke_transaction(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+    ) -> Result<(Address, BroadcastedInvokeTransactionV1), EthApiError<P::Error>> {
+        let chain_id = request.chain_id.unwrap_or(self.chain_id.into());
+
+        let from = request.from.ok_or_else(|| {
+            EthApiError::MissingParameterError("from for estimate_gas or simulate_transaction".into())
+        })?;
+        let nonce = self.nonce(from, block_id).await?.try_into().map_err(ConversionError::<u64>::from)?;
+
+        let gas_limit = request.gas.unwrap_or(U256::ZERO).try_into().map_err(ConversionError::<u64>::from)?;
+        let max_fee_per_gas = request
+            .max_fee_per_gas
+            .unwrap_or_else(|| self.chain_spec.base_fee_per_gas())
+            .try_into()
+            .map_err(ConversionError::<u128>::from)?;
+        let max_priority_fee_per_gas = request
+            .max_priority_fee_per_gas
+            .unwrap_or_else(|| U256::from(self.chain_spec.max_priority_fee_per_gas()))
+            .try_into()
+            .map_err(ConversionError::<u128>::from)?;
 
-        // Convert events to eth logs
-        let logs = events
+        let to = request.to.map_or(TransactionKind::Create, TransactionKind::Call);
+
+        let value = request.value.unwrap_or(U256::ZERO).try_into().map_err(ConversionError::<u128>::from)?;
+
+        let data = request.data.unwrap_or_default();
+
+        let tx = Transaction::Eip1559(TxEip1559 {
+            chain_id: chain_id.low_u64(),
+            nonce,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            to,
+            value,
+            access_list: AccessList(vec![]),
+            input: data,
+        });
+
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
+
+        let sender_address = self.compute_starknet_address(from, &starknet_block_id).await?;
+
+        let mut data = vec![];
+        tx.encode_with_signature(&Signature::default(), &mut data, false);
+        let data = data.into_iter().map(FieldElement::from).collect();
+        let calldata = raw_kakarot_calldata(self.kakarot_address(), data);
+
+        let tx = BroadcastedInvokeTransactionV1 {
+            max_fee: FieldElement::ZERO,
+            signature: vec![],
+            sender_address,
+            nonce: nonce.into(),
+            calldata,
+        };
+
+        Ok((from, tx))
+    }
+
+    /// Queries the events of the filter, following the continuation tokens until the last page.
+    /// Fails as soon as more than `max_results` events are returned.
+    async fn paginate_events(
+        &self,
+        event_filter: EventFilter,
+        max_results: usize,
+    ) -> Result<Vec<EmittedEvent>, EthApiError<P::Error>> {
+        let mut events = vec![];
+        let mut continuation_token = None;
+        loop {
+            let page =
+                self.starknet_provider.get_events(event_filter.clone(), continuation_token, CHUNK_SIZE_LIMIT).await?;
+            events.extend(page.events);
+            if events.len() > max_results {
+                return Err(EthApiError::TooManyResults(max_results));
+            }
+
+            continuation_token = page.continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        Ok(events)
+    }
+
+    /// Returns the number of the block bounding a log filter, resolved through the block id
+    /// resolver at query time, or `None` for the pending block. A missing bound is the latest
+    /// block.
+    async fn log_filter_block(
+        &self,
+        block: Option<&BlockNumberOrTag>,
+        current_block: u64,
+    ) -> Result<Option<u64>, EthApiError<P::Error>> {
+        match block {
+            None | Some(BlockNumberOrTag::Latest) => Ok(Some(current_block)),
+            Some(BlockNumberOrTag::Pending) => Ok(None),
+            Some(block) => match self.starknet_block_id(BlockId::Number(*block)).await? {
+                StarknetBlockId::Number(number) => Ok(Some(number)),
+                _ => Ok(Some(current_block)),
+            },
+        }
+    }
+
+    /// Converts the events emitted by Kakarot to Ethereum logs, skipping the other events.
+    fn emitted_events_to_logs(&self, events: Vec<EmittedEvent>) -> Vec<Log> {
+        events
             .into_iter()
             .filter_map(|emitted| {
                 let event: StarknetEvent =
@@ -153,25 +583,924 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
                     )
                     .ok()
             })
-            .collect::<Vec<_>>();
-        Ok(logs)
+            .collect()
     }
 
-    /// Returns the result of executing a call on a ethereum address for a given calldata and block
-    /// without creating a transaction.
-    async fn call(&self, to: Address, calldata: Bytes, block_id: BlockId) -> Result<Bytes, EthApiError<P::Error>> {
-        let starknet_block_id: StarknetBlockId = EthBlockId::new(block_id).try_into()?;
+    /// Returns the gas price of the block and the total fee paid by its transactions.
+    async fn block_fees(&self, block_number: u64) -> Result<BlockFees, EthApiError<P::Error>> {
+        if let Some(fees) = self.block_cache.as_ref().and_then(|cache| cache.get_block_fees(block_number)) {
+            return Ok(fees);
+        }
+
+        let block = self.starknet_provider.get_block_with_tx_hashes(StarknetBlockId::Number(block_number)).await?;
+        let transaction_hashes = match block {
+            MaybePendingBlockWithTxHashes::Block(block) => block.transactions,
+            MaybePendingBlockWithTxHashes::PendingBlock(block) => block.transactions,
+        };
+
+        let total_fee = self.total_fee(transaction_hashes).await?;
+        let gas_price = self.block_gas_price(block_number).await?;
+        let fees = BlockFees { gas_price, total_fee };
+        if let Some(cache) = &self.block_cache {
+            cache.insert_block_fees(block_number, fees);
+        }
+        Ok(fees)
+    }
+
+    /// Returns the total fee paid by the transactions, from their Starknet receipts.
+    async fn total_fee(&self, transaction_hashes: Vec<FieldElement>) -> Result<U256, EthApiError<P::Error>> {
+        Ok(self.total_fee_and_logs(transaction_hashes).await?.0)
+    }
+
+    /// Returns the total fee paid by the transactions and the logs they emitted through Kakarot,
+    /// from their Starknet receipts.
+    async fn total_fee_and_logs(
+        &self,
+        transaction_hashes: Vec<FieldElement>,
+    ) -> Result<(U256, Vec<Log>), EthApiError<P::Error>> {
+        let receipts: Vec<MaybePendingTransactionReceipt> = stream::iter(transaction_hashes)
+            .map(|hash| self.starknet_provider.get_transaction_receipt(hash))
+            .buffer_unordered(self.transaction_conversion_concurrency)
+            .try_collect()
+            .await?;
+
+        let mut total_fee = U256::ZERO;
+        let mut logs = vec![];
+        for receipt in receipts {
+            let fee: Felt252Wrapper = actual_fee(&receipt).into();
+            total_fee = total_fee.saturating_add(fee.into());
+            let events = match receipt {
+                MaybePendingTransactionReceipt::Receipt(StarknetTransactionReceipt::Invoke(receipt)) => receipt.events,
+                MaybePendingTransactionReceipt::PendingReceipt(PendingTransactionReceipt::Invoke(receipt)) => {
+                    receipt.events
+                }
+                _ => vec![],
+            };
+            logs.extend(
+                events
+                    .into_iter()
+                    .filter_map(|event| StarknetEvent::new(event).to_eth_log(self, None, None, None, None, None).ok()),
+            );
+        }
+        Ok((total_fee, logs))
+    }
+
+    /// Returns the gas used by the Kakarot transactions of a block and the bloom of their logs. The
+    /// gas used is the fees paid by the transactions divided by the gas price of the block, at most
+    /// the block gas limit. The pending block, without a number, is priced at the base fee per gas.
+    async fn transactions_gas_used_and_logs_bloom(
+        &self,
+        transaction_hashes: Vec<H256>,
+        block_number: Option<u64>,
+    ) -> Result<(U256, Bloom), EthApiError<P::Error>> {
+        let transaction_hashes = transaction_hashes
+            .into_iter()
+            .map(|hash| Felt252Wrapper::try_from(hash).map(Into::into))
+            .collect::<Result<Vec<FieldElement>, _>>()?;
+        let (total_fee, logs) = self.total_fee_and_logs(transaction_hashes).await?;
+        let gas_price = match block_number {
+            Some(block_number) => self.block_gas_price(block_number).await?,
+            None => self.base_fee_per_gas(),
+        };
+        let gas_used = BlockFees { gas_price, total_fee }.gas_used().min(self.block_gas_limit());
+        Ok((gas_used, logs_bloom(&logs)))
+    }
+
+    /// Returns the gas used by the Kakarot transactions of the converted block and the bloom of
+    /// their logs. The hashes of a block without its transactions aren't filtered, the Kakarot
+    /// transactions of the block are fetched to compute them. Both are cached by block hash, and
+    /// computed once for the block with and without its transactions.
+    async fn block_gas_used_and_logs_bloom(&self, block: &RichBlock) -> Result<(U256, Bloom), EthApiError<P::Error>> {
+        let cache = self.block_cache.as_ref().zip(block.header.hash);
+        if let Some(gas_used_and_bloom) = cache.and_then(|(cache, hash)| cache.get_gas_used_and_bloom(&hash)) {
+            return Ok(gas_used_and_bloom);
+        }
+
+        let transaction_hashes = match &block.transactions {
+            BlockTransactions::Full(transactions) => transactions.iter().map(|transaction| transaction.hash).collect(),
+            _ => {
+                let block_id = match block.header.hash {
+                    Some(hash) => StarknetBlockId::Hash(Felt252Wrapper::try_from(hash)?.into()),
+                    None => StarknetBlockId::Tag(BlockTag::Pending),
+                };
+                self.kakarot_transactions(block_id).await?.iter().map(|transaction| transaction.hash).collect()
+            }
+        };
+        // The number of the pending block, without a hash, isn't a Starknet block yet
+        let block_number = block.header.hash.and(block.header.number).map(|number| number.to::<u64>());
+        let gas_used_and_bloom = self.transactions_gas_used_and_logs_bloom(transaction_hashes, block_number).await?;
+        if let Some((cache, hash)) = cache {
+            cache.insert_gas_used_and_bloom(hash, gas_used_and_bloom);
+        }
+        Ok(gas_used_and_bloom)
+    }
+
+    /// Returns the Ethereum transactions executed by the Kakarot contract in the Starknet block.
+    async fn kakarot_transactions(
+        &self,
+        starknet_block_id: StarknetBlockId,
+    ) -> Result<Vec<EtherTransaction>, EthApiError<P::Error>> {
+        let starknet_block = self.starknet_provider.get_block_with_txs(starknet_block_id).await?;
+
+        let block_transactions = match starknet_block {
+            MaybePendingBlockWithTxs::PendingBlock(pending_block_with_txs) => {
+                self.filter_starknet_into_eth_txs(pending_block_with_txs.transactions.into(), None, None).await
+            }
+            MaybePendingBlockWithTxs::Block(block_with_txs) => {
+                let block_hash: Felt252Wrapper = block_with_txs.block_hash.into();
+                let block_hash = Some(block_hash.into());
+                let block_number: Felt252Wrapper = block_with_txs.block_number.into();
+                let block_number = Some(block_number.into());
+                self.filter_starknet_into_eth_txs(block_with_txs.transactions.into(), block_hash, block_number).await
+            }
+        };
+        Ok(match block_transactions {
+            BlockTransactions::Full(transactions) => transactions,
+            BlockTransactions::Hashes(_) | BlockTransactions::Uncle => vec![],
+        })
+    }
+
+    /// Returns the gas price of the block. The gas price is only exposed by the feeder gateway:
+    /// on the other networks, the base fee per gas of Kakarot is returned.
+    async fn block_gas_price(&self, block_number: u64) -> Result<U256, EthApiError<P::Error>> {
+        let Ok(url) = self.network.gateway_url() else {
+            return Ok(self.base_fee_per_gas());
+        };
+        let mut url = url
+            .join("get_block")
+            .map_err(|e| EthApiError::FeederGatewayError(format!("gateway url parsing error: {:?}", e)))?;
+        url.query_pairs_mut().append_pair("blockNumber", &block_number.to_string());
+
+        let block: serde_json::Value = self
+            .http_client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| EthApiError::FeederGatewayError(format!("gateway get_block error: {:?}", e)))?
+            .json()
+            .await
+            .map_err(|e| {
+                EthApiError::FeederGatewayError(format!("error while decoding get_block response: {:?}", e))
+            })?;
+
+        let gas_price = block
+            .get("gas_price")
+            .and_then(serde_json::Value::as_str)
+            .and_then(|gas_price| U256::from_str_radix(gas_price.trim_start_matches("0x"), 16).ok())
+            .ok_or_else(|| EthApiError::FeederGatewayError(format!("missing gas price of block {block_number}")))?;
+        Ok(gas_price)
+    }
+
+    /// Returns the chain tip state cache and the head it is valid for, when the block id is the
+    /// `latest` tag and the head watcher reported a head.
+    fn latest_state_cache(&self, starknet_block_id: &StarknetBlockId) -> Option<(&StateCache, u64)> {
+        if !matches!(starknet_block_id, StarknetBlockId::Tag(BlockTag::Latest)) {
+            return None;
+        }
+        let state_cache = self.state_cache.as_ref()?;
+        Some((state_cache, state_cache.head()?))
+    }
+
+    /// Builds the EIP-1559 transaction of the request sent by `from`, filling in the missing nonce,
+    /// gas and fees.
+    async fn fill_transaction(
+        &self,
+        from: Address,
+        request: CallRequest,
+    ) -> Result<Transaction, EthApiError<P::Error>> {
+        let block_id = BlockId::Number(BlockNumberOrTag::Latest);
+
+        let chain_id = request.chain_id.unwrap_or(self.chain_id.into());
+        let nonce = match request.nonce {
+            Some(nonce) => nonce,
+            None => self.nonce(from, block_id).await?,
+        };
+        let gas_limit = match request.gas {
+            Some(gas) => gas,
+            None => self.estimate_gas(request.clone(), block_id).await?,
+        };
+        let max_fee_per_gas = request.max_fee_per_gas.or(request.gas_price).unwrap_or_else(|| self.base_fee_per_gas());
+        let max_priority_fee_per_gas =
+            request.max_priority_fee_per_gas.unwrap_or_else(|| U256::from(self.max_priority_fee_per_gas()));
+
+        Ok(Transaction::Eip1559(TxEip1559 {
+            chain_id: chain_id.low_u64(),
+            nonce: nonce.try_into().map_err(ConversionError::<u64>::from)?,
+            gas_limit: gas_limit.try_into().map_err(ConversionError::<u64>::from)?,
+            max_fee_per_gas: max_fee_per_gas.try_into().map_err(ConversionError::<u128>::from)?,
+            max_priority_fee_per_gas: max_priority_fee_per_gas.try_into().map_err(ConversionError::<u128>::from)?,
+            to: request.to.map_or(TransactionKind::Create, TransactionKind::Call),
+            value: request.value.unwrap_or_default().try_into().map_err(ConversionError::<u128>::from)?,
+            access_list: AccessList(vec![]),
+            input: request.data.unwrap_or_default(),
+        }))
+    }
+
+    /// Returns the number of the last block accepted on L1, `None` if no block is, e.g. on a devnet
+    /// without L1. The blocks are accepted on L1 in order: the last block found is cached, and the
+    /// blocks following it are probed with a doubling step before bisecting the last range, so
+    /// that a call without newly accepted blocks only fetches the block following the cached one.
+    async fn l1_accepted_block_number(&self) -> Result<Option<u64>, EthApiError<P::Error>> {
+        let known = *self.l1_accepted_block.lock().expect("poisoned lock");
+        let mut low = match known {
+            Some(known) => known,
+            None if self.is_accepted_on_l1(EARLIEST_BLOCK_NUMBER).await? => EARLIEST_BLOCK_NUMBER,
+            None => return Ok(None),
+        };
+        let mut high = self.block_number().await?.as_u64();
+
+        let mut step = 1;
+        while low < high {
+            let probe = high.min(low + step);
+            if !self.is_accepted_on_l1(probe).await? {
+                high = probe - 1;
+                break;
+            }
+            low = probe;
+            step *= 2;
+        }
+        while low < high {
+            let middle = low + (high - low + 1) / 2;
+            if self.is_accepted_on_l1(middle).await? {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+
+        let mut known = self.l1_accepted_block.lock().expect("poisoned lock");
+        *known = Some(known.map_or(low, |known| known.max(low)));
+        Ok(Some(low))
+    }
+
+    async fn is_accepted_on_l1(&self, block_number: u64) -> Result<bool, EthApiError<P::Error>> {
+        let block = self.starknet_provider.get_block_with_tx_hashes(StarknetBlockId::Number(block_number)).await?;
+        match block {
+            MaybePendingBlockWithTxHashes::Block(block) => Ok(matches!(block.status, BlockStatus::AcceptedOnL1)),
+            MaybePendingBlockWithTxHashes::PendingBlock(_) => Ok(false),
+        }
+    }
+
+    /// Checks the new head against the cached blocks, dropping them if the chain reorganized, and
+    /// returns the changes of the state made by the new head. Returns `None` when the cached
+    /// chain tip state must be dropped entirely.
+    async fn new_head_state_changes(&self, block_number: u64) -> Result<Option<StateChanges>, EthApiError<P::Error>> {
+        let block_id = StarknetBlockId::Number(block_number);
+
+        if let Some(block_cache) = &self.block_cache {
+            let block = match self.starknet_provider.get_block_with_tx_hashes(block_id).await? {
+                MaybePendingBlockWithTxHashes::Block(block) => block,
+                MaybePendingBlockWithTxHashes::PendingBlock(_) => return Ok(None),
+            };
+            let hash: H256 = Felt252Wrapper::from(block.block_hash).into();
+            let parent_hash: H256 = Felt252Wrapper::from(block.parent_hash).into();
+            if block_cache.handle_new_head(block_number, hash, parent_hash) {
+                tracing::info!("Chain reorganized at block {block_number}, dropping the cached blocks");
+                return Ok(None);
+            }
+        }
+
+        if self.state_cache.is_none() {
+            return Ok(None);
+        }
+        match self.starknet_provider.get_state_update(block_id).await? {
+            MaybePendingStateUpdate::Update(update) => Ok(Some(StateChanges::from(&update.state_diff))),
+            MaybePendingStateUpdate::PendingUpdate(_) => Ok(None),
+        }
+    }
+
+    /// Returns the nonces and the balance of the sender of a transaction, against which it is
+    /// validated before being relayed.
+    async fn sender_state(&self, sender: Address) -> Result<SenderState, EthApiError<P::Error>> {
+        let latest = BlockId::Number(BlockNumberOrTag::Latest);
+        let pending = BlockId::Number(BlockNumberOrTag::Pending);
+        let (latest_nonce, pending_nonce, balance, fee_token_balance) = futures::try_join!(
+            self.nonce(sender, latest),
+            self.nonce(sender, pending),
+            self.balance(sender, pending),
+            self.fee_token_balance(sender)
+        )?;
+        Ok(SenderState { latest_nonce, pending_nonce, balance, fee_token_balance })
+    }
+
+    /// Returns the balance in the fee token of the Starknet account of the EVM address at the
+    /// pending block, `None` when the fees are paid in the native token.
+    async fn fee_token_balance(&self, ethereum_address: Address) -> Result<Option<U256>, EthApiError<P::Error>> {
+        let Some(fee_token_address) = self.fee_token_address else {
+            return Ok(None);
+        };
+        let pending = StarknetBlockId::Tag(BlockTag::Pending);
+        let starknet_address = self.compute_starknet_address(ethereum_address, &pending).await?;
+
+        let provider = self.starknet_provider();
+        let fee_token = StarknetErc20::new(&provider, fee_token_address);
+        Ok(Some(fee_token.balance_of(&starknet_address, &pending).await?))
+    }
+
+    /// Answers a call to the read-only ERC20 exposing the Starknet fee token at
+    /// [`NATIVE_TOKEN_ERC20_ADDRESS`], by querying the token contract on Starknet. The fee token is
+    /// the native token of Kakarot unless a distinct fee token is configured.
+    async fn native_token_erc20_call(
+        &self,
+        calldata: Bytes,
+        block_id: BlockId,
+    ) -> Result<Bytes, EthApiError<P::Error>> {
+        let call = IERC20Calls::decode(&calldata[..]).map_err(|err| {
+            EthApiError::InvalidParameterError(format!("unsupported call to the native token ERC20: {err}"))
+        })?;
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
+
+        let provider = self.starknet_provider();
+        let token = StarknetErc20::new(&provider, self.fee_token_address.unwrap_or(self.native_token_address));
+        let return_data = match call {
+            IERC20Calls::TotalSupply(_) => to_ethers_u256(token.total_supply(&starknet_block_id).await?).encode(),
+            IERC20Calls::BalanceOf(BalanceOfCall { account }) => {
+                let account =
+                    self.compute_starknet_address(account.to_fixed_bytes().into(), &starknet_block_id).await?;
+                to_ethers_u256(token.balance_of(&account, &starknet_block_id).await?).encode()
+            }
+            IERC20Calls::Allowance(AllowanceCall { owner, spender }) => {
+                let (owner, spender) = futures::try_join!(
+                    self.compute_starknet_address(owner.to_fixed_bytes().into(), &starknet_block_id),
+                    self.compute_starknet_address(spender.to_fixed_bytes().into(), &starknet_block_id)
+                )?;
+                to_ethers_u256(token.allowance(&owner, &spender, &starknet_block_id).await?).encode()
+            }
+            IERC20Calls::Name(_) => token.name(&starknet_block_id).await?.encode(),
+            IERC20Calls::Symbol(_) => token.symbol(&starknet_block_id).await?.encode(),
+            IERC20Calls::Decimals(_) => token.decimals(&starknet_block_id).await?.encode(),
+            IERC20Calls::Transfer(_) | IERC20Calls::Approve(_) => {
+                return Err(EthApiError::InvalidParameterError("the native token ERC20 is read-only".into()));
+            }
+        };
+        Ok(return_data.into())
+    }
+
+    /// Executes a call against the state modified by the overrides: the overrides are written to
+    /// the state of the sequencer by the state override backend, and the previous state is
+    /// restored once the call returns, whatever its result, or is cancelled. The reads of the state
+    /// wait for the restore.
+    async fn call_with_state_override(
+        &self,
+        to: Address,
+        calldata: Bytes,
+        block_id: BlockId,
+        state_override: StateOverride,
+    ) -> Result<Bytes, EthApiError<P::Error>> {
+        let backend = self.state_override_backend.as_ref().ok_or_else(|| {
+            EthApiError::InvalidParameterError("state overrides are not supported by this Starknet provider".into())
+        })?;
+        if !matches!(block_id, BlockId::Number(BlockNumberOrTag::Latest | BlockNumberOrTag::Pending)) {
+            return Err(EthApiError::InvalidParameterError(
+                "state overrides are only supported at the latest or pending block".into(),
+            ));
+        }
+
+        let mut writes = vec![];
+        for (address, account_override) in &state_override {
+            let starknet_address =
+                compute_starknet_address(self.kakarot_address(), self.proxy_account_class_hash(), *address);
+            let account_writes = account_override_writes(starknet_address, self.native_token_address, account_override)
+                .map_err(|err| EthApiError::InvalidParameterError(format!("state override of {address:#x}: {err}")))?;
+            writes.extend(account_writes);
+        }
+
+        let lock = Arc::clone(&self.state_override_lock).write_owned().await;
+        let restore = backend.apply(writes).await.map_err(|err| EthApiError::Other(anyhow::anyhow!("{err}")))?;
+        let restore = StateRestore::new(Arc::clone(backend), restore, lock);
+        let result = self.kakarot_call(to, calldata, block_id).await;
+        restore.restore().await;
+
+        result
+    }
+
+    /// Executes a call to the Kakarot contract account at `to`, without taking the state lock.
+    async fn kakarot_call(
+        &self,
+        to: Address,
+        calldata: Bytes,
+        block_id: BlockId,
+    ) -> Result<Bytes, EthApiError<P::Error>> {
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
 
         let to: Felt252Wrapper = to.into();
         let to = to.into();
 
-        let calldata = bytes_to_felt_vec(&calldata);
+        let calldata = bytes_to_felts(&calldata);
 
         let result = self.kakarot_contract.eth_call(&to, calldata, &starknet_block_id).await?;
 
         Ok(result)
     }
 
+    /// Validates the raw transaction and relays it to Kakarot in a Starknet invoke, returning the
+    /// hash of the Starknet transaction.
+    /// The transaction is sent from `evm_address`, its signer unless the account is impersonated.
+    async fn relay_transaction(
+        &self,
+        evm_address: Address,
+        transaction: TransactionSigned,
+        bytes: Bytes,
+    ) -> Result<H256, EthApiError<P::Error>> {
+        validate_transaction(&transaction, self.chain_id, self.chain_spec.block_gas_limit())?;
+        self.sender_policy.check(evm_address, &transaction)?;
+
+        let sender_state = self.sender_state(evm_address).await?;
+        validate_sender_state(&transaction, &sender_state, self.chain_spec.max_fee())?;
+
+        let starknet_block_id = StarknetBlockId::Tag(BlockTag::Latest);
+
+        let starknet_address = self.compute_starknet_address(evm_address, &starknet_block_id).await?;
+        // The first transaction of a sender fails without its account, unless it is deployed first
+        if let Some(relayer) = self.relayer.as_ref().filter(|relayer| relayer.auto_deploys_eoa()) {
+            self.deploy_sender_if_needed(relayer, evm_address, starknet_address).await?;
+        }
+
+        let nonce = FieldElement::from(transaction.nonce());
+
+        let calldata = raw_kakarot_calldata(self.kakarot_address(), bytes_to_felts(&bytes));
+
+        // Get estimated_fee from Starknet
+        let max_fee = self.chain_spec.max_fee();
+
+        let signature = vec![];
+
+        let request =
+            BroadcastedInvokeTransactionV1 { max_fee, signature, nonce, sender_address: starknet_address, calldata };
+
+        let starknet_transaction_hash = self.submit_starknet_transaction(request).await?;
+        self.transaction_index.index_transaction(transaction.hash(), starknet_transaction_hash);
+        // Served as pending until the Starknet node serves it
+        self.pending_transactions.insert(EtherTransaction {
+            hash: starknet_transaction_hash,
+            from: evm_address,
+            ..rpc_transaction(&transaction)
+        });
+
+        Ok(starknet_transaction_hash)
+    }
+
+    /// Polls the Starknet status of the tracked transactions of the sender and forgets the ones
+    /// the Starknet node serves, either included, in the pending block or rejected.
+    async fn refresh_pending_transactions(&self, sender: Address) {
+        let hashes = self.pending_transactions.hashes_of(sender);
+        let statuses = join_all(hashes.into_iter().map(|hash| async move {
+            let starknet_hash: FieldElement = match Felt252Wrapper::try_from(hash) {
+                Ok(starknet_hash) => starknet_hash.into(),
+                Err(_) => return (hash, false),
+            };
+            (hash, self.starknet_provider.get_transaction_receipt(starknet_hash).await.is_ok())
+        }))
+        .await;
+        for (hash, served) in statuses {
+            if served {
+                self.pending_transactions.remove(&hash);
+            }
+        }
+    }
+
+    /// Deploys the Kakarot account of the EVM address from the relayer pool if it isn't deployed
+    /// yet, returning the hash of the Starknet transaction deploying it.
+    async fn deploy_account_if_needed(
+        &self,
+        relayer: &RelayerPool,
+        evm_address: Address,
+        starknet_address: FieldElement,
+    ) -> Result<Option<FieldElement>, EthApiError<P::Error>> {
+        let pending = StarknetBlockId::Tag(BlockTag::Pending);
+        match self.starknet_provider.get_class_hash_at(pending, starknet_address).await {
+            Ok(_) => return Ok(None),
+            Err(ProviderError::StarknetError(StarknetError::ContractNotFound)) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let evm_address: Felt252Wrapper = evm_address.into();
+        let deploy = Call {
+            to: self.kakarot_address(),
+            selector: DEPLOY_EXTERNALLY_OWNED_ACCOUNT,
+            calldata: vec![evm_address.into()],
+        };
+        let transaction_hash = relayer.execute(self.starknet_provider.as_ref(), &[deploy]).await?;
+        tracing::info!("Deploying the Kakarot account {starknet_address:#x} in {transaction_hash:#x}");
+        Ok(Some(transaction_hash))
+    }
+
+    /// Deploys the Kakarot account of the sender of a relayed transaction if it isn't deployed yet,
+    /// and waits for the deployment to be executed, so that the transaction is validated against
+    /// the deployed account. The deployment is paid by the relayer account and isn't reimbursed by
+    /// the sender.
+    async fn deploy_sender_if_needed(
+        &self,
+        relayer: &RelayerPool,
+        evm_address: Address,
+        starknet_address: FieldElement,
+    ) -> Result<(), EthApiError<P::Error>> {
+        if let Some(transaction_hash) = self.deploy_account_if_needed(relayer, evm_address, starknet_address).await? {
+            self.wait_for_execution(transaction_hash).await?;
+        }
+        Ok(())
+    }
+
+    /// Polls the receipt of the Starknet transaction until it is executed, in the pending block or
+    /// in a block, failing if it is rejected or not executed after [`DEPLOYMENT_TIMEOUT`].
+    async fn wait_for_execution(&self, transaction_hash: FieldElement) -> Result<(), EthApiError<P::Error>> {
+        let deadline = Instant::now() + DEPLOYMENT_TIMEOUT;
+        loop {
+            match self.starknet_provider.get_transaction_receipt(transaction_hash).await {
+                Ok(MaybePendingTransactionReceipt::Receipt(StarknetTransactionReceipt::Invoke(receipt)))
+                    if receipt.status == TransactionStatus::Rejected =>
+                {
+                    return Err(anyhow::anyhow!("transaction {transaction_hash:#x} was rejected").into());
+                }
+                Ok(_) => return Ok(()),
+                Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => {}
+                Err(err) => return Err(err.into()),
+            }
+            if Instant::now() >= deadline {
+                return Err(anyhow::anyhow!(
+                    "transaction {transaction_hash:#x} not executed after {}s",
+                    DEPLOYMENT_TIMEOUT.as_secs()
+                )
+                .into());
+            }
+            tokio::time::sleep(DEPLOYMENT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Relays the transaction from `evm_address`, rejecting it as `already known` if it was
+    /// already relayed recently, unless its relay failed.
+    async fn relay_once(
+        &self,
+        evm_address: Address,
+        transaction: TransactionSigned,
+        bytes: Bytes,
+    ) -> Result<H256, EthApiError<P::Error>> {
+        let hash = transaction.hash();
+        if !self.relayed_transactions.insert(hash) {
+            return Err(InvalidTransactionError::AlreadyKnown.into());
+        }
+        let result = self.relay_transaction(evm_address, transaction, bytes).await;
+        if result.is_err() {
+            self.relayed_transactions.remove(&hash);
+        }
+        result
+    }
+
+    /// Fetches the receipt of a transaction from the Starknet provider and converts it. The
+    /// receipt depends on the transactions before it in its block, whose receipts are converted
+    /// along with it.
+    async fn fetch_transaction_receipt(&self, hash: H256) -> Result<Option<TransactionReceipt>, EthApiError<P::Error>> {
+        // TODO: Error when trying to transform 32 bytes hash to FieldElement
+        let transaction_hash: Felt252Wrapper = hash.try_into()?;
+        let block_hash =
+            match self.starknet_provider.get_transaction_receipt::<FieldElement>(transaction_hash.into()).await {
+                Ok(MaybePendingTransactionReceipt::Receipt(StarknetTransactionReceipt::Invoke(receipt))) => {
+                    receipt.block_hash
+                }
+                // L1Handler, Declare, Deploy and DeployAccount transactions unsupported for now in
+                // Kakarot, and pending receipts have no block yet
+                _ => return Ok(None),
+            };
+
+        let receipts = self.fetch_block_receipts(StarknetBlockId::Hash(block_hash)).await?.unwrap_or_default();
+        Ok(receipts.into_iter().find(|receipt| receipt.transaction_hash == Some(hash)))
+    }
+
+    /// Fetches the block with its transactions once and converts the receipts of its Kakarot
+    /// transactions in a single pass, each receipt being cached. Returns `None` for the pending
+    /// block, whose receipts change.
+    async fn fetch_block_receipts(
+        &self,
+        block_id: StarknetBlockId,
+    ) -> Result<Option<Vec<TransactionReceipt>>, EthApiError<P::Error>> {
+        let block = match self.starknet_provider.get_block_with_txs(block_id).await? {
+            MaybePendingBlockWithTxs::Block(block) => block,
+            MaybePendingBlockWithTxs::PendingBlock(_) => return Ok(None),
+        };
+        let block_hash: H256 = Felt252Wrapper::from(block.block_hash).into();
+        let block_number = U256::from(block.block_number);
+
+        // The transactions which aren't Kakarot transactions are filtered out by their conversion
+        let transactions: Vec<EtherTransaction> = stream::iter(block.transactions)
+            .map(|transaction| async move {
+                let transaction: StarknetTransaction = transaction.into();
+                transaction.to_eth_transaction(self, Some(block_hash), Some(block_number), None).await.ok()
+            })
+            .buffered(self.transaction_conversion_concurrency)
+            .filter_map(futures::future::ready)
+            .collect()
+            .await;
+
+        let transaction_hashes = transactions
+            .iter()
+            .map(|transaction| Felt252Wrapper::try_from(transaction.hash).map(Into::into))
+            .collect::<Result<Vec<FieldElement>, _>>()?;
+        let starknet_receipts: Vec<MaybePendingTransactionReceipt> = stream::iter(transaction_hashes)
+            .map(|hash| self.starknet_provider.get_transaction_receipt(hash))
+            .buffered(self.transaction_conversion_concurrency)
+            .try_collect()
+            .await?;
+
+        let gas_price = self.block_gas_price(block.block_number).await?;
+        let mut context = BlockReceiptContext::new(block_hash, block.block_number, gas_price);
+        let mut receipts = Vec::with_capacity(transactions.len());
+        for (transaction, receipt) in transactions.iter().zip(starknet_receipts) {
+            if let MaybePendingTransactionReceipt::Receipt(StarknetTransactionReceipt::Invoke(receipt)) = receipt {
+                receipts.push(context.receipt(self, transaction, receipt)?);
+            }
+        }
+
+        if let Some(cache) = &self.block_cache {
+            receipts.iter().for_each(|receipt| cache.insert_receipt(receipt));
+        }
+        Ok(Some(receipts))
+    }
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
+    /// Returns the latest block number
+    async fn block_number(&self) -> Result<U64, EthApiError<P::Error>> {
+        let block_number = self.starknet_provider.block_number().await?;
+        Ok(block_number.into())
+    }
+
+    /// Returns whether the client caches data of the chain tip, which must be invalidated by
+    /// [`KakarotEthApi::handle_new_head`] on each new block.
+    fn caches_chain_tip(&self) -> bool {
+        self.block_cache.is_some() || self.state_cache.is_some()
+    }
+
+    /// Returns the counters of the caches by name, empty when the caches are disabled.
+    fn cache_stats(&self) -> BTreeMap<String, CacheStats> {
+        let block_cache_stats = self.block_cache.iter().flat_map(BlockCache::stats);
+        let state_cache_stats = self.state_cache.iter().flat_map(StateCache::stats);
+        block_cache_stats.chain(state_cache_stats).map(|(name, stats)| (name.to_string(), stats)).collect()
+    }
+
+    /// Returns the statistics of the Kakarot transactions of the block, aggregated from their
+    /// receipts converted in a single pass and cached. Returns `None` for the pending block, whose
+    /// statistics change.
+    async fn block_stats(&self, block_id: BlockId) -> Result<Option<BlockStats>, EthApiError<P::Error>> {
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
+        if let (Some(cache), StarknetBlockId::Number(number)) = (&self.block_cache, starknet_block_id) {
+            if let Some(stats) = cache.get_block_stats(number) {
+                return Ok(Some(stats));
+            }
+        }
+
+        let block = self.get_eth_block_from_starknet_block(starknet_block_id, false).await?;
+        let (Some(number), Some(hash)) = (block.header.number, block.header.hash) else {
+            return Ok(None);
+        };
+        // The receipts are pinned to the block of the header, the chain tip moving in between
+        let block_hash: Felt252Wrapper = hash.try_into()?;
+        let receipts = self.fetch_block_receipts(StarknetBlockId::Hash(block_hash.into())).await?.unwrap_or_default();
+
+        let stats = BlockStats::from_receipts(number.to::<u64>(), hash, &receipts);
+        if let Some(cache) = &self.block_cache {
+            cache.insert_block_stats(stats);
+        }
+        Ok(Some(stats))
+    }
+
+    /// Invalidates the cached data made stale by a new block: the blocks after a reorganization of
+    /// the chain, and the chain tip state changed by the block. The whole chain tip state is
+    /// dropped if the changes of the block can't be fetched.
+    async fn handle_new_head(&self, block_number: u64) -> Result<(), EthApiError<P::Error>> {
+        let changes = self.new_head_state_changes(block_number).await;
+        if let Some(state_cache) = &self.state_cache {
+            state_cache.apply_new_head(block_number, changes.as_ref().ok().and_then(Option::as_ref));
+        }
+        changes.map(|_| ())
+    }
+
+    /// Returns the hash and the parent hash of a block, fetched from the Starknet node rather than
+    /// the caches so that a reorganization is seen. Returns `None` for the pending block.
+    async fn block_link(&self, block_number: u64) -> Result<Option<BlockLink>, EthApiError<P::Error>> {
+        let block = self.starknet_provider.get_block_with_tx_hashes(StarknetBlockId::Number(block_number)).await?;
+        match block {
+            MaybePendingBlockWithTxHashes::Block(block) => Ok(Some(BlockLink {
+                number: block_number,
+                hash: Felt252Wrapper::from(block.block_hash).into(),
+                parent_hash: Felt252Wrapper::from(block.parent_hash).into(),
+            })),
+            MaybePendingBlockWithTxHashes::PendingBlock(_) => Ok(None),
+        }
+    }
+
+    /// Drops the data of the blocks from `from_block` onwards, reverted by a reorganization of the
+    /// chain: the cached blocks and chain tip state, the indexed logs and transaction hashes, and
+    /// the cursors of the filters which polled them.
+    fn handle_reorg(&self, from_block: u64) {
+        if let Some(block_cache) = &self.block_cache {
+            block_cache.invalidate_from(from_block);
+        }
+        if let Some(state_cache) = &self.state_cache {
+            state_cache.apply_new_head(from_block.saturating_sub(1), None);
+        }
+        if let Some(log_index) = &self.log_index {
+            if let Err(err) = log_index.rollback(from_block) {
+                tracing::warn!("Failed to roll back the log index from block {from_block}: {err}");
+            }
+        }
+        self.transaction_index.rollback(from_block);
+        self.filters.rewind(from_block);
+    }
+
+    /// Returns the bytecode of a contract given its address and a block id.
+    #[tracing::instrument(skip(self))]
+    async fn get_code(&self, ethereum_address: Address, block_id: BlockId) -> Result<Bytes, EthApiError<P::Error>> {
+        let _state = self.state_override_lock.read().await;
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
+
+        let latest_state_cache = self.latest_state_cache(&starknet_block_id);
+        if let Some(bytecode) = latest_state_cache.and_then(|(cache, _)| cache.get_code(&ethereum_address)) {
+            return Ok(bytecode);
+        }
+
+        // Convert the hex-encoded string to a FieldElement
+        let evm_address: Felt252Wrapper = ethereum_address.into();
+        let evm_address = evm_address.into();
+
+        let starknet_contract_address =
+            self.kakarot_contract.compute_starknet_address(&evm_address, &starknet_block_id).await?;
+
+        let provider = self.starknet_provider();
+        let contract_account = ContractAccount::new(starknet_contract_address, &provider);
+        let bytecode = contract_account.bytecode(&starknet_block_id).await?;
+
+        if let Some((cache, head)) = latest_state_cache {
+            cache.insert_code(head, ethereum_address, starknet_contract_address, bytecode.clone());
+        }
+
+        // Convert the result of the function call to a vector of bytes
+        Ok(bytecode)
+    }
+
+    /// Computes the Starknet addresses of the Kakarot accounts of the EVM addresses locally, from
+    /// the configured Kakarot address and proxy account class hash.
+    fn compute_starknet_addresses(&self, ethereum_addresses: &[Address]) -> Vec<FieldElement> {
+        let (kakarot_address, proxy_account_class_hash) = (self.kakarot_address(), self.proxy_account_class_hash());
+        ethereum_addresses
+            .iter()
+            .map(|address| compute_starknet_address(kakarot_address, proxy_account_class_hash, *address))
+            .collect()
+    }
+
+    /// Returns the type of the Kakarot account backing the EVM address, along with its Starknet
+    /// address and class hashes. Accounts without bytecode are EOAs.
+    async fn account_details(
+        &self,
+        ethereum_address: Address,
+        block_id: BlockId,
+    ) -> Result<AccountDetails, EthApiError<P::Error>> {
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
+        let starknet_address = self.compute_starknet_address(ethereum_address, &starknet_block_id).await?;
+
+        let class_hash = match self.starknet_provider.get_class_hash_at(starknet_block_id, starknet_address).await {
+            Ok(class_hash) => class_hash,
+            Err(ProviderError::StarknetError(StarknetError::ContractNotFound)) => {
+                return Ok(AccountDetails::undeployed(starknet_address));
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let provider = self.starknet_provider();
+        let account = KakarotAccount::new(starknet_address, &provider);
+        // Accounts deployed before the proxy exposed its implementation have none
+        let implementation_class_hash = account.implementation_class_hash(&starknet_block_id).await.ok();
+        let bytecode = account.bytecode(&starknet_block_id).await?;
+        let account_type = if bytecode.is_empty() { AccountType::Eoa } else { AccountType::Contract };
+
+        Ok(AccountDetails { account_type, starknet_address, class_hash: Some(class_hash), implementation_class_hash })
+    }
+
+    /// Returns the logs corresponding to the filter
+    #[tracing::instrument(skip_all, fields(block_option = ?filter.block_option))]
+    async fn get_logs(&self, filter: Filter) -> Result<Vec<Log>, EthApiError<P::Error>> {
+        let max_results = self.logs_config.max_results;
+
+        // A block hash filter targets a single block
+        if filter.get_block_hash().is_some() {
+            let event_filter = EthEventFilter::from(filter).to_starknet_filter(self)?;
+            let events = self.paginate_events(event_filter, max_results).await?;
+            return Ok(self.emitted_events_to_logs(events));
+        }
+
+        // Check the block range, the pending block following the current block
+        let current_block: u64 = self.block_number().await?.low_u64();
+        let from_block = self.log_filter_block(filter.block_option.get_from_block(), current_block).await?;
+        let to_block = self.log_filter_block(filter.block_option.get_to_block(), current_block).await?;
+        let include_pending = to_block.is_none();
+        let from_block = from_block.unwrap_or(current_block + 1);
+        let to_block = to_block.unwrap_or(current_block).min(current_block);
+        let pending_only = include_pending && from_block == current_block + 1;
+        if from_block > to_block && !pending_only {
+            return Ok(vec![]);
+        }
+
+        // A block range entirely indexed is served from the log index
+        let log_index =
+            self.log_index.as_ref().filter(|log_index| !include_pending && log_index.covers(from_block, to_block));
+        if let Some(log_index) = log_index {
+            match log_index.logs(&filter, from_block, to_block) {
+                Ok(logs) if logs.len() > max_results => return Err(EthApiError::TooManyResults(max_results)),
+                Ok(logs) => return Ok(logs),
+                Err(err) => tracing::warn!("Failed to read the log index, querying the Starknet events: {err}"),
+            }
+        }
+
+        // Convert the eth log filter to a starknet event filter, whose block range is set by chunk
+        let filter: EthEventFilter = filter.into();
+        let event_filter = filter.to_starknet_filter(self)?;
+
+        // Query the chunks concurrently, merging them back in block order. The events of the pending
+        // block are included in the last chunk, or queried alone when only the pending block is.
+        let block_chunks: Vec<(StarknetBlockId, StarknetBlockId)> = if pending_only {
+            vec![(StarknetBlockId::Tag(BlockTag::Pending), StarknetBlockId::Tag(BlockTag::Pending))]
+        } else {
+            block_range_chunks(from_block, to_block, self.logs_config.block_range_chunk_size)
+                .into_iter()
+                .map(|(chunk_from, chunk_to)| {
+                    let chunk_to = if include_pending && chunk_to == to_block {
+                        StarknetBlockId::Tag(BlockTag::Pending)
+                    } else {
+                        StarknetBlockId::Number(chunk_to)
+                    };
+                    (StarknetBlockId::Number(chunk_from), chunk_to)
+                })
+                .collect()
+        };
+        let mut chunks = stream::iter(block_chunks)
+            .map(|(chunk_from, chunk_to)| {
+                let event_filter =
+                    EventFilter { from_block: Some(chunk_from), to_block: Some(chunk_to), ..event_filter.clone() };
+                self.paginate_events(event_filter, max_results)
+            })
+            .buffered(LOGS_CHUNK_CONCURRENCY);
+
+        let mut logs = vec![];
+        while let Some(events) = chunks.next().await {
+            logs.extend(self.emitted_events_to_logs(events?));
+            if logs.len() > max_results {
+                return Err(EthApiError::TooManyResults(max_results));
+            }
+        }
+        Ok(logs)
+    }
+
+    /// Indexes the logs of the inclusive block range in the log index, returning the number of
+    /// indexed logs. Fails when the log index is disabled.
+    async fn index_logs(&self, from_block: u64, to_block: u64) -> Result<usize, EthApiError<P::Error>> {
+        let log_index =
+            self.log_index.as_ref().ok_or_else(|| EthApiError::Other(anyhow::anyhow!("the log index is disabled")))?;
+
+        let event_filter = EventFilter {
+            from_block: Some(StarknetBlockId::Number(from_block)),
+            to_block: Some(StarknetBlockId::Number(to_block)),
+            address: None,
+            keys: None,
+        };
+        // All the logs of the range are needed for the index to cover it
+        let events = self.paginate_events(event_filter, usize::MAX).await?;
+        let logs = self.emitted_events_to_logs(events);
+        let indexed_logs = logs.len();
+        log_index.insert(from_block, to_block, logs).map_err(|err| EthApiError::Other(anyhow::anyhow!(err)))?;
+
+        Ok(indexed_logs)
+    }
+
+    /// Returns true if the logs are indexed locally.
+    fn log_index_enabled(&self) -> bool {
+        self.log_index.is_some()
+    }
+
+    /// Returns the result of executing a call on a ethereum address for a given calldata and block
+    /// without creating a transaction.
+    /// Overrides of the state are applied for the duration of the call by the state override
+    /// backend, at the latest or pending block only.
+    async fn call(
+        &self,
+        to: Address,
+        calldata: Bytes,
+        block_id: BlockId,
+        state_override: Option<StateOverride>,
+    ) -> Result<Bytes, EthApiError<P::Error>> {
+        let state_override =
+            state_override.filter(|state_override| !state_override.is_empty() && to != *NATIVE_TOKEN_ERC20_ADDRESS);
+        if let Some(state_override) = state_override {
+            return self.call_with_state_override(to, calldata, block_id, state_override).await;
+        }
+
+        let _state = self.state_override_lock.read().await;
+        if to == *NATIVE_TOKEN_ERC20_ADDRESS {
+            return self.native_token_erc20_call(calldata, block_id).await;
+        }
+        self.kakarot_call(to, calldata, block_id).await
+    }
+
     /// Get the syncing status of the light client
     async fn syncing(&self) -> Result<SyncStatus, EthApiError<P::Error>> {
         let status = self.starknet_provider.syncing().await?;
@@ -213,27 +1542,9 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
 
     /// Returns the number of transactions in a block given a block id.
     async fn get_transaction_count_by_block(&self, block_id: BlockId) -> Result<U64, EthApiError<P::Error>> {
-        let starknet_block_id: StarknetBlockId = EthBlockId::new(block_id).try_into()?;
-        let starknet_block = self.starknet_provider.get_block_with_txs(starknet_block_id).await?;
-
-        let block_transactions = match starknet_block {
-            MaybePendingBlockWithTxs::PendingBlock(pending_block_with_txs) => {
-                self.filter_starknet_into_eth_txs(pending_block_with_txs.transactions.into(), None, None).await
-            }
-            MaybePendingBlockWithTxs::Block(block_with_txs) => {
-                let block_hash: Felt252Wrapper = block_with_txs.block_hash.into();
-                let block_hash = Some(block_hash.into());
-                let block_number: Felt252Wrapper = block_with_txs.block_number.into();
-                let block_number = Some(block_number.into());
-                self.filter_starknet_into_eth_txs(block_with_txs.transactions.into(), block_hash, block_number).await
-            }
-        };
-        let len = match block_transactions {
-            BlockTransactions::Full(transactions) => transactions.len(),
-            BlockTransactions::Hashes(_) => 0,
-            BlockTransactions::Uncle => 0,
-        };
-        Ok(U64::from(len))
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
+        let transactions = self.kakarot_transactions(starknet_block_id).await?;
+        Ok(U64::from(transactions.len()))
     }
 
     /// Returns the transaction for a given block id and transaction index.
@@ -243,7 +1554,7 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
         tx_index: Index,
     ) -> Result<EtherTransaction, EthApiError<P::Error>> {
         let index: u64 = usize::from(tx_index) as u64;
-        let starknet_block_id: StarknetBlockId = EthBlockId::new(block_id).try_into()?;
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
 
         let starknet_tx: StarknetTransaction =
             self.starknet_provider.get_transaction_by_block_id_and_index(starknet_block_id, index).await?.into();
@@ -264,145 +1575,67 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
     }
 
     /// Returns the transaction for a given transaction hash.
+    #[tracing::instrument(skip(self))]
     async fn transaction_by_hash(&self, hash: H256) -> Result<Option<EtherTransaction>, EthApiError<P::Error>> {
-        let hash: Felt252Wrapper = hash.try_into()?;
-        let hash: FieldElement = hash.into();
-
-        let transaction: StarknetTransaction = match self.starknet_provider.get_transaction_by_hash(hash).await {
-            Err(_) => return Ok(None),
-            Ok(transaction) => transaction.into(),
-        };
-
-        let tx_receipt = match self.starknet_provider.get_transaction_receipt(hash).await {
-            Err(_) => return Ok(None),
-            Ok(receipt) => receipt,
-        };
+        // Kakarot transaction hashes are the hashes of the Starknet transactions
+        if let Some(transaction) = self.transaction_by_starknet_hash(hash).await? {
+            self.pending_transactions.remove(&hash);
+            return Ok(Some(transaction));
+        }
 
-        let (block_hash, block_num) = match tx_receipt {
-            MaybePendingTransactionReceipt::Receipt(StarknetTransactionReceipt::Invoke(tr)) => {
-                let block_hash: Felt252Wrapper = tr.block_hash.into();
-                (Some(block_hash.into()), Some(U256::from(tr.block_number)))
-            }
-            _ => (None, None), // skip all transactions other than Invoke, covers the pending case
+        // Ethereum clients compute the hash of the signed transaction themselves, look it up
+        let starknet_hash = match self.find_starknet_transaction_hash(hash).await? {
+            Some(starknet_hash) => starknet_hash,
+            None => return Ok(self.pending_transactions.get(&hash)),
         };
-        let eth_transaction = transaction.to_eth_transaction(self, block_hash, block_num, None).await?;
-        Ok(Some(eth_transaction))
-    }
-
-    /// Returns the receipt of a transaction by transaction hash.
-    async fn transaction_receipt(&self, hash: H256) -> Result<Option<TransactionReceipt>, EthApiError<P::Error>> {
-        // TODO: Error when trying to transform 32 bytes hash to FieldElement
-        let transaction_hash: Felt252Wrapper = hash.try_into()?;
-        let starknet_tx_receipt =
-            match self.starknet_provider.get_transaction_receipt::<FieldElement>(transaction_hash.into()).await {
-                Err(_) => return Ok(None),
-                Ok(receipt) => receipt,
-            };
-
-        let res_receipt = match starknet_tx_receipt {
-            MaybePendingTransactionReceipt::Receipt(receipt) => match receipt {
-                StarknetTransactionReceipt::Invoke(InvokeTransactionReceipt {
-                    transaction_hash,
-                    status,
-                    block_hash,
-                    block_number,
-                    events,
-                    ..
-                }) => {
-                    let starknet_tx: StarknetTransaction =
-                        self.starknet_provider.get_transaction_by_hash(transaction_hash).await?.into();
-
-                    let transaction_hash: Felt252Wrapper = transaction_hash.into();
-                    let transaction_hash: Option<H256> = Some(transaction_hash.into());
-
-                    let block_hash: Felt252Wrapper = block_hash.into();
-                    let block_hash: Option<H256> = Some(block_hash.into());
-
-                    let block_number: Felt252Wrapper = block_number.into();
-                    let block_number: Option<U256> = Some(block_number.into());
-
-                    let eth_tx = starknet_tx.to_eth_transaction(self, None, None, None).await?;
-                    let from = eth_tx.from;
-                    let to = eth_tx.to;
-                    let contract_address = match to {
-                        // If to is Some, means contract_address should be None as it is a normal transaction
-                        Some(_) => None,
-                        // If to is None, is a contract creation transaction so contract_address should be Some
-                        None => {
-                            let event = events
-                                .iter()
-                                .find(|event| event.keys.iter().any(|key| *key == EVM_CONTRACT_DEPLOYED))
-                                .ok_or(EthApiError::Other(anyhow::anyhow!(
-                                    "Kakarot Core: No contract deployment event found in Kakarot transaction receipt"
-                                )))?;
-
-                            let evm_address =
-                                event.data.first().ok_or(DataDecodingError::InvalidReturnArrayLength {
-                                    entrypoint: "deployment".into(),
-                                    expected: 1,
-                                    actual: 0,
-                                })?;
-
-                            let evm_address = Felt252Wrapper::from(*evm_address);
-                            Some(evm_address.try_into()?)
-                        }
-                    };
-
-                    let status_code = match status {
-                        StarknetTransactionStatus::Rejected | StarknetTransactionStatus::Pending => Some(U64::from(0)),
-                        StarknetTransactionStatus::AcceptedOnL1 | StarknetTransactionStatus::AcceptedOnL2 => {
-                            Some(U64::from(1))
-                        }
-                    };
-
-                    let logs = events
-                        .into_iter()
-                        .map(StarknetEvent::new)
-                        .filter_map(|event| {
-                            event.to_eth_log(self, block_hash, block_number, transaction_hash, None, None).ok()
-                        })
-                        .collect();
-
-                    TransactionReceipt {
-                        transaction_hash,
-                        // TODO: transition this hardcoded default out of nearing-demo-day hack and seeing how to
-                        // properly source/translate this value
-                        transaction_index: Some(U256::ZERO),
-                        block_hash,
-                        block_number,
-                        from,
-                        to,
-                        cumulative_gas_used: U256::from(1_000_000), // TODO: Fetch real data
-                        gas_used: Some(U256::from(500_000)),
-                        contract_address,
-                        logs,
-                        state_root: None,             // TODO: Fetch real data
-                        logs_bloom: Bloom::default(), // TODO: Fetch real data
-                        status_code,
-                        effective_gas_price: U128::from(1_000_000), // TODO: Fetch real data
-                        transaction_type: U8::from(0),              // TODO: Fetch real data
-                    }
-                }
-                // L1Handler, Declare, Deploy and DeployAccount transactions unsupported for now in
-                // Kakarot
-                _ => return Ok(None),
-            },
-            MaybePendingTransactionReceipt::PendingReceipt(_) => {
-                return Ok(None);
+        match self.transaction_by_starknet_hash(starknet_hash).await? {
+            Some(transaction) => {
+                self.pending_transactions.remove(&starknet_hash);
+                Ok(Some(transaction))
             }
-        };
+            // Submitted through the RPC but not served by the Starknet node yet
+            None => Ok(self.pending_transactions.get(&starknet_hash)),
+        }
+    }
+
+    /// Returns the receipt of a transaction by transaction hash.
+    #[tracing::instrument(skip(self))]
+    async fn transaction_receipt(&self, hash: H256) -> Result<Option<TransactionReceipt>, EthApiError<P::Error>> {
+        if let Some(receipt) = self.block_cache.as_ref().and_then(|cache| cache.get_receipt(&hash)) {
+            return Ok(Some(receipt));
+        }
+
+        let receipt = self.fetch_transaction_receipt(hash).await?;
+        if let (Some(cache), Some(receipt)) = (&self.block_cache, &receipt) {
+            cache.insert_receipt(receipt);
+        }
+        Ok(receipt)
+    }
 
-        Ok(Some(res_receipt))
+    /// Returns the receipts of the Kakarot transactions of a block, in the order of the block, or
+    /// `None` for the pending block.
+    #[tracing::instrument(skip(self))]
+    async fn block_receipts(
+        &self,
+        block_id: BlockId,
+    ) -> Result<Option<Vec<TransactionReceipt>>, EthApiError<P::Error>> {
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
+        self.fetch_block_receipts(starknet_block_id).await
     }
 
     /// Returns the nonce for a given ethereum address
     /// if ethereum -> stark mapping doesn't exist in the starknet provider, we translate
     /// ContractNotFound errors into zeros
+    /// The pending nonce counts the transactions submitted through the RPC which the Starknet node
+    /// doesn't serve yet.
+    #[tracing::instrument(skip(self))]
     async fn nonce(&self, ethereum_address: Address, block_id: BlockId) -> Result<U256, EthApiError<P::Error>> {
-        let starknet_block_id: StarknetBlockId = EthBlockId::new(block_id).try_into()?;
+        let _state = self.state_override_lock.read().await;
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
         let starknet_address = self.compute_starknet_address(ethereum_address, &starknet_block_id).await?;
 
-        self.starknet_provider
+        let nonce = self
+            .starknet_provider
             .get_nonce(starknet_block_id, starknet_address)
             .await
             .map(|nonce| {
@@ -412,31 +1645,54 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
             .or_else(|err| match err {
                 ProviderError::StarknetError(StarknetError::ContractNotFound) => Ok(U256::from(0)),
                 _ => Err(EthApiError::from(err)),
-            })
+            })?;
+
+        if !matches!(starknet_block_id, StarknetBlockId::Tag(BlockTag::Pending)) {
+            return Ok(nonce);
+        }
+        self.refresh_pending_transactions(ethereum_address).await;
+        Ok(self
+            .pending_transactions
+            .next_nonce(ethereum_address)
+            .map_or(nonce, |tracked_nonce| tracked_nonce.max(nonce)))
     }
 
-    /// Returns the balance in Starknet's native token of a specific EVM address.
+    /// Returns the balance in the native token of a specific EVM address, which may differ from the
+    /// token paying the Starknet fees.
+    #[tracing::instrument(skip(self))]
     async fn balance(&self, ethereum_address: Address, block_id: BlockId) -> Result<U256, EthApiError<P::Error>> {
-        let starknet_block_id: StarknetBlockId = EthBlockId::new(block_id).try_into()?;
+        let _state = self.state_override_lock.read().await;
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
+
+        let latest_state_cache = self.latest_state_cache(&starknet_block_id);
+        if let Some(balance) = latest_state_cache.and_then(|(cache, _)| cache.get_balance(&ethereum_address)) {
+            return Ok(balance);
+        }
+
         let starknet_address = self.compute_starknet_address(ethereum_address, &starknet_block_id).await?;
 
-        let native_token_address = FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap();
         let provider = self.starknet_provider();
-        let native_token = StarknetErc20::new(&provider, native_token_address);
+        let native_token = StarknetErc20::new(&provider, self.native_token_address);
         let balance = native_token.balance_of(&starknet_address, &starknet_block_id).await?;
 
+        if let Some((cache, head)) = latest_state_cache {
+            cache.insert_balance(head, ethereum_address, starknet_address, balance);
+        }
+
         Ok(balance)
     }
 
     /// Returns the storage value at a specific index of a contract given its address and a block
     /// id.
+    #[tracing::instrument(skip(self))]
     async fn storage_at(
         &self,
         address: Address,
         index: U256,
         block_id: BlockId,
     ) -> Result<U256, EthApiError<P::Error>> {
-        let starknet_block_id: StarknetBlockId = EthBlockId::new(block_id).try_into()?;
+        let _state = self.state_override_lock.read().await;
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
 
         let address: Felt252Wrapper = address.into();
         let address = address.into();
@@ -444,15 +1700,11 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
         let starknet_contract_address =
             self.kakarot_contract.compute_starknet_address(&address, &starknet_block_id).await?;
 
-        let key_low = index & U256::from(u128::MAX);
-        let key_low: Felt252Wrapper = key_low.try_into()?;
-
-        let key_high = index >> 128;
-        let key_high: Felt252Wrapper = key_high.try_into()?;
+        let [key_low, key_high] = u256_to_felts(index);
 
         let provider = self.starknet_provider();
         let contract_account = ContractAccount::new(starknet_contract_address, &provider);
-        let storage_value = contract_account.storage(&key_low.into(), &key_high.into(), &starknet_block_id).await?;
+        let storage_value = contract_account.storage(&key_low, &key_high, &starknet_block_id).await?;
 
         Ok(storage_value)
     }
@@ -478,34 +1730,180 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
     }
 
     /// Sends raw Ethereum transaction bytes to Kakarot
+    /// A transaction already relayed recently is rejected as `already known`, unless its relay
+    /// failed.
     async fn send_transaction(&self, bytes: Bytes) -> Result<H256, EthApiError<P::Error>> {
-        let mut data = bytes.as_ref();
+        // Raw transactions are EIP-2718 envelopes: typed transactions aren't wrapped in a RLP string
+        let transaction =
+            TransactionSigned::decode_enveloped(bytes.clone()).map_err(DataDecodingError::TransactionDecodingError)?;
 
-        let transaction = TransactionSigned::decode(&mut data).map_err(DataDecodingError::TransactionDecodingError)?;
+        let evm_address = transaction.recover_signer().ok_or(InvalidTransactionError::InvalidSender)?;
+        self.relay_once(evm_address, transaction, bytes).await
+    }
 
-        let evm_address = transaction.recover_signer().ok_or_else(|| {
-            EthApiError::Other(anyhow::anyhow!("Kakarot send_transaction: signature ecrecover failed"))
-        })?;
+    /// Returns the Kakarot transaction wrapped in the Starknet transaction with the given hash,
+    /// `None` if the Starknet transaction doesn't exist or doesn't wrap a Kakarot transaction.
+    async fn transaction_by_starknet_hash(
+        &self,
+        hash: H256,
+    ) -> Result<Option<EtherTransaction>, EthApiError<P::Error>> {
+        // A hash which doesn't fit in a field element can't be a Starknet transaction hash
+        let hash: FieldElement = match Felt252Wrapper::try_from(hash) {
+            Ok(hash) => hash.into(),
+            Err(_) => return Ok(None),
+        };
+
+        let transaction: StarknetTransaction = match self.starknet_provider.get_transaction_by_hash(hash).await {
+            Ok(transaction) => transaction.into(),
+            Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let tx_receipt = match self.starknet_provider.get_transaction_receipt(hash).await {
+            Ok(receipt) => receipt,
+            Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let (block_hash, block_num) = match tx_receipt {
+            MaybePendingTransactionReceipt::Receipt(StarknetTransactionReceipt::Invoke(tr)) => {
+                let block_hash: Felt252Wrapper = tr.block_hash.into();
+                (Some(block_hash.into()), Some(U256::from(tr.block_number)))
+            }
+            _ => (None, None), // skip all transactions other than Invoke, covers the pending case
+        };
+        match transaction.to_eth_transaction(self, block_hash, block_num, None).await {
+            Ok(eth_transaction) => Ok(Some(eth_transaction)),
+            // Starknet transactions which aren't sent by a Kakarot account, e.g. from an explorer
+            Err(EthApiError::KakarotDataFilteringError(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the hash of the Starknet transaction wrapping the Ethereum transaction with the
+    /// given hash, i.e. the hash of the signed Ethereum transaction
+    async fn starknet_transaction_hash(&self, ethereum_hash: H256) -> Result<Option<H256>, EthApiError<P::Error>> {
+        self.find_starknet_transaction_hash(ethereum_hash).await
+    }
+
+    /// Returns the hash of the signed Ethereum transaction wrapped in the Starknet transaction with
+    /// the given hash
+    async fn ethereum_transaction_hash(&self, starknet_hash: H256) -> Result<Option<H256>, EthApiError<P::Error>> {
+        if let Some(hash) = self.transaction_index.get_ethereum_hash(&starknet_hash) {
+            return Ok(Some(hash));
+        }
+
+        // A hash which doesn't fit in a field element can't be a Starknet transaction hash
+        let hash: FieldElement = match Felt252Wrapper::try_from(starknet_hash) {
+            Ok(hash) => hash.into(),
+            Err(_) => return Ok(None),
+        };
+        let transaction: StarknetTransaction = match self.starknet_provider.get_transaction_by_hash(hash).await {
+            Ok(transaction) => transaction.into(),
+            Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        // Starknet transactions which don't wrap a Kakarot transaction have no Ethereum hash
+        let ethereum_hash = match transaction.ethereum_transaction_hash() {
+            Ok(hash) => hash,
+            Err(_) => return Ok(None),
+        };
+        self.transaction_index.index_transaction(ethereum_hash, starknet_hash);
+
+        Ok(Some(ethereum_hash))
+    }
+
+    /// Returns the accounts deployed by Kakarot, EOAs and contracts, in order of deployment, from
+    /// the `evm_contract_deployed` events emitted since the deployment of Kakarot.
+    async fn deployed_accounts(&self) -> Result<Vec<DeployedAccount>, EthApiError<P::Error>> {
+        let event_filter = EventFilter {
+            from_block: Some(StarknetBlockId::Number(self.kakarot_deployment_block)),
+            to_block: Some(StarknetBlockId::Tag(BlockTag::Latest)),
+            address: Some(self.kakarot_address()),
+            keys: Some(vec![vec![EVM_CONTRACT_DEPLOYED]]),
+        };
+        let events = self
+            .filter_events(EventFilterWithPage {
+                event_filter,
+                result_page_request: ResultPageRequest { continuation_token: None, chunk_size: CHUNK_SIZE_LIMIT },
+            })
+            .await?;
+
+        Ok(events.iter().filter_map(DeployedAccount::from_event).collect())
+    }
 
+    /// Deploys the Kakarot account of the EVM address from the relayer pool, returning the hash of
+    /// the Starknet transaction deploying it, or `None` if the account is already deployed.
+    async fn deploy_eoa(&self, ethereum_address: Address) -> Result<Option<H256>, EthApiError<P::Error>> {
+        let relayer = self
+            .relayer
+            .as_ref()
+            .ok_or_else(|| ConfigError::EnvironmentVariableSetWrong("no relayer account configured".to_string()))?;
         let starknet_block_id = StarknetBlockId::Tag(BlockTag::Latest);
+        let starknet_address = self.compute_starknet_address(ethereum_address, &starknet_block_id).await?;
+        let transaction_hash = self.deploy_account_if_needed(relayer, ethereum_address, starknet_address).await?;
+        Ok(transaction_hash.map(|hash| Felt252Wrapper::from(hash).into()))
+    }
 
-        let starknet_address = self.compute_starknet_address(evm_address, &starknet_block_id).await?;
+    /// Fills in the missing fields of the transaction, signs it with the key of the `from` account
+    /// and sends it to Kakarot
+    async fn send_unsigned_transaction(&self, request: CallRequest) -> Result<H256, EthApiError<P::Error>> {
+        let from =
+            request.from.ok_or_else(|| EthApiError::MissingParameterError("from for send_transaction".into()))?;
+        let impersonated = self.signer.is_impersonating(&from);
+        if !impersonated && !self.signer.has_account(&from) {
+            return Err(SignerError::UnknownAccount(from).into());
+        }
+        let transaction = self.fill_transaction(from, request).await?;
+        let signed_transaction = self.signer.sign_transaction(from, transaction)?;
 
-        let nonce = FieldElement::from(transaction.nonce());
+        let mut raw_transaction = BytesMut::new();
+        signed_transaction.encode_enveloped(&mut raw_transaction);
 
-        let calldata = raw_kakarot_calldata(self.kakarot_address(), bytes_to_felt_vec(&bytes));
+        // The signature of an impersonated account doesn't recover to it
+        if impersonated {
+            return self.relay_once(from, signed_transaction, raw_transaction.to_vec().into()).await;
+        }
+        self.send_transaction(raw_transaction.to_vec().into()).await
+    }
 
-        // Get estimated_fee from Starknet
-        let max_fee = *MAX_FEE;
+    /// Returns the accounts managed by the signer
+    fn accounts(&self) -> Vec<Address> {
+        self.signer.accounts()
+    }
 
-        let signature = vec![];
+    /// Signs the message with the key of the managed account, prefixed as in EIP-191.
+    fn sign(&self, address: Address, message: Bytes) -> Result<Bytes, EthApiError<P::Error>> {
+        Ok(self.signer.sign_message(address, &message)?)
+    }
 
-        let request =
-            BroadcastedInvokeTransactionV1 { max_fee, signature, nonce, sender_address: starknet_address, calldata };
+    /// Signs the transaction with the key of the managed `from` account, filling in the missing
+    /// nonce, gas and fees, and returns it encoded, ready for `eth_sendRawTransaction`.
+    async fn sign_transaction(&self, request: CallRequest) -> Result<Bytes, EthApiError<P::Error>> {
+        let from =
+            request.from.ok_or_else(|| EthApiError::MissingParameterError("from for sign_transaction".into()))?;
+        // The signature of an impersonated account doesn't recover to it, only the managed accounts
+        // sign
+        if !self.signer.has_account(&from) {
+            return Err(SignerError::UnknownAccount(from).into());
+        }
+        let transaction = self.fill_transaction(from, request).await?;
+        let signed_transaction = self.signer.sign_transaction(from, transaction)?;
 
-        let starknet_transaction_hash = self.submit_starknet_transaction(request).await?;
+        let mut raw_transaction = BytesMut::new();
+        signed_transaction.encode_enveloped(&mut raw_transaction);
+        Ok(raw_transaction.to_vec().into())
+    }
 
-        Ok(starknet_transaction_hash)
+    /// Signs the typed data following EIP-712 with the key of the managed account.
+    fn sign_typed_data(&self, address: Address, typed_data: &TypedData) -> Result<Bytes, EthApiError<P::Error>> {
+        Ok(self.signer.sign_typed_data(address, typed_data)?)
+    }
+
+    /// Returns the configured fee recipient, if any
+    fn coinbase(&self) -> Option<Address> {
+        self.coinbase
     }
 
     /// Returns the fixed base_fee_per_gas of Kakarot
@@ -513,41 +1911,66 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
     /// incentivize faster transaction inclusion
     /// As a result, in Kakarot, gas_price := base_fee_per_gas
     fn base_fee_per_gas(&self) -> U256 {
-        U256::from(BASE_FEE_PER_GAS)
+        self.chain_spec.base_fee_per_gas()
+    }
+
+    /// Returns the chain id of Kakarot
+    fn chain_id(&self) -> u64 {
+        self.chain_id
     }
 
     /// Returns the max_priority_fee_per_gas of Kakarot
     fn max_priority_fee_per_gas(&self) -> U128 {
-        MAX_PRIORITY_FEE_PER_GAS
+        self.chain_spec.max_priority_fee_per_gas()
+    }
+
+    /// Returns the gas limit of the blocks of Kakarot
+    fn block_gas_limit(&self) -> U256 {
+        self.chain_spec.block_gas_limit()
     }
 
     /// Returns the fee history of Kakarot ending at the newest block and going back `block_count`
+    /// blocks. Following the spec, fewer blocks are returned when the history starting at the
+    /// Kakarot deployment is shorter than `block_count`.
+    #[tracing::instrument(skip(self))]
     async fn fee_history(
         &self,
         block_count: U256,
         newest_block: BlockNumberOrTag,
-        _reward_percentiles: Option<Vec<f64>>,
+        reward_percentiles: Option<Vec<f64>>,
     ) -> Result<FeeHistory, EthApiError<P::Error>> {
-        let block_count_usize =
-            usize::try_from(block_count).map_err(|e| ConversionError::<()>::ValueOutOfRange(e.to_string()))?;
-
-        let base_fee = self.base_fee_per_gas();
+        let block_count =
+            u64::try_from(block_count).map_err(|e| ConversionError::<()>::ValueOutOfRange(e.to_string()))?;
+        if let Some(percentiles) = &reward_percentiles {
+            validate_reward_percentiles(percentiles).map_err(EthApiError::InvalidParameterError)?;
+        }
 
-        let base_fee_per_gas: Vec<U256> = vec![base_fee; block_count_usize + 1];
+        let latest_block = self.block_number().await?.as_u64();
         let newest_block = match newest_block {
             BlockNumberOrTag::Number(n) => n,
-            // TODO: Add Genesis block number
-            BlockNumberOrTag::Earliest => 1_u64,
-            _ => self.block_number().await?.as_u64(),
+            BlockNumberOrTag::Earliest => self.kakarot_deployment_block,
+            _ => latest_block,
+        };
+
+        // Clamp the range to the existing blocks in which Kakarot is deployed
+        let Some((oldest_block, block_count)) =
+            fee_history_range(block_count, newest_block, self.kakarot_deployment_block, latest_block)
+        else {
+            return Ok(FeeHistory {
+                base_fee_per_gas: vec![],
+                gas_used_ratio: vec![],
+                oldest_block: U256::ZERO,
+                reward: reward_percentiles.map(|_| vec![]),
+            });
         };
 
-        let gas_used_ratio: Vec<f64> = vec![0.9; block_count_usize];
-        let newest_block = U256::from(newest_block);
-        let oldest_block: U256 = if newest_block >= block_count { newest_block - block_count } else { U256::from(0) };
+        let blocks: Vec<BlockFees> = stream::iter(0..block_count)
+            .map(|offset| self.block_fees(oldest_block + offset))
+            .buffered(FEE_HISTORY_CONCURRENCY)
+            .try_collect()
+            .await?;
 
-        // TODO: transition `reward` hardcoded default out of nearing-demo-day hack and seeing how to
-        // properly source/translate this value
-        Ok(FeeHistory { base_fee_per_gas, gas_used_ratio, oldest_block, reward: Some(vec![vec![]]) })
+        Ok(build_fee_history(oldest_block, &blocks, self.block_gas_limit(), reward_percentiles.as_deref()))
     }
 
     /// Returns the estimated gas for a transaction
@@ -555,68 +1978,129 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
         match self.network {
             Network::MainnetGateway | Network::Goerli1Gateway | Network::Goerli2Gateway => (),
             _ => {
-                return Ok(*ESTIMATE_GAS);
+                return Ok(U256::from(self.chain_spec.default_gas_estimate));
             }
         };
 
-        let chain_id = request.chain_id.unwrap_or(CHAIN_ID.into());
-
-        let from = request.from.ok_or_else(|| EthApiError::MissingParameterError("from for estimate_gas".into()))?;
-        let nonce = self.nonce(from, block_id).await?.try_into().map_err(ConversionError::<u64>::from)?;
-
-        let gas_limit = request.gas.unwrap_or(U256::ZERO).try_into().map_err(ConversionError::<u64>::from)?;
-        let max_fee_per_gas = request
-            .max_fee_per_gas
-            .unwrap_or_else(|| U256::from(BASE_FEE_PER_GAS))
-            .try_into()
-            .map_err(ConversionError::<u128>::from)?;
-        let max_priority_fee_per_gas = request
-            .max_priority_fee_per_gas
-            .unwrap_or_else(|| U256::from(MAX_PRIORITY_FEE_PER_GAS))
-            .try_into()
-            .map_err(ConversionError::<u128>::from)?;
-
-        let to = request.to.map_or(TransactionKind::Create, TransactionKind::Call);
+        // The simulation runs on top of the requested block, not only the latest one
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
 
-        let value = request.value.unwrap_or(U256::ZERO).try_into().map_err(ConversionError::<u128>::from)?;
+        // The transaction must succeed with the gas limit of the request, or of the block
+        let gas_limit = match request.gas {
+            Some(gas) => gas.try_into().map_err(ConversionError::<u64>::from)?,
+            None => self.chain_spec.block_gas_limit,
+        };
+        let (_, tx) = self
+            .unsigned_invoke_transaction(CallRequest { gas: Some(U256::from(gas_limit)), ..request.clone() }, block_id)
+            .await?;
+        let fee_estimate = self
+            .simulate_transaction(tx, starknet_block_id, true)
+            .await
+            .map_err(EthApiError::map_revert)?
+            .fee_estimation;
+
+        let minimum_gas = self.chain_spec.minimum_gas;
+        let mut gas =
+            fee_to_gas(fee_estimate.overall_fee, fee_estimate.gas_price).clamp(minimum_gas, gas_limit.max(minimum_gas));
+
+        if self.gas_estimation.binary_search && gas < gas_limit {
+            // Simulates the transaction with the given gas limit, a revert meaning that the gas limit
+            // is insufficient
+            let succeeds = |gas: u64| {
+                let request = CallRequest { gas: Some(U256::from(gas)), ..request.clone() };
+                async move {
+                    let (_, tx) = self.unsigned_invoke_transaction(request, block_id).await?;
+                    match self.simulate_transaction(tx, starknet_block_id, true).await.map_err(EthApiError::map_revert)
+                    {
+                        Ok(_) => Ok(true),
+                        Err(EthApiError::EvmRevert(_)) => Ok(false),
+                        Err(err) => Err(err),
+                    }
+                }
+            };
+            // The converted fee is usually sufficient, which narrows the search
+            let hi = if succeeds(gas).await? { gas } else { gas_limit };
+            let lo = if hi == gas { minimum_gas.saturating_sub(1) } else { gas };
+            gas = binary_search_gas(lo, hi, succeeds).await?;
+        }
 
-        let data = request.data.unwrap_or_default();
+        Ok(U256::from(self.gas_estimation.with_buffer(gas, gas_limit)))
+    }
 
-        let tx = Transaction::Eip1559(TxEip1559 {
-            chain_id: chain_id.low_u64(),
-            nonce,
-            gas_limit,
-            max_fee_per_gas,
-            max_priority_fee_per_gas,
-            to,
-            value,
-            access_list: AccessList(vec![]),
-            input: data,
-        });
+    /// Simulates the transaction on top of the given block without submitting it. Returns the gas
+    /// used, the return data, the logs and the changes of the nonces and of the native token
+    /// balances. The simulation requires the feeder gateway of a gateway network.
+    async fn simulate_ethereum_transaction(
+        &self,
+        request: SimulationRequest,
+        block_id: BlockId,
+    ) -> Result<TransactionSimulation, EthApiError<P::Error>> {
+        if self.network.gateway_url().is_err() {
+            return Err(EthApiError::Unsupported("transaction simulation without a Starknet feeder gateway".into()));
+        }
+        let starknet_block_id = self.starknet_block_id(block_id).await?;
+
+        let (from, tx) = match request {
+            SimulationRequest::Raw(bytes) => {
+                let transaction = TransactionSigned::decode_enveloped(bytes.clone())
+                    .map_err(DataDecodingError::TransactionDecodingError)?;
+                let from = transaction.recover_signer().ok_or_else(|| {
+                    EthApiError::Other(anyhow::anyhow!("Kakarot simulate_transaction: signature ecrecover failed"))
+                })?;
+                let sender_address = self.compute_starknet_address(from, &starknet_block_id).await?;
+                let calldata = raw_kakarot_calldata(self.kakarot_address(), bytes_to_felts(&bytes));
+                let tx = BroadcastedInvokeTransactionV1 {
+                    max_fee: FieldElement::ZERO,
+                    signature: vec![],
+                    sender_address,
+                    nonce: transaction.nonce().into(),
+                    calldata,
+                };
+                (from, tx)
+            }
+            SimulationRequest::Unsigned(request) => self.unsigned_invoke_transaction(request, block_id).await?,
+        };
 
-        let starknet_block_id: StarknetBlockId = EthBlockId::new(block_id).try_into()?;
-        let block_number = self.map_block_id_to_block_number(&starknet_block_id).await?;
+        let nonce: Felt252Wrapper = tx.nonce.into();
+        let simulation = self.simulate_transaction(tx, starknet_block_id, true).await?;
 
-        let sender_address = self.compute_starknet_address(from, &starknet_block_id).await?;
+        let root = simulation.trace.function_invocation.as_ref();
+        let invocation =
+            root.and_then(|root| find_simulated_invocation(root, self.kakarot_address(), ETH_SEND_TRANSACTION));
+        let return_data = match invocation {
+            Some(invocation) => felts_to_bytes(&decode_eth_call_return::<P::Error>(&invocation.result)?)?,
+            None => Bytes::default(),
+        };
 
-        let mut data = vec![];
-        tx.encode_with_signature(&Signature::default(), &mut data, false);
-        let data = data.into_iter().map(FieldElement::from).collect();
-        let calldata = raw_kakarot_calldata(self.kakarot_address(), data);
+        let events = root.map(simulated_events).unwrap_or_default();
+        let mut logs = vec![];
+        for event in events.iter().filter(|event| event.from_address == self.kakarot_address()) {
+            let log_index = Some(U256::from(logs.len()));
+            logs.push(StarknetEvent::new(event.clone()).to_eth_log(self, None, None, None, log_index, None)?);
+        }
 
-        let tx = BroadcastedInvokeTransactionV1 {
-            max_fee: FieldElement::ZERO,
-            signature: vec![],
-            sender_address,
-            nonce: nonce.into(),
-            calldata,
-        };
+        let mut state_diff: BTreeMap<Address, AccountDiff> = BTreeMap::new();
+        for (address, nonce) in nonce_changes(from, nonce.into(), &events, self.kakarot_address()) {
+            state_diff.entry(address).or_default().nonce = Some(nonce);
+        }
 
-        let fee_estimate = self.simulate_transaction(tx, block_number, true).await?.fee_estimation;
-        if fee_estimate.gas_usage < MINIMUM_GAS_FEE {
-            return Ok(U256::from(MINIMUM_GAS_FEE));
+        for (starknet_address, flow) in native_token_flows(&events, self.native_token_address) {
+            // Transfers to and from Starknet accounts which aren't Kakarot accounts are skipped
+            let Ok(address) = self.get_evm_address(&starknet_address, &starknet_block_id).await else {
+                continue;
+            };
+            let balance = self.balance(address, block_id).await?;
+            state_diff.entry(address).or_default().balance = Some(Delta { from: balance, to: flow.apply(balance) });
         }
-        Ok(U256::from(fee_estimate.gas_usage))
+
+        // The fee of the simulation converted into EVM gas, as in `eth_estimateGas`
+        let fee_estimation = simulation.fee_estimation;
+        Ok(TransactionSimulation {
+            gas_used: U256::from(fee_to_gas(fee_estimation.overall_fee, fee_estimation.gas_price)),
+            return_data,
+            logs,
+            state_diff,
+        })
     }
 
     /// Returns the gas price on the network
@@ -641,11 +2125,219 @@ impl<P: Provider + Send + Sync> KakarotEthApi<P> for KakarotClient<P> {
             calldata: raw_calldata,
         };
 
-        let block_number = self.block_number().await?.as_u64();
-        let fee_estimate = self.simulate_transaction(tx, block_number, true).await?.fee_estimation;
+        let latest = StarknetBlockId::Tag(BlockTag::Latest);
+        let fee_estimate = self.simulate_transaction(tx, latest, true).await?.fee_estimation;
 
         Ok(U256::from(fee_estimate.gas_price))
     }
+
+    /// Installs a log filter, returns its id.
+    async fn new_filter(&self, filter: Filter) -> Result<U64, EthApiError<P::Error>> {
+        let current_block = self.block_number().await?.as_u64();
+        Ok(self.filters.install(FilterKind::Log(Box::new(filter)), current_block))
+    }
+
+    /// Installs a filter notifying new blocks, returns its id.
+    async fn new_block_filter(&self) -> Result<U64, EthApiError<P::Error>> {
+        let current_block = self.block_number().await?.as_u64();
+        Ok(self.filters.install(FilterKind::Block, current_block))
+    }
+
+    /// Installs a filter notifying new pending transactions, returns its id.
+    async fn new_pending_transaction_filter(&self) -> Result<U64, EthApiError<P::Error>> {
+        let current_block = self.block_number().await?.as_u64();
+        let id = self.filters.install(FilterKind::PendingTransaction, current_block);
+        // Transactions pending at installation time are not changes
+        let pending = self.pending_transaction_hashes().await?;
+        self.filters.new_pending_transactions(id, pending);
+        Ok(id)
+    }
+
+    /// Uninstalls a filter, returns false if the filter didn't exist.
+    fn uninstall_filter(&self, id: U64) -> bool {
+        self.filters.uninstall(id)
+    }
+
+    /// Returns the changes of a filter since its last poll.
+    async fn get_filter_changes(&self, id: U64) -> Result<FilterChanges, EthApiError<P::Error>> {
+        let FilterPoll { kind, last_polled_block } =
+            self.filters.start_poll(id).ok_or(EthApiError::FilterNotFound(id))?;
+        let current_block = self.block_number().await?.as_u64();
+
+        let changes = match kind {
+            FilterKind::Block => {
+                if current_block <= last_polled_block {
+                    return Ok(FilterChanges::Hashes(vec![]));
+                }
+                FilterChanges::Hashes(self.block_hashes(last_polled_block + 1, current_block).await?)
+            }
+            FilterKind::PendingTransaction => {
+                let pending = self.pending_transaction_hashes().await?;
+                FilterChanges::Hashes(self.filters.new_pending_transactions(id, pending))
+            }
+            FilterKind::Log(filter) => {
+                // Logs of a single block can't change
+                if let FilterBlockOption::AtBlockHash(_) = filter.block_option {
+                    return Ok(FilterChanges::Logs(vec![]));
+                }
+                let from_block = filter.get_from_block().unwrap_or_default().max(last_polled_block + 1);
+                let to_block = filter.get_to_block().unwrap_or(current_block).min(current_block);
+                if from_block > to_block {
+                    return Ok(FilterChanges::Logs(vec![]));
+                }
+                FilterChanges::Logs(self.get_logs((*filter).from_block(from_block).to_block(to_block)).await?)
+            }
+        };
+
+        self.filters.end_poll(id, current_block);
+        Ok(changes)
+    }
+
+    /// Returns all the logs matching a log filter.
+    async fn get_filter_logs(&self, id: U64) -> Result<FilterChanges, EthApiError<P::Error>> {
+        match self.filters.kind(id) {
+            Some(FilterKind::Log(filter)) => Ok(FilterChanges::Logs(self.get_logs(*filter).await?)),
+            _ => Err(EthApiError::FilterNotFound(id)),
+        }
+    }
+
+    /// Returns the Kakarot transactions of the pending block, the only view of the transactions
+    /// waiting for inclusion exposed by Starknet.
+    async fn pending_transactions(&self) -> Result<Vec<EtherTransaction>, EthApiError<P::Error>> {
+        let block = self.get_eth_block_from_starknet_block(StarknetBlockId::Tag(BlockTag::Pending), true).await?;
+        match block.inner.transactions {
+            BlockTransactions::Full(transactions) => Ok(transactions),
+            BlockTransactions::Hashes(_) => Ok(vec![]),
+        }
+    }
+
+    /// Returns the trace of a transaction, reduced to its top level EVM call.
+    async fn trace_transaction(&self, hash: H256, options: TracingOptions) -> Result<GethTrace, EthApiError<P::Error>> {
+        let transaction = self.transaction_by_hash(hash).await?.ok_or(EthApiError::TransactionNotFound(hash))?;
+        let receipt = self.transaction_receipt(transaction.hash).await?;
+        let frame = self.transaction_call_frame(transaction, receipt).await?;
+        Ok(GethTrace::new(frame, &options))
+    }
+
+    /// Returns the trace of a call simulated on top of the given block. The gas used, the output
+    /// and the logs are the ones of the Starknet simulation, which requires a feeder gateway.
+    async fn trace_call(
+        &self,
+        request: CallRequest,
+        block_id: BlockId,
+        options: TracingOptions,
+    ) -> Result<GethTrace, EthApiError<P::Error>> {
+        let simulation = self
+            .simulate_ethereum_transaction(SimulationRequest::Unsigned(request.clone()), block_id)
+            .await
+            .map_err(EthApiError::map_revert);
+        let (output, error, gas_used, logs) = match simulation {
+            Ok(simulation) => (Some(simulation.return_data), None, simulation.gas_used, simulation.logs),
+            // A reverted call is traced with its revert data, as geth does
+            Err(EthApiError::EvmRevert(data)) => {
+                (Some(data), Some("execution reverted".to_string()), U256::ZERO, vec![])
+            }
+            Err(err) => return Err(err),
+        };
+
+        let call_type = if request.to.is_some() { CallType::Call } else { CallType::Create };
+        let frame = CallFrame {
+            call_type,
+            from: request.from.unwrap_or_default(),
+            to: request.to,
+            value: request.value,
+            gas: request.gas.unwrap_or(gas_used),
+            gas_used,
+            input: request.data.unwrap_or_default(),
+            output,
+            error,
+            calls: vec![],
+            logs: logs.into_iter().map(CallLogFrame::from).collect(),
+        };
+        Ok(GethTrace::new(frame, &options))
+    }
+
+    /// Returns the traces of all the transactions of a block. A transaction which can't be traced
+    /// gets an error entry instead of failing the whole block.
+    async fn trace_block_by_number(
+        &self,
+        number: BlockNumberOrTag,
+        options: TracingOptions,
+    ) -> Result<Vec<BlockTraceResult>, EthApiError<P::Error>> {
+        let starknet_block_id = self.starknet_block_id(BlockId::Number(number)).await?;
+        let block = self.get_eth_block_from_starknet_block(starknet_block_id, true).await?;
+        let mut receipts = self.receipts_by_hash(&block).await?;
+        let transactions = match block.inner.transactions {
+            BlockTransactions::Full(transactions) => transactions,
+            BlockTransactions::Hashes(_) => vec![],
+        };
+
+        let handles = transactions.into_iter().map(|transaction| {
+            let options = &options;
+            let receipt = receipts.remove(&transaction.hash);
+            async move {
+                let tx_hash = transaction.hash;
+                match self.transaction_call_frame(transaction, receipt).await {
+                    Ok(frame) => {
+                        BlockTraceResult { tx_hash, result: Some(GethTrace::new(frame, options)), error: None }
+                    }
+                    Err(err) => BlockTraceResult { tx_hash, result: None, error: Some(err.to_string()) },
+                }
+            }
+        });
+        Ok(join_all(handles).await)
+    }
+
+    /// Returns the Parity flat traces of a transaction, or `None` if the transaction is unknown.
+    async fn parity_trace_transaction(
+        &self,
+        hash: H256,
+    ) -> Result<Option<Vec<LocalizedFlatTrace>>, EthApiError<P::Error>> {
+        match self.transaction_by_hash(hash).await? {
+            Some(transaction) => {
+                let receipt = self.transaction_receipt(transaction.hash).await?;
+                Ok(Some(self.localized_traces(transaction, receipt).await?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the Parity flat traces of all the transactions of a block. Unlike the `debug`
+    /// traces, a transaction which can't be traced fails the whole block, since the indexers
+    /// expect the complete traces of the block.
+    async fn parity_trace_block(
+        &self,
+        number: BlockNumberOrTag,
+    ) -> Result<Vec<LocalizedFlatTrace>, EthApiError<P::Error>> {
+        let starknet_block_id = self.starknet_block_id(BlockId::Number(number)).await?;
+        let block = self.get_eth_block_from_starknet_block(starknet_block_id, true).await?;
+        let mut receipts = self.receipts_by_hash(&block).await?;
+        let transactions = match block.inner.transactions {
+            BlockTransactions::Full(transactions) => transactions,
+            BlockTransactions::Hashes(_) => vec![],
+        };
+
+        let traces = try_join_all(transactions.into_iter().map(|transaction| {
+            let receipt = receipts.remove(&transaction.hash);
+            self.localized_traces(transaction, receipt)
+        }))
+        .await?;
+        Ok(traces.into_iter().flatten().collect())
+    }
+
+    /// Replays a mined transaction and returns the requested traces. Only the flat traces are
+    /// available, the VM trace and the state diff are always null.
+    async fn replay_transaction(
+        &self,
+        hash: H256,
+        trace_types: HashSet<TraceType>,
+    ) -> Result<TraceResults, EthApiError<P::Error>> {
+        let transaction = self.transaction_by_hash(hash).await?.ok_or(EthApiError::TransactionNotFound(hash))?;
+        let receipt = self.transaction_receipt(transaction.hash).await?;
+        let frame = self.transaction_call_frame(transaction, receipt).await?;
+        let trace = if trace_types.contains(&TraceType::Trace) { FlatTrace::flatten(&frame) } else { vec![] };
+        Ok(TraceResults { output: frame.output.unwrap_or_default(), state_diff: None, trace, vm_trace: None })
+    }
 }
 
 #[async_trait]
@@ -660,6 +2352,11 @@ impl<P: Provider + Send + Sync> KakarotStarknetApi<P> for KakarotClient<P> {
         self.kakarot_contract.proxy_account_class_hash
     }
 
+    /// Returns the address of the native token.
+    fn native_token_address(&self) -> FieldElement {
+        self.native_token_address
+    }
+
     /// Returns a reference to the Starknet provider.
     fn starknet_provider(&self) -> Arc<P> {
         Arc::clone(&self.starknet_provider)
@@ -680,6 +2377,19 @@ impl<P: Provider + Send + Sync> KakarotStarknetApi<P> for KakarotClient<P> {
         }
     }
 
+    /// Returns the Starknet block id of an Ethereum block id. The `safe` and `finalized` blocks are
+    /// the last block accepted on L1, or the latest block if none is, and the `pending` block is
+    /// the pending block of the Starknet node.
+    async fn starknet_block_id(&self, block_id: BlockId) -> Result<StarknetBlockId, EthApiError<P::Error>> {
+        match block_id {
+            BlockId::Number(BlockNumberOrTag::Safe | BlockNumberOrTag::Finalized) => Ok(self
+                .l1_accepted_block_number()
+                .await?
+                .map_or(StarknetBlockId::Tag(BlockTag::Latest), StarknetBlockId::Number)),
+            block_id => Ok(EthBlockId::new(block_id).try_into()?),
+        }
+    }
+
     /// Returns the EVM address associated with a given Starknet address for a given block id
     /// by calling the `get_evm_address` function on the Kakarot contract.
     async fn get_evm_address(
@@ -687,8 +2397,19 @@ impl<P: Provider + Send + Sync> KakarotStarknetApi<P> for KakarotClient<P> {
         starknet_address: &FieldElement,
         starknet_block_id: &StarknetBlockId,
     ) -> Result<Address, EthApiError<P::Error>> {
+        let latest_state_cache = self.latest_state_cache(starknet_block_id);
+        if let Some(evm_address) = latest_state_cache.and_then(|(cache, _)| cache.get_evm_address(starknet_address)) {
+            return Ok(evm_address);
+        }
+
         let kakarot_account = KakarotAccount::new(*starknet_address, &self.starknet_provider);
-        kakarot_account.get_evm_address(starknet_block_id).await
+        let evm_address = kakarot_account.get_evm_address(starknet_block_id).await?;
+
+        if let Some((cache, head)) = latest_state_cache {
+            cache.insert_evm_address(head, *starknet_address, evm_address);
+        }
+
+        Ok(evm_address)
     }
 
     /// Submits a Kakarot transaction to the Starknet provider.
@@ -704,6 +2425,7 @@ impl<P: Provider + Send + Sync> KakarotStarknetApi<P> for KakarotClient<P> {
 
     /// Returns the EVM address associated with a given Starknet address for a given block id
     /// by calling the `compute_starknet_address` function on the Kakarot contract.
+    #[tracing::instrument(skip(self))]
     async fn compute_starknet_address(
         &self,
         ethereum_address: Address,
@@ -717,47 +2439,81 @@ impl<P: Provider + Send + Sync> KakarotStarknetApi<P> for KakarotClient<P> {
 
     /// Returns the Ethereum transactions executed by the Kakarot contract by filtering the provided
     /// Starknet transaction.
+    #[tracing::instrument(skip_all, fields(block_number = ?block_number))]
     async fn filter_starknet_into_eth_txs(
         &self,
         initial_transactions: StarknetTransactions,
         block_hash: Option<H256>,
         block_number: Option<U256>,
     ) -> BlockTransactions {
-        let handles = Into::<Vec<TransactionType>>::into(initial_transactions).into_iter().map(|tx| async move {
-            let tx = Into::<StarknetTransaction>::into(tx);
-            tx.to_eth_transaction(self, block_hash, block_number, None).await
-        });
-        let transactions_vec = join_all(handles).await.into_iter().filter_map(|transaction| transaction.ok()).collect();
-        BlockTransactions::Full(transactions_vec)
+        let handles = Into::<Vec<TransactionType>>::into(initial_transactions).into_iter().enumerate().map(
+            |(index, tx)| async move {
+                let tx = Into::<StarknetTransaction>::into(tx);
+                (index, tx.to_eth_transaction(self, block_hash, block_number, None).await)
+            },
+        );
+
+        // Convert the transactions concurrently, at most `transaction_conversion_concurrency` at a
+        // time, then restore their order in the block
+        let mut transactions: Vec<(usize, EtherTransaction)> = stream::iter(handles)
+            .buffer_unordered(self.transaction_conversion_concurrency)
+            .filter_map(|(index, transaction)| async move { transaction.ok().map(|transaction| (index, transaction)) })
+            .collect()
+            .await;
+        transactions.sort_unstable_by_key(|(index, _)| *index);
+
+        BlockTransactions::Full(transactions.into_iter().map(|(_, transaction)| transaction).collect())
     }
 
     /// Get the Kakarot eth block provided a Starknet block id.
+    #[tracing::instrument(skip(self))]
     async fn get_eth_block_from_starknet_block(
         &self,
         block_id: StarknetBlockId,
         hydrated_tx: bool,
     ) -> Result<RichBlock, EthApiError<P::Error>> {
-        if hydrated_tx {
+        if let Some(block) = self.block_cache.as_ref().and_then(|cache| cache.get_block(&block_id, hydrated_tx)) {
+            return Ok(block);
+        }
+
+        let mut block = if hydrated_tx {
             let block = self.starknet_provider.get_block_with_txs(block_id).await?;
             let starknet_block = BlockWithTxs::new(block);
-            Ok(starknet_block.to_eth_block(self).await)
+            starknet_block.to_eth_block(self).await
         } else {
             let block = self.starknet_provider.get_block_with_tx_hashes(block_id).await?;
             let starknet_block = BlockWithTxHashes::new(block);
-            Ok(starknet_block.to_eth_block(self).await)
+            starknet_block.to_eth_block(self).await
+        };
+        match self.block_gas_used_and_logs_bloom(&block).await {
+            Ok((gas_used, logs_bloom)) => {
+                block.inner.header.gas_used = gas_used;
+                block.inner.header.logs_bloom = logs_bloom;
+            }
+            // The block is served without its gas used and bloom, but not cached with them
+            Err(err) => {
+                tracing::warn!("Failed to compute the gas used and the logs bloom of the block: {err}");
+                return Ok(block);
+            }
+        }
+
+        if let Some(cache) = &self.block_cache {
+            cache.insert_block(&block, hydrated_tx);
         }
+        Ok(block)
     }
 
-    /// Get the simulation of the BroadcastedInvokeTransactionV1 result
+    /// Get the simulation of the BroadcastedInvokeTransactionV1 result, on top of the state of the
+    /// given block
     /// FIXME 306: make simulate_transaction agnostic of the provider (rn only works for
     /// a SequencerGatewayProvider on testnets and mainnet)
     async fn simulate_transaction(
         &self,
         request: BroadcastedInvokeTransactionV1,
-        block_number: u64,
+        block_id: StarknetBlockId,
         skip_validate: bool,
     ) -> Result<TransactionSimulationInfo, EthApiError<P::Error>> {
-        let client = Client::new();
+        let client = &self.http_client;
 
         // build the url for simulate transaction
         let url = self.network.gateway_url();
@@ -765,8 +2521,8 @@ impl<P: Provider + Send + Sync> KakarotStarknetApi<P> for KakarotClient<P> {
         // if the url is invalid, return an empty simulation (allows to call simulate_transaction on Kakana,
         // Madara, etc.)
         if url.is_err() {
-            let gas_usage = (*ESTIMATE_GAS).try_into().map_err(ConversionError::UintConversionError)?;
-            let gas_price: Felt252Wrapper = (*MAX_FEE).into();
+            let gas_usage = self.chain_spec.default_gas_estimate;
+            let gas_price: Felt252Wrapper = self.chain_spec.max_fee().into();
             let overall_fee = Felt252Wrapper::from(gas_usage) * gas_price.clone();
             return Ok(TransactionSimulationInfo {
                 trace: TransactionTrace {
@@ -790,8 +2546,9 @@ impl<P: Provider + Send + Sync> KakarotStarknetApi<P> for KakarotClient<P> {
             .map_err(|e| EthApiError::FeederGatewayError(format!("gateway url parsing error: {:?}", e)))?;
 
         // add the block number and skipValidate query params
+        let (block_param, block_value) = gateway_block_param(&block_id);
         url.query_pairs_mut()
-            .append_pair("blockNumber", &block_number.to_string())
+            .append_pair(block_param, &block_value)
             .append_pair("skipValidate", &skip_validate.to_string());
 
         // serialize the request
@@ -809,18 +2566,20 @@ impl<P: Provider + Send + Sync> KakarotStarknetApi<P> for KakarotClient<P> {
             .await
             .map_err(|e| EthApiError::FeederGatewayError(format!("gateway post error: {:?}", e)))?;
 
+        // keep the body of an error response, which holds the error of a reverted execution
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(EthApiError::FeederGatewayError(format!("http error {status}: {body}")));
+        }
+
         // decode the response to a `TransactionSimulationInfo`
-        let resp: TransactionSimulationInfo = response
-            .error_for_status()
-            .map_err(|e| EthApiError::FeederGatewayError(format!("http error: {:?}", e)))?
-            .json()
-            .await
-            .map_err(|e| {
-                EthApiError::FeederGatewayError(format!(
-                    "error while decoding response body to TransactionSimulationInfo: {:?}",
-                    e
-                ))
-            })?;
+        let resp: TransactionSimulationInfo = response.json().await.map_err(|e| {
+            EthApiError::FeederGatewayError(format!(
+                "error while decoding response body to TransactionSimulationInfo: {:?}",
+                e
+            ))
+        })?;
 
         Ok(resp)
     }
@@ -846,4 +2605,60 @@ impl<P: Provider + Send + Sync> KakarotStarknetApi<P> for KakarotClient<P> {
 
         Ok(events)
     }
+
+    /// Returns the raw Starknet trace of a transaction, as returned by the feeder gateway
+    /// (`get_transaction_trace`) on gateway networks or by the `starknet_traceTransaction`
+    /// JSON-RPC method otherwise.
+    async fn starknet_transaction_trace(
+        &self,
+        transaction_hash: FieldElement,
+    ) -> Result<serde_json::Value, EthApiError<P::Error>> {
+        let client = &self.http_client;
+        let transaction_hash = format!("{:#x}", transaction_hash);
+
+        if let Ok(url) = self.network.gateway_url() {
+            let mut url = url
+                .join("get_transaction_trace")
+                .map_err(|e| EthApiError::FeederGatewayError(format!("gateway url parsing error: {:?}", e)))?;
+            url.query_pairs_mut().append_pair("transactionHash", &transaction_hash);
+
+            let trace = client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| EthApiError::FeederGatewayError(format!("gateway get error: {:?}", e)))?
+                .error_for_status()
+                .map_err(|e| EthApiError::FeederGatewayError(format!("http error: {:?}", e)))?
+                .json()
+                .await
+                .map_err(|e| EthApiError::FeederGatewayError(format!("error while decoding trace: {:?}", e)))?;
+            return Ok(trace);
+        }
+
+        // The provider doesn't expose tracing in the version of the spec we use, query the node directly
+        let url = self.network.provider_url()?;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "starknet_traceTransaction",
+            "params": { "transaction_hash": transaction_hash },
+        });
+        let mut response: serde_json::Value = client
+            .post(url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| EthApiError::Other(anyhow::anyhow!("starknet_traceTransaction request failed: {e}")))?
+            .json()
+            .await
+            .map_err(|e| EthApiError::Other(anyhow::anyhow!("starknet_traceTransaction invalid response: {e}")))?;
+
+        match response.get_mut("result") {
+            Some(trace) => Ok(trace.take()),
+            None => Err(EthApiError::Other(anyhow::anyhow!(
+                "starknet_traceTransaction failed: {}",
+                response.get("error").cloned().unwrap_or_default()
+            ))),
+        }
+    }
 }
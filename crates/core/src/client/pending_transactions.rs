@@ -0,0 +1,166 @@
+//! Transactions submitted through the RPC which the Starknet node doesn't serve yet.
+//!
+//! Between its submission and its acceptance by the Starknet node, a relayed transaction is
+//! unknown to `starknet_getTransactionByHash`. The tracker keeps it meanwhile, so that it is served
+//! as pending by `eth_getTransactionByHash` and counted by
+//! `eth_getTransactionCount(.., "pending")`. A transaction is forgotten once the Starknet node
+//! serves it, i.e. on its inclusion or its rejection, or after [`PENDING_TRANSACTIONS_TTL`] if it
+//! never shows up.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reth_primitives::{Address, H256, U256};
+use reth_rpc_types::Transaction as EthTransaction;
+
+/// Duration after which a transaction never served by the Starknet node is dropped.
+pub const PENDING_TRANSACTIONS_TTL: Duration = Duration::from_secs(600);
+
+/// Tracked transaction, with the instant of its submission.
+#[derive(Debug, Clone)]
+struct TrackedTransaction {
+    transaction: EthTransaction,
+    submitted_at: Instant,
+}
+
+/// Submitted transactions by Starknet transaction hash.
+#[derive(Debug)]
+pub struct PendingTransactionTracker {
+    transactions: Mutex<HashMap<H256, TrackedTransaction>>,
+    ttl: Duration,
+}
+
+impl Default for PendingTransactionTracker {
+    fn default() -> Self {
+        Self::new(PENDING_TRANSACTIONS_TTL)
+    }
+}
+
+impl PendingTransactionTracker {
+    pub fn new(ttl: Duration) -> Self {
+        Self { transactions: Mutex::new(HashMap::new()), ttl }
+    }
+
+    /// Tracks the submitted transaction, whose hash is the hash of the Starknet transaction.
+    pub fn insert(&self, transaction: EthTransaction) {
+        let tracked = TrackedTransaction { transaction, submitted_at: Instant::now() };
+        let mut transactions = self.transactions.lock().expect("pending transactions poisoned");
+        transactions.insert(tracked.transaction.hash, tracked);
+    }
+
+    /// Forgets the transaction, which the Starknet node now serves.
+    pub fn remove(&self, hash: &H256) {
+        self.transactions.lock().expect("pending transactions poisoned").remove(hash);
+    }
+
+    /// Returns the transaction if it is tracked, as a pending transaction.
+    pub fn get(&self, hash: &H256) -> Option<EthTransaction> {
+        let mut transactions = self.transactions.lock().expect("pending transactions poisoned");
+        self.prune(&mut transactions);
+        transactions.get(hash).map(|tracked| tracked.transaction.clone())
+    }
+
+    /// Returns the hashes of the tracked transactions of the sender.
+    pub fn hashes_of(&self, sender: Address) -> Vec<H256> {
+        let mut transactions = self.transactions.lock().expect("pending transactions poisoned");
+        self.prune(&mut transactions);
+        transactions
+            .values()
+            .filter(|tracked| tracked.transaction.from == sender)
+            .map(|tracked| tracked.transaction.hash)
+            .collect()
+    }
+
+    /// Returns the nonce following the tracked transactions of the sender, `None` if it has none.
+    pub fn next_nonce(&self, sender: Address) -> Option<U256> {
+        let mut transactions = self.transactions.lock().expect("pending transactions poisoned");
+        self.prune(&mut transactions);
+        transactions
+            .values()
+            .filter(|tracked| tracked.transaction.from == sender)
+            .map(|tracked| tracked.transaction.nonce + U256::from(1))
+            .max()
+    }
+
+    /// Returns the number of tracked transactions.
+    pub fn len(&self) -> usize {
+        self.transactions.lock().expect("pending transactions poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the transactions tracked for longer than the TTL.
+    fn prune(&self, transactions: &mut HashMap<H256, TrackedTransaction>) {
+        let now = Instant::now();
+        transactions.retain(|_, tracked| now.duration_since(tracked.submitted_at) < self.ttl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::Bytes;
+
+    use super::*;
+
+    fn transaction(hash: u64, from: Address, nonce: u64) -> EthTransaction {
+        EthTransaction {
+            hash: H256::from_low_u64_be(hash),
+            nonce: U256::from(nonce),
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+            from,
+            to: None,
+            value: U256::ZERO,
+            gas_price: None,
+            gas: U256::from(21_000),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            input: Bytes::default(),
+            signature: None,
+            chain_id: None,
+            access_list: None,
+            transaction_type: None,
+        }
+    }
+
+    #[test]
+    fn test_tracker_serves_transactions_until_removed() {
+        // Given
+        let tracker = PendingTransactionTracker::default();
+        let (sender, other) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+
+        // When
+        tracker.insert(transaction(1, sender, 4));
+        tracker.insert(transaction(2, sender, 5));
+        tracker.insert(transaction(3, other, 0));
+
+        // Then
+        assert_eq!(Some(transaction(1, sender, 4)), tracker.get(&H256::from_low_u64_be(1)));
+        assert_eq!(Some(U256::from(6)), tracker.next_nonce(sender));
+        assert_eq!(2, tracker.hashes_of(sender).len());
+
+        // When
+        tracker.remove(&H256::from_low_u64_be(2));
+
+        // Then
+        assert_eq!(Some(U256::from(5)), tracker.next_nonce(sender));
+        assert_eq!(None, tracker.next_nonce(Address::from_low_u64_be(3)));
+        assert_eq!(2, tracker.len());
+    }
+
+    #[test]
+    fn test_tracker_expires_transactions() {
+        // Given
+        let tracker = PendingTransactionTracker::new(Duration::ZERO);
+
+        // When
+        tracker.insert(transaction(1, Address::from_low_u64_be(1), 0));
+
+        // Then
+        assert_eq!(None, tracker.get(&H256::from_low_u64_be(1)));
+        assert!(tracker.is_empty());
+    }
+}
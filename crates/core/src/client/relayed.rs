@@ -0,0 +1,91 @@
+//! Raw transactions recently relayed to Starknet. A transaction submitted again through
+//! `eth_sendRawTransaction` while it is remembered is rejected as `already known`, as geth does,
+//! instead of invoking Kakarot twice with the same payload, the second invoke failing on its nonce.
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+use reth_primitives::H256;
+
+/// Number of relayed transactions remembered.
+pub const RELAYED_TRANSACTIONS_CAPACITY: usize = 4096;
+
+/// Duration for which a relayed transaction is remembered. Past it, a transaction which was
+/// accepted is rejected by its nonce anyway.
+pub const RELAYED_TRANSACTIONS_TTL: Duration = Duration::from_secs(600);
+
+/// Hashes of the recently relayed transactions, with the instant at which they were relayed.
+#[derive(Debug)]
+pub struct RelayedTransactions {
+    entries: Mutex<LruCache<H256, Instant>>,
+    ttl: Duration,
+}
+
+impl Default for RelayedTransactions {
+    fn default() -> Self {
+        // Safe unwrap: the capacity isn't zero
+        Self::new(NonZeroUsize::new(RELAYED_TRANSACTIONS_CAPACITY).unwrap(), RELAYED_TRANSACTIONS_TTL)
+    }
+}
+
+impl RelayedTransactions {
+    pub fn new(capacity: NonZeroUsize, ttl: Duration) -> Self {
+        Self { entries: Mutex::new(LruCache::new(capacity)), ttl }
+    }
+
+    /// Records the transaction as relayed. Returns false if it was already relayed within the
+    /// remembered duration, in which case it must not be relayed again.
+    pub fn insert(&self, hash: H256) -> bool {
+        let mut entries = self.entries.lock().expect("relayed transactions poisoned");
+        let now = Instant::now();
+        if entries.get(&hash).is_some_and(|relayed_at| now.duration_since(*relayed_at) < self.ttl) {
+            return false;
+        }
+        entries.put(hash, now);
+        true
+    }
+
+    /// Forgets the transaction, which failed to be relayed and can be submitted again.
+    pub fn remove(&self, hash: &H256) {
+        self.entries.lock().expect("relayed transactions poisoned").pop(hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_transaction_is_rejected() {
+        // Given
+        let relayed = RelayedTransactions::default();
+        let hash = H256::from_low_u64_be(1);
+
+        // Then
+        assert!(relayed.insert(hash));
+        assert!(!relayed.insert(hash));
+        assert!(relayed.insert(H256::from_low_u64_be(2)));
+
+        // When
+        relayed.remove(&hash);
+
+        // Then
+        assert!(relayed.insert(hash));
+    }
+
+    #[test]
+    fn test_transaction_is_forgotten_after_ttl_or_eviction() {
+        // Given
+        let expired = RelayedTransactions::new(NonZeroUsize::new(2).unwrap(), Duration::ZERO);
+        let evicted = RelayedTransactions::new(NonZeroUsize::new(1).unwrap(), RELAYED_TRANSACTIONS_TTL);
+        let (first, second) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+
+        // Then
+        assert!(expired.insert(first));
+        assert!(expired.insert(first));
+        assert!(evicted.insert(first));
+        assert!(evicted.insert(second));
+        assert!(evicted.insert(first));
+    }
+}
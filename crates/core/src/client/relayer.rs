@@ -0,0 +1,429 @@
+//! Pool of Starknet accounts paying for the Starknet transactions sent by the RPC itself, e.g. the
+//! deployment of the Kakarot account of an EVM address with `kakarot_deployEoa`, or of a sender
+//! relaying its first transaction when the auto-deployment is enabled.
+//!
+//! Each account of the pool tracks its own nonce, fetched from the pending block on its first
+//! use and then incremented locally, so that several submissions don't wait for the previous ones
+//! to be included. The submissions of an account are queued behind each other, while the pool
+//! spreads them on its least busy account. A submission rejected by the Starknet node is retried
+//! after resyncing the nonce of the account from the chain, in case another submission or another
+//! RPC instance used it.
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use starknet::accounts::Call;
+use starknet::core::crypto::compute_hash_on_elements;
+use starknet::core::types::{
+    BlockId as StarknetBlockId, BlockTag, BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1, FieldElement,
+};
+use starknet::providers::{Provider, ProviderError};
+use starknet::signers::SigningKey;
+use tokio::sync::{Mutex, OnceCell};
+
+use super::errors::{ConfigError, EthApiError, SignerError};
+
+/// Default number of retries of a submission rejected by the Starknet node.
+pub const DEFAULT_RELAYER_MAX_RETRIES: usize = 3;
+
+/// Delay before retrying a rejected submission.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Interval between the polls of the receipt of the deployment of the account of a sender.
+pub const DEPLOYMENT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum time waited for the deployment of the account of a sender to be executed, before its
+/// transaction is relayed.
+pub const DEPLOYMENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `invoke` prefix of the hash of the invoke transactions, as a short string.
+const PREFIX_INVOKE: u64 = 0x696e_766f_6b65;
+
+/// Starknet account of the pool.
+#[derive(Clone)]
+pub struct RelayerAccount {
+    pub address: FieldElement,
+    signing_key: SigningKey,
+}
+
+impl std::fmt::Debug for RelayerAccount {
+    // Never print the private key
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RelayerAccount").field("address", &self.address).finish()
+    }
+}
+
+impl PartialEq for RelayerAccount {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address && self.signing_key.secret_scalar() == other.signing_key.secret_scalar()
+    }
+}
+
+impl Eq for RelayerAccount {}
+
+impl RelayerAccount {
+    pub fn new(address: FieldElement, private_key: FieldElement) -> Self {
+        Self { address, signing_key: SigningKey::from_secret_scalar(private_key) }
+    }
+}
+
+impl FromStr for RelayerAccount {
+    type Err = ConfigError;
+
+    /// Parses `<address>:<private key>`, both in hex.
+    fn from_str(account: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            // Do not leak the private key in the error
+            let address = account.split(':').next().unwrap_or_default();
+            ConfigError::EnvironmentVariableSetWrong(format!(
+                "relayer accounts should be <address>:<private key> hex pairs, got {address}:..."
+            ))
+        };
+        let (address, private_key) = account.trim().split_once(':').ok_or_else(invalid)?;
+        let address = FieldElement::from_hex_be(address).map_err(|_| invalid())?;
+        let private_key = FieldElement::from_hex_be(private_key).map_err(|_| invalid())?;
+        Ok(Self::new(address, private_key))
+    }
+}
+
+/// Configuration of the relayer accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayerConfig {
+    pub accounts: Vec<RelayerAccount>,
+    /// Number of retries of a submission rejected by the Starknet node.
+    pub max_retries: usize,
+    /// Whether the Kakarot account of a sender is deployed by the relayer before relaying its
+    /// first transaction. The deployment is paid by the relayer account, and isn't reimbursed by
+    /// the sender.
+    pub auto_deploy_eoa: bool,
+}
+
+impl RelayerConfig {
+    /// Create a new `RelayerConfig` from the `KAKAROT_RELAYER_ACCOUNTS` environment variable, a
+    /// comma separated list of `<address>:<private key>` pairs, `KAKAROT_RELAYER_MAX_RETRIES` and
+    /// `KAKAROT_RELAYER_AUTO_DEPLOY_EOA`. Returns `None` if no account is set, which disables the
+    /// relayer.
+    pub fn from_env() -> Result<Option<Self>, ConfigError> {
+        let Ok(accounts) = std::env::var("KAKAROT_RELAYER_ACCOUNTS") else {
+            return Ok(None);
+        };
+        let accounts = accounts
+            .split(',')
+            .filter(|account| !account.trim().is_empty())
+            .map(RelayerAccount::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if accounts.is_empty() {
+            return Ok(None);
+        }
+
+        let max_retries = match std::env::var("KAKAROT_RELAYER_MAX_RETRIES") {
+            Ok(max_retries) => max_retries.parse::<usize>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_RELAYER_MAX_RETRIES should be a positive integer, got {max_retries}"
+                ))
+            })?,
+            Err(_) => DEFAULT_RELAYER_MAX_RETRIES,
+        };
+
+        let auto_deploy_eoa = std::env::var("KAKAROT_RELAYER_AUTO_DEPLOY_EOA")
+            .map(|auto_deploy_eoa| auto_deploy_eoa.to_lowercase() == "true")
+            .unwrap_or(false);
+
+        Ok(Some(Self { accounts, max_retries, auto_deploy_eoa }))
+    }
+}
+
+/// Account of the pool with its locally tracked nonce. The lock on the nonce queues the
+/// submissions of the account.
+#[derive(Debug)]
+struct PooledAccount {
+    account: RelayerAccount,
+    /// Next nonce of the account, `None` until fetched or after a rejected submission.
+    nonce: Mutex<Option<FieldElement>>,
+    /// Submissions queued or in flight on the account.
+    load: AtomicUsize,
+}
+
+/// Counts a submission in the load of an account until dropped, also when the submission is
+/// cancelled.
+struct LoadGuard<'a>(&'a AtomicUsize);
+
+impl<'a> LoadGuard<'a> {
+    fn new(load: &'a AtomicUsize) -> Self {
+        load.fetch_add(1, Ordering::Relaxed);
+        Self(load)
+    }
+}
+
+impl Drop for LoadGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Pool of relayer accounts.
+#[derive(Debug)]
+pub struct RelayerPool {
+    accounts: Vec<PooledAccount>,
+    max_retries: usize,
+    auto_deploy_eoa: bool,
+    /// Maximum fee of the submitted transactions.
+    max_fee: FieldElement,
+    next: AtomicUsize,
+    chain_id: OnceCell<FieldElement>,
+}
+
+impl RelayerPool {
+    pub fn new(config: RelayerConfig, max_fee: FieldElement) -> Self {
+        let accounts = config
+            .accounts
+            .into_iter()
+            .map(|account| PooledAccount { account, nonce: Mutex::new(None), load: AtomicUsize::new(0) })
+            .collect();
+        Self {
+            accounts,
+            max_retries: config.max_retries,
+            auto_deploy_eoa: config.auto_deploy_eoa,
+            max_fee,
+            next: AtomicUsize::new(0),
+            chain_id: OnceCell::new(),
+        }
+    }
+
+    /// Returns the addresses of the accounts of the pool.
+    pub fn addresses(&self) -> Vec<FieldElement> {
+        self.accounts.iter().map(|pooled| pooled.account.address).collect()
+    }
+
+    /// Returns whether the Kakarot accounts of the senders are deployed before relaying their first
+    /// transaction.
+    pub fn auto_deploys_eoa(&self) -> bool {
+        self.auto_deploy_eoa
+    }
+
+    /// Resumes the nonce of an account of the pool, e.g. from its transactions found on chain after
+    /// a restart, unless a higher nonce is already tracked. Returns false if the account isn't part
+    /// of the pool.
+    pub async fn resume_nonce(&self, address: FieldElement, next_nonce: FieldElement) -> bool {
+        let Some(pooled) = self.accounts.iter().find(|pooled| pooled.account.address == address) else {
+            return false;
+        };
+        let mut nonce = pooled.nonce.lock().await;
+        *nonce = Some(nonce.map_or(next_nonce, |nonce| nonce.max(next_nonce)));
+        true
+    }
+
+    /// Returns the index of the account with the fewest queued submissions, starting the search
+    /// after the last selected account so that the idle accounts are used in turn.
+    fn select(&self) -> usize {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..self.accounts.len())
+            .map(|offset| (start + offset) % self.accounts.len())
+            .min_by_key(|index| self.accounts[*index].load.load(Ordering::Relaxed))
+            .unwrap_or_default()
+    }
+
+    /// Executes the calls from an account of the pool and returns the hash of the Starknet
+    /// transaction.
+    pub async fn execute<P: Provider + Send + Sync>(
+        &self,
+        provider: &P,
+        calls: &[Call],
+    ) -> Result<FieldElement, EthApiError<P::Error>> {
+        if self.accounts.is_empty() {
+            return Err(ConfigError::EnvironmentVariableSetWrong("no relayer account configured".to_string()).into());
+        }
+        let chain_id = *self.chain_id.get_or_try_init(|| provider.chain_id()).await?;
+        let calldata = execute_calldata(calls);
+
+        let pooled = &self.accounts[self.select()];
+        let _load = LoadGuard::new(&pooled.load);
+        self.execute_from(pooled, provider, chain_id, calldata).await
+    }
+
+    async fn execute_from<P: Provider + Send + Sync>(
+        &self,
+        pooled: &PooledAccount,
+        provider: &P,
+        chain_id: FieldElement,
+        calldata: Vec<FieldElement>,
+    ) -> Result<FieldElement, EthApiError<P::Error>> {
+        let address = pooled.account.address;
+        let mut nonce = pooled.nonce.lock().await;
+
+        let mut retries = 0;
+        loop {
+            let current_nonce = match *nonce {
+                Some(nonce) => nonce,
+                None => provider.get_nonce(StarknetBlockId::Tag(BlockTag::Pending), address).await?,
+            };
+
+            let max_fee = self.max_fee;
+            let hash = invoke_transaction_hash(address, &calldata, max_fee, chain_id, current_nonce);
+            let signature =
+                pooled.account.signing_key.sign(&hash).map_err(|err| SignerError::SigningFailed(err.to_string()))?;
+            let request = BroadcastedInvokeTransactionV1 {
+                max_fee,
+                signature: vec![signature.r, signature.s],
+                nonce: current_nonce,
+                sender_address: address,
+                calldata: calldata.clone(),
+            };
+
+            match provider.add_invoke_transaction(&BroadcastedInvokeTransaction::V1(request)).await {
+                Ok(result) => {
+                    *nonce = Some(current_nonce + FieldElement::ONE);
+                    return Ok(result.transaction_hash);
+                }
+                // The node rejected the submission, e.g. on a nonce clash: resync the nonce and retry
+                Err(err @ (ProviderError::StarknetError(_) | ProviderError::RateLimited))
+                    if retries < self.max_retries =>
+                {
+                    tracing::warn!(
+                        "Relayer {address:#x} submission with nonce {current_nonce} rejected, retrying: {err}"
+                    );
+                    *nonce = None;
+                    retries += 1;
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+                // The submission may have been received, do not send it twice
+                Err(err) => {
+                    *nonce = None;
+                    return Err(err.into());
+                }
+            }
+        }
+    }
+}
+
+/// Returns the calldata of the `__execute__` entrypoint of an account for the calls.
+pub fn execute_calldata(calls: &[Call]) -> Vec<FieldElement> {
+    let mut call_array = vec![FieldElement::from(calls.len())];
+    let mut calldata = vec![];
+    for call in calls {
+        call_array.extend([
+            call.to,
+            call.selector,
+            FieldElement::from(calldata.len()),
+            FieldElement::from(call.calldata.len()),
+        ]);
+        calldata.extend_from_slice(&call.calldata);
+    }
+    call_array.push(FieldElement::from(calldata.len()));
+    call_array.extend(calldata);
+    call_array
+}
+
+/// Computes the hash of a version 1 invoke transaction, signed by the sender.
+pub fn invoke_transaction_hash(
+    sender_address: FieldElement,
+    calldata: &[FieldElement],
+    max_fee: FieldElement,
+    chain_id: FieldElement,
+    nonce: FieldElement,
+) -> FieldElement {
+    compute_hash_on_elements(&[
+        FieldElement::from(PREFIX_INVOKE),
+        FieldElement::ONE, // version
+        sender_address,
+        FieldElement::ZERO, // entry point selector
+        compute_hash_on_elements(calldata),
+        max_fee,
+        chain_id,
+        nonce,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::constants::MAX_FEE;
+
+    fn pool(accounts: usize) -> RelayerPool {
+        let accounts =
+            (1..=accounts).map(|i| RelayerAccount::new(FieldElement::from(i), FieldElement::from(i + 100))).collect();
+        RelayerPool::new(
+            RelayerConfig { accounts, max_retries: DEFAULT_RELAYER_MAX_RETRIES, auto_deploy_eoa: false },
+            *MAX_FEE,
+        )
+    }
+
+    #[test]
+    fn test_parse_relayer_account() {
+        // When
+        let account: RelayerAccount = "0x123:0x456".parse().unwrap();
+
+        // Then
+        assert_eq!(RelayerAccount::new(FieldElement::from(0x123u64), FieldElement::from(0x456u64)), account);
+        assert!("0x123".parse::<RelayerAccount>().is_err());
+        let err = "0x123:secret".parse::<RelayerAccount>().unwrap_err();
+        assert!(!err.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn test_pool_selects_least_busy_account() {
+        // Given
+        let pool = pool(3);
+
+        // When
+        pool.accounts[0].load.store(2, Ordering::Relaxed);
+        pool.accounts[1].load.store(1, Ordering::Relaxed);
+
+        // Then
+        assert_eq!(2, pool.select());
+
+        // When
+        pool.accounts[2].load.store(1, Ordering::Relaxed);
+
+        // Then
+        let selected: Vec<usize> = (0..2).map(|_| pool.select()).collect();
+        assert!(selected.contains(&1) && selected.contains(&2));
+        assert!(!selected.contains(&0));
+    }
+
+    #[tokio::test]
+    async fn test_resume_nonce() {
+        // Given
+        let pool = pool(2);
+
+        // When
+        let resumed = pool.resume_nonce(FieldElement::from(1u8), FieldElement::from(5u8)).await;
+        pool.resume_nonce(FieldElement::from(1u8), FieldElement::from(3u8)).await;
+
+        // Then
+        assert!(resumed);
+        assert_eq!(Some(FieldElement::from(5u8)), *pool.accounts[0].nonce.lock().await);
+        assert_eq!(None, *pool.accounts[1].nonce.lock().await);
+        assert!(!pool.resume_nonce(FieldElement::from(3u8), FieldElement::ONE).await);
+    }
+
+    #[test]
+    fn test_execute_calldata() {
+        // Given
+        let calls = vec![
+            Call {
+                to: FieldElement::from(1u8),
+                selector: FieldElement::from(2u8),
+                calldata: vec![FieldElement::from(3u8)],
+            },
+            Call {
+                to: FieldElement::from(4u8),
+                selector: FieldElement::from(5u8),
+                calldata: vec![FieldElement::from(6u8), FieldElement::from(7u8)],
+            },
+        ];
+
+        // When
+        let calldata = execute_calldata(&calls);
+
+        // Then
+        let expected: Vec<FieldElement> =
+            [2u8, 1, 2, 0, 1, 4, 5, 1, 2, 3, 3, 6, 7].into_iter().map(FieldElement::from).collect();
+        assert_eq!(expected, calldata);
+    }
+
+    #[test]
+    fn test_invoke_prefix() {
+        assert_eq!(FieldElement::from_byte_slice_be(b"invoke").unwrap(), FieldElement::from(PREFIX_INVOKE));
+    }
+}
@@ -0,0 +1,115 @@
+//! Detection of the reorganizations of the chain tip. The Starknet blocks can be reverted until
+//! they are accepted on L1, which happens on the testnets: the head watcher tracks the hashes of
+//! the recent blocks, and a new block whose parent isn't the tracked block at its height reveals
+//! that the tracked blocks from the fork point were replaced.
+use std::collections::BTreeMap;
+
+use reth_primitives::H256;
+
+/// Maximum depth of the reorganizations detected, the older blocks not being tracked.
+pub const MAX_REORG_DEPTH: u64 = 64;
+
+/// Hash of a block along with the hash of its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockLink {
+    pub number: u64,
+    pub hash: H256,
+    pub parent_hash: H256,
+}
+
+/// Hashes of the recent blocks of the canonical chain, at most [`MAX_REORG_DEPTH`] of them.
+#[derive(Debug, Default)]
+pub struct ChainTracker {
+    blocks: BTreeMap<u64, H256>,
+}
+
+impl ChainTracker {
+    /// Returns the hash of the tracked block at the height.
+    pub fn hash(&self, block_number: u64) -> Option<H256> {
+        self.blocks.get(&block_number).copied()
+    }
+
+    /// Returns the number of the last tracked block.
+    pub fn tip(&self) -> Option<u64> {
+        self.blocks.keys().next_back().copied()
+    }
+
+    /// Records a block of the canonical chain, forgetting the blocks too old to be reverted.
+    pub fn record(&mut self, block: &BlockLink) {
+        self.blocks.insert(block.number, block.hash);
+        let oldest = block.number.saturating_sub(MAX_REORG_DEPTH - 1);
+        self.blocks = self.blocks.split_off(&oldest);
+    }
+
+    /// Returns the number of the first tracked block reverted according to the block, without
+    /// looking at its ancestors: the tracked block at its height if it differs, or the block
+    /// following it if the chain is now shorter than the tracked one.
+    pub fn reverted_at(&self, block: &BlockLink) -> Option<u64> {
+        match self.hash(block.number) {
+            Some(hash) if hash != block.hash => Some(block.number),
+            _ if self.tip().is_some_and(|tip| tip > block.number) => Some(block.number + 1),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of the parent of the block if it differs from the tracked block at its
+    /// height, i.e. if the parent was reverted.
+    pub fn reverted_parent(&self, block: &BlockLink) -> Option<u64> {
+        let parent_number = block.number.checked_sub(1)?;
+        self.hash(parent_number).filter(|hash| *hash != block.parent_hash).map(|_| parent_number)
+    }
+
+    /// Forgets the blocks from `from_block` onwards, reverted by a reorganization.
+    pub fn rollback(&mut self, from_block: u64) {
+        self.blocks.split_off(&from_block);
+    }
+
+    /// Forgets all the blocks, e.g. when the chain isn't followed anymore.
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(number: u64, hash: u64, parent_hash: u64) -> BlockLink {
+        BlockLink { number, hash: H256::from_low_u64_be(hash), parent_hash: H256::from_low_u64_be(parent_hash) }
+    }
+
+    #[test]
+    fn test_chain_tracker_detects_reverted_blocks() {
+        // Given
+        let mut chain = ChainTracker::default();
+        chain.record(&link(1, 0x11, 0x00));
+        chain.record(&link(2, 0x22, 0x11));
+        chain.record(&link(3, 0x33, 0x22));
+
+        // Then
+        assert_eq!(None, chain.reverted_at(&link(4, 0x44, 0x33)));
+        assert_eq!(None, chain.reverted_parent(&link(4, 0x44, 0x33)));
+        // The block 3 was replaced by a block on top of the block 2
+        assert_eq!(Some(3), chain.reverted_at(&link(3, 0x3b, 0x22)));
+        assert_eq!(None, chain.reverted_parent(&link(3, 0x3b, 0x22)));
+        // The block 4 is built on a replaced block 3
+        assert_eq!(Some(3), chain.reverted_parent(&link(4, 0x4b, 0x3b)));
+        // The chain is now shorter
+        assert_eq!(Some(3), chain.reverted_at(&link(2, 0x22, 0x11)));
+    }
+
+    #[test]
+    fn test_chain_tracker_rollback_and_depth() {
+        // Given
+        let mut chain = ChainTracker::default();
+        (1..=MAX_REORG_DEPTH + 10).for_each(|number| chain.record(&link(number, number, number - 1)));
+
+        // When
+        chain.rollback(MAX_REORG_DEPTH);
+
+        // Then
+        assert_eq!(Some(MAX_REORG_DEPTH - 1), chain.tip());
+        assert_eq!(None, chain.hash(10));
+        assert_eq!(Some(H256::from_low_u64_be(11)), chain.hash(11));
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use reth_primitives::{Address, TransactionSigned};
+
+use super::errors::{ConfigError, SenderPolicyError};
+
+/// Restrictions on the transactions relayed to Kakarot through `eth_sendRawTransaction` and
+/// `eth_sendTransaction`, for gated deployments restricting who may transact.
+///
+/// The senders are restricted by an allow-list (`KAKAROT_ALLOWED_SENDERS`) and/or a deny-list
+/// (`KAKAROT_DENIED_SENDERS`) of comma separated addresses, and the transactions by a maximum
+/// value in wei (`KAKAROT_MAX_TRANSACTION_VALUE`) and gas limit (`KAKAROT_MAX_TRANSACTION_GAS`).
+/// Without any of them, every transaction is relayed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SenderPolicy {
+    /// Only senders allowed to transact, any sender if not set.
+    pub allowed_senders: Option<HashSet<Address>>,
+    /// Senders never allowed to transact, even if allow-listed.
+    pub denied_senders: HashSet<Address>,
+    /// Maximum value transferred by a transaction, in wei.
+    pub max_value: Option<u128>,
+    /// Maximum gas limit of a transaction.
+    pub max_gas: Option<u64>,
+}
+
+impl SenderPolicy {
+    /// Create a new `SenderPolicy` from environment variables.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let allowed_senders = match std::env::var("KAKAROT_ALLOWED_SENDERS") {
+            Ok(senders) => Some(parse_addresses("KAKAROT_ALLOWED_SENDERS", &senders)?),
+            Err(_) => None,
+        };
+        let denied_senders = match std::env::var("KAKAROT_DENIED_SENDERS") {
+            Ok(senders) => parse_addresses("KAKAROT_DENIED_SENDERS", &senders)?,
+            Err(_) => HashSet::new(),
+        };
+
+        let max_value = match std::env::var("KAKAROT_MAX_TRANSACTION_VALUE") {
+            Ok(value) => Some(value.parse::<u128>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_MAX_TRANSACTION_VALUE should be an amount in wei, got {value}"
+                ))
+            })?),
+            Err(_) => None,
+        };
+        let max_gas = match std::env::var("KAKAROT_MAX_TRANSACTION_GAS") {
+            Ok(gas) => Some(gas.parse::<u64>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_MAX_TRANSACTION_GAS should be a gas limit, got {gas}"
+                ))
+            })?),
+            Err(_) => None,
+        };
+
+        Ok(Self { allowed_senders, denied_senders, max_value, max_gas })
+    }
+
+    /// Checks that the transaction of the sender may be relayed.
+    pub fn check(&self, sender: Address, transaction: &TransactionSigned) -> Result<(), SenderPolicyError> {
+        if self.denied_senders.contains(&sender) {
+            return Err(SenderPolicyError::Denied(sender));
+        }
+        if let Some(allowed_senders) = &self.allowed_senders {
+            if !allowed_senders.contains(&sender) {
+                return Err(SenderPolicyError::NotAllowed(sender));
+            }
+        }
+
+        let value = transaction.value();
+        if let Some(max_value) = self.max_value.filter(|max_value| value > *max_value) {
+            return Err(SenderPolicyError::ValueTooHigh { value, max_value });
+        }
+        let gas = transaction.gas_limit();
+        if let Some(max_gas) = self.max_gas.filter(|max_gas| gas > *max_gas) {
+            return Err(SenderPolicyError::GasTooHigh { gas, max_gas });
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_addresses(name: &str, addresses: &str) -> Result<HashSet<Address>, ConfigError> {
+    addresses
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| {
+            Address::from_str(address).map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "{name} should be a comma separated list of EVM addresses, got {address}"
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Signature, Transaction, TxEip1559};
+
+    use super::*;
+
+    fn transaction(value: u128, gas_limit: u64) -> TransactionSigned {
+        let transaction = Transaction::Eip1559(TxEip1559 { value, gas_limit, ..Default::default() });
+        TransactionSigned::from_transaction_and_signature(transaction, Signature::default())
+    }
+
+    #[test]
+    fn test_sender_policy_senders() {
+        // Given
+        let (alice, bob, carol) =
+            (Address::from_low_u64_be(1), Address::from_low_u64_be(2), Address::from_low_u64_be(3));
+        let policy = SenderPolicy {
+            allowed_senders: Some(HashSet::from([alice, bob])),
+            denied_senders: HashSet::from([bob]),
+            ..Default::default()
+        };
+
+        // Then
+        assert!(policy.check(alice, &transaction(0, 21_000)).is_ok());
+        assert!(matches!(policy.check(bob, &transaction(0, 21_000)), Err(SenderPolicyError::Denied(_))));
+        assert!(matches!(policy.check(carol, &transaction(0, 21_000)), Err(SenderPolicyError::NotAllowed(_))));
+    }
+
+    #[test]
+    fn test_sender_policy_limits() {
+        // Given
+        let policy = SenderPolicy { max_value: Some(100), max_gas: Some(21_000), ..Default::default() };
+        let sender = Address::zero();
+
+        // Then
+        assert!(policy.check(sender, &transaction(100, 21_000)).is_ok());
+        assert!(matches!(
+            policy.check(sender, &transaction(101, 21_000)),
+            Err(SenderPolicyError::ValueTooHigh { value: 101, max_value: 100 })
+        ));
+        assert!(matches!(
+            policy.check(sender, &transaction(0, 21_001)),
+            Err(SenderPolicyError::GasTooHigh { gas: 21_001, max_gas: 21_000 })
+        ));
+        assert!(SenderPolicy::default().check(sender, &transaction(u128::MAX, u64::MAX)).is_ok());
+    }
+}
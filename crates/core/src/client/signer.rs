@@ -0,0 +1,362 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip712::{Eip712, TypedData};
+use reth_primitives::{keccak256, sign_message, Address, Bytes, Transaction, TransactionSigned, H256};
+
+use super::errors::{ConfigError, SignerError};
+
+/// Signer of the transactions sent through `eth_sendTransaction`, holding the private keys of the
+/// accounts managed by the RPC.
+///
+/// The accounts are loaded from a comma separated list of hex private keys
+/// (`KAKAROT_SIGNER_PRIVATE_KEYS`) and/or from an encrypted JSON keystore
+/// (`KAKAROT_SIGNER_KEYSTORE` and `KAKAROT_SIGNER_KEYSTORE_PASSWORD`). Without any of them, the RPC
+/// manages no account and only accepts signed transactions.
+///
+/// The clones of a signer share its accounts: an account unlocked through the `personal` namespace
+/// of the dev network is used by `eth_sendTransaction`.
+///
+/// Accounts impersonated through `hardhat_impersonateAccount` are signed for with
+/// [`IMPERSONATION_PRIVATE_KEY`]: their transactions are relayed from the impersonated account,
+/// which only succeeds on a Katana devnet skipping the validation of the transactions.
+#[derive(Default, Clone)]
+pub struct EthSigner {
+    accounts: Arc<RwLock<BTreeMap<Address, ManagedKey>>>,
+    impersonated: Arc<RwLock<BTreeSet<Address>>>,
+}
+
+/// Private key signing the transactions of the impersonated accounts, the signature is never
+/// checked.
+pub const IMPERSONATION_PRIVATE_KEY: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+
+/// Secret key of a managed account, along with the instant at which an account unlocked for a
+/// limited duration is locked again.
+#[derive(Clone, Copy)]
+struct ManagedKey {
+    secret_key: H256,
+    locked_at: Option<Instant>,
+}
+
+impl ManagedKey {
+    fn is_unlocked(&self) -> bool {
+        self.locked_at.map_or(true, |locked_at| Instant::now() < locked_at)
+    }
+}
+
+impl fmt::Debug for EthSigner {
+    // Never print the secret keys
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EthSigner").field("accounts", &self.accounts()).finish()
+    }
+}
+
+impl EthSigner {
+    /// Create a new `EthSigner` managing the accounts of the given hex private keys.
+    pub fn from_private_keys<'a>(private_keys: impl IntoIterator<Item = &'a str>) -> Result<Self, SignerError> {
+        let signer = Self::default();
+        for private_key in private_keys {
+            let wallet: LocalWallet = private_key
+                .trim()
+                .trim_start_matches("0x")
+                .parse()
+                .map_err(|_| SignerError::InvalidPrivateKey(private_key.to_string()))?;
+            signer.add_wallet(&wallet);
+        }
+        Ok(signer)
+    }
+
+    /// Create a new `EthSigner` managing the account stored in an encrypted JSON keystore.
+    pub fn from_keystore(path: impl AsRef<Path>, password: &str) -> Result<Self, SignerError> {
+        let wallet = LocalWallet::decrypt_keystore(path, password).map_err(|e| SignerError::Keystore(e.to_string()))?;
+        let signer = Self::default();
+        signer.add_wallet(&wallet);
+        Ok(signer)
+    }
+
+    /// Create a new `EthSigner` from environment variables. `KAKAROT_SIGNER_PRIVATE_KEYS` and
+    /// `KAKAROT_SIGNER_KEYSTORE` are both optional and can be combined.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let invalid = |err: SignerError| ConfigError::EnvironmentVariableSetWrong(format!("Signer: {err}"));
+
+        let signer = match std::env::var("KAKAROT_SIGNER_PRIVATE_KEYS") {
+            Ok(private_keys) => Self::from_private_keys(private_keys.split(',').filter(|key| !key.trim().is_empty()))
+                .map_err(invalid)?,
+            Err(_) => Self::default(),
+        };
+
+        if let Ok(keystore) = std::env::var("KAKAROT_SIGNER_KEYSTORE") {
+            let password = std::env::var("KAKAROT_SIGNER_KEYSTORE_PASSWORD")
+                .map_err(|_| ConfigError::EnvironmentVariableMissing("KAKAROT_SIGNER_KEYSTORE_PASSWORD".to_string()))?;
+            let wallet = LocalWallet::decrypt_keystore(keystore, &password)
+                .map_err(|e| invalid(SignerError::Keystore(e.to_string())))?;
+            signer.add_wallet(&wallet);
+        }
+
+        Ok(signer)
+    }
+
+    /// Adds the account of the wallet, unlocked until the RPC stops.
+    pub fn add_wallet(&self, wallet: &LocalWallet) {
+        self.unlock(wallet, None);
+    }
+
+    /// Adds the account of the wallet, unlocked for the given duration or until the RPC stops.
+    /// Unlocking an account already managed replaces its unlock duration.
+    pub fn unlock(&self, wallet: &LocalWallet, duration: Option<Duration>) {
+        let address = Address::from(wallet.address().0);
+        let secret_key = H256::from_slice(&wallet.signer().to_bytes());
+        let locked_at = duration.map(|duration| Instant::now() + duration);
+        self.accounts.write().expect("signer accounts poisoned").insert(address, ManagedKey { secret_key, locked_at });
+    }
+
+    /// Returns the unlocked accounts, sorted by address.
+    pub fn accounts(&self) -> Vec<Address> {
+        let accounts = self.accounts.read().expect("signer accounts poisoned");
+        accounts.iter().filter(|(_, key)| key.is_unlocked()).map(|(address, _)| *address).collect()
+    }
+
+    /// Returns true if the signer holds the key of the account and the account is unlocked.
+    pub fn has_account(&self, address: &Address) -> bool {
+        self.secret_key(address).is_ok()
+    }
+
+    fn secret_key(&self, address: &Address) -> Result<H256, SignerError> {
+        let accounts = self.accounts.read().expect("signer accounts poisoned");
+        accounts
+            .get(address)
+            .filter(|key| key.is_unlocked())
+            .map(|key| key.secret_key)
+            .ok_or(SignerError::UnknownAccount(*address))
+    }
+
+    /// Starts impersonating the account, as `hardhat_impersonateAccount` does.
+    pub fn impersonate(&self, address: Address) {
+        self.impersonated.write().expect("impersonated accounts poisoned").insert(address);
+    }
+
+    /// Stops impersonating the account. Returns false if the account wasn't impersonated.
+    pub fn stop_impersonating(&self, address: &Address) -> bool {
+        self.impersonated.write().expect("impersonated accounts poisoned").remove(address)
+    }
+
+    /// Returns true if the account is impersonated and the signer doesn't hold its key, i.e. if
+    /// its transactions are signed with [`IMPERSONATION_PRIVATE_KEY`].
+    pub fn is_impersonating(&self, address: &Address) -> bool {
+        !self.has_account(address)
+            && self.impersonated.read().expect("impersonated accounts poisoned").contains(address)
+    }
+
+    /// Signs the transaction with the key of the `from` account, or with
+    /// [`IMPERSONATION_PRIVATE_KEY`] if the account is impersonated.
+    pub fn sign_transaction(&self, from: Address, transaction: Transaction) -> Result<TransactionSigned, SignerError> {
+        let secret_key = match self.secret_key(&from) {
+            Err(_) if self.is_impersonating(&from) => H256::from_str(IMPERSONATION_PRIVATE_KEY)
+                .map_err(|_| SignerError::InvalidPrivateKey(IMPERSONATION_PRIVATE_KEY.to_string()))?,
+            secret_key => secret_key?,
+        };
+        let signature = sign_message(secret_key, transaction.signature_hash())
+            .map_err(|e| SignerError::SigningFailed(e.to_string()))?;
+        Ok(TransactionSigned::from_transaction_and_signature(transaction, signature))
+    }
+
+    /// Signs the message with the key of the `from` account, as `personal_sign` does.
+    pub fn sign_message(&self, from: Address, message: &[u8]) -> Result<Bytes, SignerError> {
+        sign_personal_message(self.secret_key(&from)?, message)
+    }
+
+    /// Signs the typed data following EIP-712 with the key of the `from` account, as
+    /// `eth_signTypedData_v4` does.
+    pub fn sign_typed_data(&self, from: Address, typed_data: &TypedData) -> Result<Bytes, SignerError> {
+        let secret_key = self.secret_key(&from)?;
+        let hash = typed_data.encode_eip712().map_err(|e| SignerError::InvalidTypedData(e.to_string()))?;
+        sign_hash(secret_key, H256::from(hash))
+    }
+}
+
+/// Signs the message prefixed as in EIP-191, `"\x19Ethereum Signed Message:\n" + len(message)`,
+/// and returns the 65 bytes signature `r || s || v`, `v` being 27 or 28.
+pub fn sign_personal_message(secret_key: H256, message: &[u8]) -> Result<Bytes, SignerError> {
+    let mut prefixed_message = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    prefixed_message.extend_from_slice(message);
+    sign_hash(secret_key, keccak256(&prefixed_message))
+}
+
+/// Signs the hash and returns the 65 bytes signature `r || s || v`, `v` being 27 or 28.
+fn sign_hash(secret_key: H256, hash: H256) -> Result<Bytes, SignerError> {
+    let signature = sign_message(secret_key, hash).map_err(|e| SignerError::SigningFailed(e.to_string()))?;
+
+    let mut bytes = Vec::with_capacity(65);
+    bytes.extend_from_slice(&signature.r.to_be_bytes::<32>());
+    bytes.extend_from_slice(&signature.s.to_be_bytes::<32>());
+    bytes.push(27 + u8::from(signature.odd_y_parity));
+    Ok(bytes.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use reth_primitives::{AccessList, TransactionKind, TxEip1559};
+
+    use super::*;
+    use crate::client::constants::CHAIN_ID;
+
+    // Anvil first account
+    const PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+    #[test]
+    fn test_sign_transaction() {
+        // Given
+        let signer = EthSigner::from_private_keys([PRIVATE_KEY]).unwrap();
+        let from = Address::from_str(ADDRESS).unwrap();
+        let transaction = Transaction::Eip1559(TxEip1559 {
+            chain_id: CHAIN_ID,
+            nonce: 0,
+            gas_limit: 21_000,
+            max_fee_per_gas: 1,
+            max_priority_fee_per_gas: 0,
+            to: TransactionKind::Call(Address::zero()),
+            value: 1,
+            access_list: AccessList(vec![]),
+            input: Default::default(),
+        });
+
+        // When
+        let signed_transaction = signer.sign_transaction(from, transaction).unwrap();
+
+        // Then
+        assert_eq!(vec![from], signer.accounts());
+        assert_eq!(Some(from), signed_transaction.recover_signer());
+    }
+
+    #[test]
+    fn test_sign_transaction_unknown_account() {
+        // Given
+        let signer = EthSigner::default();
+        let transaction = Transaction::Eip1559(TxEip1559::default());
+
+        // When
+        let result = signer.sign_transaction(Address::zero(), transaction);
+
+        // Then
+        assert!(matches!(result, Err(SignerError::UnknownAccount(address)) if address == Address::zero()));
+    }
+
+    #[test]
+    fn test_sign_transaction_impersonated_account() {
+        // Given
+        let signer = EthSigner::default();
+        let from = Address::from_str(ADDRESS).unwrap();
+        signer.impersonate(from);
+
+        // When
+        let signed_transaction = signer.sign_transaction(from, Transaction::Eip1559(TxEip1559::default())).unwrap();
+
+        // Then
+        assert!(signer.is_impersonating(&from));
+        assert!(signer.accounts().is_empty());
+        assert_ne!(Some(from), signed_transaction.recover_signer());
+        assert!(signer.stop_impersonating(&from));
+        assert!(signer.sign_transaction(from, Transaction::Eip1559(TxEip1559::default())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sign_message() {
+        // Given
+        let signer = EthSigner::from_private_keys([PRIVATE_KEY]).unwrap();
+        let wallet: LocalWallet = PRIVATE_KEY.trim_start_matches("0x").parse().unwrap();
+        let from = Address::from_str(ADDRESS).unwrap();
+
+        // When
+        let signature = signer.sign_message(from, b"hello kakarot").unwrap();
+
+        // Then
+        let expected = wallet.sign_message(b"hello kakarot").await.unwrap();
+        assert_eq!(expected.to_vec(), signature.to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_sign_typed_data() {
+        // Given
+        let signer = EthSigner::from_private_keys([PRIVATE_KEY]).unwrap();
+        let wallet: LocalWallet = PRIVATE_KEY.trim_start_matches("0x").parse().unwrap();
+        let from = Address::from_str(ADDRESS).unwrap();
+        // Example of EIP-712
+        let typed_data: TypedData = serde_json::from_value(serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "Person": [{ "name": "name", "type": "string" }, { "name": "wallet", "type": "address" }],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!"
+            }
+        }))
+        .unwrap();
+
+        // When
+        let signature = signer.sign_typed_data(from, &typed_data).unwrap();
+
+        // Then
+        assert_eq!(
+            "0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2",
+            format!("{:#x}", H256::from(typed_data.encode_eip712().unwrap()))
+        );
+        let expected = wallet.sign_typed_data(&typed_data).await.unwrap();
+        assert_eq!(expected.to_vec(), signature.to_vec());
+    }
+
+    #[test]
+    fn test_unlocked_account_is_locked_after_duration() {
+        // Given
+        let signer = EthSigner::default();
+        let shared = signer.clone();
+        let wallet: LocalWallet = PRIVATE_KEY.trim_start_matches("0x").parse().unwrap();
+        let from = Address::from_str(ADDRESS).unwrap();
+
+        // When
+        signer.unlock(&wallet, Some(Duration::ZERO));
+
+        // Then
+        assert!(!shared.has_account(&from));
+        assert!(shared.accounts().is_empty());
+        assert!(matches!(shared.sign_message(from, b""), Err(SignerError::UnknownAccount(_))));
+
+        // When
+        signer.unlock(&wallet, Some(Duration::from_secs(300)));
+
+        // Then
+        assert!(shared.has_account(&from));
+        assert_eq!(vec![from], shared.accounts());
+    }
+
+    #[test]
+    fn test_invalid_private_key() {
+        assert!(matches!(EthSigner::from_private_keys(["0x1234"]), Err(SignerError::InvalidPrivateKey(_))));
+    }
+}
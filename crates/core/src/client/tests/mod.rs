@@ -1,19 +1,40 @@
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
 use reth_primitives::{BlockId, BlockNumberOrTag, Bytes, H256, U256, U64};
-use reth_rpc_types::{CallRequest, Filter, FilterBlockOption, Log, ValueOrArray};
+use reth_rpc_types::{CallRequest, Filter, FilterBlockOption, FilterChanges, Log, ValueOrArray};
+use rstest::*;
+use serde_json::json;
+use starknet::core::chain_id;
 use starknet::core::types::{BlockId as StarknetBlockId, BlockTag, BroadcastedInvokeTransactionV1};
 use starknet::providers::jsonrpc::JsonRpcMethod;
 use starknet::providers::sequencer::models::BlockId as SequencerBlockId;
+use starknet::providers::JsonRpcClient;
 use starknet_crypto::FieldElement;
 
-use crate::client::api::{KakarotEthApi, KakarotStarknetApi};
-use crate::client::constants::{CHAIN_ID, COUNTER_ADDRESS_TESTNET1, INC_SELECTOR};
+use crate::client::api::{KakarotEthApi, KakarotStarknetApi, StateOverrideBackend};
+use crate::client::config::{chain_id_from_starknet, ChainIdConfig, Network, StarknetConfig};
+use crate::client::constants::{
+    CHAIN_ID, COUNTER_ADDRESS_TESTNET1, INC_SELECTOR, NATIVE_TOKEN_ERC20_ADDRESS, STARKNET_NATIVE_TOKEN,
+};
+use crate::client::errors::EthApiError;
+use crate::client::relayer::{RelayerAccount, RelayerConfig};
+use crate::client::warmup::WarmUpConfig;
+use crate::client::KakarotClient;
+use crate::contracts::erc20::ethereum_erc20::transfer_calldata;
 use crate::mock::constants::{
     ABDEL_ETHEREUM_ADDRESS, ABDEL_STARKNET_ADDRESS, ABDEL_STARKNET_ADDRESS_HEX, ACCOUNT_ADDRESS, ACCOUNT_ADDRESS_EVM,
-    COUNTER_ADDRESS_EVM, INC_DATA, PROXY_ACCOUNT_CLASS_HASH_HEX,
+    COUNTER_ADDRESS_EVM, INC_DATA, KAKAROT_ADDRESS, OTHER_PROXY_ACCOUNT_CLASS_HASH_HEX, PROXY_ACCOUNT_CLASS_HASH,
+    PROXY_ACCOUNT_CLASS_HASH_HEX,
+};
+use crate::mock::mock_starknet::{
+    fixtures, init_mock_client, init_testnet_client, mock_starknet_provider, AvailableFixtures, ChainMockTransport,
+    MethodMockTransport, StarknetRpcFixture,
 };
-use crate::mock::mock_starknet::{fixtures, init_mock_client, init_testnet_client, AvailableFixtures};
+use crate::models::felt::Felt252Wrapper;
+use crate::models::simulation::SimulationRequest;
+use crate::models::state_override::{AccountOverride, StarknetStateWrite, StateOverride};
+use crate::models::trace::TracingOptions;
 use crate::wrap_kakarot;
 
 #[tokio::test]
@@ -42,6 +63,22 @@ async fn test_nonce() {
     assert_eq!(U256::from(1), nonce);
 }
 
+#[tokio::test]
+async fn test_starknet_block_id() {
+    // Given
+    let client = init_mock_client(Some(fixtures(vec![])));
+
+    // When
+    let pending = client.starknet_block_id(BlockId::Number(BlockNumberOrTag::Pending)).await.unwrap();
+    let latest = client.starknet_block_id(BlockId::Number(BlockNumberOrTag::Latest)).await.unwrap();
+    let number = client.starknet_block_id(BlockId::Number(BlockNumberOrTag::Number(42))).await.unwrap();
+
+    // Then
+    assert!(matches!(pending, StarknetBlockId::Tag(BlockTag::Pending)));
+    assert!(matches!(latest, StarknetBlockId::Tag(BlockTag::Latest)));
+    assert!(matches!(number, StarknetBlockId::Number(42)));
+}
+
 #[tokio::test]
 async fn test_get_evm_address() {
     // Given
@@ -57,37 +94,32 @@ async fn test_get_evm_address() {
 }
 
 #[tokio::test]
-async fn test_fee_history() {
+async fn test_fee_history_empty_range() {
     // Given
-    let fixtures = fixtures(vec![wrap_kakarot!(JsonRpcMethod::BlockNumber)]);
+    let fixtures = fixtures(vec![]);
     let client = init_mock_client(Some(fixtures));
 
     // When
-    let count = 10;
-    let block_count = U256::from(count);
-    let newest_block = BlockNumberOrTag::Latest;
-    let fee_history = client.fee_history(block_count, newest_block, None).await.unwrap();
+    let fee_history = client.fee_history(U256::ZERO, BlockNumberOrTag::Number(1), Some(vec![25., 75.])).await.unwrap();
 
     // Then
-    assert_eq!(vec![U256::from(1); count + 1], fee_history.base_fee_per_gas);
-    assert_eq!(vec![0.9; count], fee_history.gas_used_ratio);
-    assert_eq!(U256::from(19630), fee_history.oldest_block);
-    assert_eq!((Some(vec![vec![]])), fee_history.reward);
+    assert_eq!(U256::from(0), fee_history.oldest_block);
+    assert!(fee_history.base_fee_per_gas.is_empty());
+    assert!(fee_history.gas_used_ratio.is_empty());
+    assert_eq!(Some(vec![]), fee_history.reward);
 }
 
 #[tokio::test]
-async fn test_fee_history_should_return_oldest_block_0() {
+async fn test_fee_history_should_fail_on_invalid_reward_percentiles() {
     // Given
     let fixtures = fixtures(vec![]);
     let client = init_mock_client(Some(fixtures));
 
     // When
-    let block_count = U256::from(10);
-    let newest_block = BlockNumberOrTag::Number(1);
-    let fee_history = client.fee_history(block_count, newest_block, None).await.unwrap();
+    let result = client.fee_history(U256::from(10), BlockNumberOrTag::Number(1), Some(vec![75., 25.])).await;
 
     // Then
-    assert_eq!(U256::from(0), fee_history.oldest_block);
+    assert!(matches!(result, Err(EthApiError::InvalidParameterError(_))));
 }
 
 #[tokio::test]
@@ -118,6 +150,53 @@ async fn test_transaction_by_hash() {
     assert_eq!(U256::from(0), tx.nonce);
 }
 
+#[rstest]
+#[case(PROXY_ACCOUNT_CLASS_HASH_HEX, true)]
+#[case(OTHER_PROXY_ACCOUNT_CLASS_HASH_HEX, false)]
+#[tokio::test]
+async fn test_transaction_by_starknet_hash(#[case] class_hash: &str, #[case] is_kakarot: bool) {
+    // Given
+    let fixtures = fixtures(vec![
+        wrap_kakarot!(JsonRpcMethod::GetTransactionByHash),
+        wrap_kakarot!(JsonRpcMethod::GetTransactionReceipt),
+        AvailableFixtures::GetClassHashAt(ABDEL_STARKNET_ADDRESS_HEX.into(), class_hash.into()),
+        AvailableFixtures::GetEvmAddress,
+    ]);
+    let client = init_mock_client(Some(fixtures));
+    let hash = H256::from_str("0x03204b4c0e379c3a5ccb80d08661d5a538e95e2960581c9faf7ebcf8ff5a7d3c").unwrap();
+
+    // When
+    let tx = client.transaction_by_starknet_hash(hash).await.unwrap();
+
+    // Then
+    // Starknet transactions which aren't sent by a Kakarot account aren't returned
+    assert_eq!(is_kakarot, tx.is_some());
+    if let Some(tx) = tx {
+        assert_eq!(hash, tx.hash);
+        assert_eq!(*ABDEL_ETHEREUM_ADDRESS, tx.from);
+    }
+}
+
+#[rstest]
+#[case(json!({ "error": { "code": 25, "message": "Transaction hash not found" } }), true)]
+#[case(json!({ "error": { "code": 1, "message": "Failed to write transaction" } }), false)]
+#[tokio::test]
+async fn test_transaction_by_starknet_hash_propagates_errors(
+    #[case] response: serde_json::Value,
+    #[case] is_not_found: bool,
+) {
+    // Given
+    let (client, _) = relayer_client(vec![(JsonRpcMethod::GetTransactionByHash, response)]);
+
+    // When
+    let tx = client.transaction_by_starknet_hash(H256::from_low_u64_be(1)).await;
+
+    // Then
+    // Only an unknown transaction is reported as missing, the failures of the node are returned
+    assert_eq!(is_not_found, matches!(tx, Ok(None)));
+    assert_eq!(!is_not_found, tx.is_err());
+}
+
 #[tokio::test]
 #[allow(deprecated)]
 async fn test_simulate_transaction() {
@@ -146,7 +225,7 @@ async fn test_simulate_transaction() {
     };
 
     // When
-    let simulation = client.simulate_transaction(tx, block_number, true).await.unwrap();
+    let simulation = client.simulate_transaction(tx, StarknetBlockId::Number(block_number), true).await.unwrap();
 
     // Then
     assert!(simulation.fee_estimation.gas_price > 0);
@@ -230,6 +309,47 @@ async fn test_get_logs_to_less_than_from() {
     assert!(logs.is_empty());
 }
 
+#[tokio::test]
+async fn test_get_logs_from_pending_to_latest() {
+    // Given
+    let fixtures = fixtures(vec![wrap_kakarot!(JsonRpcMethod::BlockNumber)]);
+    let client = init_mock_client(Some(fixtures));
+    let filter = Filter {
+        block_option: FilterBlockOption::Range {
+            from_block: Some(BlockNumberOrTag::Pending),
+            to_block: Some(BlockNumberOrTag::Latest),
+        },
+        ..Default::default()
+    };
+
+    // When
+    let logs = client.get_logs(filter).await.unwrap();
+
+    // Then
+    assert!(logs.is_empty());
+}
+
+#[tokio::test]
+async fn test_get_logs_from_earliest() {
+    // Given
+    let fixtures = fixtures(vec![wrap_kakarot!(JsonRpcMethod::BlockNumber), wrap_kakarot!(JsonRpcMethod::GetEvents)]);
+    let client = init_mock_client(Some(fixtures));
+    let filter = Filter {
+        block_option: FilterBlockOption::Range {
+            from_block: Some(BlockNumberOrTag::Earliest),
+            to_block: Some(BlockNumberOrTag::Number(10)),
+        },
+        address: Some(ValueOrArray::Value(*ABDEL_ETHEREUM_ADDRESS)),
+        ..Default::default()
+    };
+
+    // When
+    let logs = client.get_logs(filter).await.unwrap();
+
+    // Then
+    assert_eq!(2, logs.len());
+}
+
 #[tokio::test]
 async fn test_get_logs() {
     // Given
@@ -286,3 +406,501 @@ async fn test_get_logs() {
         logs[1]
     )
 }
+
+#[tokio::test]
+async fn test_block_filter_without_new_block() {
+    // Given
+    let fixtures = fixtures(vec![wrap_kakarot!(JsonRpcMethod::BlockNumber)]);
+    let client = init_mock_client(Some(fixtures));
+    let id = client.new_block_filter().await.unwrap();
+
+    // When
+    let changes = client.get_filter_changes(id).await.unwrap();
+    let uninstalled = client.uninstall_filter(id);
+    let changes_after_uninstall = client.get_filter_changes(id).await;
+
+    // Then
+    assert_eq!(FilterChanges::Hashes(vec![]), changes);
+    assert!(uninstalled);
+    assert!(matches!(changes_after_uninstall, Err(EthApiError::FilterNotFound(_))));
+}
+
+#[rstest]
+#[case(PROXY_ACCOUNT_CLASS_HASH_HEX, true)]
+#[case(OTHER_PROXY_ACCOUNT_CLASS_HASH_HEX, false)]
+#[tokio::test]
+async fn test_validate_coinbase(#[case] class_hash: &str, #[case] expected_valid: bool) {
+    // Given
+    let fixtures = fixtures(vec![
+        AvailableFixtures::ComputeStarknetAddress,
+        AvailableFixtures::GetClassHashAt(ABDEL_STARKNET_ADDRESS_HEX.into(), class_hash.into()),
+    ]);
+    let config = StarknetConfig {
+        coinbase: Some(*ABDEL_ETHEREUM_ADDRESS),
+        ..StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH)
+    };
+    let client = KakarotClient::new(config, mock_starknet_provider(Some(fixtures)));
+
+    // When
+    let result = client.validate_coinbase().await;
+
+    // Then
+    assert_eq!(expected_valid, result.is_ok());
+    assert_eq!(Some(*ABDEL_ETHEREUM_ADDRESS), client.coinbase());
+}
+
+#[tokio::test]
+async fn test_validate_native_token() {
+    // Given
+    let fixtures = fixtures(vec![AvailableFixtures::GetClassHashAt(
+        STARKNET_NATIVE_TOKEN.into(),
+        PROXY_ACCOUNT_CLASS_HASH_HEX.into(),
+    )]);
+    let client = init_mock_client(Some(fixtures));
+    let config = StarknetConfig {
+        native_token_address: FieldElement::from(0x1234_u64),
+        ..StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH)
+    };
+    let other_token_client = KakarotClient::new(config, mock_starknet_provider(None));
+    let config = StarknetConfig {
+        fee_token_address: Some(FieldElement::from(0x1234_u64)),
+        ..StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH)
+    };
+    let other_fee_token_client = KakarotClient::new(config, mock_starknet_provider(None));
+
+    // Then
+    assert!(client.validate_native_token().await.is_ok());
+    assert!(other_token_client.validate_native_token().await.is_err());
+    assert!(other_fee_token_client.validate_native_token().await.is_err());
+}
+
+#[tokio::test]
+async fn test_configured_chain_id() {
+    // Given
+    let config = StarknetConfig {
+        chain_id: ChainIdConfig::Fixed(7),
+        ..StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH)
+    };
+    let client = KakarotClient::new(config, mock_starknet_provider(None));
+
+    // Then
+    assert_eq!(7, client.chain_id());
+    assert_eq!(CHAIN_ID, init_mock_client(None).chain_id());
+}
+
+#[rstest]
+#[case(
+    json!({ "jsonrpc": "2.0", "id": 1, "result": format!("{:#x}", chain_id::TESTNET) }),
+    Some(8_804_130_069_040_201)
+)]
+#[case(json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32603, "message": "Internal error" } }), None)]
+#[tokio::test]
+async fn test_chain_id_resolved_from_starknet(#[case] response: serde_json::Value, #[case] expected: Option<u64>) {
+    // Given
+    let mut transport = MethodMockTransport::default();
+    transport.set_response(JsonRpcMethod::ChainId, response);
+    let config = StarknetConfig {
+        chain_id: ChainIdConfig::Starknet,
+        ..StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH)
+    };
+
+    // When
+    let client = KakarotClient::try_new(config, JsonRpcClient::new(transport)).await;
+
+    // Then
+    // The client isn't created with a made up chain id when the Starknet chain id can't be fetched
+    assert_eq!(expected, client.ok().map(|client| client.chain_id()));
+}
+
+#[test]
+fn test_chain_id_from_starknet() {
+    // SN_GOERLI truncated to its lowest 53 bits
+    assert_eq!(8_804_130_069_040_201, chain_id_from_starknet(chain_id::TESTNET));
+    assert_eq!(CHAIN_ID, chain_id_from_starknet(FieldElement::from(CHAIN_ID)));
+}
+
+/// Backend recording the writes it applies, restoring each written value to zero.
+#[derive(Default)]
+struct RecordingStateOverrideBackend {
+    applied: std::sync::Mutex<Vec<Vec<StarknetStateWrite>>>,
+}
+
+#[async_trait::async_trait]
+impl StateOverrideBackend for RecordingStateOverrideBackend {
+    async fn apply(&self, writes: Vec<StarknetStateWrite>) -> eyre::Result<Vec<StarknetStateWrite>> {
+        let restore = writes
+            .iter()
+            .map(|write| match *write {
+                StarknetStateWrite::Storage { contract_address, key, .. } => {
+                    StarknetStateWrite::Storage { contract_address, key, value: FieldElement::ZERO }
+                }
+                StarknetStateWrite::Nonce { contract_address, .. } => {
+                    StarknetStateWrite::Nonce { contract_address, nonce: FieldElement::ZERO }
+                }
+            })
+            .collect();
+        self.applied.lock().unwrap().push(writes);
+        Ok(restore)
+    }
+}
+
+#[tokio::test]
+async fn test_call_with_state_override_without_backend() {
+    // Given
+    let client = init_mock_client(None);
+    let state_override = StateOverride::from([(
+        *ABDEL_ETHEREUM_ADDRESS,
+        AccountOverride { balance: Some(U256::from(1)), ..Default::default() },
+    )]);
+
+    // When
+    let result = client
+        .call(*COUNTER_ADDRESS_EVM, Bytes::default(), BlockId::Number(BlockNumberOrTag::Latest), Some(state_override))
+        .await;
+
+    // Then
+    assert!(matches!(result, Err(EthApiError::InvalidParameterError(_))));
+}
+
+#[tokio::test]
+async fn test_call_with_state_override_restores_state() {
+    // Given
+    let backend = std::sync::Arc::new(RecordingStateOverrideBackend::default());
+    let config = StarknetConfig {
+        state_override_backend: Some(backend.clone()),
+        ..StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH)
+    };
+    // The provider has no fixture for the call, which fails
+    let client = KakarotClient::new(config, mock_starknet_provider(Some(fixtures(vec![]))));
+    let state_override = StateOverride::from([(
+        *ABDEL_ETHEREUM_ADDRESS,
+        AccountOverride { nonce: Some(U64::from(5)), ..Default::default() },
+    )]);
+
+    // When
+    let result = client
+        .call(*COUNTER_ADDRESS_EVM, Bytes::default(), BlockId::Number(BlockNumberOrTag::Latest), Some(state_override))
+        .await;
+
+    // Then
+    assert!(result.is_err());
+    let contract_address = client.compute_starknet_addresses(&[*ABDEL_ETHEREUM_ADDRESS])[0];
+    let applied = backend.applied.lock().unwrap();
+    assert_eq!(
+        vec![
+            vec![StarknetStateWrite::Nonce { contract_address, nonce: FieldElement::from(5u8) }],
+            vec![StarknetStateWrite::Nonce { contract_address, nonce: FieldElement::ZERO }],
+        ],
+        *applied
+    );
+}
+
+#[tokio::test]
+async fn test_call_with_state_override_at_past_block() {
+    // Given
+    let config = StarknetConfig {
+        state_override_backend: Some(std::sync::Arc::new(RecordingStateOverrideBackend::default())),
+        ..StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH)
+    };
+    let client = KakarotClient::new(config, mock_starknet_provider(None));
+    let state_override = StateOverride::from([(*ABDEL_ETHEREUM_ADDRESS, AccountOverride::default())]);
+
+    // When
+    let result = client
+        .call(
+            *COUNTER_ADDRESS_EVM,
+            Bytes::default(),
+            BlockId::Number(BlockNumberOrTag::Number(1)),
+            Some(state_override),
+        )
+        .await;
+
+    // Then
+    assert!(matches!(result, Err(EthApiError::InvalidParameterError(_))));
+}
+
+#[tokio::test]
+async fn test_native_token_erc20_is_read_only() {
+    // Given
+    let client = init_mock_client(None);
+    let transfer = transfer_calldata(ABDEL_ETHEREUM_ADDRESS.to_fixed_bytes().into(), U256::from(1));
+
+    // When
+    let result =
+        client.call(*NATIVE_TOKEN_ERC20_ADDRESS, transfer, BlockId::Number(BlockNumberOrTag::Latest), None).await;
+
+    // Then
+    assert!(matches!(result, Err(EthApiError::InvalidParameterError(message)) if message.contains("read-only")));
+}
+
+/// Hash of the Kakarot transaction of the `starknet_getTransactionByHash` fixture.
+const KAKAROT_TRANSACTION_HASH: &str = "0x3204b4c0e379c3a5ccb80d08661d5a538e95e2960581c9faf7ebcf8ff5a7d3c";
+
+/// Returns the fixtures of the block with the hash, with and without its transactions, holding the
+/// Kakarot transaction of the `starknet_getTransactionByHash` fixture.
+fn kakarot_block_fixtures(block_hash: &str) -> Vec<StarknetRpcFixture> {
+    let read_fixture =
+        |path: &str| serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(path).unwrap()).unwrap();
+    let transaction = read_fixture("src/mock/fixtures/responses/starknet_getTransactionByHash.json")["result"].clone();
+    let mut block_with_tx_hashes =
+        read_fixture("src/mock/fixtures/responses/blocks/starknet_getBlockWithTxHashes_hash.json");
+    block_with_tx_hashes["result"]["block_hash"] = json!(block_hash);
+    let mut block_with_txs = block_with_tx_hashes.clone();
+    block_with_tx_hashes["result"]["transactions"] = json!([KAKAROT_TRANSACTION_HASH]);
+    block_with_txs["result"]["transactions"] = json!([transaction]);
+
+    let params = json!([{ "block_hash": block_hash }]);
+    let mut block_fixtures = vec![
+        StarknetRpcFixture::new(JsonRpcMethod::GetBlockWithTxHashes, params.clone(), block_with_tx_hashes),
+        StarknetRpcFixture::new(JsonRpcMethod::GetBlockWithTxs, params, block_with_txs),
+    ];
+    block_fixtures.extend(fixtures(vec![
+        AvailableFixtures::GetClassHashAt(ABDEL_STARKNET_ADDRESS_HEX.into(), PROXY_ACCOUNT_CLASS_HASH_HEX.into()),
+        AvailableFixtures::GetEvmAddress,
+    ]));
+    block_fixtures
+}
+
+#[tokio::test]
+async fn test_block_with_failed_receipt_is_not_cached() {
+    // Given
+    let block_hash = "0x197be2810df6b5eedd5d9e468b200d0b845b642b81a44755e19047f08cc8c6e";
+    let mut fixtures = kakarot_block_fixtures(block_hash);
+    fixtures.push(StarknetRpcFixture::new(
+        JsonRpcMethod::GetTransactionReceipt,
+        json!([KAKAROT_TRANSACTION_HASH]),
+        json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": 25, "message": "Transaction hash not found" } }),
+    ));
+    let client = init_mock_client(Some(fixtures));
+    let block_id = StarknetBlockId::Hash(FieldElement::from_hex_be(block_hash).unwrap());
+
+    // When
+    let block = client.get_eth_block_from_starknet_block(block_id, false).await.unwrap();
+
+    // Then
+    // The block is served without its gas used, but neither it nor its hydrated version is cached
+    assert_eq!(U256::ZERO, block.header.gas_used);
+    assert_eq!(0, client.cache_stats()["blocks"].entries);
+}
+
+#[tokio::test]
+async fn test_block_gas_used_is_computed_once() {
+    // Given
+    let block_hash = "0x197be2810df6b5eedd5d9e468b200d0b845b642b81a44755e19047f08cc8c6e";
+    let mut block_fixtures = kakarot_block_fixtures(block_hash);
+    block_fixtures.extend(fixtures(vec![wrap_kakarot!(JsonRpcMethod::GetTransactionReceipt)]));
+    let client = init_mock_client(Some(block_fixtures));
+    let block_id = StarknetBlockId::Hash(FieldElement::from_hex_be(block_hash).unwrap());
+
+    // When
+    let block = client.get_eth_block_from_starknet_block(block_id, false).await.unwrap();
+    let hydrated_block = client.get_eth_block_from_starknet_block(block_id, true).await.unwrap();
+
+    // Then
+    // The gas used and the bloom computed for the block without its transactions are reused
+    assert_eq!(block.header.gas_used, hydrated_block.header.gas_used);
+    assert_eq!(block.header.logs_bloom, hydrated_block.header.logs_bloom);
+    let stats = client.cache_stats()["gasUsed"];
+    assert_eq!((1, 1, 1), (stats.hits, stats.misses, stats.entries));
+}
+
+#[tokio::test]
+async fn test_l1_accepted_block_number_searches_from_last_found() {
+    // Given
+    let transport = ChainMockTransport::default();
+    *transport.hashes.lock().unwrap() = (1..=1000).collect();
+    *transport.l1_accepted_blocks.lock().unwrap() = 600;
+    let (l1_accepted_blocks, calls) = (Arc::clone(&transport.l1_accepted_blocks), transport.calls());
+    let config = StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH);
+    let client = KakarotClient::new(config, JsonRpcClient::new(transport));
+    let block_fetches = || calls.lock().unwrap().iter().filter(|call| *call == "starknet_getBlockWithTxHashes").count();
+
+    // When
+    let first = client.l1_accepted_block_number().await.unwrap();
+    *l1_accepted_blocks.lock().unwrap() = 603;
+    let fetches_before = block_fetches();
+    let second = client.l1_accepted_block_number().await.unwrap();
+    let fetches_after_second = block_fetches();
+    let third = client.l1_accepted_block_number().await.unwrap();
+
+    // Then
+    assert_eq!((Some(599), Some(602), Some(602)), (first, second, third));
+    // The blocks accepted since the last call are found close to the last block found
+    assert!(fetches_after_second - fetches_before <= 6);
+    // Without newly accepted blocks, only the block following the last one found is fetched
+    assert_eq!(1, block_fetches() - fetches_after_second);
+}
+
+#[tokio::test]
+async fn test_warm_up_caches_latest_blocks() {
+    // Given
+    let mut block: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string("src/mock/fixtures/responses/blocks/starknet_getBlockWithTxHashes.json").unwrap(),
+    )
+    .unwrap();
+    block["result"]["transactions"] = json!([]);
+    let mut transport = MethodMockTransport::default();
+    transport.set_response(JsonRpcMethod::GetBlockWithTxHashes, block.clone());
+    transport.set_response(JsonRpcMethod::GetBlockWithTxs, block);
+    // The state of the coinbase can't be read, which doesn't fail the warm-up
+    transport.set_response(
+        JsonRpcMethod::Call,
+        json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": 20, "message": "Contract not found" } }),
+    );
+    let config = StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH);
+    let client = KakarotClient::new(config, JsonRpcClient::new(transport));
+
+    // When
+    let report = client.warm_up(&WarmUpConfig::new(3)).await.unwrap();
+
+    // Then
+    let sequencer =
+        FieldElement::from_hex_be("0x5dcd266a80b8a5f29f04d779c6b166b80150c24f2180a75e82427242dab20a9").unwrap();
+    assert_eq!(Felt252Wrapper::from(sequencer).truncate_to_ethereum_address(), report.coinbase);
+    assert_eq!(3, report.blocks_fetched);
+    // The mocked blocks all share the same hash
+    assert_eq!(1, client.cache_stats()["blocks"].entries);
+}
+
+/// Returns a client auto-deploying the senders from a single relayer account, which doesn't retry
+/// the rejected submissions, with the Starknet methods answered by the `result` or `error` of the
+/// responses, and the methods it calls.
+fn relayer_client(
+    responses: Vec<(JsonRpcMethod, serde_json::Value)>,
+) -> (KakarotClient<JsonRpcClient<MethodMockTransport>>, Arc<Mutex<Vec<String>>>) {
+    let mut transport = MethodMockTransport::default();
+    for (method, mut response) in responses {
+        response["jsonrpc"] = json!("2.0");
+        response["id"] = json!(1);
+        transport.set_response(method, response);
+    }
+    let calls = transport.calls();
+    let relayer = RelayerConfig {
+        accounts: vec![RelayerAccount::new(FieldElement::ONE, FieldElement::from(2u8))],
+        max_retries: 0,
+        auto_deploy_eoa: true,
+    };
+    let config = StarknetConfig {
+        relayer: Some(relayer),
+        ..StarknetConfig::new(Network::Katana, *KAKAROT_ADDRESS, *PROXY_ACCOUNT_CLASS_HASH)
+    };
+    (KakarotClient::new(config, JsonRpcClient::new(transport)), calls)
+}
+
+/// Responses of the Starknet node to the deployment of an account by the relayer.
+fn deployment_responses() -> Vec<(JsonRpcMethod, serde_json::Value)> {
+    let receipt: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string("src/mock/fixtures/responses/starknet_getTransactionReceipt.json").unwrap(),
+    )
+    .unwrap();
+    vec![
+        (JsonRpcMethod::GetClassHashAt, json!({ "error": { "code": 20, "message": "Contract not found" } })),
+        (JsonRpcMethod::ChainId, json!({ "result": "0x4b4154414e41" })),
+        (JsonRpcMethod::GetNonce, json!({ "result": "0x0" })),
+        (JsonRpcMethod::AddInvokeTransaction, json!({ "result": { "transaction_hash": "0xde1" } })),
+        (JsonRpcMethod::GetTransactionReceipt, receipt),
+    ]
+}
+
+#[tokio::test]
+async fn test_relay_deploys_undeployed_sender() {
+    // Given
+    let (client, calls) = relayer_client(deployment_responses());
+    let relayer = client.relayer.as_ref().unwrap();
+    let starknet_address = client.compute_starknet_addresses(&[*ABDEL_ETHEREUM_ADDRESS])[0];
+
+    // When
+    client.deploy_sender_if_needed(relayer, *ABDEL_ETHEREUM_ADDRESS, starknet_address).await.unwrap();
+
+    // Then
+    // The deployment is executed before the transaction is relayed
+    assert_eq!(
+        vec![
+            "starknet_getClassHashAt",
+            "starknet_chainId",
+            "starknet_getNonce",
+            "starknet_addInvokeTransaction",
+            "starknet_getTransactionReceipt"
+        ],
+        *calls.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_relay_skips_deployed_sender() {
+    // Given
+    let mut responses = deployment_responses();
+    responses[0] = (JsonRpcMethod::GetClassHashAt, json!({ "result": PROXY_ACCOUNT_CLASS_HASH_HEX }));
+    let (client, calls) = relayer_client(responses);
+    let relayer = client.relayer.as_ref().unwrap();
+    let starknet_address = client.compute_starknet_addresses(&[*ABDEL_ETHEREUM_ADDRESS])[0];
+
+    // When
+    client.deploy_sender_if_needed(relayer, *ABDEL_ETHEREUM_ADDRESS, starknet_address).await.unwrap();
+
+    // Then
+    assert_eq!(vec!["starknet_getClassHashAt"], *calls.lock().unwrap());
+}
+
+#[rstest]
+#[case::submission_failure(
+    JsonRpcMethod::AddInvokeTransaction,
+    json!({ "error": { "code": 1, "message": "Failed to write transaction" } })
+)]
+#[case::rejected_deployment(JsonRpcMethod::GetTransactionReceipt, {
+    let mut receipt: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string("src/mock/fixtures/responses/starknet_getTransactionReceipt.json").unwrap(),
+    )
+    .unwrap();
+    receipt["result"]["status"] = json!("REJECTED");
+    receipt
+})]
+#[tokio::test]
+async fn test_relay_propagates_deployment_failure(#[case] method: JsonRpcMethod, #[case] response: serde_json::Value) {
+    // Given
+    let mut responses = deployment_responses();
+    responses.push((method, response));
+    let (client, _) = relayer_client(responses);
+    let relayer = client.relayer.as_ref().unwrap();
+    let starknet_address = client.compute_starknet_addresses(&[*ABDEL_ETHEREUM_ADDRESS])[0];
+
+    // When
+    let result = client.deploy_sender_if_needed(relayer, *ABDEL_ETHEREUM_ADDRESS, starknet_address).await;
+
+    // Then
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_simulate_ethereum_transaction_requires_gateway() {
+    // Given
+    let client = init_mock_client(None);
+    let request =
+        SimulationRequest::Unsigned(CallRequest { from: Some(*ABDEL_ETHEREUM_ADDRESS), ..Default::default() });
+
+    // When
+    let result = client.simulate_ethereum_transaction(request, BlockId::Number(BlockNumberOrTag::Latest)).await;
+
+    // Then
+    // Katana has no feeder gateway to simulate the transaction, no simulation is made up
+    assert!(matches!(result, Err(EthApiError::Unsupported(_))));
+}
+
+#[tokio::test]
+async fn test_trace_call_requires_gateway() {
+    // Given
+    let client = init_mock_client(None);
+    let request = CallRequest {
+        from: Some(*ABDEL_ETHEREUM_ADDRESS),
+        to: Some(*COUNTER_ADDRESS_EVM),
+        data: Some(Bytes::from_str(INC_DATA).unwrap()),
+        ..Default::default()
+    };
+
+    // When
+    let result = client.trace_call(request, BlockId::Number(BlockNumberOrTag::Latest), TracingOptions::default()).await;
+
+    // Then
+    // The trace isn't made up from an estimation when the call can't be simulated
+    assert!(matches!(result, Err(EthApiError::Unsupported(_))));
+}
@@ -0,0 +1,442 @@
+use std::num::NonZeroUsize;
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use reth_primitives::H256;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use super::errors::ConfigError;
+use crate::storage::{prefixed_key, Entry, Storage, StorageError};
+
+/// Default number of recent blocks scanned when looking up an unknown transaction hash.
+pub const DEFAULT_TRANSACTION_SCAN_DEPTH: u64 = 64;
+
+/// Number of blocks fetched concurrently while scanning.
+pub const TRANSACTION_SCAN_CONCURRENCY: usize = 8;
+
+/// Default number of transaction hash pairs kept in memory.
+pub const DEFAULT_TRANSACTION_INDEX_SIZE: usize = 100_000;
+
+/// Number of unknown hashes remembered, so that looking them up again doesn't scan the blocks
+/// until a new block arrives.
+pub const TRANSACTION_MISS_CACHE_SIZE: usize = 10_000;
+
+/// Maximum number of lookups scanning the blocks at the same time.
+pub const MAX_CONCURRENT_TRANSACTION_SCANS: usize = 4;
+
+/// Configuration of the lookup of transactions by their Ethereum hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionLookupConfig {
+    /// Number of recent blocks scanned for a transaction which isn't indexed yet.
+    pub scan_depth: u64,
+    /// Scan the whole chain, down to the Kakarot deployment block, instead of the recent blocks
+    /// only. Meant for explorer deployments which need exhaustive lookups.
+    pub exhaustive: bool,
+    /// Number of transaction hash pairs kept in memory, the older ones are read back from the
+    /// storage.
+    pub index_size: usize,
+}
+
+impl Default for TransactionLookupConfig {
+    fn default() -> Self {
+        Self {
+            scan_depth: DEFAULT_TRANSACTION_SCAN_DEPTH,
+            exhaustive: false,
+            index_size: DEFAULT_TRANSACTION_INDEX_SIZE,
+        }
+    }
+}
+
+impl TransactionLookupConfig {
+    /// Create a new `TransactionLookupConfig` from environment variables, falling back to the
+    /// default values when `KAKAROT_TRANSACTION_SCAN_DEPTH`,
+    /// `KAKAROT_EXHAUSTIVE_TRANSACTION_LOOKUP` and `KAKAROT_TRANSACTION_INDEX_SIZE` are not set.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let scan_depth = match std::env::var("KAKAROT_TRANSACTION_SCAN_DEPTH") {
+            Ok(depth) => depth.parse::<u64>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_TRANSACTION_SCAN_DEPTH should be a positive integer, got {depth}"
+                ))
+            })?,
+            Err(_) => DEFAULT_TRANSACTION_SCAN_DEPTH,
+        };
+        let exhaustive =
+            std::env::var("KAKAROT_EXHAUSTIVE_TRANSACTION_LOOKUP").map(|v| v.to_lowercase() == "true").unwrap_or(false);
+        let index_size = match std::env::var("KAKAROT_TRANSACTION_INDEX_SIZE") {
+            Ok(size) => size.parse::<usize>().ok().filter(|size| *size > 0).ok_or_else(|| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_TRANSACTION_INDEX_SIZE should be a strictly positive integer, got {size}"
+                ))
+            })?,
+            Err(_) => DEFAULT_TRANSACTION_INDEX_SIZE,
+        };
+
+        Ok(Self { scan_depth, exhaustive, index_size })
+    }
+}
+
+/// Key prefix of the (Ethereum hash, Starknet hash) pairs in the storage.
+const TRANSACTION_HASHES_PREFIX: &[u8] = b"transaction_hashes/";
+
+fn decode_pair((key, value): Entry) -> Result<(H256, H256), StorageError> {
+    let ethereum_hash = &key[TRANSACTION_HASHES_PREFIX.len()..];
+    if ethereum_hash.len() != 32 || value.len() != 32 {
+        return Err(StorageError::new("invalid stored hash"));
+    }
+    Ok((H256::from_slice(ethereum_hash), H256::from_slice(&value)))
+}
+
+#[derive(Debug)]
+struct IndexState {
+    /// Ethereum transaction hash to Starknet transaction hash.
+    hashes: LruCache<H256, H256>,
+    /// Starknet transaction hash to Ethereum transaction hash.
+    ethereum_hashes: LruCache<H256, H256>,
+    /// Unknown Ethereum transaction hashes, with the latest block when they were looked up.
+    misses: LruCache<H256, u64>,
+    /// Contiguous range of blocks whose transactions are all indexed.
+    indexed_blocks: Option<RangeInclusive<u64>>,
+}
+
+impl IndexState {
+    fn new(size: NonZeroUsize) -> Self {
+        let miss_cache_size = NonZeroUsize::new(TRANSACTION_MISS_CACHE_SIZE).expect("miss cache size is not zero");
+        Self {
+            hashes: LruCache::new(size),
+            ethereum_hashes: LruCache::new(size),
+            misses: LruCache::new(miss_cache_size),
+            indexed_blocks: None,
+        }
+    }
+
+    fn insert(&mut self, pairs: &[(H256, H256)]) {
+        for (ethereum_hash, starknet_hash) in pairs {
+            self.hashes.put(*ethereum_hash, *starknet_hash);
+            self.ethereum_hashes.put(*starknet_hash, *ethereum_hash);
+            self.misses.pop(ethereum_hash);
+        }
+    }
+}
+
+/// Index of the Ethereum hashes of the Kakarot transactions, i.e. the hashes of the signed
+/// Ethereum transactions, to the hashes of the Starknet transactions wrapping them, and back.
+///
+/// The index is filled when transactions are submitted and by scanning blocks, keeping track of
+/// the contiguous range of blocks it covers, so that a block is only scanned once. The pairs can
+/// be persisted in a [`Storage`] to survive restarts, the range of indexed blocks is not.
+///
+/// The most recently used pairs are kept in memory, an Ethereum hash evicted from memory is read
+/// back from the storage. The unknown Ethereum hashes are remembered until a new block arrives,
+/// and the scans of the lookups are bounded to [`MAX_CONCURRENT_TRANSACTION_SCANS`].
+#[derive(Debug)]
+pub struct TransactionIndex {
+    state: Mutex<IndexState>,
+    storage: Option<Arc<dyn Storage>>,
+    scans: Semaphore,
+}
+
+impl Default for TransactionIndex {
+    fn default() -> Self {
+        Self::with_size(DEFAULT_TRANSACTION_INDEX_SIZE)
+    }
+}
+
+impl TransactionIndex {
+    /// Create a new `TransactionIndex` keeping `size` pairs in memory.
+    pub fn with_size(size: usize) -> Self {
+        let size = NonZeroUsize::new(size.max(1)).expect("index size is not zero");
+        Self {
+            state: Mutex::new(IndexState::new(size)),
+            storage: None,
+            scans: Semaphore::new(MAX_CONCURRENT_TRANSACTION_SCANS),
+        }
+    }
+
+    /// Create a new `TransactionIndex` persisted in the storage, loaded with up to `size` of the
+    /// stored pairs, the other ones being read from the storage when looked up.
+    pub fn with_storage(storage: Arc<dyn Storage>, size: usize) -> Result<Self, StorageError> {
+        let pairs = storage
+            .scan_prefix(TRANSACTION_HASHES_PREFIX)?
+            .into_iter()
+            .map(decode_pair)
+            .collect::<Result<Vec<_>, _>>()?;
+        let index = Self { storage: Some(storage), ..Self::with_size(size) };
+        index.state.lock().expect("transaction index poisoned").insert(&pairs);
+        Ok(index)
+    }
+
+    /// Returns the Starknet transaction hash of an indexed Ethereum transaction hash.
+    pub fn get(&self, ethereum_hash: &H256) -> Option<H256> {
+        if let Some(hash) = self.state.lock().expect("transaction index poisoned").hashes.get(ethereum_hash) {
+            return Some(*hash);
+        }
+
+        // The pair may have been evicted from memory
+        let storage = self.storage.as_ref()?;
+        let key = prefixed_key(TRANSACTION_HASHES_PREFIX, ethereum_hash.as_bytes());
+        let value = match storage.get(&key) {
+            Ok(value) => value?,
+            Err(err) => {
+                tracing::warn!("Failed to read the transaction hash {ethereum_hash:?} from the storage: {err}");
+                return None;
+            }
+        };
+        let (_, starknet_hash) = decode_pair((key, value)).ok()?;
+        self.state.lock().expect("transaction index poisoned").insert(&[(*ethereum_hash, starknet_hash)]);
+        Some(starknet_hash)
+    }
+
+    /// Returns the Ethereum transaction hash of an indexed Starknet transaction hash.
+    pub fn get_ethereum_hash(&self, starknet_hash: &H256) -> Option<H256> {
+        self.state.lock().expect("transaction index poisoned").ethereum_hashes.get(starknet_hash).copied()
+    }
+
+    /// Returns true if the Ethereum hash was looked up without success while `latest_block` was
+    /// already the latest block.
+    pub fn is_known_miss(&self, ethereum_hash: &H256, latest_block: u64) -> bool {
+        let mut state = self.state.lock().expect("transaction index poisoned");
+        state.misses.get(ethereum_hash).map_or(false, |block| *block >= latest_block)
+    }
+
+    /// Remembers that the Ethereum hash wasn't found in the blocks up to `latest_block`.
+    pub fn record_miss(&self, ethereum_hash: H256, latest_block: u64) {
+        self.state.lock().expect("transaction index poisoned").misses.put(ethereum_hash, latest_block);
+    }
+
+    /// Waits for a slot to scan the blocks, bounding the number of concurrent scans.
+    pub async fn acquire_scan(&self) -> SemaphorePermit<'_> {
+        self.scans.acquire().await.expect("the scan semaphore is never closed")
+    }
+
+    /// Indexes a transaction which isn't part of a scanned block, e.g. a submitted transaction.
+    pub fn index_transaction(&self, ethereum_hash: H256, starknet_hash: H256) {
+        let pairs = [(ethereum_hash, starknet_hash)];
+        self.state.lock().expect("transaction index poisoned").insert(&pairs);
+        self.persist(&pairs);
+    }
+
+    fn persist(&self, pairs: &[(H256, H256)]) {
+        if let (Some(storage), false) = (&self.storage, pairs.is_empty()) {
+            let entries: Vec<Entry> = pairs
+                .iter()
+                .map(|(ethereum_hash, starknet_hash)| {
+                    (
+                        prefixed_key(TRANSACTION_HASHES_PREFIX, ethereum_hash.as_bytes()),
+                        starknet_hash.as_bytes().to_vec(),
+                    )
+                })
+                .collect();
+            // The index is still usable in memory, only the persistence is lost
+            if let Err(err) = storage.put_batch(&entries) {
+                tracing::warn!("Failed to persist {} transaction hashes: {err}", pairs.len());
+            }
+        }
+    }
+
+    /// Returns the range of blocks already indexed.
+    pub fn indexed_blocks(&self) -> Option<RangeInclusive<u64>> {
+        self.state.lock().expect("transaction index poisoned").indexed_blocks.clone()
+    }
+
+    /// Indexes the transactions of a block, given as (Ethereum hash, Starknet hash) pairs. The
+    /// block must be adjacent to the indexed range, otherwise the range restarts at this block.
+    pub fn index_block(&self, block_number: u64, transactions: impl IntoIterator<Item = (H256, H256)>) {
+        let pairs: Vec<(H256, H256)> = transactions.into_iter().collect();
+        self.persist(&pairs);
+
+        let mut state = self.state.lock().expect("transaction index poisoned");
+        state.insert(&pairs);
+
+        state.indexed_blocks = Some(match state.indexed_blocks.take() {
+            Some(range) if range.contains(&block_number) => range,
+            Some(range) if block_number == range.end() + 1 => *range.start()..=block_number,
+            Some(range) if block_number + 1 == *range.start() => block_number..=*range.end(),
+            _ => block_number..=block_number,
+        });
+    }
+
+    /// Shrinks the indexed range to the blocks before `from_block`, reverted by a reorganization of
+    /// the chain, so that the blocks replacing them are scanned again. The hashes of the reverted
+    /// transactions are kept: they are looked up on the Starknet node, which doesn't serve them.
+    pub fn rollback(&self, from_block: u64) {
+        let mut state = self.state.lock().expect("transaction index poisoned");
+        state.indexed_blocks = match state.indexed_blocks.take() {
+            Some(range) if *range.start() < from_block => Some(*range.start()..=(*range.end()).min(from_block - 1)),
+            _ => None,
+        };
+    }
+
+    /// Returns the blocks to scan, in order, to look up a transaction within the `depth` blocks
+    /// ending at `latest_block` and starting no earlier than `first_block`. The blocks newer than
+    /// the indexed range are scanned first, in ascending order to keep the indexed range
+    /// contiguous, followed by the older blocks in descending order.
+    pub fn blocks_to_scan(&self, latest_block: u64, first_block: u64, depth: Option<u64>) -> Vec<u64> {
+        let lowest_block = match depth {
+            Some(depth) => latest_block.saturating_sub(depth.saturating_sub(1)).max(first_block),
+            None => first_block,
+        };
+        if depth == Some(0) || lowest_block > latest_block {
+            return vec![];
+        }
+
+        match self.indexed_blocks() {
+            // Scan the new blocks then the older ones
+            Some(range) if range.end() + 1 >= lowest_block && *range.end() <= latest_block => {
+                let newer = range.end() + 1..=latest_block;
+                let older = (lowest_block..*range.start()).rev();
+                newer.chain(older).collect()
+            }
+            // The indexed range is out of reach, start over from the latest block
+            _ => (lowest_block..=latest_block).rev().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn test_index_block_extends_range() {
+        // Given
+        let index = TransactionIndex::default();
+        let (ethereum_hash, starknet_hash) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+
+        // When
+        index.index_block(10, vec![(ethereum_hash, starknet_hash)]);
+        index.index_block(9, vec![]);
+        index.index_block(11, vec![]);
+
+        // Then
+        assert_eq!(Some(starknet_hash), index.get(&ethereum_hash));
+        assert_eq!(Some(9..=11), index.indexed_blocks());
+    }
+
+    #[test]
+    fn test_rollback_shrinks_range() {
+        // Given
+        let index = TransactionIndex::default();
+        (10..=20).for_each(|block_number| index.index_block(block_number, vec![]));
+
+        // When
+        index.rollback(15);
+        let shrunk = index.indexed_blocks();
+        index.rollback(5);
+
+        // Then
+        assert_eq!(Some(10..=14), shrunk);
+        assert_eq!(None, index.indexed_blocks());
+    }
+
+    #[test]
+    fn test_index_block_restarts_range_on_gap() {
+        // Given
+        let index = TransactionIndex::default();
+        index.index_block(10, vec![]);
+
+        // When
+        index.index_block(20, vec![]);
+
+        // Then
+        assert_eq!(Some(20..=20), index.indexed_blocks());
+    }
+
+    #[test]
+    fn test_blocks_to_scan_without_index() {
+        // Given
+        let index = TransactionIndex::default();
+
+        // When
+        let bounded = index.blocks_to_scan(100, 0, Some(3));
+        let exhaustive = index.blocks_to_scan(2, 0, None);
+        let from_deployment = index.blocks_to_scan(100, 99, Some(10));
+
+        // Then
+        assert_eq!(vec![100, 99, 98], bounded);
+        assert_eq!(vec![2, 1, 0], exhaustive);
+        assert_eq!(vec![100, 99], from_deployment);
+    }
+
+    #[test]
+    fn test_blocks_to_scan_skips_indexed_blocks() {
+        // Given
+        let index = TransactionIndex::default();
+        (95..=98).for_each(|block_number| index.index_block(block_number, vec![]));
+
+        // When
+        let blocks = index.blocks_to_scan(100, 0, Some(8));
+
+        // Then
+        assert_eq!(vec![99, 100, 94, 93], blocks);
+    }
+
+    #[test]
+    fn test_index_transaction_maps_both_ways() {
+        // Given
+        let index = TransactionIndex::default();
+        let (ethereum_hash, starknet_hash) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+
+        // When
+        index.index_transaction(ethereum_hash, starknet_hash);
+
+        // Then
+        assert_eq!(Some(starknet_hash), index.get(&ethereum_hash));
+        assert_eq!(Some(ethereum_hash), index.get_ethereum_hash(&starknet_hash));
+        assert_eq!(None, index.indexed_blocks());
+    }
+
+    #[test]
+    fn test_index_is_bounded_and_falls_back_to_storage() {
+        // Given
+        let (first, second) = (H256::from_low_u64_be(1), H256::from_low_u64_be(3));
+        let in_memory = TransactionIndex::with_size(1);
+        let persisted = TransactionIndex::with_storage(Arc::new(MemoryStorage::default()), 1).unwrap();
+
+        // When
+        for index in [&in_memory, &persisted] {
+            index.index_transaction(first, H256::from_low_u64_be(2));
+            index.index_transaction(second, H256::from_low_u64_be(4));
+        }
+
+        // Then
+        assert_eq!(None, in_memory.get(&first));
+        assert_eq!(Some(H256::from_low_u64_be(4)), in_memory.get(&second));
+        assert_eq!(Some(H256::from_low_u64_be(2)), persisted.get(&first));
+    }
+
+    #[test]
+    fn test_miss_is_remembered_until_a_new_block() {
+        // Given
+        let index = TransactionIndex::default();
+        let ethereum_hash = H256::from_low_u64_be(1);
+
+        // When
+        index.record_miss(ethereum_hash, 10);
+
+        // Then
+        assert!(index.is_known_miss(&ethereum_hash, 10));
+        assert!(!index.is_known_miss(&ethereum_hash, 11));
+        index.index_transaction(ethereum_hash, H256::from_low_u64_be(2));
+        assert!(!index.is_known_miss(&ethereum_hash, 10));
+    }
+
+    #[test]
+    fn test_index_is_reloaded_from_storage() {
+        // Given
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let index = TransactionIndex::with_storage(Arc::clone(&storage), DEFAULT_TRANSACTION_INDEX_SIZE).unwrap();
+        let (ethereum_hash, starknet_hash) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+        index.index_transaction(ethereum_hash, starknet_hash);
+        index.index_block(10, vec![(H256::from_low_u64_be(3), H256::from_low_u64_be(4))]);
+
+        // When
+        let reloaded = TransactionIndex::with_storage(storage, DEFAULT_TRANSACTION_INDEX_SIZE).unwrap();
+
+        // Then
+        assert_eq!(Some(starknet_hash), reloaded.get(&ethereum_hash));
+        assert_eq!(Some(H256::from_low_u64_be(3)), reloaded.get_ethereum_hash(&H256::from_low_u64_be(4)));
+        assert_eq!(None, reloaded.indexed_blocks());
+    }
+}
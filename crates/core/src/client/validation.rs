@@ -0,0 +1,199 @@
+//! Pre-flight validation of the raw transactions relayed by `eth_sendRawTransaction`.
+//!
+//! A transaction is checked against the current state of its sender before being wrapped in a
+//! Starknet transaction, and rejected with the error strings of geth, which wallets and tooling
+//! already recognize, instead of the opaque errors of the Starknet sequencer.
+use reth_primitives::{TransactionSigned, U256};
+use starknet::core::types::FieldElement;
+use thiserror::Error;
+
+use crate::models::conversions::felt_to_u256;
+
+/// Transaction rejected by the pre-flight validation, displayed as the matching geth error.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum InvalidTransactionError {
+    /// The transaction was already relayed recently.
+    #[error("already known")]
+    AlreadyKnown,
+    /// The signature of the transaction doesn't recover a sender.
+    #[error("invalid sender")]
+    InvalidSender,
+    /// The transaction is signed for another chain.
+    #[error("invalid chain id")]
+    InvalidChainId,
+    /// The nonce of the transaction was already used by a mined transaction of the sender.
+    #[error("nonce too low")]
+    NonceTooLow,
+    /// The nonce of the transaction is used by a pending transaction of the sender, which can't be
+    /// replaced on Starknet.
+    #[error("replacement transaction underpriced")]
+    ReplacementUnderpriced,
+    /// The gas limit of the transaction exceeds the block gas limit.
+    #[error("exceeds block gas limit")]
+    GasLimitExceeded,
+    /// The balance of the sender doesn't cover the maximum cost of the transaction.
+    #[error("insufficient funds for gas * price + value")]
+    InsufficientFunds,
+    /// The fee token balance of the Starknet account of the sender doesn't cover the maximum fee
+    /// of the relayed Starknet transaction.
+    #[error("insufficient funds for the Starknet fee")]
+    InsufficientFeeTokenFunds,
+}
+
+/// State of the sender against which a transaction is validated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SenderState {
+    /// Nonce of the sender at the latest block.
+    pub latest_nonce: U256,
+    /// Nonce of the sender at the pending block, including its pending transactions.
+    pub pending_nonce: U256,
+    /// Balance of the sender at the pending block, in wei.
+    pub balance: U256,
+    /// Balance of the Starknet account of the sender in the fee token at the pending block, when
+    /// the Starknet fees aren't paid in the native token.
+    pub fee_token_balance: Option<U256>,
+}
+
+/// Checks the chain id and the gas limit of the transaction, which don't depend on the state of
+/// its sender.
+pub fn validate_transaction(
+    transaction: &TransactionSigned,
+    chain_id: u64,
+    block_gas_limit: U256,
+) -> Result<(), InvalidTransactionError> {
+    if transaction.chain_id().is_some_and(|transaction_chain_id| transaction_chain_id != chain_id) {
+        return Err(InvalidTransactionError::InvalidChainId);
+    }
+    if U256::from(transaction.gas_limit()) > block_gas_limit {
+        return Err(InvalidTransactionError::GasLimitExceeded);
+    }
+    Ok(())
+}
+
+/// Checks the nonce of the transaction and that the sender can pay for its maximum cost, the gas
+/// limit at the maximum fee per gas plus the transferred value. When the fees are paid in a
+/// separate fee token, the balance only has to cover the value, and the fee token balance the
+/// maximum fee `max_fee` of the relayed Starknet transaction.
+pub fn validate_sender_state(
+    transaction: &TransactionSigned,
+    sender_state: &SenderState,
+    max_fee: FieldElement,
+) -> Result<(), InvalidTransactionError> {
+    let nonce = U256::from(transaction.nonce());
+    if nonce < sender_state.latest_nonce {
+        return Err(InvalidTransactionError::NonceTooLow);
+    }
+    if nonce < sender_state.pending_nonce {
+        return Err(InvalidTransactionError::ReplacementUnderpriced);
+    }
+
+    if let Some(fee_token_balance) = sender_state.fee_token_balance {
+        if U256::from(transaction.value()) > sender_state.balance {
+            return Err(InvalidTransactionError::InsufficientFunds);
+        }
+        if felt_to_u256(max_fee) > fee_token_balance {
+            return Err(InvalidTransactionError::InsufficientFeeTokenFunds);
+        }
+        return Ok(());
+    }
+
+    let cost = U256::from(transaction.gas_limit())
+        .checked_mul(U256::from(transaction.max_fee_per_gas()))
+        .and_then(|gas_cost| gas_cost.checked_add(U256::from(transaction.value())));
+    match cost {
+        Some(cost) if cost <= sender_state.balance => Ok(()),
+        _ => Err(InvalidTransactionError::InsufficientFunds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Signature, Transaction, TxEip1559};
+
+    use super::*;
+    use crate::client::constants::MAX_FEE;
+
+    fn transaction(nonce: u64, gas_limit: u64, max_fee_per_gas: u128, value: u128) -> TransactionSigned {
+        let transaction = Transaction::Eip1559(TxEip1559 {
+            chain_id: 1263227476,
+            nonce,
+            gas_limit,
+            max_fee_per_gas,
+            value,
+            ..Default::default()
+        });
+        TransactionSigned::from_transaction_and_signature(transaction, Signature::default())
+    }
+
+    #[test]
+    fn test_validate_transaction() {
+        // Given
+        let block_gas_limit = U256::from(1_000_000);
+
+        // Then
+        assert_eq!(Ok(()), validate_transaction(&transaction(0, 1_000_000, 1, 0), 1263227476, block_gas_limit));
+        assert_eq!(
+            Err(InvalidTransactionError::InvalidChainId),
+            validate_transaction(&transaction(0, 21_000, 1, 0), 1, block_gas_limit)
+        );
+        assert_eq!(
+            Err(InvalidTransactionError::GasLimitExceeded),
+            validate_transaction(&transaction(0, 1_000_001, 1, 0), 1263227476, block_gas_limit)
+        );
+    }
+
+    #[test]
+    fn test_validate_sender_state() {
+        // Given
+        let sender_state = SenderState {
+            latest_nonce: U256::from(3),
+            pending_nonce: U256::from(4),
+            balance: U256::from(21_100),
+            fee_token_balance: None,
+        };
+
+        // Then
+        assert_eq!(Ok(()), validate_sender_state(&transaction(4, 21_000, 1, 100), &sender_state, *MAX_FEE));
+        assert_eq!(Ok(()), validate_sender_state(&transaction(6, 21_000, 1, 0), &sender_state, *MAX_FEE));
+        assert_eq!(
+            Err(InvalidTransactionError::NonceTooLow),
+            validate_sender_state(&transaction(2, 21_000, 1, 0), &sender_state, *MAX_FEE)
+        );
+        assert_eq!(
+            Err(InvalidTransactionError::ReplacementUnderpriced),
+            validate_sender_state(&transaction(3, 21_000, 1, 0), &sender_state, *MAX_FEE)
+        );
+        assert_eq!(
+            Err(InvalidTransactionError::InsufficientFunds),
+            validate_sender_state(&transaction(4, 21_000, 1, 101), &sender_state, *MAX_FEE)
+        );
+        assert_eq!(
+            Err(InvalidTransactionError::InsufficientFunds),
+            validate_sender_state(&transaction(4, u64::MAX, u128::MAX, u128::MAX), &sender_state, *MAX_FEE)
+        );
+    }
+
+    #[test]
+    fn test_validate_sender_state_with_fee_token() {
+        // Given
+        let max_fee = felt_to_u256(*MAX_FEE);
+        let sender_state = SenderState {
+            latest_nonce: U256::from(3),
+            pending_nonce: U256::from(3),
+            balance: U256::from(100),
+            fee_token_balance: Some(max_fee),
+        };
+
+        // Then
+        assert_eq!(Ok(()), validate_sender_state(&transaction(3, 21_000, 1, 100), &sender_state, *MAX_FEE));
+        assert_eq!(
+            Err(InvalidTransactionError::InsufficientFunds),
+            validate_sender_state(&transaction(3, 21_000, 1, 101), &sender_state, *MAX_FEE)
+        );
+        let sender_state = SenderState { fee_token_balance: Some(max_fee - U256::from(1)), ..sender_state };
+        assert_eq!(
+            Err(InvalidTransactionError::InsufficientFeeTokenFunds),
+            validate_sender_state(&transaction(3, 21_000, 1, 0), &sender_state, *MAX_FEE)
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use futures::{stream, StreamExt};
+use reth_primitives::{Address, BlockId, BlockNumberOrTag};
+use starknet::core::types::{BlockId as StarknetBlockId, BlockTag, MaybePendingBlockWithTxHashes};
+use starknet::providers::Provider;
+
+use super::api::{KakarotEthApi, KakarotStarknetApi};
+use super::errors::{ConfigError, EthApiError};
+use super::KakarotClient;
+use crate::models::felt::Felt252Wrapper;
+
+/// Default number of block headers prefetched by the warm-up routine.
+pub const DEFAULT_WARM_UP_BLOCK_COUNT: u64 = 16;
+
+/// Configuration of the startup warm-up routine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmUpConfig {
+    /// Number of latest block headers to prefetch.
+    pub block_count: u64,
+}
+
+impl Default for WarmUpConfig {
+    fn default() -> Self {
+        Self { block_count: DEFAULT_WARM_UP_BLOCK_COUNT }
+    }
+}
+
+impl WarmUpConfig {
+    /// Creates a warm-up configuration prefetching `block_count` block headers, at least one.
+    pub fn new(block_count: u64) -> Self {
+        Self { block_count: block_count.max(1) }
+    }
+
+    /// Create a new `WarmUpConfig` from environment variables.
+    /// Returns `None` when `KAKAROT_WARM_UP` is not set to `true`, which disables the warm-up.
+    /// The number of prefetched block headers can be set with `KAKAROT_WARM_UP_BLOCKS`.
+    pub fn from_env() -> Result<Option<Self>, ConfigError> {
+        let enabled = std::env::var("KAKAROT_WARM_UP").map(|v| v.to_lowercase() == "true").unwrap_or(false);
+        if !enabled {
+            return Ok(None);
+        }
+
+        let block_count = match std::env::var("KAKAROT_WARM_UP_BLOCKS") {
+            Ok(block_count) => block_count.parse::<u64>().ok().filter(|count| *count > 0).ok_or_else(|| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_WARM_UP_BLOCKS should be a positive integer, got {block_count}"
+                ))
+            })?,
+            Err(_) => DEFAULT_WARM_UP_BLOCK_COUNT,
+        };
+
+        Ok(Some(Self::new(block_count)))
+    }
+}
+
+/// Summary of the state fetched during the warm-up.
+#[derive(Debug, Clone)]
+pub struct WarmUpReport {
+    /// Coinbase account, i.e. the configured coinbase or the sequencer of the latest block
+    /// truncated to an EVM address.
+    pub coinbase: Address,
+    /// Number of block headers successfully fetched and cached.
+    pub blocks_fetched: u64,
+    /// Time spent warming up.
+    pub elapsed: Duration,
+}
+
+impl<P: Provider + Send + Sync> KakarotClient<P> {
+    /// Prefetches the state that is hit by almost every request: the coinbase account and the
+    /// latest block headers.
+    ///
+    /// The latest block headers are converted into the block cache, and the balance and code of
+    /// the coinbase account into the state cache, which is anchored at the latest block until the
+    /// head watcher reports a new one. The Kakarot class hashes and the native token address are
+    /// read from the configuration, so there is nothing to prefetch for them.
+    pub async fn warm_up(&self, config: &WarmUpConfig) -> Result<WarmUpReport, EthApiError<P::Error>> {
+        let start = Instant::now();
+        let latest = StarknetBlockId::Tag(BlockTag::Latest);
+        let provider = self.starknet_provider();
+
+        let (latest_block_number, coinbase) = match provider.get_block_with_tx_hashes(latest).await? {
+            MaybePendingBlockWithTxHashes::Block(block) => {
+                (block.block_number, Felt252Wrapper::from(block.sequencer_address).truncate_to_ethereum_address())
+            }
+            MaybePendingBlockWithTxHashes::PendingBlock(block) => {
+                (0, Felt252Wrapper::from(block.sequencer_address).truncate_to_ethereum_address())
+            }
+        };
+
+        let coinbase = self.coinbase.unwrap_or(coinbase);
+
+        // Anchor the state cache at the latest block, so that the state read below is cached
+        if let Some(state_cache) = self.state_cache.as_ref().filter(|cache| cache.head().is_none()) {
+            state_cache.apply_new_head(latest_block_number, None);
+        }
+        let latest_block = BlockId::Number(BlockNumberOrTag::Latest);
+        if let Err(err) =
+            futures::try_join!(self.balance(coinbase, latest_block), self.get_code(coinbase, latest_block))
+        {
+            tracing::warn!("Warm-up failed to fetch the state of the coinbase {coinbase:?}: {err}");
+        }
+
+        // The converted blocks are inserted into the block cache
+        let first_block_number = latest_block_number.saturating_sub(config.block_count.saturating_sub(1));
+        let blocks_fetched = stream::iter(first_block_number..=latest_block_number)
+            .map(|block_number| self.get_eth_block_from_starknet_block(StarknetBlockId::Number(block_number), false))
+            .buffer_unordered(self.transaction_conversion_concurrency)
+            .filter(|block| futures::future::ready(block.is_ok()))
+            .count()
+            .await as u64;
+
+        Ok(WarmUpReport { coinbase, blocks_fetched, elapsed: start.elapsed() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warm_up_config_from_env() {
+        std::env::set_var("KAKAROT_WARM_UP", "true");
+        std::env::set_var("KAKAROT_WARM_UP_BLOCKS", "0");
+        assert!(WarmUpConfig::from_env().is_err());
+        std::env::set_var("KAKAROT_WARM_UP_BLOCKS", "4");
+        assert_eq!(Some(WarmUpConfig::new(4)), WarmUpConfig::from_env().unwrap());
+        std::env::remove_var("KAKAROT_WARM_UP_BLOCKS");
+        assert_eq!(Some(WarmUpConfig::default()), WarmUpConfig::from_env().unwrap());
+        std::env::remove_var("KAKAROT_WARM_UP");
+        assert_eq!(None, WarmUpConfig::from_env().unwrap());
+    }
+}
@@ -0,0 +1,52 @@
+//! Calls to the Solidity contracts deployed on Kakarot, encoded and decoded following their ABI.
+use ethers::abi::{AbiDecode, AbiEncode};
+use reth_primitives::{BlockId, U256};
+use starknet::core::types::BlockId as StarknetBlockId;
+use starknet::providers::Provider;
+use starknet_crypto::FieldElement;
+
+use crate::client::errors::EthApiError;
+use crate::client::helpers::DataDecodingError;
+use crate::contracts::kakarot::KakarotContract;
+use crate::models::block::EthBlockId;
+use crate::models::conversions::bytes_to_felts;
+
+/// Calls the contract through the `eth_call` entrypoint of Kakarot and returns the return data.
+pub(crate) async fn call_contract<P: Provider + Send + Sync>(
+    kakarot_contract: &KakarotContract<P>,
+    address: &FieldElement,
+    call: impl AbiEncode,
+    block_id: BlockId,
+) -> Result<Vec<u8>, EthApiError<P::Error>> {
+    // Prepare the calldata for the bytecode function call
+    let calldata = bytes_to_felts(&call.encode());
+
+    let block_id = EthBlockId::new(block_id);
+    let block_id: StarknetBlockId = block_id.try_into()?;
+
+    let result = kakarot_contract.eth_call(address, calldata, &block_id).await?;
+    Ok(result.0.into())
+}
+
+/// Decodes the `uint256` returned by the entrypoint.
+pub(crate) fn decode_u256(entrypoint: &str, data: &[u8]) -> Result<U256, DataDecodingError> {
+    U256::try_from_be_slice(data).ok_or(DataDecodingError::InvalidReturnArrayLength {
+        entrypoint: entrypoint.into(),
+        expected: 32,
+        actual: data.len(),
+    })
+}
+
+/// Decodes the ABI encoded data returned by the entrypoint.
+pub(crate) fn decode_return<T: AbiDecode>(entrypoint: &str, data: Vec<u8>) -> Result<T, DataDecodingError> {
+    T::decode(data)
+        .map_err(|err| DataDecodingError::InvalidReturnData { entrypoint: entrypoint.into(), message: err.to_string() })
+}
+
+pub(crate) fn to_ethers_u256(value: U256) -> ethers::types::U256 {
+    ethers::types::U256(value.into_limbs())
+}
+
+pub(crate) fn from_ethers_u256(value: ethers::types::U256) -> U256 {
+    U256::from_limbs(value.0)
+}
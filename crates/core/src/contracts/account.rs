@@ -4,9 +4,9 @@ use starknet::core::types::{BlockId, FunctionCall, StarknetError};
 use starknet::providers::{Provider, ProviderError};
 use starknet_crypto::FieldElement;
 
-use crate::client::constants::selectors::{BYTECODE, GET_EVM_ADDRESS};
+use crate::client::constants::selectors::{BYTECODE, GET_EVM_ADDRESS, GET_IMPLEMENTATION};
 use crate::client::errors::EthApiError;
-use crate::client::helpers::{vec_felt_to_bytes, DataDecodingError};
+use crate::client::helpers::{decode_bytecode, DataDecodingError};
 use crate::models::felt::Felt252Wrapper;
 
 #[async_trait]
@@ -30,6 +30,22 @@ pub trait Account<'a, P: Provider + Send + Sync + 'a> {
         Ok(evm_address.truncate_to_ethereum_address())
     }
 
+    /// Returns the class hash of the implementation the account proxy delegates to.
+    async fn implementation_class_hash(&self, block_id: &BlockId) -> Result<FieldElement, EthApiError<P::Error>> {
+        let request = FunctionCall {
+            contract_address: self.starknet_address(),
+            entry_point_selector: GET_IMPLEMENTATION,
+            calldata: vec![],
+        };
+
+        let implementation = self.provider().call(request, block_id).await?;
+        Ok(*implementation.first().ok_or_else(|| DataDecodingError::InvalidReturnArrayLength {
+            entrypoint: "get_implementation".into(),
+            expected: 1,
+            actual: 0,
+        })?)
+    }
+
     /// Returns the evm bytecode of the contract.
     async fn bytecode(&self, block_id: &BlockId) -> Result<Bytes, EthApiError<P::Error>> {
         // Prepare the calldata for the bytecode function call
@@ -43,7 +59,7 @@ pub trait Account<'a, P: Provider + Send + Sync + 'a> {
             _ => Err(EthApiError::from(err)),
         })?;
 
-        Ok(vec_felt_to_bytes(bytecode))
+        decode_bytecode(&bytecode)
     }
 }
 
@@ -7,7 +7,7 @@ use super::account::Account;
 use crate::client::constants::selectors::STORAGE;
 use crate::client::errors::EthApiError;
 use crate::client::helpers::DataDecodingError;
-use crate::models::felt::Felt252Wrapper;
+use crate::models::conversions::felts_to_u256;
 
 /// Abstraction for a Kakarot contract account.
 pub struct ContractAccount<'a, P> {
@@ -52,10 +52,7 @@ impl<'a, P: Provider + Send + Sync> ContractAccount<'a, P> {
             }
             .into());
         }
-        let low: Felt252Wrapper = result[0].into(); // safe indexing
-        let high: Felt252Wrapper = result[1].into(); // safe indexing
-
-        let value = Into::<U256>::into(low) + (Into::<U256>::into(high) << 128);
+        let value = felts_to_u256(result[0], result[1])?; // safe indexing
         Ok(value)
     }
 }
@@ -0,0 +1,108 @@
+use ethers::abi::AbiEncode;
+use ethers::prelude::abigen;
+use ethers::types::Address;
+use reth_primitives::{BlockId, Bytes, U256};
+use starknet::providers::Provider;
+use starknet_crypto::FieldElement;
+
+use crate::client::errors::EthApiError;
+use crate::contracts::abi::{call_contract, decode_return, decode_u256, from_ethers_u256, to_ethers_u256};
+use crate::contracts::kakarot::KakarotContract;
+
+// abigen generates a lot of unused code, needs to be benchmarked if performances ever become a
+// concern
+abigen!(
+    IERC1155,
+    r#"[
+        function balanceOf(address account, uint256 id) external view returns (uint256)
+        function balanceOfBatch(address[] accounts, uint256[] ids) external view returns (uint256[])
+        function uri(uint256 id) external view returns (string)
+        function isApprovedForAll(address account, address operator) external view returns (bool)
+        function setApprovalForAll(address operator, bool approved) external
+        function safeTransferFrom(address from, address to, uint256 id, uint256 amount, bytes data) external
+        function safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] amounts, bytes data) external
+    ]"#,
+);
+
+/// Abstraction for a Kakarot ERC1155 contract.
+pub struct EthereumErc1155<'a, P> {
+    pub address: FieldElement,
+    kakarot_contract: &'a KakarotContract<P>,
+}
+
+impl<'a, P: Provider + Send + Sync> EthereumErc1155<'a, P> {
+    pub fn new(address: FieldElement, kakarot_contract: &'a KakarotContract<P>) -> Self {
+        Self { address, kakarot_contract }
+    }
+
+    pub async fn balance_of(
+        &self,
+        account: Address,
+        id: U256,
+        block_id: BlockId,
+    ) -> Result<U256, EthApiError<P::Error>> {
+        let result = self.call(BalanceOfCall { account, id: to_ethers_u256(id) }, block_id).await?;
+        Ok(decode_u256("balanceOf", &result)?)
+    }
+
+    /// Returns the balances of the (account, id) pairs, in the same order.
+    pub async fn balance_of_batch(
+        &self,
+        balances: Vec<(Address, U256)>,
+        block_id: BlockId,
+    ) -> Result<Vec<U256>, EthApiError<P::Error>> {
+        let (accounts, ids) = balances.into_iter().map(|(account, id)| (account, to_ethers_u256(id))).unzip();
+        let result = self.call(BalanceOfBatchCall { accounts, ids }, block_id).await?;
+        let balances = decode_return::<BalanceOfBatchReturn>("balanceOfBatch", result)?.0;
+        Ok(balances.into_iter().map(from_ethers_u256).collect())
+    }
+
+    pub async fn uri(&self, id: U256, block_id: BlockId) -> Result<String, EthApiError<P::Error>> {
+        let result = self.call(UriCall { id: to_ethers_u256(id) }, block_id).await?;
+        Ok(decode_return::<UriReturn>("uri", result)?.0)
+    }
+
+    pub async fn is_approved_for_all(
+        &self,
+        account: Address,
+        operator: Address,
+        block_id: BlockId,
+    ) -> Result<bool, EthApiError<P::Error>> {
+        let result = self.call(IsApprovedForAllCall { account, operator }, block_id).await?;
+        Ok(decode_return::<IsApprovedForAllReturn>("isApprovedForAll", result)?.0)
+    }
+
+    async fn call(&self, call: impl AbiEncode, block_id: BlockId) -> Result<Vec<u8>, EthApiError<P::Error>> {
+        call_contract(self.kakarot_contract, &self.address, call, block_id).await
+    }
+}
+
+/// Returns the calldata of an approval, or of its revocation, of `operator` to transfer all the
+/// tokens of the sender.
+pub fn set_approval_for_all_calldata(operator: Address, approved: bool) -> Bytes {
+    SetApprovalForAllCall { operator, approved }.encode().into()
+}
+
+/// Returns the calldata of a transfer of `amount` tokens `id` from `from` to `to`.
+pub fn safe_transfer_from_calldata(from: Address, to: Address, id: U256, amount: U256, data: Bytes) -> Bytes {
+    SafeTransferFromCall {
+        from,
+        to,
+        id: to_ethers_u256(id),
+        amount: to_ethers_u256(amount),
+        data: data.to_vec().into(),
+    }
+    .encode()
+    .into()
+}
+
+/// Returns the calldata of a transfer of `amounts` of the tokens `ids` from `from` to `to`.
+pub fn safe_batch_transfer_from_calldata(
+    from: Address,
+    to: Address,
+    transfers: Vec<(U256, U256)>,
+    data: Bytes,
+) -> Bytes {
+    let (ids, amounts) = transfers.into_iter().map(|(id, amount)| (to_ethers_u256(id), to_ethers_u256(amount))).unzip();
+    SafeBatchTransferFromCall { from, to, ids, amounts, data: data.to_vec().into() }.encode().into()
+}
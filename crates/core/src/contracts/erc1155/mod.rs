@@ -0,0 +1 @@
+pub mod ethereum_erc1155;
@@ -1,23 +1,27 @@
 use ethers::abi::AbiEncode;
 use ethers::prelude::abigen;
 use ethers::types::Address;
-use reth_primitives::{BlockId, U256};
-use starknet::core::types::BlockId as StarknetBlockId;
+use reth_primitives::{BlockId, Bytes, U256};
 use starknet::providers::Provider;
 use starknet_crypto::FieldElement;
 
 use crate::client::errors::EthApiError;
-use crate::client::helpers::DataDecodingError;
+use crate::contracts::abi::{call_contract, decode_return, decode_u256, to_ethers_u256};
 use crate::contracts::kakarot::KakarotContract;
-use crate::models::block::EthBlockId;
 
 // abigen generates a lot of unused code, needs to be benchmarked if performances ever become a
 // concern
 abigen!(
     IERC20,
     r#"[
+        function totalSupply() external view returns (uint256)
         function balanceOf(address account) external view returns (uint256)
         function allowance(address owner, address spender) external view returns (uint256)
+        function name() external view returns (string)
+        function symbol() external view returns (string)
+        function decimals() external view returns (uint8)
+        function transfer(address to, uint256 amount) external returns (bool)
+        function approve(address spender, uint256 amount) external returns (bool)
     ]"#,
 );
 
@@ -33,20 +37,53 @@ impl<'a, P: Provider + Send + Sync> EthereumErc20<'a, P> {
     }
 
     pub async fn balance_of(self, evm_address: Address, block_id: BlockId) -> Result<U256, EthApiError<P::Error>> {
-        // Prepare the calldata for the bytecode function call
-        let calldata = IERC20Calls::BalanceOf(BalanceOfCall { account: evm_address }).encode();
-        let calldata = calldata.into_iter().map(FieldElement::from).collect();
+        let result = self.call(BalanceOfCall { account: evm_address }, block_id).await?;
+        Ok(decode_u256("balanceOf", &result)?)
+    }
+
+    pub async fn allowance(
+        &self,
+        owner: Address,
+        spender: Address,
+        block_id: BlockId,
+    ) -> Result<U256, EthApiError<P::Error>> {
+        let result = self.call(AllowanceCall { owner, spender }, block_id).await?;
+        Ok(decode_u256("allowance", &result)?)
+    }
 
-        let block_id = EthBlockId::new(block_id);
-        let block_id: StarknetBlockId = block_id.try_into()?;
+    pub async fn total_supply(&self, block_id: BlockId) -> Result<U256, EthApiError<P::Error>> {
+        let result = self.call(TotalSupplyCall, block_id).await?;
+        Ok(decode_u256("totalSupply", &result)?)
+    }
 
-        let result = self.kakarot_contract.eth_call(&self.address, calldata, &block_id).await?;
-        let balance: Vec<u8> = result.0.into();
+    pub async fn name(&self, block_id: BlockId) -> Result<String, EthApiError<P::Error>> {
+        let result = self.call(NameCall, block_id).await?;
+        Ok(decode_return::<NameReturn>("name", result)?.0)
+    }
+
+    pub async fn symbol(&self, block_id: BlockId) -> Result<String, EthApiError<P::Error>> {
+        let result = self.call(SymbolCall, block_id).await?;
+        Ok(decode_return::<SymbolReturn>("symbol", result)?.0)
+    }
 
-        Ok(U256::try_from_be_slice(balance.as_slice()).ok_or(DataDecodingError::InvalidReturnArrayLength {
-            entrypoint: "balanceOf".into(),
-            expected: 32,
-            actual: balance.len(),
-        })?)
+    pub async fn decimals(&self, block_id: BlockId) -> Result<u8, EthApiError<P::Error>> {
+        let result = self.call(DecimalsCall, block_id).await?;
+        Ok(decode_return::<DecimalsReturn>("decimals", result)?.0)
     }
+
+    async fn call(&self, call: impl AbiEncode, block_id: BlockId) -> Result<Vec<u8>, EthApiError<P::Error>> {
+        call_contract(self.kakarot_contract, &self.address, call, block_id).await
+    }
+}
+
+/// Returns the calldata of a transfer of `amount` tokens to `to`, to be sent in a transaction or
+/// passed to `eth_call` and `eth_estimateGas`.
+pub fn transfer_calldata(to: Address, amount: U256) -> Bytes {
+    TransferCall { to, amount: to_ethers_u256(amount) }.encode().into()
+}
+
+/// Returns the calldata of an approval of `spender` to spend `amount` tokens, to be sent in a
+/// transaction or passed to `eth_call` and `eth_estimateGas`.
+pub fn approve_calldata(spender: Address, amount: U256) -> Bytes {
+    ApproveCall { spender, amount: to_ethers_u256(amount) }.encode().into()
 }
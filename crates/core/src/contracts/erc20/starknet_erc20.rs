@@ -1,12 +1,13 @@
 use reth_primitives::U256;
 use starknet::core::types::{BlockId, FunctionCall};
+use starknet::core::utils::parse_cairo_short_string;
 use starknet::providers::Provider;
 use starknet_crypto::FieldElement;
 
-use crate::client::constants::selectors::BALANCE_OF;
+use crate::client::constants::selectors::{ALLOWANCE, BALANCE_OF, DECIMALS, NAME, SYMBOL, TOTAL_SUPPLY};
 use crate::client::errors::EthApiError;
 use crate::client::helpers::DataDecodingError;
-use crate::models::felt::Felt252Wrapper;
+use crate::models::conversions::felts_to_u256;
 
 /// Abstraction for a Starknet ERC20 contract.
 pub struct StarknetErc20<'a, P> {
@@ -24,24 +25,88 @@ impl<'a, P: Provider + Send + Sync> StarknetErc20<'a, P> {
         starknet_address: &FieldElement,
         block_id: &BlockId,
     ) -> Result<U256, EthApiError<P::Error>> {
-        // Prepare the calldata for the bytecode function call
-        let calldata = vec![*starknet_address];
-        let request = FunctionCall { contract_address: self.address, entry_point_selector: BALANCE_OF, calldata };
+        self.call_u256("balance_of", BALANCE_OF, vec![*starknet_address], block_id).await
+    }
+
+    pub async fn total_supply(&self, block_id: &BlockId) -> Result<U256, EthApiError<P::Error>> {
+        self.call_u256("total_supply", TOTAL_SUPPLY, vec![], block_id).await
+    }
+
+    pub async fn allowance(
+        &self,
+        owner: &FieldElement,
+        spender: &FieldElement,
+        block_id: &BlockId,
+    ) -> Result<U256, EthApiError<P::Error>> {
+        self.call_u256("allowance", ALLOWANCE, vec![*owner, *spender], block_id).await
+    }
+
+    pub async fn name(&self, block_id: &BlockId) -> Result<String, EthApiError<P::Error>> {
+        let name = self.call_felt("name", NAME, block_id).await?;
+        decode_short_string("name", name)
+    }
+
+    pub async fn symbol(&self, block_id: &BlockId) -> Result<String, EthApiError<P::Error>> {
+        let symbol = self.call_felt("symbol", SYMBOL, block_id).await?;
+        decode_short_string("symbol", symbol)
+    }
+
+    pub async fn decimals(&self, block_id: &BlockId) -> Result<u8, EthApiError<P::Error>> {
+        let decimals = self.call_felt("decimals", DECIMALS, block_id).await?;
+        Ok(u8::try_from(decimals).map_err(|_| DataDecodingError::InvalidReturnData {
+            entrypoint: "decimals".into(),
+            message: format!("{decimals} overflows an u8"),
+        })?)
+    }
+
+    /// Calls an entrypoint returning a Cairo `Uint256`, made of its low and high felts.
+    async fn call_u256(
+        &self,
+        entrypoint: &str,
+        entry_point_selector: FieldElement,
+        calldata: Vec<FieldElement>,
+        block_id: &BlockId,
+    ) -> Result<U256, EthApiError<P::Error>> {
+        let request = FunctionCall { contract_address: self.address, entry_point_selector, calldata };
 
-        // Make the function call to get the account balance
         let result = self.provider.call(request, block_id).await?;
         if result.len() != 2 {
             return Err(DataDecodingError::InvalidReturnArrayLength {
-                entrypoint: "balance_of".into(),
+                entrypoint: entrypoint.into(),
                 expected: 2,
                 actual: result.len(),
             }
             .into());
         };
-        let low: Felt252Wrapper = result[0].into(); // safe indexing
-        let high: Felt252Wrapper = result[1].into(); // safe indexing
-
-        let value = Into::<U256>::into(low) + (Into::<U256>::into(high) << 128);
+        let value = felts_to_u256(result[0], result[1])?; // safe indexing
         Ok(value)
     }
+
+    /// Calls an entrypoint without arguments returning a single felt.
+    async fn call_felt(
+        &self,
+        entrypoint: &str,
+        entry_point_selector: FieldElement,
+        block_id: &BlockId,
+    ) -> Result<FieldElement, EthApiError<P::Error>> {
+        let request = FunctionCall { contract_address: self.address, entry_point_selector, calldata: vec![] };
+
+        let result = self.provider.call(request, block_id).await?;
+        match result.as_slice() {
+            [value] => Ok(*value),
+            _ => Err(DataDecodingError::InvalidReturnArrayLength {
+                entrypoint: entrypoint.into(),
+                expected: 1,
+                actual: result.len(),
+            }
+            .into()),
+        }
+    }
+}
+
+fn decode_short_string<E: std::error::Error>(entrypoint: &str, felt: FieldElement) -> Result<String, EthApiError<E>> {
+    Ok(parse_cairo_short_string(&felt).map_err(|err| DataDecodingError::InvalidReturnData {
+        entrypoint: entrypoint.into(),
+        message: err.to_string(),
+    })?)
 }
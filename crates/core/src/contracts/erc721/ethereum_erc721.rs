@@ -0,0 +1,100 @@
+use ethers::abi::AbiEncode;
+use ethers::prelude::abigen;
+use ethers::types::Address;
+use reth_primitives::{BlockId, Bytes, U256};
+use starknet::providers::Provider;
+use starknet_crypto::FieldElement;
+
+use crate::client::errors::EthApiError;
+use crate::contracts::abi::{call_contract, decode_return, decode_u256, to_ethers_u256};
+use crate::contracts::kakarot::KakarotContract;
+
+// abigen generates a lot of unused code, needs to be benchmarked if performances ever become a
+// concern
+abigen!(
+    IERC721,
+    r#"[
+        function balanceOf(address owner) external view returns (uint256)
+        function ownerOf(uint256 tokenId) external view returns (address)
+        function name() external view returns (string)
+        function symbol() external view returns (string)
+        function tokenURI(uint256 tokenId) external view returns (string)
+        function getApproved(uint256 tokenId) external view returns (address)
+        function isApprovedForAll(address owner, address operator) external view returns (bool)
+        function approve(address spender, uint256 tokenId) external
+        function setApprovalForAll(address operator, bool approved) external
+        function transferFrom(address from, address to, uint256 tokenId) external
+    ]"#,
+);
+
+/// Abstraction for a Kakarot ERC721 contract.
+pub struct EthereumErc721<'a, P> {
+    pub address: FieldElement,
+    kakarot_contract: &'a KakarotContract<P>,
+}
+
+impl<'a, P: Provider + Send + Sync> EthereumErc721<'a, P> {
+    pub fn new(address: FieldElement, kakarot_contract: &'a KakarotContract<P>) -> Self {
+        Self { address, kakarot_contract }
+    }
+
+    pub async fn balance_of(&self, owner: Address, block_id: BlockId) -> Result<U256, EthApiError<P::Error>> {
+        let result = self.call(BalanceOfCall { owner }, block_id).await?;
+        Ok(decode_u256("balanceOf", &result)?)
+    }
+
+    pub async fn owner_of(&self, token_id: U256, block_id: BlockId) -> Result<Address, EthApiError<P::Error>> {
+        let result = self.call(OwnerOfCall { token_id: to_ethers_u256(token_id) }, block_id).await?;
+        Ok(decode_return::<OwnerOfReturn>("ownerOf", result)?.0)
+    }
+
+    pub async fn name(&self, block_id: BlockId) -> Result<String, EthApiError<P::Error>> {
+        let result = self.call(NameCall, block_id).await?;
+        Ok(decode_return::<NameReturn>("name", result)?.0)
+    }
+
+    pub async fn symbol(&self, block_id: BlockId) -> Result<String, EthApiError<P::Error>> {
+        let result = self.call(SymbolCall, block_id).await?;
+        Ok(decode_return::<SymbolReturn>("symbol", result)?.0)
+    }
+
+    pub async fn token_uri(&self, token_id: U256, block_id: BlockId) -> Result<String, EthApiError<P::Error>> {
+        let result = self.call(TokenUriCall { token_id: to_ethers_u256(token_id) }, block_id).await?;
+        Ok(decode_return::<TokenUriReturn>("tokenURI", result)?.0)
+    }
+
+    pub async fn get_approved(&self, token_id: U256, block_id: BlockId) -> Result<Address, EthApiError<P::Error>> {
+        let result = self.call(GetApprovedCall { token_id: to_ethers_u256(token_id) }, block_id).await?;
+        Ok(decode_return::<GetApprovedReturn>("getApproved", result)?.0)
+    }
+
+    pub async fn is_approved_for_all(
+        &self,
+        owner: Address,
+        operator: Address,
+        block_id: BlockId,
+    ) -> Result<bool, EthApiError<P::Error>> {
+        let result = self.call(IsApprovedForAllCall { owner, operator }, block_id).await?;
+        Ok(decode_return::<IsApprovedForAllReturn>("isApprovedForAll", result)?.0)
+    }
+
+    async fn call(&self, call: impl AbiEncode, block_id: BlockId) -> Result<Vec<u8>, EthApiError<P::Error>> {
+        call_contract(self.kakarot_contract, &self.address, call, block_id).await
+    }
+}
+
+/// Returns the calldata of an approval of `spender` to transfer the token `token_id`.
+pub fn approve_calldata(spender: Address, token_id: U256) -> Bytes {
+    ApproveCall { spender, token_id: to_ethers_u256(token_id) }.encode().into()
+}
+
+/// Returns the calldata of an approval, or of its revocation, of `operator` to transfer all the
+/// tokens of the sender.
+pub fn set_approval_for_all_calldata(operator: Address, approved: bool) -> Bytes {
+    SetApprovalForAllCall { operator, approved }.encode().into()
+}
+
+/// Returns the calldata of a transfer of the token `token_id` from `from` to `to`.
+pub fn transfer_from_calldata(from: Address, to: Address, token_id: U256) -> Bytes {
+    TransferFromCall { from, to, token_id: to_ethers_u256(token_id) }.encode().into()
+}
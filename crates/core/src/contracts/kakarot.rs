@@ -1,23 +1,39 @@
 use std::sync::Arc;
 
-use reth_primitives::Bytes;
+use futures::{stream, StreamExt};
+use reth_primitives::{Address, Bytes};
 use starknet::core::types::{BlockId, FunctionCall};
 use starknet::providers::Provider;
 use starknet_crypto::FieldElement;
 
 use crate::client::constants::selectors::{COMPUTE_STARKNET_ADDRESS, ETH_CALL};
 use crate::client::errors::EthApiError;
-use crate::client::helpers::{decode_eth_call_return, vec_felt_to_bytes, DataDecodingError};
+use crate::client::helpers::{decode_eth_call_return, DataDecodingError};
+use crate::contracts::multicall::{decode_aggregate3, encode_aggregate3};
+use crate::models::conversions::{bytes_to_felts, felts_to_bytes};
+use crate::models::felt::Felt252Wrapper;
+
+/// Maximum number of Starknet calls sent concurrently by [`KakarotContract::eth_call_batch`] when
+/// no Multicall contract is configured.
+pub const ETH_CALL_BATCH_CONCURRENCY: usize = 8;
 
 pub struct KakarotContract<P> {
     pub address: FieldElement,
     pub proxy_account_class_hash: FieldElement,
+    /// EVM address of a Multicall3 contract deployed on Kakarot, aggregating the calls of
+    /// [`KakarotContract::eth_call_batch`] into a single Starknet call.
+    pub multicall_address: Option<FieldElement>,
     provider: Arc<P>,
 }
 
 impl<P: Provider + Send + Sync> KakarotContract<P> {
     pub fn new(provider: Arc<P>, address: FieldElement, proxy_account_class_hash: FieldElement) -> Self {
-        Self { address, proxy_account_class_hash, provider }
+        Self { address, proxy_account_class_hash, multicall_address: None, provider }
+    }
+
+    pub fn with_multicall(mut self, multicall_address: FieldElement) -> Self {
+        self.multicall_address = Some(multicall_address);
+        self
     }
 
     pub async fn compute_starknet_address(
@@ -55,7 +71,11 @@ impl<P: Provider + Send + Sync> KakarotContract<P> {
         calldata.append(&mut eth_calldata);
 
         let request = FunctionCall { contract_address: self.address, entry_point_selector: ETH_CALL, calldata };
-        let result = self.provider.call(request, block_id).await?;
+        let result = self
+            .provider
+            .call(request, block_id)
+            .await
+            .map_err(|err| EthApiError::<P::Error>::from(err).map_revert())?;
 
         // Parse and decode Kakarot's call return data (temporary solution and not scalable - will
         // fail is Kakarot API changes)
@@ -64,7 +84,57 @@ impl<P: Provider + Send + Sync> KakarotContract<P> {
         // params
         let return_data = decode_eth_call_return(&result)?;
 
-        let result = vec_felt_to_bytes(return_data);
+        let result = felts_to_bytes(&return_data)?;
         Ok(result)
     }
+
+    /// Executes the EVM calls, each made of the EVM address of the called contract and of its
+    /// calldata, and returns their results in the same order.
+    ///
+    /// With a Multicall contract, the calls are aggregated into a single Starknet call and a
+    /// reverted call fails with [`EthApiError::EvmRevert`] without failing the others. Otherwise
+    /// the calls are sent as concurrent Starknet calls.
+    pub async fn eth_call_batch(
+        &self,
+        calls: Vec<(FieldElement, Bytes)>,
+        block_id: &BlockId,
+    ) -> Result<Vec<Result<Bytes, EthApiError<P::Error>>>, EthApiError<P::Error>> {
+        let Some(multicall_address) = self.multicall_address.filter(|_| !calls.is_empty()) else {
+            let results = stream::iter(calls)
+                .map(|(to, calldata)| async move { self.eth_call(&to, bytes_to_felts(&calldata), block_id).await })
+                .buffered(ETH_CALL_BATCH_CONCURRENCY)
+                .collect()
+                .await;
+            return Ok(results);
+        };
+
+        let calls_count = calls.len();
+        let calls = calls
+            .into_iter()
+            .map(|(to, calldata)| {
+                let to: Address = Felt252Wrapper::from(to).try_into()?;
+                Ok((ethers::types::Address::from(to.to_fixed_bytes()), calldata.to_vec()))
+            })
+            .collect::<Result<Vec<_>, EthApiError<P::Error>>>()?;
+        let aggregated = encode_aggregate3(calls);
+        let return_data = self.eth_call(&multicall_address, bytes_to_felts(&aggregated), block_id).await?;
+
+        let results = decode_aggregate3(&return_data)?;
+        if results.len() != calls_count {
+            return Err(DataDecodingError::InvalidReturnArrayLength {
+                entrypoint: "aggregate3".into(),
+                expected: calls_count,
+                actual: results.len(),
+            }
+            .into());
+        }
+        Ok(results
+            .into_iter()
+            .map(
+                |(success, return_data)| {
+                    if success { Ok(return_data.into()) } else { Err(EthApiError::EvmRevert(return_data.into())) }
+                },
+            )
+            .collect())
+    }
 }
@@ -1,6 +1,10 @@
+pub mod abi;
 pub mod account;
 pub mod contract_account;
+pub mod erc1155;
 pub mod erc20;
+pub mod erc721;
 pub mod kakarot;
+pub mod multicall;
 #[cfg(test)]
 mod tests;
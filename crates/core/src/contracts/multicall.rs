@@ -0,0 +1,43 @@
+//! Encoding of the `aggregate3` calls of the Multicall3 contract, see
+//! <https://github.com/mds1/multicall>.
+use ethers::abi::{self, ParamType, Token};
+use ethers::types::Address;
+
+use crate::client::helpers::DataDecodingError;
+
+/// Selector of `aggregate3((address,bool,bytes)[])`.
+pub const AGGREGATE3_SELECTOR: [u8; 4] = [0x82, 0xad, 0x56, 0xcb];
+
+/// Encodes the `aggregate3` call of the given calls, each made of the called address and of its
+/// calldata. Every call is allowed to fail, so that a reverted call doesn't revert the others.
+pub(crate) fn encode_aggregate3(calls: Vec<(Address, Vec<u8>)>) -> Vec<u8> {
+    let calls = calls
+        .into_iter()
+        .map(|(target, calldata)| Token::Tuple(vec![Token::Address(target), Token::Bool(true), Token::Bytes(calldata)]))
+        .collect();
+    let mut encoded = AGGREGATE3_SELECTOR.to_vec();
+    encoded.extend(abi::encode(&[Token::Array(calls)]));
+    encoded
+}
+
+/// Decodes the data returned by `aggregate3`: the success and the return data of each call.
+pub(crate) fn decode_aggregate3(data: &[u8]) -> Result<Vec<(bool, Vec<u8>)>, DataDecodingError> {
+    let invalid = |message: String| DataDecodingError::InvalidReturnData { entrypoint: "aggregate3".into(), message };
+    let result_type = ParamType::Array(Box::new(ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes])));
+
+    let mut tokens = abi::decode(&[result_type], data).map_err(|err| invalid(err.to_string()))?;
+    let results = match tokens.pop() {
+        Some(Token::Array(results)) => results,
+        _ => return Err(invalid("expected an array of results".into())),
+    };
+    results
+        .into_iter()
+        .map(|result| match result {
+            Token::Tuple(fields) => match fields.as_slice() {
+                [Token::Bool(success), Token::Bytes(return_data)] => Ok((*success, return_data.clone())),
+                _ => Err(invalid("expected a (bool, bytes) result".into())),
+            },
+            _ => Err(invalid("expected a (bool, bytes) result".into())),
+        })
+        .collect()
+}
@@ -1,3 +1,5 @@
+use ethers::abi::{AbiEncode, Token};
+use ethers::types::Address;
 use reth_primitives::U256;
 use starknet::core::types::BlockId;
 use starknet::providers::SequencerGatewayProvider;
@@ -5,7 +7,18 @@ use starknet_crypto::FieldElement;
 
 use crate::client::api::KakarotStarknetApi;
 use crate::client::constants::{ACCOUNT_ADDRESS, STARKNET_NATIVE_TOKEN};
+use crate::client::errors::EthApiError;
+use crate::client::helpers::decode_bytecode;
+use crate::contracts::abi::{decode_return, decode_u256};
+use crate::contracts::erc1155::ethereum_erc1155::{
+    safe_batch_transfer_from_calldata, safe_transfer_from_calldata, BalanceOfBatchReturn,
+};
+use crate::contracts::erc20::ethereum_erc20::{approve_calldata, transfer_calldata, DecimalsReturn, NameReturn};
 use crate::contracts::erc20::starknet_erc20::StarknetErc20;
+use crate::contracts::erc721::ethereum_erc721::{
+    set_approval_for_all_calldata, transfer_from_calldata, OwnerOfReturn, TokenUriReturn,
+};
+use crate::contracts::multicall::{decode_aggregate3, encode_aggregate3, AGGREGATE3_SELECTOR};
 use crate::mock::mock_starknet::init_testnet_client;
 
 #[tokio::test]
@@ -24,3 +37,141 @@ async fn test_balance_of() {
     // Then
     assert_eq!(U256::from(983627765290549u64), balance);
 }
+
+#[test]
+fn test_erc20_calldata() {
+    // Given
+    let recipient = Address::from_low_u64_be(0xabcd);
+
+    // When
+    let transfer = transfer_calldata(recipient, U256::from(1_000));
+    let approve = approve_calldata(recipient, U256::MAX);
+
+    // Then
+    assert_eq!(4 + 2 * 32, transfer.len());
+    assert_eq!([0xa9, 0x05, 0x9c, 0xbb], transfer[..4]);
+    assert_eq!(recipient.as_bytes(), &transfer[16..36]);
+    assert_eq!(U256::from(1_000).to_be_bytes::<32>(), transfer[36..]);
+    assert_eq!([0x09, 0x5e, 0xa7, 0xb3], approve[..4]);
+    assert_eq!([0xff; 32], approve[36..]);
+}
+
+#[test]
+fn test_erc20_decode_return() {
+    // Given
+    let name = NameReturn("Ether".to_string()).encode();
+    let decimals = DecimalsReturn(18).encode();
+
+    // Then
+    assert_eq!("Ether", decode_return::<NameReturn>("name", name).unwrap().0);
+    assert_eq!(18, decode_return::<DecimalsReturn>("decimals", decimals).unwrap().0);
+    assert_eq!(U256::from(7), decode_u256("totalSupply", &U256::from(7).to_be_bytes::<32>()).unwrap());
+    assert!(decode_return::<NameReturn>("name", vec![0x01]).is_err());
+    assert!(decode_u256("totalSupply", &[0; 33]).is_err());
+}
+
+#[test]
+fn test_erc721_calldata() {
+    // Given
+    let from = Address::from_low_u64_be(0xabcd);
+    let to = Address::from_low_u64_be(0xef01);
+
+    // When
+    let transfer = transfer_from_calldata(from, to, U256::from(42));
+    let approval = set_approval_for_all_calldata(to, true);
+
+    // Then
+    assert_eq!(4 + 3 * 32, transfer.len());
+    assert_eq!([0x23, 0xb8, 0x72, 0xdd], transfer[..4]);
+    assert_eq!(from.as_bytes(), &transfer[16..36]);
+    assert_eq!(to.as_bytes(), &transfer[48..68]);
+    assert_eq!(U256::from(42).to_be_bytes::<32>(), transfer[68..]);
+    assert_eq!([0xa2, 0x2c, 0xb4, 0x65], approval[..4]);
+    assert_eq!(1, approval[approval.len() - 1]);
+}
+
+#[test]
+fn test_erc721_decode_return() {
+    // Given
+    let owner = Address::from_low_u64_be(0xabcd);
+    let owner_of = OwnerOfReturn(owner).encode();
+    let token_uri = TokenUriReturn("ipfs://token/42".to_string()).encode();
+
+    // Then
+    assert_eq!(owner, decode_return::<OwnerOfReturn>("ownerOf", owner_of).unwrap().0);
+    assert_eq!("ipfs://token/42", decode_return::<TokenUriReturn>("tokenURI", token_uri).unwrap().0);
+}
+
+#[test]
+fn test_erc1155_calldata() {
+    // Given
+    let from = Address::from_low_u64_be(0xabcd);
+    let to = Address::from_low_u64_be(0xef01);
+
+    // When
+    let transfer = safe_transfer_from_calldata(from, to, U256::from(1), U256::from(10), Default::default());
+    let batch_transfer = safe_batch_transfer_from_calldata(
+        from,
+        to,
+        vec![(U256::from(1), U256::from(10)), (U256::from(2), U256::from(20))],
+        Default::default(),
+    );
+
+    // Then
+    assert_eq!([0xf2, 0x42, 0x43, 0x2a], transfer[..4]);
+    assert_eq!(U256::from(1).to_be_bytes::<32>(), transfer[68..100]);
+    assert_eq!(U256::from(10).to_be_bytes::<32>(), transfer[100..132]);
+    assert_eq!([0x2e, 0xb2, 0xc2, 0xd6], batch_transfer[..4]);
+}
+
+#[test]
+fn test_erc1155_decode_balance_of_batch() {
+    // Given
+    let balances = BalanceOfBatchReturn(vec![ethers::types::U256::from(3), ethers::types::U256::from(5)]).encode();
+
+    // When
+    let balances = decode_return::<BalanceOfBatchReturn>("balanceOfBatch", balances).unwrap().0;
+
+    // Then
+    assert_eq!(vec![ethers::types::U256::from(3), ethers::types::U256::from(5)], balances);
+}
+
+#[test]
+fn test_multicall_aggregate3() {
+    // Given
+    let token = Address::from_low_u64_be(0xabcd);
+    let calls = vec![(token, vec![0x70, 0xa0, 0x82, 0x31]), (token, vec![])];
+    let returned = ethers::abi::encode(&[Token::Array(vec![
+        Token::Tuple(vec![Token::Bool(true), Token::Bytes(U256::from(5).to_be_bytes::<32>().to_vec())]),
+        Token::Tuple(vec![Token::Bool(false), Token::Bytes(vec![])]),
+    ])]);
+
+    // When
+    let encoded = encode_aggregate3(calls);
+    let results = decode_aggregate3(&returned).unwrap();
+
+    // Then
+    assert_eq!(AGGREGATE3_SELECTOR, encoded[..4]);
+    // Offset of the array, then its length
+    assert_eq!(U256::from(32).to_be_bytes::<32>(), encoded[4..36]);
+    assert_eq!(U256::from(2).to_be_bytes::<32>(), encoded[36..68]);
+    assert_eq!(vec![(true, U256::from(5).to_be_bytes::<32>().to_vec()), (false, vec![])], results);
+    assert!(decode_aggregate3(&[0x01]).is_err());
+}
+
+#[test]
+fn test_decode_bytecode_longer_than_255_bytes() {
+    // Given
+    let code: Vec<u8> = (0..300_u32).map(|i| (i % 256) as u8).collect();
+    let mut call_result = vec![FieldElement::from(code.len() as u64)];
+    call_result.extend(code.iter().map(|byte| FieldElement::from(*byte)));
+
+    // When
+    let bytecode = decode_bytecode::<std::io::Error>(&call_result).unwrap();
+
+    // Then
+    assert_eq!(code, bytecode.to_vec());
+    assert!(decode_bytecode::<std::io::Error>(&[]).unwrap().is_empty());
+    let truncated = decode_bytecode::<std::io::Error>(&call_result[..100]);
+    assert!(matches!(truncated, Err(EthApiError::DataDecodingError(_))));
+}
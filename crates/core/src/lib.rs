@@ -3,4 +3,5 @@ pub mod client;
 pub mod contracts;
 pub mod mock;
 pub mod models;
+pub mod storage;
 pub mod test_utils;
@@ -1,12 +1,12 @@
 use std::str::FromStr;
 
-use reth_primitives::{Bloom, Bytes, H160, H256, U128, U256};
+use reth_primitives::{Bloom, Bytes, H160, H256, U128, U256, U64};
 use reth_rpc_types::{Block, BlockTransactions, Rich, Signature, Transaction};
 use serde::{Deserialize, Serialize};
 use starknet::core::types::{FieldElement, InvokeTransaction, Transaction as StarknetTransaction};
 
 use crate::client::constants::gas::BASE_FEE_PER_GAS;
-use crate::client::constants::{CHAIN_ID, DIFFICULTY, GAS_LIMIT, GAS_USED, MIX_HASH, NONCE, SIZE, TOTAL_DIFFICULTY};
+use crate::client::constants::{CHAIN_ID, DIFFICULTY, GAS_LIMIT, MIX_HASH, NONCE, SIZE, TOTAL_DIFFICULTY};
 use crate::models::felt::Felt252Wrapper;
 use crate::models::signature::StarknetSignature;
 
@@ -104,7 +104,8 @@ pub fn assert_block_header(block: &Rich<Block>, starknet_res: String, hydrated:
     assert_eq!(block.header.extra_data, Bytes::default());
     assert_eq!(block.header.logs_bloom, Bloom::default());
 
-    assert_eq!(block.header.gas_used, *GAS_USED);
+    // The fee of the Kakarot transaction of the fixtures exceeds the gas limit at the default base fee
+    assert_eq!(block.header.gas_used, *GAS_LIMIT);
     assert_eq!(block.header.gas_limit, *GAS_LIMIT);
     assert_eq!(block.header.difficulty, *DIFFICULTY);
     assert_eq!(block.header.base_fee_per_gas, Some(U256::from(BASE_FEE_PER_GAS)));
@@ -113,21 +114,23 @@ pub fn assert_block_header(block: &Rich<Block>, starknet_res: String, hydrated:
 }
 
 pub fn assert_transaction(ether_tx: Transaction, starknet_tx: StarknetTransaction) {
+    // The transactions of the fixtures wrap an EIP-1559 contract deployment, with 0xdead as nonce,
+    // fee caps and gas limit
     assert_eq!(ether_tx.chain_id, Some(CHAIN_ID.into()));
-    assert_eq!(ether_tx.access_list, None);
-    assert_eq!(ether_tx.transaction_type, None);
+    assert_eq!(ether_tx.access_list, Some(vec![]));
+    assert_eq!(ether_tx.transaction_type, Some(U64::from(2)));
 
     assert_eq!(ether_tx.to, None);
-    assert_eq!(ether_tx.value, U256::from(100));
-    assert_eq!(ether_tx.gas, U256::from(100));
-    assert_eq!(ether_tx.gas_price, None);
+    assert_eq!(ether_tx.value, U256::ZERO);
+    assert_eq!(ether_tx.gas, U256::from(0xdead));
+    assert_eq!(ether_tx.gas_price, Some(U128::from(0xdead)));
     let index = match ether_tx.transaction_index {
         Some(_) => Some(U256::from(0)),
         _ => None,
     };
     assert_eq!(ether_tx.transaction_index, index);
-    assert_eq!(ether_tx.max_fee_per_gas, None);
-    assert_eq!(ether_tx.max_priority_fee_per_gas, Some(U128::ZERO));
+    assert_eq!(ether_tx.max_fee_per_gas, Some(U128::from(0xdead)));
+    assert_eq!(ether_tx.max_priority_fee_per_gas, Some(U128::from(0xdead)));
 
     match starknet_tx {
         StarknetTransaction::Invoke(invoke_tx) => {
@@ -142,15 +145,14 @@ pub fn assert_transaction(ether_tx: Transaction, starknet_tx: StarknetTransactio
                     assert_eq!(ether_tx.from, Felt252Wrapper::from(v0.contract_address).try_into().unwrap());
                     // r and s values are extracted from the calldata of the first transaction
                     // in the starknet_getBlockWithTxs.json file.
-                    // v value is calculated from the parity of the y coordinate of the signature,
-                    // to which we add 35 + 2 * CHAIN_ID (based on https://eips.ethereum.org/EIPS/eip-155).
+                    // v value is the parity of the y coordinate of the signature, the transaction
+                    // being a typed transaction (based on https://eips.ethereum.org/EIPS/eip-2718).
                     let signature = Signature {
                         r: U256::from_str("0x05e6a35e537e8d99c81bf2d4e7e8a410e7f6f3f8b1f07edc28bf226d3ac2cae12")
                             .unwrap(),
                         s: U256::from_str("0x01910d7b4784e7347a6c7dccf8b8051c06f091347eb4a4a2f6092f1541cb62de7")
                             .unwrap(),
-                        v: U256::from_str("0x000000000000000000000000000000000000000000000000000000009696a4cc")
-                            .unwrap(),
+                        v: U256::from(1),
                     };
                     assert_eq!(ether_tx.signature, Some(signature));
                 }
@@ -161,15 +163,14 @@ pub fn assert_transaction(ether_tx: Transaction, starknet_tx: StarknetTransactio
                     assert_eq!(ether_tx.from, H160::from_str("0x54b288676b749def5fc10eb17244fe2c87375de1").unwrap());
                     // r and s values are extracted from the calldata of the first transaction
                     // in the starknet_getBlockWithTxs.json file.
-                    // v value is calculated from the parity of the y coordinate of the signature,
-                    // to which we add 35 + 2 * CHAIN_ID (based on https://eips.ethereum.org/EIPS/eip-155).
+                    // v value is the parity of the y coordinate of the signature, the transaction
+                    // being a typed transaction (based on https://eips.ethereum.org/EIPS/eip-2718).
                     let signature = Signature {
                         r: U256::from_str("0x05e6a35e537e8d99c81bf2d4e7e8a410e7f6f3f8b1f07edc28bf226d3ac2cae12")
                             .unwrap(),
                         s: U256::from_str("0x01910d7b4784e7347a6c7dccf8b8051c06f091347eb4a4a2f6092f1541cb62de7")
                             .unwrap(),
-                        v: U256::from_str("0x000000000000000000000000000000000000000000000000000000009696a4cc")
-                            .unwrap(),
+                        v: U256::from(1),
                     };
                     assert_eq!(ether_tx.signature, Some(signature));
                     // TODO: test ether_tx.input
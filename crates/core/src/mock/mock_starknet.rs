@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::fs;
+use std::sync::{Arc, Mutex};
 
+use async_trait::async_trait;
 use dojo_test_utils::rpc::MockJsonRpcTransport;
 use foundry_config::find_git_root_path;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value;
-use starknet::providers::jsonrpc::JsonRpcMethod;
+use starknet::providers::jsonrpc::{JsonRpcMethod, JsonRpcResponse, JsonRpcTransport};
 use starknet::providers::{JsonRpcClient, SequencerGatewayProvider};
 use starknet_crypto::FieldElement;
 use walkdir::WalkDir;
@@ -23,6 +27,13 @@ pub struct StarknetRpcFixture {
     response: Value,
 }
 
+impl StarknetRpcFixture {
+    /// Returns a fixture answering the call of the method with the params by the response.
+    pub fn new(method: JsonRpcMethod, params: Value, response: Value) -> Self {
+        Self { method, params, response }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub enum AvailableFixtures {
     ComputeStarknetAddress,
@@ -197,6 +208,121 @@ pub fn mock_starknet_provider(fixtures: Option<Vec<StarknetRpcFixture>>) -> Json
     JsonRpcClient::new(transport)
 }
 
+/// JSON-RPC transport answering each method with the same response whatever its params, for the
+/// requests whose params can't be predicted, e.g. the signed transactions. The methods called are
+/// recorded in order.
+#[derive(Debug, Default)]
+pub struct MethodMockTransport {
+    responses: HashMap<String, Value>,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl MethodMockTransport {
+    /// Answers the calls of the method with the JSON-RPC response.
+    pub fn set_response(&mut self, method: JsonRpcMethod, response: Value) {
+        self.responses.insert(method_name(method), response);
+    }
+
+    /// Returns the names of the methods called, shared with the transport.
+    pub fn calls(&self) -> Arc<Mutex<Vec<String>>> {
+        Arc::clone(&self.calls)
+    }
+}
+
+#[async_trait]
+impl JsonRpcTransport for MethodMockTransport {
+    type Error = serde_json::Error;
+
+    async fn send_request<P, R>(&self, method: JsonRpcMethod, _params: P) -> Result<JsonRpcResponse<R>, Self::Error>
+    where
+        P: Serialize + Send,
+        R: DeserializeOwned,
+    {
+        let method = method_name(method);
+        let response = self
+            .responses
+            .get(&method)
+            .unwrap_or_else(|| panic!("Response not set in mock for method {method}"))
+            .clone();
+        self.calls.lock().expect("mock calls poisoned").push(method);
+        serde_json::from_value(response)
+    }
+}
+
+/// JSON-RPC transport serving a chain of empty blocks, which the tests edit to extend or
+/// reorganize the chain, or to accept its blocks on L1. The methods called are recorded in order.
+#[derive(Debug, Clone, Default)]
+pub struct ChainMockTransport {
+    /// Hash of each block of the chain, by block number, the parent of a block being the previous
+    /// one.
+    pub hashes: Arc<Mutex<Vec<u64>>>,
+    /// Number of blocks accepted on L1, from the first block of the chain.
+    pub l1_accepted_blocks: Arc<Mutex<u64>>,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl ChainMockTransport {
+    /// Returns the names of the methods called, shared with the transport.
+    pub fn calls(&self) -> Arc<Mutex<Vec<String>>> {
+        Arc::clone(&self.calls)
+    }
+
+    /// Returns the block with the given id, a `block_number` or `block_hash` object.
+    fn block(&self, block_id: &Value) -> Option<Value> {
+        let hashes = self.hashes.lock().expect("mock chain poisoned");
+        let number = match (block_id["block_number"].as_u64(), block_id["block_hash"].as_str()) {
+            (Some(number), _) => number,
+            (None, Some(hash)) => {
+                let hash = FieldElement::from_hex_be(hash).ok()?;
+                hashes.iter().position(|h| FieldElement::from(*h) == hash)? as u64
+            }
+            (None, None) => return None,
+        };
+        let hash = *hashes.get(number as usize)?;
+        let parent_hash = number.checked_sub(1).map_or(0, |parent| hashes[parent as usize]);
+        let l1_accepted = number < *self.l1_accepted_blocks.lock().expect("mock chain poisoned");
+        Some(serde_json::json!({
+            "status": if l1_accepted { "ACCEPTED_ON_L1" } else { "ACCEPTED_ON_L2" },
+            "block_hash": format!("{:#x}", FieldElement::from(hash)),
+            "parent_hash": format!("{:#x}", FieldElement::from(parent_hash)),
+            "block_number": number,
+            "new_root": "0x1",
+            "timestamp": number,
+            "sequencer_address": "0x1",
+            "transactions": [],
+        }))
+    }
+}
+
+#[async_trait]
+impl JsonRpcTransport for ChainMockTransport {
+    type Error = serde_json::Error;
+
+    async fn send_request<P, R>(&self, method: JsonRpcMethod, params: P) -> Result<JsonRpcResponse<R>, Self::Error>
+    where
+        P: Serialize + Send,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let result = match &method {
+            JsonRpcMethod::BlockNumber => {
+                serde_json::json!(self.hashes.lock().expect("mock chain poisoned").len().saturating_sub(1))
+            }
+            JsonRpcMethod::GetBlockWithTxHashes | JsonRpcMethod::GetBlockWithTxs => {
+                self.block(&params[0]).expect("block not in the mock chain")
+            }
+            _ => panic!("Response not set in mock for method {method:?}"),
+        };
+        self.calls.lock().expect("mock calls poisoned").push(method_name(method));
+        serde_json::from_value(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+    }
+}
+
+/// Returns the name of the JSON-RPC method, e.g. `starknet_getNonce`.
+fn method_name(method: JsonRpcMethod) -> String {
+    serde_json::to_value(method).ok().and_then(|method| method.as_str().map(ToString::to_string)).unwrap_or_default()
+}
+
 pub fn init_testnet_client() -> KakarotClient<SequencerGatewayProvider> {
     let kakarot_address = FieldElement::from_hex_be(KAKAROT_TESTNET_ADDRESS).unwrap();
     let config = StarknetConfig::new(Network::Goerli1Gateway, kakarot_address, Default::default());
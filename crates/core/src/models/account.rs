@@ -0,0 +1,129 @@
+//! Kakarot accounts, returned by the `kakarot` namespace.
+use reth_primitives::Address;
+use serde::Serialize;
+use starknet::core::types::{EmittedEvent, FieldElement};
+
+use super::kakarot_event::{EvmContractDeployed, KakarotEvent};
+
+/// An EVM account (EOA or contract) deployed by Kakarot, along with the address of the Starknet
+/// contract backing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployedAccount {
+    pub evm_address: Address,
+    pub starknet_address: FieldElement,
+}
+
+impl DeployedAccount {
+    /// Reads the deployed account from an `evm_contract_deployed` event. Returns `None` for any
+    /// other event.
+    pub fn from_event(event: &EmittedEvent) -> Option<Self> {
+        match KakarotEvent::decode(&event.keys, &event.data) {
+            Ok(KakarotEvent::EvmContractDeployed(deployed)) => Some(deployed.into()),
+            _ => None,
+        }
+    }
+}
+
+impl From<EvmContractDeployed> for DeployedAccount {
+    fn from(deployed: EvmContractDeployed) -> Self {
+        Self { evm_address: deployed.evm_address, starknet_address: deployed.starknet_address }
+    }
+}
+
+/// Type of the Kakarot account backing an EVM address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountType {
+    /// No account is deployed at the Starknet address of the EVM address yet.
+    Undeployed,
+    /// Externally owned account, without bytecode.
+    Eoa,
+    /// Contract account.
+    Contract,
+}
+
+/// Kakarot account backing an EVM address, returned by `kakarot_getAccountType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountDetails {
+    #[serde(rename = "type")]
+    pub account_type: AccountType,
+    /// Starknet address of the account, computed from the EVM address whether it is deployed or
+    /// not.
+    pub starknet_address: FieldElement,
+    /// Class hash of the contract deployed at the Starknet address, the proxy account class hash
+    /// for Kakarot accounts.
+    pub class_hash: Option<FieldElement>,
+    /// Class hash of the implementation the proxy delegates to.
+    pub implementation_class_hash: Option<FieldElement>,
+}
+
+impl AccountDetails {
+    /// Returns the details of an EVM address without deployed account.
+    pub fn undeployed(starknet_address: FieldElement) -> Self {
+        Self {
+            account_type: AccountType::Undeployed,
+            starknet_address,
+            class_hash: None,
+            implementation_class_hash: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet::macros::felt;
+
+    use super::*;
+    use crate::client::constants::selectors::EVM_CONTRACT_DEPLOYED;
+
+    fn event(keys: Vec<FieldElement>, data: Vec<FieldElement>) -> EmittedEvent {
+        EmittedEvent {
+            from_address: felt!("0x1"),
+            keys,
+            data,
+            block_hash: felt!("0x2"),
+            block_number: 1,
+            transaction_hash: felt!("0x3"),
+        }
+    }
+
+    #[test]
+    fn test_deployed_account_from_event() {
+        // Given
+        let deployed = event(vec![EVM_CONTRACT_DEPLOYED], vec![felt!("0xabde1"), felt!("0x1234")]);
+        let other = event(vec![felt!("0x42")], vec![felt!("0xabde1"), felt!("0x1234")]);
+
+        // When
+        let account = DeployedAccount::from_event(&deployed);
+
+        // Then
+        assert_eq!(
+            Some(DeployedAccount { evm_address: Address::from_low_u64_be(0xabde1), starknet_address: felt!("0x1234") }),
+            account
+        );
+        assert_eq!(None, DeployedAccount::from_event(&other));
+        assert_eq!(None, DeployedAccount::from_event(&event(vec![EVM_CONTRACT_DEPLOYED], vec![felt!("0x1")])));
+    }
+
+    #[test]
+    fn test_account_details_serialization() {
+        // Given
+        let details = AccountDetails {
+            account_type: AccountType::Eoa,
+            starknet_address: felt!("0x1234"),
+            class_hash: Some(felt!("0x1")),
+            implementation_class_hash: Some(felt!("0x2")),
+        };
+
+        // When
+        let details = serde_json::to_value(details).unwrap();
+
+        // Then
+        assert_eq!("eoa", details["type"]);
+        assert_eq!("0x1234", details["starknetAddress"]);
+        assert_eq!("0x2", details["implementationClassHash"]);
+        assert_eq!("undeployed", serde_json::to_value(AccountDetails::undeployed(felt!("0x1"))).unwrap()["type"]);
+    }
+}
@@ -0,0 +1,164 @@
+//! Activity of the watched accounts in the new blocks, pushed by the
+//! `kakarot_subscribeAccountActivity` subscription so that wallets don't have to poll the blocks,
+//! the receipts and the logs to show the activity of their accounts.
+use std::collections::{BTreeSet, HashSet};
+
+use reth_primitives::{Address, H256, U256};
+use reth_rpc_types::TransactionReceipt;
+use serde::Serialize;
+
+/// Role of an account in a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivityRole {
+    /// The account sent the transaction.
+    Sender,
+    /// The account is the recipient of the transaction, or the contract it deployed.
+    Receiver,
+    /// The account emitted a log during the transaction.
+    LogEmitter,
+}
+
+/// Activity of a watched account in a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountActivity {
+    pub address: Address,
+    pub block_hash: Option<H256>,
+    pub block_number: Option<U256>,
+    pub transaction_hash: Option<H256>,
+    pub transaction_index: Option<U256>,
+    /// Roles of the account in the transaction, in the order sender, receiver, log emitter.
+    pub roles: BTreeSet<ActivityRole>,
+    /// True when the block of the transaction was reverted by a reorganization, the activity being
+    /// sent again.
+    pub removed: bool,
+}
+
+impl AccountActivity {
+    /// Returns the activity of the watched accounts in the transactions of a block, given by their
+    /// receipts, in the order of the transactions and of the watched addresses.
+    pub fn from_receipts(watched: &[Address], receipts: &[TransactionReceipt]) -> Vec<Self> {
+        let mut activities = Vec::new();
+        for receipt in receipts {
+            let emitters: HashSet<Address> = receipt.logs.iter().map(|log| log.address).collect();
+            let receiver = receipt.to.or(receipt.contract_address);
+            for address in watched {
+                let roles: BTreeSet<ActivityRole> = [
+                    (receipt.from == *address).then_some(ActivityRole::Sender),
+                    (receiver == Some(*address)).then_some(ActivityRole::Receiver),
+                    emitters.contains(address).then_some(ActivityRole::LogEmitter),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                if roles.is_empty() {
+                    continue;
+                }
+                activities.push(Self {
+                    address: *address,
+                    block_hash: receipt.block_hash,
+                    block_number: receipt.block_number,
+                    transaction_hash: receipt.transaction_hash,
+                    transaction_index: receipt.transaction_index,
+                    roles,
+                    removed: false,
+                });
+            }
+        }
+        activities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_rpc_types::Log;
+
+    use super::*;
+    use crate::client::helpers::create_default_transaction_receipt;
+
+    fn receipt(index: u64, from: Address, to: Option<Address>, emitters: &[Address]) -> TransactionReceipt {
+        let logs = emitters
+            .iter()
+            .map(|address| Log {
+                address: *address,
+                topics: vec![],
+                data: Default::default(),
+                block_hash: None,
+                block_number: None,
+                transaction_hash: None,
+                transaction_index: None,
+                log_index: None,
+                removed: false,
+            })
+            .collect();
+        TransactionReceipt {
+            transaction_hash: Some(H256::from_low_u64_be(index)),
+            transaction_index: Some(U256::from(index)),
+            block_hash: Some(H256::from_low_u64_be(0xb)),
+            block_number: Some(U256::from(11)),
+            from,
+            to,
+            contract_address: to.is_none().then_some(Address::from_low_u64_be(0xc0de)),
+            logs,
+            ..create_default_transaction_receipt()
+        }
+    }
+
+    #[test]
+    fn test_account_activity_from_receipts() {
+        // Given
+        let (alice, bob, token) =
+            (Address::from_low_u64_be(0xa), Address::from_low_u64_be(0xb), Address::from_low_u64_be(0x7));
+        let deployed = Address::from_low_u64_be(0xc0de);
+        let receipts = vec![
+            // alice calls the token, which emits a log, bob isn't involved
+            receipt(0, alice, Some(token), &[token]),
+            // bob sends to himself
+            receipt(1, bob, Some(bob), &[]),
+            // alice deploys a contract
+            receipt(2, alice, None, &[deployed]),
+        ];
+
+        // When
+        let activities = AccountActivity::from_receipts(&[alice, bob, deployed], &receipts);
+
+        // Then
+        let summary: Vec<_> = activities
+            .iter()
+            .map(|activity| {
+                (
+                    activity.transaction_index.unwrap().to::<u64>(),
+                    activity.address,
+                    activity.roles.iter().copied().collect(),
+                )
+            })
+            .collect();
+        let expected: Vec<(u64, Address, Vec<ActivityRole>)> = vec![
+            (0, alice, vec![ActivityRole::Sender]),
+            (1, bob, vec![ActivityRole::Sender, ActivityRole::Receiver]),
+            (2, alice, vec![ActivityRole::Sender]),
+            (2, deployed, vec![ActivityRole::Receiver, ActivityRole::LogEmitter]),
+        ];
+        assert_eq!(expected, summary);
+        assert_eq!(Some(U256::from(11)), activities[0].block_number);
+    }
+
+    #[test]
+    fn test_account_activity_serialization() {
+        // Given
+        let activity = AccountActivity::from_receipts(
+            &[Address::from_low_u64_be(0xa)],
+            &[receipt(0, Address::from_low_u64_be(0xa), Some(Address::from_low_u64_be(0xa)), &[])],
+        )
+        .remove(0);
+
+        // When
+        let value = serde_json::to_value(activity).unwrap();
+
+        // Then
+        assert_eq!(serde_json::json!(["sender", "receiver"]), value["roles"]);
+        assert_eq!(serde_json::json!("0xb"), value["blockNumber"]);
+        assert_eq!(serde_json::json!(false), value["removed"]);
+    }
+}
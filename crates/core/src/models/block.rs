@@ -1,6 +1,6 @@
 use async_trait::async_trait;
-use reth_primitives::{BlockId as EthereumBlockId, BlockNumberOrTag, Bloom, Bytes, H256, H64, U256};
-use reth_rpc_types::{Block, BlockTransactions, Header, RichBlock};
+use reth_primitives::{keccak256, BlockId as EthereumBlockId, BlockNumberOrTag, Bloom, Bytes, H256, H64, U256};
+use reth_rpc_types::{Block, BlockTransactions, Header, Log, RichBlock};
 use starknet::core::types::{
     BlockId as StarknetBlockId, BlockTag, FieldElement, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs,
     Transaction,
@@ -11,9 +11,7 @@ use super::convertible::ConvertibleStarknetBlock;
 use super::felt::Felt252Wrapper;
 use super::ConversionError;
 use crate::client::api::KakarotEthApi;
-use crate::client::constants::{
-    DIFFICULTY, EARLIEST_BLOCK_NUMBER, GAS_LIMIT, GAS_USED, MIX_HASH, NONCE, SIZE, TOTAL_DIFFICULTY,
-};
+use crate::client::constants::{DIFFICULTY, EARLIEST_BLOCK_NUMBER, GAS_USED, MIX_HASH, NONCE, SIZE, TOTAL_DIFFICULTY};
 
 pub struct EthBlockId(EthereumBlockId);
 
@@ -147,25 +145,64 @@ impl BlockWithTxs {
     );
 }
 
+/// Returns the number of the Ethereum block of a Starknet block. The pending block, which doesn't
+/// have a number on Starknet, is numbered after the latest block, as in geth, or left without a
+/// number if the latest block can't be fetched.
+async fn eth_block_number<P: Provider + Send + Sync>(
+    block_number: Option<u64>,
+    client: &dyn KakarotEthApi<P>,
+) -> Option<U256> {
+    match block_number {
+        Some(block_number) => Some(U256::from(block_number)),
+        None => client.block_number().await.ok().map(|latest| U256::from(latest.as_u64() + 1)),
+    }
+}
+
+/// Returns the bloom filter of the logs, holding the address and the topics of each log, as in the
+/// Ethereum blocks and receipts.
+pub fn logs_bloom<'a>(logs: impl IntoIterator<Item = &'a Log>) -> Bloom {
+    let mut bloom = Bloom::default();
+    for log in logs {
+        accrue(&mut bloom, log.address.as_bytes());
+        for topic in &log.topics {
+            accrue(&mut bloom, topic.as_bytes());
+        }
+    }
+    bloom
+}
+
+/// Sets the 3 bits of the value in the bloom filter, each indexed by 11 bits of its hash.
+fn accrue(bloom: &mut Bloom, value: &[u8]) {
+    let hash = keccak256(value);
+    let bytes = bloom.as_bytes_mut();
+    for i in [0, 2, 4] {
+        let bit = (usize::from(hash[i]) << 8 | usize::from(hash[i + 1])) & 0x7ff;
+        bytes[255 - bit / 8] |= 1 << (bit % 8);
+    }
+}
+
 #[async_trait]
 impl ConvertibleStarknetBlock for BlockWithTxHashes {
     async fn to_eth_block<P: Provider + Send + Sync>(&self, client: &dyn KakarotEthApi<P>) -> RichBlock {
         // TODO: Fetch real data
-        let gas_limit = *GAS_LIMIT;
+        let gas_limit = client.block_gas_limit();
 
-        // TODO: Fetch real data
+        // Replaced by the gas used of the Kakarot transactions of the block, see
+        // `KakarotEthApi::get_eth_block_from_starknet_block`
         let gas_used = *GAS_USED;
 
         // TODO: Fetch real data
         let difficulty = *DIFFICULTY;
 
         // TODO: Fetch real data
-        let nonce: Option<H64> = Some(H64::zero());
+        // The pending block doesn't have a nonce, as in geth
+        let nonce: Option<H64> = self.block_hash().map(|_| H64::zero());
 
         // TODO: Fetch real data
         let size: Option<U256> = *SIZE;
 
-        // Bloom is a byte array of length 256
+        // Replaced by the bloom of the logs of the Kakarot transactions of the block, see
+        // `KakarotEthApi::get_eth_block_from_starknet_block`
         let logs_bloom = Bloom::default();
         let extra_data = Bytes::default();
 
@@ -176,10 +213,11 @@ impl ConvertibleStarknetBlock for BlockWithTxHashes {
 
         let parent_hash = H256::from_slice(&self.parent_hash().to_bytes_be());
         let sequencer = Felt252Wrapper::from(self.sequencer_address()).truncate_to_ethereum_address();
+        let miner = client.coinbase().unwrap_or(sequencer);
         let timestamp = U256::from(self.timestamp());
 
         let hash = self.block_hash().as_ref().map(|hash| H256::from_slice(&hash.to_bytes_be()));
-        let number = self.block_number().map(U256::from);
+        let number = eth_block_number(self.block_number(), client).await;
 
         // TODO: Add filter to tx_hashes
         let transactions = BlockTransactions::Hashes(
@@ -191,14 +229,14 @@ impl ConvertibleStarknetBlock for BlockWithTxHashes {
             hash,
             parent_hash,
             uncles_hash: parent_hash,
-            miner: sequencer,
+            miner,
             // PendingBlockWithTxHashes doesn't have a state root
             state_root: H256::zero(),
             // PendingBlockWithTxHashes doesn't have a transactions root
             transactions_root: H256::zero(),
             // PendingBlockWithTxHashes doesn't have a receipts root
             receipts_root: H256::zero(),
-            // PendingBlockWithTxHashes doesn't have a block number
+            // The number of the pending block is the one following the latest block
             number,
             gas_used,
             gas_limit,
@@ -227,21 +265,24 @@ impl ConvertibleStarknetBlock for BlockWithTxHashes {
 impl ConvertibleStarknetBlock for BlockWithTxs {
     async fn to_eth_block<P: Provider + Send + Sync>(&self, client: &dyn KakarotEthApi<P>) -> RichBlock {
         // TODO: Fetch real data
-        let gas_limit = *GAS_LIMIT;
+        let gas_limit = client.block_gas_limit();
 
-        // TODO: Fetch real data
+        // Replaced by the gas used of the Kakarot transactions of the block, see
+        // `KakarotEthApi::get_eth_block_from_starknet_block`
         let gas_used = *GAS_USED;
 
         // TODO: Fetch real data
         let difficulty = *DIFFICULTY;
 
         // TODO: Fetch real data
-        let nonce: Option<H64> = *NONCE;
+        // The pending block doesn't have a nonce, as in geth
+        let nonce: Option<H64> = self.block_hash().and(*NONCE);
 
         // TODO: Fetch real data
         let size: Option<U256> = *SIZE;
 
-        // Bloom is a byte array of length 256
+        // Replaced by the bloom of the logs of the Kakarot transactions of the block, see
+        // `KakarotEthApi::get_eth_block_from_starknet_block`
         let logs_bloom = Bloom::default();
         let extra_data: Bytes = Bytes::default();
 
@@ -253,26 +294,30 @@ impl ConvertibleStarknetBlock for BlockWithTxs {
         let parent_hash = H256::from_slice(&self.parent_hash().to_bytes_be());
 
         let sequencer = Felt252Wrapper::from(self.sequencer_address()).truncate_to_ethereum_address();
+        let miner = client.coinbase().unwrap_or(sequencer);
 
         let timestamp = U256::from(self.timestamp());
 
         let hash = self.block_hash().as_ref().map(|hash| H256::from_slice(&hash.to_bytes_be()));
-        let number = self.block_number().map(U256::from);
+        let number = eth_block_number(self.block_number(), client).await;
 
-        let transactions = client.filter_starknet_into_eth_txs(self.transactions().into(), hash, number).await;
+        // The transactions of the pending block don't have a block hash nor a block number
+        let transactions = client
+            .filter_starknet_into_eth_txs(self.transactions().into(), hash, self.block_number().map(U256::from))
+            .await;
         let header = Header {
             // PendingBlockWithTxs doesn't have a block hash
             hash,
             parent_hash,
             uncles_hash: parent_hash,
-            miner: sequencer,
+            miner,
             // PendingBlockWithTxs doesn't have a state root
             state_root: H256::zero(),
             // PendingBlockWithTxs doesn't have a transactions root
             transactions_root: H256::zero(),
             // PendingBlockWithTxs doesn't have a receipts root
             receipts_root: H256::zero(),
-            // PendingBlockWithTxs doesn't have a block number
+            // The number of the pending block is the one following the latest block
             number,
             gas_used,
             gas_limit,
@@ -300,11 +345,42 @@ impl ConvertibleStarknetBlock for BlockWithTxs {
 #[cfg(test)]
 mod tests {
 
+    use starknet::providers::jsonrpc::JsonRpcMethod;
+
     use super::*;
     use crate::mock::constants::{
         ABDEL_STARKNET_ADDRESS_HEX, OTHER_ADDRESS_HEX, OTHER_PROXY_ACCOUNT_CLASS_HASH_HEX, PROXY_ACCOUNT_CLASS_HASH_HEX,
     };
     use crate::mock::mock_starknet::{fixtures, init_mock_client, AvailableFixtures};
+    use crate::wrap_kakarot;
+
+    /// Number of the pending block, following the latest block of the `starknet_blockNumber`
+    /// fixture.
+    const PENDING_BLOCK_NUMBER: &str = "0x4cb9";
+
+    /// Removes the fields of the Starknet block which the pending block doesn't have.
+    fn into_pending(block: &str) -> serde_json::Value {
+        let mut block: serde_json::Value = serde_json::from_str(block).unwrap();
+        for field in ["block_hash", "block_number", "new_root", "status"] {
+            block.as_object_mut().unwrap().remove(field);
+        }
+        block
+    }
+
+    /// Sets the fields of the expected Ethereum block which differ for the pending block.
+    fn expected_pending(block: &str) -> Block {
+        let mut block: serde_json::Value = serde_json::from_str(block).unwrap();
+        block["hash"] = serde_json::Value::Null;
+        block["nonce"] = serde_json::Value::Null;
+        block["number"] = PENDING_BLOCK_NUMBER.into();
+        if let Some(serde_json::Value::Array(transactions)) = block.get_mut("transactions") {
+            for transaction in transactions.iter_mut().filter(|transaction| transaction.is_object()) {
+                transaction["blockHash"] = serde_json::Value::Null;
+                transaction["blockNumber"] = serde_json::Value::Null;
+            }
+        }
+        serde_json::from_value(block).unwrap()
+    }
 
     #[tokio::test]
     async fn test_to_eth_block_block_with_tx_hashes() {
@@ -347,4 +423,74 @@ mod tests {
             serde_json::from_str(include_str!("test_data/conversion/eth/block_with_txs.json")).unwrap();
         assert_eq!(expected, eth_block_with_txs);
     }
+
+    #[tokio::test]
+    async fn test_to_eth_block_pending_block_with_tx_hashes() {
+        // Given
+        let pending = into_pending(include_str!("test_data/conversion/starknet/block_with_tx_hashes.json"));
+        let starknet_block_with_tx_hashes: MaybePendingBlockWithTxHashes = serde_json::from_value(pending).unwrap();
+        assert!(matches!(starknet_block_with_tx_hashes, MaybePendingBlockWithTxHashes::PendingBlock(_)));
+        let starknet_block_with_tx_hashes = BlockWithTxHashes::new(starknet_block_with_tx_hashes);
+
+        let fixtures = fixtures(vec![wrap_kakarot!(JsonRpcMethod::BlockNumber)]);
+        let client = init_mock_client(Some(fixtures));
+
+        // When
+        let eth_block_with_tx_hashes = starknet_block_with_tx_hashes.to_eth_block(&client).await.inner;
+
+        // Then
+        let expected = expected_pending(include_str!("test_data/conversion/eth/block_with_tx_hashes.json"));
+        assert_eq!(expected, eth_block_with_tx_hashes);
+    }
+
+    #[test]
+    fn test_logs_bloom() {
+        // Given
+        let log = |address: u64, topic: u64| Log {
+            address: reth_primitives::Address::from_low_u64_be(address),
+            topics: vec![H256::from_low_u64_be(topic)],
+            data: Bytes::default(),
+            block_hash: None,
+            block_number: None,
+            transaction_hash: None,
+            transaction_index: None,
+            log_index: None,
+            removed: false,
+        };
+        let (first, second) = (log(1, 2), log(3, 4));
+
+        // When
+        let bloom = logs_bloom([&first, &second]);
+
+        // Then
+        let first_bloom = logs_bloom([&first]);
+        let bits = |bloom: &Bloom| bloom.as_bytes().iter().map(|byte| byte.count_ones()).sum::<u32>();
+        assert!((1..=6).contains(&bits(&first_bloom)));
+        assert_eq!(first_bloom | logs_bloom([&second]), bloom);
+        assert_eq!(Bloom::default(), logs_bloom([]));
+    }
+
+    #[tokio::test]
+    async fn test_to_eth_block_pending_block_with_txs() {
+        // Given
+        let pending = into_pending(include_str!("test_data/conversion/starknet/block_with_txs.json"));
+        let starknet_block_with_txs: MaybePendingBlockWithTxs = serde_json::from_value(pending).unwrap();
+        assert!(matches!(starknet_block_with_txs, MaybePendingBlockWithTxs::PendingBlock(_)));
+        let starknet_block_with_txs = BlockWithTxs::new(starknet_block_with_txs);
+
+        let fixtures = fixtures(vec![
+            AvailableFixtures::GetClassHashAt(ABDEL_STARKNET_ADDRESS_HEX.into(), PROXY_ACCOUNT_CLASS_HASH_HEX.into()),
+            AvailableFixtures::GetClassHashAt(OTHER_ADDRESS_HEX.into(), OTHER_PROXY_ACCOUNT_CLASS_HASH_HEX.into()),
+            AvailableFixtures::GetEvmAddress,
+            wrap_kakarot!(JsonRpcMethod::BlockNumber),
+        ]);
+        let client = init_mock_client(Some(fixtures));
+
+        // When
+        let eth_block_with_txs = starknet_block_with_txs.to_eth_block(&client).await.inner;
+
+        // Then
+        let expected = expected_pending(include_str!("test_data/conversion/eth/block_with_txs.json"));
+        assert_eq!(expected, eth_block_with_txs);
+    }
 }
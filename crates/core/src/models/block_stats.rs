@@ -0,0 +1,95 @@
+//! Aggregates of the Kakarot transactions of a block, returned by `kakarot_getBlockStats`, giving
+//! dashboards the health of the chain without scanning the receipts.
+use std::collections::HashSet;
+
+use reth_primitives::{H256, U256, U64};
+use reth_rpc_types::TransactionReceipt;
+use serde::Serialize;
+
+/// Aggregates of the Kakarot transactions of a block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStats {
+    pub number: U64,
+    pub hash: H256,
+    pub transaction_count: U64,
+    /// Transactions which reverted.
+    pub failed_transaction_count: U64,
+    /// Total gas used by the transactions.
+    pub gas_used: U256,
+    /// Average fee paid by a transaction, in wei, zero for an empty block.
+    pub average_fee: U256,
+    /// Number of distinct senders of the transactions.
+    pub unique_senders: U64,
+}
+
+impl BlockStats {
+    /// Aggregates the receipts of the transactions of the block.
+    pub fn from_receipts(number: u64, hash: H256, receipts: &[TransactionReceipt]) -> Self {
+        let mut gas_used = U256::ZERO;
+        let mut total_fee = U256::ZERO;
+        let mut failed_transaction_count = 0u64;
+        let mut senders = HashSet::new();
+        for receipt in receipts {
+            let receipt_gas_used = receipt.gas_used.unwrap_or_default();
+            gas_used = gas_used.saturating_add(receipt_gas_used);
+            total_fee =
+                total_fee.saturating_add(receipt_gas_used.saturating_mul(U256::from(receipt.effective_gas_price)));
+            if receipt.status_code.is_some_and(|status| status == U64::ZERO) {
+                failed_transaction_count += 1;
+            }
+            senders.insert(receipt.from);
+        }
+
+        let transaction_count = receipts.len() as u64;
+        let average_fee = match transaction_count {
+            0 => U256::ZERO,
+            count => total_fee / U256::from(count),
+        };
+        Self {
+            number: U64::from(number),
+            hash,
+            transaction_count: U64::from(transaction_count),
+            failed_transaction_count: U64::from(failed_transaction_count),
+            gas_used,
+            average_fee,
+            unique_senders: U64::from(senders.len() as u64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Address, U128};
+
+    use super::*;
+    use crate::client::helpers::create_default_transaction_receipt;
+
+    fn receipt(from: u64, gas_used: u64, status: u64) -> TransactionReceipt {
+        TransactionReceipt {
+            from: Address::from_low_u64_be(from),
+            gas_used: Some(U256::from(gas_used)),
+            effective_gas_price: U128::from(10),
+            status_code: Some(U64::from(status)),
+            ..create_default_transaction_receipt()
+        }
+    }
+
+    #[test]
+    fn test_block_stats_from_receipts() {
+        // Given
+        let receipts = vec![receipt(1, 21_000, 1), receipt(1, 50_000, 0), receipt(2, 30_000, 1)];
+
+        // When
+        let stats = BlockStats::from_receipts(7, H256::from_low_u64_be(0xabc), &receipts);
+
+        // Then
+        assert_eq!(U64::from(7), stats.number);
+        assert_eq!(U64::from(3), stats.transaction_count);
+        assert_eq!(U64::from(1), stats.failed_transaction_count);
+        assert_eq!(U256::from(101_000), stats.gas_used);
+        assert_eq!(U256::from(101_000 * 10 / 3), stats.average_fee);
+        assert_eq!(U64::from(2), stats.unique_senders);
+        assert_eq!(U256::ZERO, BlockStats::from_receipts(7, H256::zero(), &[]).average_fee);
+    }
+}
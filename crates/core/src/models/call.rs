@@ -1,10 +1,10 @@
 use std::slice::SliceIndex;
 
 use reth_primitives::TransactionSigned;
-use reth_rlp::Decodable;
 use starknet::accounts::Call as StarknetCall;
 use starknet_crypto::FieldElement;
 
+use super::conversions::felts_to_bytes;
 use super::ConversionError;
 use crate::models::DataDecodingError;
 
@@ -93,12 +93,10 @@ impl TryFrom<&Calls> for TransactionSigned {
             ));
         }
 
-        let call = value.0[0] // for now we decode signature only from the first call
-            .calldata
-            .iter()
-            .filter_map(|x| u8::try_from(*x).ok())
-            .collect::<Vec<u8>>();
-        TransactionSigned::decode(&mut call.as_slice())
+        // for now we decode signature only from the first call
+        let call = felts_to_bytes(&value.0[0].calldata)
+            .map_err(|e| DataDecodingError::SignatureDecodingError(e.to_string()))?;
+        TransactionSigned::decode_enveloped(call.into())
             .map_err(|e| DataDecodingError::SignatureDecodingError(e.to_string()))
     }
 }
@@ -0,0 +1,215 @@
+//! Conversions between the EVM word and byte types and Starknet field elements.
+//!
+//! An EVM word (`U256`) doesn't fit in a single field element: Kakarot stores it as two field
+//! elements holding its low and high 128 bits. EVM bytes are stored as one field element per byte.
+//! Every conversion which can lose information returns an error instead of silently truncating the
+//! value.
+use reth_primitives::{Bytes, U256};
+use starknet::core::types::FieldElement;
+
+use super::ConversionError;
+
+/// Splits a `U256` into its low and high 128 bits, in this order.
+pub fn u256_to_felts(value: U256) -> [FieldElement; 2] {
+    let bytes: [u8; 32] = value.to_be_bytes();
+    // Safe unwraps: both slices are 16 bytes long
+    let high = u128::from_be_bytes(bytes[..16].try_into().unwrap());
+    let low = u128::from_be_bytes(bytes[16..].try_into().unwrap());
+    [FieldElement::from(low), FieldElement::from(high)]
+}
+
+/// Joins the low and high 128 bits of a `U256`. Fails if one of the halves doesn't fit in 128
+/// bits, as the bits above 128 would otherwise be lost.
+pub fn felts_to_u256(low: FieldElement, high: FieldElement) -> Result<U256, ConversionError<()>> {
+    let low = felt_to_u128(low)?;
+    let high = felt_to_u128(high)?;
+    Ok(U256::from(high) << 128 | U256::from(low))
+}
+
+/// Converts a field element into a `U256`. Always succeeds since field elements are below 2**252.
+pub fn felt_to_u256(felt: FieldElement) -> U256 {
+    U256::from_be_bytes(felt.to_bytes_be())
+}
+
+/// Converts a `U256` into a single field element. Fails if the value is greater than or equal to
+/// the field prime.
+pub fn u256_to_felt(value: U256) -> Result<FieldElement, ConversionError<()>> {
+    FieldElement::from_bytes_be(&value.to_be_bytes())
+        .map_err(|_| ConversionError::Overflow { value: format!("{value:#x}"), target: "field element" })
+}
+
+/// Converts bytes into field elements, one field element per byte.
+pub fn bytes_to_felts(bytes: &[u8]) -> Vec<FieldElement> {
+    bytes.iter().copied().map(FieldElement::from).collect()
+}
+
+/// Converts field elements holding one byte each into bytes. Fails on the first field element
+/// which doesn't fit in a byte.
+pub fn felts_to_bytes(felts: &[FieldElement]) -> Result<Bytes, ConversionError<()>> {
+    let bytes = felts
+        .iter()
+        .map(|felt| {
+            u8::try_from(*felt).map_err(|_| ConversionError::Overflow { value: format!("{felt:#x}"), target: "u8" })
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+    Ok(Bytes::from(bytes))
+}
+
+/// Packs bytes into field elements holding 16 bytes each, big-endian. The last chunk is right
+/// padded with zeros.
+pub fn bytes_to_u128_felts(bytes: &[u8]) -> Vec<FieldElement> {
+    bytes
+        .chunks(16)
+        .map(|chunk| {
+            let mut word = [0u8; 16];
+            word[..chunk.len()].copy_from_slice(chunk);
+            FieldElement::from(u128::from_be_bytes(word))
+        })
+        .collect()
+}
+
+fn felt_to_u128(felt: FieldElement) -> Result<u128, ConversionError<()>> {
+    u128::try_from(felt).map_err(|_| ConversionError::Overflow { value: format!("{felt:#x}"), target: "u128" })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::*;
+
+    /// Deterministic xorshift generator, used to run the round trip properties on many values
+    /// without pulling a property testing framework.
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn next_u256(&mut self) -> U256 {
+            U256::from_limbs([self.next_u64(), self.next_u64(), self.next_u64(), self.next_u64()])
+        }
+    }
+
+    #[rstest]
+    #[case(U256::ZERO, [FieldElement::ZERO, FieldElement::ZERO])]
+    #[case(U256::from(u128::MAX), [FieldElement::from(u128::MAX), FieldElement::ZERO])]
+    #[case(U256::from(1) << 128, [FieldElement::ZERO, FieldElement::ONE])]
+    #[case(U256::MAX, [FieldElement::from(u128::MAX), FieldElement::from(u128::MAX)])]
+    fn test_u256_to_felts(#[case] value: U256, #[case] expected: [FieldElement; 2]) {
+        assert_eq!(expected, u256_to_felts(value));
+    }
+
+    #[test]
+    fn test_u256_felts_round_trip() {
+        // Given
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..1000 {
+            let value = rng.next_u256();
+
+            // When
+            let [low, high] = u256_to_felts(value);
+
+            // Then
+            assert_eq!(value, felts_to_u256(low, high).unwrap());
+        }
+    }
+
+    #[rstest]
+    #[case(FieldElement::from(u128::MAX) + FieldElement::ONE, FieldElement::ZERO)]
+    #[case(FieldElement::ZERO, FieldElement::from(u128::MAX) + FieldElement::ONE)]
+    #[case(FieldElement::MAX, FieldElement::MAX)]
+    fn test_felts_to_u256_should_fail_on_overflow(#[case] low: FieldElement, #[case] high: FieldElement) {
+        // When
+        let result = felts_to_u256(low, high);
+
+        // Then
+        assert!(matches!(result, Err(ConversionError::Overflow { target: "u128", .. })));
+    }
+
+    #[test]
+    fn test_felt_u256_round_trip() {
+        // Given
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..1000 {
+            // Clear the top bits to stay below the field prime
+            let value = rng.next_u256() >> 5;
+
+            // When
+            let felt = u256_to_felt(value).unwrap();
+
+            // Then
+            assert_eq!(value, felt_to_u256(felt));
+        }
+        let max = felt_to_u256(FieldElement::MAX);
+        assert_eq!(FieldElement::MAX, u256_to_felt(max).unwrap());
+    }
+
+    #[test]
+    fn test_u256_to_felt_should_fail_on_overflow() {
+        // Given
+        let prime = felt_to_u256(FieldElement::MAX) + U256::from(1);
+
+        // When
+        let prime_result = u256_to_felt(prime);
+        let max_result = u256_to_felt(U256::MAX);
+
+        // Then
+        assert!(matches!(prime_result, Err(ConversionError::Overflow { target: "field element", .. })));
+        assert!(matches!(max_result, Err(ConversionError::Overflow { target: "field element", .. })));
+    }
+
+    #[test]
+    fn test_bytes_felts_round_trip() {
+        // Given
+        let bytes: Vec<u8> = (0..=u8::MAX).collect();
+
+        // When
+        let felts = bytes_to_felts(&bytes);
+
+        // Then
+        assert_eq!(256, felts.len());
+        assert_eq!(Bytes::from(bytes), felts_to_bytes(&felts).unwrap());
+    }
+
+    #[test]
+    fn test_felts_to_bytes_should_fail_on_overflow() {
+        // Given
+        let felts = vec![FieldElement::ONE, FieldElement::from(256u64), FieldElement::from(2u64)];
+
+        // When
+        let result = felts_to_bytes(&felts);
+
+        // Then
+        assert!(matches!(result, Err(ConversionError::Overflow { target: "u8", .. })));
+    }
+
+    #[test]
+    fn test_felts_to_bytes_from_bytecode() {
+        // Given
+        let bytecode: Vec<FieldElement> =
+            serde_json::from_str(include_str!("test_data/bytecode/starknet/counter.json")).unwrap();
+
+        // When
+        let bytes = felts_to_bytes(&bytecode).unwrap();
+
+        // Then
+        let expected: Bytes = serde_json::from_str(include_str!("test_data/bytecode/eth/counter.json")).unwrap();
+        assert_eq!(expected, bytes);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![])]
+    #[case(vec![0xab], vec![FieldElement::from(0xab_u128 << 120)])]
+    #[case((1..=16).collect(), vec![FieldElement::from(0x0102030405060708090a0b0c0d0e0f10_u128)])]
+    #[case(vec![0xff; 17], vec![FieldElement::from(u128::MAX), FieldElement::from(0xff_u128 << 120)])]
+    fn test_bytes_to_u128_felts(#[case] bytes: Vec<u8>, #[case] expected: Vec<FieldElement>) {
+        assert_eq!(expected, bytes_to_u128_felts(&bytes));
+    }
+}
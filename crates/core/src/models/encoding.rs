@@ -0,0 +1,136 @@
+//! Hex encodings of the Ethereum JSON-RPC specification, shared by all the responses.
+//!
+//! A QUANTITY is a `0x` prefixed hex number without leading zeros, zero being `0x0`. DATA is a `0x`
+//! prefixed hex string of two digits per byte, the empty data being `0x`. Some strict clients
+//! reject quantities with leading zeros, so the responses must never be built with ad-hoc
+//! conversions.
+use reth_primitives::U256;
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Maximum number of digits of a QUANTITY, the size of a 256 bits word.
+const MAX_QUANTITY_DIGITS: usize = 64;
+
+/// Encodes the value as a QUANTITY.
+pub fn to_quantity(value: impl Into<U256>) -> String {
+    format!("{:#x}", value.into())
+}
+
+/// Encodes the bytes as DATA.
+pub fn to_data(bytes: impl AsRef<[u8]>) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Returns true if the string is a QUANTITY: `0x` prefixed, without leading zeros and at most 256
+/// bits.
+pub fn is_quantity(quantity: &str) -> bool {
+    quantity.strip_prefix("0x").is_some_and(|digits| {
+        (1..=MAX_QUANTITY_DIGITS).contains(&digits.len())
+            && (digits == "0" || !digits.starts_with('0'))
+            && digits.bytes().all(|digit| digit.is_ascii_hexdigit())
+    })
+}
+
+/// Returns true if the string is DATA, of the given number of bytes if any.
+pub fn is_data(data: &str, bytes: Option<usize>) -> bool {
+    data.strip_prefix("0x").is_some_and(|digits| {
+        digits.len() % 2 == 0
+            && bytes.map_or(true, |bytes| digits.len() == 2 * bytes)
+            && digits.bytes().all(|digit| digit.is_ascii_hexdigit())
+    })
+}
+
+/// Parses a QUANTITY, rejecting the non-canonical encodings.
+pub fn parse_quantity(quantity: &str) -> Option<U256> {
+    if !is_quantity(quantity) {
+        return None;
+    }
+    U256::from_str_radix(&quantity[2..], 16).ok()
+}
+
+/// (De)serializes a `u64` as a QUANTITY, to be used with `#[serde(with = "quantity")]`.
+pub mod quantity {
+    use serde::de::Error;
+
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_quantity(*value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let quantity = String::deserialize(deserializer)?;
+        parse_quantity(&quantity)
+            .and_then(|value| u64::try_from(value).ok())
+            .ok_or_else(|| D::Error::custom(format!("invalid quantity {quantity}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Bytes, U64};
+    use serde::Serialize;
+
+    use super::*;
+
+    // Encodings of geth's hexutil for the same values
+    const GETH_QUANTITIES: &[(u64, &str)] = &[
+        (0, "0x0"),
+        (1, "0x1"),
+        (15, "0xf"),
+        (16, "0x10"),
+        (255, "0xff"),
+        (1024, "0x400"),
+        (u64::MAX, "0xffffffffffffffff"),
+    ];
+    const GETH_DATA: &[(&[u8], &str)] = &[(&[], "0x"), (&[0], "0x00"), (&[0, 1], "0x0001"), (&[0xde, 0xad], "0xdead")];
+
+    #[test]
+    fn test_quantity_encoding_matches_geth() {
+        for (value, geth) in GETH_QUANTITIES {
+            assert_eq!(*geth, to_quantity(*value));
+            // The types of the responses encode the same way
+            assert_eq!(format!("\"{geth}\""), serde_json::to_string(&U256::from(*value)).unwrap());
+            assert_eq!(format!("\"{geth}\""), serde_json::to_string(&U64::from(*value)).unwrap());
+            assert_eq!(Some(U256::from(*value)), parse_quantity(geth));
+        }
+        assert_eq!(format!("0x{}", "f".repeat(64)), to_quantity(U256::MAX));
+    }
+
+    #[test]
+    fn test_data_encoding_matches_geth() {
+        for (bytes, geth) in GETH_DATA {
+            assert_eq!(*geth, to_data(bytes));
+            assert_eq!(format!("\"{geth}\""), serde_json::to_string(&Bytes::from(bytes.to_vec())).unwrap());
+            assert!(is_data(geth, Some(bytes.len())));
+        }
+        assert!(!is_data("0x0", None));
+        assert!(!is_data("dead", None));
+    }
+
+    #[test]
+    fn test_non_canonical_quantities_are_rejected() {
+        let too_large = format!("0x1{}", "0".repeat(64));
+        for quantity in ["0x", "0x00", "0x01", "1", "0X1", "0xg", too_large.as_str()] {
+            assert!(!is_quantity(quantity), "{quantity}");
+            assert_eq!(None, parse_quantity(quantity));
+        }
+    }
+
+    #[test]
+    fn test_quantity_serde() {
+        // Given
+        #[derive(Serialize, Deserialize)]
+        struct Progress {
+            #[serde(with = "quantity")]
+            block: u64,
+        }
+
+        // When
+        let encoded = serde_json::to_string(&Progress { block: 256 }).unwrap();
+
+        // Then
+        assert_eq!(r#"{"block":"0x100"}"#, encoded);
+        assert_eq!(256, serde_json::from_str::<Progress>(&encoded).unwrap().block);
+        assert!(serde_json::from_str::<Progress>(r#"{"block":"0x0100"}"#).is_err());
+    }
+}
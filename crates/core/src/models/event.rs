@@ -1,15 +1,11 @@
-use core::iter::once;
-
-use num_bigint::BigUint;
-use reth_primitives::{Address, Bytes, H256, U256};
+use reth_primitives::{H256, U256};
 use reth_rpc_types::Log;
 use starknet::core::types::Event;
 use starknet::providers::Provider;
 
-use super::felt::Felt252Wrapper;
+use super::kakarot_event::KakarotEvent;
 use crate::client::api::KakarotStarknetApi;
 use crate::client::errors::EthApiError;
-use crate::client::helpers::vec_felt_to_bytes;
 use crate::models::convertible::ConvertibleStarknetEvent;
 
 #[derive(Debug, Clone)]
@@ -42,47 +38,14 @@ impl ConvertibleStarknetEvent for StarknetEvent {
             return Err(EthApiError::KakarotDataFilteringError("Event".into()));
         }
 
-        // Derive the evm address from the first item in the `event.keys` vector and remove it
-        let (evm_contract_address, keys) =
-            self.0.keys.split_first().ok_or_else(|| EthApiError::KakarotDataFilteringError("Event".into()))?;
-
-        let address: Address = {
-            let felt_wrapper: Felt252Wrapper = (*evm_contract_address).into();
-            felt_wrapper.try_into()?
-        };
-
-        let topics: Vec<H256> = keys
-            .chunks(2)
-            .map(|chunk| {
-                let low = BigUint::from_bytes_be(&chunk[0].to_bytes_be());
-                let high = match chunk.get(1) {
-                    Some(h) => BigUint::from_bytes_be(&h.to_bytes_be()),
-                    None => {
-                        return Err(anyhow::anyhow!("Not a convertible event: High value doesn't exist",));
-                    }
-                };
-                let result = low + (BigUint::from(2u128).pow(128u32) * high);
-                // Converts the result to bytes.
-                let bytes = result.to_bytes_be();
-                // If the length of bytes is less than 32, prepends it with zeros to make it 32 bytes long.
-                let bytes = once(0u8).cycle().take(32 - bytes.len()).chain(bytes.into_iter()).collect::<Vec<_>>();
-                Ok(H256::from_slice(&bytes))
-            })
-            .collect::<Result<_, _>>()?;
-
-        let data: Bytes = vec_felt_to_bytes(self.0.data);
-
-        Ok(Log {
-            address,
-            topics,
-            data,
-            block_hash,
-            block_number,
-            transaction_hash,
-            log_index,
-            transaction_index,
-            removed: false,
-        })
+        match KakarotEvent::decode(&self.0.keys, &self.0.data)? {
+            KakarotEvent::EvmLog(log) => {
+                Ok(log.into_log(block_hash, block_number, transaction_hash, log_index, transaction_index))
+            }
+            KakarotEvent::EvmContractDeployed(_) => {
+                Err(EthApiError::ConversionError("evm_contract_deployed event is not a log".into()))
+            }
+        }
     }
 }
 
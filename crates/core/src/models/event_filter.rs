@@ -5,10 +5,10 @@ use starknet::providers::Provider;
 use starknet_crypto::FieldElement;
 
 use super::block::EthBlockNumberOrTag;
+use super::conversions::u256_to_felts;
 use super::felt::Felt252Wrapper;
 use crate::client::api::KakarotStarknetApi;
 use crate::client::errors::EthApiError;
-use crate::client::helpers::split_u256_into_field_elements;
 use crate::client::KakarotClient;
 
 pub struct EthEventFilter(Filter);
@@ -41,7 +41,7 @@ impl EthEventFilter {
                     None => vec![],
                     Some(topic) => {
                         let topic = U256::from_be_bytes(topic.to_fixed_bytes());
-                        split_u256_into_field_elements(topic).to_vec()
+                        u256_to_felts(topic).to_vec()
                     }
                 },
                 ValueOrArray::Array(topics) => topics
@@ -49,7 +49,7 @@ impl EthEventFilter {
                     .filter_map(|topic| {
                         topic.map(|topic| {
                             let topic = U256::from_be_bytes(topic.to_fixed_bytes());
-                            split_u256_into_field_elements(topic).to_vec()
+                            u256_to_felts(topic).to_vec()
                         })
                     })
                     .flatten()
@@ -0,0 +1,176 @@
+//! Fee history of the Kakarot blocks, returned by `eth_feeHistory`.
+//!
+//! Starknet has no priority fee: the transactions pay the gas price of their block, which is
+//! reported as the base fee per gas of the block, and the rewards are always zero. The gas used by
+//! a block is the total fee paid by its transactions divided by its gas price.
+use reth_primitives::U256;
+use reth_rpc_types::FeeHistory;
+use starknet::core::types::{
+    FieldElement, MaybePendingTransactionReceipt, PendingTransactionReceipt, TransactionReceipt,
+};
+
+use crate::client::constants::MAX_FEE_HISTORY_BLOCK_COUNT;
+
+/// Fees paid in a block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockFees {
+    /// Gas price of the block, in wei.
+    pub gas_price: U256,
+    /// Total fee paid by the transactions of the block, in wei.
+    pub total_fee: U256,
+}
+
+impl BlockFees {
+    /// Returns the gas used by the transactions of the block.
+    pub fn gas_used(&self) -> U256 {
+        if self.gas_price == U256::ZERO {
+            return U256::ZERO;
+        }
+        self.total_fee / self.gas_price
+    }
+
+    /// Returns the ratio of the gas used to the gas limit of the block, at most 1.
+    pub fn gas_used_ratio(&self, gas_limit: U256) -> f64 {
+        if gas_limit == U256::ZERO {
+            return 0.;
+        }
+        let gas_used = u128::try_from(self.gas_used().min(gas_limit)).unwrap_or(u128::MAX);
+        let gas_limit = u128::try_from(gas_limit).unwrap_or(u128::MAX);
+        gas_used as f64 / gas_limit as f64
+    }
+}
+
+/// Returns the fee paid by the transaction of the receipt.
+pub fn actual_fee(receipt: &MaybePendingTransactionReceipt) -> FieldElement {
+    match receipt {
+        MaybePendingTransactionReceipt::Receipt(receipt) => match receipt {
+            TransactionReceipt::Invoke(receipt) => receipt.actual_fee,
+            TransactionReceipt::L1Handler(receipt) => receipt.actual_fee,
+            TransactionReceipt::Declare(receipt) => receipt.actual_fee,
+            TransactionReceipt::Deploy(receipt) => receipt.actual_fee,
+            TransactionReceipt::DeployAccount(receipt) => receipt.actual_fee,
+        },
+        MaybePendingTransactionReceipt::PendingReceipt(receipt) => match receipt {
+            PendingTransactionReceipt::Invoke(receipt) => receipt.actual_fee,
+            PendingTransactionReceipt::L1Handler(receipt) => receipt.actual_fee,
+            PendingTransactionReceipt::Declare(receipt) => receipt.actual_fee,
+            PendingTransactionReceipt::Deploy(receipt) => receipt.actual_fee,
+            PendingTransactionReceipt::DeployAccount(receipt) => receipt.actual_fee,
+        },
+    }
+}
+
+/// Checks that the reward percentiles are between 0 and 100 and in increasing order.
+pub fn validate_reward_percentiles(percentiles: &[f64]) -> Result<(), String> {
+    let mut previous = 0.;
+    for percentile in percentiles {
+        if !(0. ..=100.).contains(percentile) {
+            return Err(format!("reward percentile {percentile} is not between 0 and 100"));
+        }
+        if *percentile < previous {
+            return Err(format!("reward percentiles are not increasing: {previous} > {percentile}"));
+        }
+        previous = *percentile;
+    }
+    Ok(())
+}
+
+/// Returns the oldest block and the number of blocks of a fee history ending at the newest block.
+/// The newest block is clamped to the latest block, and the range to the blocks from the first
+/// block on and to `MAX_FEE_HISTORY_BLOCK_COUNT` blocks. Returns `None` if the range is empty.
+pub fn fee_history_range(
+    block_count: u64,
+    newest_block: u64,
+    first_block: u64,
+    latest_block: u64,
+) -> Option<(u64, u64)> {
+    let newest_block = newest_block.min(latest_block);
+    let available_blocks = newest_block.saturating_add(1).saturating_sub(first_block);
+    let block_count = block_count.min(available_blocks).min(MAX_FEE_HISTORY_BLOCK_COUNT);
+    if block_count == 0 {
+        return None;
+    }
+    // The block count is at most the number of blocks up to the newest one
+    Some((newest_block - (block_count - 1), block_count))
+}
+
+/// Builds the fee history of the blocks starting at the oldest block. The base fee of the block
+/// following the newest one is the gas price of the newest block, Starknet having no base fee
+/// adjustment.
+pub fn build_fee_history(
+    oldest_block: u64,
+    blocks: &[BlockFees],
+    gas_limit: U256,
+    reward_percentiles: Option<&[f64]>,
+) -> FeeHistory {
+    let mut base_fee_per_gas: Vec<U256> = blocks.iter().map(|block| block.gas_price).collect();
+    base_fee_per_gas.push(blocks.last().map(|block| block.gas_price).unwrap_or_default());
+
+    FeeHistory {
+        base_fee_per_gas,
+        gas_used_ratio: blocks.iter().map(|block| block.gas_used_ratio(gas_limit)).collect(),
+        oldest_block: U256::from(oldest_block),
+        reward: reward_percentiles.map(|percentiles| vec![vec![U256::ZERO; percentiles.len()]; blocks.len()]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LATEST_BLOCK: u64 = u64::MAX;
+
+    #[test]
+    fn test_fee_history_range() {
+        assert_eq!(Some((19631, 10)), fee_history_range(10, 19640, 0, LATEST_BLOCK));
+        assert_eq!(Some((0, 2)), fee_history_range(10, 1, 0, LATEST_BLOCK));
+        assert_eq!(
+            Some((19640 + 1 - MAX_FEE_HISTORY_BLOCK_COUNT, MAX_FEE_HISTORY_BLOCK_COUNT)),
+            fee_history_range(100_000, 19640, 0, LATEST_BLOCK)
+        );
+        assert_eq!(Some((100, 1)), fee_history_range(10, 100, 100, LATEST_BLOCK));
+        assert_eq!(None, fee_history_range(10, 99, 100, LATEST_BLOCK));
+        assert_eq!(None, fee_history_range(0, 19640, 0, LATEST_BLOCK));
+    }
+
+    #[test]
+    fn test_fee_history_range_past_the_latest_block() {
+        assert_eq!(Some((19631, 10)), fee_history_range(10, 19650, 0, 19640));
+        assert_eq!(Some((19631, 10)), fee_history_range(10, u64::MAX, 0, 19640));
+        assert_eq!(Some((0, 1)), fee_history_range(10, u64::MAX, 0, 0));
+        assert_eq!(None, fee_history_range(10, u64::MAX, 100, 99));
+    }
+
+    #[test]
+    fn test_build_fee_history() {
+        // Given
+        let blocks = [
+            BlockFees { gas_price: U256::from(10), total_fee: U256::from(2_500) },
+            BlockFees { gas_price: U256::from(20), total_fee: U256::from(100_000) },
+            BlockFees { gas_price: U256::ZERO, total_fee: U256::ZERO },
+            BlockFees { gas_price: U256::from(30), total_fee: U256::ZERO },
+        ];
+
+        // When
+        let fee_history = build_fee_history(7, &blocks, U256::from(1_000), Some(&[25., 75.]));
+
+        // Then
+        assert_eq!(U256::from(7), fee_history.oldest_block);
+        assert_eq!(
+            vec![U256::from(10), U256::from(20), U256::ZERO, U256::from(30), U256::from(30)],
+            fee_history.base_fee_per_gas
+        );
+        assert_eq!(vec![0.25, 1., 0., 0.], fee_history.gas_used_ratio);
+        assert_eq!(Some(vec![vec![U256::ZERO; 2]; 4]), fee_history.reward);
+        assert_eq!(None, build_fee_history(7, &blocks, U256::from(1_000), None).reward);
+    }
+
+    #[test]
+    fn test_validate_reward_percentiles() {
+        assert!(validate_reward_percentiles(&[]).is_ok());
+        assert!(validate_reward_percentiles(&[0., 50., 50., 100.]).is_ok());
+        assert!(validate_reward_percentiles(&[50., 10.]).is_err());
+        assert!(validate_reward_percentiles(&[101.]).is_err());
+        assert!(validate_reward_percentiles(&[-1.]).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Typed events emitted by Kakarot, decoded from the keys and the data of the Starknet events.
+//!
+//! Kakarot emits two kinds of events:
+//! - `evm_contract_deployed`, keyed by its selector, when an EVM account is deployed, with the data
+//!   `[evm_address, starknet_address]`.
+//! - the logs of the EVM, keyed by the address of the emitting contract followed by the topics
+//!   split in `(low, high)` pairs of felts, the data holding one byte per felt.
+//!
+//! The first key tells them apart: the selector doesn't fit in an EVM address.
+use reth_primitives::{Address, Bytes, H256, U256};
+use reth_rpc_types::Log;
+use starknet::core::types::FieldElement;
+
+use super::conversions::{felts_to_bytes, felts_to_u256};
+use super::felt::Felt252Wrapper;
+use super::ConversionError;
+use crate::client::constants::selectors::EVM_CONTRACT_DEPLOYED;
+use crate::client::helpers::DataDecodingError;
+
+/// Event emitted by Kakarot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KakarotEvent {
+    EvmContractDeployed(EvmContractDeployed),
+    EvmLog(EvmLog),
+}
+
+impl KakarotEvent {
+    /// Decodes the event from its keys and data. The address of the emitting contract isn't
+    /// checked, the caller only decodes the events emitted by Kakarot.
+    pub fn decode(keys: &[FieldElement], data: &[FieldElement]) -> Result<Self, ConversionError<()>> {
+        match keys.first() {
+            Some(selector) if *selector == EVM_CONTRACT_DEPLOYED => {
+                EvmContractDeployed::decode(data).map(Self::EvmContractDeployed)
+            }
+            _ => EvmLog::decode(keys, data).map(Self::EvmLog),
+        }
+    }
+}
+
+/// `evm_contract_deployed` event, emitted when Kakarot deploys an EOA or a contract account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvmContractDeployed {
+    pub evm_address: Address,
+    pub starknet_address: FieldElement,
+}
+
+impl EvmContractDeployed {
+    fn decode(data: &[FieldElement]) -> Result<Self, ConversionError<()>> {
+        let [evm_address, starknet_address, ..] = data else {
+            return Err(invalid_event("evm_contract_deployed", format!("expected 2 data felts, got {}", data.len())));
+        };
+        Ok(Self { evm_address: Felt252Wrapper::from(*evm_address).try_into()?, starknet_address: *starknet_address })
+    }
+}
+
+/// Log emitted by an EVM contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvmLog {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+}
+
+impl EvmLog {
+    fn decode(keys: &[FieldElement], data: &[FieldElement]) -> Result<Self, ConversionError<()>> {
+        let (address, topic_keys) = keys.split_first().ok_or_else(|| invalid_event("log", "no keys".to_string()))?;
+        let address = Felt252Wrapper::from(*address).try_into()?;
+        if topic_keys.len() % 2 != 0 {
+            return Err(invalid_event("log", format!("odd number of topic keys {}", topic_keys.len())));
+        }
+        let topics = topic_keys
+            .chunks_exact(2)
+            .map(|chunk| Ok(H256::from(felts_to_u256(chunk[0], chunk[1])?.to_be_bytes::<32>())))
+            .collect::<Result<_, ConversionError<()>>>()?;
+
+        Ok(Self { address, topics, data: felts_to_bytes(data)? })
+    }
+
+    /// Returns the Ethereum log, located in the chain by the optional fields.
+    pub fn into_log(
+        self,
+        block_hash: Option<H256>,
+        block_number: Option<U256>,
+        transaction_hash: Option<H256>,
+        log_index: Option<U256>,
+        transaction_index: Option<U256>,
+    ) -> Log {
+        Log {
+            address: self.address,
+            topics: self.topics,
+            data: self.data,
+            block_hash,
+            block_number,
+            transaction_hash,
+            log_index,
+            transaction_index,
+            removed: false,
+        }
+    }
+}
+
+fn invalid_event(event: &str, message: String) -> ConversionError<()> {
+    DataDecodingError::InvalidEventData { event: event.to_string(), message }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet::core::types::Event;
+    use starknet::macros::felt;
+
+    use super::*;
+
+    fn decode_json(event: &str) -> Result<KakarotEvent, ConversionError<()>> {
+        let event: Event = serde_json::from_str(event).unwrap();
+        KakarotEvent::decode(&event.keys, &event.data)
+    }
+
+    #[test]
+    fn test_decode_evm_contract_deployed() {
+        // When
+        let event = KakarotEvent::decode(&[EVM_CONTRACT_DEPLOYED], &[felt!("0xabde1"), felt!("0x1234")]).unwrap();
+
+        // Then
+        let expected =
+            EvmContractDeployed { evm_address: Address::from_low_u64_be(0xabde1), starknet_address: felt!("0x1234") };
+        assert_eq!(KakarotEvent::EvmContractDeployed(expected), event);
+    }
+
+    #[test]
+    fn test_decode_evm_contract_deployed_should_fail_on_missing_data() {
+        // When
+        let err = KakarotEvent::decode(&[EVM_CONTRACT_DEPLOYED], &[felt!("0xabde1")]).unwrap_err();
+
+        // Then
+        assert_eq!("failed to decode the evm_contract_deployed event: expected 2 data felts, got 1", err.to_string());
+    }
+
+    #[test]
+    fn test_decode_evm_log() {
+        // When
+        let event = decode_json(include_str!("test_data/conversion/starknet/event_log3.json")).unwrap();
+
+        // Then
+        let KakarotEvent::EvmLog(log) = event else { panic!("expected a log, got {event:?}") };
+        let expected: Log = serde_json::from_str(include_str!("test_data/conversion/eth/event_log3.json")).unwrap();
+        assert_eq!(expected, log.into_log(None, None, None, None, None));
+    }
+
+    #[test]
+    fn test_decode_evm_log_without_topics() {
+        // When
+        let event = KakarotEvent::decode(&[felt!("0xabc")], &[felt!("0x1"), felt!("0xff")]).unwrap();
+
+        // Then
+        let expected =
+            EvmLog { address: Address::from_low_u64_be(0xabc), topics: vec![], data: Bytes::from(vec![0x1, 0xff]) };
+        assert_eq!(KakarotEvent::EvmLog(expected), event);
+    }
+
+    #[test]
+    fn test_decode_evm_log_should_fail_on_invalid_keys() {
+        // An address key overflowing an EVM address
+        let err = decode_json(include_str!("test_data/conversion/starknet/event_invalid_key.json")).unwrap_err();
+        assert!(matches!(err, ConversionError::ToEthereumAddressError));
+
+        // A topic without its high part
+        let err = KakarotEvent::decode(&[felt!("0xabc"), felt!("0x1")], &[]).unwrap_err();
+        assert_eq!("failed to decode the log event: odd number of topic keys 1", err.to_string());
+
+        // No keys at all
+        let err = KakarotEvent::decode(&[], &[]).unwrap_err();
+        assert_eq!("failed to decode the log event: no keys", err.to_string());
+    }
+
+    #[test]
+    fn test_decode_evm_log_should_fail_on_data_overflowing_a_byte() {
+        // When
+        let err = KakarotEvent::decode(&[felt!("0xabc")], &[felt!("0x100")]).unwrap_err();
+
+        // Then
+        assert!(matches!(err, ConversionError::Overflow { target: "u8", .. }));
+    }
+}
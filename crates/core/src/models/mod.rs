@@ -1,14 +1,27 @@
+pub mod account;
+pub mod account_activity;
 pub mod balance;
 pub mod block;
+pub mod block_stats;
 pub mod call;
+pub mod conversions;
 pub mod convertible;
+pub mod encoding;
 pub mod event;
 pub mod event_filter;
+pub mod fee_history;
 pub mod felt;
+pub mod kakarot_event;
+pub mod receipt;
+pub mod revert;
 pub mod signature;
+pub mod simulation;
+pub mod state_override;
 #[cfg(test)]
 pub mod tests;
+pub mod trace;
 pub mod transaction;
+pub mod txpool;
 
 use ruint::FromUintError;
 use starknet::core::types::FromByteArrayError;
@@ -34,6 +47,9 @@ pub enum ConversionError<T> {
          address"
     )]
     ToEthereumAddressError,
+    /// Value overflowing the target type, which would otherwise be truncated
+    #[error("value {value} overflows {target}")]
+    Overflow { value: String, target: &'static str },
     /// Value out of range error
     #[error("value out of range: {0}")]
     ValueOutOfRange(String),
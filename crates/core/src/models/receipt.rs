@@ -0,0 +1,187 @@
+//! Conversion of the receipts of a block, in a single pass over its Kakarot transactions.
+//!
+//! The fields of a receipt which depend on the other transactions of the block, the index of the
+//! transaction, the cumulative gas used and the indexes of the logs, are only known when the
+//! receipts are converted in the order of the block. The block hash, the block number and the gas
+//! price are shared by all the receipts of the block and fetched once.
+use reth_primitives::{H256, U128, U256, U64, U8};
+use reth_rpc_types::{Log, Transaction as EthTransaction, TransactionReceipt};
+use starknet::core::types::{InvokeTransactionReceipt, TransactionStatus as StarknetTransactionStatus};
+use starknet::providers::Provider;
+
+use super::fee_history::BlockFees;
+use super::felt::Felt252Wrapper;
+use super::kakarot_event::KakarotEvent;
+use crate::client::api::KakarotStarknetApi;
+use crate::client::errors::EthApiError;
+use crate::models::block::logs_bloom;
+
+/// Context shared by the receipts of a block, accumulated as they are converted in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockReceiptContext {
+    block_hash: H256,
+    block_number: U256,
+    /// Gas price of the block, the gas used by a transaction being the fee it paid divided by it.
+    gas_price: U256,
+    cumulative_gas_used: U256,
+    next_log_index: u64,
+    next_transaction_index: u64,
+}
+
+impl BlockReceiptContext {
+    pub fn new(block_hash: H256, block_number: u64, gas_price: U256) -> Self {
+        Self {
+            block_hash,
+            block_number: U256::from(block_number),
+            gas_price,
+            cumulative_gas_used: U256::ZERO,
+            next_log_index: 0,
+            next_transaction_index: 0,
+        }
+    }
+
+    /// Converts the receipt of the next Kakarot transaction of the block.
+    pub fn receipt<P: Provider + Send + Sync>(
+        &mut self,
+        client: &dyn KakarotStarknetApi<P>,
+        transaction: &EthTransaction,
+        receipt: InvokeTransactionReceipt,
+    ) -> Result<TransactionReceipt, EthApiError<P::Error>> {
+        let transaction_hash = Some(transaction.hash);
+        let transaction_index = Some(U256::from(self.next_transaction_index));
+        let (block_hash, block_number) = (Some(self.block_hash), Some(self.block_number));
+
+        let status_code = match receipt.status {
+            StarknetTransactionStatus::Rejected | StarknetTransactionStatus::Pending => Some(U64::from(0)),
+            StarknetTransactionStatus::AcceptedOnL1 | StarknetTransactionStatus::AcceptedOnL2 => Some(U64::from(1)),
+        };
+
+        let mut deployed = None;
+        let mut logs: Vec<Log> = Vec::new();
+        for event in receipt.events {
+            let from_kakarot = event.from_address == client.kakarot_address();
+            match KakarotEvent::decode(&event.keys, &event.data) {
+                Ok(KakarotEvent::EvmContractDeployed(event)) => {
+                    deployed.get_or_insert(event);
+                }
+                // The events which aren't emitted through Kakarot aren't logs and don't take an index
+                Ok(KakarotEvent::EvmLog(log)) if from_kakarot => {
+                    let log_index = Some(U256::from(self.next_log_index));
+                    logs.push(log.into_log(block_hash, block_number, transaction_hash, log_index, transaction_index));
+                    self.next_log_index += 1;
+                }
+                _ => {}
+            }
+        }
+
+        let contract_address = match transaction.to {
+            Some(_) => None,
+            // A contract creation emits the address of the deployed contract
+            None => Some(
+                deployed
+                    .ok_or(EthApiError::Other(anyhow::anyhow!(
+                        "Kakarot Core: No contract deployment event found in Kakarot transaction receipt"
+                    )))?
+                    .evm_address,
+            ),
+        };
+
+        let fee: Felt252Wrapper = receipt.actual_fee.into();
+        let gas_used = BlockFees { gas_price: self.gas_price, total_fee: fee.into() }.gas_used();
+        self.cumulative_gas_used = self.cumulative_gas_used.saturating_add(gas_used);
+        self.next_transaction_index += 1;
+
+        let transaction_type = transaction.transaction_type.map(|transaction_type| transaction_type.to::<u8>());
+        Ok(TransactionReceipt {
+            transaction_hash,
+            transaction_index,
+            block_hash,
+            block_number,
+            from: transaction.from,
+            to: transaction.to,
+            cumulative_gas_used: self.cumulative_gas_used,
+            gas_used: Some(gas_used),
+            contract_address,
+            logs_bloom: logs_bloom(&logs),
+            logs,
+            state_root: None,
+            status_code,
+            effective_gas_price: U128::from(u128::try_from(self.gas_price).unwrap_or(u128::MAX)),
+            transaction_type: U8::from(transaction_type.unwrap_or_default()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Address, Bytes};
+    use serde_json::json;
+
+    use super::*;
+    use crate::mock::mock_starknet::{fixtures, init_mock_client};
+
+    fn invoke_receipt(actual_fee: &str, events: serde_json::Value) -> InvokeTransactionReceipt {
+        serde_json::from_value(json!({
+            "transaction_hash": "0x01",
+            "actual_fee": actual_fee,
+            "status": "ACCEPTED_ON_L2",
+            "block_hash": "0x0d",
+            "block_number": 13,
+            "messages_sent": [],
+            "events": events,
+        }))
+        .unwrap()
+    }
+
+    fn kakarot_event() -> serde_json::Value {
+        serde_json::from_str(include_str!("test_data/conversion/starknet/event_log3.json")).unwrap()
+    }
+
+    #[test]
+    fn test_block_receipts_share_the_block_context() {
+        // Given
+        let client = init_mock_client(Some(fixtures(vec![])));
+        let mut context = BlockReceiptContext::new(H256::from_low_u64_be(0x0d), 13, U256::from(10));
+        let transaction = EthTransaction {
+            hash: H256::from_low_u64_be(1),
+            nonce: U256::ZERO,
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+            from: Address::from_low_u64_be(0xdef),
+            to: Some(Address::from_low_u64_be(0xabc)),
+            value: U256::ZERO,
+            gas_price: Some(U128::from(10)),
+            gas: U256::from(21_000),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            input: Bytes::default(),
+            signature: None,
+            chain_id: None,
+            access_list: None,
+            transaction_type: None,
+        };
+        let foreign_event = json!({ "from_address": "0x0123", "keys": ["0x01"], "data": [] });
+
+        // When
+        let first = context
+            .receipt(&client, &transaction, invoke_receipt("0x64", json!([kakarot_event(), foreign_event])))
+            .unwrap();
+        let second = context
+            .receipt(&client, &transaction, invoke_receipt("0xc8", json!([kakarot_event(), kakarot_event()])))
+            .unwrap();
+
+        // Then
+        assert_eq!(Some(U256::ZERO), first.transaction_index);
+        assert_eq!(Some(U256::from(1)), second.transaction_index);
+        assert_eq!(Some(U256::from(10)), first.gas_used);
+        assert_eq!(Some(U256::from(20)), second.gas_used);
+        assert_eq!(U256::from(10), first.cumulative_gas_used);
+        assert_eq!(U256::from(30), second.cumulative_gas_used);
+        assert_eq!(U128::from(10), second.effective_gas_price);
+        let log_indexes: Vec<_> =
+            first.logs.iter().chain(&second.logs).map(|log| log.log_index.unwrap().to::<u64>()).collect();
+        assert_eq!(vec![0, 1, 2], log_indexes);
+        assert_eq!(Some(U256::from(1)), second.logs[0].transaction_index);
+    }
+}
@@ -0,0 +1,122 @@
+//! Revert data of the reverted EVM executions.
+//!
+//! Kakarot fails a reverted execution with a Cairo error, `Kakarot: Reverted with reason:
+//! <reason>`, where the reason is either the hex encoded revert data of the EVM or a short string.
+//! The revert data is extracted from the error message and returned as the data of the JSON-RPC
+//! error, which ethers and viem decode as `Error(string)` or as a custom error.
+use reth_primitives::{Bytes, U256};
+
+/// Marker of the revert reason in the Cairo error of a reverted execution.
+const REVERT_REASON_MARKER: &str = "Reverted with reason: ";
+
+/// Selector of `Error(string)`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Extracts the revert data of the EVM from the error message of a reverted Kakarot execution.
+/// A reason given as a short string is encoded as `Error(string)`. Returns `None` if the message
+/// isn't a Kakarot revert.
+pub fn revert_data_from_error_message(message: &str) -> Option<Bytes> {
+    let (_, reason) = message.split_once(REVERT_REASON_MARKER)?;
+    // The message may be followed by the Cairo traceback, or be escaped in a JSON string
+    let reason = reason.split(['\n', '"', '\\']).next().unwrap_or_default().trim();
+
+    if let Some(digits) = reason.strip_prefix("0x") {
+        let digits = if digits.len() % 2 == 0 { digits.to_string() } else { format!("0{digits}") };
+        let data = hex::decode(digits).ok()?;
+        // ABI encoded revert data is a selector followed by 32 bytes words
+        if data.len() >= 4 && (data.len() - 4) % 32 == 0 {
+            return Some(data.into());
+        }
+        return Some(short_string(&data).map(|reason| encode_revert_reason(&reason)).unwrap_or_else(|| data.into()));
+    }
+    if !reason.is_empty() && reason.bytes().all(|digit| digit.is_ascii_digit()) {
+        let felt = U256::from_str_radix(reason, 10).ok()?.to_be_bytes::<32>();
+        return Some(encode_revert_reason(&short_string(&felt)?));
+    }
+    if reason.is_empty() {
+        return Some(Bytes::default());
+    }
+    Some(encode_revert_reason(reason))
+}
+
+/// Returns the printable ASCII string of the bytes of a Cairo short string, without its leading
+/// zeros.
+fn short_string(bytes: &[u8]) -> Option<String> {
+    let bytes: Vec<u8> = bytes.iter().copied().skip_while(|byte| *byte == 0).collect();
+    (!bytes.is_empty() && bytes.iter().all(|byte| byte.is_ascii_graphic() || *byte == b' '))
+        .then(|| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Returns the ABI encoding of `Error(reason)`.
+pub fn encode_revert_reason(reason: &str) -> Bytes {
+    let reason = reason.as_bytes();
+    let padded_length = (reason.len() + 31) / 32 * 32;
+
+    let mut data = ERROR_SELECTOR.to_vec();
+    data.extend(U256::from(32).to_be_bytes::<32>());
+    data.extend(U256::from(reason.len()).to_be_bytes::<32>());
+    data.extend(reason);
+    data.resize(4 + 64 + padded_length, 0);
+    data.into()
+}
+
+/// Returns the reason of revert data encoded as `Error(string)`, `None` for other revert data.
+pub fn decode_revert_reason(data: &[u8]) -> Option<String> {
+    let data = data.strip_prefix(&ERROR_SELECTOR)?;
+    let word = |offset: usize| -> Option<usize> {
+        let word = data.get(offset..offset.checked_add(32)?)?;
+        usize::try_from(U256::try_from_be_slice(word)?).ok()
+    };
+    let offset = word(0)?;
+    let length = word(offset)?;
+    let start = offset.checked_add(32)?;
+    let reason = data.get(start..start.checked_add(length)?)?;
+    String::from_utf8(reason.to_vec()).ok()
+}
+
+/// Returns the message of a reverted execution, as in geth.
+pub fn revert_message(data: &[u8]) -> String {
+    match decode_revert_reason(data) {
+        Some(reason) => format!("execution reverted: {reason}"),
+        None => "execution reverted".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revert_reason_encoding() {
+        // Given
+        let reason = "count should be strictly greater than 0";
+
+        // When
+        let data = encode_revert_reason(reason);
+
+        // Then
+        assert_eq!(4 + 32 + 32 + 64, data.len());
+        assert_eq!(Some(reason.to_string()), decode_revert_reason(&data));
+        assert_eq!("execution reverted: count should be strictly greater than 0", revert_message(&data));
+        assert_eq!("execution reverted", revert_message(&[0xde, 0xad, 0xbe, 0xef]));
+    }
+
+    #[test]
+    fn test_revert_data_from_error_message() {
+        // Given
+        let custom_error = "0xdeadbeef".to_string() + &"00".repeat(31) + "01";
+        let hex_message = format!("Error at pc=0:12:\nKakarot: Reverted with reason: {custom_error}\nCairo traceback");
+        // "Not owner" as a Cairo short string
+        let felt_message = "Error message: Kakarot: Reverted with reason: 1446877117447970645362";
+        let text_message = r#"{"message": "Kakarot: Reverted with reason: Not owner\n"}"#;
+
+        // Then
+        assert_eq!(
+            Some(Bytes::from(hex::decode(&custom_error[2..]).unwrap())),
+            revert_data_from_error_message(&hex_message)
+        );
+        assert_eq!(Some(encode_revert_reason("Not owner")), revert_data_from_error_message(felt_message));
+        assert_eq!(Some(encode_revert_reason("Not owner")), revert_data_from_error_message(text_message));
+        assert_eq!(None, revert_data_from_error_message("Contract not found"));
+    }
+}
@@ -0,0 +1,200 @@
+//! Simulation of Ethereum transactions, returned by `kakarot_simulateTransaction`.
+//!
+//! The transaction is simulated by the Starknet sequencer without being submitted. The Starknet
+//! simulation doesn't expose the storage writes of the transaction: the state diff is limited to
+//! the nonces, of the sender and of the contracts deployed by the transaction, and to the native
+//! token balances, read from the events of the simulation trace.
+use std::collections::BTreeMap;
+
+use reth_primitives::{Address, Bytes, U256};
+use reth_rpc_types::{CallRequest, Log};
+use serde::{Deserialize, Serialize};
+use starknet::core::types::{Event, FieldElement};
+use starknet::providers::sequencer::models::FunctionInvocation;
+
+use super::conversions::felts_to_u256;
+use super::kakarot_event::KakarotEvent;
+use crate::client::constants::selectors::TRANSFER;
+
+/// Transaction to simulate, either signed and RLP encoded or unsigned.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SimulationRequest {
+    Raw(Bytes),
+    Unsigned(CallRequest),
+}
+
+/// Value of the state before and after the transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Delta<T> {
+    pub from: T,
+    pub to: T,
+}
+
+/// Changes of the state of an account made by the transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountDiff {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<Delta<U256>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Delta<U256>>,
+}
+
+/// Result of the simulation of a transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionSimulation {
+    pub gas_used: U256,
+    /// Data returned by the EVM execution.
+    pub return_data: Bytes,
+    pub logs: Vec<Log>,
+    /// Changes of the state by account, limited to the nonces and the native token balances.
+    pub state_diff: BTreeMap<Address, AccountDiff>,
+}
+
+/// Native token received and sent by a Starknet address during a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenFlow {
+    pub received: U256,
+    pub sent: U256,
+}
+
+impl TokenFlow {
+    /// Returns the balance after the transaction, given the balance before it.
+    pub fn apply(&self, balance: U256) -> U256 {
+        balance.saturating_add(self.received).saturating_sub(self.sent)
+    }
+}
+
+/// Returns the invocation of the given entrypoint of the given contract in the call tree of a
+/// simulated invocation.
+pub fn find_simulated_invocation(
+    invocation: &FunctionInvocation,
+    contract_address: FieldElement,
+    selector: FieldElement,
+) -> Option<&FunctionInvocation> {
+    if invocation.contract_address == contract_address && invocation.selector == Some(selector) {
+        return Some(invocation);
+    }
+    invocation.internal_calls.iter().find_map(|call| find_simulated_invocation(call, contract_address, selector))
+}
+
+/// Returns the events emitted in the call tree of a simulated invocation, in order of emission.
+pub fn simulated_events(invocation: &FunctionInvocation) -> Vec<Event> {
+    let mut events = vec![];
+    collect_events(invocation, &mut events);
+    events.sort_by_key(|(order, _)| *order);
+    events.into_iter().map(|(_, event)| event).collect()
+}
+
+fn collect_events(invocation: &FunctionInvocation, events: &mut Vec<(u64, Event)>) {
+    events.extend(invocation.events.iter().map(|event| {
+        let from_address = invocation.contract_address;
+        (event.order, Event { from_address, keys: event.keys.clone(), data: event.data.clone() })
+    }));
+    for call in &invocation.internal_calls {
+        collect_events(call, events);
+    }
+}
+
+/// Returns the changes of the nonces made by the simulated transaction of the sender, sent with
+/// the nonce: the nonce of the sender is incremented, and the contracts deployed by Kakarot during
+/// the transaction, found from their `evm_contract_deployed` events, start at nonce 1.
+pub fn nonce_changes(
+    sender: Address,
+    nonce: U256,
+    events: &[Event],
+    kakarot_address: FieldElement,
+) -> BTreeMap<Address, Delta<U256>> {
+    let mut changes = BTreeMap::from([(sender, Delta { from: nonce, to: nonce + U256::from(1) })]);
+    for event in events.iter().filter(|event| event.from_address == kakarot_address) {
+        if let Ok(KakarotEvent::EvmContractDeployed(deployed)) = KakarotEvent::decode(&event.keys, &event.data) {
+            changes.entry(deployed.evm_address).or_insert(Delta { from: U256::ZERO, to: U256::from(1) });
+        }
+    }
+    changes
+}
+
+/// Returns the native token received and sent by each Starknet address, from the `Transfer`
+/// events `[from, to, amount_low, amount_high]` of the native token.
+pub fn native_token_flows(events: &[Event], native_token: FieldElement) -> BTreeMap<FieldElement, TokenFlow> {
+    let mut flows: BTreeMap<FieldElement, TokenFlow> = BTreeMap::new();
+    let transfers =
+        events.iter().filter(|event| event.from_address == native_token && event.keys.first() == Some(&TRANSFER));
+    for transfer in transfers {
+        let (sender, recipient, amount) = match transfer.data.as_slice() {
+            [sender, recipient, low, high, ..] => match felts_to_u256(*low, *high) {
+                Ok(amount) => (*sender, *recipient, amount),
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        let sent = &mut flows.entry(sender).or_default().sent;
+        *sent = sent.saturating_add(amount);
+        let received = &mut flows.entry(recipient).or_default().received;
+        *received = received.saturating_add(amount);
+    }
+    flows
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet::macros::felt;
+
+    use super::*;
+    use crate::client::constants::selectors::EVM_CONTRACT_DEPLOYED;
+
+    fn transfer(token: FieldElement, sender: FieldElement, recipient: FieldElement, amount: u64) -> Event {
+        Event {
+            from_address: token,
+            keys: vec![TRANSFER],
+            data: vec![sender, recipient, amount.into(), FieldElement::ZERO],
+        }
+    }
+
+    #[test]
+    fn test_native_token_flows() {
+        // Given
+        let token = felt!("0x1");
+        let (alice, bob) = (felt!("0xa"), felt!("0xb"));
+        let events = vec![
+            transfer(token, alice, bob, 100),
+            transfer(token, bob, alice, 30),
+            transfer(felt!("0x2"), alice, bob, 1_000),
+        ];
+
+        // When
+        let flows = native_token_flows(&events, token);
+
+        // Then
+        assert_eq!(2, flows.len());
+        assert_eq!(U256::from(930), flows[&alice].apply(U256::from(1_000)));
+        assert_eq!(U256::from(70), flows[&bob].apply(U256::ZERO));
+    }
+
+    #[test]
+    fn test_nonce_changes() {
+        // Given
+        let kakarot = felt!("0x1");
+        let (sender, deployed) = (Address::from_low_u64_be(0xa), Address::from_low_u64_be(0xc));
+        let deployment = |from_address| Event {
+            from_address,
+            keys: vec![EVM_CONTRACT_DEPLOYED],
+            data: vec![felt!("0xc"), felt!("0x5c")],
+        };
+        let events = vec![deployment(kakarot), deployment(felt!("0x2"))];
+
+        // When
+        let changes = nonce_changes(sender, U256::from(3), &events, kakarot);
+
+        // Then
+        assert_eq!(
+            BTreeMap::from([
+                (sender, Delta { from: U256::from(3), to: U256::from(4) }),
+                (deployed, Delta { from: U256::ZERO, to: U256::from(1) }),
+            ]),
+            changes
+        );
+    }
+}
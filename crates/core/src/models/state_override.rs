@@ -0,0 +1,285 @@
+//! State override set of `eth_call`, the optional third parameter of geth replacing the balance,
+//! nonce, code or storage of accounts for the duration of a call.
+//!
+//! Starknet calls can't be executed against a modified state, the overrides are translated into
+//! writes to the Starknet storage of the Kakarot accounts, which a backend able to write the state
+//! of the sequencer applies before the call and reverts after it.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use reth_primitives::{Address, Bytes, H256, U256, U64};
+use serde::{Deserialize, Serialize};
+use starknet::core::types::FieldElement;
+use starknet::core::utils::get_storage_var_address;
+use tokio::sync::OwnedRwLockWriteGuard;
+
+use super::conversions::{bytes_to_u128_felts, u256_to_felts};
+use crate::client::api::StateOverrideBackend;
+
+/// Overrides of the accounts, keyed by their address.
+pub type StateOverride = HashMap<Address, AccountOverride>;
+
+/// Overrides of an account. `state` replaces the whole storage of the account while `stateDiff`
+/// only replaces the given slots, they can't be set together.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<HashMap<H256, H256>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<HashMap<H256, H256>>,
+}
+
+/// Write to the Starknet state applying an override.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StarknetStateWrite {
+    /// Write of a storage slot of a Starknet contract.
+    Storage { contract_address: FieldElement, key: FieldElement, value: FieldElement },
+    /// Write of the nonce of a Starknet contract.
+    Nonce { contract_address: FieldElement, nonce: FieldElement },
+}
+
+/// Returns the writes to the Starknet state applying the overrides of the account deployed at
+/// `starknet_address`:
+/// - the balance is written to the balance of the account in the `native_token`,
+/// - the nonce is written to the nonce of the Starknet contract,
+/// - the code is written to the bytecode of the Kakarot contract account,
+/// - the slots of `stateDiff` are written to the storage of the Kakarot contract account.
+///
+/// Fails for a full storage replacement, as the slots of the account already written can't be
+/// listed from its Starknet storage.
+pub fn account_override_writes(
+    starknet_address: FieldElement,
+    native_token: FieldElement,
+    account_override: &AccountOverride,
+) -> Result<Vec<StarknetStateWrite>, String> {
+    if account_override.state.is_some() {
+        if account_override.state_diff.is_some() {
+            return Err("account override has both state and stateDiff".to_string());
+        }
+        return Err("full storage replacement (state) is not supported, use stateDiff".to_string());
+    }
+
+    let mut writes = Vec::new();
+    let storage = |contract_address: FieldElement, name: &str, keys: &[FieldElement], offset: u64, value| {
+        // The storage variable names are ASCII, computing their address can't fail
+        let key =
+            get_storage_var_address(name, keys).expect("non-ASCII storage variable name") + FieldElement::from(offset);
+        StarknetStateWrite::Storage { contract_address, key, value }
+    };
+
+    if let Some(balance) = account_override.balance {
+        for (offset, value) in u256_to_felts(balance).into_iter().enumerate() {
+            writes.push(storage(native_token, "ERC20_balances", &[starknet_address], offset as u64, value));
+        }
+    }
+
+    if let Some(nonce) = account_override.nonce {
+        writes.push(StarknetStateWrite::Nonce {
+            contract_address: starknet_address,
+            nonce: FieldElement::from(nonce.as_u64()),
+        });
+    }
+
+    if let Some(code) = &account_override.code {
+        writes.push(storage(starknet_address, "bytecode_len_", &[], 0, FieldElement::from(code.len())));
+        for (index, chunk) in bytes_to_u128_felts(code).into_iter().enumerate() {
+            writes.push(storage(starknet_address, "bytecode_", &[FieldElement::from(index)], 0, chunk));
+        }
+    }
+
+    for (slot, value) in account_override.state_diff.iter().flatten() {
+        let keys = u256_to_felts(U256::from_be_bytes(slot.0));
+        for (offset, value) in u256_to_felts(U256::from_be_bytes(value.0)).into_iter().enumerate() {
+            writes.push(storage(starknet_address, "storage_", &keys, offset as u64, value));
+        }
+    }
+
+    Ok(writes)
+}
+
+/// Restore of the state overwritten by a state override, holding the lock of the state until the
+/// state is restored. A restore dropped before it ran, e.g. as the call was cancelled, runs in a
+/// spawned task.
+pub struct StateRestore {
+    backend: Arc<dyn StateOverrideBackend>,
+    writes: Option<Vec<StarknetStateWrite>>,
+    lock: Option<OwnedRwLockWriteGuard<()>>,
+}
+
+impl StateRestore {
+    pub fn new(
+        backend: Arc<dyn StateOverrideBackend>,
+        writes: Vec<StarknetStateWrite>,
+        lock: OwnedRwLockWriteGuard<()>,
+    ) -> Self {
+        Self { backend, writes: Some(writes), lock: Some(lock) }
+    }
+
+    /// Restores the state. A failure is logged, it must not replace the result of the call.
+    pub async fn restore(mut self) {
+        if let Some(writes) = self.writes.take() {
+            restore_state(self.backend.as_ref(), writes).await;
+        }
+    }
+}
+
+impl Drop for StateRestore {
+    fn drop(&mut self) {
+        let Some(writes) = self.writes.take() else {
+            return;
+        };
+        let backend = Arc::clone(&self.backend);
+        let lock = self.lock.take();
+        tokio::spawn(async move {
+            restore_state(backend.as_ref(), writes).await;
+            drop(lock);
+        });
+    }
+}
+
+async fn restore_state(backend: &dyn StateOverrideBackend, writes: Vec<StarknetStateWrite>) {
+    if let Err(err) = backend.apply(writes).await {
+        tracing::error!("Failed to restore the state overwritten by a state override: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use tokio::sync::RwLock;
+
+    use super::*;
+    use crate::client::constants::STARKNET_NATIVE_TOKEN;
+
+    /// Backend recording the writes applied.
+    #[derive(Default)]
+    struct RecordingBackend {
+        applied: Mutex<Vec<StarknetStateWrite>>,
+    }
+
+    #[async_trait]
+    impl StateOverrideBackend for RecordingBackend {
+        async fn apply(&self, writes: Vec<StarknetStateWrite>) -> eyre::Result<Vec<StarknetStateWrite>> {
+            self.applied.lock().unwrap().extend(writes);
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn test_deserialize_state_override() {
+        // Given
+        let state_override = r#"{
+            "0x0000000000000000000000000000000000000001": {
+                "balance": "0xde0b6b3a7640000",
+                "nonce": "0x2",
+                "code": "0x6001",
+                "stateDiff": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001": "0x00000000000000000000000000000000000000000000000000000000000000ff"
+                }
+            }
+        }"#;
+
+        // When
+        let state_override: StateOverride = serde_json::from_str(state_override).unwrap();
+
+        // Then
+        let account_override = state_override.get(&Address::from_low_u64_be(1)).unwrap();
+        assert_eq!(Some(U256::from(1_000_000_000_000_000_000u64)), account_override.balance);
+        assert_eq!(Some(U64::from(2)), account_override.nonce);
+        assert_eq!(Some(Bytes::from(vec![0x60, 0x01])), account_override.code);
+        assert_eq!(None, account_override.state);
+        assert_eq!(1, account_override.state_diff.as_ref().unwrap().len());
+    }
+
+    #[test]
+    fn test_account_override_writes() {
+        // Given
+        let starknet_address = FieldElement::from_hex_be("0x1234").unwrap();
+        let slot = H256::from_low_u64_be(1);
+        let account_override = AccountOverride {
+            balance: Some(U256::from_str("0x100000000000000000000000000000001").unwrap()),
+            nonce: Some(U64::from(7)),
+            code: Some(Bytes::from(vec![0xff; 17])),
+            state_diff: Some(HashMap::from([(slot, H256::from_low_u64_be(0xff))])),
+            ..Default::default()
+        };
+
+        // When
+        let native_token = FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap();
+        let writes = account_override_writes(starknet_address, native_token, &account_override).unwrap();
+
+        // Then
+        let balance_key = get_storage_var_address("ERC20_balances", &[starknet_address]).unwrap();
+        let slot_key = get_storage_var_address("storage_", &[FieldElement::ONE, FieldElement::ZERO]).unwrap();
+        let expected = vec![
+            StarknetStateWrite::Storage { contract_address: native_token, key: balance_key, value: FieldElement::ONE },
+            StarknetStateWrite::Storage {
+                contract_address: native_token,
+                key: balance_key + FieldElement::ONE,
+                value: FieldElement::ONE,
+            },
+            StarknetStateWrite::Nonce { contract_address: starknet_address, nonce: FieldElement::from(7u8) },
+            StarknetStateWrite::Storage {
+                contract_address: starknet_address,
+                key: get_storage_var_address("bytecode_len_", &[]).unwrap(),
+                value: FieldElement::from(17u8),
+            },
+            StarknetStateWrite::Storage {
+                contract_address: starknet_address,
+                key: get_storage_var_address("bytecode_", &[FieldElement::ZERO]).unwrap(),
+                value: FieldElement::from(u128::MAX),
+            },
+            StarknetStateWrite::Storage {
+                contract_address: starknet_address,
+                key: get_storage_var_address("bytecode_", &[FieldElement::ONE]).unwrap(),
+                value: FieldElement::from(0xffu128 << 120),
+            },
+            StarknetStateWrite::Storage {
+                contract_address: starknet_address,
+                key: slot_key,
+                value: FieldElement::from(0xffu8),
+            },
+            StarknetStateWrite::Storage {
+                contract_address: starknet_address,
+                key: slot_key + FieldElement::ONE,
+                value: FieldElement::ZERO,
+            },
+        ];
+        assert_eq!(expected, writes);
+    }
+
+    #[test]
+    fn test_account_override_writes_rejects_state() {
+        // Given
+        let account_override = AccountOverride { state: Some(HashMap::new()), ..Default::default() };
+
+        // Then
+        assert!(account_override_writes(FieldElement::ONE, FieldElement::TWO, &account_override).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_state_restore_runs() {
+        // Given
+        let backend = Arc::new(RecordingBackend::default());
+        let lock = Arc::new(RwLock::new(()));
+        let write = StarknetStateWrite::Nonce { contract_address: FieldElement::ONE, nonce: FieldElement::TWO };
+        let restore = StateRestore::new(backend.clone(), vec![write], Arc::clone(&lock).write_owned().await);
+
+        // When
+        drop(restore);
+        let _read = lock.read().await;
+
+        // Then
+        assert_eq!(vec![write], *backend.applied.lock().unwrap());
+    }
+}
@@ -0,0 +1,467 @@
+//! Geth-style and Parity-style traces of Kakarot transactions.
+//!
+//! The EVM runs inside the Kakarot Cairo contract: the Starknet trace of a transaction only
+//! exposes the Cairo calls, not the EVM opcodes or the EVM sub-calls. The traces are therefore
+//! reduced to the top level EVM call, built from the Ethereum transaction, its receipt and the
+//! return data of the Kakarot invocation found in the Starknet trace. The Parity flat traces of
+//! the `trace` methods are flattened from the same call frame.
+use reth_primitives::{Address, Bytes, H256, U256};
+use reth_rpc_types::Log;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use starknet::core::types::FieldElement;
+
+/// Tracer requested through the tracing options.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Tracer {
+    /// Geth's default tracer, returning the struct logs.
+    #[default]
+    #[serde(rename = "structLogger")]
+    StructLogger,
+    #[serde(rename = "callTracer")]
+    CallTracer,
+}
+
+/// Configuration of the call tracer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallTracerConfig {
+    /// Only trace the top level call. Always the case since the EVM sub-calls aren't exposed.
+    #[serde(default)]
+    pub only_top_call: bool,
+    /// Include the logs emitted by the call.
+    #[serde(default)]
+    pub with_log: bool,
+}
+
+/// Tracing options of the `debug` methods. The struct logger options (`disableStorage`,
+/// `enableMemory`, ...) are accepted and ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TracingOptions {
+    #[serde(default)]
+    pub tracer: Tracer,
+    #[serde(default)]
+    pub tracer_config: CallTracerConfig,
+}
+
+/// Type of an EVM call frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CallType {
+    #[default]
+    Call,
+    Create,
+    SelfDestruct,
+}
+
+/// Log emitted by a call, as returned by the call tracer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CallLogFrame {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+}
+
+impl From<Log> for CallLogFrame {
+    fn from(log: Log) -> Self {
+        Self { address: log.address, topics: log.topics, data: log.data }
+    }
+}
+
+/// Call frame returned by the call tracer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    #[serde(rename = "type")]
+    pub call_type: CallType,
+    pub from: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    pub gas: U256,
+    pub gas_used: U256,
+    pub input: Bytes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Bytes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallFrame>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<CallLogFrame>,
+}
+
+/// Result of the default struct logger. The struct logs are always empty since the EVM opcodes
+/// executed by Kakarot aren't exposed by the Starknet trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefaultFrame {
+    pub failed: bool,
+    pub gas: U256,
+    /// Return data, hex encoded without prefix as geth does.
+    pub return_value: String,
+    pub struct_logs: Vec<Value>,
+}
+
+/// Trace returned by the `debug` methods, depending on the requested tracer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum GethTrace {
+    CallTracer(CallFrame),
+    Default(DefaultFrame),
+}
+
+impl GethTrace {
+    /// Formats the call frame of a transaction for the requested tracer.
+    pub fn new(mut frame: CallFrame, options: &TracingOptions) -> Self {
+        match options.tracer {
+            Tracer::CallTracer => {
+                if !options.tracer_config.with_log {
+                    frame.logs.clear();
+                }
+                Self::CallTracer(frame)
+            }
+            Tracer::StructLogger => Self::Default(DefaultFrame {
+                failed: frame.error.is_some(),
+                gas: frame.gas_used,
+                return_value: frame.output.map(hex::encode).unwrap_or_default(),
+                struct_logs: vec![],
+            }),
+        }
+    }
+}
+
+/// Trace of a transaction of a block, returned by `debug_traceBlockByNumber`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTraceResult {
+    pub tx_hash: H256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<GethTrace>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Type of a Parity trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionType {
+    Call,
+    Create,
+    Suicide,
+}
+
+/// Action of a Parity call trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallAction {
+    pub from: Address,
+    /// Always `call`, the delegate and static calls being EVM sub-calls.
+    pub call_type: String,
+    pub gas: U256,
+    pub input: Bytes,
+    pub to: Address,
+    pub value: U256,
+}
+
+/// Action of a Parity create trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CreateAction {
+    pub from: Address,
+    pub gas: U256,
+    pub init: Bytes,
+    pub value: U256,
+}
+
+/// Action of a Parity suicide trace, for a self-destructed contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuicideAction {
+    pub address: Address,
+    pub refund_address: Address,
+    pub balance: U256,
+}
+
+/// Action of a Parity trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum Action {
+    Call(CallAction),
+    Create(CreateAction),
+    Suicide(SuicideAction),
+}
+
+/// Result of a successful Parity trace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum TraceOutput {
+    #[serde(rename_all = "camelCase")]
+    Call { gas_used: U256, output: Bytes },
+    #[serde(rename_all = "camelCase")]
+    Create { address: Address, code: Bytes, gas_used: U256 },
+}
+
+/// Parity flat trace of a call of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlatTrace {
+    pub action: Action,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Result of the call, null when it failed and for the suicides.
+    pub result: Option<TraceOutput>,
+    /// Number of direct sub-calls of the call.
+    pub subtraces: usize,
+    /// Indexes of the call in the sub-calls of its ancestors, empty for the top level call.
+    pub trace_address: Vec<usize>,
+    #[serde(rename = "type")]
+    pub action_type: ActionType,
+}
+
+impl FlatTrace {
+    /// Flattens the call frame of a transaction into its Parity traces, in depth first order.
+    pub fn flatten(frame: &CallFrame) -> Vec<Self> {
+        let mut traces = vec![];
+        Self::flatten_into(frame, vec![], &mut traces);
+        traces
+    }
+
+    fn flatten_into(frame: &CallFrame, trace_address: Vec<usize>, traces: &mut Vec<Self>) {
+        let value = frame.value.unwrap_or_default();
+        let to = frame.to.unwrap_or_default();
+        let output = frame.output.clone().unwrap_or_default();
+        let (action, action_type, result) = match frame.call_type {
+            CallType::Call => (
+                Action::Call(CallAction {
+                    from: frame.from,
+                    call_type: "call".to_string(),
+                    gas: frame.gas,
+                    input: frame.input.clone(),
+                    to,
+                    value,
+                }),
+                ActionType::Call,
+                Some(TraceOutput::Call { gas_used: frame.gas_used, output }),
+            ),
+            CallType::Create => (
+                Action::Create(CreateAction { from: frame.from, gas: frame.gas, init: frame.input.clone(), value }),
+                ActionType::Create,
+                Some(TraceOutput::Create { address: to, code: output, gas_used: frame.gas_used }),
+            ),
+            CallType::SelfDestruct => (
+                Action::Suicide(SuicideAction { address: frame.from, refund_address: to, balance: value }),
+                ActionType::Suicide,
+                None,
+            ),
+        };
+        traces.push(Self {
+            action,
+            error: frame.error.clone(),
+            result: result.filter(|_| frame.error.is_none()),
+            subtraces: frame.calls.len(),
+            trace_address: trace_address.clone(),
+            action_type,
+        });
+        for (index, call) in frame.calls.iter().enumerate() {
+            let mut call_address = trace_address.clone();
+            call_address.push(index);
+            Self::flatten_into(call, call_address, traces);
+        }
+    }
+}
+
+/// Parity flat trace located in its block, returned by `trace_transaction` and `trace_block`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalizedFlatTrace {
+    #[serde(flatten)]
+    pub trace: FlatTrace,
+    pub block_hash: Option<H256>,
+    pub block_number: Option<U256>,
+    pub transaction_hash: H256,
+    pub transaction_position: Option<U256>,
+}
+
+/// Kind of trace requested from `trace_replayTransaction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TraceType {
+    Trace,
+    /// The EVM opcodes aren't exposed by Starknet, always returned as null.
+    VmTrace,
+    /// The state changes of a mined transaction aren't exposed by Starknet, always returned as
+    /// null.
+    StateDiff,
+}
+
+/// Result of `trace_replayTransaction`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceResults {
+    pub output: Bytes,
+    pub state_diff: Option<Value>,
+    /// Flat traces of the transaction, empty unless [`TraceType::Trace`] was requested.
+    pub trace: Vec<FlatTrace>,
+    pub vm_trace: Option<Value>,
+}
+
+/// Returns the invocation of the given entrypoint of the given contract in a Starknet trace, in
+/// the feeder gateway format (`function_invocation`, `internal_calls`, `selector`) or in the
+/// JSON-RPC format (`execute_invocation`, `calls`, `entry_point_selector`).
+pub fn find_invocation(trace: &Value, contract_address: FieldElement, selector: FieldElement) -> Option<&Value> {
+    let root = trace.get("function_invocation").or_else(|| trace.get("execute_invocation"))?;
+    find_invocation_in(root, contract_address, selector)
+}
+
+fn find_invocation_in(invocation: &Value, contract_address: FieldElement, selector: FieldElement) -> Option<&Value> {
+    let felt_field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| invocation.get(key))
+            .and_then(Value::as_str)
+            .and_then(|value| FieldElement::from_hex_be(value).ok())
+    };
+    if felt_field(&["contract_address"]) == Some(contract_address)
+        && felt_field(&["selector", "entry_point_selector"]) == Some(selector)
+    {
+        return Some(invocation);
+    }
+
+    let calls = invocation.get("internal_calls").or_else(|| invocation.get("calls"))?.as_array()?;
+    calls.iter().find_map(|call| find_invocation_in(call, contract_address, selector))
+}
+
+/// Returns the result of an invocation of a Starknet trace.
+pub fn invocation_result(invocation: &Value) -> Option<Vec<FieldElement>> {
+    invocation
+        .get("result")?
+        .as_array()?
+        .iter()
+        .map(|felt| felt.as_str().and_then(|felt| FieldElement::from_hex_be(felt).ok()))
+        .collect()
+}
+
+/// Returns the revert reason of a reverted Starknet transaction.
+pub fn revert_reason(trace: &Value) -> Option<String> {
+    trace
+        .get("revert_error")
+        .or_else(|| trace.get("execute_invocation").and_then(|invocation| invocation.get("revert_reason")))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use starknet::macros::felt;
+
+    use super::*;
+
+    fn gateway_trace() -> Value {
+        json!({
+            "function_invocation": {
+                "contract_address": "0x1",
+                "selector": "0x15d40a3d6ca2ac30f4031e42be28da9b056fef9bb7357ac5e85627ee876e5ad",
+                "result": ["0x1"],
+                "internal_calls": [{
+                    "contract_address": "0x2",
+                    "selector": "0x3",
+                    "result": ["0x2", "0xab", "0xcd"],
+                    "internal_calls": []
+                }]
+            }
+        })
+    }
+
+    #[test]
+    fn test_find_invocation() {
+        // Given
+        let trace = gateway_trace();
+
+        // When
+        let invocation = find_invocation(&trace, felt!("0x2"), felt!("0x3")).unwrap();
+
+        // Then
+        assert_eq!(Some(vec![felt!("0x2"), felt!("0xab"), felt!("0xcd")]), invocation_result(invocation));
+        assert_eq!(None, find_invocation(&trace, felt!("0x2"), felt!("0x4")));
+    }
+
+    #[test]
+    fn test_find_invocation_rpc_format() {
+        // Given
+        let trace = json!({
+            "execute_invocation": {
+                "contract_address": "0x1",
+                "entry_point_selector": "0x2",
+                "calls": [{ "contract_address": "0x3", "entry_point_selector": "0x4", "result": [], "calls": [] }]
+            }
+        });
+
+        // When
+        let invocation = find_invocation(&trace, felt!("0x3"), felt!("0x4"));
+
+        // Then
+        assert_eq!(Some(vec![]), invocation.and_then(invocation_result));
+    }
+
+    #[test]
+    fn test_revert_reason() {
+        assert_eq!(Some("reverted".to_string()), revert_reason(&json!({ "revert_error": "reverted" })));
+        assert_eq!(None, revert_reason(&gateway_trace()));
+    }
+
+    #[test]
+    fn test_geth_trace_formats() {
+        // Given
+        let frame = CallFrame {
+            gas_used: U256::from(21_000),
+            output: Some(Bytes::from(vec![0xab])),
+            logs: vec![CallLogFrame { address: Address::zero(), topics: vec![], data: Bytes::default() }],
+            ..Default::default()
+        };
+        let call_tracer: TracingOptions = serde_json::from_value(json!({ "tracer": "callTracer" })).unwrap();
+
+        // When
+        let default_trace = GethTrace::new(frame.clone(), &TracingOptions::default());
+        let call_trace = GethTrace::new(frame, &call_tracer);
+
+        // Then
+        let expected_default = json!({ "failed": false, "gas": "0x5208", "returnValue": "ab", "structLogs": [] });
+        assert_eq!(expected_default, serde_json::to_value(default_trace).unwrap());
+        let call_trace = serde_json::to_value(call_trace).unwrap();
+        assert_eq!("CALL", call_trace["type"]);
+        assert_eq!("0xab", call_trace["output"]);
+        assert!(call_trace.get("logs").is_none());
+    }
+
+    #[test]
+    fn test_flatten_parity_traces() {
+        // Given
+        let frame = CallFrame {
+            to: Some(Address::from_low_u64_be(0xdead)),
+            gas: U256::from(100_000),
+            gas_used: U256::from(21_000),
+            calls: vec![CallFrame { call_type: CallType::Create, ..Default::default() }],
+            ..Default::default()
+        };
+        let reverted = CallFrame { error: Some("execution reverted".to_string()), ..Default::default() };
+
+        // When
+        let traces = FlatTrace::flatten(&frame);
+        let reverted_traces = FlatTrace::flatten(&reverted);
+
+        // Then
+        assert_eq!(2, traces.len());
+        assert_eq!(1, traces[0].subtraces);
+        assert_eq!(vec![0], traces[1].trace_address);
+        assert_eq!(ActionType::Create, traces[1].action_type);
+        let top = serde_json::to_value(&traces[0]).unwrap();
+        assert_eq!(json!("call"), top["type"]);
+        assert_eq!(json!("call"), top["action"]["callType"]);
+        assert_eq!(json!("0x5208"), top["result"]["gasUsed"]);
+        assert_eq!(json!([]), top["traceAddress"]);
+        assert_eq!(None, reverted_traces[0].result);
+        assert_eq!(Some("execution reverted".to_string()), reverted_traces[0].error);
+    }
+}
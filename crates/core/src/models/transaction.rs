@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use reth_primitives::{TransactionSigned, H256, U256};
+use reth_primitives::{Transaction as PrimitiveTransaction, TransactionSigned, H256, U128, U256, U64};
 use reth_rpc_types::{Signature, Transaction as EthTransaction};
 use starknet::core::types::{BlockId as StarknetBlockId, BlockTag, FieldElement, InvokeTransaction, Transaction};
 use starknet::providers::Provider;
@@ -7,7 +7,7 @@ use starknet::providers::Provider;
 use super::felt::Felt252Wrapper;
 use super::ConversionError;
 use crate::client::api::KakarotEthApi;
-use crate::client::constants::{self, CHAIN_ID};
+use crate::client::constants;
 use crate::client::errors::EthApiError;
 use crate::models::call::Calls;
 use crate::models::convertible::ConvertibleStarknetTransaction;
@@ -85,40 +85,80 @@ impl ConvertibleStarknetTransaction for StarknetTransaction {
 
         let from = client.get_evm_address(&sender_address, &starknet_block_latest).await?;
 
-        let max_priority_fee_per_gas = Some(client.max_priority_fee_per_gas());
+        let tx = self.ethereum_transaction()?;
 
-        let calls: Calls = self.calldata()?.try_into()?;
-        let tx: TransactionSigned = (&calls).try_into()?;
-        let input = tx.input().to_owned();
-        let signature = tx.signature;
-        let to = tx.to();
-
-        let v = if signature.odd_y_parity { 1 } else { 0 } + 35 + 2 * CHAIN_ID;
-        let signature = Some(Signature { r: signature.r, s: signature.s, v: U256::from_limbs_slice(&[v]) });
-
-        Ok(EthTransaction {
-            hash,
-            nonce,
-            block_hash,
-            block_number,
-            transaction_index,
-            from,
-            to,                     // TODO fetch the to
-            value: U256::from(100), // TODO fetch the value
-            gas_price: None,        // TODO fetch the gas price
-            gas: U256::from(100),   // TODO fetch the gas amount
-            max_fee_per_gas: None,  // TODO fetch the max_fee_per_gas
-            max_priority_fee_per_gas,
-            input,
-            signature,
-            chain_id: Some(CHAIN_ID.into()),
-            access_list: None,      // TODO fetch the access list
-            transaction_type: None, // TODO fetch the transaction type
-        })
+        Ok(EthTransaction { hash, nonce, block_hash, block_number, transaction_index, from, ..rpc_transaction(&tx) })
+    }
+}
+
+/// Returns the RPC representation of the fields of the envelope of a signed Ethereum transaction:
+/// the gas price of the legacy and EIP-2930 transactions, the fee caps of the EIP-1559
+/// transactions, the access list and the type of the typed transactions. The `v` of the signature
+/// is EIP-155 encoded for a legacy transaction and the parity of `y` for a typed transaction.
+///
+/// The hash, the sender and the block context are left to the caller.
+pub fn rpc_transaction(tx: &TransactionSigned) -> EthTransaction {
+    let chain_id = tx.chain_id();
+    let parity = u64::from(tx.signature.odd_y_parity);
+    let v = match (&tx.transaction, chain_id) {
+        (PrimitiveTransaction::Legacy(_), Some(chain_id)) => parity + 35 + 2 * chain_id,
+        (PrimitiveTransaction::Legacy(_), None) => parity + 27,
+        _ => parity,
+    };
+    let signature = Some(Signature { r: tx.signature.r, s: tx.signature.s, v: U256::from(v) });
+
+    // Starknet has no base fee market: the gas price of an EIP-1559 transaction is its fee cap
+    let (gas_price, max_fee_per_gas, max_priority_fee_per_gas) = match &tx.transaction {
+        PrimitiveTransaction::Legacy(tx) => (Some(U128::from(tx.gas_price)), None, None),
+        PrimitiveTransaction::Eip2930(tx) => (Some(U128::from(tx.gas_price)), None, None),
+        PrimitiveTransaction::Eip1559(tx) => (
+            Some(U128::from(tx.max_fee_per_gas)),
+            Some(U128::from(tx.max_fee_per_gas)),
+            Some(U128::from(tx.max_priority_fee_per_gas)),
+        ),
+    };
+    let (access_list, transaction_type) = match &tx.transaction {
+        PrimitiveTransaction::Legacy(_) => (None, None),
+        PrimitiveTransaction::Eip2930(tx) => (Some(tx.access_list.0.clone()), Some(U64::from(1))),
+        PrimitiveTransaction::Eip1559(tx) => (Some(tx.access_list.0.clone()), Some(U64::from(2))),
+    };
+
+    EthTransaction {
+        hash: tx.hash(),
+        nonce: U256::from(tx.nonce()),
+        block_hash: None,
+        block_number: None,
+        transaction_index: None,
+        from: Default::default(),
+        to: tx.to(),
+        value: U256::from(tx.value()),
+        gas_price,
+        gas: U256::from(tx.gas_limit()),
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        input: tx.input().to_owned(),
+        signature,
+        chain_id: chain_id.map(U64::from),
+        access_list,
+        transaction_type,
     }
 }
 
 impl StarknetTransaction {
+    /// Returns the hash of the signed Ethereum transaction wrapped in the Starknet transaction,
+    /// i.e. the hash computed by Ethereum clients. Fails if the transaction isn't an invoke
+    /// transaction wrapping an Ethereum transaction.
+    pub fn ethereum_transaction_hash(&self) -> Result<H256, ConversionError<()>> {
+        Ok(self.ethereum_transaction()?.hash())
+    }
+
+    /// Returns the signed Ethereum transaction wrapped in the Starknet transaction. Fails if the
+    /// transaction isn't an invoke transaction wrapping an Ethereum transaction.
+    pub fn ethereum_transaction(&self) -> Result<TransactionSigned, ConversionError<()>> {
+        let calls: Calls = self.calldata()?.try_into()?;
+        Ok((&calls).try_into()?)
+    }
+
     /// Checks if the transaction is a Kakarot transaction.
     async fn is_kakarot_tx<P: Provider + Send + Sync>(
         &self,
@@ -136,6 +176,8 @@ impl StarknetTransaction {
 #[cfg(test)]
 mod tests {
 
+    use reth_primitives::{AccessList, AccessListItem, Address, TxEip2930, TxLegacy};
+
     use super::*;
     use crate::mock::constants::{ABDEL_STARKNET_ADDRESS_HEX, PROXY_ACCOUNT_CLASS_HASH_HEX};
     use crate::mock::mock_starknet::{fixtures, init_mock_client, AvailableFixtures};
@@ -160,6 +202,43 @@ mod tests {
         assert!(is_kakarot_tx);
     }
 
+    #[test]
+    fn test_rpc_transaction_envelopes() {
+        // Given
+        let signature = reth_primitives::Signature { r: U256::from(1), s: U256::from(2), odd_y_parity: true };
+        let legacy = TransactionSigned::from_transaction_and_signature(
+            PrimitiveTransaction::Legacy(TxLegacy { chain_id: Some(1), gas_price: 7, ..Default::default() }),
+            signature,
+        );
+        let access_list =
+            AccessList(vec![AccessListItem { address: Address::zero(), storage_keys: vec![H256::zero()] }]);
+        let eip2930 = TransactionSigned::from_transaction_and_signature(
+            PrimitiveTransaction::Eip2930(TxEip2930 {
+                chain_id: 1,
+                gas_price: 7,
+                access_list: access_list.clone(),
+                ..Default::default()
+            }),
+            signature,
+        );
+
+        // When
+        let legacy = rpc_transaction(&legacy);
+        let eip2930 = rpc_transaction(&eip2930);
+
+        // Then
+        assert_eq!(U256::from(38), legacy.signature.unwrap().v);
+        assert_eq!(Some(U128::from(7)), legacy.gas_price);
+        assert_eq!(None, legacy.transaction_type);
+        assert_eq!(None, legacy.access_list);
+
+        assert_eq!(U256::from(1), eip2930.signature.unwrap().v);
+        assert_eq!(Some(U128::from(7)), eip2930.gas_price);
+        assert_eq!(None, eip2930.max_fee_per_gas);
+        assert_eq!(Some(U64::from(1)), eip2930.transaction_type);
+        assert_eq!(Some(access_list.0), eip2930.access_list);
+    }
+
     #[tokio::test]
     async fn test_to_eth_transaction() {
         // Given
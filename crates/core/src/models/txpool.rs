@@ -0,0 +1,142 @@
+//! Transaction pool views returned by the `txpool` namespace.
+//!
+//! Starknet doesn't expose its mempool: the pending block is the only view of the transactions
+//! waiting to be included. Its transactions are reported as pending, nothing is ever queued.
+use std::collections::BTreeMap;
+
+use reth_primitives::{Address, U256, U64};
+use reth_rpc_types::Transaction as EthTransaction;
+use serde::Serialize;
+
+/// Transactions grouped by sender then by nonce, following geth's `txpool` format.
+pub type TxpoolTransactions<T> = BTreeMap<Address, BTreeMap<String, T>>;
+
+/// Result of `txpool_content`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TxpoolContent {
+    pub pending: TxpoolTransactions<EthTransaction>,
+    pub queued: TxpoolTransactions<EthTransaction>,
+}
+
+/// Result of `txpool_inspect`, summarizing each transaction in a single line.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TxpoolInspect {
+    pub pending: TxpoolTransactions<String>,
+    pub queued: TxpoolTransactions<String>,
+}
+
+/// Result of `txpool_status`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TxpoolStatus {
+    pub pending: U64,
+    pub queued: U64,
+}
+
+impl TxpoolContent {
+    /// Groups the pending transactions by sender and nonce.
+    pub fn from_pending(transactions: Vec<EthTransaction>) -> Self {
+        let mut pending = TxpoolTransactions::new();
+        for transaction in transactions {
+            pending.entry(transaction.from).or_default().insert(transaction.nonce.to_string(), transaction);
+        }
+        Self { pending, queued: TxpoolTransactions::new() }
+    }
+
+    /// Summarizes the transactions as geth does:
+    /// `<to>: <value> wei + <gas> gas × <gas price> wei`.
+    pub fn inspect(&self) -> TxpoolInspect {
+        let summarize = |transactions: &TxpoolTransactions<EthTransaction>| {
+            transactions
+                .iter()
+                .map(|(sender, transactions)| {
+                    let summaries = transactions
+                        .iter()
+                        .map(|(nonce, transaction)| (nonce.clone(), inspect_summary(transaction)))
+                        .collect();
+                    (*sender, summaries)
+                })
+                .collect()
+        };
+        TxpoolInspect { pending: summarize(&self.pending), queued: summarize(&self.queued) }
+    }
+
+    /// Counts the pending and queued transactions.
+    pub fn status(&self) -> TxpoolStatus {
+        let count = |transactions: &TxpoolTransactions<EthTransaction>| {
+            U64::from(transactions.values().map(BTreeMap::len).sum::<usize>())
+        };
+        TxpoolStatus { pending: count(&self.pending), queued: count(&self.queued) }
+    }
+}
+
+fn inspect_summary(transaction: &EthTransaction) -> String {
+    let to = match transaction.to {
+        Some(to) => format!("{to:#x}"),
+        None => "contract creation".to_string(),
+    };
+    let gas_price = transaction.gas_price.or(transaction.max_fee_per_gas).map(U256::from).unwrap_or_default();
+    format!("{to}: {} wei + {} gas × {gas_price} wei", transaction.value, transaction.gas)
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Bytes, H256, U128};
+
+    use super::*;
+
+    fn transaction(from: Address, nonce: u64, to: Option<Address>) -> EthTransaction {
+        EthTransaction {
+            hash: H256::from_low_u64_be(nonce),
+            nonce: U256::from(nonce),
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+            from,
+            to,
+            value: U256::from(100),
+            gas_price: Some(U128::from(1)),
+            gas: U256::from(21_000),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            input: Bytes::default(),
+            signature: None,
+            chain_id: None,
+            access_list: None,
+            transaction_type: None,
+        }
+    }
+
+    #[test]
+    fn test_txpool_content_groups_by_sender_and_nonce() {
+        // Given
+        let (alice, bob) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let transactions =
+            vec![transaction(alice, 1, Some(bob)), transaction(bob, 0, None), transaction(alice, 0, Some(bob))];
+
+        // When
+        let content = TxpoolContent::from_pending(transactions);
+
+        // Then
+        assert_eq!(vec!["0", "1"], content.pending[&alice].keys().collect::<Vec<_>>());
+        assert_eq!(1, content.pending[&bob].len());
+        assert!(content.queued.is_empty());
+        assert_eq!(TxpoolStatus { pending: U64::from(3), queued: U64::ZERO }, content.status());
+    }
+
+    #[test]
+    fn test_txpool_inspect() {
+        // Given
+        let (alice, bob) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let content = TxpoolContent::from_pending(vec![transaction(alice, 0, Some(bob)), transaction(bob, 7, None)]);
+
+        // When
+        let inspect = content.inspect();
+
+        // Then
+        assert_eq!(
+            "0x0000000000000000000000000000000000000002: 100 wei + 21000 gas × 1 wei",
+            inspect.pending[&alice]["0"]
+        );
+        assert_eq!("contract creation: 100 wei + 21000 gas × 1 wei", inspect.pending[&bob]["7"]);
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use super::{Entry, Storage, StorageError};
+
+/// Storage keeping the entries in memory, lost on restart.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    entries: RwLock<BTreeMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.entries.read().expect("memory storage poisoned").get(key).cloned())
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
+        self.entries.write().expect("memory storage poisoned").insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<(), StorageError> {
+        self.entries.write().expect("memory storage poisoned").remove(key);
+        Ok(())
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<Entry>, StorageError> {
+        let entries = self.entries.read().expect("memory storage poisoned");
+        Ok(entries
+            .range(prefix.to_vec()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+
+    fn put_batch(&self, entries: &[Entry]) -> Result<(), StorageError> {
+        self.entries.write().expect("memory storage poisoned").extend(entries.iter().cloned());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_utils::assert_storage_behavior;
+
+    #[test]
+    fn test_memory_storage() {
+        assert_storage_behavior(&MemoryStorage::default());
+    }
+}
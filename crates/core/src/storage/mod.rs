@@ -0,0 +1,182 @@
+//! Key-value storage shared by the local state of the client: the installed filters, the mapping
+//! between the Ethereum and the Starknet transaction hashes and the log index.
+//!
+//! The backends are interchangeable: the entries are kept in memory, in a sled database or, with
+//! the `rocksdb` feature, in a RocksDB database. Embedders can supply their own [`Storage`]
+//! through [`StorageConfig::Custom`]. Each user of the storage writes its entries under its own key
+//! prefix, so that a single database holds them all.
+mod memory;
+#[cfg(feature = "rocksdb")]
+mod rocksdb;
+mod sled;
+
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub use memory::MemoryStorage;
+
+#[cfg(feature = "rocksdb")]
+pub use self::rocksdb::RocksDbStorage;
+pub use self::sled::SledStorage;
+use crate::client::errors::ConfigError;
+
+/// Key and value of a stored entry.
+pub type Entry = (Vec<u8>, Vec<u8>);
+
+/// Error of a storage backend.
+#[derive(Debug, thiserror::Error)]
+#[error("storage error: {0}")]
+pub struct StorageError(pub(crate) String);
+
+impl StorageError {
+    pub fn new(err: impl ToString) -> Self {
+        Self(err.to_string())
+    }
+}
+
+/// Key-value storage backend.
+pub trait Storage: Debug + Send + Sync {
+    /// Returns the value stored under the key.
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Stores the value under the key, replacing the previous one.
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), StorageError>;
+
+    /// Removes the value stored under the key, if any.
+    fn delete(&self, key: &[u8]) -> Result<(), StorageError>;
+
+    /// Returns the entries whose key starts with the prefix, in key order.
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<Entry>, StorageError>;
+
+    /// Stores the entries, atomically for the backends which support it.
+    fn put_batch(&self, entries: &[Entry]) -> Result<(), StorageError> {
+        entries.iter().try_for_each(|(key, value)| self.put(key, value))
+    }
+
+    /// Persists the buffered writes. A no-op for the backends which persist each write.
+    fn flush(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+}
+
+/// Returns the key made of the prefix followed by the key.
+pub fn prefixed_key(prefix: &[u8], key: &[u8]) -> Vec<u8> {
+    [prefix, key].concat()
+}
+
+/// Storage backend of the client.
+#[derive(Debug, Clone, Default)]
+pub enum StorageConfig {
+    /// Keeps the entries in memory, lost on restart.
+    #[default]
+    Memory,
+    /// Persists the entries in the sled database at the given path.
+    Sled(PathBuf),
+    /// Persists the entries in the RocksDB database at the given path, requires the `rocksdb`
+    /// feature.
+    RocksDb(PathBuf),
+    /// Storage supplied by the embedder.
+    Custom(Arc<dyn Storage>),
+}
+
+impl FromStr for StorageConfig {
+    type Err = ConfigError;
+
+    /// Parses `memory`, `sled:<path>` or `rocksdb:<path>`.
+    fn from_str(storage: &str) -> Result<Self, Self::Err> {
+        if storage == "memory" {
+            return Ok(Self::Memory);
+        }
+        if let Some(path) = storage.strip_prefix("sled:") {
+            return Ok(Self::Sled(PathBuf::from(path)));
+        }
+        if let Some(path) = storage.strip_prefix("rocksdb:") {
+            return Ok(Self::RocksDb(PathBuf::from(path)));
+        }
+        Err(ConfigError::EnvironmentVariableSetWrong(format!(
+            "KAKAROT_STORAGE should be memory, sled:<path> or rocksdb:<path>, got {storage}"
+        )))
+    }
+}
+
+impl StorageConfig {
+    /// Create a new `StorageConfig` from the `KAKAROT_STORAGE` environment variable. Defaults to
+    /// the in-memory storage.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        match std::env::var("KAKAROT_STORAGE") {
+            Ok(storage) => storage.parse(),
+            Err(_) => Ok(Self::Memory),
+        }
+    }
+
+    /// Opens the configured storage.
+    pub fn open(&self) -> Result<Arc<dyn Storage>, StorageError> {
+        match self {
+            Self::Memory => Ok(Arc::new(MemoryStorage::default())),
+            Self::Sled(path) => Ok(Arc::new(SledStorage::open(path)?)),
+            #[cfg(feature = "rocksdb")]
+            Self::RocksDb(path) => Ok(Arc::new(RocksDbStorage::open(path)?)),
+            #[cfg(not(feature = "rocksdb"))]
+            Self::RocksDb(_) => {
+                Err(StorageError::new("the RocksDB storage requires building with the rocksdb feature of the core"))
+            }
+            Self::Custom(storage) => Ok(Arc::clone(storage)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+
+    /// Checks the behavior shared by all the backends.
+    pub fn assert_storage_behavior(storage: &dyn Storage) {
+        // Given
+        storage.put(b"a/1", b"one").unwrap();
+        storage.put_batch(&[(b"a/2".to_vec(), b"two".to_vec()), (b"b/1".to_vec(), b"three".to_vec())]).unwrap();
+
+        // When
+        storage.put(b"a/1", b"uno").unwrap();
+        storage.delete(b"a/2").unwrap();
+        storage.delete(b"missing").unwrap();
+
+        // Then
+        assert_eq!(Some(b"uno".to_vec()), storage.get(b"a/1").unwrap());
+        assert_eq!(None, storage.get(b"a/2").unwrap());
+        assert_eq!(vec![(b"a/1".to_vec(), b"uno".to_vec())], storage.scan_prefix(b"a/").unwrap());
+        assert_eq!(2, storage.scan_prefix(b"").unwrap().len());
+        storage.flush().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_storage_config() {
+        let parse = |storage: &str| storage.parse::<StorageConfig>();
+
+        assert!(matches!(parse("memory").unwrap(), StorageConfig::Memory));
+        assert!(matches!(parse("sled:/data").unwrap(), StorageConfig::Sled(path) if path == PathBuf::from("/data")));
+        assert!(
+            matches!(parse("rocksdb:./db").unwrap(), StorageConfig::RocksDb(path) if path == PathBuf::from("./db"))
+        );
+        assert!(parse("postgres://localhost").is_err());
+    }
+
+    #[test]
+    fn test_custom_storage_is_shared() {
+        // Given
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::default());
+        let config = StorageConfig::Custom(Arc::clone(&storage));
+
+        // When
+        config.open().unwrap().put(b"key", b"value").unwrap();
+
+        // Then
+        assert_eq!(Some(b"value".to_vec()), storage.get(b"key").unwrap());
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt::{Debug, Formatter};
+use std::path::{Path, PathBuf};
+
+use rocksdb::{Direction, IteratorMode, WriteBatch, DB};
+
+use super::{Entry, Storage, StorageError};
+
+/// Storage persisting the entries in a RocksDB database.
+pub struct RocksDbStorage {
+    db: DB,
+    path: PathBuf,
+}
+
+impl Debug for RocksDbStorage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RocksDbStorage").field("path", &self.path).finish()
+    }
+}
+
+impl RocksDbStorage {
+    /// Opens the RocksDB database at the given path, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        Ok(Self { db: DB::open_default(&path).map_err(StorageError::new)?, path })
+    }
+}
+
+impl Storage for RocksDbStorage {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        self.db.get(key).map_err(StorageError::new)
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
+        self.db.put(key, value).map_err(StorageError::new)
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<(), StorageError> {
+        self.db.delete(key).map_err(StorageError::new)
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<Entry>, StorageError> {
+        let mut entries = vec![];
+        for entry in self.db.iterator(IteratorMode::From(prefix, Direction::Forward)) {
+            let (key, value) = entry.map_err(StorageError::new)?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(entries)
+    }
+
+    fn put_batch(&self, entries: &[Entry]) -> Result<(), StorageError> {
+        let mut batch = WriteBatch::default();
+        for (key, value) in entries {
+            batch.put(key, value);
+        }
+        self.db.write(batch).map_err(StorageError::new)
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        self.db.flush().map_err(StorageError::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_utils::assert_storage_behavior;
+
+    #[test]
+    fn test_rocksdb_storage() {
+        let path = std::env::temp_dir().join(format!("kakarot-rocksdb-storage-{}", std::process::id()));
+        assert_storage_behavior(&RocksDbStorage::open(&path).unwrap());
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
@@ -38,6 +38,7 @@ use crate::client::constants::{CHAIN_ID, STARKNET_NATIVE_TOKEN};
 use crate::client::KakarotClient;
 use crate::contracts::kakarot::KakarotContract;
 use crate::models::felt::Felt252Wrapper;
+use crate::models::kakarot_event::KakarotEvent;
 use crate::test_utils::constants::EOA_WALLET;
 
 /// Macro to find the root path of the project.
@@ -276,20 +277,13 @@ async fn deploy_evm_contract<T: Tokenize>(
         .unwrap();
 
     into_receipt(maybe_receipt).and_then(|InvokeTransactionReceipt { events, .. }| {
-        events.iter().find(|event| event.keys.contains(&get_selector_from_name("evm_contract_deployed").unwrap())).map(
-            |event| {
-                (
-                    abi,
-                    ContractAddresses {
-                        eth_address: {
-                            let evm_address: Felt252Wrapper = event.data[0].into();
-                            evm_address.try_into().unwrap()
-                        },
-                        starknet_address: event.data[1],
-                    },
-                )
-            },
-        )
+        events.iter().find_map(|event| match KakarotEvent::decode(&event.keys, &event.data) {
+            Ok(KakarotEvent::EvmContractDeployed(deployed)) => Some((
+                abi.clone(),
+                ContractAddresses { eth_address: deployed.evm_address, starknet_address: deployed.starknet_address },
+            )),
+            _ => None,
+        })
     })
 }
 
@@ -548,6 +542,33 @@ async fn deploy_kakarot_contracts(
     deployments
 }
 
+/// Deploys and funds an EOA for each of the given wallets, on a sequencer on which the Kakarot
+/// system is already deployed. Returns the Starknet addresses of the EOAs.
+pub async fn deploy_and_fund_eoas(
+    starknet_sequencer: &TestSequencer,
+    kakarot_address: FieldElement,
+    eoa_wallets: &[EthersLocalWallet],
+    funding_amount: FieldElement,
+) -> Vec<FieldElement> {
+    let starknet_account = starknet_sequencer.account();
+    let fee_token_address = FieldElement::from_hex_be(STARKNET_NATIVE_TOKEN).unwrap();
+
+    let mut eoa_sn_addresses = Vec::with_capacity(eoa_wallets.len());
+    for eoa_wallet in eoa_wallets {
+        let eoa_eth_address: Address = eoa_wallet.address().into();
+        let eoa_sn_address = {
+            let address: Felt252Wrapper = eoa_eth_address.into();
+            address.try_into().unwrap()
+        };
+        let deployed_eoa_sn_address =
+            deploy_and_fund_eoa(&starknet_account, kakarot_address, funding_amount, eoa_sn_address, fee_token_address)
+                .await;
+        eoa_sn_addresses.push(deployed_eoa_sn_address);
+    }
+
+    eoa_sn_addresses
+}
+
 /// Structure representing a deployed Kakarot system, containing key details of the system.
 ///
 /// This includes the private key and address of the Externally Owned Account (EOA), the Starknet
@@ -646,6 +667,8 @@ pub enum TestContext {
     Counter,
     PlainOpcodes,
     ERC20,
+    ERC721,
+    ERC1155,
 }
 
 impl KakarotTestEnvironmentContext {
@@ -720,6 +743,26 @@ impl KakarotTestEnvironmentContext {
                     .await;
                 test_environment
             }
+            TestContext::ERC721 => {
+                // Deploy the ERC721 contract
+                test_environment = test_environment
+                    .deploy_evm_contract(ContractDeploymentArgs {
+                        name: "ERC721".into(),
+                        constructor_args: (
+                            Token::String("Test".into()), // name
+                            Token::String("TT".into()),   // symbol
+                        ),
+                    })
+                    .await;
+                test_environment
+            }
+            TestContext::ERC1155 => {
+                // Deploy the ERC1155 contract
+                test_environment = test_environment
+                    .deploy_evm_contract(ContractDeploymentArgs { name: "ERC1155".into(), constructor_args: () })
+                    .await;
+                test_environment
+            }
         }
     }
 
@@ -3,7 +3,7 @@ mod tests {
     use std::str::FromStr;
 
     use ctor::ctor;
-    use kakarot_rpc_core::client::api::KakarotEthApi;
+    use kakarot_rpc_core::client::api::{KakarotEthApi, KakarotStarknetApi};
     use kakarot_rpc_core::mock::constants::ACCOUNT_ADDRESS_EVM;
     use kakarot_rpc_core::models::balance::{TokenBalance, TokenBalances};
     use kakarot_rpc_core::test_utils::deploy_helpers::KakarotTestEnvironmentContext;
@@ -12,7 +12,7 @@ mod tests {
     use reth_primitives::{Address, BlockId, BlockNumberOrTag, Bytes, H256, U256};
     use reth_rpc_types::{Filter, FilterBlockOption, Log, ValueOrArray};
     use rstest::*;
-    use starknet::core::types::FieldElement;
+    use starknet::core::types::{BlockId as StarknetBlockId, BlockTag, FieldElement};
     use tracing_subscriber::FmtSubscriber;
 
     #[ctor]
@@ -35,6 +35,23 @@ mod tests {
         assert_eq!(U256::from(0), nonce);
     }
 
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_compute_starknet_addresses(kakarot_test_env_ctx: KakarotTestEnvironmentContext) {
+        // Given
+        let (client, kakarot) = kakarot_test_env_ctx.resources();
+        let eth_address = kakarot.eoa_addresses.eth_address;
+        let latest = StarknetBlockId::Tag(BlockTag::Latest);
+
+        // When
+        let starknet_addresses = client.compute_starknet_addresses(&[eth_address, Address::zero()]);
+
+        // Then
+        assert_eq!(kakarot.eoa_addresses.starknet_address, starknet_addresses[0]);
+        let zero_address = client.compute_starknet_address(Address::zero(), &latest).await.unwrap();
+        assert_eq!(zero_address, starknet_addresses[1]);
+    }
+
     #[rstest]
     #[tokio::test(flavor = "multi_thread")]
     async fn test_eoa_balance(kakarot_test_env_ctx: KakarotTestEnvironmentContext) {
@@ -68,6 +85,7 @@ mod tests {
                 counter_eth_address,
                 count_selector.into(),
                 BlockId::Number(reth_primitives::BlockNumberOrTag::Latest),
+                None,
             )
             .await
             .unwrap();
@@ -78,6 +96,31 @@ mod tests {
         assert_eq!(num, 1);
     }
 
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_call_at_historical_blocks(kakarot_test_env_ctx: KakarotTestEnvironmentContext) {
+        // Given
+        let (client, _, counter, counter_eth_address) = kakarot_test_env_ctx.resources_with_contract("Counter");
+        let mut block_numbers = vec![];
+        for _ in 0..2 {
+            let hash = execute_tx(&kakarot_test_env_ctx, "Counter", "inc", vec![]).await;
+            let receipt = client.transaction_receipt(hash).await.unwrap().expect("increment transaction failed");
+            block_numbers.push(u64::try_from(receipt.block_number.unwrap()).unwrap());
+        }
+        let count_selector = counter.abi.function("count").unwrap().short_signature();
+
+        // When
+        let mut counts = vec![];
+        for block_number in block_numbers {
+            let block_id = BlockId::Number(BlockNumberOrTag::Number(block_number));
+            let count = client.call(counter_eth_address, count_selector.into(), block_id, None).await.unwrap();
+            counts.push(*count.last().expect("Empty byte array"));
+        }
+
+        // Then
+        assert_eq!(vec![1, 2], counts);
+    }
+
     #[rstest]
     #[tokio::test(flavor = "multi_thread")]
     async fn test_storage_at(kakarot_test_env_ctx: KakarotTestEnvironmentContext) {
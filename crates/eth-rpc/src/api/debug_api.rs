@@ -0,0 +1,32 @@
+use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::proc_macros::rpc;
+use kakarot_rpc_core::models::trace::{BlockTraceResult, GethTrace, TracingOptions};
+use reth_primitives::{BlockId, BlockNumberOrTag, H256};
+use reth_rpc_types::CallRequest;
+
+/// Debug methods, returning geth-style traces translated from the Starknet execution traces.
+#[rpc(server, namespace = "debug")]
+#[async_trait]
+pub trait DebugApi {
+    /// Returns the trace of a transaction. Supports the default struct logger and the
+    /// `callTracer`.
+    #[method(name = "traceTransaction")]
+    async fn trace_transaction(&self, hash: H256, options: Option<TracingOptions>) -> Result<GethTrace>;
+
+    /// Executes a call on top of the given block and returns its trace.
+    #[method(name = "traceCall")]
+    async fn trace_call(
+        &self,
+        request: CallRequest,
+        block_id: Option<BlockId>,
+        options: Option<TracingOptions>,
+    ) -> Result<GethTrace>;
+
+    /// Returns the traces of all the transactions of a block.
+    #[method(name = "traceBlockByNumber")]
+    async fn trace_block_by_number(
+        &self,
+        number: BlockNumberOrTag,
+        options: Option<TracingOptions>,
+    ) -> Result<Vec<BlockTraceResult>>;
+}
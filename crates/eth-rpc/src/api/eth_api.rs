@@ -1,10 +1,11 @@
 use jsonrpsee::core::RpcResult as Result;
 use jsonrpsee::proc_macros::rpc;
+use kakarot_rpc_core::models::state_override::StateOverride;
 use reth_primitives::rpc::transaction::eip2930::AccessListWithGasUsed;
 use reth_primitives::{Address, BlockId, BlockNumberOrTag, Bytes, H256, H64, U128, U256, U64};
 use reth_rpc_types::{
     CallRequest, EIP1186AccountProofResponse, FeeHistory, Filter, FilterChanges, Index, Log, RichBlock, SyncStatus,
-    Transaction as EthTransaction, TransactionReceipt, TransactionRequest, Work,
+    Transaction as EthTransaction, TransactionReceipt, Work,
 };
 
 #[rpc(server, namespace = "eth")]
@@ -85,6 +86,10 @@ pub trait EthApi {
     #[method(name = "getTransactionReceipt")]
     async fn transaction_receipt(&self, hash: H256) -> Result<Option<TransactionReceipt>>;
 
+    /// Returns the receipts of all the transactions of a block.
+    #[method(name = "getBlockReceipts")]
+    async fn block_receipts(&self, block_id: BlockId) -> Result<Option<Vec<TransactionReceipt>>>;
+
     /// Returns the balance of the account of given address.
     #[method(name = "getBalance")]
     async fn balance(&self, address: Address, block_number: Option<BlockId>) -> Result<U256>;
@@ -106,8 +111,15 @@ pub trait EthApi {
     async fn get_logs(&self, filter: Filter) -> Result<Vec<Log>>;
 
     /// Executes a new message call immediately without creating a transaction on the block chain.
+    /// The balance, nonce, code or storage of accounts can be overridden for the duration of the
+    /// call.
     #[method(name = "call")]
-    async fn call(&self, request: CallRequest, block_id: Option<BlockId>) -> Result<Bytes>;
+    async fn call(
+        &self,
+        request: CallRequest,
+        block_id: Option<BlockId>,
+        state_override: Option<StateOverride>,
+    ) -> Result<Bytes>;
 
     /// Generates an access list for a transaction.
     ///
@@ -179,27 +191,29 @@ pub trait EthApi {
     #[method(name = "submitWork")]
     async fn submit_work(&self, nonce: H64, pow_hash: H256, mix_digest: H256) -> Result<bool>;
 
-    /// Sends transaction; will block waiting for signer to return the
-    /// transaction hash.
+    /// Signs the transaction with the key of the `from` account, which must be managed by the
+    /// RPC, and sends it. Missing nonce, gas and fees are filled in.
     #[method(name = "sendTransaction")]
-    async fn send_transaction(&self, request: TransactionRequest) -> Result<H256>;
+    async fn send_transaction(&self, request: CallRequest) -> Result<H256>;
 
     /// Sends signed transaction, returning its hash.
     #[method(name = "sendRawTransaction")]
     async fn send_raw_transaction(&self, bytes: Bytes) -> Result<H256>;
 
     /// Returns an Ethereum specific signature with: sign(keccak256("\x19Ethereum Signed Message:\n"
-    /// + len(message) + message))).
+    /// + len(message) + message))), using the key of the account, which must be managed by the RPC.
     #[method(name = "sign")]
     async fn sign(&self, address: Address, message: Bytes) -> Result<Bytes>;
 
     /// Signs a transaction that can be submitted to the network at a later time using with
-    /// `sendRawTransaction.`
+    /// `sendRawTransaction.` Missing nonce, gas and fees are filled in as for `sendTransaction`.
     #[method(name = "signTransaction")]
     async fn sign_transaction(&self, transaction: CallRequest) -> Result<Bytes>;
 
     /// Signs data via [EIP-712](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-712.md).
-    #[method(name = "signTypedData")]
+    /// Also served under the versioned names called by MetaMask and older dapps. The typed data is
+    /// accepted either as a JSON object or as a JSON string.
+    #[method(name = "signTypedData", aliases = ["eth_signTypedData_v3", "eth_signTypedData_v4"])]
     async fn sign_typed_data(&self, address: Address, data: serde_json::Value) -> Result<Bytes>;
 
     /// Returns the account and storage values of the specified account including the Merkle-proof.
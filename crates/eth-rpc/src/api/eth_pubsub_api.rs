@@ -0,0 +1,16 @@
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::proc_macros::rpc;
+use reth_rpc_types::pubsub::{Params, SubscriptionKind};
+
+/// Ethereum publish/subscribe methods, only available over WebSocket.
+#[rpc(server, namespace = "eth")]
+#[async_trait]
+pub trait EthPubSubApi {
+    /// Creates a subscription to the new heads, the logs or the new pending transactions.
+    #[subscription(
+        name = "subscribe" => "subscription",
+        unsubscribe = "unsubscribe",
+        item = reth_rpc_types::pubsub::SubscriptionResult
+    )]
+    async fn subscribe(&self, kind: SubscriptionKind, params: Option<Params>) -> SubscriptionResult;
+}
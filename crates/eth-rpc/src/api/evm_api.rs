@@ -0,0 +1,26 @@
+use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::proc_macros::rpc;
+use reth_primitives::U64;
+
+/// Test methods of Hardhat and Anvil, served by the dev network and on top of a Katana devnet.
+#[rpc(server, namespace = "evm")]
+#[async_trait]
+pub trait EvmApi {
+    /// Snapshots the state of the chain and returns the id of the snapshot.
+    #[method(name = "snapshot")]
+    async fn snapshot(&self) -> Result<U64>;
+
+    /// Reverts the state of the chain to the snapshot with the given id, discarding the later
+    /// snapshots. Returns false if there is no such snapshot.
+    #[method(name = "revert")]
+    async fn revert(&self, id: U64) -> Result<bool>;
+
+    /// Mines a block, with the given timestamp if set. Returns `0x0` as Hardhat and Anvil do.
+    #[method(name = "mine")]
+    async fn mine(&self, timestamp: Option<U64>) -> Result<String>;
+
+    /// Increases the timestamp of the next blocks by the given number of seconds and returns the
+    /// total increase.
+    #[method(name = "increaseTime")]
+    async fn increase_time(&self, seconds: U64) -> Result<U64>;
+}
@@ -0,0 +1,56 @@
+use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::proc_macros::rpc;
+use kakarot_rpc_core::models::state_override::StateOverride;
+use reth_primitives::{Address, BlockId, Bytes, H256, U256};
+use reth_rpc_types::CallRequest;
+
+/// Methods of the `eth` namespace reading or writing the state of the accounts, routed between the
+/// dev network and the forked chain in forking mode. They replace their `EthApi` counterparts.
+#[rpc(server, namespace = "eth")]
+#[async_trait]
+pub trait ForkApi {
+    /// Returns the balance of the account of given address.
+    #[method(name = "getBalance")]
+    async fn balance(&self, address: Address, block_id: Option<BlockId>) -> Result<U256>;
+
+    /// Returns the value from a storage position at a given address
+    #[method(name = "getStorageAt")]
+    async fn storage_at(&self, address: Address, index: U256, block_id: Option<BlockId>) -> Result<U256>;
+
+    /// Returns the number of transactions sent from an address at given block number.
+    #[method(name = "getTransactionCount")]
+    async fn transaction_count(&self, address: Address, block_id: Option<BlockId>) -> Result<U256>;
+
+    /// Returns code at a given address at given block number.
+    #[method(name = "getCode")]
+    async fn get_code(&self, address: Address, block_id: Option<BlockId>) -> Result<Bytes>;
+
+    /// Executes a new message call immediately without creating a transaction on the block chain.
+    #[method(name = "call")]
+    async fn call(
+        &self,
+        request: CallRequest,
+        block_id: Option<BlockId>,
+        state_override: Option<StateOverride>,
+    ) -> Result<Bytes>;
+
+    /// Signs a transaction with the key of the `from` account, which must be managed by the RPC,
+    /// and sends it to the dev network.
+    #[method(name = "sendTransaction")]
+    async fn send_transaction(&self, request: CallRequest) -> Result<H256>;
+
+    /// Sends signed transaction to the dev network, returning its hash.
+    #[method(name = "sendRawTransaction")]
+    async fn send_raw_transaction(&self, bytes: Bytes) -> Result<H256>;
+}
+
+/// Names of the methods of `ForkApi`, removed from the `eth` module in forking mode.
+pub const FORK_METHODS: &[&str] = &[
+    "eth_getBalance",
+    "eth_getStorageAt",
+    "eth_getTransactionCount",
+    "eth_getCode",
+    "eth_call",
+    "eth_sendTransaction",
+    "eth_sendRawTransaction",
+];
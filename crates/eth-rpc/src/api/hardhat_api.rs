@@ -0,0 +1,16 @@
+use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::proc_macros::rpc;
+use reth_primitives::Address;
+
+/// Impersonation methods of Hardhat, also served under their Anvil names.
+#[rpc(server, namespace = "hardhat")]
+#[async_trait]
+pub trait HardhatApi {
+    /// Sends the transactions of `eth_sendTransaction` from the account without its private key.
+    #[method(name = "impersonateAccount", aliases = ["anvil_impersonateAccount"])]
+    async fn impersonate_account(&self, address: Address) -> Result<bool>;
+
+    /// Stops impersonating the account.
+    #[method(name = "stopImpersonatingAccount", aliases = ["anvil_stopImpersonatingAccount"])]
+    async fn stop_impersonating_account(&self, address: Address) -> Result<bool>;
+}
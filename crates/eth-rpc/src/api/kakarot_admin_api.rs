@@ -0,0 +1,19 @@
+use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::proc_macros::rpc;
+use reth_primitives::U64;
+
+use crate::backfill::BackfillProgress;
+
+/// Administration methods of the optional local indexes, served when `KAKAROT_LOG_INDEX` is set.
+#[rpc(server, namespace = "kakarot")]
+#[async_trait]
+pub trait KakarotAdminApi {
+    /// Starts indexing the logs of the inclusive block range in the background, and returns the
+    /// initial progress. Fails if a backfill is already running.
+    #[method(name = "startLogBackfill")]
+    async fn start_log_backfill(&self, from_block: U64, to_block: U64) -> Result<BackfillProgress>;
+
+    /// Returns the progress of the last log backfill, with its estimated time to completion.
+    #[method(name = "logBackfillStatus")]
+    async fn log_backfill_status(&self) -> Result<Option<BackfillProgress>>;
+}
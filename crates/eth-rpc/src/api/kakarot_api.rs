@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::proc_macros::rpc;
+use kakarot_rpc_core::client::cache::CacheStats;
+use kakarot_rpc_core::models::account::{AccountDetails, DeployedAccount};
+use kakarot_rpc_core::models::block_stats::BlockStats;
+use kakarot_rpc_core::models::simulation::{SimulationRequest, TransactionSimulation};
+use reth_primitives::{Address, BlockId, H256};
+use reth_rpc_types::Transaction as EthTransaction;
+use serde_json::Value;
+use starknet::core::types::FieldElement;
+
+use crate::explorer::WithExplorerLinks;
+
+/// Kakarot specific methods, bridging the Ethereum and Starknet views of the chain.
+#[rpc(server, namespace = "kakarot")]
+#[async_trait]
+pub trait KakarotApi {
+    /// Returns the raw Starknet trace of the invoke transaction underlying the given Ethereum
+    /// transaction, unmodified.
+    #[method(name = "traceStarknetTransaction")]
+    async fn trace_starknet_transaction(&self, hash: H256) -> Result<Value>;
+
+    /// Returns the hash of the Starknet transaction wrapping the Ethereum transaction with the
+    /// given hash, i.e. the hash of the signed Ethereum transaction computed by the wallets.
+    #[method(name = "getStarknetTransactionHash")]
+    async fn get_starknet_transaction_hash(&self, hash: H256) -> Result<Option<H256>>;
+
+    /// Returns the hash of the signed Ethereum transaction wrapped in the Starknet transaction with
+    /// the given hash.
+    #[method(name = "getEthTransactionHash")]
+    async fn get_eth_transaction_hash(&self, hash: H256) -> Result<Option<H256>>;
+
+    /// Returns the Kakarot transaction wrapped in the Starknet transaction with the given hash, as
+    /// found on the Starknet explorers. `null` if the Starknet transaction doesn't wrap a Kakarot
+    /// transaction.
+    #[method(name = "getTransactionByStarknetHash")]
+    async fn get_transaction_by_starknet_hash(&self, hash: H256) -> Result<Option<EthTransaction>>;
+
+    /// Returns the address of the Starknet contract backing the given EVM address, whether it is
+    /// deployed or not. The address is computed by Kakarot from the proxy account class hash.
+    #[method(name = "getStarknetAddress")]
+    async fn get_starknet_address(&self, evm_address: Address) -> Result<FieldElement>;
+
+    /// Returns the Starknet addresses of the given EVM addresses, in the same order. The addresses
+    /// are computed by the RPC, without calling Kakarot.
+    #[method(name = "computeStarknetAddresses")]
+    async fn compute_starknet_addresses(&self, evm_addresses: Vec<Address>) -> Result<Vec<FieldElement>>;
+
+    /// Returns the EVM address of the Kakarot account deployed at the given Starknet address.
+    #[method(name = "getEvmAddress")]
+    async fn get_evm_address(&self, starknet_address: FieldElement) -> Result<Address>;
+
+    /// Returns the address of the Kakarot contract on Starknet.
+    #[method(name = "getKakarotAddress")]
+    async fn get_kakarot_address(&self) -> Result<FieldElement>;
+
+    /// Returns the accounts deployed by Kakarot, EOAs and contracts, in order of deployment. Each
+    /// account links to its Starknet contract on the explorer, when configured.
+    #[method(name = "getDeployedAccounts")]
+    async fn get_deployed_accounts(&self) -> Result<Vec<WithExplorerLinks<DeployedAccount>>>;
+
+    /// Deploys the Kakarot EOA of the EVM address from the relayer accounts, so that it can send
+    /// transactions. Returns the hash of the Starknet transaction deploying it, `null` if it is
+    /// already deployed.
+    #[method(name = "deployEoa")]
+    async fn deploy_eoa(&self, evm_address: Address) -> Result<Option<H256>>;
+
+    /// Returns whether the Kakarot account backing the EVM address is undeployed, an EOA or a
+    /// contract account, along with its Starknet address and class hashes.
+    #[method(name = "getAccountType")]
+    async fn get_account_type(
+        &self,
+        evm_address: Address,
+        block_id: Option<BlockId>,
+    ) -> Result<WithExplorerLinks<AccountDetails>>;
+
+    /// Simulates a signed raw transaction or an unsigned transaction request on top of the given
+    /// block, the latest by default, without submitting it. Returns the gas used, the return data,
+    /// the logs and the state diff of the transaction.
+    #[method(name = "simulateTransaction")]
+    async fn simulate_transaction(
+        &self,
+        request: SimulationRequest,
+        block_id: Option<BlockId>,
+    ) -> Result<TransactionSimulation>;
+
+    /// Returns the hit, miss, eviction and invalidation counters of each cache of the RPC, along
+    /// with its number of entries and capacity, to size the caches.
+    #[method(name = "cacheStats")]
+    async fn cache_stats(&self) -> Result<BTreeMap<String, CacheStats>>;
+
+    /// Returns the transaction count, gas used, average fee, failed transaction count and number
+    /// of unique senders of the Kakarot transactions of the block. `null` for the pending block.
+    #[method(name = "getBlockStats")]
+    async fn get_block_stats(&self, block_id: BlockId) -> Result<Option<BlockStats>>;
+}
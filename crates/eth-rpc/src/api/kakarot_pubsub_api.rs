@@ -0,0 +1,17 @@
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::proc_macros::rpc;
+use reth_primitives::Address;
+
+/// Kakarot specific subscriptions, only available over WebSocket.
+#[rpc(server, namespace = "kakarot")]
+#[async_trait]
+pub trait KakarotPubSubApi {
+    /// Creates a subscription to the activity of the EVM addresses in the new blocks: an item is
+    /// pushed for each transaction which an address sent, received or emitted a log in.
+    #[subscription(
+        name = "subscribeAccountActivity" => "accountActivity",
+        unsubscribe = "unsubscribeAccountActivity",
+        item = kakarot_rpc_core::models::account_activity::AccountActivity
+    )]
+    async fn subscribe_account_activity(&self, addresses: Vec<Address>) -> SubscriptionResult;
+}
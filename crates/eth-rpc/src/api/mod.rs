@@ -1,4 +1,15 @@
 pub mod alchemy_api;
+pub mod debug_api;
 pub mod eth_api;
+pub mod eth_pubsub_api;
+pub mod evm_api;
+pub mod fork_api;
+pub mod hardhat_api;
+pub mod kakarot_admin_api;
+pub mod kakarot_api;
+pub mod kakarot_pubsub_api;
 pub mod net_api;
+pub mod personal_api;
+pub mod trace_api;
+pub mod txpool_api;
 pub mod web3_api;
@@ -0,0 +1,24 @@
+use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::proc_macros::rpc;
+use reth_primitives::{Address, Bytes};
+
+/// Account methods of the `personal` namespace of geth, only served by the dev network. The
+/// accounts are created in encrypted JSON keystores and used through the signer of the RPC.
+#[rpc(server, namespace = "personal")]
+#[async_trait]
+pub trait PersonalApi {
+    /// Creates a new account in a keystore encrypted with the password and returns its address.
+    /// The account is locked until it is unlocked with `personal_unlockAccount`.
+    #[method(name = "newAccount")]
+    async fn new_account(&self, password: String) -> Result<Address>;
+
+    /// Unlocks the account for the given duration in seconds, 300 seconds when not set and until
+    /// the RPC stops for 0. An unlocked account signs the transactions of `eth_sendTransaction`.
+    #[method(name = "unlockAccount")]
+    async fn unlock_account(&self, address: Address, password: String, duration: Option<u64>) -> Result<bool>;
+
+    /// Signs the message prefixed as in EIP-191 with the key of the account, decrypted with the
+    /// password.
+    #[method(name = "sign")]
+    async fn sign(&self, message: Bytes, address: Address, password: String) -> Result<Bytes>;
+}
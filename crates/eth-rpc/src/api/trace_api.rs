@@ -0,0 +1,25 @@
+use std::collections::HashSet;
+
+use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::proc_macros::rpc;
+use kakarot_rpc_core::models::trace::{LocalizedFlatTrace, TraceResults, TraceType};
+use reth_primitives::{BlockNumberOrTag, H256};
+
+/// Trace methods, returning Parity-style flat traces translated from the Starknet execution
+/// traces, as expected by the indexers.
+#[rpc(server, namespace = "trace")]
+#[async_trait]
+pub trait TraceApi {
+    /// Returns the flat traces of a transaction, or null if the transaction is unknown.
+    #[method(name = "transaction")]
+    async fn transaction(&self, hash: H256) -> Result<Option<Vec<LocalizedFlatTrace>>>;
+
+    /// Returns the flat traces of all the transactions of a block.
+    #[method(name = "block")]
+    async fn block(&self, number: BlockNumberOrTag) -> Result<Vec<LocalizedFlatTrace>>;
+
+    /// Replays a transaction and returns the requested traces. Only the `trace` type is
+    /// supported, the `vmTrace` and the `stateDiff` are always null.
+    #[method(name = "replayTransaction")]
+    async fn replay_transaction(&self, hash: H256, trace_types: HashSet<TraceType>) -> Result<TraceResults>;
+}
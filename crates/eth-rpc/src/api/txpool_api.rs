@@ -0,0 +1,21 @@
+use jsonrpsee::core::RpcResult as Result;
+use jsonrpsee::proc_macros::rpc;
+use kakarot_rpc_core::models::txpool::{TxpoolContent, TxpoolInspect, TxpoolStatus};
+
+/// Transaction pool methods. Starknet doesn't expose its mempool, the transactions of the pending
+/// block are reported as pending and no transaction is ever queued.
+#[rpc(server, namespace = "txpool")]
+#[async_trait]
+pub trait TxPoolApi {
+    /// Returns the number of pending and queued transactions.
+    #[method(name = "status")]
+    async fn txpool_status(&self) -> Result<TxpoolStatus>;
+
+    /// Returns a summary of the pending and queued transactions, grouped by sender and nonce.
+    #[method(name = "inspect")]
+    async fn txpool_inspect(&self) -> Result<TxpoolInspect>;
+
+    /// Returns the pending and queued transactions, grouped by sender and nonce.
+    #[method(name = "content")]
+    async fn txpool_content(&self) -> Result<TxpoolContent>;
+}
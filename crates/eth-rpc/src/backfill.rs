@@ -0,0 +1,227 @@
+//! Backfill of the log index over a range of historical blocks, started through
+//! `kakarot_startLogBackfill`, so that explorers can bootstrap the index on existing chains.
+//!
+//! The range is indexed in the background by chunks of [`BACKFILL_CHUNK_SIZE`] blocks. Its
+//! progress and the estimated time to completion are returned by `kakarot_logBackfillStatus` and
+//! exported with the metrics. A failed backfill stops at the failing chunk and can be started again
+//! from its `nextBlock`.
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use eyre::{eyre, Result};
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::client::logs::block_range_chunks;
+use kakarot_rpc_core::models::encoding::quantity;
+use serde::Serialize;
+use starknet::providers::Provider;
+
+/// Number of blocks indexed at once.
+pub const BACKFILL_CHUNK_SIZE: u64 = 100;
+
+/// Status of a backfill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackfillStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progress of a backfill, returned by `kakarot_logBackfillStatus`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackfillProgress {
+    #[serde(with = "quantity")]
+    pub from_block: u64,
+    #[serde(with = "quantity")]
+    pub to_block: u64,
+    /// First block not indexed yet.
+    #[serde(with = "quantity")]
+    pub next_block: u64,
+    pub blocks_indexed: u64,
+    pub total_blocks: u64,
+    pub logs_indexed: u64,
+    pub status: BackfillStatus,
+    /// Error which stopped a failed backfill.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub elapsed_seconds: u64,
+    /// Estimated time to completion of a running backfill, from the pace of the indexed blocks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta_seconds: Option<u64>,
+}
+
+/// State of the last backfill.
+#[derive(Debug)]
+struct BackfillState {
+    from_block: u64,
+    to_block: u64,
+    next_block: u64,
+    logs_indexed: u64,
+    started_at: Instant,
+    status: BackfillStatus,
+    error: Option<String>,
+}
+
+impl BackfillState {
+    fn progress(&self) -> BackfillProgress {
+        let blocks_indexed = self.next_block - self.from_block;
+        let total_blocks = self.to_block - self.from_block + 1;
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+        let eta_seconds = (self.status == BackfillStatus::Running && blocks_indexed > 0)
+            .then(|| (elapsed * (total_blocks - blocks_indexed) as f64 / blocks_indexed as f64) as u64);
+
+        BackfillProgress {
+            from_block: self.from_block,
+            to_block: self.to_block,
+            next_block: self.next_block,
+            blocks_indexed,
+            total_blocks,
+            logs_indexed: self.logs_indexed,
+            status: self.status,
+            error: self.error.clone(),
+            elapsed_seconds: elapsed as u64,
+            eta_seconds,
+        }
+    }
+}
+
+/// Runs the log backfills, one at a time.
+#[derive(Debug, Default)]
+pub struct LogBackfill {
+    state: Arc<Mutex<Option<BackfillState>>>,
+}
+
+impl LogBackfill {
+    /// Starts indexing the logs of the inclusive block range in the background. Fails if a backfill
+    /// is already running.
+    pub fn start<P: Provider + Send + Sync + 'static>(
+        &self,
+        kakarot_client: Arc<dyn KakarotEthApi<P>>,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<BackfillProgress> {
+        if to_block < from_block {
+            return Err(eyre!("invalid block range {from_block}..={to_block}"));
+        }
+        if !kakarot_client.log_index_enabled() {
+            return Err(eyre!("the log index is disabled, set KAKAROT_LOG_INDEX to enable it"));
+        }
+
+        let progress = {
+            let mut state = self.state.lock().expect("backfill state poisoned");
+            if let Some(running) = state.as_ref().filter(|state| state.status == BackfillStatus::Running) {
+                return Err(eyre!(
+                    "a log backfill of the blocks {}..={} is already running",
+                    running.from_block,
+                    running.to_block
+                ));
+            }
+            let new_state = BackfillState {
+                from_block,
+                to_block,
+                next_block: from_block,
+                logs_indexed: 0,
+                started_at: Instant::now(),
+                status: BackfillStatus::Running,
+                error: None,
+            };
+            let progress = new_state.progress();
+            *state = Some(new_state);
+            progress
+        };
+
+        let shared_state = Arc::clone(&self.state);
+        tokio::spawn(async move {
+            for (chunk_from, chunk_to) in block_range_chunks(from_block, to_block, BACKFILL_CHUNK_SIZE) {
+                let result = kakarot_client.index_logs(chunk_from, chunk_to).await;
+
+                let mut guard = shared_state.lock().expect("backfill state poisoned");
+                let Some(state) = guard.as_mut() else { return };
+                match result {
+                    Ok(logs_indexed) => {
+                        state.next_block = chunk_to + 1;
+                        state.logs_indexed += logs_indexed as u64;
+                    }
+                    Err(err) => {
+                        tracing::warn!("Log backfill failed at the blocks {chunk_from}..={chunk_to}: {err}");
+                        state.status = BackfillStatus::Failed;
+                        state.error = Some(err.to_string());
+                        return;
+                    }
+                }
+            }
+            if let Some(state) = shared_state.lock().expect("backfill state poisoned").as_mut() {
+                state.status = BackfillStatus::Completed;
+                tracing::info!("Log backfill of the blocks {from_block}..={to_block} completed");
+            }
+        });
+
+        Ok(progress)
+    }
+
+    /// Returns the progress of the last backfill, if any.
+    pub fn progress(&self) -> Option<BackfillProgress> {
+        self.state.lock().expect("backfill state poisoned").as_ref().map(BackfillState::progress)
+    }
+
+    /// Encodes the progress of the last backfill in the Prometheus text format.
+    pub fn encode_prometheus(&self) -> String {
+        let Some(progress) = self.progress() else {
+            return String::new();
+        };
+
+        let mut encoded = String::new();
+        let _ = writeln!(encoded, "# HELP kakarot_log_backfill_blocks Blocks of the log backfill, by state.");
+        let _ = writeln!(encoded, "# TYPE kakarot_log_backfill_blocks gauge");
+        let _ = writeln!(encoded, "kakarot_log_backfill_blocks{{state=\"indexed\"}} {}", progress.blocks_indexed);
+        let _ = writeln!(encoded, "kakarot_log_backfill_blocks{{state=\"total\"}} {}", progress.total_blocks);
+        let _ = writeln!(encoded, "# HELP kakarot_log_backfill_logs Logs indexed by the log backfill.");
+        let _ = writeln!(encoded, "# TYPE kakarot_log_backfill_logs gauge");
+        let _ = writeln!(encoded, "kakarot_log_backfill_logs {}", progress.logs_indexed);
+        let _ = writeln!(encoded, "# HELP kakarot_log_backfill_running Whether the log backfill is running.");
+        let _ = writeln!(encoded, "# TYPE kakarot_log_backfill_running gauge");
+        let _ =
+            writeln!(encoded, "kakarot_log_backfill_running {}", u8::from(progress.status == BackfillStatus::Running));
+        if let Some(eta_seconds) = progress.eta_seconds {
+            let _ = writeln!(
+                encoded,
+                "# HELP kakarot_log_backfill_eta_seconds Estimated time to completion of the log backfill."
+            );
+            let _ = writeln!(encoded, "# TYPE kakarot_log_backfill_eta_seconds gauge");
+            let _ = writeln!(encoded, "kakarot_log_backfill_eta_seconds {eta_seconds}");
+        }
+        encoded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backfill_progress() {
+        // Given
+        let state = BackfillState {
+            from_block: 100,
+            to_block: 399,
+            next_block: 200,
+            logs_indexed: 42,
+            started_at: Instant::now(),
+            status: BackfillStatus::Running,
+            error: None,
+        };
+
+        // When
+        let progress = state.progress();
+
+        // Then
+        assert_eq!(100, progress.blocks_indexed);
+        assert_eq!(300, progress.total_blocks);
+        assert!(progress.eta_seconds.is_some());
+        let completed = BackfillState { next_block: 400, status: BackfillStatus::Completed, ..state }.progress();
+        assert_eq!(None, completed.eta_seconds);
+        assert_eq!(300, completed.blocks_indexed);
+    }
+}
@@ -0,0 +1,275 @@
+//! Discovery of the methods served by this deployment, through `rpc_modules` and
+//! `kakarot_capabilities`. Both are generated from the methods actually registered on the server.
+//!
+//! The optional subsystems, e.g. the filters or the block indexer, are tracked in a
+//! [`CapabilityRegistry`]. The methods depending on a disabled subsystem stay registered but fail
+//! with a [`feature_disabled`] error naming it, so that clients can tell a disabled feature from a
+//! missing method.
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use jsonrpsee::types::ErrorObject;
+use jsonrpsee::RpcModule;
+use kakarot_rpc_core::client::errors::{rpc_err, ConfigError, EthRpcErrorCode};
+use serde::Serialize;
+use serde_json::json;
+
+/// Version reported for every namespace by `rpc_modules`, following geth.
+pub const MODULE_VERSION: &str = "1.0";
+
+/// Methods registered to follow the Ethereum JSON-RPC specification but which always fail, either
+/// because they have no meaning for Kakarot or because they aren't implemented yet. They aren't
+/// advertised as capabilities.
+pub const UNSUPPORTED_METHODS: &[&str] = &["eth_createAccessList", "eth_getWork", "eth_getProof"];
+
+/// Features which are always served, and the method whose registration enables them.
+const FEATURES: &[(&str, &str)] = &[
+    ("starknetTracing", "kakarot_traceStarknetTransaction"),
+    ("evmTracing", "debug_traceTransaction"),
+    ("parityTracing", "trace_transaction"),
+];
+
+/// Optional subsystem of the RPC, which can be disabled by configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Capability {
+    /// Polling filters, `eth_newFilter` and co.
+    Filters,
+    /// WebSocket subscriptions, `eth_subscribe`.
+    Subscriptions,
+    /// Store of the blocks tailed by the background indexer.
+    Indexer,
+    /// Index of the logs of the client, administered through the `kakarot` admin methods.
+    LogIndex,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 4] = [Self::Filters, Self::Subscriptions, Self::Indexer, Self::LogIndex];
+
+    /// Name of the capability, as reported by `kakarot_capabilities` and in the errors.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Filters => "filters",
+            Self::Subscriptions => "subscriptions",
+            Self::Indexer => "indexer",
+            Self::LogIndex => "logIndex",
+        }
+    }
+
+    /// Methods which can't be served without the capability. The indexer has none, the blocks
+    /// which aren't indexed being served from Starknet.
+    pub fn methods(&self) -> &'static [&'static str] {
+        match self {
+            Self::Filters => &[
+                "eth_newFilter",
+                "eth_newBlockFilter",
+                "eth_newPendingTransactionFilter",
+                "eth_getFilterChanges",
+                "eth_getFilterLogs",
+                "eth_uninstallFilter",
+            ],
+            Self::Subscriptions => &["eth_subscribe", "eth_unsubscribe"],
+            Self::Indexer => &[],
+            Self::LogIndex => &["kakarot_startLogBackfill", "kakarot_logBackfillStatus"],
+        }
+    }
+}
+
+impl FromStr for Capability {
+    type Err = ConfigError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|capability| capability.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = Self::ALL.iter().map(Capability::name).collect();
+            ConfigError::EnvironmentVariableSetWrong(format!(
+                "unknown feature {name}, expected one of {}",
+                names.join(", ")
+            ))
+        })
+    }
+}
+
+/// Parses the comma separated list of features disabled by configuration, e.g.
+/// `filters,subscriptions`.
+pub fn parse_disabled_features(features: &str) -> Result<Vec<Capability>, ConfigError> {
+    features.split(',').map(str::trim).filter(|feature| !feature.is_empty()).map(str::parse).collect()
+}
+
+/// Capabilities enabled on this deployment. The filters and the subscriptions are enabled by
+/// default, the indexer and the log index once they are configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityRegistry {
+    enabled: BTreeSet<Capability>,
+}
+
+impl Default for CapabilityRegistry {
+    fn default() -> Self {
+        Self { enabled: BTreeSet::from([Capability::Filters, Capability::Subscriptions]) }
+    }
+}
+
+impl CapabilityRegistry {
+    pub fn enable(&mut self, capability: Capability) {
+        self.enabled.insert(capability);
+    }
+
+    pub fn disable(&mut self, capability: Capability) {
+        self.enabled.remove(&capability);
+    }
+
+    pub fn is_enabled(&self, capability: Capability) -> bool {
+        self.enabled.contains(&capability)
+    }
+
+    /// Returns the disabled capabilities, sorted.
+    pub fn disabled(&self) -> impl Iterator<Item = Capability> + '_ {
+        Capability::ALL.into_iter().filter(|capability| !self.is_enabled(*capability))
+    }
+
+    /// Returns the capability the method depends on, if it is disabled.
+    pub fn disabled_by(&self, method: &str) -> Option<Capability> {
+        self.disabled().find(|capability| capability.methods().contains(&method))
+    }
+
+    /// Fails with a [`feature_disabled`] error if the capability is disabled.
+    pub fn ensure(&self, capability: Capability) -> Result<(), ErrorObject<'static>> {
+        if self.is_enabled(capability) { Ok(()) } else { Err(feature_disabled(capability)) }
+    }
+}
+
+/// Transports served by the RPC server.
+const TRANSPORTS: &[&str] = &["http", "ws"];
+
+/// Capabilities of the deployment, returned by `kakarot_capabilities`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    /// Version of the Kakarot RPC.
+    pub version: String,
+    /// Namespaces served, with their version.
+    pub modules: BTreeMap<String, String>,
+    /// Supported methods, sorted.
+    pub methods: Vec<String>,
+    /// Optional features enabled on this deployment.
+    pub features: Vec<String>,
+    /// Optional features disabled on this deployment, whose methods fail with a
+    /// [`feature_disabled`] error.
+    pub disabled_features: Vec<String>,
+    /// Transports the methods are served over. Subscriptions are only available over WebSocket.
+    pub transports: Vec<String>,
+}
+
+impl Capabilities {
+    /// Builds the capabilities from the names of the registered methods and the registry of the
+    /// optional features.
+    pub fn from_methods<'a>(method_names: impl IntoIterator<Item = &'a str>, registry: &CapabilityRegistry) -> Self {
+        let methods: BTreeSet<&str> = method_names
+            .into_iter()
+            .filter(|method| !UNSUPPORTED_METHODS.contains(method) && registry.disabled_by(method).is_none())
+            .collect();
+
+        let modules = methods
+            .iter()
+            .filter_map(|method| method.split_once('_'))
+            .map(|(namespace, _)| (namespace.to_string(), MODULE_VERSION.to_string()))
+            .collect();
+        let features = Capability::ALL
+            .iter()
+            .filter(|capability| registry.is_enabled(**capability))
+            .map(Capability::name)
+            .chain(FEATURES.iter().filter(|(_, method)| methods.contains(method)).map(|(feature, _)| *feature))
+            .map(str::to_string)
+            .collect();
+        let disabled_features = registry.disabled().map(|capability| capability.name().to_string()).collect();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            modules,
+            methods: methods.into_iter().map(str::to_string).collect(),
+            features,
+            disabled_features,
+            transports: TRANSPORTS.iter().map(|transport| transport.to_string()).collect(),
+        }
+    }
+}
+
+/// Registers `rpc_modules` and `kakarot_capabilities` on the module. Must be called once all the
+/// other methods are registered, as the capabilities are computed at registration time.
+pub fn register_capabilities(
+    rpc_module: &mut RpcModule<()>,
+    registry: &CapabilityRegistry,
+) -> Result<(), jsonrpsee::core::Error> {
+    let method_names = rpc_module.method_names().chain(["rpc_modules", "kakarot_capabilities"]);
+    let capabilities = Capabilities::from_methods(method_names, registry);
+    let modules = capabilities.modules.clone();
+
+    rpc_module.register_method("rpc_modules", move |_, _| Ok::<_, ErrorObject<'static>>(modules.clone()))?;
+    rpc_module
+        .register_method("kakarot_capabilities", move |_, _| Ok::<_, ErrorObject<'static>>(capabilities.clone()))?;
+
+    Ok(())
+}
+
+/// Error returned by the methods which are not supported by Kakarot.
+pub fn unsupported_method(method: &str) -> ErrorObject<'static> {
+    rpc_err(
+        EthRpcErrorCode::MethodNotFound as i32,
+        format!("Unsupported method: {method}. Call kakarot_capabilities to list the supported methods"),
+    )
+}
+
+/// Error returned by the methods depending on a disabled capability. The name of the capability
+/// is in the `capability` field of the data of the error.
+pub fn feature_disabled(capability: Capability) -> ErrorObject<'static> {
+    ErrorObject::owned(
+        EthRpcErrorCode::MethodNotFound as i32,
+        format!("Feature disabled on this endpoint: {}", capability.name()),
+        Some(json!({ "capability": capability.name() })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_from_methods() {
+        // Given
+        let method_names =
+            ["eth_blockNumber", "eth_newFilter", "eth_subscribe", "eth_getWork", "net_version", "rpc_modules"];
+        let mut registry = CapabilityRegistry::default();
+        registry.disable(Capability::Filters);
+
+        // When
+        let capabilities = Capabilities::from_methods(method_names, &registry);
+
+        // Then
+        assert_eq!(vec!["eth_blockNumber", "eth_subscribe", "net_version", "rpc_modules"], capabilities.methods);
+        assert_eq!(vec!["eth", "net", "rpc"], capabilities.modules.keys().collect::<Vec<_>>());
+        assert_eq!(vec!["subscriptions"], capabilities.features);
+        assert_eq!(vec!["filters", "indexer", "logIndex"], capabilities.disabled_features);
+    }
+
+    #[test]
+    fn test_capability_registry() {
+        // Given
+        let mut registry = CapabilityRegistry::default();
+
+        // When
+        registry.disable(Capability::Subscriptions);
+        registry.enable(Capability::Indexer);
+
+        // Then
+        assert!(registry.ensure(Capability::Filters).is_ok());
+        assert!(registry.ensure(Capability::Indexer).is_ok());
+        let err = registry.ensure(Capability::Subscriptions).unwrap_err();
+        assert_eq!(Some(r#"{"capability":"subscriptions"}"#), err.data().map(|data| data.get()));
+        assert_eq!(Some(Capability::Subscriptions), registry.disabled_by("eth_subscribe"));
+        assert_eq!(Some(Capability::LogIndex), registry.disabled_by("kakarot_logBackfillStatus"));
+        assert_eq!(None, registry.disabled_by("eth_newFilter"));
+        assert_eq!(
+            vec![Capability::Filters, Capability::Subscriptions],
+            parse_disabled_features("filters, subscriptions").unwrap()
+        );
+        assert!(parse_disabled_features("mempool").is_err());
+    }
+}
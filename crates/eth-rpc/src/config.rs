@@ -1,17 +1,642 @@
-use eyre::{eyre, Result};
+//! Configuration of the RPC binary.
+//!
+//! Every setting is read from an environment variable by the `from_env` constructors of the
+//! configurations. The settings can also be given in a TOML or YAML configuration file and as
+//! command line flags, which are translated to their environment variables before the
+//! configurations are loaded, the same way `dotenv` loads a `.env` file. The flags override the
+//! environment, which overrides the configuration file.
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand};
+use eyre::{eyre, Result, WrapErr};
+use kakarot_rpc_core::client::relayer::RelayerAccount;
+use serde::Deserialize;
+use starknet::core::types::FieldElement;
+use url::Url;
+
+use crate::capabilities::{parse_disabled_features, Capability};
+use crate::export::ExportFormat;
+use crate::metrics::Metrics;
+use crate::middleware::audit::AuditLog;
+use crate::middleware::batch::BatchConfig;
+use crate::middleware::cors::CorsConfig;
+use crate::middleware::limits::LimitsConfig;
+use crate::middleware::params::ParamsMode;
+use crate::middleware::priority::PriorityConfig;
+use crate::middleware::shadow::ShadowConfig;
+use crate::middleware::standby::Standby;
+use crate::tls::TlsConfig;
+use crate::webhooks::parse_webhook_urls;
 
 pub struct RPCConfig {
     pub socket_addr: String,
+    /// Optional mirroring of the read traffic to a shadow backend.
+    pub shadow: Option<ShadowConfig>,
+    /// Sizes of the pools serving each priority class of methods.
+    pub priority: PriorityConfig,
+    /// Validation mode of the params of the requests.
+    pub params_mode: ParamsMode,
+    /// Registry of the metrics served on `/metrics`, disabled when `None`.
+    pub metrics: Option<Arc<Metrics>>,
+    /// Size and rate limits of the requests.
+    pub limits: LimitsConfig,
+    /// Concurrency of the calls of the batch requests.
+    pub batch: BatchConfig,
+    /// Disables the state-changing methods, see [`crate::rpc::STATE_CHANGING_METHODS`].
+    pub read_only: bool,
+    /// Optional features disabled on this endpoint, whose methods fail with a structured error.
+    pub disabled_features: Vec<Capability>,
+    /// Mode of a standby instance, rejecting the JSON-RPC calls until promoted, active when `None`.
+    pub standby: Option<Arc<Standby>>,
+    /// Audit log of the state-changing calls, disabled when `None`.
+    pub audit_log: Option<Arc<AuditLog>>,
+    /// Origins allowed to call the RPC from a browser.
+    pub cors: CorsConfig,
+    /// Certificate and key of the server, serving `https://` and `wss://` when set.
+    pub tls: Option<TlsConfig>,
 }
 
 impl RPCConfig {
     pub fn new(socket_addr: String) -> RPCConfig {
-        RPCConfig { socket_addr }
+        RPCConfig {
+            socket_addr,
+            shadow: None,
+            priority: PriorityConfig::default(),
+            params_mode: ParamsMode::default(),
+            metrics: None,
+            limits: LimitsConfig::default(),
+            batch: BatchConfig::default(),
+            read_only: false,
+            disabled_features: Vec::new(),
+            standby: None,
+            audit_log: None,
+            cors: CorsConfig::default(),
+            tls: None,
+        }
     }
 
     pub fn from_env() -> Result<Self> {
         let socket_addr = std::env::var("KAKAROT_HTTP_RPC_ADDRESS")
             .map_err(|_| eyre!("Missing mandatory environment variable: KAKAROT_HTTP_RPC_ADDRESS"))?;
-        Ok(RPCConfig::new(socket_addr))
+        let shadow = ShadowConfig::from_env()?;
+        let priority = PriorityConfig::from_env()?;
+        let params_mode = ParamsMode::from_env()?;
+        let metrics = Metrics::from_env();
+        let limits = LimitsConfig::from_env()?;
+        let batch = BatchConfig::from_env()?;
+        let read_only = std::env::var("KAKAROT_READ_ONLY").map(|v| v.to_lowercase() == "true").unwrap_or(false);
+        let disabled_features = match std::env::var("KAKAROT_DISABLED_FEATURES") {
+            Ok(features) => parse_disabled_features(&features)?,
+            Err(_) => Vec::new(),
+        };
+        let standby = Standby::from_env();
+        let audit_log = AuditLog::from_env()?;
+        let cors = CorsConfig::from_env()?;
+        let tls = TlsConfig::from_env()?;
+        Ok(RPCConfig {
+            shadow,
+            priority,
+            params_mode,
+            metrics,
+            limits,
+            batch,
+            read_only,
+            disabled_features,
+            standby,
+            audit_log,
+            cors,
+            tls,
+            ..RPCConfig::new(socket_addr)
+        })
+    }
+}
+
+/// Command line of the RPC binary.
+#[derive(Debug, Default, Parser)]
+#[command(name = "kakarot-rpc", version, about = "Ethereum JSON-RPC server for Kakarot, the zkEVM on Starknet")]
+pub struct Cli {
+    /// Path of a TOML or YAML configuration file, see `kakarot-rpc.example.toml`.
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+    /// Start an embedded Katana sequencer with Kakarot and funded dev accounts, requires the `dev`
+    /// feature.
+    #[arg(long)]
+    pub dev: bool,
+    /// Address the RPC server listens on [env: KAKAROT_HTTP_RPC_ADDRESS].
+    #[arg(long)]
+    pub rpc_address: Option<SocketAddr>,
+    /// Starknet network: katana, madara, sharingan, mainnet, goerli1, goerli2, testnet or the URL
+    /// of a JSON-RPC provider [env: STARKNET_NETWORK].
+    #[arg(long)]
+    pub starknet_network: Option<String>,
+    /// Address of the Kakarot contract on Starknet [env: KAKAROT_ADDRESS].
+    #[arg(long)]
+    pub kakarot_address: Option<String>,
+    /// Class hash of the proxy of the Kakarot accounts [env: PROXY_ACCOUNT_CLASS_HASH].
+    #[arg(long)]
+    pub proxy_account_class_hash: Option<String>,
+    /// Disable the methods changing the state of the chain or of the node, e.g. the transaction
+    /// relay [env: KAKAROT_READ_ONLY].
+    #[arg(long)]
+    pub read_only: bool,
+    /// Start as a standby, following the chain but rejecting the JSON-RPC calls until promoted
+    /// with `kakarot_promote` [env: KAKAROT_STANDBY].
+    #[arg(long)]
+    pub standby: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Commands run instead of the RPC server.
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    /// Export the blocks of the index configured by `KAKAROT_INDEXER_STORE` to a snapshot file.
+    ExportIndex {
+        /// Path of the snapshot file to write.
+        path: PathBuf,
+    },
+    /// Import a snapshot file into the index configured by `KAKAROT_INDEXER_STORE`. The RPC must
+    /// not be running on the same store.
+    ImportIndex {
+        /// Path of the snapshot file to read.
+        path: PathBuf,
+    },
+    /// Export the blocks of a range, their transactions and their receipts, as served by the RPC,
+    /// to NDJSON or CSV files.
+    Export {
+        /// First block to export.
+        #[arg(long)]
+        from_block: u64,
+        /// Last block to export, defaults to the latest block.
+        #[arg(long)]
+        to_block: Option<u64>,
+        #[arg(long, value_enum, default_value_t)]
+        format: ExportFormat,
+        /// Directory of the `blocks`, `transactions` and `receipts` files.
+        #[arg(long, default_value = ".")]
+        output: PathBuf,
+    },
+    /// Replay a captured request against the configured network, recording the Starknet calls
+    /// and the logs of the client in a diagnostic bundle.
+    Replay {
+        /// Path of the captured request: a JSON object with the `method`, the `params` and the
+        /// `block` the request was served at.
+        request: PathBuf,
+        /// Block the request is pinned to, overriding the block of the captured request.
+        #[arg(long)]
+        block: Option<u64>,
+        /// Path of the diagnostic bundle to write.
+        #[arg(long, default_value = "replay-bundle.json")]
+        output: PathBuf,
+    },
+    /// Run a suite of requests against the configured network and print a pass/fail report.
+    Selftest {
+        /// Account whose balance and code are read, and which is called by `eth_call`
+        /// [env: KAKAROT_SELFTEST_ACCOUNT].
+        #[arg(long)]
+        account: Option<String>,
+        /// Hex calldata of the `eth_call` check [env: KAKAROT_SELFTEST_CALL_DATA].
+        #[arg(long)]
+        call_data: Option<String>,
+    },
+}
+
+impl Cli {
+    /// Returns the environment variables set by the flags.
+    fn variables(&self) -> Result<Vec<(String, String)>> {
+        let mut variables = Vec::new();
+        if let Some(rpc_address) = self.rpc_address {
+            variables.push(("KAKAROT_HTTP_RPC_ADDRESS".to_string(), rpc_address.to_string()));
+        }
+        if let Some(network) = &self.starknet_network {
+            variables.push(("STARKNET_NETWORK".to_string(), validate_network(network)?));
+        }
+        if let Some(kakarot_address) = &self.kakarot_address {
+            variables.push(("KAKAROT_ADDRESS".to_string(), validate_felt("--kakarot-address", kakarot_address)?));
+        }
+        if let Some(class_hash) = &self.proxy_account_class_hash {
+            variables.push((
+                "PROXY_ACCOUNT_CLASS_HASH".to_string(),
+                validate_felt("--proxy-account-class-hash", class_hash)?,
+            ));
+        }
+        if self.read_only {
+            variables.push(("KAKAROT_READ_ONLY".to_string(), "true".to_string()));
+        }
+        if self.standby {
+            variables.push(("KAKAROT_STANDBY".to_string(), "true".to_string()));
+        }
+        if let Some(Command::Selftest { account, call_data }) = &self.command {
+            if let Some(account) = account {
+                variables.push(("KAKAROT_SELFTEST_ACCOUNT".to_string(), account.clone()));
+            }
+            if let Some(call_data) = call_data {
+                variables.push(("KAKAROT_SELFTEST_CALL_DATA".to_string(), call_data.clone()));
+            }
+        }
+        Ok(variables)
+    }
+}
+
+/// `[server]` section of the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerSection {
+    /// Address the RPC server listens on.
+    pub address: Option<SocketAddr>,
+    /// Disables the state-changing methods.
+    pub read_only: Option<bool>,
+    /// Starts as a standby, serving the JSON-RPC methods once promoted.
+    pub standby: Option<bool>,
+    /// Path of the audit log of the state-changing calls.
+    pub audit_log: Option<PathBuf>,
+    /// Optional features disabled on this endpoint, e.g. `["filters", "subscriptions"]`.
+    pub disabled_features: Option<Vec<String>>,
+    /// Origins allowed to call the RPC from a browser, e.g. `["https://app.kakarot.org"]`.
+    pub cors_allowed_origins: Option<Vec<String>>,
+    /// PEM file of the certificate chain of the server.
+    pub tls_cert: Option<PathBuf>,
+    /// PEM file of the private key of the server.
+    pub tls_key: Option<PathBuf>,
+}
+
+/// `[starknet]` section of the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StarknetSection {
+    /// Starknet network, see [`Cli::starknet_network`].
+    pub network: Option<String>,
+    /// URLs of the Starknet JSON-RPC endpoints to fail over to.
+    pub fallback_urls: Option<Vec<Url>>,
+    /// Maximum number of concurrent requests to the Starknet node.
+    pub max_concurrent_requests: Option<usize>,
+}
+
+/// `[kakarot]` section of the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KakarotSection {
+    /// Address of the Kakarot contract on Starknet.
+    pub address: Option<String>,
+    /// Class hash of the proxy of the Kakarot accounts.
+    pub proxy_account_class_hash: Option<String>,
+    /// Chain id, in decimal or hex, or `starknet`.
+    pub chain_id: Option<String>,
+    /// JSON file of the chain parameters of the deployment.
+    pub chain_spec: Option<PathBuf>,
+    /// Address of the native token of Kakarot on Starknet.
+    pub native_token_address: Option<String>,
+    /// Address of the token paying the Starknet fees, when it differs from the native token.
+    pub fee_token_address: Option<String>,
+}
+
+/// `[relayer]` section of the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RelayerSection {
+    /// Starknet accounts of the relayer, as `<address>:<private key>` hex pairs.
+    pub accounts: Option<Vec<String>>,
+    /// Number of retries of a submission rejected by the Starknet node.
+    pub max_retries: Option<usize>,
+    /// Whether the Kakarot account of a sender is deployed before relaying its first transaction.
+    pub auto_deploy_eoa: Option<bool>,
+}
+
+/// `[webhooks]` section of the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhooksSection {
+    /// URLs notified of the lifecycle of the relayed transactions.
+    pub urls: Option<Vec<String>>,
+    /// Number of retries of a failed delivery.
+    pub max_retries: Option<u32>,
+}
+
+/// Configuration file of the RPC. The settings without a section of their own are set in the
+/// `[env]` table, by the name of their environment variable.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub server: ServerSection,
+    #[serde(default)]
+    pub starknet: StarknetSection,
+    #[serde(default)]
+    pub kakarot: KakarotSection,
+    #[serde(default)]
+    pub relayer: RelayerSection,
+    #[serde(default)]
+    pub webhooks: WebhooksSection,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl ConfigFile {
+    /// Reads the configuration file, parsed as YAML for the `.yaml` and `.yml` extensions and as
+    /// TOML otherwise.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read the configuration file {}", path.display()))?;
+        let is_yaml = path.extension().is_some_and(|extension| extension == "yaml" || extension == "yml");
+        let config = if is_yaml { Self::from_yaml(&content) } else { Self::from_toml(&content) };
+        config.wrap_err_with(|| format!("Invalid configuration file {}", path.display()))
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(content)?)
+    }
+
+    /// Returns the environment variables set by the configuration file, after validating them.
+    fn variables(&self) -> Result<Vec<(String, String)>> {
+        let mut variables: Vec<(String, String)> = self.env.clone().into_iter().collect();
+        if let Some(address) = self.server.address {
+            variables.push(("KAKAROT_HTTP_RPC_ADDRESS".to_string(), address.to_string()));
+        }
+        if let Some(read_only) = self.server.read_only {
+            variables.push(("KAKAROT_READ_ONLY".to_string(), read_only.to_string()));
+        }
+        if let Some(standby) = self.server.standby {
+            variables.push(("KAKAROT_STANDBY".to_string(), standby.to_string()));
+        }
+        if let Some(audit_log) = &self.server.audit_log {
+            variables.push(("KAKAROT_AUDIT_LOG".to_string(), audit_log.display().to_string()));
+        }
+        if let Some(disabled_features) = &self.server.disabled_features {
+            let disabled_features = disabled_features.join(",");
+            parse_disabled_features(&disabled_features)?;
+            variables.push(("KAKAROT_DISABLED_FEATURES".to_string(), disabled_features));
+        }
+        if let Some(origins) = &self.server.cors_allowed_origins {
+            let origins = origins.join(",");
+            origins.parse::<CorsConfig>()?;
+            variables.push(("KAKAROT_CORS_ALLOWED_ORIGINS".to_string(), origins));
+        }
+        match (&self.server.tls_cert, &self.server.tls_key) {
+            (Some(cert), Some(key)) => {
+                variables.push(("KAKAROT_TLS_CERT".to_string(), cert.display().to_string()));
+                variables.push(("KAKAROT_TLS_KEY".to_string(), key.display().to_string()));
+            }
+            (None, None) => {}
+            _ => return Err(eyre!("server.tls_cert and server.tls_key should be set together")),
+        }
+        if let Some(network) = &self.starknet.network {
+            variables.push(("STARKNET_NETWORK".to_string(), validate_network(network)?));
+        }
+        if let Some(fallback_urls) = &self.starknet.fallback_urls {
+            let fallback_urls: Vec<&str> = fallback_urls.iter().map(Url::as_str).collect();
+            variables.push(("STARKNET_FALLBACK_RPC_URLS".to_string(), fallback_urls.join(",")));
+        }
+        if let Some(max_concurrent_requests) = self.starknet.max_concurrent_requests {
+            if max_concurrent_requests == 0 {
+                return Err(eyre!("starknet.max_concurrent_requests should be positive"));
+            }
+            variables.push(("STARKNET_RPC_MAX_CONCURRENT_REQUESTS".to_string(), max_concurrent_requests.to_string()));
+        }
+        if let Some(address) = &self.kakarot.address {
+            variables.push(("KAKAROT_ADDRESS".to_string(), validate_felt("kakarot.address", address)?));
+        }
+        if let Some(class_hash) = &self.kakarot.proxy_account_class_hash {
+            variables.push((
+                "PROXY_ACCOUNT_CLASS_HASH".to_string(),
+                validate_felt("kakarot.proxy_account_class_hash", class_hash)?,
+            ));
+        }
+        if let Some(native_token_address) = &self.kakarot.native_token_address {
+            variables.push((
+                "KAKAROT_NATIVE_TOKEN_ADDRESS".to_string(),
+                validate_felt("kakarot.native_token_address", native_token_address)?,
+            ));
+        }
+        if let Some(fee_token_address) = &self.kakarot.fee_token_address {
+            variables.push((
+                "KAKAROT_FEE_TOKEN_ADDRESS".to_string(),
+                validate_felt("kakarot.fee_token_address", fee_token_address)?,
+            ));
+        }
+        if let Some(chain_id) = &self.kakarot.chain_id {
+            variables.push(("KAKAROT_CHAIN_ID".to_string(), chain_id.clone()));
+        }
+        if let Some(chain_spec) = &self.kakarot.chain_spec {
+            variables.push(("KAKAROT_CHAIN_SPEC".to_string(), chain_spec.display().to_string()));
+        }
+        if let Some(accounts) = &self.relayer.accounts {
+            for account in accounts {
+                account.parse::<RelayerAccount>()?;
+            }
+            variables.push(("KAKAROT_RELAYER_ACCOUNTS".to_string(), accounts.join(",")));
+        }
+        if let Some(max_retries) = self.relayer.max_retries {
+            variables.push(("KAKAROT_RELAYER_MAX_RETRIES".to_string(), max_retries.to_string()));
+        }
+        if let Some(auto_deploy_eoa) = self.relayer.auto_deploy_eoa {
+            variables.push(("KAKAROT_RELAYER_AUTO_DEPLOY_EOA".to_string(), auto_deploy_eoa.to_string()));
+        }
+        if let Some(urls) = &self.webhooks.urls {
+            let urls = urls.join(",");
+            parse_webhook_urls(&urls)?;
+            variables.push(("KAKAROT_WEBHOOK_URLS".to_string(), urls));
+        }
+        if let Some(max_retries) = self.webhooks.max_retries {
+            variables.push(("KAKAROT_WEBHOOK_MAX_RETRIES".to_string(), max_retries.to_string()));
+        }
+        Ok(variables)
+    }
+}
+
+/// Checks that the network is a known network or a URL.
+fn validate_network(network: &str) -> Result<String> {
+    const NETWORKS: [&str; 7] = ["katana", "madara", "sharingan", "mainnet", "goerli1", "goerli2", "testnet"];
+    if !NETWORKS.contains(&network.to_lowercase().as_str()) {
+        Url::parse(network)
+            .map_err(|_| eyre!("Starknet network should be one of {} or a URL, got {network}", NETWORKS.join(", ")))?;
+    }
+    Ok(network.to_string())
+}
+
+/// Checks that the value is a hex felt.
+fn validate_felt(name: &str, value: &str) -> Result<String> {
+    FieldElement::from_hex_be(value).map_err(|_| eyre!("{name} should be a hex string, got {value}"))?;
+    Ok(value.to_string())
+}
+
+/// Loads the configuration file and the flags of the command line into the environment, from
+/// which the configurations of the RPC are then read. The variables of the configuration file
+/// don't replace the variables already set, the flags do.
+pub fn load_config(cli: &Cli) -> Result<()> {
+    if let Some(path) = &cli.config {
+        for (name, value) in ConfigFile::from_file(path)?.variables()? {
+            if std::env::var_os(&name).is_none() {
+                std::env::set_var(name, value);
+            }
+        }
+    }
+    for (name, value) in cli.variables()? {
+        std::env::set_var(name, value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_file_variables() {
+        // Given
+        let toml = r#"
+            [server]
+            address = "0.0.0.0:3030"
+            standby = true
+            audit_log = "/var/log/kakarot-rpc/audit.jsonl"
+            disabled_features = ["filters", "subscriptions"]
+            cors_allowed_origins = ["https://app.kakarot.org", "http://localhost:3000"]
+            tls_cert = "/etc/kakarot-rpc/cert.pem"
+            tls_key = "/etc/kakarot-rpc/key.pem"
+
+            [starknet]
+            network = "katana"
+            fallback_urls = ["http://localhost:5050", "http://localhost:5051"]
+            max_concurrent_requests = 32
+
+            [kakarot]
+            address = "0x1234"
+            chain_id = "starknet"
+            chain_spec = "chain-spec.json"
+
+            [relayer]
+            accounts = ["0x1:0x2", "0x3:0x4"]
+            auto_deploy_eoa = true
+
+            [webhooks]
+            urls = ["https://example.com/hook"]
+            max_retries = 3
+
+            [env]
+            KAKAROT_METRICS = "true"
+        "#;
+
+        // When
+        let variables: BTreeMap<String, String> =
+            ConfigFile::from_toml(toml).unwrap().variables().unwrap().into_iter().collect();
+
+        // Then
+        assert_eq!("0.0.0.0:3030", variables["KAKAROT_HTTP_RPC_ADDRESS"]);
+        assert_eq!("true", variables["KAKAROT_STANDBY"]);
+        assert_eq!("/var/log/kakarot-rpc/audit.jsonl", variables["KAKAROT_AUDIT_LOG"]);
+        assert_eq!("filters,subscriptions", variables["KAKAROT_DISABLED_FEATURES"]);
+        assert_eq!("https://app.kakarot.org,http://localhost:3000", variables["KAKAROT_CORS_ALLOWED_ORIGINS"]);
+        assert_eq!("/etc/kakarot-rpc/cert.pem", variables["KAKAROT_TLS_CERT"]);
+        assert_eq!("/etc/kakarot-rpc/key.pem", variables["KAKAROT_TLS_KEY"]);
+        assert_eq!("katana", variables["STARKNET_NETWORK"]);
+        assert_eq!("http://localhost:5050/,http://localhost:5051/", variables["STARKNET_FALLBACK_RPC_URLS"]);
+        assert_eq!("32", variables["STARKNET_RPC_MAX_CONCURRENT_REQUESTS"]);
+        assert_eq!("0x1234", variables["KAKAROT_ADDRESS"]);
+        assert_eq!("starknet", variables["KAKAROT_CHAIN_ID"]);
+        assert_eq!("chain-spec.json", variables["KAKAROT_CHAIN_SPEC"]);
+        assert_eq!("0x1:0x2,0x3:0x4", variables["KAKAROT_RELAYER_ACCOUNTS"]);
+        assert_eq!("true", variables["KAKAROT_RELAYER_AUTO_DEPLOY_EOA"]);
+        assert_eq!("https://example.com/hook", variables["KAKAROT_WEBHOOK_URLS"]);
+        assert_eq!("3", variables["KAKAROT_WEBHOOK_MAX_RETRIES"]);
+        assert_eq!("true", variables["KAKAROT_METRICS"]);
+        assert!(!variables.contains_key("PROXY_ACCOUNT_CLASS_HASH"));
+    }
+
+    #[test]
+    fn test_yaml_config_file() {
+        // Given
+        let yaml = "server:\n  address: 127.0.0.1:3030\nkakarot:\n  proxy_account_class_hash: \"0xabc\"\n";
+
+        // When
+        let config = ConfigFile::from_yaml(yaml).unwrap();
+
+        // Then
+        assert_eq!(Some("127.0.0.1:3030".parse().unwrap()), config.server.address);
+        assert_eq!(Some("0xabc".to_string()), config.kakarot.proxy_account_class_hash);
+    }
+
+    #[test]
+    fn test_invalid_config_file() {
+        // Unknown key
+        assert!(ConfigFile::from_toml("[server]\nport = 3030").is_err());
+        // Invalid address
+        assert!(ConfigFile::from_toml("[server]\naddress = \"localhost\"").is_err());
+        // Invalid felt
+        let config = ConfigFile::from_toml("[kakarot]\naddress = \"kakarot\"").unwrap();
+        assert!(config.variables().unwrap_err().to_string().contains("kakarot.address should be a hex string"));
+        // Invalid network
+        let config = ConfigFile::from_toml("[starknet]\nnetwork = \"goerli3\"").unwrap();
+        assert!(config.variables().is_err());
+        // Unknown feature
+        let config = ConfigFile::from_toml("[server]\ndisabled_features = [\"mempool\"]").unwrap();
+        assert!(config.variables().is_err());
+        // Invalid relayer account
+        let config = ConfigFile::from_toml("[relayer]\naccounts = [\"0x1\"]").unwrap();
+        assert!(config.variables().is_err());
+        // Invalid CORS origin
+        let config = ConfigFile::from_toml("[server]\ncors_allowed_origins = [\"kakarot.org\"]").unwrap();
+        assert!(config.variables().is_err());
+        // Certificate without its key
+        let config = ConfigFile::from_toml("[server]\ntls_cert = \"cert.pem\"").unwrap();
+        assert!(config.variables().is_err());
+    }
+
+    #[test]
+    fn test_cli_variables() {
+        // Given
+        let cli = Cli::parse_from(["kakarot-rpc", "--rpc-address", "0.0.0.0:3031", "--kakarot-address", "0x1"]);
+
+        // When
+        let variables = cli.variables().unwrap();
+
+        // Then
+        assert_eq!(
+            vec![
+                ("KAKAROT_HTTP_RPC_ADDRESS".to_string(), "0.0.0.0:3031".to_string()),
+                ("KAKAROT_ADDRESS".to_string(), "0x1".to_string())
+            ],
+            variables
+        );
+        assert!(Cli::parse_from(["kakarot-rpc", "--dev"]).dev);
+        assert_eq!(
+            vec![("KAKAROT_READ_ONLY".to_string(), "true".to_string())],
+            Cli::parse_from(["kakarot-rpc", "--read-only"]).variables().unwrap()
+        );
+        assert_eq!(
+            vec![("KAKAROT_STANDBY".to_string(), "true".to_string())],
+            Cli::parse_from(["kakarot-rpc", "--standby"]).variables().unwrap()
+        );
+        assert_eq!(
+            Some(Command::ExportIndex { path: PathBuf::from("index.jsonl") }),
+            Cli::parse_from(["kakarot-rpc", "export-index", "index.jsonl"]).command
+        );
+        assert_eq!(
+            Some(Command::Export {
+                from_block: 10,
+                to_block: None,
+                format: ExportFormat::Csv,
+                output: PathBuf::from("export")
+            }),
+            Cli::parse_from(["kakarot-rpc", "export", "--from-block", "10", "--format", "csv", "--output", "export"])
+                .command
+        );
+        assert_eq!(
+            Some(Command::Replay {
+                request: PathBuf::from("request.json"),
+                block: Some(42),
+                output: PathBuf::from("replay-bundle.json")
+            }),
+            Cli::parse_from(["kakarot-rpc", "replay", "request.json", "--block", "42"]).command
+        );
+        assert_eq!(
+            vec![("KAKAROT_SELFTEST_ACCOUNT".to_string(), "0xabc".to_string())],
+            Cli::parse_from(["kakarot-rpc", "selftest", "--account", "0xabc"]).variables().unwrap()
+        );
     }
 }
@@ -0,0 +1,347 @@
+//! Local development network started with `--dev`: an embedded Katana sequencer on which the
+//! Kakarot system is deployed, along with funded EVM accounts managed by the RPC signer.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use dojo_test_utils::sequencer::TestSequencer;
+use ethers::signers::{LocalWallet, Signer};
+use eyre::{eyre, Result};
+use kakarot_rpc_core::client::api::StateOverrideBackend;
+use kakarot_rpc_core::client::config::{Network, StarknetConfig};
+use kakarot_rpc_core::client::signer::EthSigner;
+use kakarot_rpc_core::models::state_override::StarknetStateWrite;
+use kakarot_rpc_core::test_utils::deploy_helpers::{
+    construct_kakarot_test_sequencer, deploy_and_fund_eoas, deploy_kakarot_system, DeployedKakarot,
+};
+use katana_core::db::serde::state::SerializableState;
+use katana_core::db::Db;
+use reth_primitives::{Address, U64};
+use starknet::core::types::FieldElement;
+use starknet::providers::jsonrpc::HttpTransport;
+use starknet::providers::JsonRpcClient;
+use starknet_api::core::{ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::StarkFelt;
+use starknet_api::state::StorageKey;
+
+use crate::katana::{EvmBackend, KatanaDevClient};
+
+/// Private keys of the dev accounts, the first accounts of Anvil.
+pub const DEV_PRIVATE_KEYS: &[&str] = &[
+    "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+    "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+    "0x5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a",
+    "0x7c852118294e51e653712a81e05800f419141751be58f605c371e15141b007a6",
+    "0x47e179ec197488593b187f80a00eb0da91f1b9d0b13f8733639f19c30a34926a",
+];
+
+/// Address served by the dev network when `KAKAROT_HTTP_RPC_ADDRESS` isn't set.
+pub const DEV_RPC_ADDRESS: &str = "0.0.0.0:8545";
+
+/// Directory of the keystores of the accounts created through `personal_newAccount`, under the
+/// temporary directory, when `KAKAROT_DEV_KEYSTORE_DIR` isn't set.
+pub const DEV_KEYSTORE_DIR: &str = "kakarot-dev-keystore";
+
+/// Balance of each dev account, 100 ETH.
+pub const DEV_ACCOUNT_BALANCE: u128 = 100_000_000_000_000_000_000;
+
+/// Snapshots taken through `evm_snapshot`, keyed by increasing ids starting at 1.
+#[derive(Debug)]
+pub struct Snapshots<T> {
+    next_id: u64,
+    snapshots: BTreeMap<u64, T>,
+}
+
+impl<T> Default for Snapshots<T> {
+    fn default() -> Self {
+        Self { next_id: 1, snapshots: BTreeMap::new() }
+    }
+}
+
+impl<T> Snapshots<T> {
+    /// Stores the snapshot and returns its id.
+    pub fn insert(&mut self, snapshot: T) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.snapshots.insert(id, snapshot);
+        id
+    }
+
+    /// Removes and returns the snapshot with the given id. As in Hardhat and Anvil, the snapshots
+    /// taken after it are discarded too, and a snapshot can only be reverted to once.
+    pub fn take(&mut self, id: u64) -> Option<T> {
+        let snapshot = self.snapshots.remove(&id)?;
+        self.snapshots.split_off(&id);
+        Some(snapshot)
+    }
+}
+
+/// A running dev network. The sequencer stops when it is dropped.
+pub struct DevNetwork {
+    sequencer: TestSequencer,
+    kakarot: DeployedKakarot,
+    signer: EthSigner,
+    keystore_dir: PathBuf,
+    snapshots: Mutex<Snapshots<SerializableState>>,
+    katana: KatanaDevClient,
+}
+
+impl DevNetwork {
+    /// Starts a Katana sequencer, deploys the Kakarot system and the dev accounts.
+    ///
+    /// The compiled Kakarot contracts are read from `COMPILED_KAKAROT_PATH`, the keystores of the
+    /// new accounts are written to `KAKAROT_DEV_KEYSTORE_DIR`.
+    pub async fn start() -> Result<Self> {
+        let keystore_dir = match std::env::var("KAKAROT_DEV_KEYSTORE_DIR") {
+            Ok(keystore_dir) => PathBuf::from(keystore_dir),
+            Err(_) => std::env::temp_dir().join(DEV_KEYSTORE_DIR),
+        };
+        std::fs::create_dir_all(&keystore_dir)
+            .map_err(|err| eyre!("failed to create the keystore directory {}: {err}", keystore_dir.display()))?;
+
+        let wallets = DEV_PRIVATE_KEYS
+            .iter()
+            .map(|private_key| private_key.trim_start_matches("0x").parse::<LocalWallet>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let signer = EthSigner::from_private_keys(DEV_PRIVATE_KEYS.iter().copied())?;
+
+        let sequencer = construct_kakarot_test_sequencer().await;
+        let balance = FieldElement::from(DEV_ACCOUNT_BALANCE);
+
+        // The first account is deployed along with Kakarot
+        let (first_wallet, other_wallets) = wallets.split_first().expect("no dev account");
+        let kakarot = deploy_kakarot_system(&sequencer, first_wallet.clone(), balance).await;
+        deploy_and_fund_eoas(&sequencer, kakarot.kakarot_address, other_wallets, balance).await;
+
+        let katana = KatanaDevClient::new(&sequencer.url())?;
+
+        Ok(Self { sequencer, kakarot, signer, keystore_dir, snapshots: Mutex::default(), katana })
+    }
+
+    /// Snapshots the state of the sequencer and returns the id of the snapshot.
+    pub async fn snapshot(&self) -> Result<U64> {
+        let state = self.sequencer.sequencer.backend.state.write().await.dump_state().map_err(|e| eyre!("{e}"))?;
+        let id = self.snapshots.lock().expect("snapshots poisoned").insert(state);
+        Ok(U64::from(id))
+    }
+
+    /// Restores the state of the sequencer to the snapshot with the given id. Returns false if
+    /// there is no such snapshot. Only the state is restored, the blocks mined since the snapshot
+    /// are kept.
+    pub async fn revert(&self, id: U64) -> Result<bool> {
+        let snapshot = self.snapshots.lock().expect("snapshots poisoned").take(id.as_u64());
+        let Some(state) = snapshot else {
+            return Ok(false);
+        };
+
+        let backend = &self.sequencer.sequencer.backend;
+        backend.state.write().await.load_state(state).map_err(|e| eyre!("{e}"))?;
+        // Generate the blocks from the restored state
+        backend.generate_latest_block().await;
+        backend.generate_pending_block().await;
+
+        Ok(true)
+    }
+
+    /// Returns the configuration of the Kakarot client for the dev network, the dev accounts
+    /// being managed by the signer and the state overrides of `eth_call` written to the state of
+    /// the sequencer.
+    pub fn starknet_config(self: &Arc<Self>) -> StarknetConfig {
+        StarknetConfig {
+            signer: self.signer.clone(),
+            state_override_backend: Some(Arc::clone(self) as Arc<dyn StateOverrideBackend>),
+            ..StarknetConfig::new(
+                Network::JsonRpcProvider(self.sequencer.url()),
+                self.kakarot.kakarot_address,
+                self.kakarot.proxy_class_hash,
+            )
+        }
+    }
+
+    /// Returns a provider connected to the embedded sequencer.
+    pub fn starknet_provider(&self) -> JsonRpcClient<HttpTransport> {
+        JsonRpcClient::new(HttpTransport::new(self.sequencer.url()))
+    }
+
+    /// Returns the dev accounts with their private key.
+    pub fn accounts(&self) -> Vec<(Address, &'static str)> {
+        dev_accounts()
+    }
+
+    /// Returns the embedded sequencer.
+    pub fn sequencer(&self) -> &TestSequencer {
+        &self.sequencer
+    }
+
+    /// Returns the signer of the dev accounts, shared with the Kakarot client.
+    pub fn signer(&self) -> &EthSigner {
+        &self.signer
+    }
+
+    /// Returns the directory of the keystores of the accounts created through
+    /// `personal_newAccount`.
+    pub fn keystore_dir(&self) -> &Path {
+        &self.keystore_dir
+    }
+}
+
+#[async_trait]
+impl EvmBackend for DevNetwork {
+    async fn snapshot(&self) -> Result<U64> {
+        DevNetwork::snapshot(self).await
+    }
+
+    async fn revert(&self, id: U64) -> Result<bool> {
+        DevNetwork::revert(self, id).await
+    }
+
+    /// Mines a block through the dev methods of the embedded sequencer.
+    async fn mine(&self, timestamp: Option<u64>) -> Result<()> {
+        self.katana.mine(timestamp).await
+    }
+
+    async fn increase_time(&self, seconds: u64) -> Result<U64> {
+        self.katana.increase_time(seconds).await
+    }
+}
+
+#[async_trait]
+impl StateOverrideBackend for DevNetwork {
+    /// Writes the overrides to the state of the sequencer, without snapshotting it: the previous
+    /// values of the written slots and nonces are returned, to be written back after the call.
+    /// Fails without writing anything if one of the contracts isn't deployed.
+    async fn apply(&self, writes: Vec<StarknetStateWrite>) -> Result<Vec<StarknetStateWrite>> {
+        let backend = &self.sequencer.sequencer.backend;
+        let mut restore = Vec::with_capacity(writes.len());
+        {
+            let mut state = backend.state.write().await;
+
+            let mut records = Vec::with_capacity(writes.len());
+            for write in &writes {
+                let (StarknetStateWrite::Storage { contract_address, .. }
+                | StarknetStateWrite::Nonce { contract_address, .. }) = *write;
+                let address = ContractAddress(patricia_key(contract_address)?);
+                if !state.storage.contains_key(&address) {
+                    return Err(eyre!("no contract deployed at {contract_address:#x}"));
+                }
+                records.push(address);
+            }
+
+            for (address, write) in records.into_iter().zip(writes) {
+                // Safe unwrap: the contracts were checked above
+                let record = state.storage.get_mut(&address).unwrap();
+                match write {
+                    StarknetStateWrite::Storage { contract_address, key, value } => {
+                        let previous = record
+                            .storage
+                            .insert(StorageKey(patricia_key(key)?), stark_felt(value)?)
+                            .unwrap_or_default();
+                        restore.push(StarknetStateWrite::Storage {
+                            contract_address,
+                            key,
+                            value: field_element(previous)?,
+                        });
+                    }
+                    StarknetStateWrite::Nonce { contract_address, nonce } => {
+                        let previous = std::mem::replace(&mut record.nonce, Nonce(stark_felt(nonce)?));
+                        restore.push(StarknetStateWrite::Nonce { contract_address, nonce: field_element(previous.0)? });
+                    }
+                }
+            }
+        }
+        // The calls at the pending block read the state of the pending block
+        backend.generate_pending_block().await;
+
+        // A slot written twice is restored to its value before the first write
+        restore.reverse();
+        Ok(restore)
+    }
+}
+
+/// Returns the addresses of the dev accounts, derived from their private keys, with the keys.
+fn dev_accounts() -> Vec<(Address, &'static str)> {
+    DEV_PRIVATE_KEYS
+        .iter()
+        .map(|private_key| {
+            let wallet: LocalWallet = private_key.trim_start_matches("0x").parse().expect("valid dev private key");
+            (Address::from(wallet.address().0), *private_key)
+        })
+        .collect()
+}
+
+fn stark_felt(felt: FieldElement) -> Result<StarkFelt> {
+    StarkFelt::new(felt.to_bytes_be()).map_err(|e| eyre!("{e}"))
+}
+
+fn patricia_key(felt: FieldElement) -> Result<PatriciaKey> {
+    PatriciaKey::try_from(stark_felt(felt)?).map_err(|e| eyre!("{e}"))
+}
+
+fn field_element(felt: StarkFelt) -> Result<FieldElement> {
+    FieldElement::from_byte_slice_be(felt.bytes()).map_err(|e| eyre!("{e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshots_take_discards_later_snapshots() {
+        // Given
+        let mut snapshots = Snapshots::default();
+        let ids: Vec<u64> = ["a", "b", "c"].into_iter().map(|snapshot| snapshots.insert(snapshot)).collect();
+
+        // When
+        let reverted = snapshots.take(2);
+
+        // Then
+        assert_eq!(vec![1, 2, 3], ids);
+        assert_eq!(Some("b"), reverted);
+        assert_eq!(None, snapshots.take(3));
+        assert_eq!(None, snapshots.take(2));
+        assert_eq!(4, snapshots.insert("d"));
+        assert_eq!(Some("a"), snapshots.take(1));
+    }
+
+    #[test]
+    fn test_snapshots_take_unknown_id_keeps_snapshots() {
+        // Given
+        let mut snapshots = Snapshots::default();
+        snapshots.insert("a");
+        snapshots.insert("b");
+
+        // When
+        let reverted = snapshots.take(3);
+
+        // Then
+        // A revert to a missing snapshot doesn't discard the snapshots taken before it
+        assert_eq!(None, reverted);
+        assert_eq!(Some("b"), snapshots.take(2));
+        assert_eq!(Some("a"), snapshots.take(1));
+    }
+
+    #[test]
+    fn test_dev_accounts() {
+        // Given
+        let anvil_addresses = [
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+            "0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC",
+            "0x90F79bf6EB2c4f870365E785982E1f101E93b906",
+            "0x15d34AAf54267DB7D7c367839AAf71A00a2C6A65",
+        ];
+        let signer = EthSigner::from_private_keys(DEV_PRIVATE_KEYS.iter().copied()).unwrap();
+
+        // When
+        let accounts = dev_accounts();
+
+        // Then
+        // The dev accounts are the accounts of Anvil, managed by the signer
+        let addresses: Vec<Address> = accounts.iter().map(|(address, _)| *address).collect();
+        let expected: Vec<Address> = anvil_addresses.iter().map(|address| address.parse().unwrap()).collect();
+        assert_eq!(expected, addresses);
+        assert_eq!(DEV_PRIVATE_KEYS, accounts.iter().map(|(_, private_key)| *private_key).collect::<Vec<_>>());
+        assert!(addresses.iter().all(|address| signer.has_account(address)));
+    }
+}
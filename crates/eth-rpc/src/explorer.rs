@@ -0,0 +1,114 @@
+//! Links to a Starknet block explorer, added to the responses of the `kakarot` methods to jump
+//! from the Ethereum view of the chain to the underlying Starknet blocks, transactions and
+//! contracts while debugging.
+//!
+//! The links follow the paths shared by Starkscan and Voyager: `/block/<number>`, `/tx/<hash>` and
+//! `/contract/<address>`.
+use eyre::{eyre, Result};
+use serde::Serialize;
+use starknet::core::types::FieldElement;
+use url::Url;
+
+/// Starknet block explorer the links point to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StarknetExplorer {
+    base_url: Url,
+}
+
+impl StarknetExplorer {
+    pub fn new(base_url: Url) -> Self {
+        Self { base_url }
+    }
+
+    /// Create a new `StarknetExplorer` from the `KAKAROT_STARKNET_EXPLORER_URL` environment
+    /// variable, e.g. https://testnet.starkscan.co. Returns `None` when not set, which disables the
+    /// links.
+    pub fn from_env() -> Result<Option<Self>> {
+        let base_url = match std::env::var("KAKAROT_STARKNET_EXPLORER_URL") {
+            Ok(base_url) => base_url,
+            Err(_) => return Ok(None),
+        };
+        let base_url = Url::parse(&base_url)
+            .map_err(|err| eyre!("KAKAROT_STARKNET_EXPLORER_URL should be a URL, got {base_url}: {err}"))?;
+        Ok(Some(Self::new(base_url)))
+    }
+
+    /// Returns the link to the Starknet transaction.
+    pub fn transaction_url(&self, hash: FieldElement) -> String {
+        self.url(&format!("tx/{hash:#x}"))
+    }
+
+    /// Returns the link to the Starknet block.
+    pub fn block_url(&self, block_number: u64) -> String {
+        self.url(&format!("block/{block_number}"))
+    }
+
+    /// Returns the link to the Starknet contract.
+    pub fn contract_url(&self, address: FieldElement) -> String {
+        self.url(&format!("contract/{address:#x}"))
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{path}", self.base_url.as_str().trim_end_matches('/'))
+    }
+}
+
+/// Explorer links of a response, serialized as its `explorer` field.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplorerLinks {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+}
+
+/// Response extended with explorer links, serialized as the response with an additional
+/// `explorer` field when links are set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WithExplorerLinks<T> {
+    #[serde(flatten)]
+    pub inner: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explorer: Option<ExplorerLinks>,
+}
+
+impl<T> WithExplorerLinks<T> {
+    pub fn new(inner: T, explorer: Option<ExplorerLinks>) -> Self {
+        Self { inner, explorer }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_explorer_links() {
+        // Given
+        let explorer = StarknetExplorer::new(Url::parse("https://testnet.starkscan.co/").unwrap());
+
+        // Then
+        assert_eq!("https://testnet.starkscan.co/tx/0x1234", explorer.transaction_url(FieldElement::from(0x1234u16)));
+        assert_eq!("https://testnet.starkscan.co/block/42", explorer.block_url(42));
+        assert_eq!("https://testnet.starkscan.co/contract/0xabc", explorer.contract_url(FieldElement::from(0xabcu16)));
+    }
+
+    #[test]
+    fn test_with_explorer_links_serialization() {
+        // Given
+        let links =
+            ExplorerLinks { contract: Some("https://voyager.online/contract/0x1".to_string()), ..Default::default() };
+
+        // Then
+        assert_eq!(
+            json!({ "a": 1, "explorer": { "contract": "https://voyager.online/contract/0x1" } }),
+            serde_json::to_value(WithExplorerLinks::new(json!({ "a": 1 }), Some(links))).unwrap()
+        );
+        assert_eq!(json!({ "a": 1 }), serde_json::to_value(WithExplorerLinks::new(json!({ "a": 1 }), None)).unwrap());
+    }
+}
@@ -0,0 +1,280 @@
+//! Export of the chain data by the `export` command, for the analysts who need the Kakarot blocks
+//! in bulk without writing a script against the RPC.
+//!
+//! The blocks of a range are converted by the client, as served by `eth_getBlockByNumber` and
+//! `eth_getBlockReceipts`, and streamed in order to three files of the output directory:
+//! `blocks`, `transactions` and `receipts`. In NDJSON, each line is the JSON object returned by
+//! the RPC, the blocks holding the hashes of their transactions. In CSV, each line holds the main
+//! fields of the object, the logs of the receipts being only counted.
+use std::fmt::LowerHex;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use clap::ValueEnum;
+use eyre::{eyre, Result};
+use futures::{stream, StreamExt, TryStreamExt};
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use reth_primitives::{BlockId, BlockNumberOrTag, Bytes};
+use reth_rpc_types::{Block, BlockTransactions, Transaction, TransactionReceipt};
+use serde::Serialize;
+use starknet::core::types::BlockId as StarknetBlockId;
+use starknet::providers::Provider;
+
+/// Number of blocks fetched concurrently, the files being written in the order of the blocks.
+const BLOCK_CONCURRENCY: usize = 4;
+
+const BLOCK_COLUMNS: [&str; 9] = [
+    "number",
+    "hash",
+    "parent_hash",
+    "timestamp",
+    "miner",
+    "gas_used",
+    "gas_limit",
+    "base_fee_per_gas",
+    "transaction_count",
+];
+
+const TRANSACTION_COLUMNS: [&str; 10] =
+    ["hash", "block_number", "transaction_index", "from", "to", "value", "gas", "gas_price", "nonce", "input"];
+
+const RECEIPT_COLUMNS: [&str; 9] = [
+    "transaction_hash",
+    "block_number",
+    "from",
+    "to",
+    "status",
+    "gas_used",
+    "effective_gas_price",
+    "contract_address",
+    "log_count",
+];
+
+/// Format of the exported files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One JSON object per line.
+    #[default]
+    Ndjson,
+    Csv,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Ndjson => "ndjson",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// Number of records written to each file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub blocks: u64,
+    pub transactions: u64,
+    pub receipts: u64,
+}
+
+/// File of records of one kind.
+struct RecordWriter {
+    format: ExportFormat,
+    writer: BufWriter<File>,
+}
+
+impl RecordWriter {
+    /// Creates the file `name` in the output directory, starting with the columns in CSV.
+    fn create(output: &Path, name: &str, format: ExportFormat, columns: &[&str]) -> Result<Self> {
+        let path = output.join(format!("{name}.{}", format.extension()));
+        let file = File::create(&path).map_err(|err| eyre!("Failed to create {}: {err}", path.display()))?;
+        let mut writer = Self { format, writer: BufWriter::new(file) };
+        if format == ExportFormat::Csv {
+            writer.write_line(&csv_line(columns.iter().map(ToString::to_string)))?;
+        }
+        Ok(writer)
+    }
+
+    /// Writes the record, as its JSON object in NDJSON or as its row in CSV.
+    fn write<T: Serialize>(&mut self, record: &T, row: impl FnOnce() -> Vec<String>) -> Result<()> {
+        let line = match self.format {
+            ExportFormat::Ndjson => serde_json::to_string(record)?,
+            ExportFormat::Csv => csv_line(row()),
+        };
+        self.write_line(&line)
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Exports the blocks `from_block..=to_block`, their transactions and their receipts to the output
+/// directory, which is created if needed.
+pub async fn export_chain_data<P: Provider + Send + Sync>(
+    kakarot_client: &dyn KakarotEthApi<P>,
+    from_block: u64,
+    to_block: u64,
+    format: ExportFormat,
+    output: &Path,
+) -> Result<ExportSummary> {
+    if from_block > to_block {
+        return Err(eyre!("The first block {from_block} is after the last block {to_block}"));
+    }
+    std::fs::create_dir_all(output)?;
+    let mut blocks = RecordWriter::create(output, "blocks", format, &BLOCK_COLUMNS)?;
+    let mut transactions = RecordWriter::create(output, "transactions", format, &TRANSACTION_COLUMNS)?;
+    let mut receipts = RecordWriter::create(output, "receipts", format, &RECEIPT_COLUMNS)?;
+
+    let mut summary = ExportSummary::default();
+    let mut fetched_blocks = stream::iter(from_block..=to_block)
+        .map(|number| fetch_block(kakarot_client, number))
+        .buffered(BLOCK_CONCURRENCY);
+    while let Some((mut block, block_receipts)) = fetched_blocks.try_next().await? {
+        let block_transactions = match std::mem::replace(&mut block.transactions, BlockTransactions::Hashes(vec![])) {
+            BlockTransactions::Full(block_transactions) => block_transactions,
+            _ => vec![],
+        };
+        block.transactions =
+            BlockTransactions::Hashes(block_transactions.iter().map(|transaction| transaction.hash).collect());
+
+        blocks.write(&block, || block_row(&block, block_transactions.len()))?;
+        for transaction in &block_transactions {
+            transactions.write(transaction, || transaction_row(transaction))?;
+        }
+        for receipt in &block_receipts {
+            receipts.write(receipt, || receipt_row(receipt))?;
+        }
+
+        summary.blocks += 1;
+        summary.transactions += block_transactions.len() as u64;
+        summary.receipts += block_receipts.len() as u64;
+    }
+
+    blocks.flush()?;
+    transactions.flush()?;
+    receipts.flush()?;
+    Ok(summary)
+}
+
+/// Fetches the block with its transactions and the receipts of its transactions.
+async fn fetch_block<P: Provider + Send + Sync>(
+    kakarot_client: &dyn KakarotEthApi<P>,
+    number: u64,
+) -> Result<(Block, Vec<TransactionReceipt>)> {
+    let block = kakarot_client
+        .get_eth_block_from_starknet_block(StarknetBlockId::Number(number), true)
+        .await
+        .map_err(|err| eyre!("Failed to fetch the block {number}: {err}"))?
+        .inner;
+    let receipts = kakarot_client
+        .block_receipts(BlockId::Number(BlockNumberOrTag::Number(number)))
+        .await
+        .map_err(|err| eyre!("Failed to fetch the receipts of the block {number}: {err}"))?
+        .ok_or_else(|| eyre!("Missing receipts of the block {number}"))?;
+    Ok((block, receipts))
+}
+
+fn block_row(block: &Block, transaction_count: usize) -> Vec<String> {
+    let header = &block.header;
+    vec![
+        optional(header.number),
+        optional(header.hash.map(full_hex)),
+        full_hex(header.parent_hash),
+        header.timestamp.to_string(),
+        full_hex(header.miner),
+        header.gas_used.to_string(),
+        header.gas_limit.to_string(),
+        optional(header.base_fee_per_gas),
+        transaction_count.to_string(),
+    ]
+}
+
+fn transaction_row(transaction: &Transaction) -> Vec<String> {
+    vec![
+        full_hex(transaction.hash),
+        optional(transaction.block_number),
+        optional(transaction.transaction_index),
+        full_hex(transaction.from),
+        optional(transaction.to.map(full_hex)),
+        transaction.value.to_string(),
+        transaction.gas.to_string(),
+        optional(transaction.gas_price),
+        transaction.nonce.to_string(),
+        bytes(&transaction.input),
+    ]
+}
+
+fn receipt_row(receipt: &TransactionReceipt) -> Vec<String> {
+    vec![
+        optional(receipt.transaction_hash.map(full_hex)),
+        optional(receipt.block_number),
+        full_hex(receipt.from),
+        optional(receipt.to.map(full_hex)),
+        optional(receipt.status_code),
+        optional(receipt.gas_used),
+        receipt.effective_gas_price.to_string(),
+        optional(receipt.contract_address.map(full_hex)),
+        receipt.logs.len().to_string(),
+    ]
+}
+
+/// Formats a hash or an address as a full 0x-prefixed hex string.
+fn full_hex(value: impl LowerHex) -> String {
+    format!("{value:#x}")
+}
+
+fn bytes(value: &Bytes) -> String {
+    format!("0x{}", hex::encode(value))
+}
+
+/// Formats the value, or an empty field if it is missing.
+fn optional(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Joins the fields of a CSV line, quoting the fields holding a separator, a quote or a newline.
+fn csv_line(fields: impl IntoIterator<Item = String>) -> String {
+    fields
+        .into_iter()
+        .map(
+            |field| {
+                if field.contains([',', '"', '\n']) { format!("\"{}\"", field.replace('"', "\"\"")) } else { field }
+            },
+        )
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Address, H256};
+
+    use super::*;
+
+    #[test]
+    fn test_csv_line() {
+        // Given
+        let fields = vec!["0x1".to_string(), "a,b".to_string(), "say \"hi\"".to_string(), String::new()];
+
+        // When
+        let line = csv_line(fields);
+
+        // Then
+        assert_eq!(r#"0x1,"a,b","say ""hi""","#, line);
+    }
+
+    #[test]
+    fn test_full_hex() {
+        assert_eq!(format!("0x{}", "00".repeat(31) + "01"), full_hex(H256::from_low_u64_be(1)));
+        assert_eq!(format!("0x{}", "00".repeat(19) + "ff"), full_hex(Address::from_low_u64_be(0xff)));
+        assert_eq!("0xabcd", bytes(&Bytes::from(vec![0xab, 0xcd])));
+        assert_eq!("", optional(None::<u64>));
+    }
+}
@@ -0,0 +1,152 @@
+//! Forking mode of the dev network: the state of the accounts is read from a remote Kakarot RPC at
+//! a pinned block, until the accounts are written locally. Once an account is the sender, the
+//! recipient or the creation of a local transaction, its state is served by the dev network.
+//!
+//! The remote state isn't copied into the dev network: a local transaction calling a forked
+//! contract executes against the local state, in which the contract doesn't exist.
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use eyre::{eyre, Result};
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::params::ArrayParams;
+use jsonrpsee::core::RpcResult;
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use jsonrpsee::rpc_params;
+use kakarot_rpc_core::client::errors::{rpc_err, EthRpcErrorCode};
+use reth_primitives::{Address, BlockId, BlockNumberOrTag, U64};
+use serde::de::DeserializeOwned;
+use url::Url;
+
+/// Configuration of the forking mode.
+#[derive(Debug, Clone)]
+pub struct ForkConfig {
+    /// Url of the Kakarot RPC of the forked chain.
+    pub url: Url,
+    /// Block of the forked chain at which the state is read, the latest block if not set.
+    pub block_number: Option<u64>,
+}
+
+impl ForkConfig {
+    pub fn new(url: Url, block_number: Option<u64>) -> Self {
+        Self { url, block_number }
+    }
+
+    /// Create a new `ForkConfig` from environment variables.
+    /// Returns `None` if `KAKAROT_FORK_URL` is not set, which disables the forking mode.
+    pub fn from_env() -> Result<Option<Self>> {
+        let url = match std::env::var("KAKAROT_FORK_URL") {
+            Ok(url) => Url::parse(&url).map_err(|err| eyre!("KAKAROT_FORK_URL is not a valid url: {err}"))?,
+            Err(_) => return Ok(None),
+        };
+        let block_number = match std::env::var("KAKAROT_FORK_BLOCK_NUMBER") {
+            Ok(block_number) => Some(
+                block_number.parse::<u64>().map_err(|_| eyre!("KAKAROT_FORK_BLOCK_NUMBER should be a block number"))?,
+            ),
+            Err(_) => None,
+        };
+        Ok(Some(Self::new(url, block_number)))
+    }
+}
+
+/// Connection to the forked chain, along with the accounts whose state lives in the dev network.
+#[derive(Debug)]
+pub struct Fork {
+    client: HttpClient,
+    block_number: U64,
+    local_accounts: RwLock<HashSet<Address>>,
+}
+
+impl Fork {
+    fn new(client: HttpClient, block_number: U64, local_accounts: impl IntoIterator<Item = Address>) -> Self {
+        Self { client, block_number, local_accounts: RwLock::new(local_accounts.into_iter().collect()) }
+    }
+
+    /// Connects to the forked chain and pins the fork block, the latest block of the forked chain
+    /// if the configuration doesn't set one. The given accounts are served by the dev network.
+    pub async fn connect(config: &ForkConfig, local_accounts: impl IntoIterator<Item = Address>) -> Result<Self> {
+        let client = HttpClientBuilder::default().build(config.url.as_str())?;
+        let block_number = match config.block_number {
+            Some(block_number) => U64::from(block_number),
+            None => client.request("eth_blockNumber", rpc_params![]).await?,
+        };
+        Ok(Self::new(client, block_number, local_accounts))
+    }
+
+    /// Returns the block of the forked chain at which the state is read.
+    pub fn block_number(&self) -> U64 {
+        self.block_number
+    }
+
+    /// Returns true if the state of the account is served by the dev network.
+    pub fn is_local(&self, address: &Address) -> bool {
+        self.local_accounts.read().expect("local accounts poisoned").contains(address)
+    }
+
+    /// Serves the state of the accounts from the dev network from now on.
+    pub fn mark_local(&self, addresses: impl IntoIterator<Item = Address>) {
+        self.local_accounts.write().expect("local accounts poisoned").extend(addresses);
+    }
+
+    /// Returns the block of the forked chain at which a request for the given block is served:
+    /// the requested block if it precedes the fork block, the fork block otherwise.
+    pub fn remote_block_id(&self, block_id: Option<BlockId>) -> BlockId {
+        let fork_block = BlockId::Number(BlockNumberOrTag::Number(self.block_number.as_u64()));
+        match block_id {
+            Some(BlockId::Number(BlockNumberOrTag::Number(number))) => {
+                BlockId::Number(BlockNumberOrTag::Number(number.min(self.block_number.as_u64())))
+            }
+            Some(block_id @ (BlockId::Hash(_) | BlockId::Number(BlockNumberOrTag::Earliest))) => block_id,
+            _ => fork_block,
+        }
+    }
+
+    /// Sends the request to the forked chain.
+    pub async fn request<T: DeserializeOwned>(&self, method: &str, params: ArrayParams) -> RpcResult<T> {
+        self.client.request(method, params).await.map_err(|err| {
+            rpc_err(EthRpcErrorCode::InternalError as i32, format!("Forked chain request {method} failed: {err}"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::H256;
+
+    use super::*;
+
+    fn fork(block_number: u64, local_accounts: impl IntoIterator<Item = Address>) -> Fork {
+        let client = HttpClientBuilder::default().build("http://localhost:8545").unwrap();
+        Fork::new(client, U64::from(block_number), local_accounts)
+    }
+
+    #[test]
+    fn test_remote_block_id_is_pinned() {
+        // Given
+        let fork = fork(100, []);
+        let number = |number| BlockId::Number(BlockNumberOrTag::Number(number));
+
+        // Then
+        assert_eq!(number(100), fork.remote_block_id(None));
+        assert_eq!(number(100), fork.remote_block_id(Some(BlockId::Number(BlockNumberOrTag::Latest))));
+        assert_eq!(number(100), fork.remote_block_id(Some(number(150))));
+        assert_eq!(number(42), fork.remote_block_id(Some(number(42))));
+        let hash = BlockId::from(H256::from_low_u64_be(1));
+        assert_eq!(hash, fork.remote_block_id(Some(hash)));
+    }
+
+    #[test]
+    fn test_mark_local() {
+        // Given
+        let (alice, bob) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let fork = fork(100, [alice]);
+
+        // When
+        fork.mark_local([bob]);
+
+        // Then
+        assert!(fork.is_local(&alice));
+        assert!(fork.is_local(&bob));
+        assert!(!fork.is_local(&Address::from_low_u64_be(3)));
+    }
+}
@@ -0,0 +1,80 @@
+//! Test methods of Hardhat and Anvil served on top of a local Katana devnet, mapped to the dev
+//! methods of Katana (`katana_generateBlock`, `katana_setNextBlockTimestamp` and
+//! `katana_increaseNextBlockTimestamp`), so that Foundry and Hardhat test suites run unmodified
+//! against Kakarot.
+//!
+//! Katana doesn't expose its state over RPC: the snapshots are only supported by the embedded dev
+//! network of the `dev` feature, see `crate::dev::DevNetwork`.
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::params::ArrayParams;
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use jsonrpsee::rpc_params;
+use reth_primitives::U64;
+use serde::de::DeserializeOwned;
+use url::Url;
+
+/// Backend of the `evm` test methods.
+#[async_trait]
+pub trait EvmBackend: Send + Sync {
+    /// Snapshots the state of the chain and returns the id of the snapshot.
+    async fn snapshot(&self) -> Result<U64>;
+
+    /// Reverts the state of the chain to the snapshot with the given id. Returns false if there is
+    /// no such snapshot.
+    async fn revert(&self, id: U64) -> Result<bool>;
+
+    /// Mines a block, with the given timestamp if set.
+    async fn mine(&self, timestamp: Option<u64>) -> Result<()>;
+
+    /// Increases the timestamp of the next blocks by the given number of seconds and returns the
+    /// total increase.
+    async fn increase_time(&self, seconds: u64) -> Result<U64>;
+}
+
+/// Client of the dev methods of a Katana devnet.
+#[derive(Debug)]
+pub struct KatanaDevClient {
+    client: HttpClient,
+    /// Sum of the increases of the timestamp through `evm_increaseTime`.
+    time_offset: AtomicU64,
+}
+
+impl KatanaDevClient {
+    pub fn new(url: &Url) -> Result<Self> {
+        let client = HttpClientBuilder::default().build(url.as_str())?;
+        Ok(Self { client, time_offset: AtomicU64::new(0) })
+    }
+
+    /// Sends the request to Katana.
+    async fn request<T: DeserializeOwned>(&self, method: &str, params: ArrayParams) -> Result<T> {
+        self.client.request(method, params).await.map_err(|err| eyre!("Katana request {method} failed: {err}"))
+    }
+}
+
+#[async_trait]
+impl EvmBackend for KatanaDevClient {
+    async fn snapshot(&self) -> Result<U64> {
+        Err(eyre!("evm_snapshot is only supported by the dev network, Katana doesn't expose its state over RPC"))
+    }
+
+    async fn revert(&self, _id: U64) -> Result<bool> {
+        Err(eyre!("evm_revert is only supported by the dev network, Katana doesn't expose its state over RPC"))
+    }
+
+    async fn mine(&self, timestamp: Option<u64>) -> Result<()> {
+        if let Some(timestamp) = timestamp {
+            self.request::<()>("katana_setNextBlockTimestamp", rpc_params![timestamp]).await?;
+        }
+        self.request("katana_generateBlock", rpc_params![]).await
+    }
+
+    async fn increase_time(&self, seconds: u64) -> Result<U64> {
+        self.request::<()>("katana_increaseNextBlockTimestamp", rpc_params![seconds]).await?;
+        let time_offset = self.time_offset.fetch_add(seconds, Ordering::SeqCst) + seconds;
+        Ok(U64::from(time_offset))
+    }
+}
@@ -4,17 +4,42 @@ use std::net::{AddrParseError, SocketAddr};
 
 use config::RPCConfig;
 pub mod api;
+pub mod backfill;
+pub mod capabilities;
 pub mod config;
+#[cfg(feature = "dev")]
+pub mod dev;
+pub mod explorer;
+pub mod export;
+pub mod fork;
+pub mod katana;
+pub mod metrics;
+pub mod middleware;
+pub mod replay;
 pub mod rpc;
+pub mod selftest;
 pub mod servers;
+pub mod shutdown;
+pub mod telemetry;
 pub mod test_utils;
+pub mod tls;
+pub mod webhooks;
 
 use eyre::Result;
 use jsonrpsee::server::{ServerBuilder, ServerHandle};
 use jsonrpsee::RpcModule;
+use metrics::MetricsLayer;
+use middleware::audit::AuditLayer;
+use middleware::batch::BatchLayer;
+use middleware::limits::LimitsLayer;
+use middleware::params::ParamsLayer;
+use middleware::priority::PriorityLayer;
+use middleware::shadow::ShadowLayer;
+use middleware::standby::StandbyLayer;
+use telemetry::TracingLayer;
 use thiserror::Error;
+use tokio::net::TcpListener;
 use tower::ServiceBuilder;
-use tower_http::cors::{Any, CorsLayer};
 
 #[derive(Error, Debug)]
 pub enum RpcError {
@@ -22,8 +47,17 @@ pub enum RpcError {
     JsonRpcServerError(#[from] jsonrpsee::core::Error),
     #[error(transparent)]
     ParseError(#[from] AddrParseError),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    TlsError(#[from] tls::TlsError),
 }
 
+/// Starts the RPC server, serving both HTTP and WebSocket connections on the configured address.
+/// Subscriptions (`eth_subscribe`) are only available over WebSocket. The health endpoints are
+/// served on `/health` and `/ready`, see [`middleware::standby`]. With a TLS configuration, the
+/// connections are served over `https://` and `wss://`, see [`tls`].
+///
 /// # Errors
 ///
 /// Will return `Err` if an error occurs when running the `ServerBuilder` start fails.
@@ -31,17 +65,59 @@ pub async fn run_server(
     kakarot_rpc_module: RpcModule<()>,
     rpc_config: RPCConfig,
 ) -> Result<(SocketAddr, ServerHandle), RpcError> {
-    let RPCConfig { socket_addr } = rpc_config;
+    let RPCConfig {
+        socket_addr,
+        shadow,
+        priority,
+        params_mode,
+        metrics,
+        limits,
+        batch,
+        standby,
+        audit_log,
+        cors,
+        tls,
+        ..
+    } = rpc_config;
+    let socket_addr = socket_addr.parse::<SocketAddr>()?;
 
-    let cors = CorsLayer::new().allow_methods(Any).allow_origin(Any).allow_headers(Any);
+    if let Some(metrics) = &metrics {
+        metrics.register_methods(kakarot_rpc_module.method_names());
+    }
+    let shadow = ShadowLayer::new(shadow);
+    if let (Some(metrics), Some(stats)) = (&metrics, shadow.stats()) {
+        metrics.add_source(move || stats.encode_prometheus());
+    }
 
-    let service = ServiceBuilder::new().layer(cors);
+    let service = ServiceBuilder::new()
+        .layer(cors.layer())
+        .layer(LimitsLayer::new(limits))
+        .layer(StandbyLayer::new(standby))
+        .layer(AuditLayer::new(audit_log))
+        .layer(TracingLayer)
+        .layer(MetricsLayer::new(metrics))
+        .layer(ParamsLayer::new(params_mode))
+        .layer(BatchLayer::new(&batch))
+        .layer(PriorityLayer::new(&priority))
+        .layer(shadow);
 
-    let server = ServerBuilder::default().set_middleware(service).build(socket_addr.parse::<SocketAddr>()?).await?;
+    let server_builder = ServerBuilder::default().set_middleware(service);
 
-    let addr = server.local_addr()?;
+    let Some(tls) = tls else {
+        let server = server_builder.build(socket_addr).await?;
+        let addr = server.local_addr()?;
+        let handle = server.start(kakarot_rpc_module)?;
+        return Ok((addr, handle));
+    };
 
+    // The server only accepts the connections decrypted by the TLS proxy
+    let acceptor = tls.acceptor()?;
+    let listener = TcpListener::bind(socket_addr).await?;
+    let addr = listener.local_addr()?;
+    let server = server_builder.build(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+    let upstream = server.local_addr()?;
     let handle = server.start(kakarot_rpc_module)?;
+    tls::spawn_tls_proxy(listener, acceptor, upstream, handle.clone());
 
     Ok((addr, handle))
 }
@@ -1,65 +1,486 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
 use std::sync::Arc;
 
+use clap::Parser;
 use dotenv::dotenv;
 use eyre::Result;
-use kakarot_rpc::config::RPCConfig;
+use jsonrpsee::RpcModule;
+use kakarot_rpc::backfill::LogBackfill;
+use kakarot_rpc::capabilities::Capability;
+use kakarot_rpc::config::{load_config, Cli, Command, RPCConfig};
+#[cfg(feature = "dev")]
+use kakarot_rpc::dev::{DevNetwork, DEV_RPC_ADDRESS};
+use kakarot_rpc::explorer::StarknetExplorer;
+use kakarot_rpc::export::{export_chain_data, ExportFormat, ExportSummary};
+#[cfg(feature = "dev")]
+use kakarot_rpc::fork::{Fork, ForkConfig};
+use kakarot_rpc::katana::KatanaDevClient;
+use kakarot_rpc::metrics::{encode_cache_hit_ratios, CountingTransport, Metrics, StarknetRoundTrips};
+use kakarot_rpc::replay::{replay_request, CapturedRequest};
 use kakarot_rpc::rpc::KakarotRpcModuleBuilder;
 use kakarot_rpc::run_server;
+use kakarot_rpc::selftest::{run_selftest, SelfTestConfig, SelfTestReport};
+use kakarot_rpc::shutdown::{serve_until_shutdown, BackgroundTasks, ShutdownConfig};
+use kakarot_rpc::telemetry::{init_tracing, shutdown_tracing, TelemetryConfig, TracingTransport};
+use kakarot_rpc::webhooks::{WebhookConfig, Webhooks};
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::client::cache::encode_prometheus;
+use kakarot_rpc_core::client::cold_start::ColdStartConfig;
 use kakarot_rpc_core::client::config::{
     JsonRpcClientBuilder, Network, SequencerGatewayProviderBuilder, StarknetConfig,
 };
+use kakarot_rpc_core::client::fallback::{FallbackConfig, FallbackTransport};
+use kakarot_rpc_core::client::limiter::{ConcurrencyLimiter, LimitedTransport};
+use kakarot_rpc_core::client::warmup::WarmUpConfig;
 use kakarot_rpc_core::client::KakarotClient;
+use kakarot_rpc_core::storage::StorageConfig;
+use kakarot_rpc_indexer::config::IndexerConfig;
+use kakarot_rpc_indexer::indexer::Indexer;
+use kakarot_rpc_indexer::snapshot::{export_snapshot, import_snapshot};
+use kakarot_rpc_indexer::store::IndexStore;
 use starknet::providers::jsonrpc::HttpTransport;
-use starknet::providers::{JsonRpcClient, SequencerGatewayProvider};
-use tracing_subscriber::util::SubscriberInitExt;
+use starknet::providers::{JsonRpcClient, Provider, SequencerGatewayProvider};
 
 enum StarknetProvider {
-    JsonRpcClient(JsonRpcClient<HttpTransport>),
+    JsonRpcClient(JsonRpcClient<LimitedTransport<CountingTransport<TracingTransport<HttpTransport>>>>),
+    FallbackProvider(JsonRpcClient<LimitedTransport<CountingTransport<TracingTransport<FallbackTransport>>>>),
     SequencerGatewayProvider(SequencerGatewayProvider),
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
+    let cli = Cli::parse();
+    load_config(&cli)?;
     // Environment variables are safe to use after this
 
-    let filter = tracing_subscriber::EnvFilter::try_from_default_env()?;
-    tracing_subscriber::FmtSubscriber::builder().with_env_filter(filter).finish().try_init()?;
+    init_tracing(TelemetryConfig::from_env())?;
 
+    let result = match cli.command {
+        Some(Command::ExportIndex { path }) => export_index(&path).await,
+        Some(Command::ImportIndex { path }) => import_index(&path).await,
+        Some(Command::Export { from_block, to_block, format, output }) => {
+            export(from_block, to_block, format, &output).await
+        }
+        Some(Command::Replay { request, block, output }) => replay(&request, block, &output).await,
+        Some(Command::Selftest { .. }) => selftest().await,
+        None if cli.dev => run_dev_network().await,
+        None => run().await,
+    };
+    shutdown_tracing();
+    result
+}
+
+/// Opens the store of the configured indexer.
+async fn open_index_store() -> Result<Arc<dyn IndexStore>> {
+    let indexer_config =
+        IndexerConfig::from_env()?.ok_or_else(|| eyre::eyre!("KAKAROT_INDEXER_STORE is not set, there is no index"))?;
+    Ok(indexer_config.store.open().await?)
+}
+
+/// Exports the blocks of the index to a snapshot file.
+async fn export_index(path: &Path) -> Result<()> {
+    let store = open_index_store().await?;
+    let kakarot_address = StarknetConfig::from_env()?.kakarot_address;
+    let writer = BufWriter::new(File::create(path)?);
+    let header = export_snapshot(store.as_ref(), kakarot_address, writer).await?;
+    println!("Exported the blocks {}..={} to {}", header.from_block, header.to_block, path.display());
+    Ok(())
+}
+
+/// Imports a snapshot file into the index.
+async fn import_index(path: &Path) -> Result<()> {
+    let store = open_index_store().await?;
+    let kakarot_address = StarknetConfig::from_env()?.kakarot_address;
+    let reader = BufReader::new(File::open(path)?);
+    let header = import_snapshot(store.as_ref(), kakarot_address, reader).await?;
+    println!("Imported the blocks {}..={} from {}", header.from_block, header.to_block, path.display());
+    Ok(())
+}
+
+/// Exports the blocks `from_block..=to_block` of the configured network to the output directory.
+async fn export(from_block: u64, to_block: Option<u64>, format: ExportFormat, output: &Path) -> Result<()> {
     let starknet_config = StarknetConfig::from_env()?;
+    let limiter = ConcurrencyLimiter::from_env()?;
+    let round_trips = Arc::new(StarknetRoundTrips::default());
 
-    let rpc_config = RPCConfig::from_env()?;
+    let summary = match starknet_provider(&starknet_config, round_trips, limiter)? {
+        StarknetProvider::JsonRpcClient(starknet_provider) => {
+            export_with(starknet_config, starknet_provider, from_block, to_block, format, output).await
+        }
+        StarknetProvider::FallbackProvider(starknet_provider) => {
+            export_with(starknet_config, starknet_provider, from_block, to_block, format, output).await
+        }
+        StarknetProvider::SequencerGatewayProvider(starknet_provider) => {
+            export_with(starknet_config, starknet_provider, from_block, to_block, format, output).await
+        }
+    }?;
+    println!(
+        "Exported {} blocks, {} transactions and {} receipts to {}",
+        summary.blocks,
+        summary.transactions,
+        summary.receipts,
+        output.display()
+    );
+    Ok(())
+}
 
-    let starknet_provider: StarknetProvider = match &starknet_config.network {
-        Network::Madara | Network::Katana | Network::Sharingan => {
-            StarknetProvider::JsonRpcClient(JsonRpcClientBuilder::with_http(&starknet_config).unwrap().build())
+/// Replays a captured request against the configured network and writes the diagnostic bundle.
+async fn replay(request_path: &Path, block: Option<u64>, output: &Path) -> Result<()> {
+    let mut request = CapturedRequest::from_file(request_path)?;
+    request.block = block.or(request.block);
+    let bundle = replay_request(StarknetConfig::from_env()?, request).await?;
+    serde_json::to_writer_pretty(BufWriter::new(File::create(output)?), &bundle)?;
+    println!(
+        "Replayed {} with {} Starknet calls in {}ms, wrote the bundle to {}",
+        bundle.request.method,
+        bundle.starknet_calls.len(),
+        bundle.duration_ms,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Runs the self-test against the configured network and prints its report, failing if a check
+/// failed.
+async fn selftest() -> Result<()> {
+    let starknet_config = StarknetConfig::from_env()?;
+    let selftest_config = SelfTestConfig::from_env()?;
+    let limiter = ConcurrencyLimiter::from_env()?;
+    let round_trips = Arc::new(StarknetRoundTrips::default());
+
+    let report = match starknet_provider(&starknet_config, round_trips, limiter)? {
+        StarknetProvider::JsonRpcClient(starknet_provider) => {
+            selftest_with(starknet_config, starknet_provider, &selftest_config).await
+        }
+        StarknetProvider::FallbackProvider(starknet_provider) => {
+            selftest_with(starknet_config, starknet_provider, &selftest_config).await
         }
-        Network::JsonRpcProvider(url) => {
-            StarknetProvider::JsonRpcClient(JsonRpcClientBuilder::new(HttpTransport::new(url.clone())).build())
+        StarknetProvider::SequencerGatewayProvider(starknet_provider) => {
+            selftest_with(starknet_config, starknet_provider, &selftest_config).await
+        }
+    }?;
+    println!("{report}");
+    ensure_selftest_passed(&report)
+}
+
+/// Builds the RPC module for the given provider and runs the self-test against it.
+async fn selftest_with<P: Provider + Send + Sync + 'static>(
+    mut starknet_config: StarknetConfig,
+    starknet_provider: P,
+    selftest_config: &SelfTestConfig,
+) -> Result<SelfTestReport> {
+    // The self-test doesn't use the local state, whose database may be locked by a running RPC
+    starknet_config.storage = StorageConfig::Memory;
+    let kakarot_client =
+        Arc::new(KakarotClient::try_new(starknet_config, starknet_provider).await.map_err(|err| eyre::eyre!("{err}"))?);
+    let kakarot_rpc_module = KakarotRpcModuleBuilder::new(kakarot_client).rpc_module()?;
+    Ok(run_selftest(&kakarot_rpc_module, selftest_config).await)
+}
+
+fn ensure_selftest_passed(report: &SelfTestReport) -> Result<()> {
+    if !report.passed() {
+        return Err(eyre::eyre!("{} of {} self-test checks failed", report.failures(), report.checks.len()));
+    }
+    Ok(())
+}
+
+/// Builds the Kakarot client for the given provider and exports the blocks, up to the latest block
+/// if no last block is given.
+async fn export_with<P: Provider + Send + Sync + 'static>(
+    mut starknet_config: StarknetConfig,
+    starknet_provider: P,
+    from_block: u64,
+    to_block: Option<u64>,
+    format: ExportFormat,
+    output: &Path,
+) -> Result<ExportSummary> {
+    // The export doesn't use the local state, whose database may be locked by a running RPC
+    starknet_config.storage = StorageConfig::Memory;
+    let kakarot_client =
+        KakarotClient::try_new(starknet_config, starknet_provider).await.map_err(|err| eyre::eyre!("{err}"))?;
+
+    let to_block = match to_block {
+        Some(to_block) => to_block,
+        None => kakarot_client.block_number().await.map_err(|err| eyre::eyre!("{err}"))?.low_u64(),
+    };
+    export_chain_data(&kakarot_client, from_block, to_block, format, output).await
+}
+
+/// Builds the provider of the configured Starknet network: a JSON-RPC client, failing over between
+/// several endpoints if configured, or the feeder gateway.
+fn starknet_provider(
+    starknet_config: &StarknetConfig,
+    round_trips: Arc<StarknetRoundTrips>,
+    limiter: Option<Arc<ConcurrencyLimiter>>,
+) -> Result<StarknetProvider> {
+    let starknet_provider = match &starknet_config.network {
+        Network::Madara | Network::Katana | Network::Sharingan | Network::JsonRpcProvider(_) => {
+            let url = starknet_config.network.provider_url()?;
+            match FallbackConfig::from_env(url.clone())? {
+                Some(fallback_config) => {
+                    tracing::info!("Failing over between {} Starknet endpoints", fallback_config.urls.len());
+                    let transport = FallbackTransport::new(fallback_config);
+                    transport.spawn_health_checks();
+                    let transport = CountingTransport::new(TracingTransport::new(transport), round_trips);
+                    let transport = LimitedTransport::new(transport, limiter);
+                    StarknetProvider::FallbackProvider(JsonRpcClientBuilder::new(transport).build())
+                }
+                None => {
+                    let transport = CountingTransport::new(TracingTransport::new(HttpTransport::new(url)), round_trips);
+                    let transport = LimitedTransport::new(transport, limiter);
+                    StarknetProvider::JsonRpcClient(JsonRpcClientBuilder::new(transport).build())
+                }
+            }
         }
         _ => StarknetProvider::SequencerGatewayProvider(
             SequencerGatewayProviderBuilder::new(&starknet_config.network).build(),
         ),
     };
+    Ok(starknet_provider)
+}
+
+/// Connects to the configured Starknet network and serves the RPC on top of it until the server
+/// stops.
+async fn run() -> Result<()> {
+    let starknet_config = StarknetConfig::from_env()?;
 
-    let kakarot_rpc_module = match starknet_provider {
+    let rpc_config = RPCConfig::from_env()?;
+
+    let warm_up_config = WarmUpConfig::from_env()?;
+
+    let shutdown_config = ShutdownConfig::from_env()?;
+
+    let round_trips = Arc::new(StarknetRoundTrips::default());
+    if let Some(metrics) = &rpc_config.metrics {
+        let round_trips = Arc::clone(&round_trips);
+        metrics.add_source(move || round_trips.encode_prometheus());
+    }
+    if let (Some(metrics), Some(standby)) = (&rpc_config.metrics, &rpc_config.standby) {
+        let standby = Arc::clone(standby);
+        metrics.add_source(move || standby.encode_prometheus());
+    }
+
+    // The requests waiting for the limit are not counted in the round trips to the Starknet node
+    let limiter = ConcurrencyLimiter::from_env()?;
+    if let (Some(metrics), Some(limiter)) = (&rpc_config.metrics, &limiter) {
+        let limiter = Arc::clone(limiter);
+        metrics.add_source(move || limiter.encode_prometheus());
+    }
+
+    let starknet_provider = starknet_provider(&starknet_config, round_trips, limiter)?;
+
+    let (kakarot_rpc_module, background) = match starknet_provider {
         StarknetProvider::JsonRpcClient(starknet_provider) => {
-            let kakarot_client = Arc::new(KakarotClient::new(starknet_config, starknet_provider));
-            KakarotRpcModuleBuilder::new(kakarot_client).rpc_module()
+            kakarot_rpc_module(starknet_config, starknet_provider, warm_up_config, &rpc_config).await
+        }
+        StarknetProvider::FallbackProvider(starknet_provider) => {
+            kakarot_rpc_module(starknet_config, starknet_provider, warm_up_config, &rpc_config).await
         }
         StarknetProvider::SequencerGatewayProvider(starknet_provider) => {
-            let kakarot_client = Arc::new(KakarotClient::new(starknet_config, starknet_provider));
-            KakarotRpcModuleBuilder::new(kakarot_client).rpc_module()
+            kakarot_rpc_module(starknet_config, starknet_provider, warm_up_config, &rpc_config).await
         }
     }?;
 
+    if SelfTestConfig::on_startup() {
+        let report = run_selftest(&kakarot_rpc_module, &SelfTestConfig::from_env()?).await;
+        println!("{report}");
+        ensure_selftest_passed(&report)?;
+    }
+
+    let standby = rpc_config.standby.is_some();
+    let scheme = if rpc_config.tls.is_some() { "https" } else { "http" };
     let (server_addr, server_handle) = run_server(kakarot_rpc_module, rpc_config).await?;
 
-    let url = format!("http://{server_addr}");
+    let url = format!("{scheme}://{server_addr}");
 
     println!("RPC Server running on {url}...");
+    if standby {
+        println!("Standby until promoted with kakarot_promote");
+    }
+
+    serve_until_shutdown(server_handle, shutdown_config, background).await;
+
+    Ok(())
+}
+
+/// Starts an embedded Katana sequencer with Kakarot and funded dev accounts, and serves the RPC
+/// on top of it until the server stops.
+#[cfg(feature = "dev")]
+async fn run_dev_network() -> Result<()> {
+    let rpc_config = match std::env::var("KAKAROT_HTTP_RPC_ADDRESS") {
+        Ok(_) => RPCConfig::from_env()?,
+        Err(_) => RPCConfig::new(DEV_RPC_ADDRESS.to_string()),
+    };
+
+    let dev_network = Arc::new(DevNetwork::start().await?);
+    let kakarot_client = Arc::new(KakarotClient::new(dev_network.starknet_config(), dev_network.starknet_provider()));
+    if let Some(metrics) = &rpc_config.metrics {
+        register_cache_metrics(metrics, kakarot_client.clone());
+    }
+    let mut kakarot_rpc_module_builder =
+        KakarotRpcModuleBuilder::new(kakarot_client).with_dev_network(Arc::clone(&dev_network));
+
+    let fork = match ForkConfig::from_env()? {
+        Some(fork_config) => {
+            let local_accounts = dev_network.accounts().into_iter().map(|(address, _)| address);
+            let fork = Arc::new(Fork::connect(&fork_config, local_accounts).await?);
+            kakarot_rpc_module_builder = kakarot_rpc_module_builder.with_fork(Arc::clone(&fork));
+            Some((fork_config.url, fork.block_number()))
+        }
+        None => None,
+    };
+    if rpc_config.read_only {
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.read_only();
+    }
+    for capability in &rpc_config.disabled_features {
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.disable(*capability);
+    }
+    let kakarot_rpc_module = kakarot_rpc_module_builder.rpc_module()?;
+
+    let scheme = if rpc_config.tls.is_some() { "https" } else { "http" };
+    let (server_addr, server_handle) = run_server(kakarot_rpc_module, rpc_config).await?;
+
+    println!("Available accounts");
+    println!("==================");
+    for (index, (address, private_key)) in dev_network.accounts().into_iter().enumerate() {
+        println!("({index}) {address:#x} {private_key}");
+    }
+    println!();
+    println!("Katana sequencer running on {}", dev_network.sequencer().url());
+    if let Some((url, block_number)) = fork {
+        println!("Forking {url} at block {block_number}");
+    }
+    println!("RPC Server running on {scheme}://{server_addr}...");
 
-    server_handle.stopped().await;
+    serve_until_shutdown(server_handle, ShutdownConfig::from_env()?, BackgroundTasks::default()).await;
 
     Ok(())
 }
+
+/// The embedded Katana sequencer of the dev mode is only built with the `dev` feature.
+#[cfg(not(feature = "dev"))]
+async fn run_dev_network() -> Result<()> {
+    Err(eyre::eyre!("--dev requires building kakarot-rpc with the dev feature"))
+}
+
+/// Builds the Kakarot client for the given provider, warms it up if requested and returns the
+/// merged RPC module, along with the background tasks to stop on shutdown.
+async fn kakarot_rpc_module<P: Provider + Send + Sync + 'static>(
+    mut starknet_config: StarknetConfig,
+    starknet_provider: P,
+    warm_up_config: Option<WarmUpConfig>,
+    rpc_config: &RPCConfig,
+) -> Result<(RpcModule<()>, BackgroundTasks)> {
+    // The storage is opened here rather than by the client, to be flushed on shutdown
+    let storage = starknet_config.storage.open().map_err(|err| eyre::eyre!("{err}"))?;
+    starknet_config.storage = StorageConfig::Custom(Arc::clone(&storage));
+
+    // The test methods of Hardhat and Anvil are mapped to the dev methods of a local Katana
+    let katana = match starknet_config.network {
+        Network::Katana => {
+            Some((KatanaDevClient::new(&starknet_config.network.provider_url()?)?, starknet_config.signer.clone()))
+        }
+        _ => None,
+    };
+
+    let kakarot_client =
+        Arc::new(KakarotClient::try_new(starknet_config, starknet_provider).await.map_err(|err| eyre::eyre!("{err}"))?);
+    tracing::info!("Serving chain id {}", kakarot_client.chain_id());
+
+    kakarot_client.validate_coinbase().await.map_err(|err| eyre::eyre!("{err}"))?;
+    kakarot_client.validate_native_token().await.map_err(|err| eyre::eyre!("{err}"))?;
+
+    if let Some(metrics) = &rpc_config.metrics {
+        register_cache_metrics(metrics, kakarot_client.clone());
+    }
+
+    if let Some(warm_up_config) = warm_up_config {
+        match kakarot_client.warm_up(&warm_up_config).await {
+            Ok(report) => tracing::info!(
+                "Warm-up done in {:?}: {} block headers fetched, coinbase {:?}",
+                report.elapsed,
+                report.blocks_fetched,
+                report.coinbase
+            ),
+            // A failed warm-up only means the first requests will be slower, do not prevent the start
+            Err(err) => tracing::warn!("Warm-up failed: {err}"),
+        }
+    }
+
+    // Rebuild the relayed transactions and the relayer nonces lost by a restart
+    match kakarot_client.cold_start_scan(&ColdStartConfig::from_env()?).await {
+        Ok(report) if report.blocks_scanned > 0 => tracing::info!(
+            "Cold-start scan done in {:?}: {} blocks scanned, {} relayed transactions, {} relayer nonces resumed",
+            report.elapsed,
+            report.blocks_scanned,
+            report.relayed_transactions,
+            report.relayer_nonces
+        ),
+        Ok(_) => {}
+        Err(err) => tracing::warn!("Cold-start scan failed: {err}"),
+    }
+
+    // The indexer tails the blocks in the background, the RPC serves the indexed blocks from its store
+    let mut background = BackgroundTasks::default().with_storage(storage);
+    let index = match IndexerConfig::from_env()? {
+        Some(indexer_config) if !rpc_config.disabled_features.contains(&Capability::Indexer) => {
+            let store = indexer_config.store.open().await?;
+            let indexer = Indexer::new(kakarot_client.clone(), store.clone(), &indexer_config).start();
+            background = background.with_indexer(indexer, store.clone());
+            tracing::info!("Block indexer started");
+            Some(store)
+        }
+        _ => None,
+    };
+
+    let log_index_enabled = kakarot_client.log_index_enabled();
+    let mut kakarot_rpc_module_builder = KakarotRpcModuleBuilder::new(kakarot_client.clone());
+    if let Some(index) = index {
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.with_index(index);
+    }
+    if log_index_enabled {
+        let backfill = Arc::new(LogBackfill::default());
+        if let Some(metrics) = &rpc_config.metrics {
+            let backfill = Arc::clone(&backfill);
+            metrics.add_source(move || backfill.encode_prometheus());
+        }
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.with_log_backfill(backfill);
+    }
+    if let Some(webhook_config) = WebhookConfig::from_env()? {
+        let webhooks = Arc::new(Webhooks::new(webhook_config));
+        webhooks.start(kakarot_client.clone());
+        tracing::info!("Webhooks of the relayed transactions enabled");
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.with_webhooks(webhooks);
+    }
+    if let Some(explorer) = StarknetExplorer::from_env()? {
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.with_explorer(explorer);
+    }
+    if let Some((katana, signer)) = katana {
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.with_katana(katana, signer);
+    }
+    if rpc_config.read_only {
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.read_only();
+    }
+    for capability in &rpc_config.disabled_features {
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.disable(*capability);
+    }
+    if let Some(standby) = &rpc_config.standby {
+        kakarot_rpc_module_builder = kakarot_rpc_module_builder.with_standby(Arc::clone(standby));
+    }
+
+    Ok((kakarot_rpc_module_builder.rpc_module()?, background))
+}
+
+/// Exports the counters and hit ratios of the caches of the client with the metrics.
+fn register_cache_metrics<P: Provider + Send + Sync + 'static>(
+    metrics: &Metrics,
+    kakarot_client: Arc<KakarotClient<P>>,
+) {
+    metrics.add_source(move || {
+        let stats = kakarot_client.cache_stats();
+        encode_prometheus(&stats) + &encode_cache_hit_ratios(&stats)
+    });
+}
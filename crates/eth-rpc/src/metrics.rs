@@ -0,0 +1,434 @@
+//! Prometheus metrics of the RPC, served in the Prometheus text format on `GET /metrics`:
+//! - the number of calls, errors and the latency of each JSON-RPC method, measured on the HTTP
+//!   requests (a batch request counts toward each of its methods);
+//! - the number of round trips to the Starknet node, their errors and their duration, by Starknet
+//!   method, counted by [`CountingTransport`] for JSON-RPC providers;
+//! - the requests waiting for the limit of concurrent Starknet requests and their wait time, when
+//!   `STARKNET_RPC_MAX_CONCURRENT_REQUESTS` is set;
+//! - the counters and hit ratio of each cache of the client.
+//!
+//! Methods not registered on the server are counted under the `other` label, so that the number
+//! of series doesn't grow with the requests.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Method, Request, Response};
+use kakarot_rpc_core::client::cache::CacheStats;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use starknet::providers::jsonrpc::{JsonRpcMethod, JsonRpcResponse, JsonRpcTransport};
+use tower::{Layer, Service};
+
+use crate::middleware::JsonRpcBody;
+use crate::telemetry::starknet_method_name;
+
+/// Path of the metrics endpoint.
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Upper bounds of the buckets of the latency histograms, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Label of the methods not registered on the server.
+const OTHER_METHOD: &str = "other";
+
+/// Calls, errors and latency histogram of a JSON-RPC method.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct MethodMetrics {
+    requests: u64,
+    errors: u64,
+    /// Number of calls in each latency bucket, not cumulated.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    duration_sum: f64,
+}
+
+impl MethodMetrics {
+    fn record(&mut self, duration: Duration, failed: bool) {
+        let seconds = duration.as_secs_f64();
+        self.requests += 1;
+        self.errors += u64::from(failed);
+        self.duration_sum += seconds;
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+    }
+}
+
+/// Encoded metrics of another part of the RPC, e.g. the caches of the client.
+type MetricsSource = Box<dyn Fn() -> String + Send + Sync>;
+
+/// Registry of the metrics of the RPC.
+#[derive(Default)]
+pub struct Metrics {
+    methods: Mutex<BTreeMap<String, MethodMetrics>>,
+    registered_methods: Mutex<HashSet<String>>,
+    sources: Mutex<Vec<MetricsSource>>,
+}
+
+impl Metrics {
+    /// Create a new `Metrics` registry if the `KAKAROT_METRICS` environment variable is set to
+    /// `true`, else returns `None`, which disables the metrics endpoint.
+    pub fn from_env() -> Option<Arc<Self>> {
+        let enabled = std::env::var("KAKAROT_METRICS").map(|v| v.to_lowercase() == "true").unwrap_or(false);
+        enabled.then(|| Arc::new(Self::default()))
+    }
+
+    /// Registers the methods served, which are the only ones labelled by name.
+    pub fn register_methods<'a>(&self, methods: impl IntoIterator<Item = &'a str>) {
+        self.registered_methods.lock().expect("poisoned lock").extend(methods.into_iter().map(ToString::to_string));
+    }
+
+    /// Adds metrics encoded by another part of the RPC to the exported metrics.
+    pub fn add_source(&self, source: impl Fn() -> String + Send + Sync + 'static) {
+        self.sources.lock().expect("poisoned lock").push(Box::new(source));
+    }
+
+    /// Records a call of the method.
+    pub fn record(&self, method: &str, duration: Duration, failed: bool) {
+        let method =
+            if self.registered_methods.lock().expect("poisoned lock").contains(method) { method } else { OTHER_METHOD };
+        self.methods.lock().expect("poisoned lock").entry(method.to_string()).or_default().record(duration, failed);
+    }
+
+    /// Encodes the metrics in the Prometheus text format.
+    pub fn encode(&self) -> String {
+        let methods = self.methods.lock().expect("poisoned lock").clone();
+
+        // Writing to a string never fails
+        let mut encoded = String::new();
+        let _ = writeln!(encoded, "# HELP kakarot_rpc_requests_total JSON-RPC calls served.");
+        let _ = writeln!(encoded, "# TYPE kakarot_rpc_requests_total counter");
+        for (method, metrics) in &methods {
+            let _ = writeln!(encoded, "kakarot_rpc_requests_total{{method=\"{method}\"}} {}", metrics.requests);
+        }
+        let _ = writeln!(encoded, "# HELP kakarot_rpc_errors_total JSON-RPC calls answered with an error.");
+        let _ = writeln!(encoded, "# TYPE kakarot_rpc_errors_total counter");
+        for (method, metrics) in &methods {
+            let _ = writeln!(encoded, "kakarot_rpc_errors_total{{method=\"{method}\"}} {}", metrics.errors);
+        }
+        let _ = writeln!(encoded, "# HELP kakarot_rpc_request_duration_seconds Latency of the JSON-RPC calls.");
+        let _ = writeln!(encoded, "# TYPE kakarot_rpc_request_duration_seconds histogram");
+        for (method, metrics) in &methods {
+            let mut cumulated = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(metrics.buckets) {
+                cumulated += count;
+                let _ = writeln!(
+                    encoded,
+                    "kakarot_rpc_request_duration_seconds_bucket{{method=\"{method}\",le=\"{bound}\"}} {cumulated}"
+                );
+            }
+            let _ = writeln!(
+                encoded,
+                "kakarot_rpc_request_duration_seconds_bucket{{method=\"{method}\",le=\"+Inf\"}} {}",
+                metrics.requests
+            );
+            let _ = writeln!(
+                encoded,
+                "kakarot_rpc_request_duration_seconds_sum{{method=\"{method}\"}} {}",
+                metrics.duration_sum
+            );
+            let _ = writeln!(
+                encoded,
+                "kakarot_rpc_request_duration_seconds_count{{method=\"{method}\"}} {}",
+                metrics.requests
+            );
+        }
+
+        for source in self.sources.lock().expect("poisoned lock").iter() {
+            encoded.push_str(&source());
+        }
+        encoded
+    }
+}
+
+/// Encodes the hit ratio of each cache, the share of the lookups served by the cache, in the
+/// Prometheus text format. The counters of the caches are encoded by
+/// [`kakarot_rpc_core::client::cache::encode_prometheus`].
+pub fn encode_cache_hit_ratios(stats: &BTreeMap<String, CacheStats>) -> String {
+    let mut encoded = String::new();
+    let _ = writeln!(encoded, "# HELP kakarot_cache_hit_ratio Share of the lookups served by the cache.");
+    let _ = writeln!(encoded, "# TYPE kakarot_cache_hit_ratio gauge");
+    for (cache, cache_stats) in stats {
+        let lookups = cache_stats.hits + cache_stats.misses;
+        let ratio = if lookups == 0 { 0.0 } else { cache_stats.hits as f64 / lookups as f64 };
+        let _ = writeln!(encoded, "kakarot_cache_hit_ratio{{cache=\"{cache}\"}} {ratio}");
+    }
+    encoded
+}
+
+/// Round trips to the Starknet node of a Starknet method.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RoundTrips {
+    requests: u64,
+    errors: u64,
+    duration_sum: f64,
+}
+
+/// Round trips to the Starknet node, by Starknet method.
+#[derive(Debug, Default)]
+pub struct StarknetRoundTrips {
+    methods: Mutex<BTreeMap<String, RoundTrips>>,
+}
+
+impl StarknetRoundTrips {
+    fn record(&self, method: JsonRpcMethod, duration: Duration, failed: bool) {
+        let method = starknet_method_name(method);
+        let mut methods = self.methods.lock().expect("poisoned lock");
+        let round_trips = methods.entry(method).or_default();
+        round_trips.requests += 1;
+        round_trips.errors += u64::from(failed);
+        round_trips.duration_sum += duration.as_secs_f64();
+    }
+
+    /// Encodes the round trips in the Prometheus text format.
+    pub fn encode_prometheus(&self) -> String {
+        type Metric = (&'static str, &'static str, &'static str, fn(&RoundTrips) -> String);
+        const METRICS: [Metric; 3] = [
+            ("kakarot_starknet_requests_total", "counter", "Requests sent to the Starknet node.", |round_trips| {
+                round_trips.requests.to_string()
+            }),
+            (
+                "kakarot_starknet_request_errors_total",
+                "counter",
+                "Requests to the Starknet node that failed to get a response.",
+                |round_trips| round_trips.errors.to_string(),
+            ),
+            (
+                "kakarot_starknet_request_duration_seconds_sum",
+                "counter",
+                "Total time spent waiting for the Starknet node.",
+                |round_trips| round_trips.duration_sum.to_string(),
+            ),
+        ];
+
+        let methods = self.methods.lock().expect("poisoned lock");
+        let mut encoded = String::new();
+        for (name, kind, help, value) in METRICS {
+            let _ = writeln!(encoded, "# HELP {name} {help}");
+            let _ = writeln!(encoded, "# TYPE {name} {kind}");
+            for (method, round_trips) in methods.iter() {
+                let _ = writeln!(encoded, "{name}{{method=\"{method}\"}} {}", value(round_trips));
+            }
+        }
+        encoded
+    }
+}
+
+/// JSON-RPC transport counting the round trips of the inner transport to the Starknet node.
+#[derive(Debug)]
+pub struct CountingTransport<T> {
+    inner: T,
+    round_trips: Arc<StarknetRoundTrips>,
+}
+
+impl<T> CountingTransport<T> {
+    pub fn new(inner: T, round_trips: Arc<StarknetRoundTrips>) -> Self {
+        Self { inner, round_trips }
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcTransport + Send + Sync> JsonRpcTransport for CountingTransport<T> {
+    type Error = T::Error;
+
+    async fn send_request<P, R>(&self, method: JsonRpcMethod, params: P) -> Result<JsonRpcResponse<R>, Self::Error>
+    where
+        P: Serialize + Send,
+        R: DeserializeOwned,
+    {
+        let started_at = Instant::now();
+        let response = self.inner.send_request(method, params).await;
+        self.round_trips.record(method, started_at.elapsed(), response.is_err());
+        response
+    }
+}
+
+/// Returns the method of each call of a request body, along with its id, for both single and
+/// batch requests. Notifications have a `null` id.
+fn jsonrpc_calls(body: &[u8]) -> Vec<(Value, String)> {
+    let call = |request: &Value| {
+        let method = request.get("method").and_then(Value::as_str)?;
+        Some((request.get("id").cloned().unwrap_or(Value::Null), method.to_string()))
+    };
+
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(requests)) => requests.iter().filter_map(call).collect(),
+        Ok(request) => call(&request).into_iter().collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Returns the number of error responses of each id in a response body, for both single and
+/// batch responses.
+fn jsonrpc_error_ids(body: &[u8]) -> HashMap<String, usize> {
+    let mut error_ids = HashMap::new();
+    let mut count = |response: &Value| {
+        if response.get("error").is_some() {
+            let id = response.get("id").cloned().unwrap_or(Value::Null);
+            *error_ids.entry(id.to_string()).or_insert(0) += 1;
+        }
+    };
+
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(responses)) => responses.iter().for_each(&mut count),
+        Ok(response) => count(&response),
+        Err(_) => {}
+    }
+    error_ids
+}
+
+/// Tower layer recording the metrics of the HTTP requests and serving them on
+/// [`METRICS_PATH`]. The layer is a no-op when built without a registry.
+#[derive(Clone)]
+pub struct MetricsLayer {
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl MetricsLayer {
+    pub fn new(metrics: Option<Arc<Metrics>>) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService { inner, metrics: self.metrics.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl<S> Service<Request<Body>> for MetricsService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let Some(metrics) = self.metrics.clone() else {
+            return Box::pin(inner.call(request));
+        };
+
+        if request.method() == Method::GET && request.uri().path() == METRICS_PATH {
+            let response = Response::builder()
+                .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+                .body(Body::from(metrics.encode()))
+                .expect("valid metrics response");
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(async move {
+            let started_at = Instant::now();
+            let (request, body) = JsonRpcBody::read(request).await?;
+            let calls = jsonrpc_calls(&body.bytes);
+            let response = inner.call(request).await?;
+            if calls.is_empty() {
+                return Ok(response);
+            }
+
+            let (parts, response_body) = response.into_parts();
+            let response_body = hyper::body::to_bytes(response_body).await?;
+
+            let duration = started_at.elapsed();
+            let mut error_ids = jsonrpc_error_ids(&response_body);
+            for (id, method) in &calls {
+                let failed = match error_ids.get_mut(&id.to_string()) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        true
+                    }
+                    _ => false,
+                };
+                metrics.record(method, duration, failed);
+            }
+
+            Ok(Response::from_parts(parts, Body::from(response_body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_method_metrics() {
+        // Given
+        let metrics = Metrics::default();
+        metrics.register_methods(["eth_call"]);
+
+        // When
+        metrics.record("eth_call", Duration::from_millis(20), false);
+        metrics.record("eth_call", Duration::from_millis(200), true);
+        metrics.record("eth_unknownMethod", Duration::from_millis(1), true);
+        metrics.add_source(|| "kakarot_cache_hit_ratio{cache=\"blocks\"} 0.5\n".to_string());
+        let encoded = metrics.encode();
+
+        // Then
+        assert!(encoded.contains("kakarot_rpc_requests_total{method=\"eth_call\"} 2\n"));
+        assert!(encoded.contains("kakarot_rpc_errors_total{method=\"eth_call\"} 1\n"));
+        assert!(encoded.contains("kakarot_rpc_errors_total{method=\"other\"} 1\n"));
+        assert!(encoded.contains("kakarot_rpc_request_duration_seconds_bucket{method=\"eth_call\",le=\"0.025\"} 1\n"));
+        assert!(encoded.contains("kakarot_rpc_request_duration_seconds_bucket{method=\"eth_call\",le=\"0.25\"} 2\n"));
+        assert!(encoded.contains("kakarot_rpc_request_duration_seconds_count{method=\"eth_call\"} 2\n"));
+        assert!(encoded.ends_with("kakarot_cache_hit_ratio{cache=\"blocks\"} 0.5\n"));
+    }
+
+    #[test]
+    fn test_batch_errors_are_matched_by_id() {
+        // Given
+        let request =
+            br#"[{"jsonrpc":"2.0","id":1,"method":"eth_chainId"},{"jsonrpc":"2.0","id":2,"method":"eth_call"}]"#;
+        let response =
+            br#"[{"jsonrpc":"2.0","id":2,"error":{"code":3,"message":"reverted"}},{"jsonrpc":"2.0","id":1,"result":"0x1"}]"#;
+
+        // When
+        let calls = jsonrpc_calls(request);
+        let error_ids = jsonrpc_error_ids(response);
+
+        // Then
+        assert_eq!(vec![(Value::from(1), "eth_chainId".to_string()), (Value::from(2), "eth_call".to_string())], calls);
+        assert_eq!(HashMap::from([("2".to_string(), 1)]), error_ids);
+    }
+
+    #[test]
+    fn test_encode_cache_hit_ratios() {
+        // Given
+        let stats = BTreeMap::from([
+            ("blocks".to_string(), CacheStats { hits: 3, misses: 1, ..Default::default() }),
+            ("receipts".to_string(), CacheStats::default()),
+        ]);
+
+        // When
+        let encoded = encode_cache_hit_ratios(&stats);
+
+        // Then
+        assert!(encoded.contains("kakarot_cache_hit_ratio{cache=\"blocks\"} 0.75\n"));
+        assert!(encoded.contains("kakarot_cache_hit_ratio{cache=\"receipts\"} 0\n"));
+    }
+}
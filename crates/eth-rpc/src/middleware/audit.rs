@@ -0,0 +1,270 @@
+//! Audit log of the state-changing calls, for the operators of public relays with compliance
+//! requirements.
+//!
+//! Every call of a state-changing method, see [`crate::rpc::is_state_changing`], and of
+//! [`PROMOTE_METHOD`] is appended to a JSON lines file once answered, with the time of the call,
+//! the identity of the client, the hash of the relayed transaction and the outcome of the call.
+//! The params of the calls are not logged, since some of them are secrets, e.g. the password of
+//! `personal_unlockAccount`. Only the HTTP calls are audited, an operator needing a complete log
+//! should not expose the WebSocket endpoint.
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eyre::{eyre, Result};
+use hyper::header::USER_AGENT;
+use hyper::http::HeaderMap;
+use hyper::{Body, Request, Response};
+use serde::Serialize;
+use serde_json::Value;
+use tower::{Layer, Service};
+
+use super::limits::client_ip;
+use super::standby::PROMOTE_METHOD;
+use super::JsonRpcBody;
+use crate::rpc::is_state_changing;
+
+/// Methods whose result is the hash of the relayed transaction.
+const TRANSACTION_METHODS: [&str; 2] = ["eth_sendRawTransaction", "eth_sendTransaction"];
+
+/// Returns true if the calls of the method are audited.
+fn is_audited(method: &str) -> bool {
+    is_state_changing(method) || method == PROMOTE_METHOD
+}
+
+/// Outcome of an audited call.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum AuditOutcome {
+    Success,
+    Error {
+        code: i64,
+        message: String,
+    },
+    /// The response of the call couldn't be found, e.g. the connection was dropped.
+    Unknown,
+}
+
+/// Line of the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// Time of the call, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// IP of the client, from the headers of the reverse proxy.
+    pub client_ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub method: String,
+    /// Id of the JSON-RPC call.
+    pub id: Value,
+    /// Hash of the relayed transaction, for the successful transaction methods.
+    pub transaction_hash: Option<String>,
+    pub outcome: AuditOutcome,
+}
+
+/// Append-only audit log.
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Opens the audit log at the given path, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| eyre!("Failed to open the audit log {}: {err}", path.display()))?;
+        Ok(Self { file: Mutex::new(file) })
+    }
+
+    /// Create a new `AuditLog` from the `KAKAROT_AUDIT_LOG` environment variable, the path of the
+    /// log. Returns `None` if it is not set, which disables the audit log.
+    pub fn from_env() -> Result<Option<Arc<Self>>> {
+        match std::env::var("KAKAROT_AUDIT_LOG") {
+            Ok(path) => Ok(Some(Arc::new(Self::open(Path::new(&path))?))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Appends the entries to the log. A failed write is reported in the logs, it doesn't fail the
+    /// calls which were already answered.
+    pub fn record(&self, entries: &[AuditEntry]) {
+        let mut lines = Vec::new();
+        for entry in entries {
+            // Serializing the entry never fails
+            let _ = serde_json::to_writer(&mut lines, entry);
+            lines.push(b'\n');
+        }
+        let mut file = self.file.lock().expect("audit log poisoned");
+        if let Err(err) = file.write_all(&lines).and_then(|_| file.flush()) {
+            tracing::error!("Failed to write to the audit log: {err}");
+        }
+    }
+}
+
+/// Returns the audit entries of the calls of a request, matched with their responses by id.
+fn audit_entries(headers: &HeaderMap, request: &[u8], response: &[u8], timestamp: u64) -> Vec<AuditEntry> {
+    let as_vec = |value| match value {
+        Ok(Value::Array(values)) => values,
+        Ok(value) => vec![value],
+        Err(_) => vec![],
+    };
+    let calls: Vec<Value> = as_vec(serde_json::from_slice::<Value>(request))
+        .into_iter()
+        .filter(|call| call.get("method").and_then(Value::as_str).is_some_and(is_audited))
+        .collect();
+    if calls.is_empty() {
+        return vec![];
+    }
+
+    let mut responses: HashMap<String, Value> = HashMap::new();
+    for response in as_vec(serde_json::from_slice::<Value>(response)) {
+        let id = response.get("id").cloned().unwrap_or(Value::Null).to_string();
+        responses.entry(id).or_insert(response);
+    }
+
+    let client_ip = client_ip(headers);
+    let user_agent = headers.get(USER_AGENT).and_then(|value| value.to_str().ok()).map(ToString::to_string);
+    calls
+        .into_iter()
+        .map(|call| {
+            let method = call.get("method").and_then(Value::as_str).unwrap_or_default().to_string();
+            let id = call.get("id").cloned().unwrap_or(Value::Null);
+            let response = responses.get(&id.to_string());
+            let outcome = match response.and_then(|response| response.get("error")) {
+                Some(error) => AuditOutcome::Error {
+                    code: error.get("code").and_then(Value::as_i64).unwrap_or_default(),
+                    message: error.get("message").and_then(Value::as_str).unwrap_or_default().to_string(),
+                },
+                None if response.is_some() => AuditOutcome::Success,
+                None => AuditOutcome::Unknown,
+            };
+            let transaction_hash = response
+                .filter(|_| TRANSACTION_METHODS.contains(&method.as_str()))
+                .and_then(|response| response.get("result"))
+                .and_then(Value::as_str)
+                .map(ToString::to_string);
+            AuditEntry { timestamp, client_ip, user_agent: user_agent.clone(), method, id, transaction_hash, outcome }
+        })
+        .collect()
+}
+
+/// Tower layer appending the state-changing calls to the audit log. The layer is a no-op when
+/// built without a log.
+#[derive(Clone)]
+pub struct AuditLayer {
+    audit_log: Option<Arc<AuditLog>>,
+}
+
+impl AuditLayer {
+    pub fn new(audit_log: Option<Arc<AuditLog>>) -> Self {
+        Self { audit_log }
+    }
+}
+
+impl<S> Layer<S> for AuditLayer {
+    type Service = AuditService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditService { inner, audit_log: self.audit_log.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuditService<S> {
+    inner: S,
+    audit_log: Option<Arc<AuditLog>>,
+}
+
+impl<S> Service<Request<Body>> for AuditService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let Some(audit_log) = self.audit_log.clone() else {
+            return Box::pin(inner.call(request));
+        };
+
+        Box::pin(async move {
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_millis() as u64);
+            let (request, body) = JsonRpcBody::read(request).await?;
+            let audited = body.methods.iter().any(|method| is_audited(method));
+            let headers = request.headers().clone();
+            let response = inner.call(request).await?;
+            if !audited {
+                return Ok(response);
+            }
+
+            let (parts, response_body) = response.into_parts();
+            let response_body = hyper::body::to_bytes(response_body).await?;
+            audit_log.record(&audit_entries(&headers, &body.bytes, &response_body, timestamp));
+
+            Ok(Response::from_parts(parts, Body::from(response_body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::HeaderValue;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_audit_entries() {
+        // Given
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("10.0.0.1"));
+        let request = br#"[
+            {"jsonrpc":"2.0","id":1,"method":"eth_sendRawTransaction","params":["0x02"]},
+            {"jsonrpc":"2.0","id":2,"method":"eth_blockNumber","params":[]},
+            {"jsonrpc":"2.0","id":3,"method":"evm_mine","params":[]}
+        ]"#;
+        let response = br#"[
+            {"jsonrpc":"2.0","id":1,"result":"0xabc"},
+            {"jsonrpc":"2.0","id":2,"result":"0x1"},
+            {"jsonrpc":"2.0","id":3,"error":{"code":-32601,"message":"Method not found"}}
+        ]"#;
+
+        // When
+        let entries = audit_entries(&headers, request, response, 42);
+
+        // Then
+        assert_eq!(2, entries.len());
+        assert_eq!(Some("10.0.0.1".parse().unwrap()), entries[0].client_ip);
+        assert_eq!("eth_sendRawTransaction", entries[0].method);
+        assert_eq!(Some("0xabc".to_string()), entries[0].transaction_hash);
+        assert_eq!(AuditOutcome::Success, entries[0].outcome);
+        assert_eq!(json!(3), entries[1].id);
+        assert_eq!(None, entries[1].transaction_hash);
+        assert_eq!(
+            json!({ "status": "error", "code": -32601, "message": "Method not found" }),
+            serde_json::to_value(&entries[1].outcome).unwrap()
+        );
+        assert!(audit_entries(&headers, br#"{"id":1,"method":"eth_call"}"#, b"", 42).is_empty());
+    }
+}
@@ -0,0 +1,286 @@
+//! Execution of the batch requests: the calls of a batch are served as separate requests, at most
+//! `max_concurrency` at a time, so that a large batch from an indexer doesn't flood the Starknet
+//! node with hundreds of concurrent requests.
+//!
+//! Each call of the batch is isolated from the others: a call whose response is lost, because the
+//! server failed or panicked while serving it or returned an invalid body, is answered with an
+//! internal error carrying its id, the other calls of the batch being answered as usual. The
+//! responses are returned in the order of the calls. The maximum number of calls of a batch is a
+//! limit of [`super::limits`].
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use eyre::{eyre, Result};
+use futures::{FutureExt, StreamExt};
+use hyper::header::CONTENT_LENGTH;
+use hyper::http::request::Parts;
+use hyper::{Body, Request, Response};
+use jsonrpsee::types::error::INTERNAL_ERROR_CODE;
+use serde_json::{json, Value};
+use tower::{Layer, Service};
+
+use super::params::json_response;
+use super::JsonRpcBody;
+
+/// Default number of calls of a batch served concurrently.
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 16;
+
+/// Configuration of the execution of the batch requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchConfig {
+    /// Maximum number of calls of a batch served concurrently.
+    pub max_concurrency: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self { max_concurrency: DEFAULT_BATCH_CONCURRENCY }
+    }
+}
+
+impl BatchConfig {
+    /// Create a new `BatchConfig` from the `KAKAROT_BATCH_CONCURRENCY` environment variable.
+    /// Defaults to [`DEFAULT_BATCH_CONCURRENCY`] if not set.
+    pub fn from_env() -> Result<Self> {
+        let max_concurrency = match std::env::var("KAKAROT_BATCH_CONCURRENCY") {
+            Ok(concurrency) => concurrency
+                .parse::<usize>()
+                .ok()
+                .filter(|concurrency| *concurrency > 0)
+                .ok_or_else(|| eyre!("KAKAROT_BATCH_CONCURRENCY should be a strictly positive integer"))?,
+            Err(_) => DEFAULT_BATCH_CONCURRENCY,
+        };
+        Ok(Self { max_concurrency })
+    }
+}
+
+/// Returns the response of a call whose response was lost, `None` for a notification.
+fn internal_error(call: &Value, reason: &str) -> Option<Value> {
+    let id = call.get("id")?;
+    Some(json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": INTERNAL_ERROR_CODE, "message": format!("internal error: {reason}") },
+    }))
+}
+
+/// Returns the request serving a single call of the batch, with the method, the URI and the headers
+/// of the batch request.
+fn call_request(parts: &Parts, call: &Value) -> Request<Body> {
+    let body = JsonRpcBody::new(call.to_string().into());
+    let mut request = Request::new(());
+    *request.method_mut() = parts.method.clone();
+    *request.uri_mut() = parts.uri.clone();
+    *request.version_mut() = parts.version;
+    *request.headers_mut() = parts.headers.clone();
+    request.headers_mut().insert(CONTENT_LENGTH, body.bytes.len().into());
+    body.into_request(request.into_parts().0)
+}
+
+/// Serves a single call of the batch, returning its response or `None` for a notification.
+async fn serve_call<S>(mut inner: S, request: Request<Body>, call: Value) -> Option<Value>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+{
+    // A panic of the server must not lose the responses of the other calls
+    let response = AssertUnwindSafe(async {
+        futures::future::poll_fn(|cx| inner.poll_ready(cx)).await.map_err(|_| "server unavailable")?;
+        let response = inner.call(request).await.map_err(|_| "request failed")?;
+        hyper::body::to_bytes(response.into_body()).await.map_err(|_| "response lost")
+    })
+    .catch_unwind()
+    .await
+    .unwrap_or(Err("request panicked"));
+
+    let body = match response {
+        Ok(body) => body,
+        Err(reason) => {
+            tracing::warn!("Batch call {} failed: {reason}", call.get("method").unwrap_or(&Value::Null));
+            return internal_error(&call, reason);
+        }
+    };
+    match serde_json::from_slice::<Value>(&body) {
+        Ok(response @ Value::Object(_)) => Some(response),
+        // Notifications get an empty body
+        _ if call.get("id").is_none() => None,
+        _ => internal_error(&call, "invalid response"),
+    }
+}
+
+/// Tower layer serving the calls of the batch requests with a bounded concurrency.
+#[derive(Clone)]
+pub struct BatchLayer {
+    max_concurrency: usize,
+}
+
+impl BatchLayer {
+    pub fn new(config: &BatchConfig) -> Self {
+        Self { max_concurrency: config.max_concurrency }
+    }
+}
+
+impl<S> Layer<S> for BatchLayer {
+    type Service = BatchService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BatchService { inner, max_concurrency: self.max_concurrency }
+    }
+}
+
+#[derive(Clone)]
+pub struct BatchService<S> {
+    inner: S,
+    max_concurrency: usize,
+}
+
+impl<S> Service<Request<Body>> for BatchService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let max_concurrency = self.max_concurrency;
+
+        Box::pin(async move {
+            let (request, body) = JsonRpcBody::read(request).await?;
+
+            // Single calls, empty batches and invalid bodies are left to the server
+            let calls = match serde_json::from_slice::<Value>(&body.bytes) {
+                Ok(Value::Array(calls)) if !calls.is_empty() => calls,
+                _ => return inner.call(request).await,
+            };
+            let (parts, _) = request.into_parts();
+
+            let responses: Vec<Value> = futures::stream::iter(calls)
+                .map(move |call| serve_call(inner.clone(), call_request(&parts, &call), call))
+                .buffered(max_concurrency)
+                .filter_map(futures::future::ready)
+                .collect()
+                .await;
+
+            // A batch of notifications gets an empty body
+            Ok(json_response((!responses.is_empty()).then_some(Value::Array(responses))))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// Server answering each call with its method, counting the calls served concurrently.
+    #[derive(Clone, Default)]
+    struct EchoService {
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl Service<Request<Body>> for EchoService {
+        type Response = Response<Body>;
+        type Error = hyper::Error;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<Body>) -> Self::Future {
+            let service = self.clone();
+            Box::pin(async move {
+                let body = hyper::body::to_bytes(request.into_body()).await?;
+                let call: Value = serde_json::from_slice(&body).unwrap();
+                let in_flight = service.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                service.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                service.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                match call["method"].as_str() {
+                    Some("eth_panic") => panic!("eth_panic"),
+                    Some("eth_garbage") => Ok(Response::new(Body::from("<html>"))),
+                    Some(method) if call.get("id").is_some() => {
+                        Ok(json_response(Some(json!({ "jsonrpc": "2.0", "id": call["id"], "result": method }))))
+                    }
+                    _ => Ok(json_response(None)),
+                }
+            })
+        }
+    }
+
+    fn batch_request(calls: Value) -> Request<Body> {
+        Request::post("/").body(Body::from(calls.to_string())).unwrap()
+    }
+
+    async fn response_body(response: Response<Body>) -> Value {
+        serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_batch_concurrency_is_bounded() {
+        // Given
+        let server = EchoService::default();
+        let mut service = BatchLayer::new(&BatchConfig { max_concurrency: 3 }).layer(server.clone());
+        let calls: Vec<Value> =
+            (0..10).map(|id| json!({ "jsonrpc": "2.0", "id": id, "method": "eth_blockNumber" })).collect();
+
+        // When
+        let response = service.call(batch_request(Value::Array(calls))).await.unwrap();
+
+        // Then
+        let responses = response_body(response).await;
+        let ids: Vec<_> = responses.as_array().unwrap().iter().map(|response| response["id"].clone()).collect();
+        assert_eq!((0..10).map(|id| json!(id)).collect::<Vec<_>>(), ids);
+        assert_eq!(3, server.max_in_flight.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_batch_call_failures_are_isolated() {
+        // Given
+        let mut service = BatchLayer::new(&BatchConfig::default()).layer(EchoService::default());
+        let calls = json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "eth_chainId" },
+            { "jsonrpc": "2.0", "id": 2, "method": "eth_panic" },
+            { "jsonrpc": "2.0", "method": "eth_notify" },
+            { "jsonrpc": "2.0", "id": 3, "method": "eth_garbage" },
+            { "jsonrpc": "2.0", "id": 4, "method": "eth_blockNumber" }
+        ]);
+
+        // When
+        let response = service.call(batch_request(calls)).await.unwrap();
+
+        // Then
+        let responses = response_body(response).await;
+        assert_eq!(4, responses.as_array().unwrap().len());
+        assert_eq!(json!("eth_chainId"), responses[0]["result"]);
+        assert_eq!(json!({ "code": -32603, "message": "internal error: request panicked" }), responses[1]["error"]);
+        assert_eq!(json!(3), responses[2]["id"]);
+        assert_eq!(json!("internal error: invalid response"), responses[2]["error"]["message"]);
+        assert_eq!(json!("eth_blockNumber"), responses[3]["result"]);
+    }
+
+    #[test]
+    fn test_batch_config_from_env() {
+        std::env::set_var("KAKAROT_BATCH_CONCURRENCY", "0");
+        assert!(BatchConfig::from_env().is_err());
+        std::env::set_var("KAKAROT_BATCH_CONCURRENCY", "4");
+        assert_eq!(BatchConfig { max_concurrency: 4 }, BatchConfig::from_env().unwrap());
+        std::env::remove_var("KAKAROT_BATCH_CONCURRENCY");
+        assert_eq!(BatchConfig::default(), BatchConfig::from_env().unwrap());
+    }
+}
@@ -0,0 +1,89 @@
+//! Cross-origin requests: the origins allowed to call the RPC from a browser. All the origins are
+//! allowed by default, so that the dapps served from any domain can reach a public endpoint.
+use eyre::{eyre, Result};
+use hyper::http::HeaderValue;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use url::Url;
+
+/// Configuration of the CORS layer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// Origins allowed to call the RPC, any origin when `None`.
+    pub allowed_origins: Option<Vec<HeaderValue>>,
+}
+
+impl CorsConfig {
+    /// Create a new `CorsConfig` from the `KAKAROT_CORS_ALLOWED_ORIGINS` environment variable, a
+    /// comma-separated list of origins or `*`. Allows any origin if not set.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("KAKAROT_CORS_ALLOWED_ORIGINS") {
+            Ok(origins) => origins.parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Returns the CORS layer of the server.
+    pub fn layer(&self) -> CorsLayer {
+        let allow_origin = match &self.allowed_origins {
+            Some(origins) => AllowOrigin::list(origins.clone()),
+            None => AllowOrigin::any(),
+        };
+        CorsLayer::new().allow_methods(Any).allow_headers(Any).allow_origin(allow_origin)
+    }
+}
+
+impl std::str::FromStr for CorsConfig {
+    type Err = eyre::Report;
+
+    fn from_str(origins: &str) -> Result<Self> {
+        if origins.trim() == "*" {
+            return Ok(Self::default());
+        }
+        let allowed_origins = origins
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(parse_origin)
+            .collect::<Result<Vec<_>>>()?;
+        if allowed_origins.is_empty() {
+            return Err(eyre!("KAKAROT_CORS_ALLOWED_ORIGINS should be * or a list of origins"));
+        }
+        Ok(Self { allowed_origins: Some(allowed_origins) })
+    }
+}
+
+/// Parses an origin, e.g. `https://app.kakarot.org`, as sent by the browsers in the `Origin`
+/// header: a scheme, a host and an optional port, without a path.
+fn parse_origin(origin: &str) -> Result<HeaderValue> {
+    let invalid =
+        || eyre!("KAKAROT_CORS_ALLOWED_ORIGINS should contain origins like https://example.com, got {origin}");
+    let url = Url::parse(origin).map_err(|_| invalid())?;
+    if !matches!(url.scheme(), "http" | "https") || url.host().is_none() || url.path() != "/" || origin.ends_with('/') {
+        return Err(invalid());
+    }
+    HeaderValue::from_str(origin).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cors_config() {
+        let parse = |origins: &str| origins.parse::<CorsConfig>();
+
+        assert_eq!(CorsConfig::default(), parse("*").unwrap());
+        assert_eq!(
+            Some(vec![
+                HeaderValue::from_static("https://app.kakarot.org"),
+                HeaderValue::from_static("http://localhost:3000")
+            ]),
+            parse("https://app.kakarot.org, http://localhost:3000").unwrap().allowed_origins
+        );
+        assert!(parse("").is_err());
+        assert!(parse("app.kakarot.org").is_err());
+        assert!(parse("https://app.kakarot.org/").is_err());
+        assert!(parse("https://app.kakarot.org/swap").is_err());
+        assert!(parse("ftp://app.kakarot.org").is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! Limits on the HTTP requests, so that a public endpoint can't be saturated by a few clients: a
+//! maximum body size, a maximum number of calls per batch, and global and per-IP rate limits.
+//!
+//! The rejections follow geth: an oversized body is answered with `413 Payload Too Large` and an
+//! oversized batch with a single `batch too large` error. A rate limited request is answered with
+//! `429 Too Many Requests` and the `limit exceeded` error of EIP-1474.
+//!
+//! The rate limits count the calls of a request, a batch of 100 calls costs 100 requests. The IP of
+//! a client is read from the `X-Forwarded-For` and `X-Real-IP` headers set by the reverse proxy in
+//! front of the RPC, the requests without these headers share a single per-IP limit.
+//!
+//! The limits are the outermost layer: the body is read here only, within its maximum size, and
+//! passed down to the inner layers as a [`JsonRpcBody`]. The `GET` requests, to the health and
+//! metrics endpoints, aren't rate limited.
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use eyre::{eyre, Result};
+use hyper::body::{Bytes, HttpBody};
+use hyper::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::http::HeaderMap;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde_json::{json, Value};
+use tower::{Layer, Service};
+
+use super::JsonRpcBody;
+
+/// Default maximum size of a request body, in bytes, as in geth.
+pub const DEFAULT_MAX_REQUEST_BODY_SIZE: usize = 5 * 1024 * 1024;
+/// Default maximum number of calls in a batch, as in geth.
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 1000;
+/// Maximum number of clients tracked by the per-IP rate limit, the clients with a full bucket are
+/// forgotten beyond it.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// JSON-RPC error code of an invalid request.
+const INVALID_REQUEST_CODE: i32 = -32600;
+/// JSON-RPC error code of a request exceeding a limit, from EIP-1474.
+const LIMIT_EXCEEDED_CODE: i32 = -32005;
+
+/// Limits applied to the HTTP requests. The rate limits are disabled when `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitsConfig {
+    /// Maximum size of a request body, in bytes.
+    pub max_request_body_size: usize,
+    /// Maximum number of calls in a batch.
+    pub max_batch_size: usize,
+    /// Maximum number of calls per second, for all the clients.
+    pub global_rate_limit: Option<u32>,
+    /// Maximum number of calls per second, for each client IP.
+    pub per_ip_rate_limit: Option<u32>,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_request_body_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            global_rate_limit: None,
+            per_ip_rate_limit: None,
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// Create a new `LimitsConfig` from the `KAKAROT_MAX_REQUEST_BODY_SIZE`,
+    /// `KAKAROT_MAX_BATCH_SIZE`, `KAKAROT_RATE_LIMIT` and `KAKAROT_RATE_LIMIT_PER_IP` environment
+    /// variables. The sizes fall back to the defaults and the rate limits are disabled when not
+    /// set.
+    pub fn from_env() -> Result<Self> {
+        fn positive<T: std::str::FromStr + PartialOrd + Default>(name: &str) -> Result<Option<T>> {
+            match std::env::var(name) {
+                Ok(value) => value
+                    .parse::<T>()
+                    .ok()
+                    .filter(|value| *value > T::default())
+                    .map(Some)
+                    .ok_or_else(|| eyre!("{name} should be a strictly positive integer, got {value}")),
+                Err(_) => Ok(None),
+            }
+        }
+        Ok(Self {
+            max_request_body_size: positive("KAKAROT_MAX_REQUEST_BODY_SIZE")?.unwrap_or(DEFAULT_MAX_REQUEST_BODY_SIZE),
+            max_batch_size: positive("KAKAROT_MAX_BATCH_SIZE")?.unwrap_or(DEFAULT_MAX_BATCH_SIZE),
+            global_rate_limit: positive("KAKAROT_RATE_LIMIT")?,
+            per_ip_rate_limit: positive("KAKAROT_RATE_LIMIT_PER_IP")?,
+        })
+    }
+}
+
+/// Token bucket holding up to a second of calls, refilled continuously.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(rate: u32, now: Instant) -> Self {
+        Self { tokens: f64::from(rate), refilled_at: now }
+    }
+
+    fn refill(&mut self, rate: u32, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * f64::from(rate)).min(f64::from(rate));
+        self.refilled_at = now;
+    }
+
+    fn is_full(&self, rate: u32) -> bool {
+        self.tokens >= f64::from(rate)
+    }
+
+    /// Takes the tokens of the calls if the bucket holds enough of them.
+    fn try_take(&mut self, calls: usize, rate: u32, now: Instant) -> bool {
+        self.refill(rate, now);
+        if self.tokens < calls as f64 {
+            return false;
+        }
+        self.tokens -= calls as f64;
+        true
+    }
+}
+
+/// Global and per-IP token buckets.
+#[derive(Debug)]
+struct RateLimiter {
+    global_rate_limit: Option<u32>,
+    per_ip_rate_limit: Option<u32>,
+    global: Mutex<Option<TokenBucket>>,
+    per_ip: Mutex<HashMap<Option<IpAddr>, TokenBucket>>,
+}
+
+impl RateLimiter {
+    fn new(config: &LimitsConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            global_rate_limit: config.global_rate_limit,
+            per_ip_rate_limit: config.per_ip_rate_limit,
+            global: Mutex::new(config.global_rate_limit.map(|rate| TokenBucket::full(rate, now))),
+            per_ip: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns true if the client can make the calls now, taking their tokens from its bucket and
+    /// from the global one.
+    fn check(&self, client: Option<IpAddr>, calls: usize, now: Instant) -> bool {
+        if let Some(rate) = self.per_ip_rate_limit {
+            let mut per_ip = self.per_ip.lock().expect("Failed to lock the per-IP rate limits");
+            if per_ip.len() >= MAX_TRACKED_CLIENTS {
+                per_ip.retain(|_, bucket| {
+                    bucket.refill(rate, now);
+                    !bucket.is_full(rate)
+                });
+            }
+            let bucket = per_ip.entry(client).or_insert_with(|| TokenBucket::full(rate, now));
+            if !bucket.try_take(calls, rate, now) {
+                return false;
+            }
+        }
+        if let Some(rate) = self.global_rate_limit {
+            let mut global = self.global.lock().expect("Failed to lock the global rate limit");
+            if let Some(bucket) = global.as_mut() {
+                return bucket.try_take(calls, rate, now);
+            }
+        }
+        true
+    }
+}
+
+/// Returns the IP of the client, from the headers of the reverse proxy.
+pub(crate) fn client_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    header("x-forwarded-for")
+        .and_then(|forwarded_for| forwarded_for.split(',').next())
+        .or_else(|| header("x-real-ip"))
+        .and_then(|ip| ip.trim().parse().ok())
+}
+
+/// Returns the id of the first call of a batch request body.
+fn first_call_id(body: &[u8]) -> Value {
+    let Ok(Value::Array(requests)) = serde_json::from_slice::<Value>(body) else {
+        return Value::Null;
+    };
+    requests.iter().find_map(|request| request.get("id").cloned()).unwrap_or(Value::Null)
+}
+
+fn error_response(status: StatusCode, body: String, content_type: &'static str) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+fn jsonrpc_error(id: Value, code: i32, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn body_too_large_response(size: usize, max_size: usize) -> Response<Body> {
+    error_response(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!("content length too large ({size}>{max_size})"),
+        "text/plain; charset=utf-8",
+    )
+}
+
+/// Reads the body, failing with the size read so far once it exceeds the maximum size.
+async fn read_body(mut body: Body, max_size: usize) -> Result<Result<Bytes, usize>, hyper::Error> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        bytes.extend_from_slice(&chunk?);
+        if bytes.len() > max_size {
+            return Ok(Err(bytes.len()));
+        }
+    }
+    Ok(Ok(bytes.into()))
+}
+
+/// Tower layer rejecting the HTTP requests exceeding the limits.
+#[derive(Clone)]
+pub struct LimitsLayer {
+    config: LimitsConfig,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl LimitsLayer {
+    pub fn new(config: LimitsConfig) -> Self {
+        let rate_limiter = Arc::new(RateLimiter::new(&config));
+        Self { config, rate_limiter }
+    }
+}
+
+impl<S> Layer<S> for LimitsLayer {
+    type Service = LimitsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LimitsService { inner, config: self.config.clone(), rate_limiter: Arc::clone(&self.rate_limiter) }
+    }
+}
+
+#[derive(Clone)]
+pub struct LimitsService<S> {
+    inner: S,
+    config: LimitsConfig,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl<S> Service<Request<Body>> for LimitsService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let config = self.config.clone();
+        let rate_limiter = Arc::clone(&self.rate_limiter);
+
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+
+            // Reject the announced oversized bodies before reading them
+            let content_length = parts
+                .headers
+                .get(CONTENT_LENGTH)
+                .and_then(|length| length.to_str().ok())
+                .and_then(|length| length.parse::<usize>().ok());
+            if let Some(content_length) = content_length.filter(|length| *length > config.max_request_body_size) {
+                return Ok(body_too_large_response(content_length, config.max_request_body_size));
+            }
+            let body = match read_body(body, config.max_request_body_size).await? {
+                Ok(body) => JsonRpcBody::new(body),
+                Err(size) => return Ok(body_too_large_response(size, config.max_request_body_size)),
+            };
+
+            if body.calls > config.max_batch_size {
+                let error = jsonrpc_error(first_call_id(&body.bytes), INVALID_REQUEST_CODE, "batch too large");
+                return Ok(error_response(StatusCode::OK, Value::Array(vec![error]).to_string(), "application/json"));
+            }
+
+            if parts.method != Method::GET && !rate_limiter.check(client_ip(&parts.headers), body.calls, Instant::now())
+            {
+                let error = jsonrpc_error(Value::Null, LIMIT_EXCEEDED_CODE, "limit exceeded");
+                return Ok(error_response(StatusCode::TOO_MANY_REQUESTS, error.to_string(), "application/json"));
+            }
+
+            inner.call(body.into_request(parts)).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        // Given
+        let config = LimitsConfig { global_rate_limit: Some(10), per_ip_rate_limit: Some(4), ..Default::default() };
+        let rate_limiter = RateLimiter::new(&config);
+        let (alice, bob) = (Some("10.0.0.1".parse().unwrap()), Some("10.0.0.2".parse().unwrap()));
+        let now = Instant::now();
+
+        // Then
+        assert!(rate_limiter.check(alice, 4, now));
+        assert!(!rate_limiter.check(alice, 1, now));
+        assert!(rate_limiter.check(bob, 4, now));
+        // The per-IP buckets are refilled after a quarter of a second, not the global one
+        assert!(rate_limiter.check(alice, 1, now + Duration::from_millis(250)));
+        assert!(!rate_limiter.check(bob, 4, now + Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn test_client_ip() {
+        // Given
+        let mut headers = HeaderMap::new();
+
+        // Then
+        assert_eq!(None, client_ip(&headers));
+        headers.insert("x-real-ip", HeaderValue::from_static("10.0.0.2"));
+        assert_eq!(Some("10.0.0.2".parse().unwrap()), client_ip(&headers));
+        headers.insert("x-forwarded-for", HeaderValue::from_static("10.0.0.1, 172.16.0.1"));
+        assert_eq!(Some("10.0.0.1".parse().unwrap()), client_ip(&headers));
+    }
+
+    #[test]
+    fn test_first_call_id() {
+        assert_eq!(Value::Null, first_call_id(br#"{"jsonrpc":"2.0","id":1,"method":"eth_chainId"}"#));
+        assert_eq!(json!(7), first_call_id(br#"[{"method":"eth_getLogs"},{"id":7,"method":"eth_getLogs"}]"#));
+        assert_eq!(Value::Null, first_call_id(b"[]"));
+    }
+
+    #[tokio::test]
+    async fn test_read_body_over_max_size() {
+        assert_eq!(Ok(Bytes::from_static(b"1234")), read_body(Body::from("1234"), 4).await.unwrap());
+        assert_eq!(Err(5), read_body(Body::from("12345"), 4).await.unwrap());
+    }
+}
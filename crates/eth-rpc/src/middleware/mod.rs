@@ -0,0 +1,95 @@
+pub mod audit;
+pub mod batch;
+pub mod cors;
+pub mod limits;
+pub mod params;
+pub mod priority;
+pub mod shadow;
+pub mod standby;
+
+use std::sync::Arc;
+
+use hyper::body::Bytes;
+use hyper::http::request::Parts;
+use hyper::{Body, Request};
+use serde_json::Value;
+
+/// Body of an HTTP request, read once by [`limits::LimitsLayer`], the outermost layer, and passed
+/// down to the inner layers in the request extensions with the JSON-RPC methods it calls.
+#[derive(Debug, Clone)]
+pub struct JsonRpcBody {
+    pub bytes: Bytes,
+    /// Methods called, for both single and batch requests. Empty if the body isn't valid
+    /// JSON-RPC.
+    pub methods: Arc<[String]>,
+    /// Number of calls, a body which isn't a JSON-RPC batch counting as a single call.
+    pub calls: usize,
+}
+
+impl JsonRpcBody {
+    pub fn new(bytes: Bytes) -> Self {
+        let method = |request: &Value| request.get("method").and_then(Value::as_str).map(ToString::to_string);
+        let (methods, calls) = match serde_json::from_slice::<Value>(&bytes) {
+            Ok(Value::Array(requests)) => (requests.iter().filter_map(method).collect(), requests.len().max(1)),
+            Ok(request) => (method(&request).into_iter().collect(), 1),
+            Err(_) => (vec![], 1),
+        };
+        Self { bytes, methods: methods.into(), calls }
+    }
+
+    /// Returns the request and its body, taken from the request extensions. The body of a request
+    /// which didn't go through [`limits::LimitsLayer`] is read, without a size limit.
+    pub async fn read(request: Request<Body>) -> Result<(Request<Body>, Self), hyper::Error> {
+        if let Some(body) = request.extensions().get::<Self>().cloned() {
+            return Ok((request, body));
+        }
+        let (parts, body) = request.into_parts();
+        let body = Self::new(hyper::body::to_bytes(body).await?);
+        Ok((body.clone().into_request(parts), body))
+    }
+
+    /// Returns the request with this body, carried in its extensions for the inner layers.
+    pub fn into_request(self, mut parts: Parts) -> Request<Body> {
+        let body = Body::from(self.bytes.clone());
+        parts.extensions.insert(self);
+        Request::from_parts(parts, body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonrpc_body() {
+        // Given
+        let single = r#"{"jsonrpc":"2.0","id":1,"method":"eth_blockNumber","params":[]}"#;
+        let batch = r#"[{"jsonrpc":"2.0","id":1,"method":"eth_chainId"},{"id":2},{"id":3,"method":"eth_call"}]"#;
+
+        // When
+        let single = JsonRpcBody::new(Bytes::from_static(single.as_bytes()));
+        let batch = JsonRpcBody::new(Bytes::from_static(batch.as_bytes()));
+        let invalid = JsonRpcBody::new(Bytes::from_static(b"not json"));
+        let empty_batch = JsonRpcBody::new(Bytes::from_static(b"[]"));
+
+        // Then
+        assert_eq!((&["eth_blockNumber".to_string()][..], 1), (&*single.methods, single.calls));
+        assert_eq!((&["eth_chainId".to_string(), "eth_call".to_string()][..], 3), (&*batch.methods, batch.calls));
+        assert!(invalid.methods.is_empty() && invalid.calls == 1);
+        assert!(empty_batch.methods.is_empty() && empty_batch.calls == 1);
+    }
+
+    #[tokio::test]
+    async fn test_jsonrpc_body_is_read_once() {
+        // Given
+        let body = JsonRpcBody::new(Bytes::from_static(br#"{"id":1,"method":"eth_chainId"}"#));
+        let request = body.into_request(Request::new(()).into_parts().0);
+
+        // When
+        let (request, body) = JsonRpcBody::read(request).await.unwrap();
+
+        // Then
+        assert_eq!(&["eth_chainId".to_string()][..], &*body.methods);
+        assert_eq!(body.bytes, hyper::body::to_bytes(request.into_body()).await.unwrap());
+    }
+}
@@ -0,0 +1,632 @@
+//! Validation of the JSON-RPC params: the params of the calls to the Ethereum methods are checked
+//! against the specification before reaching the RPC methods, so that every malformed param is
+//! rejected with the same `-32602` error, carrying the path of the param (e.g. `params[0].to`).
+//!
+//! In the strict mode, the params must be exactly as specified. In the lenient mode, common
+//! deviations are first coerced to the specified encoding: quantities given as JSON numbers or
+//! decimal strings, hex strings missing their `0x` prefix, quantities with leading zeros and tags
+//! in upper case. A string of decimal digits given for a quantity is read as a decimal number.
+//!
+//! The params are validated on HTTP requests, calls to methods without specification and params
+//! given by name are forwarded unchecked.
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use eyre::{eyre, Result};
+use hyper::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::{Body, Request, Response};
+use jsonrpsee::types::error::INVALID_PARAMS_CODE;
+use kakarot_rpc_core::models::encoding::{is_data, is_quantity, to_quantity};
+use reth_primitives::U256;
+use serde_json::{json, Value};
+use tower::{Layer, Service};
+
+use super::JsonRpcBody;
+
+/// Validation mode of the params.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParamsMode {
+    /// Params are rejected unless exactly as specified.
+    Strict,
+    /// Common deviations are coerced before the params are validated.
+    #[default]
+    Lenient,
+}
+
+impl ParamsMode {
+    /// Create a new `ParamsMode` from the `KAKAROT_PARAMS_MODE` environment variable, either
+    /// `strict` or `lenient`, falling back to the lenient mode when not set.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("KAKAROT_PARAMS_MODE") {
+            Ok(mode) => match mode.to_lowercase().as_str() {
+                "strict" => Ok(Self::Strict),
+                "lenient" => Ok(Self::Lenient),
+                _ => Err(eyre!("KAKAROT_PARAMS_MODE should be either strict or lenient, got {mode}")),
+            },
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+/// Expected type of a param.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Quantity,
+    BlockNumber,
+    BlockId,
+    Address,
+    Hash,
+    Data,
+    StorageKey,
+    Bool,
+    CallRequest,
+    Filter,
+    Addresses,
+    Topics,
+    Percentiles,
+    AccessList,
+    StateOverride,
+    StorageSlots,
+}
+
+impl Kind {
+    fn expected(self) -> &'static str {
+        match self {
+            Self::Quantity => "a hex encoded quantity",
+            Self::BlockNumber => "a hex encoded block number or a block tag",
+            Self::BlockId => "a block number, a block tag or a block hash object",
+            Self::Address => "a hex encoded 20 bytes address",
+            Self::Hash => "a hex encoded 32 bytes hash",
+            Self::Data => "hex encoded bytes",
+            Self::StorageKey => "a hex encoded storage slot of at most 32 bytes",
+            Self::Bool => "a boolean",
+            Self::CallRequest => "a transaction call object",
+            Self::Filter => "a filter object",
+            Self::Addresses => "an address or an array of addresses",
+            Self::Topics => "an array of topics",
+            Self::Percentiles => "an array of percentiles",
+            Self::AccessList => "an access list",
+            Self::StateOverride => "an object of account overrides keyed by address",
+            Self::StorageSlots => "an object of 32 bytes storage values keyed by slot",
+        }
+    }
+}
+
+/// Specification of a positional param.
+#[derive(Debug, Clone, Copy)]
+struct Param {
+    kind: Kind,
+    optional: bool,
+}
+
+const fn required(kind: Kind) -> Param {
+    Param { kind, optional: false }
+}
+
+const fn optional(kind: Kind) -> Param {
+    Param { kind, optional: true }
+}
+
+const BLOCK_TAGS: [&str; 5] = ["earliest", "finalized", "safe", "latest", "pending"];
+
+const CALL_REQUEST_FIELDS: [(&str, Kind); 13] = [
+    ("from", Kind::Address),
+    ("to", Kind::Address),
+    ("gas", Kind::Quantity),
+    ("gasPrice", Kind::Quantity),
+    ("maxFeePerGas", Kind::Quantity),
+    ("maxPriorityFeePerGas", Kind::Quantity),
+    ("value", Kind::Quantity),
+    ("nonce", Kind::Quantity),
+    ("chainId", Kind::Quantity),
+    ("type", Kind::Quantity),
+    ("data", Kind::Data),
+    ("input", Kind::Data),
+    ("accessList", Kind::AccessList),
+];
+
+const FILTER_FIELDS: [(&str, Kind); 5] = [
+    ("fromBlock", Kind::BlockNumber),
+    ("toBlock", Kind::BlockNumber),
+    ("blockHash", Kind::Hash),
+    ("address", Kind::Addresses),
+    ("topics", Kind::Topics),
+];
+
+/// Promotes a list of params to a static slice, which the calls to `required` and `optional`
+/// prevent outside of a constant.
+macro_rules! params {
+    ($($param:expr),*) => {{
+        const PARAMS: &[Param] = &[$($param),*];
+        PARAMS
+    }};
+}
+
+const ACCOUNT_OVERRIDE_FIELDS: [(&str, Kind); 5] = [
+    ("balance", Kind::Quantity),
+    ("nonce", Kind::Quantity),
+    ("code", Kind::Data),
+    ("state", Kind::StorageSlots),
+    ("stateDiff", Kind::StorageSlots),
+];
+
+/// Returns the specification of the params of the method, `None` if the method isn't checked.
+fn method_params(method: &str) -> Option<&'static [Param]> {
+    use Kind::*;
+
+    let params = match method {
+        "eth_getBalance" | "eth_getCode" | "eth_getTransactionCount" => params!(required(Address), optional(BlockId)),
+        "eth_getStorageAt" => params!(required(Address), required(StorageKey), optional(BlockId)),
+        "eth_call" => params!(required(CallRequest), optional(BlockId), optional(StateOverride)),
+        "eth_estimateGas" => params!(required(CallRequest), optional(BlockId)),
+        "eth_getBlockByNumber" => params!(required(BlockNumber), required(Bool)),
+        "eth_getBlockByHash" => params!(required(Hash), required(Bool)),
+        "eth_getBlockTransactionCountByNumber" => params!(required(BlockNumber)),
+        "eth_getBlockReceipts" => params!(required(BlockId)),
+        "eth_getBlockTransactionCountByHash" | "eth_getTransactionByHash" | "eth_getTransactionReceipt" => {
+            params!(required(Hash))
+        }
+        "eth_getTransactionByBlockNumberAndIndex" => params!(required(BlockNumber), required(Quantity)),
+        "eth_getTransactionByBlockHashAndIndex" => params!(required(Hash), required(Quantity)),
+        "eth_sendRawTransaction" => params!(required(Data)),
+        "eth_getLogs" | "eth_newFilter" => params!(required(Filter)),
+        "eth_getFilterChanges" | "eth_getFilterLogs" | "eth_uninstallFilter" => params!(required(Quantity)),
+        "eth_feeHistory" => params!(required(Quantity), required(BlockNumber), optional(Percentiles)),
+        _ => return None,
+    };
+    Some(params)
+}
+
+/// Param not matching its specification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidParam {
+    /// Path of the param in the call, e.g. `params[1].fromBlock`.
+    pub path: String,
+    /// Description of the expected param.
+    pub expected: &'static str,
+}
+
+impl InvalidParam {
+    fn new(path: &str, expected: &'static str) -> Self {
+        Self { path: path.to_string(), expected }
+    }
+}
+
+impl fmt::Display for InvalidParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: expected {}", self.path, self.expected)
+    }
+}
+
+/// Checks the params of a JSON-RPC call against the specification of its method, after coercing
+/// them in the lenient mode.
+pub fn check_params(call: &mut Value, mode: ParamsMode) -> Result<(), InvalidParam> {
+    let Some(specification) = call.get("method").and_then(Value::as_str).and_then(method_params) else {
+        return Ok(());
+    };
+
+    let mut no_params = vec![];
+    let params = match call.get_mut("params") {
+        Some(Value::Array(params)) => params,
+        None | Some(Value::Null) => &mut no_params,
+        // Params given by name are left to the RPC methods
+        Some(_) => return Ok(()),
+    };
+
+    if params.len() > specification.len() {
+        return Err(InvalidParam::new(&format!("params[{}]", specification.len()), "no more params"));
+    }
+    for (index, param) in specification.iter().enumerate() {
+        let path = format!("params[{index}]");
+        match params.get_mut(index) {
+            None | Some(Value::Null) if param.optional => {}
+            None => return Err(InvalidParam::new(&path, param.kind.expected())),
+            Some(value) => check_value(value, param.kind, mode, &path)?,
+        }
+    }
+    Ok(())
+}
+
+fn check_value(value: &mut Value, kind: Kind, mode: ParamsMode, path: &str) -> Result<(), InvalidParam> {
+    let invalid = || InvalidParam::new(path, kind.expected());
+
+    match kind {
+        Kind::Quantity => {
+            if mode == ParamsMode::Lenient {
+                coerce_quantity(value);
+            }
+            value.as_str().filter(|quantity| is_quantity(quantity)).map(|_| ()).ok_or_else(invalid)
+        }
+        Kind::BlockNumber => {
+            if let Some(tag) = value.as_str().map(str::to_lowercase).filter(|tag| BLOCK_TAGS.contains(&tag.as_str())) {
+                if mode == ParamsMode::Lenient {
+                    *value = Value::String(tag);
+                }
+            }
+            match value.as_str() {
+                Some(tag) if BLOCK_TAGS.contains(&tag) => Ok(()),
+                _ => check_value(value, Kind::Quantity, mode, path).map_err(|_| invalid()),
+            }
+        }
+        Kind::BlockId => match value {
+            Value::Object(block_id) => {
+                match (block_id.contains_key("blockHash"), block_id.contains_key("blockNumber")) {
+                    (true, false) => {
+                        let hash = block_id.get_mut("blockHash").ok_or_else(invalid)?;
+                        check_value(hash, Kind::Hash, mode, &format!("{path}.blockHash"))
+                    }
+                    (false, true) => {
+                        let number = block_id.get_mut("blockNumber").ok_or_else(invalid)?;
+                        check_value(number, Kind::Quantity, mode, &format!("{path}.blockNumber"))
+                    }
+                    _ => Err(invalid()),
+                }
+            }
+            _ => check_value(value, Kind::BlockNumber, mode, path).map_err(|_| invalid()),
+        },
+        Kind::Address => check_hex(value, mode, Some(20)).ok_or_else(invalid),
+        Kind::Hash => check_hex(value, mode, Some(32)).ok_or_else(invalid),
+        Kind::Data => check_hex(value, mode, None).ok_or_else(invalid),
+        Kind::StorageKey => {
+            if mode == ParamsMode::Lenient {
+                coerce_hex_prefix(value);
+            }
+            let is_storage_key = |key: &str| {
+                key.strip_prefix("0x").is_some_and(|digits| {
+                    (1..=64).contains(&digits.len()) && digits.bytes().all(|digit| digit.is_ascii_hexdigit())
+                })
+            };
+            value.as_str().filter(|key| is_storage_key(key)).map(|_| ()).ok_or_else(invalid)
+        }
+        Kind::Bool => value.as_bool().map(|_| ()).ok_or_else(invalid),
+        Kind::CallRequest => check_fields(value, &CALL_REQUEST_FIELDS, mode, path).ok_or_else(invalid)?,
+        Kind::Filter => check_fields(value, &FILTER_FIELDS, mode, path).ok_or_else(invalid)?,
+        Kind::Addresses => match value {
+            Value::Array(addresses) => addresses.iter_mut().enumerate().try_for_each(|(index, address)| {
+                check_value(address, Kind::Address, mode, &format!("{path}[{index}]"))
+            }),
+            _ => check_value(value, Kind::Address, mode, path).map_err(|_| invalid()),
+        },
+        Kind::Topics => {
+            let topics = value.as_array_mut().ok_or_else(invalid)?;
+            for (index, topic) in topics.iter_mut().enumerate() {
+                let path = format!("{path}[{index}]");
+                match topic {
+                    Value::Null => {}
+                    Value::Array(alternatives) => {
+                        for (alternative_index, alternative) in alternatives.iter_mut().enumerate() {
+                            if !alternative.is_null() {
+                                check_value(alternative, Kind::Hash, mode, &format!("{path}[{alternative_index}]"))?;
+                            }
+                        }
+                    }
+                    _ => check_value(topic, Kind::Hash, mode, &path)?,
+                }
+            }
+            Ok(())
+        }
+        // Percentiles and access lists are left to the RPC methods, only their shape is checked
+        Kind::Percentiles | Kind::AccessList => value.as_array().map(|_| ()).ok_or_else(invalid),
+        // The keys of the objects aren't coerced, only their values
+        Kind::StateOverride => {
+            for (address, account_override) in value.as_object_mut().ok_or_else(invalid)? {
+                let path = format!("{path}.{address}");
+                check_hex(&mut Value::String(address.clone()), ParamsMode::Strict, Some(20))
+                    .ok_or_else(|| InvalidParam::new(&path, Kind::Address.expected()))?;
+                check_fields(account_override, &ACCOUNT_OVERRIDE_FIELDS, mode, &path)
+                    .ok_or_else(|| InvalidParam::new(&path, "an account override object"))??;
+            }
+            Ok(())
+        }
+        Kind::StorageSlots => {
+            for (slot, storage_value) in value.as_object_mut().ok_or_else(invalid)? {
+                let path = format!("{path}.{slot}");
+                check_hex(&mut Value::String(slot.clone()), ParamsMode::Strict, Some(32))
+                    .ok_or_else(|| InvalidParam::new(&path, Kind::Hash.expected()))?;
+                check_value(storage_value, Kind::Hash, mode, &path)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Checks the known fields of an object, returns `None` if the value isn't an object.
+fn check_fields(
+    value: &mut Value,
+    fields: &[(&str, Kind)],
+    mode: ParamsMode,
+    path: &str,
+) -> Option<Result<(), InvalidParam>> {
+    let object = value.as_object_mut()?;
+    Some(fields.iter().try_for_each(|(field, kind)| match object.get_mut(*field) {
+        None | Some(Value::Null) => Ok(()),
+        Some(value) => check_value(value, *kind, mode, &format!("{path}.{field}")),
+    }))
+}
+
+/// Checks that the value is a `0x` prefixed hex string of whole bytes, of the given number of bytes
+/// if any.
+fn check_hex(value: &mut Value, mode: ParamsMode, bytes: Option<usize>) -> Option<()> {
+    if mode == ParamsMode::Lenient {
+        coerce_hex_prefix(value);
+    }
+    is_data(value.as_str()?, bytes).then_some(())
+}
+
+/// Adds the missing `0x` prefix of a hex string.
+fn coerce_hex_prefix(value: &mut Value) {
+    if let Value::String(hex) = value {
+        if let Some(digits) = hex.strip_prefix("0X") {
+            *hex = format!("0x{digits}");
+        } else if !hex.starts_with("0x") && !hex.is_empty() && hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            *hex = format!("0x{hex}");
+        }
+    }
+}
+
+/// Rewrites a quantity given as a JSON number, a decimal string or a hex string without prefix or
+/// with leading zeros as a canonical hex quantity.
+fn coerce_quantity(value: &mut Value) {
+    let quantity = match value {
+        Value::Number(number) => number.as_u64().map(U256::from),
+        Value::String(quantity) => match quantity.strip_prefix("0x").or_else(|| quantity.strip_prefix("0X")) {
+            Some(digits) => parse_digits(digits, 16),
+            None if quantity.bytes().all(|digit| digit.is_ascii_digit()) => parse_digits(quantity, 10),
+            None => parse_digits(quantity, 16),
+        },
+        _ => None,
+    };
+    if let Some(quantity) = quantity {
+        *value = Value::String(to_quantity(quantity));
+    }
+}
+
+fn parse_digits(digits: &str, radix: u64) -> Option<U256> {
+    if digits.is_empty() {
+        return None;
+    }
+    U256::from_str_radix(digits, radix).ok()
+}
+
+/// Checked request: the calls to forward to the RPC methods and the error responses of the
+/// rejected calls.
+#[derive(Debug, PartialEq)]
+struct CheckedRequest {
+    forward: Option<Value>,
+    errors: Vec<Value>,
+}
+
+fn check_request(request: Value, mode: ParamsMode) -> CheckedRequest {
+    let check_call = |mut call: Value| match check_params(&mut call, mode) {
+        Ok(()) => Ok(call),
+        // Notifications, without id, get no response
+        Err(err) => Err(call.get("id").map(|id| invalid_params_response(id.clone(), &err))),
+    };
+
+    match request {
+        Value::Array(calls) if !calls.is_empty() => {
+            let (mut forward, mut errors) = (vec![], vec![]);
+            for call in calls {
+                match check_call(call) {
+                    Ok(call) => forward.push(call),
+                    Err(error) => errors.extend(error),
+                }
+            }
+            CheckedRequest { forward: (!forward.is_empty()).then_some(Value::Array(forward)), errors }
+        }
+        Value::Array(_) => CheckedRequest { forward: Some(request), errors: vec![] },
+        call => match check_call(call) {
+            Ok(call) => CheckedRequest { forward: Some(call), errors: vec![] },
+            Err(error) => CheckedRequest { forward: None, errors: error.into_iter().collect() },
+        },
+    }
+}
+
+fn invalid_params_response(id: Value, err: &InvalidParam) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": INVALID_PARAMS_CODE,
+            "message": format!("invalid params: {err}"),
+            "data": { "path": err.path },
+        },
+    })
+}
+
+pub(super) fn json_response(body: Option<Value>) -> Response<Body> {
+    let body = body.map(|body| body.to_string()).unwrap_or_default();
+    let mut response = Response::new(Body::from(body));
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+/// Tower layer validating the params of the HTTP requests.
+#[derive(Clone)]
+pub struct ParamsLayer {
+    mode: ParamsMode,
+}
+
+impl ParamsLayer {
+    pub fn new(mode: ParamsMode) -> Self {
+        Self { mode }
+    }
+}
+
+impl<S> Layer<S> for ParamsLayer {
+    type Service = ParamsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ParamsService { inner, mode: self.mode }
+    }
+}
+
+#[derive(Clone)]
+pub struct ParamsService<S> {
+    inner: S,
+    mode: ParamsMode,
+}
+
+impl<S> Service<Request<Body>> for ParamsService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let mode = self.mode;
+
+        Box::pin(async move {
+            let (request, body) = JsonRpcBody::read(request).await?;
+
+            // Requests which aren't valid JSON are left to the server to reject
+            let Ok(calls) = serde_json::from_slice::<Value>(&body.bytes) else {
+                return inner.call(request).await;
+            };
+            let (mut parts, _) = request.into_parts();
+            let is_batch = calls.is_array();
+            let CheckedRequest { forward, mut errors } = check_request(calls, mode);
+
+            let Some(forward) = forward else {
+                return Ok(json_response(if is_batch { Some(Value::Array(errors)) } else { errors.pop() }));
+            };
+
+            // The params may have been coerced, the forwarded body is re-encoded
+            let forward = JsonRpcBody::new(forward.to_string().into());
+            parts.headers.insert(CONTENT_LENGTH, forward.bytes.len().into());
+            let response = inner.call(forward.into_request(parts)).await?;
+            if errors.is_empty() {
+                return Ok(response);
+            }
+
+            // Merge the responses of the forwarded calls of the batch with the rejected ones
+            let (parts, response_body) = response.into_parts();
+            let response_body = hyper::body::to_bytes(response_body).await?;
+            let mut responses = match serde_json::from_slice::<Value>(&response_body) {
+                Ok(Value::Array(responses)) => responses,
+                Ok(response) => vec![response],
+                Err(_) => vec![],
+            };
+            responses.append(&mut errors);
+            let body = Value::Array(responses).to_string();
+
+            let mut response = Response::from_parts(parts, Body::from(body.clone()));
+            response.headers_mut().insert(CONTENT_LENGTH, body.len().into());
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params })
+    }
+
+    #[test]
+    fn test_strict_params() {
+        // Given
+        let address = "0x".to_string() + &"ab".repeat(20);
+        let mut valid = call("eth_getBalance", json!([address, "latest"]));
+        let mut decimal = call("eth_getBlockByNumber", json!(["100", false]));
+        let mut leading_zero = call("eth_getTransactionByBlockNumberAndIndex", json!(["0x10", "0x01"]));
+        let mut missing = call("eth_getBlockByHash", json!([]));
+
+        // Then
+        assert_eq!(Ok(()), check_params(&mut valid, ParamsMode::Strict));
+        assert_eq!("params[0]", check_params(&mut decimal, ParamsMode::Strict).unwrap_err().path);
+        assert_eq!("params[1]", check_params(&mut leading_zero, ParamsMode::Strict).unwrap_err().path);
+        assert_eq!("params[0]", check_params(&mut missing, ParamsMode::Strict).unwrap_err().path);
+    }
+
+    #[test]
+    fn test_strict_params_nested_path() {
+        // Given
+        let mut call_request = call("eth_call", json!([{ "to": "0x1234", "data": "0x" }, "latest"]));
+        let mut filter = call("eth_getLogs", json!([{ "fromBlock": "0x1", "topics": [null, ["0x12"]] }]));
+
+        // Then
+        assert_eq!("params[0].to", check_params(&mut call_request, ParamsMode::Strict).unwrap_err().path);
+        assert_eq!("params[0].topics[1][0]", check_params(&mut filter, ParamsMode::Strict).unwrap_err().path);
+    }
+
+    #[test]
+    fn test_state_override_params() {
+        // Given
+        let address = "0x".to_string() + &"ab".repeat(20);
+        let slot = "0x".to_string() + &"00".repeat(31) + "01";
+        let request = json!({ "to": address, "data": "0x" });
+        let mut valid = call(
+            "eth_call",
+            json!([request, "latest", { &address: { "balance": "100", "stateDiff": { &slot: slot } } }]),
+        );
+        let mut invalid_slot =
+            call("eth_call", json!([request, "latest", { &address: { "stateDiff": { "0x01": slot } } }]));
+        let mut estimate_gas = call("eth_estimateGas", json!([request, "latest", {}]));
+
+        // When
+        check_params(&mut valid, ParamsMode::Lenient).unwrap();
+
+        // Then
+        assert_eq!(json!("0x64"), valid["params"][2][&address]["balance"]);
+        assert_eq!(
+            format!("params[2].{address}.stateDiff.0x01"),
+            check_params(&mut invalid_slot, ParamsMode::Lenient).unwrap_err().path
+        );
+        assert_eq!("params[2]", check_params(&mut estimate_gas, ParamsMode::Lenient).unwrap_err().path);
+    }
+
+    #[test]
+    fn test_lenient_params_coercion() {
+        // Given
+        let address = "ab".repeat(20);
+        let mut balance = call("eth_getBalance", json!([address, { "blockNumber": 255 }]));
+        let mut block = call("eth_getBlockByNumber", json!(["100", false]));
+        let mut fee_history = call("eth_feeHistory", json!(["0X00a", "Latest", []]));
+
+        // When
+        check_params(&mut balance, ParamsMode::Lenient).unwrap();
+        check_params(&mut block, ParamsMode::Lenient).unwrap();
+        check_params(&mut fee_history, ParamsMode::Lenient).unwrap();
+
+        // Then
+        assert_eq!(json!([format!("0x{address}"), { "blockNumber": "0xff" }]), balance["params"]);
+        assert_eq!(json!(["0x64", false]), block["params"]);
+        assert_eq!(json!(["0xa", "latest", []]), fee_history["params"]);
+    }
+
+    #[test]
+    fn test_check_batch_request() {
+        // Given
+        let request = json!([
+            call("eth_getBlockByNumber", json!(["0x1", false])),
+            call("eth_getTransactionByHash", json!(["0x12"])),
+            { "jsonrpc": "2.0", "method": "eth_getTransactionByHash", "params": ["0x12"] },
+            call("eth_blockNumber", json!([])),
+        ]);
+
+        // When
+        let CheckedRequest { forward, errors } = check_request(request, ParamsMode::Strict);
+
+        // Then
+        assert_eq!(2, forward.unwrap().as_array().unwrap().len());
+        assert_eq!(1, errors.len());
+        assert_eq!(json!(INVALID_PARAMS_CODE), errors[0]["error"]["code"]);
+        assert_eq!(json!("params[0]"), errors[0]["error"]["data"]["path"]);
+    }
+}
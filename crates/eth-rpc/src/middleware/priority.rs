@@ -0,0 +1,217 @@
+//! Priority classes: the JSON-RPC methods are tagged into classes (transaction submission,
+//! chain tip reads, historical scans), each served by its own bounded pool of concurrent
+//! requests. A burst of heavy `eth_getLogs` queries saturates the historical pool only, without
+//! delaying `eth_sendRawTransaction`.
+//!
+//! The classes are applied to HTTP requests: a batch is served in the pool of its lowest priority
+//! method, the calls of the batches split by [`super::batch`] being each served in their own pool.
+//! WebSocket connections are not scheduled.
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use eyre::{eyre, Result};
+use hyper::{Body, Request, Response};
+use tokio::sync::Semaphore;
+use tower::{Layer, Service};
+
+use super::JsonRpcBody;
+
+/// Default number of concurrent transaction submissions.
+pub const DEFAULT_SUBMISSION_CONCURRENCY: usize = 64;
+/// Default number of concurrent chain tip reads.
+pub const DEFAULT_CHAIN_TIP_CONCURRENCY: usize = 256;
+/// Default number of concurrent historical scans.
+pub const DEFAULT_HISTORICAL_CONCURRENCY: usize = 16;
+
+/// Priority class of a JSON-RPC method, from the highest to the lowest priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PriorityClass {
+    /// Transaction submission.
+    Submission,
+    /// Reads of the chain tip, and every method not tagged otherwise.
+    ChainTip,
+    /// Scans of the chain history, spanning many blocks.
+    Historical,
+}
+
+impl PriorityClass {
+    /// Returns the priority class of the method.
+    pub fn of_method(method: &str) -> Self {
+        const SUBMISSION_METHODS: [&str; 3] =
+            ["eth_sendRawTransaction", "eth_sendTransaction", "personal_sendTransaction"];
+        const HISTORICAL_METHODS: [&str; 4] =
+            ["eth_getLogs", "eth_getFilterLogs", "eth_feeHistory", "kakarot_getDeployedAccounts"];
+        const HISTORICAL_NAMESPACES: [&str; 2] = ["debug_", "trace_"];
+
+        if SUBMISSION_METHODS.contains(&method) {
+            Self::Submission
+        } else if HISTORICAL_METHODS.contains(&method)
+            || HISTORICAL_NAMESPACES.iter().any(|namespace| method.starts_with(namespace))
+        {
+            Self::Historical
+        } else {
+            Self::ChainTip
+        }
+    }
+
+    /// Returns the priority class of a request calling the methods, the class of its lowest
+    /// priority method. Returns `None` for a request without methods.
+    pub fn of_request(methods: &[String]) -> Option<Self> {
+        methods.iter().map(|method| Self::of_method(method)).max()
+    }
+}
+
+/// Sizes of the pools of each priority class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriorityConfig {
+    pub submission_concurrency: usize,
+    pub chain_tip_concurrency: usize,
+    pub historical_concurrency: usize,
+}
+
+impl Default for PriorityConfig {
+    fn default() -> Self {
+        Self {
+            submission_concurrency: DEFAULT_SUBMISSION_CONCURRENCY,
+            chain_tip_concurrency: DEFAULT_CHAIN_TIP_CONCURRENCY,
+            historical_concurrency: DEFAULT_HISTORICAL_CONCURRENCY,
+        }
+    }
+}
+
+impl PriorityConfig {
+    /// Create a new `PriorityConfig` from the `KAKAROT_SUBMISSION_CONCURRENCY`,
+    /// `KAKAROT_CHAIN_TIP_CONCURRENCY` and `KAKAROT_HISTORICAL_CONCURRENCY` environment variables,
+    /// falling back to the defaults when not set.
+    pub fn from_env() -> Result<Self> {
+        let concurrency = |name: &str, default: usize| match std::env::var(name) {
+            Ok(concurrency) => concurrency
+                .parse::<usize>()
+                .ok()
+                .filter(|concurrency| *concurrency > 0)
+                .ok_or_else(|| eyre!("{name} should be a strictly positive integer, got {concurrency}")),
+            Err(_) => Ok(default),
+        };
+        Ok(Self {
+            submission_concurrency: concurrency("KAKAROT_SUBMISSION_CONCURRENCY", DEFAULT_SUBMISSION_CONCURRENCY)?,
+            chain_tip_concurrency: concurrency("KAKAROT_CHAIN_TIP_CONCURRENCY", DEFAULT_CHAIN_TIP_CONCURRENCY)?,
+            historical_concurrency: concurrency("KAKAROT_HISTORICAL_CONCURRENCY", DEFAULT_HISTORICAL_CONCURRENCY)?,
+        })
+    }
+}
+
+/// Bounded pools of concurrent requests, one per priority class.
+struct PriorityPools {
+    submission: Arc<Semaphore>,
+    chain_tip: Arc<Semaphore>,
+    historical: Arc<Semaphore>,
+}
+
+impl PriorityPools {
+    fn new(config: &PriorityConfig) -> Self {
+        Self {
+            submission: Arc::new(Semaphore::new(config.submission_concurrency)),
+            chain_tip: Arc::new(Semaphore::new(config.chain_tip_concurrency)),
+            historical: Arc::new(Semaphore::new(config.historical_concurrency)),
+        }
+    }
+
+    fn pool(&self, class: PriorityClass) -> Arc<Semaphore> {
+        match class {
+            PriorityClass::Submission => Arc::clone(&self.submission),
+            PriorityClass::ChainTip => Arc::clone(&self.chain_tip),
+            PriorityClass::Historical => Arc::clone(&self.historical),
+        }
+    }
+}
+
+/// Tower layer serving each HTTP request in the pool of its priority class.
+#[derive(Clone)]
+pub struct PriorityLayer {
+    pools: Arc<PriorityPools>,
+}
+
+impl PriorityLayer {
+    pub fn new(config: &PriorityConfig) -> Self {
+        Self { pools: Arc::new(PriorityPools::new(config)) }
+    }
+}
+
+impl<S> Layer<S> for PriorityLayer {
+    type Service = PriorityService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PriorityService { inner, pools: Arc::clone(&self.pools) }
+    }
+}
+
+#[derive(Clone)]
+pub struct PriorityService<S> {
+    inner: S,
+    pools: Arc<PriorityPools>,
+}
+
+impl<S> Service<Request<Body>> for PriorityService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let pools = Arc::clone(&self.pools);
+
+        Box::pin(async move {
+            let (request, body) = JsonRpcBody::read(request).await?;
+            let Some(class) = PriorityClass::of_request(&body.methods) else {
+                return inner.call(request).await;
+            };
+
+            // The pools are never closed, acquiring a permit can't fail
+            let _permit = pools.pool(class).acquire_owned().await.ok();
+            inner.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_class_of_request() {
+        let methods = |methods: &[&str]| methods.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        assert_eq!(Some(PriorityClass::Submission), PriorityClass::of_request(&methods(&["eth_sendRawTransaction"])));
+        assert_eq!(Some(PriorityClass::ChainTip), PriorityClass::of_request(&methods(&["eth_getBalance"])));
+        assert_eq!(Some(PriorityClass::Historical), PriorityClass::of_request(&methods(&["debug_traceTransaction"])));
+        assert_eq!(
+            Some(PriorityClass::Historical),
+            PriorityClass::of_request(&methods(&["eth_sendRawTransaction", "eth_getLogs"]))
+        );
+        assert_eq!(None, PriorityClass::of_request(&[]));
+    }
+
+    #[tokio::test]
+    async fn test_saturated_pool_does_not_block_other_classes() {
+        // Given
+        let pools = PriorityPools::new(&PriorityConfig { historical_concurrency: 1, ..Default::default() });
+        let _scan = pools.pool(PriorityClass::Historical).acquire_owned().await.unwrap();
+
+        // Then
+        assert!(pools.pool(PriorityClass::Historical).try_acquire_owned().is_err());
+        assert!(pools.pool(PriorityClass::Submission).try_acquire_owned().is_ok());
+    }
+}
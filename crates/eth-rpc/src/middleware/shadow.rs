@@ -0,0 +1,344 @@
+//! Request shadowing: a configurable share of the read traffic is mirrored, asynchronously, to a
+//! second backend (e.g. a new Starknet node or a new kakarot-rpc version). Responses of both
+//! backends are compared and differences are reported in the logs and in the shadow statistics.
+//! The mirrored requests in flight are bounded, a request is not mirrored while the shadow
+//! backend is too slow to keep up.
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use eyre::{eyre, Result};
+use hyper::body::Bytes;
+use hyper::{Body, Request, Response};
+use serde_json::Value;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::{Layer, Service};
+use url::Url;
+
+use super::standby::PROMOTE_METHOD;
+use super::JsonRpcBody;
+use crate::rpc::is_state_changing;
+
+/// Methods whose results are bound to the instance serving them, the ids of the filters.
+const FILTER_METHODS: [&str; 6] = [
+    "eth_newFilter",
+    "eth_newBlockFilter",
+    "eth_newPendingTransactionFilter",
+    "eth_getFilterChanges",
+    "eth_getFilterLogs",
+    "eth_uninstallFilter",
+];
+
+/// Returns true if the method can be mirrored to the shadow backend: it doesn't change the state,
+/// see [`is_state_changing`], and its result can be compared.
+fn is_mirrorable(method: &str) -> bool {
+    !is_state_changing(method) && method != PROMOTE_METHOD && !FILTER_METHODS.contains(&method)
+}
+
+/// Default maximum number of mirrored requests in flight.
+pub const DEFAULT_SHADOW_MAX_IN_FLIGHT: usize = 64;
+
+/// Configuration of the shadowing mode.
+#[derive(Debug, Clone)]
+pub struct ShadowConfig {
+    /// Url of the backend receiving the mirrored traffic.
+    pub url: Url,
+    /// Percentage of the read traffic mirrored to the shadow backend, between 0 and 100.
+    pub percentage: u64,
+    /// Maximum number of mirrored requests in flight, the requests sampled above it are dropped.
+    pub max_in_flight: usize,
+}
+
+impl ShadowConfig {
+    pub fn new(url: Url, percentage: u64) -> Self {
+        Self { url, percentage: percentage.min(100), max_in_flight: DEFAULT_SHADOW_MAX_IN_FLIGHT }
+    }
+
+    /// Create a new `ShadowConfig` from environment variables.
+    /// Returns `None` if `KAKAROT_SHADOW_URL` is not set, which disables the shadowing. The
+    /// mirrored requests in flight are bounded by `KAKAROT_SHADOW_MAX_IN_FLIGHT`.
+    pub fn from_env() -> Result<Option<Self>> {
+        let url = match std::env::var("KAKAROT_SHADOW_URL") {
+            Ok(url) => Url::parse(&url).map_err(|err| eyre!("KAKAROT_SHADOW_URL is not a valid url: {err}"))?,
+            Err(_) => return Ok(None),
+        };
+        let percentage = match std::env::var("KAKAROT_SHADOW_PERCENTAGE") {
+            Ok(percentage) => percentage
+                .parse::<u64>()
+                .map_err(|_| eyre!("KAKAROT_SHADOW_PERCENTAGE should be an integer between 0 and 100"))?,
+            Err(_) => 100,
+        };
+        let max_in_flight = match std::env::var("KAKAROT_SHADOW_MAX_IN_FLIGHT") {
+            Ok(max_in_flight) => max_in_flight
+                .parse::<usize>()
+                .map_err(|_| eyre!("KAKAROT_SHADOW_MAX_IN_FLIGHT should be a positive integer"))?,
+            Err(_) => DEFAULT_SHADOW_MAX_IN_FLIGHT,
+        };
+        Ok(Some(Self { max_in_flight, ..Self::new(url, percentage) }))
+    }
+}
+
+/// Counters describing the outcome of the mirrored requests.
+#[derive(Debug, Default)]
+pub struct ShadowStats {
+    mirrored: AtomicU64,
+    matched: AtomicU64,
+    mismatched: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl ShadowStats {
+    /// Number of requests mirrored to the shadow backend.
+    pub fn mirrored(&self) -> u64 {
+        self.mirrored.load(Ordering::Relaxed)
+    }
+
+    /// Number of mirrored requests for which both backends returned the same response.
+    pub fn matched(&self) -> u64 {
+        self.matched.load(Ordering::Relaxed)
+    }
+
+    /// Number of mirrored requests for which the responses differed.
+    pub fn mismatched(&self) -> u64 {
+        self.mismatched.load(Ordering::Relaxed)
+    }
+
+    /// Number of mirrored requests that couldn't be compared because the shadow backend failed.
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Number of sampled requests that weren't mirrored because too many mirrored requests were
+    /// in flight.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Encodes the counters in the Prometheus text format.
+    pub fn encode_prometheus(&self) -> String {
+        let metrics: [(&str, &str, u64); 5] = [
+            ("kakarot_shadow_mirrored_total", "Requests mirrored to the shadow backend.", self.mirrored()),
+            ("kakarot_shadow_matched_total", "Mirrored requests with matching responses.", self.matched()),
+            ("kakarot_shadow_mismatched_total", "Mirrored requests with differing responses.", self.mismatched()),
+            ("kakarot_shadow_failed_total", "Mirrored requests the shadow backend failed to serve.", self.failed()),
+            ("kakarot_shadow_dropped_total", "Sampled requests dropped by the in-flight limit.", self.dropped()),
+        ];
+
+        // Writing to a string never fails
+        let mut encoded = String::new();
+        for (name, help, value) in metrics {
+            let _ = writeln!(encoded, "# HELP {name} {help}");
+            let _ = writeln!(encoded, "# TYPE {name} counter");
+            let _ = writeln!(encoded, "{name} {value}");
+        }
+        encoded
+    }
+}
+
+struct Shadow {
+    config: ShadowConfig,
+    client: reqwest::Client,
+    stats: Arc<ShadowStats>,
+    requests: AtomicU64,
+    in_flight: Arc<Semaphore>,
+}
+
+impl Shadow {
+    /// Samples the requests to mirror so that `percentage` percent of them are selected.
+    fn should_mirror(&self) -> bool {
+        let n = self.requests.fetch_add(1, Ordering::Relaxed);
+        (n + 1) * self.config.percentage / 100 > n * self.config.percentage / 100
+    }
+
+    /// Reserves a slot for a mirrored request, or counts it as dropped if the in-flight limit is
+    /// reached.
+    fn reserve(&self) -> Option<OwnedSemaphorePermit> {
+        let permit = Arc::clone(&self.in_flight).try_acquire_owned().ok();
+        if permit.is_none() {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        permit
+    }
+
+    /// Sends the request to the shadow backend and compares its response with the primary one.
+    async fn mirror(&self, methods: Arc<[String]>, request: Bytes, primary_response: Bytes) {
+        self.stats.mirrored.fetch_add(1, Ordering::Relaxed);
+
+        let shadow_response = match self
+            .client
+            .post(self.config.url.clone())
+            .header("content-type", "application/json")
+            .body(request)
+            .send()
+            .await
+        {
+            Ok(response) => response.bytes().await,
+            Err(err) => Err(err),
+        };
+        let shadow_response = match shadow_response {
+            Ok(response) => response,
+            Err(err) => {
+                self.stats.failed.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!("Shadow request {methods:?} failed: {err}");
+                return;
+            }
+        };
+
+        let primary: Option<Value> = serde_json::from_slice(&primary_response).ok();
+        let shadow: Option<Value> = serde_json::from_slice(&shadow_response).ok();
+        if primary.is_some() && primary == shadow {
+            self.stats.matched.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!("Shadow response for {methods:?} matches");
+        } else {
+            self.stats.mismatched.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(
+                "Shadow response mismatch for {methods:?}: primary {}, shadow {}",
+                String::from_utf8_lossy(&primary_response),
+                String::from_utf8_lossy(&shadow_response)
+            );
+        }
+    }
+}
+
+/// Tower layer mirroring read requests to a shadow backend. The layer is a no-op when built
+/// without a configuration.
+#[derive(Clone)]
+pub struct ShadowLayer {
+    shadow: Option<Arc<Shadow>>,
+}
+
+impl ShadowLayer {
+    pub fn new(config: Option<ShadowConfig>) -> Self {
+        let shadow = config.map(|config| {
+            Arc::new(Shadow {
+                client: reqwest::Client::new(),
+                stats: Arc::new(ShadowStats::default()),
+                requests: AtomicU64::new(0),
+                in_flight: Arc::new(Semaphore::new(config.max_in_flight)),
+                config,
+            })
+        });
+        Self { shadow }
+    }
+
+    /// Returns the shadowing statistics, if the shadowing is enabled.
+    pub fn stats(&self) -> Option<Arc<ShadowStats>> {
+        self.shadow.as_ref().map(|shadow| Arc::clone(&shadow.stats))
+    }
+}
+
+impl<S> Layer<S> for ShadowLayer {
+    type Service = ShadowService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ShadowService { inner, shadow: self.shadow.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct ShadowService<S> {
+    inner: S,
+    shadow: Option<Arc<Shadow>>,
+}
+
+impl<S> Service<Request<Body>> for ShadowService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let shadow = match &self.shadow {
+            Some(shadow) if shadow.config.percentage > 0 => Arc::clone(shadow),
+            _ => return Box::pin(inner.call(request)),
+        };
+
+        Box::pin(async move {
+            let (request, body) = JsonRpcBody::read(request).await?;
+            let sampled = !body.methods.is_empty()
+                && body.methods.iter().all(|method| is_mirrorable(method))
+                && shadow.should_mirror();
+            let permit = if sampled { shadow.reserve() } else { None };
+
+            let response = inner.call(request).await?;
+            let Some(permit) = permit else {
+                return Ok(response);
+            };
+
+            let (parts, response_body) = response.into_parts();
+            let response_body = hyper::body::to_bytes(response_body).await?;
+
+            let primary_response = response_body.clone();
+            tokio::spawn(async move {
+                shadow.mirror(body.methods, body.bytes, primary_response).await;
+                drop(permit);
+            });
+
+            Ok(Response::from_parts(parts, Body::from(response_body)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_mirror_samples_percentage() {
+        // Given
+        let layer = ShadowLayer::new(Some(ShadowConfig::new(Url::parse("http://localhost:3030").unwrap(), 25)));
+        let shadow = layer.shadow.unwrap();
+
+        // When
+        let mirrored = (0..1000).filter(|_| shadow.should_mirror()).count();
+
+        // Then
+        assert_eq!(250, mirrored);
+    }
+
+    #[test]
+    fn test_reserve_drops_above_max_in_flight() {
+        // Given
+        let config =
+            ShadowConfig { max_in_flight: 1, ..ShadowConfig::new(Url::parse("http://localhost:3030").unwrap(), 100) };
+        let shadow = ShadowLayer::new(Some(config)).shadow.unwrap();
+
+        // When
+        let permit = shadow.reserve();
+        let dropped = shadow.reserve();
+
+        // Then
+        assert!(permit.is_some());
+        assert!(dropped.is_none());
+        assert_eq!(1, shadow.stats.dropped());
+        drop(permit);
+        assert!(shadow.reserve().is_some());
+    }
+
+    #[test]
+    fn test_is_mirrorable() {
+        assert!(is_mirrorable("eth_call"));
+        assert!(is_mirrorable("eth_getLogs"));
+        assert!(!is_mirrorable("eth_sendRawTransaction"));
+        assert!(!is_mirrorable("kakarot_startLogBackfill"));
+        assert!(!is_mirrorable("personal_sign"));
+        assert!(!is_mirrorable(PROMOTE_METHOD));
+        assert!(!is_mirrorable("eth_getFilterChanges"));
+    }
+}
@@ -0,0 +1,246 @@
+//! Health endpoints and warm standby mode.
+//!
+//! `GET /health` answers `200 OK` as long as the server runs, and `GET /ready` answers `200 OK`
+//! once the instance serves the JSON-RPC methods, `503 Service Unavailable` before. They let a load
+//! balancer route the traffic to the active instance only.
+//!
+//! A standby instance runs like an active one, following the chain with its head watcher, indexer
+//! and caches, but rejects the JSON-RPC calls with `503 Service Unavailable` until an operator
+//! calls [`PROMOTE_METHOD`]. The promotion is instant since the state of the instance is already
+//! warm, and can't be undone short of a restart. The promotion method is callable by any client
+//! reaching the standby instance, which should only be exposed behind a proxy filtering it.
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use jsonrpsee::types::ErrorObject;
+use jsonrpsee::RpcModule;
+use serde_json::json;
+use tower::{Layer, Service};
+
+use super::JsonRpcBody;
+use crate::metrics::METRICS_PATH;
+
+/// Path of the liveness endpoint.
+pub const HEALTH_PATH: &str = "/health";
+/// Path of the readiness endpoint.
+pub const READY_PATH: &str = "/ready";
+/// Method promoting a standby instance to active.
+pub const PROMOTE_METHOD: &str = "kakarot_promote";
+
+/// JSON-RPC error code of an unavailable resource, from EIP-1474.
+const RESOURCE_UNAVAILABLE_CODE: i32 = -32002;
+
+/// Mode of the instance, standby until promoted.
+#[derive(Debug)]
+pub struct Standby {
+    standby: AtomicBool,
+}
+
+impl Standby {
+    pub fn new() -> Self {
+        Self { standby: AtomicBool::new(true) }
+    }
+
+    /// Create a new `Standby` from the `KAKAROT_STANDBY` environment variable. Returns `None` if
+    /// it is not set to `true`, the instance being active from the start.
+    pub fn from_env() -> Option<Arc<Self>> {
+        let standby = std::env::var("KAKAROT_STANDBY").map(|v| v.to_lowercase() == "true").unwrap_or(false);
+        standby.then(|| Arc::new(Self::new()))
+    }
+
+    pub fn is_standby(&self) -> bool {
+        self.standby.load(Ordering::Acquire)
+    }
+
+    /// Promotes the instance to active. Returns false if it was already active.
+    pub fn promote(&self) -> bool {
+        let promoted = self.standby.swap(false, Ordering::AcqRel);
+        if promoted {
+            tracing::info!("Standby instance promoted, serving the JSON-RPC methods");
+        }
+        promoted
+    }
+
+    /// Encodes the mode of the instance in the Prometheus text format.
+    pub fn encode_prometheus(&self) -> String {
+        format!(
+            "# HELP kakarot_standby Whether the instance is a standby waiting for its promotion.\n# TYPE \
+             kakarot_standby gauge\nkakarot_standby {}\n",
+            u8::from(self.is_standby())
+        )
+    }
+}
+
+impl Default for Standby {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Registers the [`PROMOTE_METHOD`], returning true if the call promoted the instance and false
+/// if it was already active.
+pub fn register_promote_method(
+    rpc_module: &mut RpcModule<()>,
+    standby: Arc<Standby>,
+) -> Result<(), jsonrpsee::core::Error> {
+    rpc_module.register_method(PROMOTE_METHOD, move |_, _| Ok::<_, ErrorObject<'static>>(standby.promote()))?;
+    Ok(())
+}
+
+/// Returns true if the request can be served by a standby instance: the health and metrics
+/// endpoints, and the calls of the promotion method.
+fn served_in_standby(method: &Method, path: &str, methods: &[String]) -> bool {
+    if *method == Method::GET {
+        return path == HEALTH_PATH || path == READY_PATH || path == METRICS_PATH;
+    }
+    !methods.is_empty() && methods.iter().all(|method| method == PROMOTE_METHOD)
+}
+
+/// Returns the response of the health endpoints, `None` for the other requests.
+fn health_response(method: &Method, path: &str, standby: bool) -> Option<Response<Body>> {
+    if *method != Method::GET {
+        return None;
+    }
+    let status = match path {
+        HEALTH_PATH => StatusCode::OK,
+        READY_PATH if standby => StatusCode::SERVICE_UNAVAILABLE,
+        READY_PATH => StatusCode::OK,
+        _ => return None,
+    };
+    let mode = if standby { "standby" } else { "active" };
+    Some(json_response(status, json!({ "status": mode }).to_string()))
+}
+
+fn json_response(status: StatusCode, body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+/// Tower layer serving the health endpoints, and rejecting the JSON-RPC calls while the instance
+/// is a standby. An instance built without a [`Standby`] is always active.
+#[derive(Clone)]
+pub struct StandbyLayer {
+    standby: Option<Arc<Standby>>,
+}
+
+impl StandbyLayer {
+    pub fn new(standby: Option<Arc<Standby>>) -> Self {
+        Self { standby }
+    }
+}
+
+impl<S> Layer<S> for StandbyLayer {
+    type Service = StandbyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StandbyService { inner, standby: self.standby.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct StandbyService<S> {
+    inner: S,
+    standby: Option<Arc<Standby>>,
+}
+
+impl<S> Service<Request<Body>> for StandbyService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let standby = self.standby.as_ref().is_some_and(|standby| standby.is_standby());
+        if let Some(response) = health_response(request.method(), request.uri().path(), standby) {
+            return Box::pin(async move { Ok(response) });
+        }
+        if !standby {
+            return Box::pin(inner.call(request));
+        }
+
+        Box::pin(async move {
+            let (request, body) = JsonRpcBody::read(request).await?;
+            if !served_in_standby(request.method(), request.uri().path(), &body.methods) {
+                let error = json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": {
+                        "code": RESOURCE_UNAVAILABLE_CODE,
+                        "message": format!("standby instance, call {PROMOTE_METHOD} to promote it"),
+                    },
+                });
+                return Ok(json_response(StatusCode::SERVICE_UNAVAILABLE, error.to_string()));
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promote_standby() {
+        // Given
+        let standby = Standby::new();
+
+        // When
+        let promoted = standby.promote();
+
+        // Then
+        assert!(promoted);
+        assert!(!standby.is_standby());
+        assert!(!standby.promote());
+        assert!(standby.encode_prometheus().ends_with("kakarot_standby 0\n"));
+    }
+
+    #[test]
+    fn test_health_response() {
+        // Given
+        let ready_in_standby = health_response(&Method::GET, READY_PATH, true).unwrap();
+        let ready_when_active = health_response(&Method::GET, READY_PATH, false).unwrap();
+        let health_in_standby = health_response(&Method::GET, HEALTH_PATH, true).unwrap();
+
+        // Then
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, ready_in_standby.status());
+        assert_eq!(StatusCode::OK, ready_when_active.status());
+        assert_eq!(StatusCode::OK, health_in_standby.status());
+        assert!(health_response(&Method::POST, HEALTH_PATH, true).is_none());
+        assert!(health_response(&Method::GET, "/", true).is_none());
+    }
+
+    #[test]
+    fn test_served_in_standby() {
+        let methods = |methods: &[&str]| methods.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let promote = methods(&[PROMOTE_METHOD]);
+        let batch = methods(&[PROMOTE_METHOD, "eth_blockNumber"]);
+        let call = methods(&["eth_blockNumber"]);
+
+        assert!(served_in_standby(&Method::POST, "/", &promote));
+        assert!(served_in_standby(&Method::GET, METRICS_PATH, &[]));
+        assert!(!served_in_standby(&Method::POST, "/", &batch));
+        assert!(!served_in_standby(&Method::POST, "/", &call));
+        assert!(!served_in_standby(&Method::POST, "/", &[]));
+        assert!(!served_in_standby(&Method::GET, "/", &[]));
+    }
+}
@@ -0,0 +1,272 @@
+//! Replay of a captured request by the `replay` command, turning the report of a bad response into
+//! a reproducible artifact.
+//!
+//! The request is re-executed by a Kakarot client against the configured Starknet JSON-RPC node,
+//! pinned to the block it was served at: the `latest`, `pending`, `safe` and `finalized` tags of
+//! its params are replaced by the block number. Every request to the Starknet node is recorded
+//! with its response, and the logs of the client are captured at the debug level, so that the
+//! conversion steps show up. They are written along with the response to a JSON diagnostic
+//! bundle.
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+use kakarot_rpc_core::client::config::StarknetConfig;
+use kakarot_rpc_core::client::KakarotClient;
+use kakarot_rpc_core::storage::StorageConfig;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use starknet::providers::jsonrpc::{JsonRpcMethod, JsonRpcResponse, JsonRpcTransport};
+use starknet::providers::JsonRpcClient;
+use tracing::instrument::WithSubscriber;
+use tracing_subscriber::EnvFilter;
+use url::Url;
+
+use crate::rpc::KakarotRpcModuleBuilder;
+use crate::telemetry::starknet_method_name;
+
+/// Block tags replaced by the pinned block number.
+const PINNED_TAGS: [&str; 4] = ["latest", "pending", "safe", "finalized"];
+
+/// Logs captured during the replay, the HTTP clients being kept quiet.
+const REPLAY_LOG_FILTER: &str = "debug,hyper=info,reqwest=info,h2=info,rustls=info";
+
+/// Captured request, e.g. the body of a JSON-RPC request along with the block it was served at.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapturedRequest {
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    /// Block the request was served at, the block tags of the params being left as is when not
+    /// set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<u64>,
+}
+
+impl CapturedRequest {
+    /// Reads the captured request from a JSON file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|err| eyre!("Failed to read {}: {err}", path.display()))?;
+        serde_json::from_str(&content).map_err(|err| eyre!("Invalid captured request {}: {err}", path.display()))
+    }
+
+    /// Returns the request with the block tags of its params replaced by the pinned block.
+    pub fn pinned(mut self) -> Self {
+        if let Some(block) = self.block {
+            pin_block(&mut self.params, block);
+        }
+        self
+    }
+
+    fn to_jsonrpc(&self) -> String {
+        let params = if self.params.is_null() { json!([]) } else { self.params.clone() };
+        json!({ "jsonrpc": "2.0", "id": 1, "method": self.method, "params": params }).to_string()
+    }
+}
+
+/// Replaces the block tags found in the params by the block number.
+pub fn pin_block(params: &mut Value, block: u64) {
+    match params {
+        Value::String(tag) if PINNED_TAGS.contains(&tag.as_str()) => *params = json!(format!("{block:#x}")),
+        Value::Array(values) => values.iter_mut().for_each(|value| pin_block(value, block)),
+        Value::Object(fields) => fields.values_mut().for_each(|value| pin_block(value, block)),
+        _ => {}
+    }
+}
+
+/// Request sent to the Starknet node during the replay.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StarknetCall {
+    pub method: String,
+    pub params: Value,
+    /// Response of the node, as a string when it isn't valid JSON.
+    pub response: Value,
+    pub duration_ms: u64,
+}
+
+/// Error of the [`RecordingTransport`].
+#[derive(Debug, thiserror::Error)]
+pub enum RecordingTransportError {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// JSON-RPC transport recording the requests to the Starknet node and their responses.
+#[derive(Debug)]
+pub struct RecordingTransport {
+    client: Client,
+    url: Url,
+    calls: Arc<Mutex<Vec<StarknetCall>>>,
+}
+
+impl RecordingTransport {
+    pub fn new(url: Url) -> Self {
+        Self { client: Client::new(), url, calls: Arc::default() }
+    }
+
+    /// Returns the recorded requests, shared with the transport.
+    pub fn calls(&self) -> Arc<Mutex<Vec<StarknetCall>>> {
+        Arc::clone(&self.calls)
+    }
+}
+
+#[async_trait]
+impl JsonRpcTransport for RecordingTransport {
+    type Error = RecordingTransportError;
+
+    async fn send_request<P, R>(&self, method: JsonRpcMethod, params: P) -> Result<JsonRpcResponse<R>, Self::Error>
+    where
+        P: Serialize + Send,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let request_body = json!({ "id": 1, "jsonrpc": "2.0", "method": method, "params": params });
+
+        let started = Instant::now();
+        let response_body = self
+            .client
+            .post(self.url.clone())
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&request_body)?)
+            .send()
+            .await?
+            .text()
+            .await?;
+
+        let response = serde_json::from_str(&response_body).unwrap_or_else(|_| Value::String(response_body.clone()));
+        let call = StarknetCall {
+            method: starknet_method_name(method),
+            params,
+            response,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        tracing::debug!("Starknet call {} answered in {}ms", call.method, call.duration_ms);
+        self.calls.lock().expect("recorded calls poisoned").push(call);
+
+        Ok(serde_json::from_str(&response_body)?)
+    }
+}
+
+/// Logs written by the subscriber of the replay.
+#[derive(Debug, Clone, Default)]
+struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().expect("log buffer poisoned").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl LogBuffer {
+    fn lines(&self) -> Vec<String> {
+        let logs = self.0.lock().expect("log buffer poisoned");
+        String::from_utf8_lossy(&logs).lines().map(ToString::to_string).collect()
+    }
+}
+
+/// Diagnostic bundle of a replayed request.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayBundle {
+    /// Version of the RPC which replayed the request.
+    pub version: String,
+    /// Replayed request, pinned to its block.
+    pub request: CapturedRequest,
+    /// JSON-RPC response of the replayed request.
+    pub response: Value,
+    pub duration_ms: u64,
+    /// Requests sent to the Starknet node, in order, including the resolution of the chain id.
+    pub starknet_calls: Vec<StarknetCall>,
+    /// Logs of the client while serving the request.
+    pub logs: Vec<String>,
+}
+
+/// Replays the request against the Starknet JSON-RPC node of the configuration and returns the
+/// diagnostic bundle.
+pub async fn replay_request(mut starknet_config: StarknetConfig, request: CapturedRequest) -> Result<ReplayBundle> {
+    let url = starknet_config
+        .network
+        .provider_url()
+        .map_err(|err| eyre!("The replay requires a Starknet JSON-RPC node: {err}"))?;
+    let transport = RecordingTransport::new(url);
+    let calls = transport.calls();
+    let starknet_provider = JsonRpcClient::new(transport);
+
+    // The replay must not write to the local state of a running RPC
+    starknet_config.storage = StorageConfig::Memory;
+    let kakarot_client =
+        Arc::new(KakarotClient::try_new(starknet_config, starknet_provider).await.map_err(|err| eyre!("{err}"))?);
+    let kakarot_rpc_module = KakarotRpcModuleBuilder::new(kakarot_client).rpc_module()?;
+
+    let request = request.pinned();
+    let logs = LogBuffer::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(REPLAY_LOG_FILTER))
+        .with_ansi(false)
+        .with_writer({
+            let logs = logs.clone();
+            move || logs.clone()
+        })
+        .finish();
+
+    let started = Instant::now();
+    let (response, _) =
+        kakarot_rpc_module.raw_json_request(&request.to_jsonrpc(), 1).with_subscriber(subscriber).await?;
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let response = serde_json::from_str(&response.result).unwrap_or(Value::String(response.result));
+    let starknet_calls = calls.lock().expect("recorded calls poisoned").clone();
+    Ok(ReplayBundle {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        request,
+        response,
+        duration_ms,
+        starknet_calls,
+        logs: logs.lines(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_block() {
+        // Given
+        let mut params = json!([{ "fromBlock": "latest", "toBlock": "pending", "address": "0x01" }, "earliest", true]);
+
+        // When
+        pin_block(&mut params, 255);
+
+        // Then
+        assert_eq!(json!([{ "fromBlock": "0xff", "toBlock": "0xff", "address": "0x01" }, "earliest", true]), params);
+    }
+
+    #[test]
+    fn test_captured_request_from_jsonrpc_body() {
+        // Given
+        let body =
+            r#"{ "jsonrpc": "2.0", "id": 7, "method": "eth_getBalance", "params": ["0x01", "latest"], "block": 3 }"#;
+
+        // When
+        let request: CapturedRequest = serde_json::from_str(body).unwrap();
+
+        // Then
+        assert_eq!(json!(["0x01", "0x3"]), request.clone().pinned().params);
+        let jsonrpc: Value = serde_json::from_str(&request.to_jsonrpc()).unwrap();
+        assert_eq!("eth_getBalance", jsonrpc["method"]);
+    }
+}
@@ -1,59 +1,300 @@
 use std::collections::HashMap;
-use std::marker::PhantomData;
 use std::sync::Arc;
 
 use jsonrpsee::core::Error;
 use jsonrpsee::{Methods, RpcModule};
 use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::client::head_watcher::{HeadWatcher, DEFAULT_HEAD_POLL_INTERVAL};
+use kakarot_rpc_core::client::signer::EthSigner;
+use kakarot_rpc_indexer::store::IndexStore;
 use starknet::providers::Provider;
 
 use crate::api::alchemy_api::AlchemyApiServer;
+use crate::api::debug_api::DebugApiServer;
 use crate::api::eth_api::EthApiServer;
+use crate::api::eth_pubsub_api::EthPubSubApiServer;
+use crate::api::evm_api::EvmApiServer;
+use crate::api::fork_api::{ForkApiServer, FORK_METHODS};
+use crate::api::hardhat_api::HardhatApiServer;
+use crate::api::kakarot_admin_api::KakarotAdminApiServer;
+use crate::api::kakarot_api::KakarotApiServer;
+use crate::api::kakarot_pubsub_api::KakarotPubSubApiServer;
 use crate::api::net_api::NetApiServer;
+use crate::api::personal_api::PersonalApiServer;
+use crate::api::trace_api::TraceApiServer;
+use crate::api::txpool_api::TxPoolApiServer;
 use crate::api::web3_api::Web3ApiServer;
+use crate::backfill::LogBackfill;
+use crate::capabilities::{feature_disabled, register_capabilities, Capability, CapabilityRegistry};
+#[cfg(feature = "dev")]
+use crate::dev::DevNetwork;
+use crate::explorer::StarknetExplorer;
+use crate::fork::Fork;
+use crate::katana::KatanaDevClient;
+use crate::middleware::standby::{register_promote_method, Standby};
 use crate::servers::alchemy_rpc::AlchemyRpc;
+use crate::servers::debug_rpc::DebugRpc;
+use crate::servers::eth_pubsub_rpc::EthPubSubRpc;
 use crate::servers::eth_rpc::KakarotEthRpc;
+use crate::servers::evm_rpc::EvmRpc;
+use crate::servers::fork_rpc::ForkRpc;
+use crate::servers::hardhat_rpc::HardhatRpc;
+use crate::servers::kakarot_admin_rpc::KakarotAdminRpc;
+use crate::servers::kakarot_pubsub_rpc::KakarotPubSubRpc;
+use crate::servers::kakarot_rpc::KakarotRpc;
 use crate::servers::net_rpc::NetRpc;
+use crate::servers::personal_rpc::PersonalRpc;
+use crate::servers::trace_rpc::TraceRpc;
+use crate::servers::txpool_rpc::TxPoolRpc;
 use crate::servers::web3_rpc::Web3Rpc;
+use crate::webhooks::Webhooks;
+
+/// Namespaces whose methods all change the state of the chain or of the node: the test methods
+/// and the accounts of the signer.
+pub const STATE_CHANGING_NAMESPACES: &[&str] = &["evm", "hardhat", "anvil", "personal"];
+
+/// Methods changing the state of the chain or of the node, or using the keys of the managed
+/// accounts, disabled in read-only mode along with the methods of the
+/// [`STATE_CHANGING_NAMESPACES`].
+pub const STATE_CHANGING_METHODS: &[&str] = &[
+    "eth_sendTransaction",
+    "eth_sendRawTransaction",
+    "eth_sign",
+    "eth_signTransaction",
+    "eth_signTypedData",
+    "eth_signTypedData_v3",
+    "eth_signTypedData_v4",
+    "kakarot_startLogBackfill",
+    "kakarot_deployEoa",
+];
+
+/// Returns true if the method changes the state of the chain or of the node.
+pub fn is_state_changing(method: &str) -> bool {
+    STATE_CHANGING_METHODS.contains(&method)
+        || method.split_once('_').map_or(false, |(namespace, _)| STATE_CHANGING_NAMESPACES.contains(&namespace))
+}
 
 /// Represents RPC modules that are supported by reth
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum KakarotRpcModule {
     Eth,
+    EthPubSub,
     Alchemy,
     Web3,
     Net,
+    Kakarot,
+    KakarotPubSub,
+    Debug,
+    Trace,
+    TxPool,
+    Evm,
+    Personal,
+    Fork,
+    Hardhat,
+    KakarotAdmin,
 }
 
 pub struct KakarotRpcModuleBuilder<P: Provider + Send + Sync + 'static> {
     modules: HashMap<KakarotRpcModule, Methods>,
-    _phantom: PhantomData<P>,
+    kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    head_watcher: Arc<HeadWatcher<P>>,
+    read_only: bool,
+    capabilities: CapabilityRegistry,
+    standby: Option<Arc<Standby>>,
+    index: Option<Arc<dyn IndexStore>>,
+    webhooks: Option<Arc<Webhooks>>,
 }
 
 impl<P: Provider + Send + Sync + 'static> KakarotRpcModuleBuilder<P> {
     pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>) -> Self {
         let eth_rpc_module = KakarotEthRpc::new(kakarot_client.clone()).into_rpc();
-        let alchemy_rpc_module = AlchemyRpc::new(kakarot_client).into_rpc();
+        // The head watcher feeds the subscriptions, and invalidates the caches of the client on each
+        // new block even without subscribers
+        let head_watcher = Arc::new(HeadWatcher::new(kakarot_client.clone(), DEFAULT_HEAD_POLL_INTERVAL));
+        if kakarot_client.caches_chain_tip() {
+            head_watcher.start();
+        }
+        let eth_pubsub_rpc_module = EthPubSubRpc::new(kakarot_client.clone(), head_watcher.clone()).into_rpc();
+        let alchemy_rpc_module = AlchemyRpc::new(kakarot_client.clone()).into_rpc();
+        let kakarot_rpc_module = KakarotRpc::new(kakarot_client.clone()).into_rpc();
+        let kakarot_pubsub_rpc_module = KakarotPubSubRpc::new(kakarot_client.clone(), head_watcher.clone()).into_rpc();
+        let debug_rpc_module = DebugRpc::new(kakarot_client.clone()).into_rpc();
+        let trace_rpc_module = TraceRpc::new(kakarot_client.clone()).into_rpc();
+        let txpool_rpc_module = TxPoolRpc::new(kakarot_client.clone()).into_rpc();
         let web3_rpc_module = Web3Rpc::default().into_rpc();
-        let net_rpc_module = NetRpc::default().into_rpc();
+        let net_rpc_module = NetRpc::new(kakarot_client.chain_id()).into_rpc();
 
         let mut modules: HashMap<KakarotRpcModule, Methods> = HashMap::new();
 
         modules.insert(KakarotRpcModule::Eth, eth_rpc_module.into());
+        modules.insert(KakarotRpcModule::EthPubSub, eth_pubsub_rpc_module.into());
         modules.insert(KakarotRpcModule::Alchemy, alchemy_rpc_module.into());
         modules.insert(KakarotRpcModule::Web3, web3_rpc_module.into());
         modules.insert(KakarotRpcModule::Net, net_rpc_module.into());
+        modules.insert(KakarotRpcModule::Kakarot, kakarot_rpc_module.into());
+        modules.insert(KakarotRpcModule::KakarotPubSub, kakarot_pubsub_rpc_module.into());
+        modules.insert(KakarotRpcModule::Debug, debug_rpc_module.into());
+        modules.insert(KakarotRpcModule::Trace, trace_rpc_module.into());
+        modules.insert(KakarotRpcModule::TxPool, txpool_rpc_module.into());
+
+        Self {
+            modules,
+            kakarot_client,
+            head_watcher,
+            read_only: false,
+            capabilities: CapabilityRegistry::default(),
+            standby: None,
+            index: None,
+            webhooks: None,
+        }
+    }
+
+    /// Adds the `evm` and `hardhat` test methods, which control the state of the embedded dev
+    /// network, and the `personal` methods managing the accounts of its signer.
+    #[cfg(feature = "dev")]
+    pub fn with_dev_network(mut self, dev_network: Arc<DevNetwork>) -> Self {
+        let personal_rpc = PersonalRpc::new(dev_network.signer().clone(), dev_network.keystore_dir().to_path_buf());
+        self.modules.insert(KakarotRpcModule::Personal, personal_rpc.into_rpc().into());
+        self.modules.insert(KakarotRpcModule::Hardhat, HardhatRpc::new(dev_network.signer().clone()).into_rpc().into());
+        self.modules.insert(KakarotRpcModule::Evm, EvmRpc::new(dev_network).into_rpc().into());
+        self
+    }
 
-        Self { modules, _phantom: PhantomData }
+    /// Adds the `evm` and `hardhat` test methods on top of a Katana devnet, the impersonated
+    /// accounts being signed for by the signer of the client.
+    pub fn with_katana(mut self, katana: KatanaDevClient, signer: EthSigner) -> Self {
+        self.modules.insert(KakarotRpcModule::Hardhat, HardhatRpc::new(signer).into_rpc().into());
+        self.modules.insert(KakarotRpcModule::Evm, EvmRpc::new(Arc::new(katana)).into_rpc().into());
+        self
+    }
+
+    /// Adds links to the Starknet explorer to the responses of the `kakarot` methods.
+    pub fn with_explorer(mut self, explorer: StarknetExplorer) -> Self {
+        let kakarot_rpc = KakarotRpc::new(self.kakarot_client.clone()).with_explorer(explorer);
+        self.modules.insert(KakarotRpcModule::Kakarot, kakarot_rpc.into_rpc().into());
+        self
+    }
+
+    /// Adds the administration methods of the log index, backfilling it through the given runner.
+    pub fn with_log_backfill(mut self, backfill: Arc<LogBackfill>) -> Self {
+        let kakarot_admin_rpc = KakarotAdminRpc::new(self.kakarot_client.clone(), backfill);
+        self.modules.insert(KakarotRpcModule::KakarotAdmin, kakarot_admin_rpc.into_rpc().into());
+        self.capabilities.enable(Capability::LogIndex);
+        self
+    }
+
+    /// Serves the `eth` methods from the store of the indexed blocks, falling back to Starknet for
+    /// the blocks which aren't indexed.
+    pub fn with_index(mut self, index: Arc<dyn IndexStore>) -> Self {
+        self.index = Some(index);
+        self.insert_eth_module();
+        self.capabilities.enable(Capability::Indexer);
+        self
+    }
+
+    /// Notifies the webhooks of the transactions relayed through the `eth` methods.
+    pub fn with_webhooks(mut self, webhooks: Arc<Webhooks>) -> Self {
+        self.webhooks = Some(webhooks);
+        self.insert_eth_module();
+        self
+    }
+
+    /// Replaces the `eth` methods by the ones served with the index and the webhooks.
+    fn insert_eth_module(&mut self) {
+        let mut eth_rpc = KakarotEthRpc::new(self.kakarot_client.clone());
+        if let Some(index) = &self.index {
+            eth_rpc = eth_rpc.with_index(Arc::clone(index));
+        }
+        if let Some(webhooks) = &self.webhooks {
+            eth_rpc = eth_rpc.with_webhooks(Arc::clone(webhooks));
+        }
+        self.modules.insert(KakarotRpcModule::Eth, eth_rpc.into_rpc().into());
+    }
+
+    /// Routes the state of the accounts between the dev network and the forked chain, replacing
+    /// the corresponding `eth` methods.
+    pub fn with_fork(mut self, fork: Arc<Fork>) -> Self {
+        if let Some(eth_methods) = self.modules.get_mut(&KakarotRpcModule::Eth) {
+            for method in FORK_METHODS {
+                eth_methods.remove_method(method);
+            }
+        }
+        let fork_rpc = ForkRpc::new(KakarotEthRpc::new(self.kakarot_client.clone()), fork);
+        self.modules.insert(KakarotRpcModule::Fork, fork_rpc.into_rpc().into());
+        self
+    }
+
+    /// Leaves out the state-changing methods, see [`is_state_changing`], for replicas which only
+    /// serve data. The methods are not registered at all, so that they can't be reached.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Disables an optional capability, whose methods then fail with a
+    /// [`feature_disabled`] error.
+    pub fn disable(mut self, capability: Capability) -> Self {
+        self.capabilities.disable(capability);
+        self
+    }
+
+    /// Adds the `kakarot_promote` method of a standby instance. The head watcher follows the chain
+    /// while in standby, so that the state of the instance is warm once promoted.
+    pub fn with_standby(mut self, standby: Arc<Standby>) -> Self {
+        self.head_watcher.start();
+        self.standby = Some(standby);
+        self
     }
 
     pub fn rpc_module(&self) -> Result<RpcModule<()>, Error> {
         let mut rpc_module = RpcModule::new(());
 
-        for methods in self.modules.values().cloned() {
+        for mut methods in self.modules.values().cloned() {
+            let removed: Vec<&'static str> = methods
+                .method_names()
+                .filter(|method| {
+                    (self.read_only && is_state_changing(method)) || self.capabilities.disabled_by(method).is_some()
+                })
+                .collect();
+            for method in removed {
+                methods.remove_method(method);
+            }
             rpc_module.merge(methods)?;
         }
+        // The methods of the disabled capabilities are still registered, to tell the clients why they
+        // fail
+        for capability in self.capabilities.disabled() {
+            for method in capability.methods() {
+                if self.read_only && is_state_changing(method) {
+                    continue;
+                }
+                rpc_module.register_method(method, move |_, _| Err::<(), _>(feature_disabled(capability)))?;
+            }
+        }
+        if let Some(standby) = &self.standby {
+            register_promote_method(&mut rpc_module, Arc::clone(standby))?;
+        }
+        register_capabilities(&mut rpc_module, &self.capabilities)?;
 
         Ok(rpc_module)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_state_changing() {
+        assert!(is_state_changing("eth_sendRawTransaction"));
+        assert!(is_state_changing("evm_mine"));
+        assert!(is_state_changing("anvil_impersonateAccount"));
+        assert!(is_state_changing("personal_newAccount"));
+        assert!(is_state_changing("kakarot_startLogBackfill"));
+        assert!(is_state_changing("kakarot_deployEoa"));
+        assert!(is_state_changing("eth_signTypedData_v4"));
+        assert!(!is_state_changing("eth_call"));
+        assert!(!is_state_changing("eth_newFilter"));
+        assert!(!is_state_changing("kakarot_logBackfillStatus"));
+    }
+}
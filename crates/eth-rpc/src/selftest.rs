@@ -0,0 +1,230 @@
+//! Self-test of a deployment: a curated suite of requests served by the RPC module against the
+//! configured Starknet node, run by the `selftest` command or at startup, so that an operator can
+//! check in seconds that the chain id, the blocks, the state and the calls are served.
+//!
+//! The requests go through the whole RPC module rather than the client, so that the conversions
+//! to the Ethereum types are checked along with the connection to the node. A check passes when
+//! the method returns a result of the expected shape, the values depending on the chain.
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Instant;
+
+use eyre::{eyre, Result};
+use jsonrpsee::RpcModule;
+use reth_primitives::Address;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Configuration of the self-test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestConfig {
+    /// Account whose balance and code are read, and which is called by `eth_call`.
+    pub account: Address,
+    /// Hex calldata of the `eth_call` check.
+    pub call_data: String,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        Self { account: Address::zero(), call_data: "0x".to_string() }
+    }
+}
+
+impl SelfTestConfig {
+    pub fn new(account: &str, call_data: &str) -> Result<Self> {
+        let account = Address::from_str(account).map_err(|_| eyre!("The self-test account should be an address"))?;
+        hex::decode(call_data.trim_start_matches("0x"))
+            .map_err(|_| eyre!("The self-test calldata should be a hex string, got {call_data}"))?;
+        Ok(Self { account, call_data: call_data.to_string() })
+    }
+
+    /// Create a new `SelfTestConfig` from the `KAKAROT_SELFTEST_ACCOUNT` and
+    /// `KAKAROT_SELFTEST_CALL_DATA` environment variables. Defaults to the zero address and an
+    /// empty calldata.
+    pub fn from_env() -> Result<Self> {
+        let account = std::env::var("KAKAROT_SELFTEST_ACCOUNT").unwrap_or_else(|_| format!("{:#x}", Address::zero()));
+        let call_data = std::env::var("KAKAROT_SELFTEST_CALL_DATA").unwrap_or_else(|_| "0x".to_string());
+        Self::new(&account, &call_data)
+    }
+
+    /// Returns true if the self-test must pass before the server starts, from the
+    /// `KAKAROT_SELFTEST_ON_STARTUP` environment variable.
+    pub fn on_startup() -> bool {
+        std::env::var("KAKAROT_SELFTEST_ON_STARTUP").map(|v| v.to_lowercase() == "true").unwrap_or(false)
+    }
+
+    /// Returns the checks of the suite, in order.
+    fn checks(&self) -> Vec<Check> {
+        let account = format!("{:#x}", self.account);
+        vec![
+            Check::new("eth_chainId", json!([]), is_quantity),
+            Check::new("eth_blockNumber", json!([]), is_quantity),
+            Check::new("eth_getBlockByNumber", json!(["latest", false]), |block| {
+                block.get("number").is_some_and(is_quantity) && block.get("hash").is_some_and(is_data)
+            }),
+            Check::new("eth_getBalance", json!([account, "latest"]), is_quantity),
+            Check::new("eth_getCode", json!([account, "latest"]), is_data),
+            Check::new("eth_call", json!([{ "to": account, "data": self.call_data }, "latest"]), is_data),
+        ]
+    }
+}
+
+/// Request of the suite and the check of its result.
+struct Check {
+    method: &'static str,
+    params: Value,
+    is_valid: fn(&Value) -> bool,
+}
+
+impl Check {
+    fn new(method: &'static str, params: Value, is_valid: fn(&Value) -> bool) -> Self {
+        Self { method, params, is_valid }
+    }
+
+    async fn run(&self, rpc_module: &RpcModule<()>) -> CheckResult {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": self.method, "params": self.params }).to_string();
+        let started = Instant::now();
+        let outcome = match rpc_module.raw_json_request(&request, 1).await {
+            Ok((response, _)) => self.outcome(&response.result),
+            Err(err) => Err(err.to_string()),
+        };
+        let duration_ms = started.elapsed().as_millis() as u64;
+        match outcome {
+            Ok(result) => CheckResult { method: self.method, passed: true, detail: result.to_string(), duration_ms },
+            Err(detail) => CheckResult { method: self.method, passed: false, detail, duration_ms },
+        }
+    }
+
+    /// Returns the result of the JSON-RPC response if it is valid, or the reason of the failure.
+    fn outcome(&self, response: &str) -> Result<Value, String> {
+        let response: Value = serde_json::from_str(response).map_err(|err| format!("invalid response: {err}"))?;
+        if let Some(error) = response.get("error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+            return Err(format!("error: {message}"));
+        }
+        let result = response.get("result").cloned().unwrap_or(Value::Null);
+        if !(self.is_valid)(&result) {
+            return Err(format!("unexpected result: {result}"));
+        }
+        Ok(result)
+    }
+}
+
+fn is_quantity(value: &Value) -> bool {
+    value
+        .as_str()
+        .and_then(|value| value.strip_prefix("0x"))
+        .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|digit| digit.is_ascii_hexdigit()))
+}
+
+fn is_data(value: &Value) -> bool {
+    value.as_str().and_then(|value| value.strip_prefix("0x")).is_some_and(|digits| hex::decode(digits).is_ok())
+}
+
+/// Outcome of a check of the self-test.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckResult {
+    pub method: &'static str,
+    pub passed: bool,
+    /// Result of the method if the check passed, the reason of the failure otherwise.
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+/// Report of the self-test.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|check| !check.passed).count()
+    }
+}
+
+impl Display for SelfTestReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            let status = if check.passed { "PASS" } else { "FAIL" };
+            writeln!(f, "{status} {:<22} {:>6}ms  {}", check.method, check.duration_ms, check.detail)?;
+        }
+        write!(f, "{}/{} checks passed", self.checks.len() - self.failures(), self.checks.len())
+    }
+}
+
+/// Runs the checks of the self-test against the RPC module, one after the other.
+pub async fn run_selftest(rpc_module: &RpcModule<()>, config: &SelfTestConfig) -> SelfTestReport {
+    let mut checks = Vec::new();
+    for check in config.checks() {
+        let result = check.run(rpc_module).await;
+        if !result.passed {
+            tracing::warn!("Self-test check {} failed: {}", result.method, result.detail);
+        }
+        checks.push(result);
+    }
+    SelfTestReport { checks }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee::types::ErrorObject;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_selftest() {
+        // Given
+        let mut rpc_module = RpcModule::new(());
+        rpc_module.register_method("eth_chainId", |_, _| Ok::<_, ErrorObject<'static>>("0x1")).unwrap();
+        rpc_module.register_method("eth_blockNumber", |_, _| Ok::<_, ErrorObject<'static>>("0x2a")).unwrap();
+        rpc_module
+            .register_method("eth_getBlockByNumber", |_, _| {
+                Ok::<_, ErrorObject<'static>>(json!({ "number": "0x2a", "hash": format!("0x{}", "00".repeat(32)) }))
+            })
+            .unwrap();
+        rpc_module.register_method("eth_getBalance", |_, _| Ok::<_, ErrorObject<'static>>(42)).unwrap();
+        rpc_module.register_method("eth_getCode", |_, _| Ok::<_, ErrorObject<'static>>("0x6080")).unwrap();
+        rpc_module
+            .register_method("eth_call", |_, _| {
+                Err::<(), _>(ErrorObject::owned(-32000, "execution reverted", None::<()>))
+            })
+            .unwrap();
+
+        // When
+        let report = run_selftest(&rpc_module, &SelfTestConfig::default()).await;
+
+        // Then
+        let passed: Vec<_> = report.checks.iter().map(|check| (check.method, check.passed)).collect();
+        assert_eq!(
+            vec![
+                ("eth_chainId", true),
+                ("eth_blockNumber", true),
+                ("eth_getBlockByNumber", true),
+                ("eth_getBalance", false),
+                ("eth_getCode", true),
+                ("eth_call", false)
+            ],
+            passed
+        );
+        assert_eq!("unexpected result: 42", report.checks[3].detail);
+        assert_eq!("error: execution reverted", report.checks[5].detail);
+        assert!(!report.passed());
+        assert!(report.to_string().ends_with("4/6 checks passed"));
+    }
+
+    #[test]
+    fn test_selftest_config() {
+        assert_eq!(
+            Address::from_low_u64_be(0xabc),
+            SelfTestConfig::new("0x0000000000000000000000000000000000000abc", "0x").unwrap().account
+        );
+        assert!(SelfTestConfig::new("0xabc", "0x").is_err());
+        assert!(SelfTestConfig::new("0x0000000000000000000000000000000000000abc", "0xzz").is_err());
+    }
+}
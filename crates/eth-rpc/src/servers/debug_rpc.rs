@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use jsonrpsee::core::{async_trait, RpcResult as Result};
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::models::trace::{BlockTraceResult, GethTrace, TracingOptions};
+use reth_primitives::{BlockId, BlockNumberOrTag, H256};
+use reth_rpc_types::CallRequest;
+use starknet::providers::Provider;
+
+use crate::api::debug_api::DebugApiServer;
+
+/// The RPC module for the debug methods.
+pub struct DebugRpc<P: Provider + Send + Sync> {
+    pub kakarot_client: Arc<dyn KakarotEthApi<P>>,
+}
+
+impl<P: Provider + Send + Sync> DebugRpc<P> {
+    pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>) -> Self {
+        Self { kakarot_client }
+    }
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync + 'static> DebugApiServer for DebugRpc<P> {
+    async fn trace_transaction(&self, hash: H256, options: Option<TracingOptions>) -> Result<GethTrace> {
+        let trace = self.kakarot_client.trace_transaction(hash, options.unwrap_or_default()).await?;
+        Ok(trace)
+    }
+
+    async fn trace_call(
+        &self,
+        request: CallRequest,
+        block_id: Option<BlockId>,
+        options: Option<TracingOptions>,
+    ) -> Result<GethTrace> {
+        let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
+        let trace = self.kakarot_client.trace_call(request, block_id, options.unwrap_or_default()).await?;
+        Ok(trace)
+    }
+
+    async fn trace_block_by_number(
+        &self,
+        number: BlockNumberOrTag,
+        options: Option<TracingOptions>,
+    ) -> Result<Vec<BlockTraceResult>> {
+        let traces = self.kakarot_client.trace_block_by_number(number, options.unwrap_or_default()).await?;
+        Ok(traces)
+    }
+}
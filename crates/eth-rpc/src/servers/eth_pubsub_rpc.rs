@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use jsonrpsee::core::{async_trait, SubscriptionResult};
+use jsonrpsee::types::ErrorObject;
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage, SubscriptionSink};
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::client::errors::{rpc_err, EthRpcErrorCode};
+use kakarot_rpc_core::client::head_watcher::{HeadEvent, HeadWatcher};
+use kakarot_rpc_core::client::reorg::MAX_REORG_DEPTH;
+use reth_rpc_types::pubsub::{Params, SubscriptionKind, SubscriptionResult as SubscriptionItem};
+use reth_rpc_types::{Filter, Log, Rich};
+use serde::Serialize;
+use starknet::providers::Provider;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+
+use crate::api::eth_pubsub_api::EthPubSubApiServer;
+
+/// The RPC module for the Ethereum subscriptions, fed by a background task polling the Starknet
+/// provider for new blocks.
+pub struct EthPubSubRpc<P: Provider + Send + Sync + 'static> {
+    pub kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    head_watcher: Arc<HeadWatcher<P>>,
+}
+
+impl<P: Provider + Send + Sync + 'static> EthPubSubRpc<P> {
+    pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>, head_watcher: Arc<HeadWatcher<P>>) -> Self {
+        Self { kakarot_client, head_watcher }
+    }
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync + 'static> EthPubSubApiServer for EthPubSubRpc<P> {
+    async fn subscribe(
+        &self,
+        pending: PendingSubscriptionSink,
+        kind: SubscriptionKind,
+        params: Option<Params>,
+    ) -> SubscriptionResult {
+        let filter = match (kind, params) {
+            (SubscriptionKind::Logs, Some(Params::Logs(filter))) => Some(*filter),
+            (SubscriptionKind::Logs, _) => Some(Filter::default()),
+            (SubscriptionKind::NewHeads | SubscriptionKind::NewPendingTransactions, None | Some(Params::None)) => None,
+            (SubscriptionKind::NewPendingTransactions, Some(Params::Bool(false))) => None,
+            (SubscriptionKind::Syncing, _) => {
+                pending.reject(invalid_params("syncing subscriptions are not supported")).await;
+                return Ok(());
+            }
+            (_, Some(_)) => {
+                pending.reject(invalid_params("unexpected subscription params")).await;
+                return Ok(());
+            }
+        };
+
+        let sink = pending.accept().await?;
+
+        match (kind, filter) {
+            (SubscriptionKind::NewHeads, _) => {
+                let heads = self.head_watcher.subscribe_new_heads();
+                tokio::spawn(pipe(sink, heads, |event: HeadEvent| async move {
+                    match event {
+                        HeadEvent::NewHead(block) => {
+                            let header = Rich { inner: block.inner.header, extra_info: block.extra_info };
+                            vec![SubscriptionItem::Header(Box::new(header))]
+                        }
+                        // The headers of the blocks replacing the reverted ones are sent next
+                        HeadEvent::Reorg { .. } => vec![],
+                    }
+                }));
+            }
+            (SubscriptionKind::Logs, Some(filter)) => {
+                let heads = self.head_watcher.subscribe_new_heads();
+                let kakarot_client = Arc::clone(&self.kakarot_client);
+                let sent_logs: Arc<SentItems<Log>> = Arc::default();
+                tokio::spawn(pipe(sink, heads, move |event: HeadEvent| {
+                    let kakarot_client = Arc::clone(&kakarot_client);
+                    let filter = filter.clone();
+                    let sent_logs = Arc::clone(&sent_logs);
+                    async move {
+                        let block = match event {
+                            HeadEvent::NewHead(block) => block,
+                            HeadEvent::Reorg { from_block } => {
+                                return sent_logs
+                                    .revert(from_block)
+                                    .into_iter()
+                                    .map(|log| SubscriptionItem::Log(Box::new(Log { removed: true, ..log })))
+                                    .collect();
+                            }
+                        };
+                        let block_number = match block.inner.header.number {
+                            Some(block_number) => block_number.saturating_to::<u64>(),
+                            None => return vec![],
+                        };
+                        match kakarot_client.get_logs(filter.from_block(block_number).to_block(block_number)).await {
+                            Ok(logs) => {
+                                sent_logs.record(block_number, logs.clone());
+                                logs.into_iter().map(|log| SubscriptionItem::Log(Box::new(log))).collect()
+                            }
+                            Err(err) => {
+                                tracing::warn!("Failed to fetch the logs of block {block_number}: {err}");
+                                vec![]
+                            }
+                        }
+                    }
+                }));
+            }
+            _ => {
+                let transactions = self.head_watcher.subscribe_pending_transactions();
+                tokio::spawn(pipe(
+                    sink,
+                    transactions,
+                    |hash| async move { vec![SubscriptionItem::TransactionHash(hash)] },
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn invalid_params(message: &str) -> ErrorObject<'static> {
+    rpc_err(EthRpcErrorCode::InvalidParams as i32, message)
+}
+
+/// Items sent for the recent blocks, sent again as removed if their block is reverted.
+pub(crate) struct SentItems<T>(Mutex<BTreeMap<u64, Vec<T>>>);
+
+impl<T> Default for SentItems<T> {
+    fn default() -> Self {
+        Self(Mutex::default())
+    }
+}
+
+impl<T> SentItems<T> {
+    /// Records the items sent for the block, forgetting the blocks too old to be reverted.
+    pub(crate) fn record(&self, block_number: u64, items: Vec<T>) {
+        let mut sent = self.0.lock().expect("sent items poisoned");
+        sent.insert(block_number, items);
+        *sent = sent.split_off(&block_number.saturating_sub(MAX_REORG_DEPTH - 1));
+    }
+
+    /// Returns the items sent for the blocks from `from_block` onwards, forgetting them.
+    pub(crate) fn revert(&self, from_block: u64) -> Vec<T> {
+        self.0.lock().expect("sent items poisoned").split_off(&from_block).into_values().flatten().collect()
+    }
+}
+
+/// Forwards the items published by the head watcher to the subscriber, until it unsubscribes or
+/// disconnects.
+pub(crate) async fn pipe<T, I, F, Fut>(sink: SubscriptionSink, mut receiver: Receiver<T>, mut into_items: F)
+where
+    T: Clone,
+    I: Serialize,
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = Vec<I>>,
+{
+    loop {
+        let item = tokio::select! {
+            _ = sink.closed() => return,
+            item = receiver.recv() => item,
+        };
+        let item = match item {
+            Ok(item) => item,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Subscription {:?} lagged, {skipped} items skipped", sink.subscription_id());
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        for item in into_items(item).await {
+            let message = match SubscriptionMessage::from_json(&item) {
+                Ok(message) => message,
+                Err(err) => {
+                    tracing::error!("Failed to serialize subscription item: {err}");
+                    continue;
+                }
+            };
+            if sink.send(message).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sent_items_revert() {
+        // Given
+        let sent = SentItems::default();
+        sent.record(10, vec![1, 2]);
+        sent.record(11, vec![]);
+        sent.record(12, vec![3]);
+
+        // When
+        let reverted = sent.revert(11);
+
+        // Then
+        assert_eq!(vec![3], reverted);
+        // The reverted items are forgotten, the older ones are kept
+        assert!(sent.revert(11).is_empty());
+        assert_eq!(vec![1, 2], sent.revert(0));
+    }
+
+    #[test]
+    fn test_sent_items_record_forgets_old_blocks() {
+        // Given
+        let sent = SentItems::default();
+        let last_block = MAX_REORG_DEPTH + 10;
+
+        // When
+        for block_number in 0..=last_block {
+            sent.record(block_number, vec![block_number]);
+        }
+
+        // Then
+        // Only the blocks which can still be reverted are kept
+        let kept = sent.revert(0);
+        assert_eq!(MAX_REORG_DEPTH as usize, kept.len());
+        assert_eq!(Some(&(last_block + 1 - MAX_REORG_DEPTH)), kept.first());
+        assert_eq!(Some(&last_block), kept.last());
+    }
+}
@@ -1,34 +1,95 @@
 use std::sync::Arc;
 
+use ethers::types::transaction::eip712::TypedData;
 use jsonrpsee::core::{async_trait, RpcResult as Result};
-use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, METHOD_NOT_FOUND_CODE};
-use kakarot_rpc_core::client::api::KakarotEthApi;
-use kakarot_rpc_core::client::constants::CHAIN_ID;
-use kakarot_rpc_core::client::errors::{rpc_err, EthApiError};
+use jsonrpsee::types::ErrorObject;
+use kakarot_rpc_core::client::api::{KakarotEthApi, KakarotStarknetApi};
+use kakarot_rpc_core::client::errors::{rpc_err, EthApiError, EthRpcErrorCode, SignerError};
+use kakarot_rpc_core::client::log_index::log_matches;
 use kakarot_rpc_core::models::block::EthBlockId;
+use kakarot_rpc_core::models::state_override::StateOverride;
+use kakarot_rpc_indexer::store::{IndexStore, StoreError};
 use reth_primitives::rpc::transaction::eip2930::AccessListWithGasUsed;
 use reth_primitives::{Address, BlockId, BlockNumberOrTag, Bytes, H256, H64, U128, U256, U64};
 use reth_rpc_types::{
-    CallRequest, EIP1186AccountProofResponse, FeeHistory, Filter, FilterChanges, Index, Log, RichBlock, SyncStatus,
-    Transaction as EtherTransaction, TransactionReceipt, TransactionRequest, Work,
+    CallRequest, EIP1186AccountProofResponse, FeeHistory, Filter, FilterBlockOption, FilterChanges, Index, Log,
+    RichBlock, SyncStatus, Transaction as EtherTransaction, TransactionReceipt, Work,
 };
 use serde_json::Value;
-use starknet::core::types::BlockId as StarknetBlockId;
+use starknet::core::types::{BlockId as StarknetBlockId, BlockTag};
 use starknet::providers::Provider;
 
 use crate::api::eth_api::EthApiServer;
+use crate::capabilities::unsupported_method;
+use crate::webhooks::Webhooks;
 
 /// The RPC module for the Ethereum protocol required by Kakarot.
 pub struct KakarotEthRpc<P: Provider + Send + Sync> {
     pub kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    /// Store of the indexed blocks, queried before Starknet.
+    index: Option<Arc<dyn IndexStore>>,
+    /// Webhooks notified of the transactions relayed through the RPC.
+    webhooks: Option<Arc<Webhooks>>,
 }
 
 impl<P: Provider + Send + Sync> KakarotEthRpc<P> {
     pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>) -> Self {
-        Self { kakarot_client }
+        Self { kakarot_client, index: None, webhooks: None }
+    }
+
+    /// Serves the blocks, transactions, receipts and logs indexed in the store from the store.
+    pub fn with_index(mut self, index: Arc<dyn IndexStore>) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Notifies the webhooks of the relayed transactions.
+    pub fn with_webhooks(mut self, webhooks: Arc<Webhooks>) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    fn notify_relayed(&self, transaction_hash: H256) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.relayed(transaction_hash);
+        }
     }
 }
 
+/// Returns the result of a lookup in the index store. A failed lookup is logged and treated as a
+/// miss, so that the request falls back to Starknet.
+fn index_hit<T>(lookup: std::result::Result<Option<T>, StoreError>) -> Option<T> {
+    lookup.unwrap_or_else(|err| {
+        tracing::warn!("Index store lookup failed, falling back to Starknet: {err}");
+        None
+    })
+}
+
+/// Returns the indexed logs matching the filter, `None` if its block range isn't entirely indexed.
+/// Only the ranges between block numbers and the block hashes are served from the index.
+async fn indexed_logs(index: &dyn IndexStore, filter: &Filter) -> std::result::Result<Option<Vec<Log>>, StoreError> {
+    let (from_block, to_block) = match &filter.block_option {
+        FilterBlockOption::AtBlockHash(hash) => match index.block_number_by_hash(*hash).await? {
+            Some(number) => (number, number),
+            None => return Ok(None),
+        },
+        FilterBlockOption::Range {
+            from_block: Some(BlockNumberOrTag::Number(from_block)),
+            to_block: Some(BlockNumberOrTag::Number(to_block)),
+        } => (*from_block, *to_block),
+        _ => return Ok(None),
+    };
+    let Some(indexed_blocks) = index.indexed_blocks().await? else {
+        return Ok(None);
+    };
+    if to_block < from_block || !indexed_blocks.contains(&from_block) || !indexed_blocks.contains(&to_block) {
+        return Ok(None);
+    }
+
+    let logs = index.logs(from_block, to_block).await?;
+    Ok(Some(logs.into_iter().filter(|log| log_matches(filter, log)).collect()))
+}
+
 #[async_trait]
 impl<P: Provider + Send + Sync + 'static> EthApiServer for KakarotEthRpc<P> {
     async fn block_number(&self) -> Result<U64> {
@@ -42,51 +103,75 @@ impl<P: Provider + Send + Sync + 'static> EthApiServer for KakarotEthRpc<P> {
     }
 
     async fn author(&self) -> Result<Address> {
-        todo!()
+        if let Some(coinbase) = self.kakarot_client.coinbase() {
+            return Ok(coinbase);
+        }
+
+        // Without a configured coinbase, the miner of the latest block is the fee recipient
+        let latest = StarknetBlockId::Tag(BlockTag::Latest);
+        let block = self.kakarot_client.get_eth_block_from_starknet_block(latest, false).await?;
+        Ok(block.inner.header.miner)
     }
 
     async fn accounts(&self) -> Result<Vec<Address>> {
-        Ok(Vec::new())
+        Ok(self.kakarot_client.accounts())
     }
 
     async fn chain_id(&self) -> Result<Option<U64>> {
-        Ok(Some(CHAIN_ID.into()))
+        Ok(Some(self.kakarot_client.chain_id().into()))
     }
 
+    #[tracing::instrument(name = "eth_getBlockByHash", skip(self))]
     async fn block_by_hash(&self, hash: H256, full: bool) -> Result<Option<RichBlock>> {
+        if let Some(index) = &self.index {
+            if let Some(block) = index_hit(index.block_by_hash(hash).await) {
+                return Ok(Some(block.rich_block(full)));
+            }
+        }
+
         let block_id = EthBlockId::new(BlockId::Hash(hash.into()));
         let starknet_block_id: StarknetBlockId = block_id.try_into().map_err(EthApiError::<P::Error>::from)?;
         let block = self.kakarot_client.get_eth_block_from_starknet_block(starknet_block_id, full).await?;
         Ok(Some(block))
     }
 
+    #[tracing::instrument(name = "eth_getBlockByNumber", skip(self))]
     async fn block_by_number(&self, number: BlockNumberOrTag, full: bool) -> Result<Option<RichBlock>> {
-        let block_id = EthBlockId::new(BlockId::Number(number));
-        let starknet_block_id: StarknetBlockId = block_id.try_into().map_err(EthApiError::<P::Error>::from)?;
+        if let (Some(index), BlockNumberOrTag::Number(block_number)) = (&self.index, number) {
+            if let Some(block) = index_hit(index.block_by_number(block_number).await) {
+                return Ok(Some(block.rich_block(full)));
+            }
+        }
+
+        let starknet_block_id = self.kakarot_client.starknet_block_id(BlockId::Number(number)).await?;
         let block = self.kakarot_client.get_eth_block_from_starknet_block(starknet_block_id, full).await?;
         Ok(Some(block))
     }
 
+    #[tracing::instrument(name = "eth_getBlockTransactionCountByHash", skip(self))]
     async fn block_transaction_count_by_hash(&self, hash: H256) -> Result<U64> {
         let transaction_count = self.kakarot_client.block_transaction_count_by_hash(hash).await?;
         Ok(transaction_count)
     }
 
+    #[tracing::instrument(name = "eth_getBlockTransactionCountByNumber", skip(self))]
     async fn block_transaction_count_by_number(&self, number: BlockNumberOrTag) -> Result<U64> {
         let transaction_count = self.kakarot_client.block_transaction_count_by_number(number).await?;
         Ok(transaction_count)
     }
 
+    // Starknet has no uncles, the blocks of Kakarot have none
+
     async fn block_uncles_count_by_hash(&self, _hash: H256) -> Result<U256> {
-        todo!()
+        Ok(U256::ZERO)
     }
 
     async fn block_uncles_count_by_number(&self, _number: BlockNumberOrTag) -> Result<U256> {
-        todo!()
+        Ok(U256::ZERO)
     }
 
     async fn uncle_by_block_hash_and_index(&self, _hash: H256, _index: Index) -> Result<Option<RichBlock>> {
-        todo!()
+        Ok(None)
     }
 
     async fn uncle_by_block_number_and_index(
@@ -94,20 +179,29 @@ impl<P: Provider + Send + Sync + 'static> EthApiServer for KakarotEthRpc<P> {
         _number: BlockNumberOrTag,
         _index: Index,
     ) -> Result<Option<RichBlock>> {
-        todo!()
+        Ok(None)
     }
 
-    async fn transaction_by_hash(&self, _hash: H256) -> Result<Option<EtherTransaction>> {
-        let ether_tx = self.kakarot_client.transaction_by_hash(_hash).await?;
+    #[tracing::instrument(name = "eth_getTransactionByHash", skip(self))]
+    async fn transaction_by_hash(&self, hash: H256) -> Result<Option<EtherTransaction>> {
+        if let Some(index) = &self.index {
+            if let Some(transaction) = index_hit(index.transaction(hash).await) {
+                return Ok(Some(transaction));
+            }
+        }
+
+        let ether_tx = self.kakarot_client.transaction_by_hash(hash).await?;
         Ok(ether_tx)
     }
 
+    #[tracing::instrument(name = "eth_getTransactionByBlockHashAndIndex", skip(self))]
     async fn transaction_by_block_hash_and_index(&self, hash: H256, index: Index) -> Result<Option<EtherTransaction>> {
         let block_id = BlockId::Hash(hash.into());
         let tx = self.kakarot_client.transaction_by_block_id_and_index(block_id, index).await?;
         Ok(Some(tx))
     }
 
+    #[tracing::instrument(name = "eth_getTransactionByBlockNumberAndIndex", skip(self))]
     async fn transaction_by_block_number_and_index(
         &self,
         number: BlockNumberOrTag,
@@ -118,23 +212,39 @@ impl<P: Provider + Send + Sync + 'static> EthApiServer for KakarotEthRpc<P> {
         Ok(Some(tx))
     }
 
+    #[tracing::instrument(name = "eth_getTransactionReceipt", skip(self))]
     async fn transaction_receipt(&self, hash: H256) -> Result<Option<TransactionReceipt>> {
+        if let Some(index) = &self.index {
+            if let Some(receipt) = index_hit(index.receipt(hash).await) {
+                return Ok(Some(receipt));
+            }
+        }
+
         let receipt = self.kakarot_client.transaction_receipt(hash).await?;
         Ok(receipt)
     }
 
+    #[tracing::instrument(name = "eth_getBlockReceipts", skip(self))]
+    async fn block_receipts(&self, block_id: BlockId) -> Result<Option<Vec<TransactionReceipt>>> {
+        let receipts = self.kakarot_client.block_receipts(block_id).await?;
+        Ok(receipts)
+    }
+
+    #[tracing::instrument(name = "eth_getBalance", skip(self))]
     async fn balance(&self, address: Address, block_id: Option<BlockId>) -> Result<U256> {
         let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
         let balance = self.kakarot_client.balance(address, block_id).await?;
         Ok(balance)
     }
 
+    #[tracing::instrument(name = "eth_getStorageAt", skip(self))]
     async fn storage_at(&self, address: Address, index: U256, block_id: Option<BlockId>) -> Result<U256> {
         let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
         let value = self.kakarot_client.storage_at(address, index, block_id).await?;
         Ok(value)
     }
 
+    #[tracing::instrument(name = "eth_getTransactionCount", skip(self))]
     async fn transaction_count(&self, address: Address, block_id: Option<BlockId>) -> Result<U256> {
         let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
 
@@ -143,29 +253,49 @@ impl<P: Provider + Send + Sync + 'static> EthApiServer for KakarotEthRpc<P> {
         Ok(transaction_count)
     }
 
+    #[tracing::instrument(name = "eth_getCode", skip(self))]
     async fn get_code(&self, address: Address, block_id: Option<BlockId>) -> Result<Bytes> {
         let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
         let code = self.kakarot_client.get_code(address, block_id).await?;
         Ok(code)
     }
 
+    #[tracing::instrument(name = "eth_getLogs", skip_all, fields(block_option = ?filter.block_option))]
     async fn get_logs(&self, filter: Filter) -> Result<Vec<Log>> {
+        if let Some(index) = &self.index {
+            if let Some(logs) = index_hit(indexed_logs(index.as_ref(), &filter).await) {
+                return Ok(logs);
+            }
+        }
+
         let logs = self.kakarot_client.get_logs(filter).await?;
         Ok(logs)
     }
 
-    async fn call(&self, request: CallRequest, block_id: Option<BlockId>) -> Result<Bytes> {
+    #[tracing::instrument(name = "eth_call", skip(self, request, state_override), fields(to = ?request.to))]
+    async fn call(
+        &self,
+        request: CallRequest,
+        block_id: Option<BlockId>,
+        state_override: Option<StateOverride>,
+    ) -> Result<Bytes> {
         // unwrap option or return jsonrpc error
         let to = request.to.ok_or_else(|| {
-            rpc_err(INTERNAL_ERROR_CODE, "CallRequest `to` field is None. Cannot process a Kakarot call")
+            rpc_err(
+                EthRpcErrorCode::InvalidParams as i32,
+                "CallRequest `to` field is None. Cannot process a Kakarot call",
+            )
         })?;
 
         let calldata = request.data.ok_or_else(|| {
-            rpc_err(INTERNAL_ERROR_CODE, "CallRequest `data` field is None. Cannot process a Kakarot call")
+            rpc_err(
+                EthRpcErrorCode::InvalidParams as i32,
+                "CallRequest `data` field is None. Cannot process a Kakarot call",
+            )
         })?;
 
         let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
-        let result = self.kakarot_client.call(to, Bytes::from(calldata.0), block_id).await?;
+        let result = self.kakarot_client.call(to, Bytes::from(calldata.0), block_id, state_override).await?;
 
         Ok(result)
     }
@@ -175,9 +305,10 @@ impl<P: Provider + Send + Sync + 'static> EthApiServer for KakarotEthRpc<P> {
         _request: CallRequest,
         _block_id: Option<BlockId>,
     ) -> Result<AccessListWithGasUsed> {
-        todo!()
+        Err(unsupported_method("eth_createAccessList"))
     }
 
+    #[tracing::instrument(name = "eth_estimateGas", skip(self, request), fields(to = ?request.to))]
     async fn estimate_gas(&self, request: CallRequest, block_id: Option<BlockId>) -> Result<U256> {
         let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
 
@@ -189,6 +320,7 @@ impl<P: Provider + Send + Sync + 'static> EthApiServer for KakarotEthRpc<P> {
         Ok(gas_price)
     }
 
+    #[tracing::instrument(name = "eth_feeHistory", skip(self))]
     async fn fee_history(
         &self,
         block_count: U256,
@@ -205,45 +337,56 @@ impl<P: Provider + Send + Sync + 'static> EthApiServer for KakarotEthRpc<P> {
         Ok(max_priority_fee)
     }
 
+    // The blocks are produced by the Starknet sequencer, there is no proof of work
+
     async fn is_mining(&self) -> Result<bool> {
-        Err(rpc_err(METHOD_NOT_FOUND_CODE, "Unsupported method: eth_mining. See available methods at https://github.com/sayajin-labs/kakarot-rpc/blob/main/docs/rpc_api_status.md".to_string()))
+        Ok(false)
     }
 
     async fn hashrate(&self) -> Result<U256> {
-        Err(rpc_err(METHOD_NOT_FOUND_CODE, "Unsupported method: eth_hashrate. See available methods at https://github.com/sayajin-labs/kakarot-rpc/blob/main/docs/rpc_api_status.md".to_string()))
+        Ok(U256::ZERO)
     }
 
     async fn get_work(&self) -> Result<Work> {
-        Err(rpc_err(METHOD_NOT_FOUND_CODE, "Unsupported method: eth_getWork. See available methods at https://github.com/sayajin-labs/kakarot-rpc/blob/main/docs/rpc_api_status.md".to_string()))
+        Err(unsupported_method("eth_getWork"))
     }
 
     async fn submit_hashrate(&self, _hashrate: U256, _id: H256) -> Result<bool> {
-        todo!()
+        Ok(false)
     }
 
     async fn submit_work(&self, _nonce: H64, _pow_hash: H256, _mix_digest: H256) -> Result<bool> {
-        todo!()
+        Ok(false)
     }
 
-    async fn send_transaction(&self, _request: TransactionRequest) -> Result<H256> {
-        todo!()
+    async fn send_transaction(&self, request: CallRequest) -> Result<H256> {
+        let transaction_hash = self.kakarot_client.send_unsigned_transaction(request).await?;
+        self.notify_relayed(transaction_hash);
+        Ok(transaction_hash)
     }
 
     async fn send_raw_transaction(&self, bytes: Bytes) -> Result<H256> {
         let transaction_hash = self.kakarot_client.send_transaction(bytes).await?;
+        self.notify_relayed(transaction_hash);
         Ok(transaction_hash)
     }
 
-    async fn sign(&self, _address: Address, _message: Bytes) -> Result<Bytes> {
-        todo!()
+    async fn sign(&self, address: Address, message: Bytes) -> Result<Bytes> {
+        Ok(self.kakarot_client.sign(address, message)?)
     }
 
-    async fn sign_transaction(&self, _transaction: CallRequest) -> Result<Bytes> {
-        todo!()
+    async fn sign_transaction(&self, transaction: CallRequest) -> Result<Bytes> {
+        Ok(self.kakarot_client.sign_transaction(transaction).await?)
     }
 
-    async fn sign_typed_data(&self, _address: Address, _data: Value) -> Result<Bytes> {
-        todo!()
+    async fn sign_typed_data(&self, address: Address, data: Value) -> Result<Bytes> {
+        // MetaMask sends the typed data as a JSON string
+        let typed_data = match data {
+            Value::String(data) => serde_json::from_str::<TypedData>(&data),
+            data => serde_json::from_value::<TypedData>(data),
+        }
+        .map_err(|err| ErrorObject::from(SignerError::InvalidTypedData(err.to_string())))?;
+        Ok(self.kakarot_client.sign_typed_data(address, &typed_data)?)
     }
 
     async fn get_proof(
@@ -252,30 +395,35 @@ impl<P: Provider + Send + Sync + 'static> EthApiServer for KakarotEthRpc<P> {
         _keys: Vec<H256>,
         _block_id: Option<BlockId>,
     ) -> Result<EIP1186AccountProofResponse> {
-        todo!()
+        Err(unsupported_method("eth_getProof"))
     }
 
-    async fn new_filter(&self, _filter: Filter) -> Result<U64> {
-        todo!()
+    async fn new_filter(&self, filter: Filter) -> Result<U64> {
+        let id = self.kakarot_client.new_filter(filter).await?;
+        Ok(id)
     }
 
     async fn new_block_filter(&self) -> Result<U64> {
-        todo!()
+        let id = self.kakarot_client.new_block_filter().await?;
+        Ok(id)
     }
 
     async fn new_pending_transaction_filter(&self) -> Result<U64> {
-        todo!()
+        let id = self.kakarot_client.new_pending_transaction_filter().await?;
+        Ok(id)
     }
 
-    async fn uninstall_filter(&self, _id: U64) -> Result<bool> {
-        todo!()
+    async fn uninstall_filter(&self, id: U64) -> Result<bool> {
+        Ok(self.kakarot_client.uninstall_filter(id))
     }
 
-    async fn get_filter_changes(&self, _id: U64) -> Result<FilterChanges> {
-        todo!()
+    async fn get_filter_changes(&self, id: U64) -> Result<FilterChanges> {
+        let changes = self.kakarot_client.get_filter_changes(id).await?;
+        Ok(changes)
     }
 
-    async fn get_filter_logs(&self, _id: U64) -> Result<FilterChanges> {
-        todo!()
+    async fn get_filter_logs(&self, id: U64) -> Result<FilterChanges> {
+        let logs = self.kakarot_client.get_filter_logs(id).await?;
+        Ok(logs)
     }
 }
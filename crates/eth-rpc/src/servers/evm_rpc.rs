@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use jsonrpsee::core::{async_trait, RpcResult as Result};
+use kakarot_rpc_core::client::errors::{rpc_err, EthRpcErrorCode};
+use kakarot_rpc_core::models::encoding::to_quantity;
+use reth_primitives::U64;
+
+use crate::api::evm_api::EvmApiServer;
+use crate::katana::EvmBackend;
+
+/// The RPC module for the `evm` test methods, backed by the embedded dev network or a Katana
+/// devnet.
+pub struct EvmRpc {
+    pub backend: Arc<dyn EvmBackend>,
+}
+
+impl EvmRpc {
+    pub fn new(backend: Arc<dyn EvmBackend>) -> Self {
+        Self { backend }
+    }
+}
+
+#[async_trait]
+impl EvmApiServer for EvmRpc {
+    async fn snapshot(&self) -> Result<U64> {
+        self.backend.snapshot().await.map_err(|err| rpc_err(EthRpcErrorCode::InternalError as i32, err.to_string()))
+    }
+
+    async fn revert(&self, id: U64) -> Result<bool> {
+        self.backend.revert(id).await.map_err(|err| rpc_err(EthRpcErrorCode::InternalError as i32, err.to_string()))
+    }
+
+    async fn mine(&self, timestamp: Option<U64>) -> Result<String> {
+        let timestamp = timestamp.map(|timestamp| timestamp.as_u64());
+        self.backend
+            .mine(timestamp)
+            .await
+            .map_err(|err| rpc_err(EthRpcErrorCode::InternalError as i32, err.to_string()))?;
+        Ok(to_quantity(0u64))
+    }
+
+    async fn increase_time(&self, seconds: U64) -> Result<U64> {
+        self.backend
+            .increase_time(seconds.as_u64())
+            .await
+            .map_err(|err| rpc_err(EthRpcErrorCode::InternalError as i32, err.to_string()))
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use ethers::utils::get_contract_address;
+use jsonrpsee::core::{async_trait, RpcResult as Result};
+use jsonrpsee::rpc_params;
+use kakarot_rpc_core::client::errors::{rpc_err, EthRpcErrorCode};
+use kakarot_rpc_core::models::state_override::StateOverride;
+use reth_primitives::{Address, BlockId, Bytes, TransactionSigned, H256, U256};
+use reth_rlp::Decodable;
+use reth_rpc_types::CallRequest;
+use starknet::providers::Provider;
+
+use crate::api::eth_api::EthApiServer;
+use crate::api::fork_api::ForkApiServer;
+use crate::fork::Fork;
+use crate::servers::eth_rpc::KakarotEthRpc;
+
+/// The RPC module routing the state of the accounts between the dev network and the forked chain.
+/// The accounts written locally are served by the dev network, the others by the forked chain at
+/// the fork block.
+pub struct ForkRpc<P: Provider + Send + Sync> {
+    pub local: KakarotEthRpc<P>,
+    pub fork: Arc<Fork>,
+}
+
+impl<P: Provider + Send + Sync> ForkRpc<P> {
+    pub fn new(local: KakarotEthRpc<P>, fork: Arc<Fork>) -> Self {
+        Self { local, fork }
+    }
+}
+
+/// Returns the address of the contract deployed by `from` with the given nonce.
+fn created_address(from: Address, nonce: u64) -> Address {
+    Address::from(get_contract_address(from.0, nonce).0)
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync + 'static> ForkApiServer for ForkRpc<P> {
+    async fn balance(&self, address: Address, block_id: Option<BlockId>) -> Result<U256> {
+        if self.fork.is_local(&address) {
+            return EthApiServer::balance(&self.local, address, block_id).await;
+        }
+        self.fork.request("eth_getBalance", rpc_params![address, self.fork.remote_block_id(block_id)]).await
+    }
+
+    async fn storage_at(&self, address: Address, index: U256, block_id: Option<BlockId>) -> Result<U256> {
+        if self.fork.is_local(&address) {
+            return EthApiServer::storage_at(&self.local, address, index, block_id).await;
+        }
+        self.fork.request("eth_getStorageAt", rpc_params![address, index, self.fork.remote_block_id(block_id)]).await
+    }
+
+    async fn transaction_count(&self, address: Address, block_id: Option<BlockId>) -> Result<U256> {
+        if self.fork.is_local(&address) {
+            return EthApiServer::transaction_count(&self.local, address, block_id).await;
+        }
+        self.fork.request("eth_getTransactionCount", rpc_params![address, self.fork.remote_block_id(block_id)]).await
+    }
+
+    async fn get_code(&self, address: Address, block_id: Option<BlockId>) -> Result<Bytes> {
+        if self.fork.is_local(&address) {
+            return EthApiServer::get_code(&self.local, address, block_id).await;
+        }
+        self.fork.request("eth_getCode", rpc_params![address, self.fork.remote_block_id(block_id)]).await
+    }
+
+    async fn call(
+        &self,
+        request: CallRequest,
+        block_id: Option<BlockId>,
+        state_override: Option<StateOverride>,
+    ) -> Result<Bytes> {
+        match request.to {
+            Some(to) if !self.fork.is_local(&to) => {
+                let mut params = rpc_params![request, self.fork.remote_block_id(block_id)];
+                if let Some(state_override) = state_override {
+                    params
+                        .insert(state_override)
+                        .map_err(|err| rpc_err(EthRpcErrorCode::InvalidParams as i32, err.to_string()))?;
+                }
+                self.fork.request("eth_call", params).await
+            }
+            _ => EthApiServer::call(&self.local, request, block_id, state_override).await,
+        }
+    }
+
+    async fn send_transaction(&self, request: CallRequest) -> Result<H256> {
+        let created = match (request.from, request.to) {
+            (Some(from), None) => {
+                let nonce = match request.nonce {
+                    Some(nonce) => nonce,
+                    None => EthApiServer::transaction_count(&self.local, from, None).await?,
+                };
+                let nonce = u64::try_from(nonce)
+                    .map_err(|_| rpc_err(EthRpcErrorCode::InvalidParams as i32, "Invalid nonce"))?;
+                Some(created_address(from, nonce))
+            }
+            _ => None,
+        };
+        self.fork.mark_local(request.from.into_iter().chain(request.to).chain(created));
+
+        EthApiServer::send_transaction(&self.local, request).await
+    }
+
+    async fn send_raw_transaction(&self, bytes: Bytes) -> Result<H256> {
+        let transaction = TransactionSigned::decode(&mut bytes.as_ref())
+            .map_err(|err| rpc_err(EthRpcErrorCode::InvalidParams as i32, format!("Invalid transaction: {err}")))?;
+        let from = transaction.recover_signer().ok_or_else(|| {
+            rpc_err(EthRpcErrorCode::InvalidParams as i32, "Invalid transaction: signature ecrecover failed")
+        })?;
+        let to = transaction.to().unwrap_or_else(|| created_address(from, transaction.nonce()));
+        self.fork.mark_local([from, to]);
+
+        EthApiServer::send_raw_transaction(&self.local, bytes).await
+    }
+}
@@ -0,0 +1,28 @@
+use jsonrpsee::core::{async_trait, RpcResult as Result};
+use kakarot_rpc_core::client::signer::EthSigner;
+use reth_primitives::Address;
+
+use crate::api::hardhat_api::HardhatApiServer;
+
+/// The RPC module for the impersonation methods, backed by the signer of the client.
+pub struct HardhatRpc {
+    signer: EthSigner,
+}
+
+impl HardhatRpc {
+    pub fn new(signer: EthSigner) -> Self {
+        Self { signer }
+    }
+}
+
+#[async_trait]
+impl HardhatApiServer for HardhatRpc {
+    async fn impersonate_account(&self, address: Address) -> Result<bool> {
+        self.signer.impersonate(address);
+        Ok(true)
+    }
+
+    async fn stop_impersonating_account(&self, address: Address) -> Result<bool> {
+        Ok(self.signer.stop_impersonating(&address))
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use jsonrpsee::core::{async_trait, RpcResult as Result};
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::client::errors::{rpc_err, EthRpcErrorCode};
+use reth_primitives::U64;
+use starknet::providers::Provider;
+
+use crate::api::kakarot_admin_api::KakarotAdminApiServer;
+use crate::backfill::{BackfillProgress, LogBackfill};
+
+/// The RPC module for the administration methods of the local indexes.
+pub struct KakarotAdminRpc<P: Provider + Send + Sync> {
+    pub kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    backfill: Arc<LogBackfill>,
+}
+
+impl<P: Provider + Send + Sync> KakarotAdminRpc<P> {
+    pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>, backfill: Arc<LogBackfill>) -> Self {
+        Self { kakarot_client, backfill }
+    }
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync + 'static> KakarotAdminApiServer for KakarotAdminRpc<P> {
+    async fn start_log_backfill(&self, from_block: U64, to_block: U64) -> Result<BackfillProgress> {
+        self.backfill
+            .start(self.kakarot_client.clone(), from_block.as_u64(), to_block.as_u64())
+            .map_err(|err| rpc_err(EthRpcErrorCode::InvalidParams as i32, err.to_string()))
+    }
+
+    async fn log_backfill_status(&self) -> Result<Option<BackfillProgress>> {
+        Ok(self.backfill.progress())
+    }
+}
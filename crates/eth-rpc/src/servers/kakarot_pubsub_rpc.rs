@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use jsonrpsee::core::{async_trait, SubscriptionResult};
+use jsonrpsee::PendingSubscriptionSink;
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::client::head_watcher::{HeadEvent, HeadWatcher};
+use kakarot_rpc_core::models::account_activity::AccountActivity;
+use reth_primitives::{Address, BlockId, BlockNumberOrTag};
+use starknet::providers::Provider;
+
+use super::eth_pubsub_rpc::{invalid_params, pipe, SentItems};
+use crate::api::kakarot_pubsub_api::KakarotPubSubApiServer;
+
+/// Maximum number of addresses watched by a single `kakarot_subscribeAccountActivity`
+/// subscription.
+pub const MAX_WATCHED_ADDRESSES: usize = 1_000;
+
+/// The RPC module for the Kakarot specific subscriptions, fed by the head watcher like the
+/// Ethereum subscriptions.
+pub struct KakarotPubSubRpc<P: Provider + Send + Sync + 'static> {
+    pub kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    head_watcher: Arc<HeadWatcher<P>>,
+}
+
+impl<P: Provider + Send + Sync + 'static> KakarotPubSubRpc<P> {
+    pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>, head_watcher: Arc<HeadWatcher<P>>) -> Self {
+        Self { kakarot_client, head_watcher }
+    }
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync + 'static> KakarotPubSubApiServer for KakarotPubSubRpc<P> {
+    async fn subscribe_account_activity(
+        &self,
+        pending: PendingSubscriptionSink,
+        addresses: Vec<Address>,
+    ) -> SubscriptionResult {
+        let mut watched: Vec<Address> = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            if !watched.contains(&address) {
+                watched.push(address);
+            }
+        }
+        if watched.is_empty() {
+            pending.reject(invalid_params("at least one address should be watched")).await;
+            return Ok(());
+        }
+        if watched.len() > MAX_WATCHED_ADDRESSES {
+            let message = format!("at most {MAX_WATCHED_ADDRESSES} addresses can be watched, got {}", watched.len());
+            pending.reject(invalid_params(&message)).await;
+            return Ok(());
+        }
+
+        let sink = pending.accept().await?;
+
+        let heads = self.head_watcher.subscribe_new_heads();
+        let kakarot_client = Arc::clone(&self.kakarot_client);
+        let watched = Arc::new(watched);
+        let sent_activities: Arc<SentItems<AccountActivity>> = Arc::default();
+        tokio::spawn(pipe(sink, heads, move |event: HeadEvent| {
+            let kakarot_client = Arc::clone(&kakarot_client);
+            let watched = Arc::clone(&watched);
+            let sent_activities = Arc::clone(&sent_activities);
+            async move {
+                let block = match event {
+                    HeadEvent::NewHead(block) => block,
+                    HeadEvent::Reorg { from_block } => {
+                        return sent_activities
+                            .revert(from_block)
+                            .into_iter()
+                            .map(|activity| AccountActivity { removed: true, ..activity })
+                            .collect();
+                    }
+                };
+                let block_number = match block.inner.header.number {
+                    Some(block_number) => block_number.saturating_to::<u64>(),
+                    None => return vec![],
+                };
+                let block_id = BlockId::Number(BlockNumberOrTag::Number(block_number));
+                match kakarot_client.block_receipts(block_id).await {
+                    Ok(receipts) => {
+                        let activities = AccountActivity::from_receipts(&watched, &receipts.unwrap_or_default());
+                        sent_activities.record(block_number, activities.clone());
+                        activities
+                    }
+                    Err(err) => {
+                        tracing::warn!("Failed to fetch the receipts of block {block_number}: {err}");
+                        vec![]
+                    }
+                }
+            }
+        }));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use jsonrpsee::core::{async_trait, RpcResult as Result};
+use jsonrpsee::types::error::ErrorObject;
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::client::cache::CacheStats;
+use kakarot_rpc_core::client::errors::{rpc_err, EthApiError, EthRpcErrorCode};
+use kakarot_rpc_core::models::account::{AccountDetails, DeployedAccount};
+use kakarot_rpc_core::models::block_stats::BlockStats;
+use kakarot_rpc_core::models::felt::Felt252Wrapper;
+use kakarot_rpc_core::models::simulation::{SimulationRequest, TransactionSimulation};
+use reth_primitives::{Address, BlockId, BlockNumberOrTag, H256};
+use reth_rpc_types::Transaction as EthTransaction;
+use serde_json::Value;
+use starknet::core::types::{BlockId as StarknetBlockId, BlockTag, FieldElement};
+use starknet::providers::Provider;
+
+use crate::api::kakarot_api::KakarotApiServer;
+use crate::explorer::{ExplorerLinks, StarknetExplorer, WithExplorerLinks};
+
+/// Maximum number of addresses computed by a single `kakarot_computeStarknetAddresses` request.
+pub const MAX_COMPUTED_ADDRESSES: usize = 10_000;
+
+/// The RPC module for the Kakarot specific methods.
+pub struct KakarotRpc<P: Provider + Send + Sync> {
+    pub kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    /// Explorer the Starknet blocks, transactions and contracts of the responses link to.
+    explorer: Option<StarknetExplorer>,
+}
+
+impl<P: Provider + Send + Sync> KakarotRpc<P> {
+    pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>) -> Self {
+        Self { kakarot_client, explorer: None }
+    }
+
+    /// Adds links to the explorer to the responses.
+    pub fn with_explorer(mut self, explorer: StarknetExplorer) -> Self {
+        self.explorer = Some(explorer);
+        self
+    }
+
+    /// Returns the explorer links of a Starknet contract, `None` without explorer.
+    fn contract_links(&self, address: FieldElement) -> Option<ExplorerLinks> {
+        let explorer = self.explorer.as_ref()?;
+        Some(ExplorerLinks { contract: Some(explorer.contract_url(address)), ..Default::default() })
+    }
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync + 'static> KakarotApiServer for KakarotRpc<P> {
+    async fn trace_starknet_transaction(&self, hash: H256) -> Result<Value> {
+        // Resolve the Ethereum hash to the hash of the underlying Starknet invoke transaction, a
+        // hash without mapping is taken as a Starknet hash
+        let hash = self.kakarot_client.starknet_transaction_hash(hash).await?.unwrap_or(hash);
+        let hash: Felt252Wrapper = hash.try_into().map_err(EthApiError::<P::Error>::from)?;
+        let hash: FieldElement = hash.into();
+        match self.kakarot_client.starknet_transaction_trace(hash).await {
+            Ok(trace) => Ok(trace),
+            Err(err) => {
+                let err = ErrorObject::from(err);
+                let Some(explorer) = &self.explorer else {
+                    return Err(err.into());
+                };
+                // Point to the transaction on the explorer, keeping any data of the error
+                let links = ExplorerLinks { transaction: Some(explorer.transaction_url(hash)), ..Default::default() };
+                let data = serde_json::json!({ "explorer": links, "data": err.data() });
+                Err(ErrorObject::owned(err.code(), err.message().to_string(), Some(data)).into())
+            }
+        }
+    }
+
+    async fn get_starknet_transaction_hash(&self, hash: H256) -> Result<Option<H256>> {
+        Ok(self.kakarot_client.starknet_transaction_hash(hash).await?)
+    }
+
+    async fn get_eth_transaction_hash(&self, hash: H256) -> Result<Option<H256>> {
+        Ok(self.kakarot_client.ethereum_transaction_hash(hash).await?)
+    }
+
+    async fn get_transaction_by_starknet_hash(&self, hash: H256) -> Result<Option<EthTransaction>> {
+        Ok(self.kakarot_client.transaction_by_starknet_hash(hash).await?)
+    }
+
+    async fn get_starknet_address(&self, evm_address: Address) -> Result<FieldElement> {
+        let starknet_block_id = StarknetBlockId::Tag(BlockTag::Latest);
+        Ok(self.kakarot_client.compute_starknet_address(evm_address, &starknet_block_id).await?)
+    }
+
+    async fn compute_starknet_addresses(&self, evm_addresses: Vec<Address>) -> Result<Vec<FieldElement>> {
+        if evm_addresses.len() > MAX_COMPUTED_ADDRESSES {
+            return Err(rpc_err(
+                EthRpcErrorCode::InvalidParams as i32,
+                format!("Too many addresses: {}, at most {MAX_COMPUTED_ADDRESSES} per request", evm_addresses.len()),
+            ));
+        }
+        Ok(self.kakarot_client.compute_starknet_addresses(&evm_addresses))
+    }
+
+    async fn get_evm_address(&self, starknet_address: FieldElement) -> Result<Address> {
+        let starknet_block_id = StarknetBlockId::Tag(BlockTag::Latest);
+        Ok(self.kakarot_client.get_evm_address(&starknet_address, &starknet_block_id).await?)
+    }
+
+    async fn get_kakarot_address(&self) -> Result<FieldElement> {
+        Ok(self.kakarot_client.kakarot_address())
+    }
+
+    async fn get_deployed_accounts(&self) -> Result<Vec<WithExplorerLinks<DeployedAccount>>> {
+        let accounts = self.kakarot_client.deployed_accounts().await?;
+        Ok(accounts
+            .into_iter()
+            .map(|account| WithExplorerLinks::new(account, self.contract_links(account.starknet_address)))
+            .collect())
+    }
+
+    async fn deploy_eoa(&self, evm_address: Address) -> Result<Option<H256>> {
+        Ok(self.kakarot_client.deploy_eoa(evm_address).await?)
+    }
+
+    async fn get_account_type(
+        &self,
+        evm_address: Address,
+        block_id: Option<BlockId>,
+    ) -> Result<WithExplorerLinks<AccountDetails>> {
+        let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
+        let details = self.kakarot_client.account_details(evm_address, block_id).await?;
+        Ok(WithExplorerLinks::new(details, self.contract_links(details.starknet_address)))
+    }
+
+    async fn simulate_transaction(
+        &self,
+        request: SimulationRequest,
+        block_id: Option<BlockId>,
+    ) -> Result<TransactionSimulation> {
+        let block_id = block_id.unwrap_or(BlockId::Number(BlockNumberOrTag::Latest));
+        Ok(self.kakarot_client.simulate_ethereum_transaction(request, block_id).await?)
+    }
+
+    async fn cache_stats(&self) -> Result<BTreeMap<String, CacheStats>> {
+        Ok(self.kakarot_client.cache_stats())
+    }
+
+    async fn get_block_stats(&self, block_id: BlockId) -> Result<Option<BlockStats>> {
+        Ok(self.kakarot_client.block_stats(block_id).await?)
+    }
+}
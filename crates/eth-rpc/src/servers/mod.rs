@@ -1,4 +1,15 @@
 pub mod alchemy_rpc;
+pub mod debug_rpc;
+pub mod eth_pubsub_rpc;
 pub mod eth_rpc;
+pub mod evm_rpc;
+pub mod fork_rpc;
+pub mod hardhat_rpc;
+pub mod kakarot_admin_rpc;
+pub mod kakarot_pubsub_rpc;
+pub mod kakarot_rpc;
 pub mod net_rpc;
+pub mod personal_rpc;
+pub mod trace_rpc;
+pub mod txpool_rpc;
 pub mod web3_rpc;
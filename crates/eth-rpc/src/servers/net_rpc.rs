@@ -1,32 +1,54 @@
 use jsonrpsee::core::{async_trait, RpcResult as Result};
 use reth_primitives::U64;
-use reth_rpc_types::PeerCount;
 
 use crate::api::net_api::NetApiServer;
 
 /// The RPC module for the implementing Net api
-#[derive(Default)]
-pub struct NetRpc {}
+pub struct NetRpc {
+    chain_id: u64,
+}
 
 impl NetRpc {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(chain_id: u64) -> Self {
+        Self { chain_id }
     }
 }
 
 #[async_trait]
 impl NetApiServer for NetRpc {
-    /// Get the protocol version of the Kakarot Starknet RPC.
-    fn version(&self) -> Result<U64> {
-        let protocol_version = 1_u64;
-        Ok(protocol_version.into())
+    /// Returns the Kakarot chain id, which is also its network id.
+    fn version(&self) -> Result<String> {
+        Ok(self.chain_id.to_string())
     }
 
-    fn peer_count(&self) -> Result<PeerCount> {
-        todo!()
+    /// The Kakarot RPC isn't part of a peer-to-peer network, it has no peers.
+    fn peer_count(&self) -> Result<U64> {
+        Ok(U64::ZERO)
     }
 
+    /// The Kakarot RPC is listening as long as it serves requests.
     fn listening(&self) -> Result<bool> {
-        todo!()
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kakarot_rpc_core::client::constants::CHAIN_ID;
+
+    use super::*;
+
+    #[test]
+    fn test_net_version_is_decimal_chain_id() {
+        // Given
+        let net_rpc = NetRpc::new(CHAIN_ID);
+
+        // When
+        let version = net_rpc.version().unwrap();
+
+        // Then
+        assert_eq!("1263227476", version);
+        assert_eq!(U64::ZERO, net_rpc.peer_count().unwrap());
+        assert!(net_rpc.listening().unwrap());
     }
 }
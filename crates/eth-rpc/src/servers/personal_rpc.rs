@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use ethers::core::rand::thread_rng;
+use ethers::signers::{LocalWallet, Signer};
+use jsonrpsee::core::{async_trait, RpcResult as Result};
+use jsonrpsee::types::error::ErrorObject;
+use kakarot_rpc_core::client::errors::{rpc_err, EthRpcErrorCode, SignerError};
+use kakarot_rpc_core::client::signer::{sign_personal_message, EthSigner};
+use reth_primitives::{Address, Bytes, H256};
+
+use crate::api::personal_api::PersonalApiServer;
+
+/// Duration for which `personal_unlockAccount` unlocks an account when none is given, as in geth.
+pub const DEFAULT_UNLOCK_DURATION: Duration = Duration::from_secs(300);
+
+/// The RPC module for the `personal` methods, backed by the signer of the dev network.
+pub struct PersonalRpc {
+    signer: EthSigner,
+    keystore_dir: PathBuf,
+    /// Keystore of each account created through `personal_newAccount`.
+    keystores: Mutex<BTreeMap<Address, PathBuf>>,
+}
+
+impl PersonalRpc {
+    pub fn new(signer: EthSigner, keystore_dir: PathBuf) -> Self {
+        Self { signer, keystore_dir, keystores: Mutex::default() }
+    }
+
+    /// Decrypts the keystore of the account with the password.
+    async fn decrypt(&self, address: Address, password: String) -> Result<LocalWallet> {
+        let keystore = self.keystores.lock().expect("keystores poisoned").get(&address).cloned();
+        let keystore = keystore.ok_or_else(|| ErrorObject::from(SignerError::UnknownAccount(address)))?;
+
+        // Decrypting a keystore is CPU bound
+        tokio::task::spawn_blocking(move || LocalWallet::decrypt_keystore(keystore, password))
+            .await
+            .map_err(|err| rpc_err(EthRpcErrorCode::InternalError as i32, err.to_string()))?
+            .map_err(|_| rpc_err(EthRpcErrorCode::InvalidInput as i32, "could not decrypt key with given password"))
+    }
+}
+
+#[async_trait]
+impl PersonalApiServer for PersonalRpc {
+    async fn new_account(&self, password: String) -> Result<Address> {
+        let keystore_dir = self.keystore_dir.clone();
+        let (wallet, name) = tokio::task::spawn_blocking(move || {
+            LocalWallet::new_keystore(&keystore_dir, &mut thread_rng(), password, None)
+        })
+        .await
+        .map_err(|err| rpc_err(EthRpcErrorCode::InternalError as i32, err.to_string()))?
+        .map_err(|err| ErrorObject::from(SignerError::Keystore(err.to_string())))?;
+
+        let address = Address::from(wallet.address().0);
+        self.keystores.lock().expect("keystores poisoned").insert(address, self.keystore_dir.join(name));
+        Ok(address)
+    }
+
+    async fn unlock_account(&self, address: Address, password: String, duration: Option<u64>) -> Result<bool> {
+        let wallet = self.decrypt(address, password).await?;
+        let duration = match duration {
+            None => Some(DEFAULT_UNLOCK_DURATION),
+            Some(0) => None,
+            Some(seconds) => Some(Duration::from_secs(seconds)),
+        };
+        self.signer.unlock(&wallet, duration);
+        Ok(true)
+    }
+
+    async fn sign(&self, message: Bytes, address: Address, password: String) -> Result<Bytes> {
+        // The dev accounts have no keystore, they are always unlocked
+        let has_keystore = self.keystores.lock().expect("keystores poisoned").contains_key(&address);
+        if !has_keystore && self.signer.has_account(&address) {
+            return self.signer.sign_message(address, &message).map_err(ErrorObject::from);
+        }
+
+        let wallet = self.decrypt(address, password).await?;
+        let secret_key = H256::from_slice(&wallet.signer().to_bytes());
+        sign_personal_message(secret_key, &message).map_err(ErrorObject::from)
+    }
+}
@@ -0,0 +1,39 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use jsonrpsee::core::{async_trait, RpcResult as Result};
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::models::trace::{LocalizedFlatTrace, TraceResults, TraceType};
+use reth_primitives::{BlockNumberOrTag, H256};
+use starknet::providers::Provider;
+
+use crate::api::trace_api::TraceApiServer;
+
+/// The RPC module for the trace methods.
+pub struct TraceRpc<P: Provider + Send + Sync> {
+    pub kakarot_client: Arc<dyn KakarotEthApi<P>>,
+}
+
+impl<P: Provider + Send + Sync> TraceRpc<P> {
+    pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>) -> Self {
+        Self { kakarot_client }
+    }
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync + 'static> TraceApiServer for TraceRpc<P> {
+    async fn transaction(&self, hash: H256) -> Result<Option<Vec<LocalizedFlatTrace>>> {
+        let traces = self.kakarot_client.parity_trace_transaction(hash).await?;
+        Ok(traces)
+    }
+
+    async fn block(&self, number: BlockNumberOrTag) -> Result<Vec<LocalizedFlatTrace>> {
+        let traces = self.kakarot_client.parity_trace_block(number).await?;
+        Ok(traces)
+    }
+
+    async fn replay_transaction(&self, hash: H256, trace_types: HashSet<TraceType>) -> Result<TraceResults> {
+        let results = self.kakarot_client.replay_transaction(hash, trace_types).await?;
+        Ok(results)
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use jsonrpsee::core::{async_trait, RpcResult as Result};
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use kakarot_rpc_core::models::txpool::{TxpoolContent, TxpoolInspect, TxpoolStatus};
+use starknet::providers::Provider;
+
+use crate::api::txpool_api::TxPoolApiServer;
+
+/// The RPC module for the transaction pool, backed by the Starknet pending block.
+pub struct TxPoolRpc<P: Provider + Send + Sync> {
+    pub kakarot_client: Arc<dyn KakarotEthApi<P>>,
+}
+
+impl<P: Provider + Send + Sync> TxPoolRpc<P> {
+    pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>) -> Self {
+        Self { kakarot_client }
+    }
+
+    async fn content(&self) -> Result<TxpoolContent> {
+        let transactions = self.kakarot_client.pending_transactions().await?;
+        Ok(TxpoolContent::from_pending(transactions))
+    }
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync + 'static> TxPoolApiServer for TxPoolRpc<P> {
+    async fn txpool_status(&self) -> Result<TxpoolStatus> {
+        Ok(self.content().await?.status())
+    }
+
+    async fn txpool_inspect(&self) -> Result<TxpoolInspect> {
+        Ok(self.content().await?.inspect())
+    }
+
+    async fn txpool_content(&self) -> Result<TxpoolContent> {
+        self.content().await
+    }
+}
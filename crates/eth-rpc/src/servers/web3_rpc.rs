@@ -25,3 +25,23 @@ impl Web3ApiServer for Web3Rpc {
         Ok(hash)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_sha3() {
+        // Given
+        let web3_rpc = Web3Rpc::new();
+
+        // When
+        let hash = web3_rpc.sha3(Bytes::from_str("0x68656c6c6f20776f726c64").unwrap()).unwrap();
+
+        // Then
+        let expected = H256::from_str("0x47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad").unwrap();
+        assert_eq!(expected, hash);
+    }
+}
@@ -0,0 +1,160 @@
+//! Graceful shutdown of the RPC, so that the rolling deploys don't drop the in-flight requests.
+//!
+//! On SIGTERM or SIGINT, the server stops accepting new connections and the in-flight calls are
+//! answered, up to the drain timeout, after which the remaining connections are dropped. The
+//! background tasks are then stopped, and the index store and the storage of the client flushed to
+//! disk. The caches of the client only live in memory and don't need to be flushed.
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use jsonrpsee::server::ServerHandle;
+use kakarot_rpc_core::storage::Storage;
+use kakarot_rpc_indexer::store::IndexStore;
+use tokio::task::JoinHandle;
+
+/// Default time given to the in-flight calls to complete.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Configuration of the graceful shutdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownConfig {
+    /// Time given to the in-flight calls to complete once a shutdown signal is received.
+    pub drain_timeout: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self { drain_timeout: DEFAULT_DRAIN_TIMEOUT }
+    }
+}
+
+impl ShutdownConfig {
+    /// Create a new `ShutdownConfig` from the `KAKAROT_SHUTDOWN_DRAIN_TIMEOUT_MS` environment
+    /// variable. Defaults to [`DEFAULT_DRAIN_TIMEOUT`].
+    pub fn from_env() -> Result<Self> {
+        let drain_timeout = match std::env::var("KAKAROT_SHUTDOWN_DRAIN_TIMEOUT_MS") {
+            Ok(timeout) => Duration::from_millis(timeout.parse::<u64>().map_err(|_| {
+                eyre!("KAKAROT_SHUTDOWN_DRAIN_TIMEOUT_MS should be a number of milliseconds, got {timeout}")
+            })?),
+            Err(_) => DEFAULT_DRAIN_TIMEOUT,
+        };
+        Ok(Self { drain_timeout })
+    }
+}
+
+/// Background tasks of the RPC, stopped once the server is drained.
+#[derive(Default)]
+pub struct BackgroundTasks {
+    indexer: Option<JoinHandle<()>>,
+    index_store: Option<Arc<dyn IndexStore>>,
+    storage: Option<Arc<dyn Storage>>,
+}
+
+impl BackgroundTasks {
+    /// Adds the block indexer and its store, flushed once the indexer is stopped.
+    pub fn with_indexer(mut self, indexer: JoinHandle<()>, index_store: Arc<dyn IndexStore>) -> Self {
+        self.indexer = Some(indexer);
+        self.index_store = Some(index_store);
+        self
+    }
+
+    /// Adds the storage of the client, flushed once the tasks are stopped.
+    pub fn with_storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Stops the tasks and flushes the index store and the storage. A failed flush is reported in
+    /// the logs, the flushed blocks are indexed again on the next start.
+    pub async fn stop(self) {
+        if let Some(indexer) = self.indexer {
+            indexer.abort();
+            // The aborted task always returns a cancellation error
+            let _ = indexer.await;
+        }
+        if let Some(index_store) = self.index_store {
+            if let Err(err) = index_store.flush().await {
+                tracing::error!("Failed to flush the index store: {err}");
+            }
+        }
+        if let Some(storage) = self.storage {
+            if let Err(err) = storage.flush() {
+                tracing::error!("Failed to flush the storage: {err}");
+            }
+        }
+    }
+}
+
+/// Waits for SIGTERM or SIGINT and returns the name of the received signal.
+pub async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = terminate.recv() => "SIGTERM",
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+            },
+            Err(err) => {
+                tracing::warn!("Failed to listen to SIGTERM: {err}");
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+/// Serves until the server stops or a shutdown signal is received, then drains the server and
+/// stops the background tasks.
+pub async fn serve_until_shutdown(server_handle: ServerHandle, config: ShutdownConfig, background: BackgroundTasks) {
+    tokio::select! {
+        _ = server_handle.clone().stopped() => {}
+        signal = shutdown_signal() => {
+            tracing::info!("Received {signal}, draining the in-flight requests");
+            if !drain(server_handle, config.drain_timeout).await {
+                tracing::warn!(
+                    "The in-flight requests didn't complete within {:?}, dropping them",
+                    config.drain_timeout
+                );
+            }
+        }
+    }
+    background.stop().await;
+}
+
+/// Stops accepting new connections and waits for the in-flight calls to complete. Returns false
+/// if they didn't complete within the timeout.
+pub async fn drain(server_handle: ServerHandle, timeout: Duration) -> bool {
+    // The server may already be stopped, in which case it is drained
+    let _ = server_handle.stop();
+    tokio::time::timeout(timeout, server_handle.stopped()).await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpsee::server::ServerBuilder;
+    use jsonrpsee::RpcModule;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_stops_the_server() {
+        // Given
+        let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+        let server_handle = server.start(RpcModule::new(())).unwrap();
+
+        // When
+        let drained = drain(server_handle.clone(), Duration::from_secs(5)).await;
+
+        // Then
+        assert!(drained);
+        assert!(server_handle.is_stopped());
+    }
+}
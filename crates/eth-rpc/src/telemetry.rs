@@ -0,0 +1,187 @@
+//! Tracing of the requests, from the JSON-RPC method down to the Starknet round trips it makes.
+//!
+//! Each HTTP request is served in a `jsonrpc` span carrying the called methods, the methods of
+//! the RPC and of the client open child spans with their block ids, and [`TracingTransport`]
+//! opens a `starknet_request` span for each request to the Starknet node. The spans are exported
+//! over OTLP when `OTEL_EXPORTER_OTLP_ENDPOINT` is set, e.g. to Jaeger or Tempo, so that the
+//! round trips dominating a slow request show up in its trace.
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+use hyper::{Body, Request, Response};
+use opentelemetry::sdk::{trace, Resource};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use starknet::providers::jsonrpc::{JsonRpcMethod, JsonRpcResponse, JsonRpcTransport};
+use tower::{Layer, Service};
+use tracing::Instrument;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::middleware::JsonRpcBody;
+
+/// Default name of the service in the exported traces.
+pub const DEFAULT_SERVICE_NAME: &str = "kakarot-rpc";
+
+/// Configuration of the OTLP exporter of the traces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryConfig {
+    /// Endpoint of the OTLP collector, over gRPC.
+    pub endpoint: String,
+    /// Name of the service in the exported traces.
+    pub service_name: String,
+}
+
+impl TelemetryConfig {
+    /// Create a new `TelemetryConfig` from the standard `OTEL_EXPORTER_OTLP_ENDPOINT` and
+    /// `OTEL_SERVICE_NAME` environment variables. Returns `None` if `OTEL_EXPORTER_OTLP_ENDPOINT`
+    /// is not set, which disables the export.
+    pub fn from_env() -> Option<Self> {
+        let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+        let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| DEFAULT_SERVICE_NAME.to_string());
+        Some(Self { endpoint, service_name })
+    }
+}
+
+/// Installs the global subscriber, logging the events filtered by `RUST_LOG` and exporting the
+/// spans over OTLP when configured. Must be called from within the Tokio runtime, which drives the
+/// export.
+pub fn init_tracing(config: Option<TelemetryConfig>) -> Result<()> {
+    let filter = EnvFilter::try_from_default_env()?;
+    let fmt = tracing_subscriber::fmt::layer();
+
+    let Some(config) = config else {
+        return tracing_subscriber::registry().with(filter).with(fmt).try_init().map_err(|err| eyre!(err));
+    };
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(&config.endpoint))
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new("service.name", config.service_name)])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+        .map_err(|err| eyre!("Failed to install the OTLP exporter for {}: {err}", config.endpoint))?;
+    let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
+
+    tracing_subscriber::registry().with(filter).with(fmt).with(telemetry).try_init().map_err(|err| eyre!(err))
+}
+
+/// Flushes the spans not exported yet, before the process exits.
+pub fn shutdown_tracing() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Returns the JSON-RPC name of a Starknet method.
+pub(crate) fn starknet_method_name(method: JsonRpcMethod) -> String {
+    // The methods serialize to their JSON-RPC name
+    match serde_json::to_value(method) {
+        Ok(Value::String(method)) => method,
+        _ => format!("{method:?}"),
+    }
+}
+
+/// JSON-RPC transport opening a span for each request of the inner transport to the Starknet
+/// node.
+#[derive(Debug)]
+pub struct TracingTransport<T> {
+    inner: T,
+}
+
+impl<T> TracingTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcTransport + Send + Sync> JsonRpcTransport for TracingTransport<T> {
+    type Error = T::Error;
+
+    async fn send_request<P, R>(&self, method: JsonRpcMethod, params: P) -> Result<JsonRpcResponse<R>, Self::Error>
+    where
+        P: Serialize + Send,
+        R: DeserializeOwned,
+    {
+        let span = tracing::info_span!(
+            "starknet_request",
+            otel.kind = "client",
+            rpc.system = "jsonrpc",
+            rpc.method = %starknet_method_name(method),
+            otel.status_code = tracing::field::Empty,
+        );
+        let response = self.inner.send_request(method, params).instrument(span.clone()).await;
+        if response.is_err() {
+            span.record("otel.status_code", "ERROR");
+        }
+        response
+    }
+}
+
+/// Tower layer serving each HTTP request in a `jsonrpc` span carrying the called methods.
+#[derive(Clone, Default)]
+pub struct TracingLayer;
+
+impl<S> Layer<S> for TracingLayer {
+    type Service = TracingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TracingService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct TracingService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for TracingService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let (request, body) = JsonRpcBody::read(request).await?;
+            let span = tracing::info_span!(
+                "jsonrpc",
+                otel.kind = "server",
+                rpc.system = "jsonrpc",
+                rpc.method = %body.methods.join(","),
+                batch_size = body.methods.len(),
+            );
+            inner.call(request).instrument(span).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_starknet_method_name() {
+        assert_eq!("starknet_getBlockWithTxs", starknet_method_name(JsonRpcMethod::GetBlockWithTxs));
+        assert_eq!("starknet_blockNumber", starknet_method_name(JsonRpcMethod::BlockNumber));
+    }
+}
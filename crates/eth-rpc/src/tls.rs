@@ -0,0 +1,163 @@
+//! Native TLS of the RPC server, so that simple deployments don't need a reverse proxy in front of
+//! it.
+//!
+//! The server doesn't accept TLS connections itself: it is bound on the loopback interface, and
+//! the TLS connections accepted on the configured address are decrypted and forwarded to it. Both
+//! `https://` and `wss://` are served this way. The client address is therefore not seen by the
+//! server, the `X-Forwarded-For` header being the only source of it in the audit log.
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use eyre::{eyre, Result};
+use jsonrpsee::server::ServerHandle;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::{self, Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// Certificate chain and private key of the server, as PEM files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// Error of the TLS configuration.
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("failed to read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("no certificate found in {0}")]
+    MissingCertificate(PathBuf),
+    #[error("no private key found in {0}")]
+    MissingPrivateKey(PathBuf),
+    #[error(transparent)]
+    Rustls(#[from] rustls::Error),
+}
+
+impl TlsConfig {
+    /// Create a new `TlsConfig` from the `KAKAROT_TLS_CERT` and `KAKAROT_TLS_KEY` environment
+    /// variables. Returns `None` if neither is set, which serves plain HTTP and WebSocket.
+    pub fn from_env() -> Result<Option<Self>> {
+        match (std::env::var("KAKAROT_TLS_CERT"), std::env::var("KAKAROT_TLS_KEY")) {
+            (Ok(cert), Ok(key)) => Ok(Some(Self { cert: PathBuf::from(cert), key: PathBuf::from(key) })),
+            (Err(_), Err(_)) => Ok(None),
+            _ => Err(eyre!("KAKAROT_TLS_CERT and KAKAROT_TLS_KEY should be set together")),
+        }
+    }
+
+    /// Loads the certificate chain and the private key.
+    pub fn acceptor(&self) -> Result<TlsAcceptor, TlsError> {
+        let certs = load_certs(&self.cert)?;
+        let key = load_private_key(&self.key)?;
+        let config = ServerConfig::builder().with_safe_defaults().with_no_client_auth().with_single_cert(certs, key)?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, TlsError> {
+    File::open(path).map(BufReader::new).map_err(|err| TlsError::Io(path.to_path_buf(), err))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<Certificate>, TlsError> {
+    let certs = rustls_pemfile::certs(&mut open(path)?).map_err(|err| TlsError::Io(path.to_path_buf(), err))?;
+    if certs.is_empty() {
+        return Err(TlsError::MissingCertificate(path.to_path_buf()));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+/// Loads the first PKCS#8, PKCS#1 or SEC1 private key of the file.
+fn load_private_key(path: &Path) -> Result<PrivateKey, TlsError> {
+    let items = rustls_pemfile::read_all(&mut open(path)?).map_err(|err| TlsError::Io(path.to_path_buf(), err))?;
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| TlsError::MissingPrivateKey(path.to_path_buf()))
+}
+
+/// Accepts the TLS connections on the listener and forwards them to the server at `upstream`,
+/// until the server is stopped.
+pub fn spawn_tls_proxy(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    upstream: SocketAddr,
+    server_handle: ServerHandle,
+) {
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = accept_connections(listener, acceptor, upstream) => {}
+            _ = server_handle.stopped() => {}
+        }
+    });
+}
+
+async fn accept_connections(listener: TcpListener, acceptor: TlsAcceptor, upstream: SocketAddr) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                tracing::warn!("Failed to accept a TLS connection: {err}");
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        tokio::spawn(async move {
+            let mut stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::debug!("TLS handshake with {peer} failed: {err}");
+                    return;
+                }
+            };
+            let mut server = match TcpStream::connect(upstream).await {
+                Ok(server) => server,
+                Err(err) => {
+                    tracing::error!("Failed to forward a TLS connection to the server: {err}");
+                    return;
+                }
+            };
+            // The connection is closed by either side, e.g. at the end of a WebSocket session
+            let _ = tokio::io::copy_bidirectional(&mut stream, &mut server).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tls_config_missing_files() {
+        // Given
+        let config = TlsConfig { cert: PathBuf::from("/nonexistent/cert.pem"), key: PathBuf::from("key.pem") };
+
+        // When
+        let err = config.acceptor().unwrap_err();
+
+        // Then
+        assert!(matches!(err, TlsError::Io(path, _) if path == config.cert));
+    }
+
+    #[test]
+    fn test_tls_config_without_certificate() {
+        // Given
+        let path = std::env::temp_dir().join(format!("kakarot-rpc-empty-cert-{}.pem", std::process::id()));
+        std::fs::write(&path, "not a certificate").unwrap();
+        let config = TlsConfig { cert: path.clone(), key: path.clone() };
+
+        // When
+        let err = config.acceptor().unwrap_err();
+
+        // Then
+        assert!(matches!(err, TlsError::MissingCertificate(_)));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,338 @@
+//! Webhooks notifying the integrations, e.g. merchants and exchanges, of the lifecycle of the
+//! transactions relayed through the RPC, so that they don't have to poll their receipts.
+//!
+//! A notification is POSTed to each configured URL when a transaction is relayed, then when it is
+//! accepted on L2, accepted on L1 or rejected, the status of the relayed transactions being polled
+//! in the background. A delivery failing or answered with a non-2xx status is retried with an
+//! exponential backoff, and dropped after the configured number of retries.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use eyre::{eyre, Result};
+use kakarot_rpc_core::client::api::{KakarotEthApi, KakarotStarknetApi};
+use kakarot_rpc_core::models::felt::Felt252Wrapper;
+use reth_primitives::{H256, U64};
+use serde::Serialize;
+use starknet::core::types::{
+    FieldElement, MaybePendingTransactionReceipt, StarknetError, TransactionReceipt, TransactionStatus,
+};
+use starknet::providers::{Provider, ProviderError};
+use tokio::task::JoinHandle;
+use url::Url;
+
+/// Default number of retries of a failed delivery.
+pub const DEFAULT_WEBHOOK_MAX_RETRIES: u32 = 5;
+
+/// Interval between two polls of the status of the relayed transactions.
+pub const WEBHOOK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Duration after which a transaction which isn't accepted on L1 nor rejected stops being tracked.
+pub const MAX_TRACKING_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum number of transactions tracked at once, the transactions relayed past it only get the
+/// `relayed` notification.
+pub const MAX_TRACKED_TRANSACTIONS: usize = 10_000;
+
+/// Delay before the first retry of a failed delivery, doubled on each retry.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Configuration of the webhooks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookConfig {
+    /// URLs the notifications are POSTed to.
+    pub urls: Vec<Url>,
+    /// Number of retries of a failed delivery.
+    pub max_retries: u32,
+}
+
+impl WebhookConfig {
+    /// Create a new `WebhookConfig` from the `KAKAROT_WEBHOOK_URLS` and
+    /// `KAKAROT_WEBHOOK_MAX_RETRIES` environment variables. Returns `None` when no URL is set,
+    /// which disables the webhooks.
+    pub fn from_env() -> Result<Option<Self>> {
+        let urls = match std::env::var("KAKAROT_WEBHOOK_URLS") {
+            Ok(urls) => parse_webhook_urls(&urls)?,
+            Err(_) => return Ok(None),
+        };
+        if urls.is_empty() {
+            return Ok(None);
+        }
+        let max_retries = match std::env::var("KAKAROT_WEBHOOK_MAX_RETRIES") {
+            Ok(max_retries) => max_retries
+                .parse::<u32>()
+                .map_err(|_| eyre!("KAKAROT_WEBHOOK_MAX_RETRIES should be a positive integer, got {max_retries}"))?,
+            Err(_) => DEFAULT_WEBHOOK_MAX_RETRIES,
+        };
+        Ok(Some(Self { urls, max_retries }))
+    }
+}
+
+/// Parses the comma separated URLs of the webhooks.
+pub fn parse_webhook_urls(urls: &str) -> Result<Vec<Url>> {
+    urls.split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(|url| Url::parse(url).map_err(|err| eyre!("Webhook URL should be a URL, got {url}: {err}")))
+        .collect()
+}
+
+/// Event of the lifecycle of a relayed transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionEvent {
+    Relayed,
+    AcceptedOnL2,
+    AcceptedOnL1,
+    Rejected,
+}
+
+impl TransactionEvent {
+    /// Returns true if no event follows this one.
+    fn is_final(self) -> bool {
+        matches!(self, Self::AcceptedOnL1 | Self::Rejected)
+    }
+}
+
+/// Returns the events to notify when a transaction whose last notified event is `last` reaches
+/// the Starknet status, in order. A transaction accepted on L1 before being seen accepted on L2
+/// gets both notifications.
+pub fn status_events(last: TransactionEvent, status: TransactionStatus) -> Vec<TransactionEvent> {
+    let events: &[TransactionEvent] = match status {
+        TransactionStatus::Pending => &[],
+        TransactionStatus::AcceptedOnL2 => &[TransactionEvent::AcceptedOnL2],
+        TransactionStatus::AcceptedOnL1 => &[TransactionEvent::AcceptedOnL2, TransactionEvent::AcceptedOnL1],
+        TransactionStatus::Rejected => &[TransactionEvent::Rejected],
+    };
+    events.iter().copied().filter(|event| *event > last && !last.is_final()).collect()
+}
+
+/// Body of a webhook notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookNotification {
+    pub event: TransactionEvent,
+    pub transaction_hash: H256,
+    /// Block of the transaction, once accepted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<U64>,
+    /// Unix timestamp of the notification, in seconds.
+    pub timestamp: u64,
+}
+
+impl WebhookNotification {
+    fn new(event: TransactionEvent, transaction_hash: H256, block_number: Option<u64>) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+        Self { event, transaction_hash, block_number: block_number.map(U64::from), timestamp }
+    }
+}
+
+/// Relayed transaction whose status is polled.
+#[derive(Debug, Clone, Copy)]
+struct TrackedTransaction {
+    last_event: TransactionEvent,
+    relayed_at: Instant,
+}
+
+/// Notifier of the webhooks, tracking the relayed transactions until they are final.
+pub struct Webhooks {
+    config: WebhookConfig,
+    client: reqwest::Client,
+    tracked: Mutex<HashMap<H256, TrackedTransaction>>,
+}
+
+impl Webhooks {
+    pub fn new(config: WebhookConfig) -> Self {
+        Self { config, client: reqwest::Client::new(), tracked: Mutex::default() }
+    }
+
+    /// Notifies that the transaction was relayed, and tracks it until it is final.
+    pub fn relayed(&self, transaction_hash: H256) {
+        {
+            let mut tracked = self.tracked.lock().expect("tracked transactions poisoned");
+            if tracked.len() < MAX_TRACKED_TRANSACTIONS {
+                let transaction =
+                    TrackedTransaction { last_event: TransactionEvent::Relayed, relayed_at: Instant::now() };
+                tracked.insert(transaction_hash, transaction);
+            } else {
+                tracing::warn!("Too many tracked transactions, {transaction_hash:#x} isn't tracked");
+            }
+        }
+        self.notify(WebhookNotification::new(TransactionEvent::Relayed, transaction_hash, None));
+    }
+
+    /// Returns the number of the tracked transactions.
+    pub fn tracked_transactions(&self) -> usize {
+        self.tracked.lock().expect("tracked transactions poisoned").len()
+    }
+
+    /// Starts polling the status of the tracked transactions in the background.
+    pub fn start<P: Provider + Send + Sync + 'static>(
+        self: &Arc<Self>,
+        kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    ) -> JoinHandle<()> {
+        let webhooks = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(WEBHOOK_POLL_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                webhooks.poll(kakarot_client.as_ref()).await;
+            }
+        })
+    }
+
+    /// Polls the status of the tracked transactions, notifying their new events and forgetting the
+    /// final ones.
+    async fn poll<P: Provider + Send + Sync + 'static>(&self, kakarot_client: &dyn KakarotEthApi<P>) {
+        let tracked: Vec<(H256, TrackedTransaction)> = {
+            let mut tracked = self.tracked.lock().expect("tracked transactions poisoned");
+            tracked.retain(|hash, transaction| {
+                let expired = transaction.relayed_at.elapsed() > MAX_TRACKING_DURATION;
+                if expired {
+                    tracing::debug!("Transaction {hash:#x} isn't tracked anymore, it isn't final after a day");
+                }
+                !expired
+            });
+            tracked.iter().map(|(hash, transaction)| (*hash, *transaction)).collect()
+        };
+
+        let provider = kakarot_client.starknet_provider();
+        for (hash, transaction) in tracked {
+            let (status, block_number) = match transaction_status(provider.as_ref(), hash).await {
+                Ok(Some(status)) => status,
+                Ok(None) => continue,
+                Err(err) => {
+                    tracing::warn!("Failed to fetch the status of the transaction {hash:#x}: {err}");
+                    continue;
+                }
+            };
+
+            let events = status_events(transaction.last_event, status);
+            let Some(last_event) = events.last().copied() else {
+                continue;
+            };
+            {
+                let mut tracked = self.tracked.lock().expect("tracked transactions poisoned");
+                if last_event.is_final() {
+                    tracked.remove(&hash);
+                } else if let Some(transaction) = tracked.get_mut(&hash) {
+                    transaction.last_event = last_event;
+                }
+            }
+            for event in events {
+                self.notify(WebhookNotification::new(event, hash, block_number));
+            }
+        }
+    }
+
+    /// Delivers the notification to the configured URLs in the background.
+    fn notify(&self, notification: WebhookNotification) {
+        for url in &self.config.urls {
+            tokio::spawn(deliver(self.client.clone(), url.clone(), notification.clone(), self.config.max_retries));
+        }
+    }
+}
+
+/// Returns the status of the Starknet transaction and its block number, `None` if the transaction
+/// isn't known by the Starknet node yet.
+async fn transaction_status<P: Provider + Send + Sync>(
+    provider: &P,
+    hash: H256,
+) -> Result<Option<(TransactionStatus, Option<u64>)>, ProviderError<P::Error>> {
+    // Kakarot transaction hashes are the hashes of the Starknet transactions
+    let hash: FieldElement = match Felt252Wrapper::try_from(hash) {
+        Ok(hash) => hash.into(),
+        Err(_) => return Ok(None),
+    };
+    match provider.get_transaction_receipt(hash).await {
+        Ok(MaybePendingTransactionReceipt::Receipt(TransactionReceipt::Invoke(receipt))) => {
+            Ok(Some((receipt.status, Some(receipt.block_number))))
+        }
+        Ok(MaybePendingTransactionReceipt::Receipt(_)) => Ok(None),
+        Ok(MaybePendingTransactionReceipt::PendingReceipt(_)) => Ok(Some((TransactionStatus::Pending, None))),
+        Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// POSTs the notification to the URL, retrying with an exponential backoff.
+async fn deliver(client: reqwest::Client, url: Url, notification: WebhookNotification, max_retries: u32) {
+    let mut delay = INITIAL_RETRY_DELAY;
+    for attempt in 0..=max_retries {
+        let result = client.post(url.clone()).json(&notification).send().await;
+        let error = match result {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => format!("status {}", response.status()),
+            Err(err) => err.to_string(),
+        };
+        if attempt == max_retries {
+            tracing::warn!(
+                "Dropped the {:?} webhook of {:#x} to {url} after {} attempts: {error}",
+                notification.event,
+                notification.transaction_hash,
+                max_retries + 1
+            );
+            return;
+        }
+        tracing::debug!("Webhook delivery to {url} failed, retrying in {delay:?}: {error}");
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_events() {
+        use TransactionEvent::*;
+
+        assert_eq!(Vec::<TransactionEvent>::new(), status_events(Relayed, TransactionStatus::Pending));
+        assert_eq!(vec![AcceptedOnL2], status_events(Relayed, TransactionStatus::AcceptedOnL2));
+        assert_eq!(Vec::<TransactionEvent>::new(), status_events(AcceptedOnL2, TransactionStatus::AcceptedOnL2));
+        // A transaction accepted on L1 between two polls gets both notifications
+        assert_eq!(vec![AcceptedOnL2, AcceptedOnL1], status_events(Relayed, TransactionStatus::AcceptedOnL1));
+        assert_eq!(vec![AcceptedOnL1], status_events(AcceptedOnL2, TransactionStatus::AcceptedOnL1));
+        assert_eq!(vec![Rejected], status_events(Relayed, TransactionStatus::Rejected));
+        assert_eq!(Vec::<TransactionEvent>::new(), status_events(Rejected, TransactionStatus::AcceptedOnL2));
+    }
+
+    #[test]
+    fn test_webhook_notification_serialization() {
+        // Given
+        let notification = WebhookNotification {
+            event: TransactionEvent::AcceptedOnL2,
+            transaction_hash: H256::from_low_u64_be(0xabc),
+            block_number: Some(U64::from(42)),
+            timestamp: 1_700_000_000,
+        };
+
+        // When
+        let value = serde_json::to_value(notification).unwrap();
+
+        // Then
+        assert_eq!(serde_json::json!("acceptedOnL2"), value["event"]);
+        assert_eq!(serde_json::json!("0x2a"), value["blockNumber"]);
+        assert_eq!(serde_json::json!(1_700_000_000), value["timestamp"]);
+    }
+
+    #[test]
+    fn test_webhook_config_from_env() {
+        std::env::remove_var("KAKAROT_WEBHOOK_URLS");
+        assert_eq!(None, WebhookConfig::from_env().unwrap());
+
+        std::env::set_var("KAKAROT_WEBHOOK_URLS", "https://example.com/hook, http://localhost:8080");
+        std::env::set_var("KAKAROT_WEBHOOK_MAX_RETRIES", "2");
+        let config = WebhookConfig::from_env().unwrap().unwrap();
+        assert_eq!(2, config.urls.len());
+        assert_eq!(2, config.max_retries);
+
+        std::env::set_var("KAKAROT_WEBHOOK_URLS", "not a url");
+        assert!(WebhookConfig::from_env().is_err());
+
+        std::env::remove_var("KAKAROT_WEBHOOK_URLS");
+        std::env::remove_var("KAKAROT_WEBHOOK_MAX_RETRIES");
+    }
+}
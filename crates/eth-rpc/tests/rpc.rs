@@ -7,8 +7,9 @@ mod tests {
 
     use kakarot_rpc::api::eth_api::EthApiServer;
     use kakarot_rpc_core::mock::assert_helpers::{assert_block, assert_block_header, assert_transaction};
-    use reth_primitives::{BlockNumberOrTag, H160, H256, U256, U64};
-    use reth_rpc_types::Index;
+    use kakarot_rpc_core::models::block::logs_bloom;
+    use reth_primitives::{BlockId, BlockNumberOrTag, Bytes, H160, H256, H64, U256, U64};
+    use reth_rpc_types::{Filter, Index};
     use serde_json::json;
     use starknet::core::types::{FieldElement, Transaction as StarknetTransaction};
     use starknet::macros::felt;
@@ -903,6 +904,26 @@ mod tests {
         assert_block_header(&block, starknet_res.to_string(), false);
     }
 
+    #[tokio::test]
+    async fn test_uncle_and_proof_of_work_methods_are_empty() {
+        let kakarot_rpc = setup_mock_eth_rpc().await;
+        let hash = H256::from_low_u64_be(1);
+
+        // Starknet has no uncles
+        assert_eq!(U256::ZERO, kakarot_rpc.block_uncles_count_by_hash(hash).await.unwrap());
+        assert_eq!(U256::ZERO, kakarot_rpc.block_uncles_count_by_number(BlockNumberOrTag::Latest).await.unwrap());
+        assert!(kakarot_rpc.uncle_by_block_hash_and_index(hash, Index::default()).await.unwrap().is_none());
+        let uncle = kakarot_rpc.uncle_by_block_number_and_index(BlockNumberOrTag::Latest, Index::default()).await;
+        assert!(uncle.unwrap().is_none());
+
+        // The blocks are produced by the Starknet sequencer, without proof of work
+        assert!(!kakarot_rpc.is_mining().await.unwrap());
+        assert_eq!(U256::ZERO, kakarot_rpc.hashrate().await.unwrap());
+        assert!(!kakarot_rpc.submit_hashrate(U256::from(1), hash).await.unwrap());
+        assert!(!kakarot_rpc.submit_work(H64::zero(), hash, hash).await.unwrap());
+        assert!(kakarot_rpc.get_work().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_block_transaction_count_by_hash_is_ok() {
         let kakarot_rpc = setup_mock_eth_rpc().await;
@@ -949,21 +970,54 @@ mod tests {
         assert_eq!(transaction_receipt.status_code, Some(U64::from(1)));
 
         assert_eq!(transaction_receipt.from, H160::from_str("0x54b288676b749def5fc10eb17244fe2c87375de1").unwrap());
+        assert_eq!(transaction_receipt.logs_bloom, logs_bloom(&transaction_receipt.logs));
+
+        // The only transaction of its block
+        assert_eq!(transaction_receipt.transaction_index, Some(U256::ZERO));
+        assert_eq!(Some(transaction_receipt.cumulative_gas_used), transaction_receipt.gas_used);
 
         // TODO
         // assert_eq!(transaction_receipt.logs, None);
         // assert_eq!(transaction_receipt.contract_address, Some(U64::from(1)));
 
-        // assert_eq!(transaction_receipt.transaction_index, None);
         // assert_eq!(transaction_receipt.to, None);
-        // assert_eq!(transaction_receipt.cumulative_gas_used, U256::from(1000000));
-        // assert_eq!(transaction_receipt.gas_used, None);
-        // assert_eq!(transaction_receipt.logs_bloom, Bloom::default());
         // assert_eq!(transaction_receipt.state_root, None);
-        // assert_eq!(transaction_receipt.effective_gas_price, U128::from(1000000));
         // assert_eq!(transaction_receipt.transaction_type, U256::from(0));
     }
 
+    #[tokio::test]
+    async fn test_block_receipts_is_ok() {
+        let kakarot_rpc = setup_mock_eth_rpc().await;
+        let block_hash = H256::from_low_u64_be(0x0d);
+        let hash = H256::from_str("0x03204b4c0e379c3a5ccb80d08661d5a538e95e2960581c9faf7ebcf8ff5a7d3c").unwrap();
+
+        let receipts = kakarot_rpc.block_receipts(BlockId::Hash(block_hash.into())).await.unwrap().unwrap();
+
+        let transaction_receipt = kakarot_rpc.transaction_receipt(hash).await.unwrap().unwrap();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].transaction_hash, transaction_receipt.transaction_hash);
+        assert_eq!(receipts[0].block_hash, Some(block_hash));
+        assert_eq!(receipts[0].cumulative_gas_used, transaction_receipt.cumulative_gas_used);
+        assert_eq!(receipts[0].logs.len(), transaction_receipt.logs.len());
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_pending_is_ok() {
+        let kakarot_rpc = setup_mock_eth_rpc().await;
+        let address = H160::from_str("0x54b288676b749def5fc10eb17244fe2c87375de1").unwrap();
+        let filter =
+            Filter::new().address(address).from_block(BlockNumberOrTag::Pending).to_block(BlockNumberOrTag::Pending);
+
+        let logs = kakarot_rpc.get_logs(filter.clone()).await.unwrap();
+        let latest_logs = kakarot_rpc.get_logs(filter.to_block(BlockNumberOrTag::Latest)).await.unwrap();
+
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].address, address);
+        assert_eq!(logs[0].data, Bytes::from(vec![0xca, 0xfe]));
+        assert_eq!(logs[0].block_number, Some(U256::from(19641)));
+        assert!(latest_logs.is_empty());
+    }
+
     #[tokio::test]
     async fn test_transaction_by_block_number_and_index_is_ok() {
         let kakarot_rpc = setup_mock_eth_rpc().await;
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use kakarot_rpc_core::client::errors::ConfigError;
+
+use crate::store::{IndexStore, MemoryStore, SledStore, StoreError};
+
+/// Default interval between two polls of the chain tip.
+pub const DEFAULT_INDEXER_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Store of the indexed blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreConfig {
+    /// Keeps the blocks in memory, lost on restart.
+    Memory,
+    /// Persists the blocks in the sled database at the given path.
+    Sled(PathBuf),
+    /// Persists the blocks in the Postgres database at the given URL, requires the `postgres`
+    /// feature.
+    Postgres(String),
+}
+
+impl FromStr for StoreConfig {
+    type Err = ConfigError;
+
+    /// Parses `memory`, `sled:<path>` or a `postgres://` URL.
+    fn from_str(store: &str) -> Result<Self, Self::Err> {
+        if store == "memory" {
+            return Ok(Self::Memory);
+        }
+        if let Some(path) = store.strip_prefix("sled:") {
+            return Ok(Self::Sled(PathBuf::from(path)));
+        }
+        if store.starts_with("postgres://") || store.starts_with("postgresql://") {
+            return Ok(Self::Postgres(store.to_string()));
+        }
+        Err(ConfigError::EnvironmentVariableSetWrong(format!(
+            "KAKAROT_INDEXER_STORE should be memory, sled:<path> or a postgres:// URL, got {store}"
+        )))
+    }
+}
+
+impl StoreConfig {
+    /// Opens the configured store.
+    pub async fn open(&self) -> Result<Arc<dyn IndexStore>, StoreError> {
+        match self {
+            Self::Memory => Ok(Arc::new(MemoryStore::default())),
+            Self::Sled(path) => Ok(Arc::new(SledStore::open(path)?)),
+            #[cfg(feature = "postgres")]
+            Self::Postgres(url) => Ok(Arc::new(crate::store::PostgresStore::connect(url).await?)),
+            #[cfg(not(feature = "postgres"))]
+            Self::Postgres(_) => {
+                Err(StoreError::new("the Postgres store requires building with the postgres feature of the indexer"))
+            }
+        }
+    }
+}
+
+/// Configuration of the block indexer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexerConfig {
+    pub store: StoreConfig,
+    /// First block indexed on an empty store.
+    pub start_block: u64,
+    /// Interval between two polls of the chain tip.
+    pub poll_interval: Duration,
+}
+
+impl IndexerConfig {
+    /// Create a new `IndexerConfig` from the `KAKAROT_INDEXER_STORE`,
+    /// `KAKAROT_INDEXER_START_BLOCK` and `KAKAROT_INDEXER_POLL_INTERVAL_MS` environment variables.
+    /// Returns `None` if `KAKAROT_INDEXER_STORE` is not set, which disables the indexer.
+    pub fn from_env() -> Result<Option<Self>, ConfigError> {
+        let Ok(store) = std::env::var("KAKAROT_INDEXER_STORE") else {
+            return Ok(None);
+        };
+        let store = store.parse()?;
+
+        let start_block = match std::env::var("KAKAROT_INDEXER_START_BLOCK") {
+            Ok(block) => block.parse::<u64>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_INDEXER_START_BLOCK should be a block number, got {block}"
+                ))
+            })?,
+            Err(_) => 0,
+        };
+        let poll_interval = match std::env::var("KAKAROT_INDEXER_POLL_INTERVAL_MS") {
+            Ok(interval) => Duration::from_millis(interval.parse::<u64>().map_err(|_| {
+                ConfigError::EnvironmentVariableSetWrong(format!(
+                    "KAKAROT_INDEXER_POLL_INTERVAL_MS should be a number of milliseconds, got {interval}"
+                ))
+            })?),
+            Err(_) => DEFAULT_INDEXER_POLL_INTERVAL,
+        };
+
+        Ok(Some(Self { store, start_block, poll_interval }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_store_config() {
+        assert_eq!(StoreConfig::Memory, "memory".parse().unwrap());
+        assert_eq!(StoreConfig::Sled(PathBuf::from("/data/index")), "sled:/data/index".parse().unwrap());
+        assert_eq!(
+            StoreConfig::Postgres("postgres://localhost/kakarot".to_string()),
+            "postgres://localhost/kakarot".parse().unwrap()
+        );
+        assert!("redis://localhost".parse::<StoreConfig>().is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::{eyre, Result};
+use futures::{stream, StreamExt, TryStreamExt};
+use kakarot_rpc_core::client::api::KakarotEthApi;
+use reth_rpc_types::BlockTransactions;
+use starknet::core::types::BlockId as StarknetBlockId;
+use starknet::providers::Provider;
+use tokio::task::JoinHandle;
+
+use crate::config::IndexerConfig;
+use crate::store::{IndexStore, IndexedBlock};
+
+/// Number of receipts of a block fetched concurrently.
+pub const RECEIPT_CONCURRENCY: usize = 8;
+
+/// Tails the Starknet blocks, converts them to Ethereum blocks, receipts and logs once and
+/// persists them in an [`IndexStore`].
+///
+/// The blocks are indexed in order, starting after the last stored block, or from the configured
+/// start block on an empty store.
+pub struct Indexer<P: Provider + Send + Sync> {
+    kakarot_client: Arc<dyn KakarotEthApi<P>>,
+    store: Arc<dyn IndexStore>,
+    start_block: u64,
+    poll_interval: Duration,
+}
+
+impl<P: Provider + Send + Sync + 'static> Indexer<P> {
+    pub fn new(kakarot_client: Arc<dyn KakarotEthApi<P>>, store: Arc<dyn IndexStore>, config: &IndexerConfig) -> Self {
+        Self { kakarot_client, store, start_block: config.start_block, poll_interval: config.poll_interval }
+    }
+
+    /// Fetches the block with the given number and the receipts of its transactions.
+    pub async fn fetch_block(&self, number: u64) -> Result<IndexedBlock> {
+        let block = self
+            .kakarot_client
+            .get_eth_block_from_starknet_block(StarknetBlockId::Number(number), true)
+            .await
+            .map_err(|err| eyre!("{err}"))?
+            .inner;
+        let transaction_hashes = match &block.transactions {
+            BlockTransactions::Full(transactions) => transactions.iter().map(|transaction| transaction.hash).collect(),
+            BlockTransactions::Hashes(hashes) => hashes.clone(),
+        };
+
+        let receipts = stream::iter(transaction_hashes)
+            .map(|hash| async move {
+                match self.kakarot_client.transaction_receipt(hash).await {
+                    Ok(Some(receipt)) => Ok(receipt),
+                    Ok(None) => Err(eyre!("missing receipt of the transaction {hash:#x} of the block {number}")),
+                    Err(err) => Err(eyre!("{err}")),
+                }
+            })
+            .buffered(RECEIPT_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        Ok(IndexedBlock { block, receipts })
+    }
+
+    /// Indexes the blocks up to the chain tip and returns the number of indexed blocks.
+    pub async fn sync(&self) -> Result<u64> {
+        let next_block = match self.store.indexed_blocks().await? {
+            Some(indexed_blocks) => indexed_blocks.end() + 1,
+            None => self.start_block,
+        };
+        let chain_tip = self.kakarot_client.block_number().await.map_err(|err| eyre!("{err}"))?.low_u64();
+
+        let mut indexed = 0;
+        for number in next_block..=chain_tip {
+            let block = self.fetch_block(number).await?;
+            self.store.insert_block(block).await?;
+            indexed += 1;
+        }
+        Ok(indexed)
+    }
+
+    /// Starts indexing the blocks in the background, polling the chain tip at the configured
+    /// interval.
+    pub fn start(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.poll_interval);
+            loop {
+                interval.tick().await;
+                match self.sync().await {
+                    Ok(0) => {}
+                    Ok(indexed) => tracing::debug!("Indexed {indexed} blocks"),
+                    // The failed block is retried on the next poll
+                    Err(err) => tracing::warn!("Block indexer failed: {err}"),
+                }
+            }
+        })
+    }
+}
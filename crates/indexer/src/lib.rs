@@ -0,0 +1,13 @@
+//! Background indexer of the Kakarot blocks.
+//!
+//! The [`indexer::Indexer`] tails the Starknet blocks, converts them to Ethereum blocks, receipts
+//! and logs once and persists them in a pluggable [`store::IndexStore`], kept in memory, in a sled
+//! database or in a Postgres database. The RPC servers query the store for the indexed blocks
+//! instead of deriving the same data from Starknet on each request.
+//!
+//! The index can be exported to a portable [`snapshot`] and imported on a new node, which then
+//! only indexes the blocks produced since the snapshot.
+pub mod config;
+pub mod indexer;
+pub mod snapshot;
+pub mod store;
@@ -0,0 +1,148 @@
+//! Portable snapshots of the index, to bootstrap the index of a new node without indexing the
+//! whole chain again.
+//!
+//! A snapshot is a JSON lines file: a [`SnapshotHeader`] followed by the indexed blocks in order,
+//! one per line. It doesn't depend on the store it was exported from, e.g. a snapshot exported
+//! from a sled store can be imported in a Postgres store.
+use std::io::{BufRead, Write};
+use std::ops::RangeInclusive;
+
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use starknet::core::types::FieldElement;
+
+use crate::store::{IndexStore, IndexedBlock};
+
+/// Version of the snapshot format.
+pub const SNAPSHOT_VERSION: u64 = 1;
+
+/// Number of blocks read from the store at once while exporting.
+const EXPORT_CHUNK_SIZE: u64 = 1_000;
+
+/// First line of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotHeader {
+    pub version: u64,
+    /// Address of the Kakarot contract the blocks were indexed for.
+    pub kakarot_address: FieldElement,
+    pub from_block: u64,
+    pub to_block: u64,
+}
+
+impl SnapshotHeader {
+    /// Returns the range of the blocks of the snapshot.
+    pub fn blocks(&self) -> RangeInclusive<u64> {
+        self.from_block..=self.to_block
+    }
+}
+
+/// Writes all the blocks of the store to the writer and returns the header of the snapshot.
+pub async fn export_snapshot(
+    store: &dyn IndexStore,
+    kakarot_address: FieldElement,
+    mut writer: impl Write,
+) -> Result<SnapshotHeader> {
+    let indexed_blocks = store.indexed_blocks().await?.ok_or_else(|| eyre!("the index is empty"))?;
+    let header = SnapshotHeader {
+        version: SNAPSHOT_VERSION,
+        kakarot_address,
+        from_block: *indexed_blocks.start(),
+        to_block: *indexed_blocks.end(),
+    };
+    serde_json::to_writer(&mut writer, &header)?;
+    writeln!(writer)?;
+
+    let mut chunk_from = header.from_block;
+    while chunk_from <= header.to_block {
+        let chunk_to = chunk_from.saturating_add(EXPORT_CHUNK_SIZE - 1).min(header.to_block);
+        for block in store.blocks(chunk_from, chunk_to).await? {
+            serde_json::to_writer(&mut writer, &block)?;
+            writeln!(writer)?;
+        }
+        tracing::info!("Exported the blocks {}..={chunk_to}", header.from_block);
+        chunk_from = chunk_to + 1;
+    }
+    writer.flush()?;
+
+    Ok(header)
+}
+
+/// Reads the snapshot from the reader into the store and returns its header.
+///
+/// The snapshot must be for the same Kakarot contract, and its blocks must extend the blocks of
+/// the store without leaving a gap, so that the indexed blocks stay contiguous.
+pub async fn import_snapshot(
+    store: &dyn IndexStore,
+    kakarot_address: FieldElement,
+    reader: impl BufRead,
+) -> Result<SnapshotHeader> {
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or_else(|| eyre!("the snapshot is empty"))??;
+    let header: SnapshotHeader = serde_json::from_str(&header)?;
+    if header.version != SNAPSHOT_VERSION {
+        return Err(eyre!("unsupported snapshot version {}, expected {SNAPSHOT_VERSION}", header.version));
+    }
+    if header.kakarot_address != kakarot_address {
+        return Err(eyre!(
+            "the snapshot is for the Kakarot contract {:#x}, expected {kakarot_address:#x}",
+            header.kakarot_address
+        ));
+    }
+    if let Some(indexed_blocks) = store.indexed_blocks().await? {
+        if header.from_block > indexed_blocks.end() + 1 || header.to_block + 1 < *indexed_blocks.start() {
+            return Err(eyre!(
+                "the blocks {:?} of the snapshot would leave a gap with the indexed blocks {indexed_blocks:?}",
+                header.blocks()
+            ));
+        }
+    }
+
+    let mut expected_block = header.from_block;
+    for line in lines {
+        let block: IndexedBlock = serde_json::from_str(&line?)?;
+        if block.number() != expected_block {
+            return Err(eyre!("invalid snapshot: expected the block {expected_block}, got {}", block.number()));
+        }
+        store.insert_block(block).await?;
+        expected_block += 1;
+    }
+    if expected_block != header.to_block + 1 {
+        return Err(eyre!("truncated snapshot: the blocks from {expected_block} are missing"));
+    }
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_utils::indexed_block;
+    use crate::store::MemoryStore;
+
+    #[tokio::test]
+    async fn test_export_import_snapshot() {
+        // Given
+        let kakarot_address = FieldElement::from(1u8);
+        let source = MemoryStore::default();
+        for number in 5..=7 {
+            source.insert_block(indexed_block(number)).await.unwrap();
+        }
+        let mut snapshot = vec![];
+
+        // When
+        let exported = export_snapshot(&source, kakarot_address, &mut snapshot).await.unwrap();
+        let destination = MemoryStore::default();
+        let imported = import_snapshot(&destination, kakarot_address, snapshot.as_slice()).await.unwrap();
+
+        // Then
+        assert_eq!(exported, imported);
+        assert_eq!(5..=7, imported.blocks());
+        assert_eq!(Some(5..=7), destination.indexed_blocks().await.unwrap());
+        assert_eq!(source.blocks(5, 7).await.unwrap(), destination.blocks(5, 7).await.unwrap());
+        assert!(import_snapshot(&MemoryStore::default(), FieldElement::from(2u8), snapshot.as_slice()).await.is_err());
+        assert!(
+            import_snapshot(&MemoryStore::default(), kakarot_address, &snapshot[..snapshot.len() / 2]).await.is_err()
+        );
+    }
+}
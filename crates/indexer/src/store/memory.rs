@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use reth_primitives::H256;
+use reth_rpc_types::TransactionReceipt;
+
+use super::{IndexStore, IndexedBlock, StoreError};
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    blocks: BTreeMap<u64, IndexedBlock>,
+    /// Block hash to block number.
+    block_numbers: HashMap<H256, u64>,
+    /// Transaction hash to block number.
+    transaction_blocks: HashMap<H256, u64>,
+}
+
+/// Index store keeping the blocks in memory, lost on restart.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    state: RwLock<MemoryState>,
+}
+
+#[async_trait]
+impl IndexStore for MemoryStore {
+    async fn insert_block(&self, block: IndexedBlock) -> Result<(), StoreError> {
+        let mut state = self.state.write().expect("memory store poisoned");
+        let number = block.number();
+        if let Some(replaced) = state.blocks.remove(&number) {
+            state.block_numbers.remove(&replaced.hash());
+            for transaction in replaced.transactions() {
+                state.transaction_blocks.remove(&transaction.hash);
+            }
+        }
+        state.block_numbers.insert(block.hash(), number);
+        for transaction in block.transactions() {
+            state.transaction_blocks.insert(transaction.hash, number);
+        }
+        state.blocks.insert(number, block);
+        Ok(())
+    }
+
+    async fn indexed_blocks(&self) -> Result<Option<RangeInclusive<u64>>, StoreError> {
+        let state = self.state.read().expect("memory store poisoned");
+        let first = state.blocks.keys().next();
+        let last = state.blocks.keys().next_back();
+        Ok(first.zip(last).map(|(first, last)| *first..=*last))
+    }
+
+    async fn block_by_number(&self, number: u64) -> Result<Option<IndexedBlock>, StoreError> {
+        Ok(self.state.read().expect("memory store poisoned").blocks.get(&number).cloned())
+    }
+
+    async fn block_number_by_hash(&self, hash: H256) -> Result<Option<u64>, StoreError> {
+        Ok(self.state.read().expect("memory store poisoned").block_numbers.get(&hash).copied())
+    }
+
+    async fn receipt(&self, transaction_hash: H256) -> Result<Option<TransactionReceipt>, StoreError> {
+        let state = self.state.read().expect("memory store poisoned");
+        let Some(block) = state.transaction_blocks.get(&transaction_hash).and_then(|number| state.blocks.get(number))
+        else {
+            return Ok(None);
+        };
+        Ok(block.receipts.iter().find(|receipt| receipt.transaction_hash == Some(transaction_hash)).cloned())
+    }
+
+    async fn blocks(&self, from_block: u64, to_block: u64) -> Result<Vec<IndexedBlock>, StoreError> {
+        if to_block < from_block {
+            return Ok(vec![]);
+        }
+        let state = self.state.read().expect("memory store poisoned");
+        Ok(state.blocks.range(from_block..=to_block).map(|(_, block)| block.clone()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_utils::indexed_block;
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        // Given
+        let store = MemoryStore::default();
+        let block = indexed_block(7);
+        let transaction_hash = block.transactions()[0].hash;
+
+        // When
+        store.insert_block(indexed_block(6)).await.unwrap();
+        store.insert_block(block.clone()).await.unwrap();
+
+        // Then
+        assert_eq!(Some(6..=7), store.indexed_blocks().await.unwrap());
+        assert_eq!(Some(block.clone()), store.block_by_hash(block.hash()).await.unwrap());
+        assert_eq!(Some(block.receipts[0].clone()), store.receipt(transaction_hash).await.unwrap());
+        assert_eq!(Some(block.transactions()[0].clone()), store.transaction(transaction_hash).await.unwrap());
+        assert_eq!(2, store.logs(0, 10).await.unwrap().len());
+        assert_eq!(None, store.receipt(H256::zero()).await.unwrap());
+    }
+}
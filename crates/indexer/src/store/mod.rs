@@ -0,0 +1,209 @@
+//! Storage of the indexed blocks. The stores are interchangeable: the blocks are kept in memory,
+//! in a sled database or, with the `postgres` feature, in a Postgres database.
+mod memory;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod sled;
+
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+
+use async_trait::async_trait;
+pub use memory::MemoryStore;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresStore;
+use reth_primitives::H256;
+use reth_rpc_types::{Block, BlockTransactions, Log, RichBlock, Transaction, TransactionReceipt};
+use serde::{Deserialize, Serialize};
+
+pub use self::sled::SledStore;
+
+/// A block converted to Ethereum once, with its full transactions and their receipts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedBlock {
+    pub block: Block,
+    /// Receipts of the transactions of the block, in the same order.
+    pub receipts: Vec<TransactionReceipt>,
+}
+
+impl IndexedBlock {
+    /// Returns the number of the block.
+    pub fn number(&self) -> u64 {
+        self.block.header.number.and_then(|number| u64::try_from(number).ok()).unwrap_or_default()
+    }
+
+    /// Returns the hash of the block.
+    pub fn hash(&self) -> H256 {
+        self.block.header.hash.unwrap_or_default()
+    }
+
+    /// Returns the transactions of the block.
+    pub fn transactions(&self) -> &[Transaction] {
+        match &self.block.transactions {
+            BlockTransactions::Full(transactions) => transactions,
+            BlockTransactions::Hashes(_) => &[],
+        }
+    }
+
+    /// Returns the block as served by `eth_getBlockBy*`, with the hashes of its transactions only
+    /// unless `full` is set.
+    pub fn rich_block(&self, full: bool) -> RichBlock {
+        let mut block = self.block.clone();
+        if !full {
+            block.transactions =
+                BlockTransactions::Hashes(self.transactions().iter().map(|transaction| transaction.hash).collect());
+        }
+        block.into()
+    }
+
+    /// Returns the logs of the block, in the order of its transactions.
+    pub fn logs(&self) -> impl Iterator<Item = &Log> {
+        self.receipts.iter().flat_map(|receipt| receipt.logs.iter())
+    }
+}
+
+/// Error of an index store.
+#[derive(Debug, thiserror::Error)]
+#[error("index store error: {0}")]
+pub struct StoreError(pub(crate) String);
+
+impl StoreError {
+    pub(crate) fn new(err: impl ToString) -> Self {
+        Self(err.to_string())
+    }
+}
+
+/// Storage of the indexed blocks, queried by the RPC servers.
+///
+/// The blocks are indexed in order, so that the stored blocks always form a contiguous range.
+#[async_trait]
+pub trait IndexStore: Debug + Send + Sync {
+    /// Stores the block, its transactions and their receipts, replacing a block with the same
+    /// number.
+    async fn insert_block(&self, block: IndexedBlock) -> Result<(), StoreError>;
+
+    /// Returns the range of the stored blocks, `None` when the store is empty.
+    async fn indexed_blocks(&self) -> Result<Option<RangeInclusive<u64>>, StoreError>;
+
+    /// Returns the stored block with the given number.
+    async fn block_by_number(&self, number: u64) -> Result<Option<IndexedBlock>, StoreError>;
+
+    /// Returns the number of the stored block with the given hash.
+    async fn block_number_by_hash(&self, hash: H256) -> Result<Option<u64>, StoreError>;
+
+    /// Returns the receipt of the stored transaction with the given hash.
+    async fn receipt(&self, transaction_hash: H256) -> Result<Option<TransactionReceipt>, StoreError>;
+
+    /// Returns the stored blocks of the inclusive range, in order.
+    async fn blocks(&self, from_block: u64, to_block: u64) -> Result<Vec<IndexedBlock>, StoreError>;
+
+    /// Persists the buffered writes, called before the RPC exits. A no-op for the stores which
+    /// persist each write.
+    async fn flush(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    /// Returns the stored block with the given hash.
+    async fn block_by_hash(&self, hash: H256) -> Result<Option<IndexedBlock>, StoreError> {
+        match self.block_number_by_hash(hash).await? {
+            Some(number) => self.block_by_number(number).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the stored transaction with the given hash.
+    async fn transaction(&self, transaction_hash: H256) -> Result<Option<Transaction>, StoreError> {
+        let receipt = self.receipt(transaction_hash).await?;
+        let Some(block_number) = receipt.and_then(|receipt| u64::try_from(receipt.block_number?).ok()) else {
+            return Ok(None);
+        };
+        let block = self.block_by_number(block_number).await?;
+        Ok(block.and_then(|block| {
+            block.transactions().iter().find(|transaction| transaction.hash == transaction_hash).cloned()
+        }))
+    }
+
+    /// Returns the logs of the stored blocks of the inclusive range, in order.
+    async fn logs(&self, from_block: u64, to_block: u64) -> Result<Vec<Log>, StoreError> {
+        let blocks = self.blocks(from_block, to_block).await?;
+        Ok(blocks.iter().flat_map(IndexedBlock::logs).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use reth_primitives::{Address, Bloom, Bytes, U128, U256, U64, U8};
+    use reth_rpc_types::Header;
+
+    use super::*;
+
+    /// Returns an indexed block with a single transaction emitting a single log.
+    pub fn indexed_block(number: u64) -> IndexedBlock {
+        let block_hash = H256::from_low_u64_be(number + 1);
+        let transaction_hash = H256::from_low_u64_be(number + 1_000);
+        let transaction = Transaction {
+            hash: transaction_hash,
+            block_hash: Some(block_hash),
+            block_number: Some(U256::from(number)),
+            ..Default::default()
+        };
+        let log = Log {
+            address: Address::from_low_u64_be(number),
+            topics: vec![],
+            data: Bytes::default(),
+            block_hash: Some(block_hash),
+            block_number: Some(U256::from(number)),
+            transaction_hash: Some(transaction_hash),
+            transaction_index: Some(U256::ZERO),
+            log_index: Some(U256::ZERO),
+            removed: false,
+        };
+        let receipt = TransactionReceipt {
+            transaction_hash: Some(transaction_hash),
+            transaction_index: Some(U256::ZERO),
+            block_hash: Some(block_hash),
+            block_number: Some(U256::from(number)),
+            from: Address::zero(),
+            to: None,
+            cumulative_gas_used: U256::ZERO,
+            gas_used: None,
+            contract_address: None,
+            logs: vec![log],
+            state_root: None,
+            logs_bloom: Bloom::default(),
+            status_code: Some(U64::from(1)),
+            effective_gas_price: U128::ZERO,
+            transaction_type: U8::ZERO,
+        };
+        let header = Header {
+            hash: Some(block_hash),
+            parent_hash: H256::from_low_u64_be(number),
+            uncles_hash: H256::zero(),
+            miner: Address::zero(),
+            state_root: H256::zero(),
+            transactions_root: H256::zero(),
+            receipts_root: H256::zero(),
+            number: Some(U256::from(number)),
+            gas_used: U256::ZERO,
+            gas_limit: U256::ZERO,
+            extra_data: Bytes::default(),
+            logs_bloom: Bloom::default(),
+            timestamp: U256::ZERO,
+            difficulty: U256::ZERO,
+            nonce: None,
+            base_fee_per_gas: None,
+            mix_hash: H256::zero(),
+            withdrawals_root: None,
+        };
+        let block = Block {
+            header,
+            total_difficulty: U256::ZERO,
+            uncles: vec![],
+            transactions: BlockTransactions::Full(vec![transaction]),
+            size: None,
+            withdrawals: None,
+        };
+        IndexedBlock { block, receipts: vec![receipt] }
+    }
+}
@@ -0,0 +1,137 @@
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use async_trait::async_trait;
+use reth_primitives::H256;
+use reth_rpc_types::TransactionReceipt;
+
+use super::{IndexStore, IndexedBlock, StoreError};
+
+/// Index store persisting the blocks in a sled database, encoded in JSON and keyed by number,
+/// along with the numbers of the blocks by block hash and by transaction hash.
+#[derive(Debug, Clone)]
+pub struct SledStore {
+    blocks: sled::Tree,
+    block_numbers: sled::Tree,
+    transaction_blocks: sled::Tree,
+}
+
+impl SledStore {
+    /// Opens the sled database at the given path, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::new(&sled::open(path).map_err(StoreError::new)?)
+    }
+
+    pub fn new(db: &sled::Db) -> Result<Self, StoreError> {
+        Ok(Self {
+            blocks: db.open_tree("blocks").map_err(StoreError::new)?,
+            block_numbers: db.open_tree("block_numbers").map_err(StoreError::new)?,
+            transaction_blocks: db.open_tree("transaction_blocks").map_err(StoreError::new)?,
+        })
+    }
+}
+
+fn decode_number(bytes: &[u8]) -> Result<u64, StoreError> {
+    let bytes: [u8; 8] = bytes.try_into().map_err(|_| StoreError::new("invalid stored block number"))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+fn decode_block(bytes: &[u8]) -> Result<IndexedBlock, StoreError> {
+    serde_json::from_slice(bytes).map_err(StoreError::new)
+}
+
+#[async_trait]
+impl IndexStore for SledStore {
+    async fn insert_block(&self, block: IndexedBlock) -> Result<(), StoreError> {
+        let number = block.number().to_be_bytes();
+        if let Some(replaced) = self.blocks.get(number).map_err(StoreError::new)? {
+            let replaced = decode_block(&replaced)?;
+            self.block_numbers.remove(replaced.hash().as_bytes()).map_err(StoreError::new)?;
+            for transaction in replaced.transactions() {
+                self.transaction_blocks.remove(transaction.hash.as_bytes()).map_err(StoreError::new)?;
+            }
+        }
+
+        let mut transaction_blocks = sled::Batch::default();
+        for transaction in block.transactions() {
+            transaction_blocks.insert(transaction.hash.as_bytes(), &number[..]);
+        }
+        self.transaction_blocks.apply_batch(transaction_blocks).map_err(StoreError::new)?;
+        self.block_numbers.insert(block.hash().as_bytes(), &number[..]).map_err(StoreError::new)?;
+        // The block is stored last, so that a stored block is always fully indexed
+        let encoded = serde_json::to_vec(&block).map_err(StoreError::new)?;
+        self.blocks.insert(number, encoded).map_err(StoreError::new)?;
+        Ok(())
+    }
+
+    async fn indexed_blocks(&self) -> Result<Option<RangeInclusive<u64>>, StoreError> {
+        let first = self.blocks.first().map_err(StoreError::new)?;
+        let last = self.blocks.last().map_err(StoreError::new)?;
+        match first.zip(last) {
+            Some(((first, _), (last, _))) => Ok(Some(decode_number(&first)?..=decode_number(&last)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn block_by_number(&self, number: u64) -> Result<Option<IndexedBlock>, StoreError> {
+        let block = self.blocks.get(number.to_be_bytes()).map_err(StoreError::new)?;
+        block.map(|block| decode_block(&block)).transpose()
+    }
+
+    async fn block_number_by_hash(&self, hash: H256) -> Result<Option<u64>, StoreError> {
+        let number = self.block_numbers.get(hash.as_bytes()).map_err(StoreError::new)?;
+        number.map(|number| decode_number(&number)).transpose()
+    }
+
+    async fn receipt(&self, transaction_hash: H256) -> Result<Option<TransactionReceipt>, StoreError> {
+        let Some(number) = self.transaction_blocks.get(transaction_hash.as_bytes()).map_err(StoreError::new)? else {
+            return Ok(None);
+        };
+        let block = self.block_by_number(decode_number(&number)?).await?;
+        Ok(block.and_then(|block| {
+            block.receipts.into_iter().find(|receipt| receipt.transaction_hash == Some(transaction_hash))
+        }))
+    }
+
+    async fn blocks(&self, from_block: u64, to_block: u64) -> Result<Vec<IndexedBlock>, StoreError> {
+        if to_block < from_block {
+            return Ok(vec![]);
+        }
+        self.blocks
+            .range(from_block.to_be_bytes()..=to_block.to_be_bytes())
+            .map(|entry| decode_block(&entry.map_err(StoreError::new)?.1))
+            .collect()
+    }
+
+    /// Flushes the database, shared by the trees, to disk.
+    async fn flush(&self) -> Result<(), StoreError> {
+        self.blocks.flush_async().await.map_err(StoreError::new)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::test_utils::indexed_block;
+
+    #[tokio::test]
+    async fn test_sled_store_persists_blocks() {
+        // Given
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let block = indexed_block(3);
+        let transaction_hash = block.transactions()[0].hash;
+
+        // When
+        let store = SledStore::new(&db).unwrap();
+        store.insert_block(indexed_block(2)).await.unwrap();
+        store.insert_block(block.clone()).await.unwrap();
+        let reopened = SledStore::new(&db).unwrap();
+
+        // Then
+        assert_eq!(Some(2..=3), reopened.indexed_blocks().await.unwrap());
+        assert_eq!(Some(3), reopened.block_number_by_hash(block.hash()).await.unwrap());
+        assert_eq!(Some(block.receipts[0].clone()), reopened.receipt(transaction_hash).await.unwrap());
+        assert_eq!(vec![block], reopened.blocks(3, 5).await.unwrap());
+    }
+}